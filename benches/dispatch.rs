@@ -0,0 +1,84 @@
+//! Decode and dispatch throughput.
+//!
+//! ```text
+//! cargo bench --features bench --bench dispatch
+//! ```
+//! `decode/match` is the bit field decoder the executor used to run for every
+//! instruction, `decode/table` the precomputed table it uses now. `dispatch`
+//! compares finding each opcode's handler with a match, as the executor used
+//! to, against the handler table it uses now. `step` compares the interpreter
+//! with and without the decode cache.
+use std::sync::{Arc, Mutex};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use r6502::cache::DecodeCache;
+use r6502::emulator::{CPUEmulatorBuilder, DefaultVirtualMemory};
+use r6502::instructions::{self, Instruction};
+use r6502::state::SystemState;
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(256));
+    group.bench_function("match", |b| {
+        b.iter(|| (0..=255u8).map(|byte| instructions::decode(black_box(byte)).length()).sum::<u16>())
+    });
+    group.bench_function("table", |b| {
+        b.iter(|| (0..=255u8).map(|byte| Instruction::from(black_box(byte)).length()).sum::<u16>())
+    });
+    group.finish();
+}
+
+fn dispatch(c: &mut Criterion) {
+    // Register only instructions, whose handlers are cheap enough for the dispatch to show.
+    let instructions = [0xE8, 0x88, 0xAA, 0x18, 0xEA, 0xC8, 0x8A, 0x38].map(Instruction::from);
+    let mut emulator = CPUEmulatorBuilder::default()
+        .state(SystemState { pc: 0x0200, running: true, ..Default::default() })
+        .memory(Arc::new(Mutex::new(DefaultVirtualMemory::default())))
+        .build()
+        .unwrap();
+    let mut group = c.benchmark_group("dispatch");
+    group.throughput(Throughput::Elements(instructions.len() as u64 * 100));
+    group.bench_function("match", |b| {
+        b.iter(|| {
+            for instruction in instructions.iter().cycle().take(instructions.len() * 100) {
+                black_box(instruction).execute_matched(&mut emulator).unwrap();
+            }
+        })
+    });
+    group.bench_function("table", |b| {
+        b.iter(|| {
+            for instruction in instructions.iter().cycle().take(instructions.len() * 100) {
+                black_box(instruction).execute(&mut emulator).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn step(c: &mut Criterion) {
+    // loop: INX; BNE loop; INY; JMP loop
+    let program = [0xE8, 0xD0, 0xFD, 0xC8, 0x4C, 0x00, 0x02];
+    let mut group = c.benchmark_group("step");
+    group.throughput(Throughput::Elements(1000));
+    for (name, decode_cache) in [("loop", None), ("loop cached", Some(DecodeCache::new()))] {
+        let memory = DefaultVirtualMemory::default().with_image(0x0200, &program);
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState { pc: 0x0200, running: true, ..Default::default() })
+            .memory(Arc::new(Mutex::new(memory)))
+            .decode_cache(decode_cache)
+            .build()
+            .unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for _ in 0..1000 {
+                    emulator.execute_next_instruction().unwrap();
+                }
+                emulator.state.cycles.clear();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode, dispatch, step);
+criterion_main!(benches);
@@ -0,0 +1,219 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::disassembler::{disassemble_at, DisassembledInstruction};
+use crate::instructions::{AddressingMode, OpCode};
+use crate::symbols::SymbolTable;
+use crate::vectors::Vector;
+
+// Static control flow recovery by recursive descent from a set of entry points.
+// Subroutine calls are assumed to return, indirect jumps end the trace.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    FallThrough,
+    Branch,
+    Jump,
+    Call,
+}
+
+impl std::fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FallThrough => write!(f, "fall through"),
+            Self::Branch => write!(f, "branch"),
+            Self::Jump => write!(f, "jump"),
+            Self::Call => write!(f, "call"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Edge {
+    /// Start address of the source block.
+    pub from: u16,
+    pub to: u16,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: u16,
+    pub instructions: Vec<DisassembledInstruction>,
+}
+
+impl BasicBlock {
+    /// Address of the last instruction in the block.
+    pub fn last(&self) -> u16 {
+        self.instructions.last().map(|instruction| instruction.address).unwrap_or(self.start)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ControlFlowGraph {
+    pub blocks: BTreeMap<u16, BasicBlock>,
+    pub edges: Vec<Edge>,
+    /// Edges leading outside the image, e.g. calls into a kernal ROM.
+    pub external: Vec<Edge>,
+    /// Addresses of `JMP (indirect)` instructions whose targets are unknown.
+    pub indirect_jumps: Vec<u16>,
+    /// Inclusive address ranges of the image never reached by the trace.
+    pub unreachable: Vec<(u16, u16)>,
+}
+
+fn ends_block(instruction: &DisassembledInstruction) -> bool {
+    !instruction.is_valid()
+        || instruction.instruction.mode == Some(AddressingMode::Relative)
+        || matches!(
+            instruction.instruction.opcode,
+            OpCode::JMP | OpCode::JSR | OpCode::RTS | OpCode::RTI | OpCode::BRK | OpCode::KIL
+        )
+}
+
+// Successor addresses of an instruction, with the kind of edge leading to each.
+fn successors(instruction: &DisassembledInstruction) -> Vec<(u16, EdgeKind)> {
+    let next = instruction.address.wrapping_add(instruction.length());
+    if !instruction.is_valid() {
+        return vec![];
+    }
+    match (instruction.instruction.opcode, instruction.instruction.mode) {
+        (_, Some(AddressingMode::Relative)) => {
+            vec![(instruction.target().unwrap(), EdgeKind::Branch), (next, EdgeKind::FallThrough)]
+        }
+        (OpCode::JMP, Some(AddressingMode::DirectAbsolute)) => vec![(instruction.target().unwrap(), EdgeKind::Jump)],
+        (OpCode::JMP, _) => vec![],
+        (OpCode::JSR, _) => vec![(instruction.target().unwrap(), EdgeKind::Call), (next, EdgeKind::FallThrough)],
+        (OpCode::RTS | OpCode::RTI | OpCode::BRK | OpCode::KIL, _) => vec![],
+        _ => vec![(next, EdgeKind::FallThrough)],
+    }
+}
+
+/// Targets of the NMI, reset and IRQ vectors, for those vectors the image covers.
+pub fn vector_entries(image: &[u8], origin: u16) -> Vec<u16> {
+    let mut entries = vec![];
+    for vector in Vector::ALL {
+        let offset = vector.address().wrapping_sub(origin) as usize;
+        if let (Some(low), Some(high)) = (image.get(offset), image.get(offset + 1)) {
+            entries.push(((*high as u16) << 8) + *low as u16);
+        }
+    }
+    entries
+}
+
+/// Builds the graph for `image` loaded at `origin`, starting from `entries`.
+pub fn build(image: &[u8], origin: u16, entries: &[u16]) -> ControlFlowGraph {
+    let in_image = |address: u16| {
+        (address as usize) >= origin as usize && (address as usize) < origin as usize + image.len()
+    };
+    let fetch = |address: u16| disassemble_at(&image[(address - origin) as usize..], address);
+
+    let mut graph = ControlFlowGraph::default();
+    let mut visited: BTreeMap<u16, DisassembledInstruction> = BTreeMap::new();
+    let mut leaders: BTreeSet<u16> = entries.iter().copied().filter(|address| in_image(*address)).collect();
+    let mut worklist: Vec<u16> = leaders.iter().copied().collect();
+
+    while let Some(address) = worklist.pop() {
+        if visited.contains_key(&address) {
+            continue;
+        }
+        let instruction = fetch(address);
+        for (successor, kind) in successors(&instruction) {
+            if kind != EdgeKind::FallThrough || ends_block(&instruction) {
+                leaders.insert(successor);
+            }
+            if in_image(successor) {
+                worklist.push(successor);
+            } else {
+                graph.external.push(Edge { from: address, to: successor, kind });
+            }
+        }
+        if instruction.instruction.opcode == OpCode::JMP
+            && instruction.instruction.mode == Some(AddressingMode::IndirectAbsolute)
+        {
+            graph.indirect_jumps.push(address);
+        }
+        visited.insert(address, instruction);
+    }
+
+    for leader in leaders.iter().copied().filter(|leader| visited.contains_key(leader)) {
+        let mut block = BasicBlock { start: leader, instructions: vec![] };
+        let mut address = leader;
+        loop {
+            let instruction = visited[&address].clone();
+            let next = address.wrapping_add(instruction.length());
+            let last = ends_block(&instruction) || leaders.contains(&next) || !visited.contains_key(&next);
+            if last {
+                for (to, kind) in successors(&instruction) {
+                    if in_image(to) {
+                        graph.edges.push(Edge { from: leader, to, kind });
+                    }
+                }
+            }
+            block.instructions.push(instruction);
+            if last {
+                break;
+            }
+            address = next;
+        }
+        graph.blocks.insert(leader, block);
+    }
+    // Re-home external edges onto the block that contains them.
+    for edge in graph.external.iter_mut() {
+        if let Some((start, _)) = graph.blocks.range(..=edge.from).next_back() {
+            edge.from = *start;
+        }
+    }
+
+    let mut covered = vec![false; image.len()];
+    for instruction in visited.values() {
+        for index in 0..instruction.length() {
+            let offset = instruction.address.wrapping_add(index).wrapping_sub(origin) as usize;
+            if offset < covered.len() {
+                covered[offset] = true;
+            }
+        }
+    }
+    let mut offset = 0;
+    while offset < covered.len() {
+        if covered[offset] {
+            offset += 1;
+            continue;
+        }
+        let start = offset;
+        while offset < covered.len() && !covered[offset] {
+            offset += 1;
+        }
+        graph.unreachable.push((origin.wrapping_add(start as u16), origin.wrapping_add(offset as u16 - 1)));
+    }
+    graph
+}
+
+impl ControlFlowGraph {
+    /// Renders the graph in Graphviz DOT format, one box per basic block.
+    pub fn to_dot(&self, symbols: Option<&SymbolTable>) -> String {
+        let mut out = String::from("digraph cfg {\n    node [shape=box fontname=\"monospace\"];\n");
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            if let Some(name) = symbols.and_then(|symbols| symbols.name_for(block.start)) {
+                label.push_str(&format!("{}:\\l", name));
+            }
+            for instruction in block.instructions.iter() {
+                label.push_str(&format!("{:04X}: {}\\l", instruction.address, instruction.text(symbols)));
+            }
+            out.push_str(&format!("    \"{:04X}\" [label=\"{}\"];\n", block.start, label.replace('"', "\\\"")));
+        }
+        for edge in self.edges.iter().chain(self.external.iter()) {
+            let style = match edge.kind {
+                EdgeKind::FallThrough => "",
+                EdgeKind::Branch => " color=blue",
+                EdgeKind::Jump => " color=darkgreen",
+                EdgeKind::Call => " style=dashed",
+            };
+            out.push_str(&format!(
+                "    \"{:04X}\" -> \"{:04X}\" [label=\"{}\"{}];\n",
+                edge.from, edge.to, edge.kind, style
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
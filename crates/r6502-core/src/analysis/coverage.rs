@@ -0,0 +1,144 @@
+// Which bytes of the address space have been fetched as instructions (opcode or
+// operand). Anything never executed in a representative run is likely data.
+// Mapped through ca65 listings it becomes line coverage of the assembly source,
+// exported as lcov or Cobertura XML for CI tooling. Only whether a line ran is
+// known, so every hit count is 0 or 1.
+
+use crate::analysis::cfg::ControlFlowGraph;
+use crate::analysis::listing::Listing;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct ExecutedBytes {
+    bits: Vec<u64>,
+}
+
+impl Default for ExecutedBytes {
+    fn default() -> Self {
+        Self { bits: vec![0; 0x10000 / 64] }
+    }
+}
+
+impl std::fmt::Debug for ExecutedBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ExecutedBytes({} bytes)", self.count())
+    }
+}
+
+impl ExecutedBytes {
+    pub fn mark(&mut self, address: u16) {
+        self.bits[address as usize / 64] |= 1 << (address % 64);
+    }
+
+    /// Marks `length` bytes starting at `address`, wrapping at the top of memory.
+    pub fn mark_range(&mut self, address: u16, length: u16) {
+        for offset in 0..length {
+            self.mark(address.wrapping_add(offset));
+        }
+    }
+
+    pub fn is_executed(&self, address: u16) -> bool {
+        self.bits[address as usize / 64] & (1 << (address % 64)) != 0
+    }
+
+    /// Number of distinct executed bytes.
+    pub fn count(&self) -> usize {
+        self.bits.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        (0..=u16::MAX).filter(|address| self.is_executed(*address))
+    }
+
+    /// Inclusive ranges of consecutive executed addresses.
+    pub fn ranges(&self) -> Vec<(u16, u16)> {
+        let mut ranges: Vec<(u16, u16)> = vec![];
+        for address in self.iter() {
+            match ranges.last_mut() {
+                Some((_, end)) if end.wrapping_add(1) == address => *end = address,
+                _ => ranges.push((address, address)),
+            }
+        }
+        ranges
+    }
+
+    pub fn merge(&mut self, other: &ExecutedBytes) {
+        for (word, other) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *word |= other;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+
+    /// Source lines of `listing` with whether their opcode was executed.
+    pub fn lines<'a>(&'a self, listing: &'a Listing) -> impl Iterator<Item = (usize, bool)> + 'a {
+        listing.lines.iter().map(|line| (line.line, self.is_executed(line.address)))
+    }
+
+    /// Line coverage of each listing's source file as an lcov tracefile.
+    pub fn lcov(&self, listings: &[Listing]) -> String {
+        let mut out = String::new();
+        for listing in listings {
+            out += &format!("TN:\nSF:{}\n", listing.file);
+            let mut hit = 0;
+            for (line, executed) in self.lines(listing) {
+                out += &format!("DA:{},{}\n", line, executed as u8);
+                hit += executed as usize;
+            }
+            out += &format!("LF:{}\nLH:{}\nend_of_record\n", listing.lines.len(), hit);
+        }
+        out
+    }
+
+    /// Line coverage of each listing's source file as a Cobertura report, one
+    /// class per file.
+    pub fn cobertura(&self, listings: &[Listing]) -> String {
+        let rate = |hit: usize, valid: usize| if valid == 0 { 1.0 } else { hit as f64 / valid as f64 };
+        let mut classes = String::new();
+        let (mut total_hit, mut total_valid) = (0, 0);
+        for listing in listings {
+            let hit = self.lines(listing).filter(|(_, executed)| *executed).count();
+            let valid = listing.lines.len();
+            let file = escape(&listing.file);
+            classes += &format!(
+                "        <class name=\"{}\" filename=\"{}\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"0\">\n          <methods/>\n          <lines>\n",
+                file, file, rate(hit, valid)
+            );
+            for (line, executed) in self.lines(listing) {
+                classes += &format!("            <line number=\"{}\" hits=\"{}\"/>\n", line, executed as u8);
+            }
+            classes += "          </lines>\n        </class>\n";
+            total_hit += hit;
+            total_valid += valid;
+        }
+        let line_rate = rate(total_hit, total_valid);
+        let mut out = String::from("<?xml version=\"1.0\" ?>\n");
+        out += &format!(
+            "<coverage line-rate=\"{:.4}\" branch-rate=\"0\" lines-covered=\"{}\" lines-valid=\"{}\" branches-covered=\"0\" branches-valid=\"0\" complexity=\"0\" version=\"r6502\" timestamp=\"0\">\n",
+            line_rate, total_hit, total_valid
+        );
+        out += "  <sources>\n    <source>.</source>\n  </sources>\n  <packages>\n";
+        out += &format!("    <package name=\"r6502\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"0\">\n      <classes>\n", line_rate);
+        out += &classes;
+        out += "      </classes>\n    </package>\n  </packages>\n</coverage>\n";
+        out
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Marks every instruction reached by static control flow recovery.
+impl From<&ControlFlowGraph> for ExecutedBytes {
+    fn from(graph: &ControlFlowGraph) -> Self {
+        let mut executed = Self::default();
+        for block in graph.blocks.values() {
+            for instruction in block.instructions.iter() {
+                executed.mark_range(instruction.address, instruction.length());
+            }
+        }
+        executed
+    }
+}
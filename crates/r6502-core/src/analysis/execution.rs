@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::instructions::{AddressingMode, Instruction, OpCode};
+use crate::symbols::SymbolTable;
+
+// Control transfers observed while running, as a complement to the static
+// graph in `cfg`: indirect jumps, computed returns and interrupts all resolve
+// to the addresses the program actually went to.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransferKind {
+    Branch,
+    Jump,
+    IndirectJump,
+    Call,
+    Return,
+    Interrupt,
+    ReturnFromInterrupt,
+}
+
+impl TransferKind {
+    /// Classifies the instruction that just moved the program counter from `from` to `to`.
+    /// Returns `None` for sequential execution, including branches not taken.
+    pub fn classify(instruction: &Instruction, from: u16, to: u16) -> Option<Self> {
+        match (instruction.opcode, instruction.mode) {
+            (_, Some(AddressingMode::Relative)) if to != from.wrapping_add(instruction.length()) => Some(Self::Branch),
+            (_, Some(AddressingMode::Relative)) => None,
+            (OpCode::JMP, Some(AddressingMode::IndirectAbsolute)) => Some(Self::IndirectJump),
+            (OpCode::JMP, _) => Some(Self::Jump),
+            (OpCode::JSR, _) => Some(Self::Call),
+            (OpCode::RTS, _) => Some(Self::Return),
+            // A BRK handled as a syscall carries on past its signature byte.
+            (OpCode::BRK, _) if to == from.wrapping_add(2) => None,
+            (OpCode::BRK, _) => Some(Self::Interrupt),
+            (OpCode::RTI, _) => Some(Self::ReturnFromInterrupt),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for TransferKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Branch => write!(f, "branch"),
+            Self::Jump => write!(f, "jump"),
+            Self::IndirectJump => write!(f, "indirect jump"),
+            Self::Call => write!(f, "call"),
+            Self::Return => write!(f, "return"),
+            Self::Interrupt => write!(f, "interrupt"),
+            Self::ReturnFromInterrupt => write!(f, "return from interrupt"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transfer {
+    pub from: u16,
+    pub to: u16,
+    pub kind: TransferKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionGraph {
+    transfers: HashMap<Transfer, u64>,
+    calls: HashMap<(Option<u16>, u16), u64>,
+    // Entry points of the subroutines/handlers currently active.
+    call_stack: Vec<u16>,
+}
+
+impl ExecutionGraph {
+    pub fn record(&mut self, from: u16, to: u16, kind: TransferKind) {
+        *self.transfers.entry(Transfer { from, to, kind }).or_default() += 1;
+        match kind {
+            TransferKind::Call | TransferKind::Interrupt => {
+                let caller = self.call_stack.last().copied();
+                *self.calls.entry((caller, to)).or_default() += 1;
+                self.call_stack.push(to);
+            }
+            TransferKind::Return | TransferKind::ReturnFromInterrupt => {
+                // Code that uses RTS as a computed jump can unbalance the stack; ignore underflow.
+                self.call_stack.pop();
+            }
+            _ => (),
+        }
+    }
+
+    /// Every distinct transfer with the number of times it was taken.
+    pub fn transfers(&self) -> impl Iterator<Item = (&Transfer, u64)> {
+        self.transfers.iter().map(|(transfer, count)| (transfer, *count))
+    }
+
+    /// Observed (caller, callee) subroutine pairs with call counts. The caller is
+    /// `None` for calls made before any subroutine was entered.
+    pub fn calls(&self) -> impl Iterator<Item = (Option<u16>, u16, u64)> + '_ {
+        self.calls.iter().map(|((caller, callee), count)| (*caller, *callee, *count))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Renders the dynamic call graph in Graphviz DOT format.
+    pub fn call_graph_dot(&self, symbols: Option<&SymbolTable>) -> String {
+        let name = |address: u16| -> String {
+            symbols
+                .and_then(|symbols| symbols.name_for(address))
+                .map(|name| name.to_owned())
+                .unwrap_or(format!("${:04X}", address))
+        };
+        let mut calls: Vec<_> = self.calls().collect();
+        calls.sort();
+        let mut out = String::from("digraph calls {\n    node [shape=box fontname=\"monospace\"];\n");
+        for (caller, callee, count) in calls {
+            let caller = caller.map(name).unwrap_or("entry".to_owned());
+            out.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n", caller, name(callee), count));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Renders every observed transfer in Graphviz DOT format, one node per address.
+    pub fn to_dot(&self) -> String {
+        let mut transfers: Vec<_> = self.transfers().collect();
+        transfers.sort_by_key(|(transfer, _)| (transfer.from, transfer.to));
+        let mut out = String::from("digraph execution {\n    node [shape=box fontname=\"monospace\"];\n");
+        for (transfer, count) in transfers {
+            out.push_str(&format!(
+                "    \"{:04X}\" -> \"{:04X}\" [label=\"{} x{}\"];\n",
+                transfer.from, transfer.to, transfer.kind, count
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
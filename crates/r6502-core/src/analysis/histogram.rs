@@ -0,0 +1,119 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::instructions::{AddressingMode, Instruction, OpCode};
+
+// Static opcode statistics from a linear sweep over an image. The sweep decodes
+// data as if it were code, which is the point: a patched routine changes the
+// instruction mix, while compressed or encrypted regions show up as random
+// opcode soup with near 8 bits of entropy per byte.
+
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    pub opcodes: HashMap<OpCode, usize>,
+    pub modes: HashMap<Option<AddressingMode>, usize>,
+    /// Number of instructions decoded by the sweep.
+    pub instructions: usize,
+    /// Bytes that don't decode to any known opcode.
+    pub undecodable: usize,
+    /// Shannon entropy of the whole image in bits per byte.
+    pub entropy: f64,
+}
+
+impl Histogram {
+    pub fn from_image(image: &[u8]) -> Self {
+        let mut histogram = Self { entropy: entropy(image), ..Default::default() };
+        let mut offset = 0;
+        while offset < image.len() {
+            let instruction = Instruction::from(image[offset]);
+            match instruction.opcode {
+                OpCode::UnknownInstruction | OpCode::BadInstruction => {
+                    histogram.undecodable += 1;
+                    offset += 1;
+                }
+                opcode => {
+                    *histogram.opcodes.entry(opcode).or_default() += 1;
+                    *histogram.modes.entry(instruction.mode).or_default() += 1;
+                    histogram.instructions += 1;
+                    offset += instruction.length() as usize;
+                }
+            }
+        }
+        histogram
+    }
+}
+
+/// Shannon entropy in bits per byte.
+pub fn entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+    let total = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Entropy of consecutive `window` sized chunks, as (offset, bits per byte).
+pub fn entropy_windows(image: &[u8], window: usize) -> Vec<(usize, f64)> {
+    image
+        .chunks(window.max(1))
+        .enumerate()
+        .map(|(index, chunk)| (index * window.max(1), entropy(chunk)))
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistogramDiff {
+    /// Opcodes whose counts differ, as (opcode, left, right), sorted by mnemonic.
+    pub opcodes: Vec<(OpCode, usize, usize)>,
+    /// Addressing modes whose counts differ, as (mode, left, right).
+    pub modes: Vec<(Option<AddressingMode>, usize, usize)>,
+    pub entropy: (f64, f64),
+}
+
+impl HistogramDiff {
+    pub fn is_empty(&self) -> bool {
+        self.opcodes.is_empty() && self.modes.is_empty()
+    }
+}
+
+pub fn diff(left: &Histogram, right: &Histogram) -> HistogramDiff {
+    fn changed<K: Copy + Eq + std::hash::Hash>(
+        left: &HashMap<K, usize>,
+        right: &HashMap<K, usize>,
+    ) -> Vec<(K, usize, usize)> {
+        let keys: HashSet<K> = left.keys().chain(right.keys()).copied().collect();
+        keys.into_iter()
+            .map(|key| (key, *left.get(&key).unwrap_or(&0), *right.get(&key).unwrap_or(&0)))
+            .filter(|(_, a, b)| a != b)
+            .collect()
+    }
+    let mut opcodes = changed(&left.opcodes, &right.opcodes);
+    opcodes.sort_by_key(|(opcode, _, _)| format!("{:?}", opcode));
+    let mut modes = changed(&left.modes, &right.modes);
+    modes.sort_by_key(|(mode, _, _)| mode.map(|mode| mode.short_name()).unwrap_or(""));
+    HistogramDiff { opcodes, modes, entropy: (left.entropy, right.entropy) }
+}
+
+impl std::fmt::Display for HistogramDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "entropy: {:.3} -> {:.3} bits/byte", self.entropy.0, self.entropy.1)?;
+        for (opcode, left, right) in self.opcodes.iter() {
+            writeln!(f, "{:<6} {:>6} -> {:<6} ({:+})", format!("{:?}", opcode), left, right, *right as i64 - *left as i64)?;
+        }
+        for (mode, left, right) in self.modes.iter() {
+            let name = mode.map(|mode| mode.short_name()).unwrap_or("none");
+            writeln!(f, "{:<6} {:>6} -> {:<6} ({:+})", name, left, right, *right as i64 - *left as i64)?;
+        }
+        Ok(())
+    }
+}
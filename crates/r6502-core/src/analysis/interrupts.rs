@@ -0,0 +1,145 @@
+// Interrupt activity over a run: how often IRQ, NMI and BRK handlers were
+// entered, how deeply they nested, the cycles spent in them and how long
+// requests waited for their handler. Handlers are matched to the RTI that ends
+// them through a stack of the ones in progress, so an NMI taken inside an IRQ
+// handler counts as nesting. A handler that never returns, like a BRK that
+// restarts the program, stays on the stack until the next RTI.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InterruptKind {
+    Irq,
+    Nmi,
+    Brk,
+}
+
+impl InterruptKind {
+    pub const ALL: [InterruptKind; 3] = [Self::Irq, Self::Nmi, Self::Brk];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl std::fmt::Display for InterruptKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Irq => write!(f, "IRQ"),
+            Self::Nmi => write!(f, "NMI"),
+            Self::Brk => write!(f, "BRK"),
+        }
+    }
+}
+
+/// Totals for one kind of interrupt.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HandlerStats {
+    pub entries: u64,
+    /// Handlers left through RTI.
+    pub exits: u64,
+    /// Entries made while another handler was running.
+    pub nested: u64,
+    /// Cycles from taking the interrupt to the end of its RTI, including any
+    /// handlers nested inside.
+    pub cycles: u64,
+    pub max_cycles: u64,
+    /// Longest wait from the request to taking it, including time masked by
+    /// the I flag. Always zero for BRK.
+    pub max_latency: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InterruptStats {
+    handlers: [HandlerStats; 3],
+    /// Handlers in progress, innermost last, with the cycle they were entered on.
+    active: Vec<(InterruptKind, u64)>,
+    max_depth: usize,
+    irq_since: Option<u64>,
+    nmi_since: Option<u64>,
+}
+
+impl InterruptStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Samples the IRQ input, asserted or not, before an instruction starts.
+    pub fn irq_line(&mut self, asserted: bool, clock: u64) {
+        match asserted {
+            true => _ = self.irq_since.get_or_insert(clock),
+            false => self.irq_since = None,
+        }
+    }
+
+    pub fn nmi_requested(&mut self, clock: u64) {
+        self.nmi_since.get_or_insert(clock);
+    }
+
+    /// Records the processor starting to take an interrupt on `clock`.
+    pub fn enter(&mut self, kind: InterruptKind, clock: u64) {
+        let since = match kind {
+            InterruptKind::Irq => self.irq_since.take(),
+            InterruptKind::Nmi => self.nmi_since.take(),
+            InterruptKind::Brk => None,
+        };
+        let nested = !self.active.is_empty();
+        let handler = &mut self.handlers[kind.index()];
+        handler.entries += 1;
+        handler.nested += nested as u64;
+        handler.max_latency = handler.max_latency.max(since.map_or(0, |since| clock.saturating_sub(since)));
+        self.active.push((kind, clock));
+        self.max_depth = self.max_depth.max(self.active.len());
+    }
+
+    /// Records an RTI finishing on `clock`. An RTI with no handler in progress,
+    /// used as a computed jump, is ignored.
+    pub fn exit(&mut self, clock: u64) {
+        if let Some((kind, entered)) = self.active.pop() {
+            let handler = &mut self.handlers[kind.index()];
+            let cycles = clock.saturating_sub(entered);
+            handler.exits += 1;
+            handler.cycles += cycles;
+            handler.max_cycles = handler.max_cycles.max(cycles);
+        }
+    }
+
+    pub fn get(&self, kind: InterruptKind) -> &HandlerStats {
+        &self.handlers[kind.index()]
+    }
+
+    /// Handlers in progress, outermost first.
+    pub fn active(&self) -> impl Iterator<Item = InterruptKind> + '_ {
+        self.active.iter().map(|(kind, _)| *kind)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.active.len()
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// A table with a row per kind of interrupt, then the nesting depth.
+    pub fn report(&self) -> String {
+        let mut out = String::from("kind   entries    exits   nested     cycles  max cycles  max latency\n");
+        for kind in InterruptKind::ALL {
+            let handler = self.get(kind);
+            out += &format!(
+                "{:<5} {:>8} {:>8} {:>8} {:>10} {:>11} {:>12}\n",
+                kind.to_string(),
+                handler.entries,
+                handler.exits,
+                handler.nested,
+                handler.cycles,
+                handler.max_cycles,
+                handler.max_latency
+            );
+        }
+        out += &format!("max depth {}, in progress {}\n", self.max_depth, self.active.len());
+        out
+    }
+}
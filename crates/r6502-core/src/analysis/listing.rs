@@ -0,0 +1,122 @@
+use std::path::Path;
+
+use crate::error::{R6502Error, Result};
+use crate::symbols::SymbolTable;
+
+// Source line addresses from a ca65 listing (`ca65 -l`), to map coverage of
+// executed bytes back to the assembly source. A listing line looks like
+//
+//     000002r 1  8D 00 02     sta $0200
+//
+// with the address (an offset into the current segment when followed by `r`),
+// the include depth, up to four of the bytes emitted and the source text from
+// column 24. Lines at depth 1 are the main file's, one per source line, so
+// their line numbers are counted; included files are skipped. Segment offsets
+// are turned into addresses with the segment starts of an ld65 map, which is
+// right as long as the object file comes first in each of its segments.
+
+/// Column the source text starts at.
+const SOURCE_COLUMN: usize = 24;
+
+/// An instruction in the main file of a listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListingLine {
+    /// Line number in the source file, from 1.
+    pub line: usize,
+    pub address: u16,
+    /// Bytes shown in the listing, at most four.
+    pub length: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listing {
+    /// The main file as named in the listing header.
+    pub file: String,
+    /// Source lines that assemble to instructions, in order. Data directives
+    /// and lines without code aren't included.
+    pub lines: Vec<ListingLine>,
+}
+
+impl Listing {
+    /// Parses a ca65 listing, placing relocatable code with the segments of
+    /// `symbols` if given.
+    pub fn parse(text: &str, symbols: Option<&SymbolTable>) -> Result<Self> {
+        let mut file = None;
+        let mut lines = vec![];
+        let mut line_number = 0;
+        let mut segment = "CODE".to_owned();
+        for text in text.lines() {
+            if let Some(name) = text.strip_prefix("Main file") {
+                file = name.trim_start().strip_prefix(':').map(|name| name.trim().to_owned());
+                continue;
+            }
+            let Some((offset, relocatable, depth)) = parse_prefix(text) else { continue };
+            let bytes = text.get(11..SOURCE_COLUMN.min(text.len())).unwrap_or_default();
+            let source = text.get(SOURCE_COLUMN..).unwrap_or_default();
+            let length = bytes.split_whitespace().count() as u16;
+            // Bytes beyond the first four continue on lines without source.
+            if depth != '1' || (length > 0 && source.trim().is_empty()) {
+                continue;
+            }
+            line_number += 1;
+            let statement = statement(source);
+            if let Some(name) = segment_name(statement) {
+                segment = name;
+                continue;
+            }
+            if length == 0 || statement.starts_with('.') {
+                continue;
+            }
+            let base = match relocatable {
+                true => symbols.and_then(|symbols| symbols.segment(&segment)).map_or(0, |segment| segment.start as u32),
+                false => 0,
+            };
+            let address = u16::try_from(base + offset).map_err(|_| R6502Error::AddressOutOfRange(base + offset))?;
+            lines.push(ListingLine { line: line_number, address, length });
+        }
+        let file = file.ok_or_else(|| R6502Error::BadListing("No \"Main file\" line, not a ca65 listing".to_owned()))?;
+        Ok(Self { file, lines })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P, symbols: Option<&SymbolTable>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?, symbols)
+    }
+}
+
+// Address, whether it is relocatable and the include depth at the start of a
+// listing line, or `None` for header lines.
+fn parse_prefix(line: &str) -> Option<(u32, bool, char)> {
+    let offset = u32::from_str_radix(line.get(..6)?, 16).ok()?;
+    let mut flags = line.get(6..9)?.chars();
+    let relocatable = match flags.next()? {
+        'r' => true,
+        ' ' => false,
+        _ => return None,
+    };
+    let depth = flags.nth(1).filter(char::is_ascii_digit)?;
+    Some((offset, relocatable, depth))
+}
+
+// Source text without its label and comment.
+fn statement(source: &str) -> &str {
+    let code = source.split(';').next().unwrap_or_default().trim();
+    match code.split_once(':') {
+        Some((label, rest)) if !label.contains(char::is_whitespace) && !label.starts_with('.') => rest.trim(),
+        _ => code,
+    }
+}
+
+// Segment selected by a `.segment` directive or one of its shorthands.
+fn segment_name(statement: &str) -> Option<String> {
+    let (directive, argument) = statement.split_once(char::is_whitespace).unwrap_or((statement, ""));
+    let name = match directive.to_ascii_lowercase().as_str() {
+        ".segment" => argument.trim().split(',').next()?.trim().trim_matches('"'),
+        ".code" => "CODE",
+        ".rodata" => "RODATA",
+        ".data" => "DATA",
+        ".bss" => "BSS",
+        ".zeropage" => "ZEROPAGE",
+        _ => return None,
+    };
+    Some(name.to_owned())
+}
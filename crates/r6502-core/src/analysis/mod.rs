@@ -0,0 +1,10 @@
+// Offline analysis of ROM images and recorded runs.
+
+pub mod cfg;
+pub mod coverage;
+pub mod execution;
+pub mod histogram;
+pub mod interrupts;
+pub mod listing;
+pub mod watch;
+pub mod zero_page;
@@ -0,0 +1,96 @@
+// Chronological log of the writes into memory regions of interest, e.g. to see
+// who changes a game's score variable and when. Easier than filtering the raw
+// cycle log, and it keeps the value each write replaced. Grouped by the writing
+// instruction it answers "who wrote to $0200?" for a corrupted range, and the
+// busiest writers are where to put breakpoints to catch the next one.
+
+use std::collections::BTreeMap;
+
+/// One write into a watched region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchedWrite {
+    /// Cycle the writing instruction started on.
+    pub cycle: u64,
+    pub pc: u16,
+    pub address: u16,
+    pub old: u8,
+    pub new: u8,
+}
+
+/// An instruction that wrote into a range, with how often and when.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Writer {
+    pub pc: u16,
+    pub writes: usize,
+    /// Cycles of its first and last writes.
+    pub first: u64,
+    pub last: u64,
+    /// Address and value of its last write.
+    pub address: u16,
+    pub value: u8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchLog {
+    // Inclusive (start, end) ranges.
+    regions: Vec<(u16, u16)>,
+    pub writes: Vec<WatchedWrite>,
+}
+
+impl WatchLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Logs writes to `start..=end` from now on.
+    pub fn watch(&mut self, start: u16, end: u16) {
+        self.regions.push((start, end));
+    }
+
+    pub fn regions(&self) -> &[(u16, u16)] {
+        &self.regions
+    }
+
+    pub fn contains(&self, address: u16) -> bool {
+        self.regions.iter().any(|&(start, end)| start <= address && address <= end)
+    }
+
+    pub fn record(&mut self, write: WatchedWrite) {
+        self.writes.push(write);
+    }
+
+    /// Writes to a single address, oldest first.
+    pub fn history(&self, address: u16) -> impl Iterator<Item = &WatchedWrite> {
+        self.writes.iter().filter(move |write| write.address == address)
+    }
+
+    /// Instructions that wrote into `start..=end`, most writes first, then by
+    /// address. Only writes made while the range was watched are seen.
+    pub fn writers(&self, start: u16, end: u16) -> Vec<Writer> {
+        let mut writers: BTreeMap<u16, Writer> = BTreeMap::new();
+        for write in self.writes.iter().filter(|write| start <= write.address && write.address <= end) {
+            let writer = writers.entry(write.pc).or_insert(Writer { pc: write.pc, writes: 0, first: write.cycle, last: 0, address: 0, value: 0 });
+            writer.writes += 1;
+            writer.last = write.cycle;
+            writer.address = write.address;
+            writer.value = write.new;
+        }
+        let mut writers: Vec<Writer> = writers.into_values().collect();
+        writers.sort_by_key(|writer| std::cmp::Reverse(writer.writes));
+        writers
+    }
+
+    pub fn clear(&mut self) {
+        self.writes.clear();
+    }
+
+    /// The log as CSV with a `cycle,pc,address,old,new` header, addresses and
+    /// values in hex.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("cycle,pc,address,old,new\n");
+        for write in self.writes.iter() {
+            out += &format!("{},{:04X},{:04X},{:02X},{:02X}\n", write.cycle, write.pc, write.address, write.old, write.new);
+        }
+        out
+    }
+}
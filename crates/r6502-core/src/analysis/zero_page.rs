@@ -0,0 +1,143 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::symbols::SymbolTable;
+
+// Who uses which zero page locations. Every CPU read and write of page zero is
+// counted against the instruction that made it, so two libraries that both
+// assume they own $FB-$FE show up as two code regions on the same addresses.
+// The pointer fetches of the indirect modes count as reads, so both bytes of a
+// pointer appear. Regions are the nearest symbol at or below the instruction
+// when symbols are given, and its page otherwise.
+
+/// Reads and writes of one location.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Access {
+    pub reads: u64,
+    pub writes: u64,
+}
+
+impl Access {
+    fn add(&mut self, other: Access) {
+        self.reads += other.reads;
+        self.writes += other.writes;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ZeroPageUsage {
+    /// Accesses by (zero page address, pc of the instruction).
+    accesses: HashMap<(u8, u16), Access>,
+}
+
+impl ZeroPageUsage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_read(&mut self, address: u8, pc: u16) {
+        self.accesses.entry((address, pc)).or_default().reads += 1;
+    }
+
+    pub fn record_write(&mut self, address: u8, pc: u16) {
+        self.accesses.entry((address, pc)).or_default().writes += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.accesses.clear();
+    }
+
+    /// Locations touched at all, in order.
+    pub fn used(&self) -> Vec<u8> {
+        let mut used: Vec<u8> = self.accesses.keys().map(|(address, _)| *address).collect();
+        used.sort();
+        used.dedup();
+        used
+    }
+
+    /// Locations nothing touched, the ones left for a new allocation.
+    pub fn free(&self) -> Vec<u8> {
+        let used = self.used();
+        (0..=255).filter(|address| used.binary_search(address).is_err()).collect()
+    }
+
+    pub fn access(&self, address: u8) -> Access {
+        let mut total = Access::default();
+        for access in self.accesses.iter().filter(|((zp, _), _)| *zp == address).map(|(_, access)| access) {
+            total.add(*access);
+        }
+        total
+    }
+
+    /// Instructions that touched `address`, by pc.
+    pub fn users(&self, address: u8) -> Vec<(u16, Access)> {
+        let mut users: Vec<(u16, Access)> =
+            self.accesses.iter().filter(|((zp, _), _)| *zp == address).map(|((_, pc), access)| (*pc, *access)).collect();
+        users.sort_by_key(|(pc, _)| *pc);
+        users
+    }
+
+    /// Code regions that touched `address`, with their combined accesses.
+    pub fn regions(&self, address: u8, symbols: Option<&SymbolTable>) -> Vec<(String, Access)> {
+        let names = Regions::new(symbols);
+        let mut regions: BTreeMap<String, Access> = BTreeMap::new();
+        for (pc, access) in self.users(address) {
+            regions.entry(names.name(pc)).or_default().add(access);
+        }
+        regions.into_iter().collect()
+    }
+
+    /// Locations more than one region uses where at least one of them writes:
+    /// the candidates for a conflict.
+    pub fn shared(&self, symbols: Option<&SymbolTable>) -> Vec<u8> {
+        self.used()
+            .into_iter()
+            .filter(|address| {
+                let regions = self.regions(*address, symbols);
+                regions.len() > 1 && regions.iter().any(|(_, access)| access.writes > 0)
+            })
+            .collect()
+    }
+
+    /// A line per location used, with its accesses and the regions behind them.
+    /// Shared locations are marked with `*`.
+    pub fn report(&self, symbols: Option<&SymbolTable>) -> String {
+        let used = self.used();
+        let shared = self.shared(symbols);
+        let mut out = format!("zero page: {} of 256 bytes used, {} shared\naddr   reads  writes  regions\n", used.len(), shared.len());
+        for address in used {
+            let access = self.access(address);
+            let regions: Vec<String> = self
+                .regions(address, symbols)
+                .into_iter()
+                .map(|(name, access)| match (access.reads > 0, access.writes > 0) {
+                    (true, true) => format!("{} (rw)", name),
+                    (false, true) => format!("{} (w)", name),
+                    _ => format!("{} (r)", name),
+                })
+                .collect();
+            let marker = if shared.contains(&address) { " *" } else { "" };
+            out += &format!("${:02X} {:>7} {:>7}  {}{}\n", address, access.reads, access.writes, regions.join(", "), marker);
+        }
+        out
+    }
+}
+
+// Names a pc by the symbol at or below it.
+struct Regions<'a> {
+    symbols: Vec<(u16, &'a str)>,
+}
+
+impl<'a> Regions<'a> {
+    fn new(symbols: Option<&'a SymbolTable>) -> Self {
+        let mut symbols: Vec<(u16, &str)> = symbols.map(|symbols| symbols.iter().map(|(name, address)| (address, name)).collect()).unwrap_or_default();
+        symbols.sort();
+        Self { symbols }
+    }
+
+    fn name(&self, pc: u16) -> String {
+        match self.symbols.partition_point(|(address, _)| *address <= pc) {
+            0 => format!("${:02X}xx", pc >> 8),
+            index => self.symbols[index - 1].1.to_owned(),
+        }
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+
+use crate::error::{R6502Error, Result};
+use crate::instructions::{AddressingMode, Instruction, OpCode, DECODE_TABLE};
+use crate::symbols::SymbolTable;
+
+// A small two pass assembler for test fixtures and examples, so short programs
+// can be written as source instead of hand encoded bytes. It knows the
+// instructions the emulator implements and a handful of directives:
+//
+//     counter = $10          ; constants
+//             .org $0200
+//     start:  ldx #<(table+2)
+//     loop:   inc counter
+//             dex
+//             bne loop
+//             .byte 1, 2, "text"
+//     table:  .word start, $FFFF
+//
+// Numbers are decimal, `$` hex, `%` binary or a quoted character; `*` is the
+// current address and `<`/`>` take the low or high byte of an expression.
+// Operands that fit in a byte use zero page addressing when the instruction has
+// it, unless they refer to a label defined further down.
+
+/// Machine code produced from a source file, with its labels.
+#[derive(Debug, Clone)]
+pub struct Assembly {
+    pub origin: u16,
+    pub image: Vec<u8>,
+    pub symbols: SymbolTable,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Number(i64),
+    Label(String),
+    Here,
+}
+
+#[derive(Debug, Clone)]
+struct Expression {
+    /// Terms with their sign.
+    terms: Vec<(bool, Term)>,
+    low: bool,
+    high: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    None,
+    Accumulator,
+    Immediate(Expression),
+    Address(Expression),
+    AddressX(Expression),
+    AddressY(Expression),
+    Indirect(Expression),
+    IndirectX(Expression),
+    IndirectY(Expression),
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Byte(Expression),
+    Text(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+enum Statement {
+    Empty,
+    Instruction(OpCode, Operand),
+    Bytes(Vec<Item>),
+    Words(Vec<Expression>),
+    Org(Expression),
+    Constant(String, Expression),
+}
+
+struct Line {
+    number: usize,
+    label: Option<String>,
+    statement: Statement,
+}
+
+fn error(line: usize, reason: String) -> R6502Error {
+    R6502Error::Assembly { line, reason }
+}
+
+/// Opcode byte for `opcode` in `mode`, if the emulator implements that combination.
+pub fn encode(opcode: OpCode, mode: Option<AddressingMode>) -> Option<u8> {
+    let implied = |candidate: Option<AddressingMode>| matches!(candidate, None | Some(AddressingMode::Implied));
+    DECODE_TABLE
+        .iter()
+        .position(|Instruction { opcode: candidate, mode: candidate_mode }| {
+            *candidate == opcode
+                && opcode.is_implemented()
+                && (*candidate_mode == mode || (implied(mode) && implied(*candidate_mode)))
+        })
+        .map(|byte| byte as u8)
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn parse_number(text: &str, line: usize) -> Result<i64> {
+    let parsed = if let Some(hex) = text.strip_prefix('$') {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(binary) = text.strip_prefix('%') {
+        i64::from_str_radix(binary, 2)
+    } else {
+        text.parse::<i64>()
+    };
+    parsed.map_err(|_| error(line, format!("bad number {}", text)))
+}
+
+fn parse_term(text: &str, line: usize) -> Result<Term> {
+    let text = text.trim();
+    if text == "*" {
+        Ok(Term::Here)
+    } else if text.len() == 3 && text.starts_with('\'') && text.ends_with('\'') {
+        Ok(Term::Number(text.as_bytes()[1] as i64))
+    } else if is_identifier(text) {
+        Ok(Term::Label(text.to_owned()))
+    } else {
+        parse_number(text, line).map(Term::Number)
+    }
+}
+
+fn parse_expression(text: &str, line: usize) -> Result<Expression> {
+    let mut text = text.trim();
+    let (low, high) = match text.chars().next() {
+        Some('<') => (true, false),
+        Some('>') => (false, true),
+        _ => (false, false),
+    };
+    if low || high {
+        text = text[1..].trim();
+    }
+    if text.starts_with('(') && text.ends_with(')') {
+        text = &text[1..text.len() - 1];
+    }
+    if text.is_empty() {
+        return Err(error(line, "missing expression".to_owned()));
+    }
+
+    let mut terms = vec![];
+    let mut positive = true;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        // A leading sign or a `*` term isn't an operator.
+        if (c == '+' || c == '-') && index > start {
+            terms.push((positive, parse_term(&text[start..index], line)?));
+            positive = c == '+';
+            start = index + 1;
+        } else if c == '-' && index == start {
+            positive = !positive;
+            start = index + 1;
+        }
+    }
+    terms.push((positive, parse_term(&text[start..], line)?));
+    Ok(Expression { terms, low, high })
+}
+
+fn parse_operand(text: &str, line: usize) -> Result<Operand> {
+    let text: String = match text.contains('\'') {
+        true => text.trim().to_owned(),
+        false => text.split_whitespace().collect(),
+    };
+    let text = text.as_str();
+    let upper = text.to_ascii_uppercase();
+    if text.is_empty() {
+        return Ok(Operand::None);
+    }
+    if upper == "A" {
+        return Ok(Operand::Accumulator);
+    }
+    if let Some(value) = text.strip_prefix('#') {
+        return parse_expression(value, line).map(Operand::Immediate);
+    }
+    if let Some(inside) = text.strip_prefix('(') {
+        if let Some(inner) = upper.strip_suffix(",X)") {
+            return parse_expression(&text[1..inner.len()], line).map(Operand::IndirectX);
+        }
+        if let Some(inner) = upper.strip_suffix("),Y") {
+            return parse_expression(&text[1..inner.len()], line).map(Operand::IndirectY);
+        }
+        if let Some(address) = inside.strip_suffix(')').filter(|address| !address.contains('(')) {
+            return parse_expression(address, line).map(Operand::Indirect);
+        }
+    }
+    if let Some(address) = upper.strip_suffix(",X") {
+        return parse_expression(&text[..address.len()], line).map(Operand::AddressX);
+    }
+    if let Some(address) = upper.strip_suffix(",Y") {
+        return parse_expression(&text[..address.len()], line).map(Operand::AddressY);
+    }
+    parse_expression(text, line).map(Operand::Address)
+}
+
+// Splits on commas outside of quotes.
+fn split_list(text: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                parts.push(text[start..index].trim());
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(text[start..].trim());
+    parts
+}
+
+fn strip_comment(text: &str) -> &str {
+    let mut quoted = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            '"' | '\'' => quoted = !quoted,
+            ';' if !quoted => return &text[..index],
+            _ => (),
+        }
+    }
+    text
+}
+
+fn parse_line(text: &str, number: usize) -> Result<Line> {
+    let mut text = strip_comment(text).trim();
+    let mut label = None;
+    if let Some((name, rest)) = text.split_once(':') {
+        if is_identifier(name.trim()) && !name.contains('"') {
+            label = Some(name.trim().to_owned());
+            text = rest.trim();
+        }
+    }
+    if let Some((name, value)) = text.split_once('=') {
+        if is_identifier(name.trim()) {
+            let expression = parse_expression(value, number)?;
+            return Ok(Line { number, label, statement: Statement::Constant(name.trim().to_owned(), expression) });
+        }
+    }
+
+    let (word, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let statement = match word.to_ascii_lowercase().as_str() {
+        "" => Statement::Empty,
+        ".org" => Statement::Org(parse_expression(rest, number)?),
+        ".byte" | ".db" => Statement::Bytes(
+            split_list(rest)
+                .into_iter()
+                .map(|item| match item.strip_prefix('"').and_then(|item| item.strip_suffix('"')) {
+                    Some(text) => Ok(Item::Text(text.as_bytes().to_vec())),
+                    None => parse_expression(item, number).map(Item::Byte),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        ".word" | ".dw" => Statement::Words(
+            split_list(rest).into_iter().map(|item| parse_expression(item, number)).collect::<Result<_>>()?,
+        ),
+        mnemonic => {
+            let opcode = mnemonic
+                .parse::<OpCode>()
+                .ok()
+                .filter(|opcode| opcode.is_implemented())
+                .ok_or_else(|| error(number, format!("unknown instruction {}", word)))?;
+            Statement::Instruction(opcode, parse_operand(rest, number)?)
+        }
+    };
+    Ok(Line { number, label, statement })
+}
+
+struct Context<'a> {
+    labels: &'a HashMap<String, i64>,
+    here: u16,
+    line: usize,
+    /// Whether labels that aren't defined yet are an error.
+    strict: bool,
+}
+
+impl Context<'_> {
+    // None if the expression refers to a label that isn't known yet.
+    fn evaluate(&self, expression: &Expression) -> Result<Option<i64>> {
+        let mut total = 0;
+        for (positive, term) in expression.terms.iter() {
+            let value = match term {
+                Term::Number(value) => *value,
+                Term::Here => self.here as i64,
+                Term::Label(name) => match self.labels.get(name) {
+                    Some(value) => *value,
+                    None if self.strict => return Err(error(self.line, format!("undefined label {}", name))),
+                    None => return Ok(None),
+                },
+            };
+            total += if *positive { value } else { -value };
+        }
+        Ok(Some(match (expression.low, expression.high) {
+            (true, _) => total & 0xFF,
+            (_, true) => (total >> 8) & 0xFF,
+            _ => total,
+        }))
+    }
+
+    fn byte(&self, expression: &Expression) -> Result<u8> {
+        match self.evaluate(expression)? {
+            Some(value) if (-128..=255).contains(&value) => Ok(value as u8),
+            Some(value) => Err(error(self.line, format!("{} does not fit in a byte", value))),
+            None => Ok(0),
+        }
+    }
+
+    fn word(&self, expression: &Expression) -> Result<u16> {
+        match self.evaluate(expression)? {
+            Some(value) if (-32768..=65535).contains(&value) => Ok(value as u16),
+            Some(value) => Err(error(self.line, format!("{} does not fit in a word", value))),
+            None => Ok(0),
+        }
+    }
+}
+
+// Picks the addressing mode for an instruction and encodes it. Zero page forms
+// are only used when the operand is known to fit, so both passes agree on the
+// length as long as `zero_page` is carried over from the first.
+fn encode_instruction(opcode: OpCode, operand: &Operand, context: &Context, zero_page: &mut Option<bool>) -> Result<Vec<u8>> {
+    use AddressingMode::*;
+    let line = context.line;
+    let missing = |mode: &str| error(line, format!("{} has no {} mode", opcode, mode));
+    let with = |mode: AddressingMode| encode(opcode, Some(mode)).ok_or_else(|| missing(mode.short_name()));
+
+    let indexed = |expression: &Expression, short: AddressingMode, long: AddressingMode, zero_page: &mut Option<bool>| -> Result<Vec<u8>> {
+        let fits = *zero_page.get_or_insert_with(|| {
+            encode(opcode, Some(short)).is_some()
+                && matches!(context.evaluate(expression), Ok(Some(value)) if (0..=0xFF).contains(&value))
+        });
+        if fits {
+            Ok(vec![with(short)?, context.byte(expression)?])
+        } else {
+            let address = context.word(expression)?;
+            Ok(vec![with(long)?, address as u8, (address >> 8) as u8])
+        }
+    };
+
+    match operand {
+        Operand::None => match encode(opcode, None) {
+            Some(byte) => Ok(vec![byte]),
+            None => Ok(vec![with(Accumulator).map_err(|_| error(line, format!("{} needs an operand", opcode)))?]),
+        },
+        Operand::Accumulator => Ok(vec![with(Accumulator)?]),
+        Operand::Immediate(expression) => Ok(vec![with(Immediate)?, context.byte(expression)?]),
+        Operand::Address(expression) if encode(opcode, Some(Relative)).is_some() => {
+            let target = context.word(expression)? as i64;
+            let offset = target - (context.here as i64 + 2);
+            if context.strict && !(-128..=127).contains(&offset) {
+                return Err(error(line, format!("branch to {:#06x} is out of range", target)));
+            }
+            Ok(vec![with(Relative)?, offset as u8])
+        }
+        Operand::Address(expression) => indexed(expression, DirectZeroPage, DirectAbsolute, zero_page),
+        Operand::AddressX(expression) => indexed(expression, DirectZeroPageX, DirectAbsoluteX, zero_page),
+        Operand::AddressY(expression) => indexed(expression, DirectZeroPageY, DirectAbsoluteY, zero_page),
+        Operand::Indirect(expression) => {
+            let address = context.word(expression)?;
+            Ok(vec![with(IndirectAbsolute)?, address as u8, (address >> 8) as u8])
+        }
+        Operand::IndirectX(expression) => Ok(vec![with(IndirectZeroPageX)?, context.byte(expression)?]),
+        Operand::IndirectY(expression) => Ok(vec![with(IndirectZeroPageY)?, context.byte(expression)?]),
+    }
+}
+
+/// Assembles `source` into an image starting at `origin`. Gaps left by `.org` are
+/// filled with zeroes.
+pub fn assemble(source: &str, origin: u16) -> Result<Assembly> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| parse_line(text, index + 1))
+        .collect::<Result<Vec<Line>>>()?;
+
+    let mut labels: HashMap<String, i64> = HashMap::new();
+    let mut zero_page = vec![None; lines.len()];
+    let mut image = vec![];
+    for strict in [false, true] {
+        let mut here = origin;
+        image.clear();
+        for (line, zero_page) in lines.iter().zip(zero_page.iter_mut()) {
+            if let Some(label) = line.label.as_ref() {
+                if !strict && labels.insert(label.clone(), here as i64).is_some() {
+                    return Err(error(line.number, format!("{} is defined twice", label)));
+                }
+            }
+            let context = Context { labels: &labels, here, line: line.number, strict };
+            let bytes = match &line.statement {
+                Statement::Empty => vec![],
+                Statement::Constant(name, expression) => {
+                    let Some(value) = context.evaluate(expression)? else {
+                        return Err(error(line.number, format!("{} must be defined after the labels it uses", name)));
+                    };
+                    labels.insert(name.clone(), value);
+                    vec![]
+                }
+                Statement::Org(expression) => {
+                    let Some(address) = context.evaluate(expression)? else {
+                        return Err(error(line.number, ".org needs an address known up front".to_owned()));
+                    };
+                    if address < here as i64 || address > 0xFFFF {
+                        return Err(error(line.number, format!(".org {:#06x} is behind the current address", address)));
+                    }
+                    vec![0; (address - here as i64) as usize]
+                }
+                Statement::Bytes(items) => {
+                    let mut bytes = vec![];
+                    for item in items.iter() {
+                        match item {
+                            Item::Byte(expression) => bytes.push(context.byte(expression)?),
+                            Item::Text(text) => bytes.extend_from_slice(text),
+                        }
+                    }
+                    bytes
+                }
+                Statement::Words(expressions) => {
+                    let mut bytes = vec![];
+                    for expression in expressions.iter() {
+                        let word = context.word(expression)?;
+                        bytes.extend_from_slice(&[word as u8, (word >> 8) as u8]);
+                    }
+                    bytes
+                }
+                Statement::Instruction(opcode, operand) => encode_instruction(*opcode, operand, &context, zero_page)?,
+            };
+            if here as usize + bytes.len() > 0x10000 {
+                return Err(R6502Error::ImageTooLarge { length: here as usize + bytes.len() - origin as usize, origin });
+            }
+            here = here.wrapping_add(bytes.len() as u16);
+            image.extend(bytes);
+        }
+    }
+
+    let mut symbols = SymbolTable::default();
+    for line in lines.iter() {
+        if let Some(label) = line.label.as_ref() {
+            symbols.insert(label, labels[label] as u16);
+        }
+    }
+    Ok(Assembly { origin, image, symbols })
+}
@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// Runs independent jobs (one emulator per test case) across a pool of worker threads.
+// Workers pull the next case index from a shared counter, so slow cases don't
+// hold up a whole chunk of the input.
+
+pub struct BatchRunner {
+    threads: usize,
+}
+
+impl Default for BatchRunner {
+    fn default() -> Self {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Self { threads }
+    }
+}
+
+#[derive(Debug)]
+pub struct BatchSummary<E> {
+    pub total: usize,
+    pub passed: usize,
+    /// Index of the failing case along with its error, sorted by index.
+    pub failures: Vec<(usize, E)>,
+}
+
+impl<E> BatchSummary<E> {
+    pub fn all_passed(&self) -> bool {
+        self.passed == self.total
+    }
+}
+
+impl BatchRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Runs `job` once per case and returns the results in the order of `cases`.
+    pub fn run<C, R, F>(&self, cases: &[C], job: F) -> Vec<R>
+    where
+        C: Sync,
+        R: Send,
+        F: Fn(&C) -> R + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let results: Mutex<Vec<(usize, R)>> = Mutex::new(Vec::with_capacity(cases.len()));
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(cases.len()) {
+                scope.spawn(|| loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(case) = cases.get(index) else {
+                        break;
+                    };
+                    let result = job(case);
+                    results.lock().unwrap().push((index, result));
+                });
+            }
+        });
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    /// Runs a pass/fail `job` per case and aggregates the outcome.
+    pub fn run_checked<C, E, F>(&self, cases: &[C], job: F) -> BatchSummary<E>
+    where
+        C: Sync,
+        E: Send,
+        F: Fn(&C) -> Result<(), E> + Sync,
+    {
+        let results = self.run(cases, job);
+        let total = results.len();
+        let failures: Vec<(usize, E)> = results
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, result)| result.err().map(|error| (index, error)))
+            .collect();
+        BatchSummary { total, passed: total - failures.len(), failures }
+    }
+}
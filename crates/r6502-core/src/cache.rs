@@ -0,0 +1,65 @@
+use crate::instructions::Instruction;
+
+// Direct mapped cache of decoded instructions keyed by address. A hit saves the
+// opcode fetch through the memory lock; the executor still reads operands, so
+// only the opcode byte has to be invalidated when it is written.
+//
+// Memory that changes behind the CPU's back (bank switching, DMA, `poke`
+// through another handle) isn't seen, so the cache is opt-in.
+
+const ENTRIES: usize = 1024;
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    address: u16,
+    byte: u8,
+    instruction: Instruction,
+}
+
+#[derive(Debug, Clone)]
+pub struct DecodeCache {
+    entries: Vec<Option<Entry>>,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Default for DecodeCache {
+    fn default() -> Self {
+        Self { entries: vec![None; ENTRIES], hits: 0, misses: 0 }
+    }
+}
+
+impl DecodeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opcode byte and decoded instruction cached for `address`.
+    pub fn get(&mut self, address: u16) -> Option<(u8, Instruction)> {
+        match self.entries[address as usize % ENTRIES] {
+            Some(entry) if entry.address == address => {
+                self.hits += 1;
+                Some((entry.byte, entry.instruction))
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, address: u16, byte: u8, instruction: Instruction) {
+        self.entries[address as usize % ENTRIES] = Some(Entry { address, byte, instruction });
+    }
+
+    pub fn invalidate(&mut self, address: u16) {
+        let slot = &mut self.entries[address as usize % ENTRIES];
+        if slot.is_some_and(|entry| entry.address == address) {
+            *slot = None;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
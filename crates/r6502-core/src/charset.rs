@@ -0,0 +1,107 @@
+// Character sets 6502 machines store text in, for searching memory for a string
+// and for showing memory as text beside hex dumps.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    #[default]
+    Ascii,
+    /// Commodore PETSCII in the power-on uppercase/graphics mode, where letters
+    /// are $41-$5A whatever their case in the search text.
+    Petscii,
+    /// C64 screen codes, as found in screen memory: `@` is 0, letters start at 1,
+    /// and the top bit selects reverse video.
+    ScreenCode,
+    /// Apple II screen bytes, with the top bit set for normal video. Lower values
+    /// are the same characters flashing or in inverse.
+    Apple,
+}
+
+impl Charset {
+    /// Byte for `c`, if the character set has it.
+    pub fn encode(&self, c: char) -> Option<u8> {
+        match self {
+            Self::Ascii => c.is_ascii().then_some(c as u8),
+            Self::Petscii => match c {
+                'a'..='z' => Some(c.to_ascii_uppercase() as u8),
+                ' '..='Z' | '[' | ']' => Some(c as u8),
+                '£' => Some(0x5C),
+                '↑' => Some(0x5E),
+                '←' => Some(0x5F),
+                '\n' | '\r' => Some(0x0D),
+                _ => None,
+            },
+            Self::ScreenCode => match c {
+                '@' => Some(0),
+                'a'..='z' | 'A'..='Z' => Some(c.to_ascii_uppercase() as u8 - 0x40),
+                '[' => Some(0x1B),
+                '£' => Some(0x1C),
+                ']' => Some(0x1D),
+                '↑' => Some(0x1E),
+                '←' => Some(0x1F),
+                ' '..='?' => Some(c as u8),
+                _ => None,
+            },
+            Self::Apple => match c.to_ascii_uppercase() {
+                c @ ' '..='_' => Some(c as u8 | 0x80),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn encode_str(&self, text: &str) -> Option<Vec<u8>> {
+        text.chars().map(|c| self.encode(c)).collect()
+    }
+
+    /// Character shown for `byte`, `.` for control codes and graphics characters.
+    pub fn decode(&self, byte: u8) -> char {
+        let shown = match self {
+            Self::Ascii => byte,
+            Self::Petscii => match byte {
+                0x5C => return '£',
+                0x5E => return '↑',
+                0x5F => return '←',
+                0xA0 => b' ',
+                0x20..=0x5D => byte,
+                _ => 0,
+            },
+            Self::ScreenCode => match byte & 0x7F {
+                0x00 => b'@',
+                code @ 0x01..=0x1A => code + 0x40,
+                0x1B => b'[',
+                0x1C => return '£',
+                0x1D => b']',
+                0x1E => return '↑',
+                0x1F => return '←',
+                code @ 0x20..=0x3F => code,
+                _ => 0,
+            },
+            // The 2513 generator only has upper case, picked by the low six bits.
+            Self::Apple => match byte & 0x3F {
+                code @ 0x00..=0x1F => code + 0x40,
+                code => code,
+            },
+        };
+        match shown {
+            0x20..=0x7E => shown as char,
+            _ => '.',
+        }
+    }
+
+    pub fn decode_bytes(&self, bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| self.decode(*byte)).collect()
+    }
+}
+
+impl std::str::FromStr for Charset {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "ascii" => Ok(Self::Ascii),
+            "petscii" => Ok(Self::Petscii),
+            "screen" | "screencode" => Ok(Self::ScreenCode),
+            "apple" => Ok(Self::Apple),
+            _ => Err(format!("Unknown character set {}, expected ascii, petscii, screen or apple", name)),
+        }
+    }
+}
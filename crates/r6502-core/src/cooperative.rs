@@ -0,0 +1,82 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+
+// Running the emulator from async code, e.g. a tokio server that runs
+// submitted programs, without giving each one a thread. `run_async` runs a
+// slice of cycles per poll and then yields back to the executor, so a long or
+// endless program can't starve the other tasks. Nothing here depends on a
+// particular runtime.
+
+/// Cycles run per poll unless [`RunAsync::slice`] says otherwise.
+pub const DEFAULT_SLICE: u64 = 10_000;
+
+/// Future returned by [`CPUEmulator::run_async`].
+pub struct RunAsync<'a, M: VirtualMemory> {
+    emulator: &'a mut CPUEmulator<M>,
+    remaining: u64,
+    slice: u64,
+}
+
+impl<M: VirtualMemory> RunAsync<'_, M> {
+    /// Yields every `cycles` cycles instead of every [`DEFAULT_SLICE`].
+    pub fn slice(mut self, cycles: u64) -> Self {
+        self.slice = cycles.max(1);
+        self
+    }
+}
+
+impl<M: VirtualMemory> Future for RunAsync<'_, M> {
+    type Output = StopReason;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<StopReason> {
+        let this = self.get_mut();
+        let start = this.emulator.clock();
+        match this.emulator.run_for_cycles(this.slice.min(this.remaining)) {
+            StopReason::Timeout => {
+                this.remaining = this.remaining.saturating_sub(this.emulator.clock() - start);
+                if this.remaining == 0 {
+                    return Poll::Ready(StopReason::Timeout);
+                }
+                context.waker().wake_by_ref();
+                Poll::Pending
+            }
+            reason => Poll::Ready(reason),
+        }
+    }
+}
+
+impl<M: VirtualMemory> CPUEmulator<M> {
+    /// Like [`CPUEmulator::run_for_cycles`], but yields to the executor between
+    /// slices of cycles. Resolves to [`StopReason::Timeout`] once `budget` cycles
+    /// have run.
+    pub fn run_async(&mut self, budget: u64) -> RunAsync<'_, M> {
+        RunAsync { emulator: self, remaining: budget, slice: DEFAULT_SLICE }
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Polls `future` to completion on the current thread, for callers without an
+/// async runtime.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
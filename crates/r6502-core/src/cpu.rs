@@ -0,0 +1,73 @@
+use crate::cycle_log::CycleLog;
+use crate::emulator::{CPUEmulator, VirtualMemory};
+use crate::state::Registers;
+
+// The interface between a 6502 core and whatever hosts it, so hosts like the
+// differential runner can drive cores other than `CPUEmulator`: a cycle
+// stepped core, a wrapper around another crate, or r6502 with different
+// settings.
+
+/// (address, value) of each write an instruction made, in order.
+pub type Writes = Vec<(u16, u8)>;
+
+pub trait Cpu6502 {
+    /// Runs the next instruction, taking a pending interrupt first. Returns false
+    /// without running anything once the processor has stopped, e.g. after KIL
+    /// or a fault.
+    fn step(&mut self) -> bool;
+    /// Writes made by the last step.
+    fn last_writes(&self) -> Writes;
+    fn registers(&self) -> Registers;
+    fn set_registers(&mut self, registers: Registers);
+    /// Loads the program counter from the reset vector and starts the processor.
+    fn reset(&mut self);
+    /// Drives the IRQ input, true while held low. Devices on the bus can pull it too.
+    fn set_irq(&mut self, active: bool);
+    /// Signals a falling edge on NMI, taken before the next instruction.
+    fn nmi(&mut self);
+    /// Cycles run so far.
+    fn clock(&self) -> u64;
+    /// Reads memory without side effects on the processor.
+    fn peek(&self, address: u16) -> u8;
+}
+
+// The cycle log is cleared before each step, so it only ever holds the last
+// instruction's cycles.
+impl<M: VirtualMemory> Cpu6502 for CPUEmulator<M> {
+    fn step(&mut self) -> bool {
+        self.state.cycles.clear();
+        self.execute_next_instruction().is_ok()
+    }
+
+    fn last_writes(&self) -> Writes {
+        self.state.cycles.writes().map(|cycle| (cycle.address, cycle.value)).collect()
+    }
+
+    fn registers(&self) -> Registers {
+        self.state.registers()
+    }
+
+    fn set_registers(&mut self, registers: Registers) {
+        self.state.set_registers(registers);
+    }
+
+    fn reset(&mut self) {
+        CPUEmulator::reset(self);
+    }
+
+    fn set_irq(&mut self, active: bool) {
+        self.set_irq_line(active);
+    }
+
+    fn nmi(&mut self) {
+        CPUEmulator::nmi(self);
+    }
+
+    fn clock(&self) -> u64 {
+        CPUEmulator::clock(self)
+    }
+
+    fn peek(&self, address: u16) -> u8 {
+        CPUEmulator::peek(self, address)
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::VecDeque;
+#[cfg(feature = "pretty")]
+use std::path::Path;
+
+#[cfg(feature = "pretty")]
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "pretty")]
+use tabled::{builder::Builder, settings::Style};
+
+#[cfg(feature = "pretty")]
+use crate::{disassembler::disassemble_at, error::Result, opcodes};
+use crate::format::number_format;
+use crate::state::{Registers, SystemCycle};
+
+// The last instructions the processor retired, kept whatever the log level is,
+// so that a program dying on an illegal opcode or a failing handler leaves more
+// to go on than the address it died at. When that happens the emulator files a
+// `CrashReport` holding the trace, the registers and the memory around the
+// program counter and on the stack, which can be saved as JSON for a bug report
+// or rendered for a person to read.
+
+/// Instructions kept by a default [`TraceRing`].
+pub const TRACE_DEPTH: usize = 64;
+
+/// Bytes of memory on each side of the program counter in a [`CrashReport`].
+const CONTEXT: u16 = 16;
+
+/// Bus cycles kept in a [`CrashReport`].
+const CYCLES_KEPT: usize = 32;
+
+/// Instructions listed from the program counter on in a rendered report.
+#[cfg(feature = "pretty")]
+const LISTED_AFTER: usize = 3;
+
+/// An instruction the processor finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Retired {
+    /// Clock as the instruction started.
+    pub clock: u64,
+    pub opcode: u8,
+    /// Registers as the instruction started, so `registers.pc` is its address.
+    pub registers: Registers,
+}
+
+/// The last `capacity` retired instructions, oldest first. Pushing doesn't
+/// allocate once the ring is full.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRing {
+    entries: VecDeque<Retired>,
+    capacity: usize,
+}
+
+impl Default for TraceRing {
+    fn default() -> Self {
+        Self::new(TRACE_DEPTH)
+    }
+}
+
+impl TraceRing {
+    /// A ring keeping `capacity` instructions. Zero keeps none.
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, retired: Retired) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(retired);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Retired> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// What the machine looked like when an instruction faulted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// What went wrong, as logged.
+    pub fault: String,
+    pub clock: u64,
+    /// The byte at the program counter.
+    pub opcode: u8,
+    /// Registers as the faulting instruction started.
+    pub registers: Registers,
+    /// Instructions retired before the fault, oldest first.
+    pub trace: Vec<Retired>,
+    /// Address of the first byte of `memory`.
+    pub memory_start: u16,
+    /// Memory around the program counter.
+    pub memory: Vec<u8>,
+    /// The stack page above the stack pointer, from the last byte pushed up to $01FF.
+    pub stack: Vec<u8>,
+    /// The last bus cycles, including any the faulting instruction made.
+    pub cycles: Vec<SystemCycle>,
+}
+
+impl CrashReport {
+    /// A report of `fault` with the machine as `read` sees it, after the bus
+    /// cycles in `cycles`.
+    pub fn new(fault: String, clock: u64, registers: Registers, trace: &TraceRing, cycles: &[SystemCycle], mut read: impl FnMut(u16) -> u8) -> Self {
+        let memory_start = registers.pc.saturating_sub(CONTEXT);
+        let memory_end = registers.pc.saturating_add(CONTEXT - 1);
+        Self {
+            fault,
+            clock,
+            opcode: read(registers.pc),
+            registers,
+            trace: trace.iter().copied().collect(),
+            memory_start,
+            memory: (memory_start..=memory_end).map(&mut read).collect(),
+            stack: (registers.s as u16 + 1..0x100).map(|offset| read(0x0100 + offset)).collect(),
+            cycles: cycles[cycles.len().saturating_sub(CYCLES_KEPT)..].to_vec(),
+        }
+    }
+}
+
+// Saving and rendering use the serde_json, tabled and colored of the `cli` feature.
+#[cfg(feature = "pretty")]
+impl CrashReport {
+    /// Writes the report to `path` as JSON.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::from)?;
+        Ok(std::fs::write(path, json)?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?).map_err(std::io::Error::from)?)
+    }
+
+    /// The report laid out for a terminal: the fault, registers, a listing
+    /// around the program counter, the instructions that led there, the stack
+    /// and the last bus cycles.
+    pub fn render(&self) -> String {
+        let format = number_format();
+        let mut out = format!("{}\n", format!("Crashed: {}", self).red().bold());
+
+        let mut registers = Builder::default();
+        registers.push_record(["PC", "A", "X", "Y", "S", "NV-BDIZC"]);
+        let flags: String = (0..8).map(|bit| if self.registers.p.bits() & (0x80 >> bit) != 0 { '1' } else { '0' }).collect();
+        registers.push_record([
+            format.word(self.registers.pc),
+            format.byte(self.registers.a),
+            format.byte(self.registers.x),
+            format.byte(self.registers.y),
+            format.byte(self.registers.s),
+            flags,
+        ]);
+        out.push_str(&format!("{}\n", registers.build().with(Style::modern())));
+
+        out.push_str(&format!("\n{}\n", "Code".bold()));
+        let mut address = self.listing_start();
+        let mut after = 0;
+        while after <= LISTED_AFTER {
+            let offset = address.wrapping_sub(self.memory_start) as usize;
+            let Some(bytes) = self.memory.get(offset..) else { break };
+            let instruction = disassemble_at(bytes, address);
+            if offset + instruction.bytes.len() > self.memory.len() {
+                break;
+            }
+            let hex: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let line = format!("{:04X}  {:<8}  {}", address, hex.join(" "), instruction);
+            match address == self.registers.pc {
+                true => out.push_str(&format!("{} {}\n", "=>".red().bold(), line.red().bold())),
+                false => out.push_str(&format!("   {}\n", line)),
+            }
+            if address >= self.registers.pc {
+                after += 1;
+            }
+            address = address.wrapping_add(instruction.length());
+        }
+
+        if !self.trace.is_empty() {
+            out.push_str(&format!("\n{}\n", format!("Last {} instructions", self.trace.len()).bold()));
+            let mut trace = Builder::default();
+            trace.push_record(["Clock", "PC", "Instruction", "A", "X", "Y", "S", "P"]);
+            for retired in self.trace.iter() {
+                let info = opcodes::describe(retired.opcode);
+                trace.push_record([
+                    retired.clock.to_string(),
+                    format.word(retired.registers.pc),
+                    format!("{} {}", info.mnemonic(), info.mode_name()),
+                    format.byte(retired.registers.a),
+                    format.byte(retired.registers.x),
+                    format.byte(retired.registers.y),
+                    format.byte(retired.registers.s),
+                    format.byte(retired.registers.p.bits()),
+                ]);
+            }
+            out.push_str(&format!("{}\n", trace.build().with(Style::modern())));
+        }
+
+        out.push_str(&format!("\n{}\n", "Stack".bold()));
+        if self.stack.is_empty() {
+            out.push_str("(empty)\n");
+        }
+        let top = 0x0100 + self.registers.s as u16 + 1;
+        for (row, bytes) in self.stack.chunks(8).enumerate() {
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            out.push_str(&format!("{:04X}  {}\n", top + row as u16 * 8, hex.join(" ")));
+        }
+
+        if !self.cycles.is_empty() {
+            out.push_str(&format!("\n{}\n", format!("Last {} bus cycles", self.cycles.len()).bold()));
+            let mut cycles = Builder::default();
+            cycles.push_record(["Address", "Value", "Action"]);
+            for cycle in &self.cycles {
+                cycles.push_record([format.word(cycle.address), format.byte(cycle.value), cycle.action.to_string()]);
+            }
+            out.push_str(&format!("{}\n", cycles.build().with(Style::modern())));
+        }
+        out
+    }
+
+    // Where to start listing so the program counter falls on an instruction
+    // boundary: the oldest retired instruction in the memory around it that
+    // decodes up to it, or the program counter itself.
+    fn listing_start(&self) -> u16 {
+        let reaches_pc = |start: u16| {
+            let mut address = start;
+            while address < self.registers.pc {
+                let offset = (address - self.memory_start) as usize;
+                address = address.wrapping_add(disassemble_at(&self.memory[offset..], address).length());
+            }
+            address == self.registers.pc
+        };
+        self.trace
+            .iter()
+            .map(|retired| retired.registers.pc)
+            .filter(|&pc| self.memory_start <= pc && pc < self.registers.pc)
+            .find(|&pc| reaches_pc(pc))
+            .unwrap_or(self.registers.pc)
+    }
+}
+
+impl std::fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = number_format();
+        write!(f, "{} at {} after {} cycles", self.fault, format.word(self.registers.pc), self.clock)
+    }
+}
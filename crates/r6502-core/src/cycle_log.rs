@@ -0,0 +1,248 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
+
+use crate::error::{R6502Error, Result};
+use crate::state::{SystemAction, SystemCycle};
+
+// Bus cycles streamed to a file, for traces too long to keep in memory. The
+// emulator hands over its cycle log before every instruction (see
+// `CPUEmulator::stream_cycles`) and `CycleReader` replays it for offline
+// analysis.
+//
+// A .r6c file is "R6C" 1 followed by one record per run of identical cycles:
+//
+//     tag     bit 7 set for a write, bits 6-5 how the address is stored,
+//             bits 4-0 how many more times the cycle repeats
+//     address nothing when it is the previous one (0) or the one after it (1),
+//             a signed byte added to the previous one (2), or the whole
+//             address little endian (3)
+//     value
+//
+// so most cycles take two bytes, and polling a register takes two bytes for
+// up to 32 reads.
+//
+// `CycleLog` queries a log held in memory, whether `state.cycles` or cycles
+// read back from a file and collected.
+
+const MAGIC: &[u8; 4] = b"R6C\x01";
+
+const WRITE: u8 = 0x80;
+const SAME: u8 = 0;
+const NEXT: u8 = 1;
+const DELTA: u8 = 2;
+const ABSOLUTE: u8 = 3;
+/// Longest run one record holds.
+const MAX_RUN: u8 = 32;
+
+/// Encodes cycles to `W` as they are pushed, and finishes the file when dropped.
+/// Buffer `W` if it is a file.
+pub struct CycleWriter<W: Write> {
+    writer: W,
+    previous: u16,
+    // The cycle being repeated and how many times so far.
+    run: Option<(SystemCycle, u8)>,
+    written: u64,
+}
+
+impl CycleWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> CycleWriter<W> {
+    /// Writes the header, ready for cycles.
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        Ok(Self { writer, previous: 0, run: None, written: 0 })
+    }
+
+    pub fn push(&mut self, cycle: &SystemCycle) -> Result<()> {
+        self.written += 1;
+        match self.run.as_mut() {
+            Some((repeated, count)) if repeated == cycle && *count < MAX_RUN => {
+                *count += 1;
+                Ok(())
+            }
+            _ => {
+                self.end_run()?;
+                self.run = Some((cycle.clone(), 1));
+                Ok(())
+            }
+        }
+    }
+
+    /// Pushes every cycle in `cycles` and empties it, e.g. the emulator's cycle log.
+    pub fn drain(&mut self, cycles: &mut Vec<SystemCycle>) -> Result<()> {
+        for cycle in cycles.iter() {
+            self.push(cycle)?;
+        }
+        cycles.clear();
+        Ok(())
+    }
+
+    /// Cycles pushed so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Writes out the run in progress and flushes `W`, leaving a complete file.
+    /// More cycles can still be pushed afterwards.
+    pub fn flush(&mut self) -> Result<()> {
+        self.end_run()?;
+        Ok(self.writer.flush()?)
+    }
+
+    /// The writer, complete up to the last flush.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    fn end_run(&mut self) -> Result<()> {
+        let Some((cycle, count)) = self.run.take() else { return Ok(()) };
+        let delta = cycle.address.wrapping_sub(self.previous) as i16;
+        let (mode, address) = match delta {
+            0 => (SAME, vec![]),
+            1 => (NEXT, vec![]),
+            -128..=127 => (DELTA, vec![delta as i8 as u8]),
+            _ => (ABSOLUTE, cycle.address.to_le_bytes().to_vec()),
+        };
+        let action = match cycle.action {
+            SystemAction::READ => 0,
+            SystemAction::WRITE => WRITE,
+        };
+        self.writer.write_all(&[action | mode << 5 | (count - 1)])?;
+        self.writer.write_all(&address)?;
+        self.writer.write_all(&[cycle.value])?;
+        self.previous = cycle.address;
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for CycleWriter<W> {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            log::warn!("Couldn't finish the cycle log: {}", error);
+        }
+    }
+}
+
+/// Decodes the cycles a [`CycleWriter`] wrote, in order.
+pub struct CycleReader<R: Read> {
+    reader: R,
+    previous: u16,
+    run: Option<(SystemCycle, u8)>,
+}
+
+impl CycleReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CycleReader<R> {
+    /// Checks the header, ready to iterate.
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic).map_err(|_| R6502Error::BadCycleLog("Not an r6502 cycle log".to_owned()))?;
+        if &magic != MAGIC {
+            return Err(R6502Error::BadCycleLog("Not an r6502 cycle log".to_owned()));
+        }
+        Ok(Self { reader, previous: 0, run: None })
+    }
+
+    // The next record, or `None` at the end of the file.
+    fn record(&mut self) -> Result<Option<(SystemCycle, u8)>> {
+        let mut tag = [0; 1];
+        if self.reader.read(&mut tag)? == 0 {
+            return Ok(None);
+        }
+        let [tag] = tag;
+        let address = match (tag >> 5) & 0x03 {
+            SAME => self.previous,
+            NEXT => self.previous.wrapping_add(1),
+            DELTA => self.previous.wrapping_add_signed(self.byte()? as i8 as i16),
+            _ => u16::from_le_bytes([self.byte()?, self.byte()?]),
+        };
+        let value = self.byte()?;
+        let action = match tag & WRITE != 0 {
+            true => SystemAction::WRITE,
+            false => SystemAction::READ,
+        };
+        self.previous = address;
+        Ok(Some((SystemCycle { address, value, action }, (tag & 0x1F) + 1)))
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        let mut byte = [0; 1];
+        self.reader.read_exact(&mut byte).map_err(|_| R6502Error::BadCycleLog("Truncated record".to_owned()))?;
+        Ok(byte[0])
+    }
+}
+
+impl<R: Read> Iterator for CycleReader<R> {
+    type Item = Result<SystemCycle>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((cycle, count)) = self.run.as_mut() {
+            *count -= 1;
+            let cycle = cycle.clone();
+            if *count == 0 {
+                self.run = None;
+            }
+            return Some(Ok(cycle));
+        }
+        match self.record() {
+            Ok(Some((cycle, count))) => {
+                if count > 1 {
+                    self.run = Some((cycle.clone(), count - 1));
+                }
+                Some(Ok(cycle))
+            }
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// Queries over logged cycles. Cycles are in the order they happened, and a
+/// window of them is picked by position in the log, so that queries chain:
+/// `cycles.between(1000..2000).reads_of(0x2002)`.
+pub trait CycleLog {
+    /// The cycles at positions `window` in the log, as much of it as there is.
+    fn between(&self, window: Range<usize>) -> &[SystemCycle];
+    fn reads(&self) -> impl Iterator<Item = &SystemCycle>;
+    fn writes(&self) -> impl Iterator<Item = &SystemCycle>;
+    /// Reads and writes of any address in `addresses`.
+    fn within(&self, addresses: RangeInclusive<u16>) -> impl Iterator<Item = &SystemCycle>;
+    fn accesses_to(&self, address: u16) -> impl Iterator<Item = &SystemCycle> {
+        self.within(address..=address)
+    }
+    fn reads_of(&self, address: u16) -> impl Iterator<Item = &SystemCycle> {
+        self.accesses_to(address).filter(|cycle| cycle.action == SystemAction::READ)
+    }
+    fn writes_to(&self, address: u16) -> impl Iterator<Item = &SystemCycle> {
+        self.accesses_to(address).filter(|cycle| cycle.action == SystemAction::WRITE)
+    }
+}
+
+impl CycleLog for [SystemCycle] {
+    fn between(&self, window: Range<usize>) -> &[SystemCycle] {
+        let end = window.end.min(self.len());
+        &self[window.start.min(end)..end]
+    }
+
+    fn reads(&self) -> impl Iterator<Item = &SystemCycle> {
+        self.iter().filter(|cycle| cycle.action == SystemAction::READ)
+    }
+
+    fn writes(&self) -> impl Iterator<Item = &SystemCycle> {
+        self.iter().filter(|cycle| cycle.action == SystemAction::WRITE)
+    }
+
+    fn within(&self, addresses: RangeInclusive<u16>) -> impl Iterator<Item = &SystemCycle> {
+        self.iter().filter(move |cycle| addresses.contains(&cycle.address))
+    }
+}
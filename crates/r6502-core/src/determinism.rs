@@ -0,0 +1,60 @@
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+use crate::error::{R6502Error, Result};
+
+// Determinism audit. Movies, netplay, fuzzers and differential runs all count
+// on the same program on the same machine going the same way every time. An
+// audit builds the workload twice from scratch, runs the two side by side and
+// compares their state hashes at checkpoints, so a device reading the host
+// clock, a seed taken from the host or anything iterating a hash map shows up
+// here rather than as a desynced replay much later.
+//
+// Both runs happen on the calling thread, one checkpoint of each in turn.
+
+/// The state of a run at a checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub clock: u64,
+    /// See [`CPUEmulator::state_hash`].
+    pub hash: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Audit {
+    /// Cycles between checkpoints.
+    pub every: u64,
+    /// Checkpoints to reach before the audit passes, unless the workload stops first.
+    pub checkpoints: usize,
+}
+
+impl Audit {
+    pub fn new(every: u64, checkpoints: usize) -> Self {
+        Self { every: every.max(1), checkpoints }
+    }
+
+    /// Builds the workload twice with `build` and runs both copies, failing with
+    /// [`R6502Error::Nondeterministic`] at the first checkpoint where they differ
+    /// in state or in how they stopped. Returns the checkpoints the runs agreed on.
+    pub fn run<M, F>(&self, mut build: F) -> Result<Vec<Checkpoint>>
+    where M: VirtualMemory, F: FnMut() -> Result<CPUEmulator<M>> {
+        let (mut first, mut second) = (build()?, build()?);
+        let mut agreed = Vec::with_capacity(self.checkpoints);
+        for _ in 0..self.checkpoints {
+            let (reason, checkpoint) = self.step(&mut first);
+            let (other_reason, other) = self.step(&mut second);
+            if checkpoint != other || reason != other_reason {
+                log::warn!("runs diverged after {} checkpoints: {:?} then {:?}", agreed.len(), reason, other_reason);
+                return Err(R6502Error::Nondeterministic { clock: checkpoint.clock, first: checkpoint.hash, second: other.hash });
+            }
+            agreed.push(checkpoint);
+            if reason != StopReason::Timeout {
+                break;
+            }
+        }
+        Ok(agreed)
+    }
+
+    fn step<M: VirtualMemory>(&self, emulator: &mut CPUEmulator<M>) -> (StopReason, Checkpoint) {
+        let reason = emulator.run_for_cycles(self.every);
+        (reason, Checkpoint { clock: emulator.clock(), hash: emulator.state_hash() })
+    }
+}
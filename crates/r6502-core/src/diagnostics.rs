@@ -0,0 +1,80 @@
+// Non-fatal emulation anomalies.
+// Real hardware carries on when a program writes to ROM or feeds garbage into
+// decimal mode, so the emulator does too, but it keeps a note of it here so the
+// program can be inspected after the run.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnomalyKind {
+    /// A write landed on memory that is mapped read-only. The write is dropped.
+    RomWrite,
+    /// A read from a register that only accepts writes.
+    WriteOnlyRead,
+    /// ADC/SBC in decimal mode with an operand nibble above 9.
+    InvalidBcdDigit,
+    /// A write into a region pinned with `PinPolicy::Report`. The write goes ahead.
+    PinnedWrite,
+    /// A write into a region pinned with `PinPolicy::Delay`, held back until it is released.
+    DelayedWrite,
+    /// A write by the program to the NMI, reset or IRQ vector, when vector writes are watched.
+    VectorWrite,
+}
+
+impl std::fmt::Display for AnomalyKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RomWrite => write!(f, "write to read-only memory"),
+            Self::WriteOnlyRead => write!(f, "read of write-only register"),
+            Self::InvalidBcdDigit => write!(f, "invalid BCD digit"),
+            Self::PinnedWrite => write!(f, "write to pinned memory"),
+            Self::DelayedWrite => write!(f, "write to pinned memory held back"),
+            Self::VectorWrite => write!(f, "write to a vector"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anomaly {
+    pub kind: AnomalyKind,
+    /// Address of the instruction that caused the anomaly.
+    pub pc: u16,
+    /// Memory address involved, if any.
+    pub address: Option<u16>,
+    pub value: u8,
+}
+
+impl std::fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#06x}: {}", self.pc, self.kind)?;
+        if let Some(address) = self.address {
+            write!(f, " at {:#06x}", address)?;
+        }
+        write!(f, " (value {:#04x})", self.value)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    anomalies: Vec<Anomaly>,
+}
+
+impl Diagnostics {
+    pub fn record(&mut self, kind: AnomalyKind, pc: u16, address: Option<u16>, value: u8) {
+        self.anomalies.push(Anomaly { kind, pc, address, value });
+    }
+
+    pub fn anomalies(&self) -> &[Anomaly] {
+        &self.anomalies
+    }
+
+    pub fn of_kind(&self, kind: AnomalyKind) -> impl Iterator<Item = &Anomaly> {
+        self.anomalies.iter().filter(move |anomaly| anomaly.kind == kind)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.anomalies.clear();
+    }
+}
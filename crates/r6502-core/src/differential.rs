@@ -0,0 +1,123 @@
+use crate::cpu::{Cpu6502, Writes};
+use crate::state::Registers;
+
+// Differential execution: two processors run the same program an instruction
+// at a time, and the runner stops at the first instruction after which their
+// registers, the writes they made or the memory they wrote differ. Pointing it
+// at r6502 and another core (or r6502 with different settings, say with the
+// decode cache on) localises a correctness bug to one instruction.
+//
+// Both processors have to start from the same state with their own copy of
+// the same memory.
+
+/// The first point at which the two processors disagree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Instructions both ran the same before this one.
+    pub step: u64,
+    /// Address of the instruction, according to the reference.
+    pub pc: u16,
+    /// Registers after the instruction, reference then candidate.
+    pub registers: (Registers, Registers),
+    pub writes: (Writes, Writes),
+    /// Addresses either side wrote whose contents now differ, with both values.
+    pub memory: Vec<(u16, u8, u8)>,
+    /// Whether each processor had stopped.
+    pub stopped: (bool, bool),
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "diverged at step {}, instruction at {:04X}", self.step, self.pc)?;
+        match self.stopped {
+            (true, false) => writeln!(f, "the reference stopped, the candidate did not")?,
+            (false, true) => writeln!(f, "the candidate stopped, the reference did not")?,
+            _ => (),
+        }
+        writeln!(f, "reference: {}", self.registers.0)?;
+        writeln!(f, "candidate: {}", self.registers.1)?;
+        for change in self.registers.0.diff(&self.registers.1) {
+            writeln!(f, "  {}", change)?;
+        }
+        let writes = |writes: &[(u16, u8)]| writes.iter().map(|(address, value)| format!("{:04X}={:02X}", address, value)).collect::<Vec<_>>().join(" ");
+        if self.writes.0 != self.writes.1 {
+            writeln!(f, "reference writes: {}", writes(&self.writes.0))?;
+            writeln!(f, "candidate writes: {}", writes(&self.writes.1))?;
+        }
+        for (address, reference, candidate) in self.memory.iter() {
+            writeln!(f, "  {:04X}: {:02X} -> {:02X}", address, reference, candidate)?;
+        }
+        Ok(())
+    }
+}
+
+/// How a differential run ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DifferentialOutcome {
+    /// Both processors stopped at the same point without diverging.
+    Stopped { steps: u64 },
+    /// Ran the requested number of steps in agreement.
+    StepLimit,
+    Diverged(Box<Divergence>),
+}
+
+pub struct DifferentialRunner<A: Cpu6502, B: Cpu6502> {
+    pub reference: A,
+    pub candidate: B,
+    steps: u64,
+}
+
+impl<A: Cpu6502, B: Cpu6502> DifferentialRunner<A, B> {
+    pub fn new(reference: A, candidate: B) -> Self {
+        Self { reference, candidate, steps: 0 }
+    }
+
+    /// Instructions run in agreement so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Steps both processors once and compares them.
+    pub fn step(&mut self) -> Result<bool, Box<Divergence>> {
+        let pc = self.reference.registers().pc;
+        let step = |cpu: &mut dyn Cpu6502| cpu.step().then(|| cpu.last_writes());
+        let (left, right) = (step(&mut self.reference), step(&mut self.candidate));
+        let registers = (self.reference.registers(), self.candidate.registers());
+        if left.is_none() && right.is_none() && registers.0 == registers.1 {
+            return Ok(false);
+        }
+        let (left_writes, right_writes) = (left.clone().unwrap_or_default(), right.clone().unwrap_or_default());
+        let mut addresses: Vec<u16> = left_writes.iter().chain(right_writes.iter()).map(|(address, _)| *address).collect();
+        addresses.sort();
+        addresses.dedup();
+        let memory: Vec<(u16, u8, u8)> = addresses
+            .into_iter()
+            .map(|address| (address, self.reference.peek(address), self.candidate.peek(address)))
+            .filter(|(_, reference, candidate)| reference != candidate)
+            .collect();
+        if left.is_some() == right.is_some() && registers.0 == registers.1 && left_writes == right_writes && memory.is_empty() {
+            self.steps += 1;
+            return Ok(true);
+        }
+        Err(Box::new(Divergence {
+            step: self.steps,
+            pc,
+            registers,
+            writes: (left_writes, right_writes),
+            memory,
+            stopped: (left.is_none(), right.is_none()),
+        }))
+    }
+
+    /// Steps until the processors diverge, both stop, or `max_steps` more instructions have run.
+    pub fn run(&mut self, max_steps: u64) -> DifferentialOutcome {
+        for _ in 0..max_steps {
+            match self.step() {
+                Ok(true) => (),
+                Ok(false) => return DifferentialOutcome::Stopped { steps: self.steps },
+                Err(divergence) => return DifferentialOutcome::Diverged(divergence),
+            }
+        }
+        DifferentialOutcome::StepLimit
+    }
+}
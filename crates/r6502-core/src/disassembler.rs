@@ -0,0 +1,143 @@
+use crate::analysis::coverage::ExecutedBytes;
+use crate::format::number_format;
+use crate::instructions::{AddressingMode, Instruction, OpCode};
+use crate::symbols::SymbolTable;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisassembledInstruction {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    pub instruction: Instruction,
+}
+
+impl DisassembledInstruction {
+    /// Whether the byte decodes to an instruction at all. Anything else is rendered as `.byte`.
+    pub fn is_valid(&self) -> bool {
+        !matches!(self.instruction.opcode, OpCode::UnknownInstruction | OpCode::BadInstruction)
+    }
+
+    pub fn length(&self) -> u16 {
+        self.bytes.len() as u16
+    }
+
+    /// Raw operand, little endian for two byte operands.
+    pub fn operand(&self) -> Option<u16> {
+        match self.bytes.as_slice() {
+            [_, low] => Some(*low as u16),
+            [_, low, high] => Some(((*high as u16) << 8) + *low as u16),
+            _ => None,
+        }
+    }
+
+    /// Destination of a branch, jump or call, if it can be known statically.
+    pub fn target(&self) -> Option<u16> {
+        match (self.instruction.opcode, self.instruction.mode) {
+            (_, Some(AddressingMode::Relative)) => {
+                let offset = self.operand()? as u8 as i8;
+                Some(self.address.wrapping_add(2).wrapping_add(offset as u16))
+            }
+            (OpCode::JMP | OpCode::JSR, Some(AddressingMode::DirectAbsolute)) => self.operand(),
+            _ => None,
+        }
+    }
+
+    /// Renders the instruction in ca65 syntax, substituting symbol names for
+    /// absolute addresses and branch targets where known.
+    pub fn text(&self, symbols: Option<&SymbolTable>) -> String {
+        if !self.is_valid() {
+            return format!(".byte {}", number_format().operand(self.bytes[0] as u16, 2));
+        }
+        let format = number_format();
+        let mnemonic = self.instruction.opcode.to_string();
+        let name = |address: u16, width: usize| -> String {
+            match symbols.and_then(|symbols| symbols.name_for(address)) {
+                Some(name) => name.to_owned(),
+                None => format.operand(address, width),
+            }
+        };
+        let operand = match (self.instruction.mode, self.operand()) {
+            (Some(AddressingMode::Accumulator), _) => "A".to_owned(),
+            (Some(AddressingMode::Immediate), Some(value)) => format!("#{}", format.operand(value, 2)),
+            (Some(AddressingMode::DirectZeroPage), Some(value)) => name(value, 2),
+            (Some(AddressingMode::DirectZeroPageX), Some(value)) => format!("{},X", name(value, 2)),
+            (Some(AddressingMode::DirectZeroPageY), Some(value)) => format!("{},Y", name(value, 2)),
+            (Some(AddressingMode::IndirectZeroPageX), Some(value)) => format!("({},X)", name(value, 2)),
+            (Some(AddressingMode::IndirectZeroPageY), Some(value)) => format!("({}),Y", name(value, 2)),
+            (Some(AddressingMode::DirectAbsolute), Some(value)) => name(value, 4),
+            (Some(AddressingMode::DirectAbsoluteX), Some(value)) => format!("{},X", name(value, 4)),
+            (Some(AddressingMode::DirectAbsoluteY), Some(value)) => format!("{},Y", name(value, 4)),
+            (Some(AddressingMode::IndirectAbsolute), Some(value)) => format!("({})", name(value, 4)),
+            (Some(AddressingMode::Relative), Some(_)) => name(self.target().unwrap_or_default(), 4),
+            _ => String::new(),
+        };
+        if operand.is_empty() {
+            mnemonic
+        } else {
+            format!("{} {}", mnemonic, operand)
+        }
+    }
+}
+
+impl std::fmt::Display for DisassembledInstruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text(None))
+    }
+}
+
+/// Decodes the instruction at the start of `bytes`, which are located at `address`.
+/// Operands running past the end of `bytes` are treated as zero.
+pub fn disassemble_at(bytes: &[u8], address: u16) -> DisassembledInstruction {
+    let opcode = bytes.first().copied().unwrap_or(0);
+    let instruction = Instruction::from(opcode);
+    let length = match instruction.opcode {
+        OpCode::UnknownInstruction | OpCode::BadInstruction => 1,
+        _ => instruction.length() as usize,
+    };
+    let bytes = (0..length).map(|index| bytes.get(index).copied().unwrap_or(0)).collect();
+    DisassembledInstruction { address, bytes, instruction }
+}
+
+/// Linear sweep over `image` loaded at `origin`.
+pub fn disassemble(image: &[u8], origin: u16) -> Vec<DisassembledInstruction> {
+    let mut out = vec![];
+    let mut offset = 0;
+    while offset < image.len() {
+        let instruction = disassemble_at(&image[offset..], origin.wrapping_add(offset as u16));
+        offset += instruction.length() as usize;
+        out.push(instruction);
+    }
+    out
+}
+
+/// Produces a listing of `image` loaded at `origin` that only decodes bytes known to
+/// have been executed; everything else is emitted as `.byte` data.
+pub fn annotated_listing(image: &[u8], origin: u16, executed: &ExecutedBytes, symbols: Option<&SymbolTable>) -> String {
+    let mut out = String::new();
+    let mut offset = 0;
+    while offset < image.len() {
+        let address = origin.wrapping_add(offset as u16);
+        if let Some(name) = symbols.and_then(|symbols| symbols.name_for(address)) {
+            out.push_str(&format!("{}:\n", name));
+        }
+        if executed.is_executed(address) {
+            let instruction = disassemble_at(&image[offset..], address);
+            let bytes: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            out.push_str(&format!("{:04X}  {:<8}  {}\n", address, bytes.join(" "), instruction.text(symbols)));
+            offset += instruction.length() as usize;
+            continue;
+        }
+        // Group data into rows of up to eight bytes, stopping at code or a label.
+        let start = offset;
+        offset += 1;
+        while offset < image.len() && offset - start < 8 {
+            let next = origin.wrapping_add(offset as u16);
+            if executed.is_executed(next) || symbols.and_then(|symbols| symbols.name_for(next)).is_some() {
+                break;
+            }
+            offset += 1;
+        }
+        let bytes: Vec<String> = image[start..offset].iter().map(|byte| number_format().operand(*byte as u16, 2)).collect();
+        out.push_str(&format!("{:04X}  {:<8}  .byte {}\n", address, "", bytes.join(", ")));
+    }
+    out
+}
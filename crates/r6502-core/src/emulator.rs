@@ -0,0 +1,1147 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+
+use crate::{cache::DecodeCache, crash::{CrashReport, Retired, TraceRing}, cycle_log::{CycleLog, CycleWriter}, disassembler::{disassemble_at, DisassembledInstruction}, analysis::{coverage::ExecutedBytes, execution::{ExecutionGraph, TransferKind}, interrupts::{InterruptKind, InterruptStats}, watch::{WatchLog, WatchedWrite, Writer}, zero_page::ZeroPageUsage}, diagnostics::{AnomalyKind, Diagnostics}, hashing::StateHasher, idle::{Idle, IdleWatch}, inspect::{InspectHandle, Published}, error::{R6502Error, Result}, format::number_format, instructions::{Instruction, OpCode}, loaders::{self, LoadedProgram}, opcodes, pinning::{PinId, PinPolicy, Pins}, power_on::RamPattern, registers::{self, Register}, shutdown::Shutdown, state::{Registers, SystemAction, SystemCycle, SystemFlags, SystemState}, sync::SyncBoundary, vectors::{Vector, VectorWatch, Vectors}};
+use derive_builder::Builder;
+
+/// Replacement behaviour for a single opcode byte. The handler runs with the program
+/// counter already past the opcode and is responsible for fetching its own operands.
+pub type OpcodeHandler<M> = Arc<dyn Fn(&mut CPUEmulator<M>) -> Result<()> + Send + Sync>;
+
+/// Rust side of a BRK system call, run with the program counter already past the
+/// signature byte so returning carries on with the next instruction.
+pub type SyscallHandler<M> = Arc<dyn Fn(&mut CPUEmulator<M>) -> Result<()> + Send + Sync>;
+
+/// Called when a run stops because the processor did, with the reason the run
+/// returns: [`StopReason::Halted`], [`StopReason::Exit`] or [`StopReason::Error`].
+pub type HaltHandler<M> = Arc<dyn Fn(&mut CPUEmulator<M>, &StopReason) + Send + Sync>;
+
+/// Called when the processor is spinning in a busy-wait loop, see [`CPUEmulator::on_idle`].
+pub type IdleHandler<M> = Arc<dyn Fn(&mut CPUEmulator<M>, &Idle) + Send + Sync>;
+
+/// Where [`CPUEmulatorBuilder::trace_writer`] sends the trace.
+pub type TraceWriter = Box<dyn Write + Send>;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct CPUEmulator<M>
+where M: VirtualMemory {
+    memory: Arc<Mutex<M>>,
+    pub state: SystemState,
+    #[builder(default, setter(custom))]
+    breakpoints: HashSet<u16>,
+    #[builder(default)]
+    pub diagnostics: Diagnostics,
+    #[builder(default)]
+    overrides: HashMap<u8, OpcodeHandler<M>>,
+    #[builder(default)]
+    syscalls: HashMap<u8, SyscallHandler<M>>,
+    /// When set, every control transfer taken is recorded here.
+    #[builder(default)]
+    pub execution_graph: Option<ExecutionGraph>,
+    /// When set, the opcode and operand bytes of every executed instruction are marked here.
+    #[builder(default)]
+    pub executed: Option<ExecutedBytes>,
+    /// When set, decoded instructions are cached by address. Only safe when memory
+    /// doesn't change without the CPU writing to it.
+    #[builder(default)]
+    pub decode_cache: Option<DecodeCache>,
+    /// When set, writes into its regions are logged with the value they replace.
+    /// The old value is read through the bus, so watch RAM rather than device registers.
+    #[builder(default)]
+    pub watches: Option<WatchLog>,
+    /// When set, interrupts taken and the RTIs that end their handlers are counted here.
+    #[builder(default)]
+    pub interrupt_stats: Option<InterruptStats>,
+    /// When set, reads and writes of page zero are counted here by the instruction making them.
+    #[builder(default)]
+    pub zero_page: Option<ZeroPageUsage>,
+    /// Device registers whose writes are traced with their decoded bitfields.
+    #[builder(default)]
+    registers: &'static [Register],
+    /// When set, a write to this address stops the processor and `run` returns
+    /// [`StopReason::Exit`] with the value written. Test ROMs use [`EXIT_PORT`].
+    #[builder(default)]
+    exit_port: Option<u16>,
+    #[builder(setter(skip))]
+    exit_code: Option<u8>,
+    /// When set, `run` returns [`StopReason::TrapLoop`] at an instruction that
+    /// jumps or branches to itself without changing anything. Off by default,
+    /// since games often wait for interrupts in a `JMP *` loop.
+    #[builder(default)]
+    pub detect_traps: bool,
+    /// How far [`CPUEmulator::step_sync`] runs before handing control back.
+    #[builder(default)]
+    pub sync: SyncBoundary,
+    /// When set, writes by the program to $FFFA-$FFFF are recorded as
+    /// [`AnomalyKind::VectorWrite`], and with [`VectorWatch::Break`] stop the run.
+    #[builder(default)]
+    pub watch_vectors: Option<VectorWatch>,
+    #[builder(setter(skip))]
+    vector_written: Option<u16>,
+    /// The last instructions retired, kept for crash reports. Give it a larger
+    /// capacity to see further back.
+    #[builder(default)]
+    pub trace: TraceRing,
+    /// When set, a line per instruction with its address, disassembly and the
+    /// registers after it ran, as `snapshot::trace` writes them.
+    #[builder(default, setter(custom))]
+    trace_writer: Option<TraceWriter>,
+    #[builder(default, setter(custom))]
+    on_halt: Option<HaltHandler<M>>,
+    #[builder(setter(skip))]
+    cycle_stream: Option<CycleWriter<TraceWriter>>,
+    #[builder(setter(skip))]
+    idle: Option<(IdleWatch, IdleHandler<M>)>,
+    #[builder(setter(skip))]
+    crash: Option<CrashReport>,
+    #[builder(setter(skip))]
+    hasher: StateHasher,
+    #[builder(setter(skip))]
+    inspector: Option<Arc<Published>>,
+    /// IRQ input driven from outside the bus, see [`CPUEmulator::set_irq_line`].
+    #[builder(setter(skip))]
+    irq_line: bool,
+    #[builder(setter(skip))]
+    pins: Pins,
+    #[builder(setter(skip))]
+    nmi_pending: bool,
+    /// Cycles the current instruction takes beyond its base count, for taken branches.
+    #[builder(setter(skip))]
+    pub(crate) extra_cycles: u64,
+    /// Whether the current instruction's indexed access carried into the high byte.
+    #[builder(setter(skip))]
+    pub(crate) page_crossed: bool,
+    /// Set by a taken branch that stays in its page: interrupts aren't polled
+    /// before the next instruction.
+    #[builder(setter(skip))]
+    pub(crate) interrupt_delayed: bool,
+    #[builder(setter(skip))]
+    instruction_pc: u16,
+    #[builder(setter(skip))]
+    clock: u64,
+}
+
+/// Instructions between reads of the wall clock in [`CPUEmulator::run_with_limits`].
+pub const TIMEOUT_CHECK_INTERVAL: u32 = 1024;
+
+/// Where test programs report their exit code, e.g. to fail a CI job: zero for
+/// success, anything else for failure. Only watched when the emulator is built
+/// with `exit_port`.
+pub const EXIT_PORT: u16 = 0xFFF0;
+
+/// What an [`CPUEmulator::override_opcode`] handler costs unless it calls
+/// [`CPUEmulator::add_cycles`]: the shortest instruction on the 6502.
+pub const OVERRIDE_CYCLES: u64 = 2;
+
+/// Why a call to [`CPUEmulator::run`] returned control to the caller.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The processor is no longer running (e.g. after `KIL`).
+    Halted,
+    /// The program counter reached a registered breakpoint.
+    Breakpoint(u16),
+    /// An instruction could not be decoded or executed.
+    Error(Option<Instruction>),
+    /// The frame or scanline boundary asked for was reached.
+    Stepped,
+    /// Something outside the emulator asked it to stop.
+    Interrupted,
+    /// The cycle budget or wall-clock timeout given to the run ran out.
+    Timeout,
+    /// The instruction at this address loops to itself with nothing changing, the
+    /// way test ROMs such as Klaus Dormann's signal success or failure.
+    TrapLoop(u16),
+    /// The program wrote this exit code to the exit port.
+    Exit(u8),
+    /// The program wrote to this byte of a vector, with vectors watched by
+    /// [`VectorWatch::Break`]. The write has been made.
+    VectorWrite(u16),
+}
+
+
+impl <M> CPUEmulator <M>
+where M: VirtualMemory {
+    /// Fetches, decodes and executes one instruction. Unless it fails or a recorder
+    /// such as `executed` is attached, this doesn't allocate as long as
+    /// `state.cycles` has spare capacity (see [`SystemState::with_cycle_capacity`]).
+    pub fn execute_next_instruction(&mut self) -> Result<Instruction, Option<Instruction>> {
+        if !self.state.running {
+            return Err(None);
+        }
+        if let Some(stream) = self.cycle_stream.as_mut() {
+            if let Err(error) = stream.drain(&mut self.state.cycles) {
+                log::warn!("Couldn't stream the cycle log, no longer streaming: {}", error);
+                self.cycle_stream = None;
+            }
+        }
+        let logged = self.state.cycles.len();
+        if let Some(stats) = self.interrupt_stats.as_mut() {
+            stats.irq_line(self.irq_line || self.memory.lock().unwrap().irq(), self.clock);
+        }
+        if std::mem::take(&mut self.interrupt_delayed) {
+            log::trace!("{:#06x}: interrupt polling skipped after a branch", self.state.pc);
+        } else if self.nmi_pending {
+            self.nmi_pending = false;
+            self.interrupt(Vector::Nmi);
+        } else if self.irq_pending() {
+            self.interrupt(Vector::Irq);
+        }
+        self.instruction_pc = self.state.pc;
+        let retired = Retired { clock: self.clock, opcode: 0, registers: self.state.registers() };
+        let cached = self.decode_cache.as_mut().and_then(|cache| cache.get(self.state.pc));
+        let ibyte = match cached {
+            Some((ibyte, _)) => ibyte,
+            None => self.memory.lock().unwrap().read(self.state.pc),
+        };
+
+        let handler = self.overrides.get(&ibyte).cloned();
+        let instruction = match (&handler, cached) {
+            (Some(_), _) => Instruction { opcode: OpCode::Override, mode: None },
+            (None, Some((_, instruction))) => instruction,
+            (None, None) => {
+                let instruction = Instruction::from(ibyte);
+                if let Some(cache) = self.decode_cache.as_mut() {
+                    cache.insert(self.state.pc, ibyte, instruction);
+                }
+                instruction
+            }
+        };
+        match instruction.opcode {
+            OpCode::UnknownInstruction => {
+                log::warn!("{:#06x}: unknown opcode {:#04x}", self.instruction_pc, ibyte);
+                self.crashed(format!("unknown opcode {:#04x}", ibyte), retired);
+                return Err(Some(instruction));
+            },
+            OpCode::BadInstruction => {
+                log::warn!("{:#06x}: bad opcode {:#04x}", self.instruction_pc, ibyte);
+                self.crashed(format!("bad opcode {:#04x}", ibyte), retired);
+                return Err(Some(instruction));
+            },
+            _ => ()
+        };
+
+        let traced = self.trace_writer.is_some().then(|| {
+            let mut memory = self.memory.lock().unwrap();
+            let bytes: Vec<u8> = (0..3).map(|offset| memory.peek(self.instruction_pc.wrapping_add(offset))).collect();
+            disassemble_at(&bytes, self.instruction_pc)
+        });
+        self.state.pc = self.state.pc.wrapping_add(1);
+        self.extra_cycles = 0;
+        self.page_crossed = false;
+
+        let outcome = match &handler {
+            Some(handler) => handler(self),
+            None => instruction.execute(self),
+        };
+        match outcome {
+            Ok(_) => {
+                if handler.is_some() {
+                    log::trace!("{:#06x}: override for {:#04x}", self.instruction_pc, ibyte);
+                } else if log::log_enabled!(log::Level::Trace) {
+                    // Devices haven't been ticked yet, so this is the beam as the instruction started.
+                    match self.memory.lock().unwrap().beam() {
+                        Some((scanline, dot)) => log::trace!("{}: {} [{:3},{:3}]", number_format().word(self.instruction_pc), instruction, scanline, dot),
+                        None => log::trace!("{}: {}", number_format().word(self.instruction_pc), instruction),
+                    }
+                }
+                let page_cross = (self.page_crossed && opcodes::indexing_penalty(ibyte)) as u64;
+                let base = if handler.is_some() { OVERRIDE_CYCLES } else { opcodes::base_cycles(ibyte) as u64 };
+                let started = self.clock;
+                self.advance(base + self.extra_cycles + page_cross);
+                if let Some(executed) = self.executed.as_mut() {
+                    executed.mark_range(self.instruction_pc, instruction.length());
+                }
+                if let Some(graph) = self.execution_graph.as_mut() {
+                    if let Some(kind) = TransferKind::classify(&instruction, self.instruction_pc, self.state.pc) {
+                        graph.record(self.instruction_pc, self.state.pc, kind);
+                    }
+                }
+                if let Some(stats) = self.interrupt_stats.as_mut() {
+                    match TransferKind::classify(&instruction, self.instruction_pc, self.state.pc) {
+                        Some(TransferKind::Interrupt) => stats.enter(InterruptKind::Brk, started),
+                        Some(TransferKind::ReturnFromInterrupt) => stats.exit(self.clock),
+                        _ => (),
+                    }
+                }
+                self.trace.push(Retired { opcode: ibyte, ..retired });
+                if let Some(disassembly) = traced {
+                    self.write_trace(&disassembly);
+                }
+                if self.idle.is_some() {
+                    self.watch_idle(logged, started);
+                }
+                Ok(instruction)
+            }
+            Err(error) if handler.is_some() => {
+                log::error!("{:#06x}: override for {:#04x} failed: {}", self.instruction_pc, ibyte, error);
+                self.crashed(format!("override for {:#04x} failed: {}", ibyte, error), retired);
+                Err(Some(instruction))
+            },
+            Err(error) => {
+                log::error!("{:#06x}: failed to execute {}: {}", self.instruction_pc, instruction, error);
+                self.crashed(format!("failed to execute {}: {}", instruction, error), retired);
+                Err(Some(instruction))
+            },
+        }
+        
+    }
+
+    /// Loads the program counter from the reset vector at $FFFC and starts the processor.
+    pub fn reset(&mut self) {
+        self.state.pc = self.memory.lock().unwrap().read_u16(Vector::Reset.address());
+        self.state.s = 0xFD;
+        self.state.p.insert(SystemFlags::interrupt_disable);
+        self.state.running = true;
+        self.exit_code = None;
+        self.crash = None;
+    }
+
+    /// Watches `port` for an exit code from now on, or stops watching.
+    pub fn set_exit_port(&mut self, port: Option<u16>) {
+        self.exit_port = port;
+    }
+
+    /// A handle other threads can poll for the registers, clock and raster position
+    /// without locking the emulator. From here on they are published after every
+    /// instruction.
+    pub fn inspect_handle(&mut self) -> InspectHandle {
+        let published = self.inspector.get_or_insert_with(Default::default).clone();
+        published.publish(&self.state.registers(), self.clock, self.memory.lock().unwrap().raster(), self.state.running);
+        InspectHandle::new(published)
+    }
+
+    /// Streams the cycle log to `writer` from now on, see [`cycle_log`](crate::cycle_log).
+    /// The log is handed over before each instruction, so `state.cycles` only
+    /// holds the cycles of the last one. Buffer `writer` if it is a file.
+    pub fn stream_cycles<W: Write + Send + 'static>(&mut self, writer: W) -> Result<()> {
+        self.cycle_stream = Some(CycleWriter::new(Box::new(writer) as TraceWriter)?);
+        Ok(())
+    }
+
+    /// Stops streaming the cycle log, writing out what it holds first. Returns
+    /// the number of cycles streamed.
+    pub fn stop_streaming_cycles(&mut self) -> Result<u64> {
+        let Some(mut stream) = self.cycle_stream.take() else { return Ok(0) };
+        stream.drain(&mut self.state.cycles)?;
+        stream.flush()?;
+        Ok(stream.written())
+    }
+
+    /// Writes out the trace, the cycle stream and whatever the memory keeps on
+    /// disk, such as battery backed RAM, e.g. from a frontend's shutdown hook.
+    /// Tracing and streaming carry on afterwards.
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = self.trace_writer.as_mut() {
+            writer.flush()?;
+        }
+        if let Some(stream) = self.cycle_stream.as_mut() {
+            stream.drain(&mut self.state.cycles)?;
+            stream.flush()?;
+        }
+        self.memory.lock().unwrap().flush()
+    }
+
+    /// Code the program wrote to the exit port, once it has.
+    pub fn exit_code(&self) -> Option<u8> {
+        self.exit_code
+    }
+
+    /// What the machine looked like when the last instruction to fault did,
+    /// until the next reset.
+    pub fn crash(&self) -> Option<&CrashReport> {
+        self.crash.as_ref()
+    }
+
+    /// The crash report laid out for a terminal, see [`CrashReport::render`].
+    #[cfg(feature = "pretty")]
+    pub fn crash_report(&self) -> Option<String> {
+        self.crash.as_ref().map(CrashReport::render)
+    }
+
+    // Stops the processor on a fault in the instruction `retired` started.
+    fn crashed(&mut self, fault: String, retired: Retired) {
+        self.state.running = false;
+        let mut memory = self.memory.lock().unwrap();
+        let report = CrashReport::new(fault, retired.clock, retired.registers, &self.trace, &self.state.cycles, |address| memory.peek(address));
+        drop(memory);
+        self.crash = Some(report);
+    }
+
+    // Writes the trace line of the instruction just run. A writer that fails is
+    // dropped rather than stopping the run.
+    fn write_trace(&mut self, instruction: &DisassembledInstruction) {
+        let Some(writer) = self.trace_writer.as_mut() else { return };
+        if let Err(error) = writeln!(writer, "{:04X}  {:<14} {}", self.instruction_pc, instruction.to_string(), self.state.registers()) {
+            log::warn!("Couldn't write the trace, no longer tracing: {}", error);
+            self.trace_writer = None;
+        }
+    }
+
+    // Feeds the instruction just run to the idle watch and calls its handler when
+    // the watch fires; `logged` is where the instruction's cycles start in
+    // `state.cycles`, interrupt entry included.
+    fn watch_idle(&mut self, logged: usize, started: u64) {
+        let wrote = self.state.cycles.between(logged..usize::MAX).writes().next().is_some();
+        let Some((watch, handler)) = self.idle.as_mut() else { return };
+        if let Some(idle) = watch.record(self.instruction_pc, wrote, started, self.clock) {
+            log::trace!("idle in {:#06x}-{:#06x} for {} cycles", idle.start, idle.end, idle.cycles);
+            let handler = handler.clone();
+            handler(self, &idle);
+        }
+    }
+
+    // Hands `reason` to the halt handler, for a run that stopped because the
+    // processor did.
+    pub(crate) fn stopped(&mut self, reason: StopReason) -> StopReason {
+        if let Some(handler) = self.on_halt.clone() {
+            handler(self, &reason);
+        }
+        reason
+    }
+
+    // The vector byte written since the last call, when writes to vectors break.
+    pub(crate) fn take_vector_write(&mut self) -> Option<u16> {
+        self.vector_written.take()
+    }
+
+    // Why the processor isn't running.
+    pub(crate) fn halted(&self) -> StopReason {
+        match self.exit_code {
+            Some(code) => StopReason::Exit(code),
+            None => StopReason::Halted,
+        }
+    }
+
+    /// Whether an IRQ will be taken before the next instruction.
+    pub fn irq_pending(&self) -> bool {
+        !self.state.p.contains(SystemFlags::interrupt_disable) && (self.irq_line || self.memory.lock().unwrap().irq())
+    }
+
+    /// Holds the IRQ input low (`true`) or releases it, for hosts wiring up
+    /// interrupt sources that aren't devices on the bus.
+    pub fn set_irq_line(&mut self, active: bool) {
+        self.irq_line = active;
+    }
+
+    /// Signals an NMI. It is taken before the next instruction, ahead of any IRQ.
+    pub fn nmi(&mut self) {
+        self.nmi_pending = true;
+        if let Some(stats) = self.interrupt_stats.as_mut() {
+            stats.nmi_requested(self.clock);
+        }
+    }
+
+    // Enters the handler behind `vector` the way the processor responds to IRQ and
+    // NMI: the return address and flags (with break clear) go on the stack.
+    fn interrupt(&mut self, vector: Vector) {
+        let from = self.state.pc;
+        self.push_word(from);
+        self.push(self.state.p.to_push(false));
+        self.state.p.insert(SystemFlags::interrupt_disable);
+        self.state.pc = self.read_u16(vector.address());
+        log::trace!("{:#06x}: {} interrupt to {:#06x}", from, vector, self.state.pc);
+        if let Some(graph) = self.execution_graph.as_mut() {
+            graph.record(from, self.state.pc, TransferKind::Interrupt);
+        }
+        if let Some(stats) = self.interrupt_stats.as_mut() {
+            stats.enter(if vector == Vector::Nmi { InterruptKind::Nmi } else { InterruptKind::Irq }, self.clock);
+        }
+        self.advance(7);
+    }
+
+    // The stack is page one and S wraps within it, so a push at S=$00 lands on
+    // $0100 and the next one on $01FF.
+    pub(crate) fn push(&mut self, value: u8) {
+        self.write(stack_address(self.state.s), value);
+        self.state.s = self.state.s.wrapping_sub(1);
+    }
+
+    pub(crate) fn pull(&mut self) -> u8 {
+        self.state.s = self.state.s.wrapping_add(1);
+        self.read(stack_address(self.state.s))
+    }
+
+    /// Pushes the high byte first, so the word reads little endian on the stack.
+    pub fn push_word(&mut self, value: u16) {
+        self.push((value >> 8) as u8);
+        self.push(value as u8);
+    }
+
+    pub(crate) fn pull_word(&mut self) -> u16 {
+        let low_byte = self.pull() as u16;
+        let high_byte = self.pull() as u16;
+        (high_byte << 8) | low_byte
+    }
+
+    // Lets the devices catch up with the CPU, including any time they hold it up for.
+    fn advance(&mut self, cycles: u64) {
+        let mut memory = self.memory.lock().unwrap();
+        memory.tick(cycles);
+        let stall = memory.stall();
+        if stall > 0 {
+            memory.tick(stall);
+        }
+        self.clock += cycles + stall;
+        if memory.nmi() {
+            self.nmi_pending = true;
+            if let Some(stats) = self.interrupt_stats.as_mut() {
+                stats.nmi_requested(self.clock);
+            }
+        }
+        if let Some(published) = self.inspector.as_ref() {
+            published.publish(&self.state.registers(), self.clock, memory.raster(), self.state.running);
+        }
+        drop(memory);
+        if !self.pins.is_empty() {
+            self.release_pins();
+        }
+    }
+
+    // Whether `execute_next_instruction` would start by taking an interrupt.
+    #[cfg(feature = "jit")]
+    pub(crate) fn interrupt_next(&self) -> bool {
+        !self.interrupt_delayed && (self.nmi_pending || self.irq_pending())
+    }
+
+    // Bookkeeping for an instruction executed outside `execute_next_instruction`.
+    #[cfg(feature = "jit")]
+    pub(crate) fn retire(&mut self, address: u16, length: u16, cycles: u64) {
+        self.instruction_pc = address;
+        // The instruction after a branch was the one that skipped interrupt polling.
+        self.interrupt_delayed = false;
+        if let Some(executed) = self.executed.as_mut() {
+            executed.mark_range(address, length);
+        }
+        self.advance(cycles);
+    }
+
+    /// Reads memory on behalf of a debugger, bypassing the cycle log and diagnostics.
+    pub fn peek(&self, address: u16) -> u8 {
+        self.memory.lock().unwrap().peek(address)
+    }
+
+    /// Where the NMI, reset and IRQ vectors point, read through the bus.
+    pub fn vectors(&self) -> Vectors {
+        let mut vectors = Vectors::default();
+        for vector in Vector::ALL {
+            let address = vector.address();
+            vectors.set(vector, u16::from_le_bytes([self.peek(address), self.peek(address + 1)]));
+        }
+        vectors
+    }
+
+    /// Points `vector` at `target` by poking both bytes through the bus, so on a
+    /// machine with the vectors in ROM this does nothing.
+    pub fn set_vector(&mut self, vector: Vector, target: u16) {
+        let [low, high] = target.to_le_bytes();
+        self.poke(vector.address(), low);
+        self.poke(vector.address() + 1, high);
+    }
+
+    /// Writes memory on behalf of a debugger, bypassing the cycle log and diagnostics.
+    pub fn poke(&mut self, address: u16, value: u8) {
+        if let Some(cache) = self.decode_cache.as_mut() {
+            cache.invalidate(address);
+        }
+        self.hasher.record_write(address, value);
+        self.memory.lock().unwrap().write(address, value)
+    }
+
+    /// Hash of the registers, the cycles run and the memory written since the
+    /// emulator was built or [`CPUEmulator::rebase_state_hash`] was called. Two
+    /// runs from the same start hash the same until they diverge, see `hashing`.
+    pub fn state_hash(&mut self) -> u64 {
+        self.hasher.hash(&self.state.registers(), self.clock)
+    }
+
+    /// Makes the current state the start for [`CPUEmulator::state_hash`], e.g.
+    /// after restoring a save state: earlier writes and cycles stop counting.
+    pub fn rebase_state_hash(&mut self) {
+        self.hasher.rebase(self.clock);
+    }
+
+    /// Runs `f` with the memory locked, e.g. to reach devices on a bus between frames.
+    pub fn with_memory<R, F: FnOnce(&mut M) -> R>(&self, f: F) -> R {
+        f(&mut self.memory.lock().unwrap())
+    }
+
+    /// CPU cycles elapsed since the emulator was built.
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+
+    /// Address of the instruction currently (or most recently) being executed.
+    pub fn instruction_pc(&self) -> u16 {
+        self.instruction_pc
+    }
+
+    /// Replaces the decoder and executor for `opcode` with `handler`, e.g. to add
+    /// instructions of an extended CPU or a syscall trap for test fixtures.
+    pub fn override_opcode<F>(&mut self, opcode: u8, handler: F)
+    where F: Fn(&mut CPUEmulator<M>) -> Result<()> + Send + Sync + 'static {
+        self.overrides.insert(opcode, Arc::new(handler));
+    }
+
+    /// Charges `cycles` on top of what the current instruction costs, for override
+    /// and syscall handlers that model slower instructions.
+    pub fn add_cycles(&mut self, cycles: u64) {
+        self.extra_cycles += cycles;
+    }
+
+    pub fn has_override(&self, opcode: u8) -> bool {
+        self.overrides.contains_key(&opcode)
+    }
+
+    /// Restores the built-in behaviour of `opcode`.
+    pub fn clear_override(&mut self, opcode: u8) -> bool {
+        self.overrides.remove(&opcode).is_some()
+    }
+
+    /// Handles `BRK signature` in Rust, the way an operating system dispatches
+    /// software interrupts on the byte after the BRK. The BRK costs its usual 7
+    /// cycles but pushes nothing and doesn't go through the IRQ vector; BRKs with
+    /// other signature bytes still do.
+    pub fn on_syscall<F>(&mut self, signature: u8, handler: F)
+    where F: Fn(&mut CPUEmulator<M>) -> Result<()> + Send + Sync + 'static {
+        self.syscalls.insert(signature, Arc::new(handler));
+    }
+
+    /// Calls `handler` whenever the processor has spent another `watch.cycles`
+    /// cycles spinning without writes in a loop no wider than `watch.window`,
+    /// e.g. to sleep the host thread while a game waits for vblank. The
+    /// handler runs between instructions and may change the emulator.
+    pub fn on_idle<F>(&mut self, watch: IdleWatch, handler: F)
+    where F: Fn(&mut CPUEmulator<M>, &Idle) + Send + Sync + 'static {
+        self.idle = Some((watch, Arc::new(handler)));
+    }
+
+    /// Stops watching for busy-wait loops.
+    pub fn clear_on_idle(&mut self) -> bool {
+        self.idle.take().is_some()
+    }
+
+    /// Sends `BRK signature` back through the IRQ vector.
+    pub fn clear_syscall(&mut self, signature: u8) -> bool {
+        self.syscalls.remove(&signature).is_some()
+    }
+
+    /// Signature and handler of a BRK at the current program counter, which points
+    /// at its signature byte.
+    pub(crate) fn syscall(&mut self) -> Option<(u8, SyscallHandler<M>)> {
+        if self.syscalls.is_empty() {
+            return None;
+        }
+        let signature = self.read(self.state.pc);
+        self.syscalls.get(&signature).map(|handler| (signature, handler.clone()))
+    }
+
+    /// Pins `start..=end` against CPU writes for the next `cycles` cycles, or
+    /// until unpinned, see [`crate::pinning`].
+    pub fn pin(&mut self, start: u16, end: u16, cycles: Option<u64>, policy: PinPolicy) -> PinId {
+        self.pins.pin(start, end, cycles.map(|cycles| self.clock.saturating_add(cycles)), policy)
+    }
+
+    /// Releases a pin, carrying out the writes it held back. Returns whether it was still pinned.
+    pub fn unpin(&mut self, id: PinId) -> bool {
+        let unpinned = self.pins.unpin(id);
+        self.release_pins();
+        unpinned
+    }
+
+    /// Writes held back by pins, oldest first.
+    pub fn delayed_writes(&self) -> &[(u16, u8)] {
+        self.pins.delayed()
+    }
+
+    // Carries out the held back writes whose pins have gone, straight to memory
+    // since the instructions that made them have long finished.
+    fn release_pins(&mut self) {
+        let released = self.pins.release(self.clock);
+        if released.is_empty() {
+            return;
+        }
+        let mut memory = self.memory.lock().unwrap();
+        for (address, value) in released {
+            if let Some(cache) = self.decode_cache.as_mut() {
+                cache.invalidate(address);
+            }
+            self.hasher.record_write(address, value);
+            memory.write(address, value);
+        }
+    }
+
+    /// Starts logging writes to `start..=end` in `watches`.
+    pub fn watch_region(&mut self, start: u16, end: u16) {
+        self.watches.get_or_insert_with(WatchLog::default).watch(start, end);
+    }
+
+    /// The instructions that wrote into `start..=end`, busiest first, see
+    /// [`WatchLog::writers`]. Empty until the range is watched.
+    pub fn who_wrote(&self, start: u16, end: u16) -> Vec<Writer> {
+        self.watches.as_ref().map(|watches| watches.writers(start, end)).unwrap_or_default()
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = &u16> {
+        self.breakpoints.iter()
+    }
+
+    /// Executes instructions until the processor halts, faults or lands on a breakpoint.
+    /// The breakpoint check happens after each instruction, so calling `run` again
+    /// while sitting on a breakpoint steps past it.
+    pub fn run(&mut self) -> StopReason {
+        self.run_while(|_| true)
+    }
+
+    /// Like [`CPUEmulator::run`], but also returns once `shutdown` is requested.
+    pub fn run_until_shutdown(&mut self, shutdown: &Shutdown) -> StopReason {
+        self.run_until(|| shutdown.is_requested())
+    }
+
+    /// Like [`CPUEmulator::run`], but polls `interrupt` after every instruction and
+    /// returns [`StopReason::Interrupted`] once it is true.
+    pub fn run_until<F>(&mut self, interrupt: F) -> StopReason
+    where F: Fn() -> bool {
+        match self.run_while(|_| !interrupt()) {
+            StopReason::Stepped => StopReason::Interrupted,
+            reason => reason,
+        }
+    }
+
+    /// Like [`CPUEmulator::run`], but returns [`StopReason::Timeout`] once another
+    /// `cycles` cycles have run, for harnesses that can't trust a program to stop.
+    pub fn run_for_cycles(&mut self, cycles: u64) -> StopReason {
+        self.run_with_limits(Some(cycles), None)
+    }
+
+    /// Like [`CPUEmulator::run`], but returns [`StopReason::Timeout`] after
+    /// `timeout` of wall-clock time.
+    pub fn run_for_duration(&mut self, timeout: Duration) -> StopReason {
+        self.run_with_limits(None, Some(timeout))
+    }
+
+    /// Runs until either limit is reached, whichever comes first, or the run stops
+    /// for any other reason. At least one instruction runs, and the wall clock is
+    /// only read every [`TIMEOUT_CHECK_INTERVAL`] instructions.
+    pub fn run_with_limits(&mut self, cycles: Option<u64>, timeout: Option<Duration>) -> StopReason {
+        let end = cycles.map(|cycles| self.clock.saturating_add(cycles));
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let executed = Cell::new(0u32);
+        let within_limits = |emulator: &Self| {
+            if end.is_some_and(|end| emulator.clock >= end) {
+                return false;
+            }
+            executed.set(executed.get().wrapping_add(1));
+            !(executed.get().is_multiple_of(TIMEOUT_CHECK_INTERVAL) && deadline.is_some_and(|deadline| Instant::now() >= deadline))
+        };
+        match self.run_while(within_limits) {
+            StopReason::Stepped => StopReason::Timeout,
+            reason => reason,
+        }
+    }
+
+    /// Runs until the video device starts a new frame.
+    pub fn step_frame(&mut self) -> Result<StopReason> {
+        let (frame, _) = self.raster()?;
+        Ok(self.run_while(|emulator| emulator.raster().is_ok_and(|(current, _)| current == frame)))
+    }
+
+    /// Runs until the video device starts a new scanline.
+    pub fn step_scanline(&mut self) -> Result<StopReason> {
+        let position = self.raster()?;
+        Ok(self.run_while(|emulator| emulator.raster().is_ok_and(|current| current == position)))
+    }
+
+    fn raster(&self) -> Result<(u64, u16)> {
+        self.memory.lock().unwrap().raster().ok_or(R6502Error::NoVideoDevice)
+    }
+
+    // Whether the instruction just run went back to itself without writing
+    // anything or changing the registers from `registers`; `logged` is where its
+    // cycles start in `state.cycles`.
+    fn is_trapped(&self, registers: &Registers, logged: usize) -> bool {
+        self.state.pc == self.instruction_pc
+            && self.state.registers() == *registers
+            && self.state.cycles.between(logged..usize::MAX).writes().next().is_none()
+    }
+
+    // Executes instructions for as long as `condition` holds, stopping early like `run`.
+    fn run_while<F>(&mut self, condition: F) -> StopReason
+    where F: Fn(&Self) -> bool {
+        // Already stopped, so the halt handler has had its call.
+        if !self.state.running {
+            return self.halted();
+        }
+        loop {
+            let before = self.detect_traps.then(|| (self.state.registers(), self.state.cycles.len()));
+            match self.execute_next_instruction() {
+                Ok(_) => (),
+                Err(None) => return self.stopped(self.halted()),
+                Err(Some(instruction)) => return self.stopped(StopReason::Error(Some(instruction))),
+            }
+            if let Some((registers, logged)) = before {
+                if self.is_trapped(&registers, logged) {
+                    log::debug!("trapped at {:#06x}", self.state.pc);
+                    return StopReason::TrapLoop(self.state.pc);
+                }
+            }
+            if let Some(address) = self.take_vector_write() {
+                return StopReason::VectorWrite(address);
+            }
+            if self.breakpoints.contains(&self.state.pc) {
+                log::debug!("breakpoint hit at {:#06x}", self.state.pc);
+                return StopReason::Breakpoint(self.state.pc);
+            }
+            if !condition(self) {
+                return StopReason::Stepped;
+            }
+        }
+    }
+}
+
+impl<M> CPUEmulatorBuilder<M>
+where M: VirtualMemory {
+    /// Breakpoints set from the start, as by [`CPUEmulator::add_breakpoint`].
+    pub fn breakpoints<I: IntoIterator<Item = u16>>(mut self, addresses: I) -> Self {
+        self.breakpoints = Some(addresses.into_iter().collect());
+        self
+    }
+
+    /// Writes a line to `writer` for every instruction run, with its address,
+    /// disassembly and the registers after it.
+    pub fn trace_writer<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        self.trace_writer = Some(Some(Box::new(writer)));
+        self
+    }
+
+    /// Calls `handler` when a run stops because the processor halted, wrote
+    /// an exit code or faulted, see [`HaltHandler`].
+    pub fn on_halt<F>(mut self, handler: F) -> Self
+    where F: Fn(&mut CPUEmulator<M>, &StopReason) + Send + Sync + 'static {
+        self.on_halt = Some(Some(Arc::new(handler)));
+        self
+    }
+}
+
+impl CPUEmulator<DefaultVirtualMemory> {
+    /// Builds an emulator for `program`, reset and ready to run.
+    pub fn from_program(program: LoadedProgram) -> Result<Self> {
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState::default())
+            .memory(Arc::new(Mutex::new(program.memory)))
+            .build()?;
+        emulator.reset();
+        if let Some(entry) = program.entry {
+            emulator.state.pc = entry;
+        }
+        Ok(emulator)
+    }
+
+    /// Raw binary loaded at `origin`.
+    pub fn from_binary<P: AsRef<Path>>(path: P, origin: u16) -> Result<Self> {
+        Self::from_program(loaders::binary_file(path, origin)?)
+    }
+
+    /// NES cartridge in iNES format.
+    pub fn from_ines<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_program(loaders::ines_file(path)?)
+    }
+
+    /// Commodore PRG file.
+    pub fn from_prg<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_program(loaders::prg_file(path)?)
+    }
+}
+
+/// Address in page one that the stack pointer `s` points at.
+pub fn stack_address(s: u8) -> u16 {
+    0x0100 | s as u16
+}
+
+impl <M> VirtualMemory for CPUEmulator <M>
+where M: VirtualMemory {
+    fn read(&mut self, address: u16) -> u8 {
+        let mut memory = self.memory.lock().unwrap();
+        if let Some(kind) = memory.check_access(address, &SystemAction::READ) {
+            log::debug!("{:#06x}: {} at {:#06x}", self.instruction_pc, kind, address);
+            self.diagnostics.record(kind, self.instruction_pc, Some(address), 0);
+        }
+        if let Some(usage) = self.zero_page.as_mut().filter(|_| address < 0x100) {
+            usage.record_read(address as u8, self.instruction_pc);
+        }
+        let byte = memory.read(address);
+        self.state.cycles.push(SystemCycle {address, value: byte, action: SystemAction::READ});
+        byte
+    }
+
+    fn peek(&mut self, address: u16) -> u8 {
+        CPUEmulator::peek(self, address)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        CPUEmulator::flush(self)
+    }
+    
+    fn write(&mut self, address: u16, value: u8) {
+        let mut memory = self.memory.lock().unwrap();
+        if let Some(kind) = memory.check_access(address, &SystemAction::WRITE) {
+            log::debug!("{:#06x}: {} at {:#06x}", self.instruction_pc, kind, address);
+            self.diagnostics.record(kind, self.instruction_pc, Some(address), value);
+        }
+        match self.pins.policy(address, self.clock) {
+            Some(PinPolicy::Report) => self.diagnostics.record(AnomalyKind::PinnedWrite, self.instruction_pc, Some(address), value),
+            Some(PinPolicy::Delay) => {
+                log::debug!("{:#06x}: write to pinned {:#06x} held back", self.instruction_pc, address);
+                self.diagnostics.record(AnomalyKind::DelayedWrite, self.instruction_pc, Some(address), value);
+                self.pins.delay(address, value);
+                self.state.cycles.push(SystemCycle {address, value, action: SystemAction::WRITE});
+                return;
+            }
+            None => (),
+        }
+        if let Some(cache) = self.decode_cache.as_mut() {
+            cache.invalidate(address);
+        }
+        if let Some(register) = registers::find(self.registers, address) {
+            log::trace!("{:#06x}: {} <- {:#04x} ({})", self.instruction_pc, register.name, value, register.decode(value));
+        }
+        if let Some(watches) = self.watches.as_mut().filter(|watches| watches.contains(address)) {
+            let old = memory.peek(address);
+            watches.record(WatchedWrite { cycle: self.clock, pc: self.instruction_pc, address, old, new: value });
+        }
+        if let Some(usage) = self.zero_page.as_mut().filter(|_| address < 0x100) {
+            usage.record_write(address as u8, self.instruction_pc);
+        }
+        if let (Some(watch), Some(vector)) = (self.watch_vectors, Vector::containing(address)) {
+            log::info!("{:#06x}: {} vector byte {:#06x} <- {:#04x}", self.instruction_pc, vector, address, value);
+            self.diagnostics.record(AnomalyKind::VectorWrite, self.instruction_pc, Some(address), value);
+            if watch == VectorWatch::Break {
+                self.vector_written = Some(address);
+            }
+        }
+        self.hasher.record_write(address, value);
+        memory.write(address, value);
+        self.state.cycles.push(SystemCycle {address, value, action: SystemAction::WRITE});
+        if self.exit_port == Some(address) {
+            log::debug!("{:#06x}: exit code {}", self.instruction_pc, value);
+            self.exit_code = Some(value);
+            self.state.running = false;
+        }
+    }
+}
+
+
+
+/// Bytes the 16 bit address bus reaches.
+pub const ADDRESS_SPACE: usize = 0x10000;
+
+/// Address `value` if it fits on the address bus, for address arithmetic done in
+/// wider integers that must not quietly wrap or grow memory.
+pub fn checked_address(value: usize) -> Result<u16> {
+    u16::try_from(value).map_err(|_| R6502Error::AddressOutOfRange(value as u32))
+}
+
+// Exactly 64K, so an address can never land outside it.
+type Memory64K = Box<[u8; ADDRESS_SPACE]>;
+
+fn zeroed() -> Memory64K {
+    vec![0; ADDRESS_SPACE].into_boxed_slice().try_into().expect("a 64K slice")
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DefaultVirtualMemory {
+    m: Memory64K,
+    // Inclusive (start, end) ranges that ignore writes.
+    rom: Vec<(u16, u16)>,
+}
+
+impl <'a> Default for DefaultVirtualMemory{
+    fn default() -> Self {
+        Self { m: zeroed(), rom: vec![] }
+    }
+}
+
+impl DefaultVirtualMemory {
+    /// Fills all 64K with `pattern`, as RAM would come up. Fill before loading
+    /// images, which this overwrites.
+    pub fn with_pattern(mut self, pattern: RamPattern) -> Self {
+        pattern.fill(&mut self.m[..]);
+        self
+    }
+
+    /// Marks `start..=end` as ROM: writes there are dropped and reported as anomalies.
+    pub fn with_rom(mut self, start: u16, end: u16) -> Self {
+        self.rom.push((start, end));
+        self
+    }
+
+    /// Copies `image` into memory starting at `origin`. Bytes past $FFFF are
+    /// dropped with a warning; loaders check the size first.
+    pub fn with_image(mut self, origin: u16, image: &[u8]) -> Self {
+        let start = origin as usize;
+        let end = (start + image.len()).min(ADDRESS_SPACE);
+        if end - start < image.len() {
+            log::warn!("{} bytes of a {} byte image at {:#06x} are past $FFFF and dropped", image.len() - (end - start), image.len(), origin);
+        }
+        self.m[start..end].copy_from_slice(&image[..end - start]);
+        self
+    }
+
+    /// The whole 64K, for save states.
+    pub fn bytes(&self) -> &[u8] {
+        &self.m[..]
+    }
+
+    /// Mutable access to the whole 64K. Writes here bypass the ROM ranges.
+    pub fn bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.m[..]
+    }
+
+    fn is_rom(&self, address: u16) -> bool {
+        self.rom.iter().any(|&(start, end)| start <= address && address <= end)
+    }
+}
+
+// Shorter images are padded with zeroes, longer ones don't fit.
+impl TryFrom<Vec<u8>> for DefaultVirtualMemory {
+    type Error = R6502Error;
+
+    fn try_from(value: Vec<u8>) -> Result<Self> {
+        if value.len() > ADDRESS_SPACE {
+            return Err(R6502Error::ImageTooLarge { length: value.len(), origin: 0 });
+        }
+        Ok(Self::default().with_image(0, &value))
+    }
+}
+
+impl<'a> IntoIterator for &'a DefaultVirtualMemory {
+    type Item = &'a u8;
+
+    type IntoIter = std::slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.m.iter()
+    }
+}
+
+pub trait VirtualMemory {
+    fn read(&mut self, address: u16) -> u8;
+    fn write(&mut self, address: u16, value: u8);
+    /// Reads `address` for a debugger or trace without the side effects of a
+    /// processor read, such as a timer read clearing its interrupt flag. Only
+    /// devices with such side effects need to override this.
+    fn peek(&mut self, address: u16) -> u8 {
+        self.read(address)
+    }
+    /// Little endian word at `address`, the high byte wrapping to $0000 after $FFFF.
+    fn read_u16(&mut self, address: u16) -> u16 {
+        let low_byte = self.read(address) as u16;
+        let high_byte = self.read(address.wrapping_add(1)) as u16;
+        (high_byte << 8) | low_byte
+    }
+    /// Little endian pointer at `address` in page zero. The high byte comes from
+    /// `address + 1` wrapped within page zero, as the 6502 fetches (zp,X) and
+    /// (zp),Y pointers: the pointer at $FF takes its high byte from $00.
+    fn read_u16_zp_wrapped(&mut self, address: u8) -> u16 {
+        let low_byte = self.read(address as u16) as u16;
+        let high_byte = self.read(address.wrapping_add(1) as u16) as u16;
+        (high_byte << 8) | low_byte
+    }
+    /// Writes `value` little endian, low byte first.
+    fn write_u16(&mut self, address: u16, value: u16) {
+        self.write(address, value as u8);
+        self.write(address.wrapping_add(1), (value >> 8) as u8);
+    }
+    /// Reports accesses that real hardware tolerates but which usually point at a bug,
+    /// such as writing to ROM. Called by the emulator before the access is performed.
+    fn check_access(&self, _address: u16, _action: &SystemAction) -> Option<AnomalyKind> {
+        None
+    }
+    /// Advances attached devices by `cycles` CPU cycles. Called after every instruction.
+    fn tick(&mut self, _cycles: u64) {}
+    /// CPU cycles the processor has to wait before the next instruction, for devices
+    /// that pull RDY low. Called after `tick`.
+    fn stall(&mut self) -> u64 {
+        0
+    }
+    /// Current (frame, scanline) of an attached video device.
+    fn raster(&self) -> Option<(u64, u16)> {
+        None
+    }
+    /// Current (scanline, dot) of an attached video device.
+    fn beam(&self) -> Option<(u16, u16)> {
+        None
+    }
+    /// Level of the IRQ line, true while a device is pulling it low. Checked before
+    /// every instruction.
+    fn irq(&self) -> bool {
+        false
+    }
+    /// Whether a device pulled the edge triggered NMI line since the last call.
+    /// Checked after every `tick`.
+    fn nmi(&mut self) -> bool {
+        false
+    }
+    /// Saves whatever the memory keeps on disk, such as battery backed RAM.
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+impl VirtualMemory for DefaultVirtualMemory {
+    fn read(&mut self, address: u16) -> u8 {
+        self.m[address as usize]
+    }
+    fn write(&mut self, address: u16, value: u8) {
+        if self.is_rom(address) {
+            return;
+        }
+        self.m[address as usize] = value;
+    }
+    fn check_access(&self, address: u16, action: &SystemAction) -> Option<AnomalyKind> {
+        match action {
+            SystemAction::WRITE if self.is_rom(address) => Some(AnomalyKind::RomWrite),
+            _ => None,
+        }
+    }
+}
+
+
+
+// https://www.nesdev.org/wiki/CPU_ALL
+
+/*
+        .--\/--.
+ AD1 <- |01  40| -- +5V
+ AD2 <- |02  39| -> OUT0
+/RST -> |03  38| -> OUT1
+ A00 <- |04  37| -> OUT2
+ A01 <- |05  36| -> /OE1
+ A02 <- |06  35| -> /OE2
+ A03 <- |07  34| -> R/W
+ A04 <- |08  33| <- /NMI
+ A05 <- |09  32| <- /IRQ
+ A06 <- |10  31| -> M2
+ A07 <- |11  30| <- TST (usually GND)
+ A08 <- |12  29| <- CLK
+ A09 <- |13  28| <> D0
+ A10 <- |14  27| <> D1
+ A11 <- |15  26| <> D2
+ A12 <- |16  25| <> D3
+ A13 <- |17  24| <> D4
+ A14 <- |18  23| <> D5
+ A15 <- |19  22| <> D6
+ GND -- |20  21| <> D7
+
+*/
@@ -0,0 +1,166 @@
+use crate::emulator::CPUEmulatorBuilderError;
+use crate::state::EmulatorError;
+
+// The one error type of the library API, so code embedding the emulator can match
+// on what went wrong. The binaries and examples wrap it in anyhow. Display and
+// Error are written out by hand rather than derived with thiserror, which keeps
+// the core's dependencies down to what it already had.
+
+pub type Result<T, E = R6502Error> = std::result::Result<T, E>;
+
+/// Non-exhaustive: some variants only exist with a feature enabled, and new
+/// devices and loaders bring new failures.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum R6502Error {
+    /// An instruction faulted, e.g. an unimplemented (illegal) opcode.
+    Execution(EmulatorError),
+    /// A handler installed with `CPUEmulator::override_opcode` failed.
+    Handler(Box<dyn std::error::Error + Send + Sync>),
+    /// The emulator builder was missing a required field.
+    Builder(CPUEmulatorBuilderError),
+    /// A file could not be read.
+    Io(std::io::Error),
+    /// An image of `length` bytes doesn't fit in the address space at `origin`.
+    ImageTooLarge { length: usize, origin: u16 },
+    NotINes,
+    /// Not a PSID or RSID music file.
+    NotSidFile,
+    TruncatedImage,
+    UnsupportedMapper(u8),
+    UnsupportedPrgSize(usize),
+    /// An Atari 2600 cartridge that isn't 2K or 4K, so needs bank switching.
+    UnsupportedCartridgeSize(usize),
+    MissingLoadAddress,
+    /// An Intel HEX or Motorola S-record file that couldn't be read, with its line number.
+    BadRecord { line: usize, reason: String },
+    UnknownFormat(String),
+    /// A ZIP archive that couldn't be read, or didn't hold exactly one ROM, with the reason.
+    BadArchive(String),
+    /// A line of a symbol file that isn't in the expected format.
+    BadSymbolLine(String),
+    BadHex(String),
+    /// A value that doesn't fit in 16 bits where an address was expected.
+    AddressOutOfRange(u32),
+    UnknownMachine(String),
+    /// A machine config file that isn't valid TOML or doesn't match the schema.
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// A machine config that parsed but describes something that can't be built.
+    Config(String),
+    UnknownDevice(String),
+    NoVideoDevice,
+    /// Names of the threads that panicked while a `ThreadGroup` was shutting down.
+    ThreadsPanicked(Vec<String>),
+    /// Source that `assembler::assemble` couldn't assemble, with its line number.
+    Assembly { line: usize, reason: String },
+    /// A monitor command that couldn't be parsed, with the reason.
+    BadCommand(String),
+    /// An input script or movie that couldn't be parsed, with the reason.
+    BadInput(String),
+    /// A movie file that couldn't be read, with the reason.
+    BadMovie(String),
+    /// Playback of a movie diverged from the recording at `frame`.
+    Desync { frame: u64, expected: u64, actual: u64 },
+    /// Two runs of the same workload had different state hashes at `clock`.
+    Nondeterministic { clock: u64, first: u64, second: u64 },
+    /// A lockstep peer sent something unexpected, or the transport failed.
+    Netplay(String),
+    /// A ca65 listing that couldn't be read, with the reason.
+    BadListing(String),
+    /// A cycle log file that couldn't be read, with the reason.
+    BadCycleLog(String),
+    /// A grading spec that couldn't be parsed, with its line number.
+    BadSpec { line: usize, reason: String },
+    /// A .sid tune's init or play routine at `routine` didn't return, with why.
+    SidRoutine { routine: u16, reason: String },
+}
+
+impl R6502Error {
+    /// Wraps an error raised by user code, such as an opcode handler.
+    pub fn handler<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> Self {
+        Self::Handler(error.into())
+    }
+}
+
+impl std::fmt::Display for R6502Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Execution(error) => write!(f, "{}", error),
+            Self::Handler(error) => write!(f, "Opcode handler failed: {}", error),
+            Self::Builder(error) => write!(f, "{}", error),
+            Self::Io(error) => write!(f, "{}", error),
+            Self::ImageTooLarge { length, origin } => write!(f, "{} byte image does not fit at {:#06x}", length, origin),
+            Self::NotINes => write!(f, "Not an iNES image"),
+            Self::NotSidFile => write!(f, "Not a PSID or RSID file"),
+            Self::TruncatedImage => write!(f, "Image is truncated"),
+            Self::UnsupportedMapper(mapper) => write!(f, "Mapper {} is not supported, only NROM (0) is", mapper),
+            Self::UnsupportedPrgSize(size) => write!(f, "Unexpected PRG ROM size {} for NROM", size),
+            Self::UnsupportedCartridgeSize(size) => write!(f, "Unexpected 2600 cartridge size {}, only 2K and 4K are supported", size),
+            Self::BadRecord { line, reason } => write!(f, "Record line {}: {}", line, reason),
+            Self::UnknownFormat(name) => write!(f, "Unknown file format {}", name),
+            Self::BadArchive(reason) => write!(f, "Invalid ZIP archive: {}", reason),
+            Self::MissingLoadAddress => write!(f, "PRG file is missing its load address"),
+            Self::BadSymbolLine(line) => write!(f, "Unrecognised label line: {}", line),
+            Self::BadHex(value) => write!(f, "Invalid hex value {}", value),
+            Self::AddressOutOfRange(value) => write!(f, "Address {:#x} is outside of the 6502 address space", value),
+            Self::UnknownMachine(name) => write!(f, "Unknown machine profile {}", name),
+            #[cfg(feature = "toml")]
+            Self::Toml(error) => write!(f, "{}", error),
+            Self::Config(reason) => write!(f, "Invalid machine config: {}", reason),
+            Self::UnknownDevice(name) => write!(f, "Unknown device type {}", name),
+            Self::NoVideoDevice => write!(f, "No video device is attached"),
+            Self::ThreadsPanicked(names) => write!(f, "Threads panicked during shutdown: {}", names.join(", ")),
+            Self::Assembly { line, reason } => write!(f, "Line {}: {}", line, reason),
+            Self::BadCommand(reason) => write!(f, "{}", reason),
+            Self::BadInput(reason) => write!(f, "Invalid input script: {}", reason),
+            Self::BadMovie(reason) => write!(f, "Invalid movie: {}", reason),
+            Self::Netplay(reason) => write!(f, "Netplay: {}", reason),
+            Self::BadListing(reason) => write!(f, "Invalid listing: {}", reason),
+            Self::BadCycleLog(reason) => write!(f, "Invalid cycle log: {}", reason),
+            Self::BadSpec { line, reason } => write!(f, "Spec line {}: {}", line, reason),
+            Self::SidRoutine { routine, reason } => write!(f, "Tune routine at {:#06x} didn't return: {}", routine, reason),
+            Self::Nondeterministic { clock, first, second } => write!(f, "Runs diverged by cycle {}: state hash {:016x}, then {:016x}", clock, first, second),
+            Self::Desync { frame, expected, actual } => write!(f, "Playback desynced at frame {}: checksum {:016x}, recorded {:016x}", frame, actual, expected),
+        }
+    }
+}
+
+impl std::error::Error for R6502Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Execution(error) => Some(error),
+            Self::Handler(error) => Some(error.as_ref()),
+            Self::Builder(error) => Some(error),
+            Self::Io(error) => Some(error),
+            #[cfg(feature = "toml")]
+            Self::Toml(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<EmulatorError> for R6502Error {
+    fn from(error: EmulatorError) -> Self {
+        Self::Execution(error)
+    }
+}
+
+impl From<CPUEmulatorBuilderError> for R6502Error {
+    fn from(error: CPUEmulatorBuilderError) -> Self {
+        Self::Builder(error)
+    }
+}
+
+impl From<std::io::Error> for R6502Error {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[cfg(feature = "toml")]
+impl From<toml::de::Error> for R6502Error {
+    fn from(error: toml::de::Error) -> Self {
+        Self::Toml(error)
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+
+use crate::analysis::coverage::ExecutedBytes;
+use crate::disassembler::{disassemble_at, DisassembledInstruction};
+use crate::instructions::AddressingMode;
+use crate::opcodes;
+use crate::symbols::SymbolTable;
+
+// Writes a ROM image back out as assembler source that rebuilds the same bytes.
+// Bytes marked as code are disassembled, everything else becomes `.byte` data.
+// Symbols inside the image turn into labels, symbols outside it (hardware
+// registers, kernal entry points) into equates.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Ca65,
+    Dasm,
+}
+
+enum Item {
+    Code(DisassembledInstruction),
+    Byte(u16, u8),
+}
+
+impl Item {
+    fn address(&self) -> u16 {
+        match self {
+            Self::Code(instruction) => instruction.address,
+            Self::Byte(address, _) => *address,
+        }
+    }
+}
+
+/// Renders `image` loaded at `origin` as a complete source file.
+pub fn project(image: &[u8], origin: u16, code: &ExecutedBytes, symbols: Option<&SymbolTable>, syntax: Syntax) -> String {
+    let end = origin as usize + image.len();
+    let in_image = |address: u16| (address as usize) >= origin as usize && (address as usize) < end;
+
+    let mut items = vec![];
+    let mut offset = 0;
+    while offset < image.len() {
+        let address = origin.wrapping_add(offset as u16);
+        let instruction = disassemble_at(&image[offset..], address);
+        // Illegal opcodes are spelled differently by every assembler, keep them as data.
+        let encodable = instruction.is_valid()
+            && opcodes::describe(instruction.bytes[0]).legal
+            && offset + instruction.length() as usize <= image.len();
+        if code.is_executed(address) && encodable {
+            offset += instruction.length() as usize;
+            items.push(Item::Code(instruction));
+        } else {
+            items.push(Item::Byte(address, image[offset]));
+            offset += 1;
+        }
+    }
+    let starts: BTreeSet<u16> = items.iter().map(Item::address).collect();
+
+    // Labels can only go where a line starts, anything else is emitted as an equate.
+    let mut labels = SymbolTable::default();
+    let mut equates: Vec<(String, u16)> = vec![];
+    if let Some(symbols) = symbols {
+        let mut named: Vec<(&str, u16)> = symbols.iter().collect();
+        named.sort_by_key(|(name, address)| (*address, name.to_string()));
+        for (name, address) in named {
+            let primary = symbols.name_for(address) == Some(name);
+            if primary && in_image(address) && starts.contains(&address) {
+                labels.insert(name, address);
+            } else {
+                equates.push((name.to_owned(), address));
+            }
+        }
+    }
+    for item in items.iter() {
+        if let Item::Code(instruction) = item {
+            let target = instruction.target().or_else(|| match instruction.instruction.mode {
+                Some(AddressingMode::DirectAbsolute | AddressingMode::DirectAbsoluteX | AddressingMode::DirectAbsoluteY) => {
+                    instruction.operand()
+                }
+                _ => None,
+            });
+            if let Some(target) = target.filter(|target| in_image(*target) && starts.contains(target)) {
+                if labels.name_for(target).is_none() {
+                    labels.insert(&format!("L{:04X}", target), target);
+                }
+            }
+        }
+    }
+    let mut names = labels.clone();
+    if let Some(symbols) = symbols {
+        names.extend(symbols.clone());
+    }
+
+    let mut out = String::new();
+    match syntax {
+        Syntax::Ca65 => out.push_str("; Generated by r6502\n\n.setcpu \"6502\"\n\n"),
+        Syntax::Dasm => out.push_str("; Generated by r6502\n\n    processor 6502\n\n"),
+    }
+    for (name, address) in equates.iter() {
+        let width = if *address < 0x100 { 2 } else { 4 };
+        out.push_str(&format!("{} = ${:0width$X}\n", name, address, width = width));
+    }
+    if !equates.is_empty() {
+        out.push('\n');
+    }
+    match syntax {
+        Syntax::Ca65 => out.push_str(&format!(".org ${:04X}\n\n", origin)),
+        Syntax::Dasm => out.push_str(&format!("    org ${:04X}\n\n", origin)),
+    }
+
+    let mut index = 0;
+    while index < items.len() {
+        let address = items[index].address();
+        if let Some(name) = labels.name_for(address) {
+            match syntax {
+                Syntax::Ca65 => out.push_str(&format!("{}:\n", name)),
+                Syntax::Dasm => out.push_str(&format!("{}\n", name)),
+            }
+        }
+        match &items[index] {
+            Item::Code(instruction) => {
+                out.push_str(&format!("    {}\n", source_text(instruction, &names, syntax)));
+                index += 1;
+            }
+            Item::Byte(..) => {
+                // Rows of up to eight bytes, broken at code and labels.
+                let mut bytes = vec![];
+                while let Some(Item::Byte(address, byte)) = items.get(index) {
+                    if !bytes.is_empty() && (bytes.len() == 8 || labels.name_for(*address).is_some()) {
+                        break;
+                    }
+                    bytes.push(format!("${:02X}", byte));
+                    index += 1;
+                }
+                out.push_str(&format!("    .byte {}\n", bytes.join(", ")));
+            }
+        }
+    }
+    out
+}
+
+// Absolute operands below $0100 would be shortened to zero page by the assembler,
+// so they get an explicit size override.
+fn source_text(instruction: &DisassembledInstruction, names: &SymbolTable, syntax: Syntax) -> String {
+    let text = instruction.text(Some(names));
+    let absolute = matches!(
+        instruction.instruction.mode,
+        Some(AddressingMode::DirectAbsolute | AddressingMode::DirectAbsoluteX | AddressingMode::DirectAbsoluteY)
+    );
+    if !absolute || instruction.operand().unwrap_or_default() >= 0x100 {
+        return text;
+    }
+    let (mnemonic, operand) = text.split_at(3);
+    match syntax {
+        Syntax::Ca65 => format!("{} a:{}", mnemonic, operand.trim_start()),
+        Syntax::Dasm => format!("{}.w{}", mnemonic, operand),
+    }
+}
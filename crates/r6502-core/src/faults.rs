@@ -0,0 +1,224 @@
+use crate::diagnostics::AnomalyKind;
+use crate::emulator::VirtualMemory;
+use crate::error::Result;
+use crate::power_on::splitmix64;
+use crate::state::SystemAction;
+
+// Fault injection for robustness tests: `FaultyMemory` wraps any memory and,
+// following a seeded `FaultPlan`, corrupts some of the accesses that go
+// through it. Each rule covers an address range, reads, writes or both, and a
+// percentage of those accesses. The same plan over the same program injects
+// the same faults, so a failure found this way can be replayed.
+//
+// A failed read sees an open bus ($FF) and a failed write is dropped. Every
+// access counts, including the emulator's `peek`, so inspect the results
+// through `inner` once the faults have been injected.
+
+/// What happens to an access a rule picks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// One bit of the value read or written is flipped.
+    FlipBit,
+    /// A random byte is read or written instead.
+    WrongData,
+    /// The read sees an open bus, the write is dropped.
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultRule {
+    pub fault: Fault,
+    pub start: u16,
+    pub end: u16,
+    /// Reads or writes only, or both when `None`.
+    pub action: Option<SystemAction>,
+    /// Share of the accesses covered that are faulted, from 0 to 100.
+    pub percent: f64,
+}
+
+impl FaultRule {
+    /// Faults `percent` of the reads and writes of `start..=end`.
+    pub fn new(fault: Fault, start: u16, end: u16, percent: f64) -> Self {
+        Self { fault, start, end, action: None, percent }
+    }
+
+    pub fn reads_only(self) -> Self {
+        Self { action: Some(SystemAction::READ), ..self }
+    }
+
+    pub fn writes_only(self) -> Self {
+        Self { action: Some(SystemAction::WRITE), ..self }
+    }
+
+    fn covers(&self, address: u16, action: &SystemAction) -> bool {
+        self.start <= address && address <= self.end && self.action.as_ref().is_none_or(|only| only == action)
+    }
+}
+
+/// Rules checked in order for every access; the first whose roll comes up
+/// faults it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FaultPlan {
+    pub seed: u64,
+    pub rules: Vec<FaultRule>,
+}
+
+impl FaultPlan {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rules: vec![] }
+    }
+
+    pub fn with(mut self, rule: FaultRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+}
+
+/// A fault that was injected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectedFault {
+    pub fault: Fault,
+    pub address: u16,
+    pub action: SystemAction,
+    /// Accesses through the wrapper before this one.
+    pub access: u64,
+    /// The value the access would have read or written.
+    pub expected: u8,
+    /// What was read or written instead, `None` for a dropped write.
+    pub actual: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FaultyMemory<M> {
+    inner: M,
+    plan: FaultPlan,
+    state: u64,
+    accesses: u64,
+    enabled: bool,
+    injected: Vec<InjectedFault>,
+}
+
+impl<M> FaultyMemory<M>
+where M: VirtualMemory {
+    pub fn new(inner: M, plan: FaultPlan) -> Self {
+        Self { inner, state: plan.seed, plan, accesses: 0, enabled: true, injected: vec![] }
+    }
+
+    /// Faults injected so far, oldest first.
+    pub fn injected(&self) -> &[InjectedFault] {
+        &self.injected
+    }
+
+    /// Turns injection off or back on. Accesses made while it is off pass
+    /// through without counting or rolling.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut M {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> M {
+        self.inner
+    }
+
+    // The fault to inject into this access, if any.
+    fn roll(&mut self, address: u16, action: &SystemAction) -> Option<Fault> {
+        if !self.enabled {
+            return None;
+        }
+        self.accesses += 1;
+        for rule in self.plan.rules.iter().filter(|rule| rule.covers(address, action)) {
+            let roll = (splitmix64(&mut self.state) >> 11) as f64 / (1u64 << 53) as f64;
+            if roll * 100.0 < rule.percent {
+                return Some(rule.fault);
+            }
+        }
+        None
+    }
+
+    // The value `fault` turns `value` into, except for `Fail`.
+    fn corrupt(&mut self, fault: Fault, value: u8) -> u8 {
+        let random = splitmix64(&mut self.state);
+        match fault {
+            Fault::FlipBit => value ^ (1 << (random % 8)),
+            Fault::WrongData | Fault::Fail => random as u8,
+        }
+    }
+
+    fn record(&mut self, fault: Fault, address: u16, action: SystemAction, expected: u8, actual: Option<u8>) {
+        log::debug!("injected {:?} into the {} of {:#06x} of {:#04x}", fault, action, address, expected);
+        self.injected.push(InjectedFault { fault, address, action, access: self.accesses - 1, expected, actual });
+    }
+}
+
+impl<M> VirtualMemory for FaultyMemory<M>
+where M: VirtualMemory {
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.inner.read(address);
+        let Some(fault) = self.roll(address, &SystemAction::READ) else { return value };
+        let actual = match fault {
+            Fault::Fail => 0xFF,
+            fault => self.corrupt(fault, value),
+        };
+        self.record(fault, address, SystemAction::READ, value, Some(actual));
+        actual
+    }
+
+    // Debugger reads are neither faulted nor counted as accesses.
+    fn peek(&mut self, address: u16) -> u8 {
+        self.inner.peek(address)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let Some(fault) = self.roll(address, &SystemAction::WRITE) else {
+            return self.inner.write(address, value);
+        };
+        let actual = match fault {
+            Fault::Fail => None,
+            fault => {
+                let actual = self.corrupt(fault, value);
+                self.inner.write(address, actual);
+                Some(actual)
+            }
+        };
+        self.record(fault, address, SystemAction::WRITE, value, actual);
+    }
+
+    fn check_access(&self, address: u16, action: &SystemAction) -> Option<AnomalyKind> {
+        self.inner.check_access(address, action)
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.inner.tick(cycles)
+    }
+
+    fn stall(&mut self) -> u64 {
+        self.inner.stall()
+    }
+
+    fn raster(&self) -> Option<(u64, u16)> {
+        self.inner.raster()
+    }
+
+    fn beam(&self) -> Option<(u16, u16)> {
+        self.inner.beam()
+    }
+
+    fn irq(&self) -> bool {
+        self.inner.irq()
+    }
+
+    fn nmi(&mut self) -> bool {
+        self.inner.nmi()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
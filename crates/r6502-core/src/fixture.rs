@@ -0,0 +1,244 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembler;
+use crate::disassembler::disassemble_at;
+use crate::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, StopReason, ADDRESS_SPACE};
+use crate::error::{R6502Error, Result};
+use crate::state::{Registers, SystemState};
+use crate::symbols::SymbolTable;
+
+// Regression tests for 6502 code: assemble (or load) a program, run it until it
+// halts and compare the registers and memory it leaves behind. A failed check
+// reports every mismatch at once along with how the registers moved, in the
+// spirit of a snapshot diff. `run_fixture!` wraps the whole thing:
+//
+//     run_fixture! {
+//         source: "ldx #3\n loop: dex\n bne loop\n brk",
+//         origin: 0x0200,
+//         registers: { x: 0, pc: 0x0206 },
+//         memory: { 0x0200 => [0xA2, 0x03] },
+//     };
+//
+// BRK and KIL both end the run.
+
+const DEFAULT_CYCLES: u64 = 1_000_000;
+
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    origin: u16,
+    image: Vec<u8>,
+    entry: u16,
+    max_cycles: u64,
+    symbols: SymbolTable,
+}
+
+impl Fixture {
+    /// Assembles `source` at `origin`, see [`assembler::assemble`].
+    pub fn source(source: &str, origin: u16) -> Result<Self> {
+        let assembly = assembler::assemble(source, origin)?;
+        Ok(Self { origin, image: assembly.image, entry: origin, max_cycles: DEFAULT_CYCLES, symbols: assembly.symbols })
+    }
+
+    pub fn binary(image: &[u8], origin: u16) -> Result<Self> {
+        if origin as usize + image.len() > ADDRESS_SPACE {
+            return Err(R6502Error::ImageTooLarge { length: image.len(), origin });
+        }
+        Ok(Self { origin, image: image.to_vec(), entry: origin, max_cycles: DEFAULT_CYCLES, symbols: SymbolTable::default() })
+    }
+
+    /// Where execution starts, the origin unless set.
+    pub fn entry(mut self, entry: u16) -> Self {
+        self.entry = entry;
+        self
+    }
+
+    /// Cycles after which a program that hasn't halted fails the fixture.
+    pub fn max_cycles(mut self, cycles: u64) -> Self {
+        self.max_cycles = cycles;
+        self
+    }
+
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// Runs the program until it halts, faults or uses up its cycles.
+    pub fn run(&self) -> Result<FixtureRun> {
+        let memory = DefaultVirtualMemory::default().with_image(self.origin, &self.image);
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState { pc: self.entry, s: 0xFD, running: true, ..Default::default() })
+            .memory(Arc::new(Mutex::new(memory)))
+            .build()?;
+        emulator.override_opcode(0x00, |emulator| {
+            emulator.state.running = false;
+            Ok(())
+        });
+
+        let initial = emulator.state.registers();
+        let stop = loop {
+            match emulator.execute_next_instruction() {
+                Ok(_) => (),
+                Err(None) => break StopReason::Halted,
+                Err(Some(instruction)) => break StopReason::Error(Some(instruction)),
+            }
+            emulator.state.cycles.clear();
+            if emulator.clock() >= self.max_cycles {
+                break StopReason::Timeout;
+            }
+        };
+        Ok(FixtureRun { emulator, initial, stop, symbols: self.symbols.clone() })
+    }
+}
+
+/// What a fixture should leave behind.
+#[derive(Debug, Clone, Default)]
+pub struct Expectations {
+    /// By name as in [`Registers`]: `pc`, `a`, `x`, `y`, `s` or `p`.
+    pub registers: Vec<(&'static str, u16)>,
+    /// Bytes expected from an address onwards.
+    pub memory: Vec<(u16, Vec<u8>)>,
+}
+
+impl Expectations {
+    pub fn register(mut self, name: &'static str, value: u16) -> Self {
+        self.registers.push((name, value));
+        self
+    }
+
+    pub fn memory(mut self, address: u16, bytes: &[u8]) -> Self {
+        self.memory.push((address, bytes.to_vec()));
+        self
+    }
+}
+
+pub struct FixtureRun {
+    pub emulator: CPUEmulator<DefaultVirtualMemory>,
+    /// Registers at the entry point.
+    pub initial: Registers,
+    /// `Halted` unless the program faulted, or `Timeout` if it ran out of cycles.
+    pub stop: StopReason,
+    symbols: SymbolTable,
+}
+
+pub(crate) fn register_value(registers: &Registers, name: &str) -> Option<u16> {
+    match name {
+        "pc" => Some(registers.pc),
+        "a" => Some(registers.a as u16),
+        "x" => Some(registers.x as u16),
+        "y" => Some(registers.y as u16),
+        "s" => Some(registers.s as u16),
+        "p" => Some(registers.p.bits() as u16),
+        _ => None,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(" ")
+}
+
+impl FixtureRun {
+    pub fn registers(&self) -> Registers {
+        self.emulator.state.registers()
+    }
+
+    /// Compares the final state with `expected`, reporting every difference.
+    pub fn check(&self, expected: &Expectations) -> std::result::Result<(), FixtureFailure> {
+        let registers = self.registers();
+        let mut mismatches = vec![];
+        if self.stop != StopReason::Halted {
+            mismatches.push(format!("  program did not halt: {:?}", self.stop));
+        }
+
+        for (name, value) in expected.registers.iter() {
+            match register_value(&registers, name) {
+                Some(actual) if actual == *value => (),
+                Some(actual) => {
+                    let width = if *name == "pc" { 4 } else { 2 };
+                    mismatches.push(format!("  {}: expected {:0width$X}, got {:0width$X}", name, value, actual, width = width));
+                }
+                None => mismatches.push(format!("  {}: no such register", name)),
+            }
+        }
+
+        for (address, bytes) in expected.memory.iter() {
+            let actual: Vec<u8> = (0..bytes.len()).map(|offset| self.emulator.peek(address.wrapping_add(offset as u16))).collect();
+            if actual != *bytes {
+                let label = self.symbols.name_for(*address).map(|name| format!(" ({})", name)).unwrap_or_default();
+                let marks: String = bytes
+                    .iter()
+                    .zip(actual.iter())
+                    .map(|(expected, actual)| if expected == actual { "   " } else { "^^ " })
+                    .collect();
+                mismatches.push(format!(
+                    "  ${:04X}{}:\n    expected {}\n    got      {}\n             {}",
+                    address, label, hex(bytes), hex(&actual), marks.trim_end()
+                ));
+            }
+        }
+
+        if mismatches.is_empty() {
+            return Ok(());
+        }
+        let mut report = format!("fixture failed after {} cycles\n{}\n", self.emulator.clock(), mismatches.join("\n"));
+        let changes = self.initial.diff(&registers);
+        report += &format!("final registers: {}\n", registers);
+        if !changes.is_empty() {
+            let changes: Vec<String> = changes.iter().map(|change| change.to_string()).collect();
+            report += &format!("changed since entry: {}\n", changes.join(", "));
+        }
+        let last = self.emulator.instruction_pc();
+        let bytes: Vec<u8> = (0..3).map(|offset| self.emulator.peek(last.wrapping_add(offset))).collect();
+        report += &format!("last instruction: {}", disassemble_at(&bytes, last));
+        Err(FixtureFailure { report })
+    }
+}
+
+/// The report from a failed [`FixtureRun::check`].
+#[derive(Debug, Clone)]
+pub struct FixtureFailure {
+    pub report: String,
+}
+
+impl std::fmt::Display for FixtureFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.report)
+    }
+}
+
+impl std::error::Error for FixtureFailure {}
+
+/// Assembles (`source:`) or loads (`binary:`) a program, runs it to completion and
+/// panics with a report of every mismatch unless the listed registers and memory
+/// hold the expected values. Evaluates to the [`fixture::FixtureRun`](crate::fixture::FixtureRun)
+/// for further checks.
+#[macro_export]
+macro_rules! run_fixture {
+    (
+        $kind:ident: $program:expr,
+        origin: $origin:expr
+        $(, entry: $entry:expr)?
+        $(, cycles: $cycles:expr)?
+        $(, registers: { $($register:ident: $value:expr),* $(,)? })?
+        $(, memory: { $($address:expr => $bytes:expr),* $(,)? })?
+        $(,)?
+    ) => {{
+        let fixture = match $crate::fixture::Fixture::$kind($program, $origin) {
+            Ok(fixture) => fixture,
+            Err(error) => panic!("fixture program: {}", error),
+        };
+        $(let fixture = fixture.entry($entry);)?
+        $(let fixture = fixture.max_cycles($cycles);)?
+        let run = fixture.run().expect("fixture emulator");
+        #[allow(unused_mut)]
+        let mut expected = $crate::fixture::Expectations::default();
+        $($(expected = expected.register(stringify!($register), $value as u16);)*)?
+        $($(
+            let bytes: &[u8] = &$bytes;
+            expected = expected.memory($address, bytes);
+        )*)?
+        if let Err(failure) = run.check(&expected) {
+            panic!("{}", failure);
+        }
+        run
+    }};
+}
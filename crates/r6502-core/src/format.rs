@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// How numbers are written in the monitor, disassembly, instruction traces and
+// register diffs. Commodore users expect `$C000`, NES tools and modern
+// debuggers `0xC000`, and some people would rather read decimal or binary.
+// The setting is process wide, like the log level, so every view agrees.
+//
+// Address columns of listings and hex dumps stay hex so they line up.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// Bare hex digits in tables, `$` where the syntax needs a prefix.
+    #[default]
+    Hex,
+    /// `$C000`
+    Dollar,
+    /// `0xC000`
+    ZeroX,
+    Decimal,
+    /// `%1100000000000000`
+    Binary,
+}
+
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+const FORMATS: [NumberFormat; 5] = [NumberFormat::Hex, NumberFormat::Dollar, NumberFormat::ZeroX, NumberFormat::Decimal, NumberFormat::Binary];
+
+/// Changes the format for every view from now on.
+pub fn set_number_format(format: NumberFormat) {
+    CURRENT.store(FORMATS.iter().position(|candidate| *candidate == format).unwrap_or(0) as u8, Ordering::Relaxed);
+}
+
+pub fn number_format() -> NumberFormat {
+    FORMATS[CURRENT.load(Ordering::Relaxed) as usize]
+}
+
+impl NumberFormat {
+    // `value` in a field of `bits` bits, `prefixed` when a bare hex number would
+    // be ambiguous.
+    fn render(&self, value: u16, bits: usize, prefixed: bool) -> String {
+        let digits = bits / 4;
+        match self {
+            Self::Hex if prefixed => format!("${:0digits$X}", value),
+            Self::Hex => format!("{:0digits$X}", value),
+            Self::Dollar => format!("${:0digits$X}", value),
+            Self::ZeroX => format!("0x{:0digits$X}", value),
+            Self::Decimal => value.to_string(),
+            Self::Binary => format!("%{:0bits$b}", value),
+        }
+    }
+
+    pub fn byte(&self, value: u8) -> String {
+        self.render(value as u16, 8, false)
+    }
+
+    pub fn word(&self, value: u16) -> String {
+        self.render(value, 16, false)
+    }
+
+    /// Instruction operand of `digits` hex digits, always marked as a number.
+    pub fn operand(&self, value: u16, digits: usize) -> String {
+        self.render(value, digits * 4, true)
+    }
+}
+
+impl std::str::FromStr for NumberFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "hex" => Ok(Self::Hex),
+            "$" | "dollar" => Ok(Self::Dollar),
+            "0x" => Ok(Self::ZeroX),
+            "dec" | "decimal" => Ok(Self::Decimal),
+            "bin" | "binary" => Ok(Self::Binary),
+            _ => Err(format!("Unknown number format {}, expected hex, $, 0x, dec or bin", name)),
+        }
+    }
+}
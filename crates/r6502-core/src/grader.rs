@@ -0,0 +1,337 @@
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::assembler;
+use crate::batch::BatchRunner;
+use crate::emulator::{stack_address, CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, StopReason, ADDRESS_SPACE};
+use crate::error::{R6502Error, Result};
+use crate::fixture::register_value;
+use crate::state::{SystemFlags, SystemState};
+use crate::symbols::SymbolTable;
+
+// Autograding for 6502 assignments: an instructor describes test cases, each
+// calling one routine of the student's program with given registers and memory
+// and checking what it leaves behind, and every case gets a pass/fail line per
+// assertion. Specs can be written in Rust or in a small text format:
+//
+//     # Multiplies A by X into result (little endian).
+//     case three times four
+//       call multiply
+//       given a=03 x=04
+//       given result: FF FF
+//       expect result: 0C 00
+//       expect c=0
+//       expect cycles<=400
+//
+// Numbers are hex, addresses are hex or symbols from the program. `given` and
+// `expect` take registers (pc, a, x, y, s, p) and flags (n, v, d, i, z, c), or
+// an address followed by `:` and bytes on a line of its own. Every case runs on a fresh machine and
+// returns through RTS; one that doesn't return in time fails.
+
+/// Where a routine under test returns to. Reaching it ends the case.
+pub const RETURN_ADDRESS: u16 = 0xFFFF;
+
+const DEFAULT_CYCLES: u64 = 1_000_000;
+
+const FLAGS: [(char, SystemFlags); 6] = [
+    ('n', SystemFlags::negative),
+    ('v', SystemFlags::overflow),
+    ('d', SystemFlags::decimal),
+    ('i', SystemFlags::interrupt_disable),
+    ('z', SystemFlags::zero),
+    ('c', SystemFlags::carry),
+];
+
+fn register_name(name: &str) -> Option<&'static str> {
+    ["pc", "a", "x", "y", "s", "p"].into_iter().find(|register| *register == name)
+}
+
+fn flag(name: char) -> Option<SystemFlags> {
+    FLAGS.iter().find(|(letter, _)| *letter == name).map(|(_, flag)| *flag)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(" ")
+}
+
+/// A precondition or postcondition of a test case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Register(&'static str, u16),
+    Flag(char, bool),
+    Memory(u16, Vec<u8>),
+    /// Only as an expectation: the routine returns within this many cycles.
+    MaxCycles(u64),
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Register(name, value) => write!(f, "{}={:02X}", name, value),
+            Self::Flag(name, set) => write!(f, "{}={}", name, *set as u8),
+            Self::Memory(address, bytes) => write!(f, "{:04X}: {}", address, hex(bytes)),
+            Self::MaxCycles(cycles) => write!(f, "cycles<={}", cycles),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: String,
+    pub routine: u16,
+    pub given: Vec<Condition>,
+    pub expect: Vec<Condition>,
+}
+
+impl TestCase {
+    pub fn new(name: &str, routine: u16) -> Self {
+        Self { name: name.to_owned(), routine, given: vec![], expect: vec![] }
+    }
+
+    pub fn given(mut self, condition: Condition) -> Self {
+        self.given.push(condition);
+        self
+    }
+
+    pub fn expect(mut self, condition: Condition) -> Self {
+        self.expect.push(condition);
+        self
+    }
+}
+
+/// The outcome of one assertion.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AssertionResult {
+    /// The assertion as it is written in a spec.
+    pub assertion: String,
+    pub passed: bool,
+    /// What the machine had instead, empty when the assertion passed.
+    pub actual: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CaseReport {
+    pub name: String,
+    /// Whether the routine returned, rather than faulting or running out of cycles.
+    pub returned: bool,
+    /// Why the run ended, e.g. `Timeout`.
+    pub stop: String,
+    pub cycles: u64,
+    pub results: Vec<AssertionResult>,
+}
+
+impl CaseReport {
+    pub fn passed(&self) -> bool {
+        self.returned && self.results.iter().all(|result| result.passed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GradeReport {
+    pub cases: Vec<CaseReport>,
+}
+
+impl GradeReport {
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|case| case.passed()).count()
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.passed() == self.cases.len()
+    }
+}
+
+impl std::fmt::Display for GradeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for case in self.cases.iter() {
+            let verdict = if case.passed() { "PASS" } else { "FAIL" };
+            write!(f, "{} {} ({} cycles)", verdict, case.name, case.cycles)?;
+            if !case.returned {
+                write!(f, ", did not return: {}", case.stop)?;
+            }
+            writeln!(f)?;
+            for result in case.results.iter() {
+                match result.passed {
+                    true => writeln!(f, "  ok   {}", result.assertion)?,
+                    false => writeln!(f, "  FAIL {}, got {}", result.assertion, result.actual)?,
+                }
+            }
+        }
+        write!(f, "{}/{} cases passed", self.passed(), self.cases.len())
+    }
+}
+
+/// The student program under test.
+#[derive(Debug, Clone)]
+pub struct Grader {
+    origin: u16,
+    image: Vec<u8>,
+    symbols: SymbolTable,
+    max_cycles: u64,
+}
+
+impl Grader {
+    /// Assembles `source` at `origin`; its labels can be used in specs.
+    pub fn source(source: &str, origin: u16) -> Result<Self> {
+        let assembly = assembler::assemble(source, origin)?;
+        Ok(Self { origin, image: assembly.image, symbols: assembly.symbols, max_cycles: DEFAULT_CYCLES })
+    }
+
+    pub fn binary(image: &[u8], origin: u16) -> Result<Self> {
+        if origin as usize + image.len() > ADDRESS_SPACE {
+            return Err(R6502Error::ImageTooLarge { length: image.len(), origin });
+        }
+        Ok(Self { origin, image: image.to_vec(), symbols: SymbolTable::default(), max_cycles: DEFAULT_CYCLES })
+    }
+
+    /// Symbols for specs to refer to, e.g. from the student's ld65 map.
+    pub fn symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
+    /// Cycles a routine gets to return before its case fails.
+    pub fn max_cycles(mut self, cycles: u64) -> Self {
+        self.max_cycles = cycles;
+        self
+    }
+
+    /// Parses a spec in the text format, see the module comment.
+    pub fn parse_spec(&self, spec: &str) -> Result<Vec<TestCase>> {
+        let mut cases: Vec<TestCase> = vec![];
+        for (index, line) in spec.lines().enumerate() {
+            let bad = |reason: String| R6502Error::BadSpec { line: index + 1, reason };
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((keyword, rest)) = line.split_once(char::is_whitespace).or((!line.is_empty()).then_some((line, ""))) else {
+                continue;
+            };
+            let rest = rest.trim();
+            if keyword == "case" {
+                cases.push(TestCase::new(rest, self.origin));
+                continue;
+            }
+            let case = cases.last_mut().ok_or_else(|| bad(format!("{} before the first case", keyword)))?;
+            match keyword {
+                "call" => case.routine = self.address(rest).map_err(bad)?,
+                "given" => case.given.extend(self.conditions(rest).map_err(bad)?),
+                "expect" => case.expect.extend(self.conditions(rest).map_err(bad)?),
+                _ => return Err(bad(format!("unknown keyword {}", keyword))),
+            }
+        }
+        Ok(cases)
+    }
+
+    fn address(&self, text: &str) -> std::result::Result<u16, String> {
+        match self.symbols.lookup(text) {
+            Some(address) => Ok(address),
+            None => u16::from_str_radix(text.trim_start_matches('$'), 16).map_err(|_| format!("unknown address {}", text)),
+        }
+    }
+
+    fn conditions(&self, text: &str) -> std::result::Result<Vec<Condition>, String> {
+        let number = |value: &str| u64::from_str_radix(value.trim_start_matches('$'), 16).map_err(|_| format!("bad number {}", value));
+        if let Some((address, bytes)) = text.split_once(':') {
+            let bytes = bytes.split_whitespace().map(|byte| number(byte).map(|byte| byte as u8)).collect::<std::result::Result<_, _>>()?;
+            return Ok(vec![Condition::Memory(self.address(address.trim())?, bytes)]);
+        }
+        text.split_whitespace()
+            .map(|term| {
+                if let Some(cycles) = term.strip_prefix("cycles<=") {
+                    return cycles.parse().map(Condition::MaxCycles).map_err(|_| format!("bad cycle count {}", cycles));
+                }
+                let (name, value) = term.split_once('=').ok_or_else(|| format!("expected name=value, got {}", term))?;
+                let value = number(value)?;
+                if let Some(register) = register_name(name) {
+                    return Ok(Condition::Register(register, value as u16));
+                }
+                match name.chars().collect::<Vec<_>>().as_slice() {
+                    [letter] if flag(*letter).is_some() => Ok(Condition::Flag(*letter, value != 0)),
+                    _ => Err(format!("unknown register or flag {}", name)),
+                }
+            })
+            .collect()
+    }
+
+    /// Runs every case, in parallel, and reports on each assertion.
+    pub fn grade(&self, cases: &[TestCase]) -> GradeReport {
+        GradeReport { cases: BatchRunner::new().run(cases, |case| self.run_case(case)) }
+    }
+
+    /// Parses `spec` and grades the program against it.
+    pub fn grade_spec(&self, spec: &str) -> Result<GradeReport> {
+        Ok(self.grade(&self.parse_spec(spec)?))
+    }
+
+    fn run_case(&self, case: &TestCase) -> CaseReport {
+        let memory = DefaultVirtualMemory::default().with_image(self.origin, &self.image);
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState { pc: case.routine, s: 0xFD, running: true, ..Default::default() })
+            .memory(Arc::new(Mutex::new(memory)))
+            .build()
+            .expect("emulator with state and memory");
+        for condition in case.given.iter() {
+            match condition {
+                Condition::Register(name, value) => set_register(&mut emulator, name, *value),
+                Condition::Flag(name, set) => emulator.state.p.set(flag(*name).unwrap_or_default(), *set),
+                Condition::Memory(address, bytes) => {
+                    for (offset, byte) in bytes.iter().enumerate() {
+                        emulator.poke(address.wrapping_add(offset as u16), *byte);
+                    }
+                }
+                Condition::MaxCycles(_) => (),
+            }
+        }
+        // As if called with JSR from just before the return address.
+        let s = emulator.state.s;
+        emulator.poke(stack_address(s), (RETURN_ADDRESS.wrapping_sub(1) >> 8) as u8);
+        emulator.poke(stack_address(s.wrapping_sub(1)), RETURN_ADDRESS.wrapping_sub(1) as u8);
+        emulator.state.s = s.wrapping_sub(2);
+        emulator.add_breakpoint(RETURN_ADDRESS);
+
+        let mut stop = StopReason::Timeout;
+        while emulator.clock() < self.max_cycles {
+            stop = emulator.run_for_cycles((self.max_cycles - emulator.clock()).min(10_000));
+            emulator.state.cycles.clear();
+            if stop != StopReason::Timeout {
+                break;
+            }
+        }
+        let returned = stop == StopReason::Breakpoint(RETURN_ADDRESS);
+        let cycles = emulator.clock();
+        let results = case.expect.iter().map(|condition| check(&emulator, condition, cycles)).collect();
+        CaseReport { name: case.name.clone(), returned, stop: format!("{:?}", stop), cycles, results }
+    }
+}
+
+fn set_register(emulator: &mut CPUEmulator<DefaultVirtualMemory>, name: &str, value: u16) {
+    let state = &mut emulator.state;
+    match name {
+        "pc" => state.pc = value,
+        "a" => state.a = value as u8,
+        "x" => state.x = value as u8,
+        "y" => state.y = value as u8,
+        "s" => state.s = value as u8,
+        _ => state.p = SystemFlags::from_bits_retain(value as u8),
+    }
+}
+
+fn check(emulator: &CPUEmulator<DefaultVirtualMemory>, condition: &Condition, cycles: u64) -> AssertionResult {
+    let registers = emulator.state.registers();
+    let (passed, actual) = match condition {
+        Condition::Register(name, value) => {
+            let actual = register_value(&registers, name).unwrap_or_default();
+            (actual == *value, format!("{}={:02X}", name, actual))
+        }
+        Condition::Flag(name, set) => {
+            let actual = registers.p.contains(flag(*name).unwrap_or_default());
+            (actual == *set, format!("{}={}", name, actual as u8))
+        }
+        Condition::Memory(address, bytes) => {
+            let actual: Vec<u8> = (0..bytes.len()).map(|offset| emulator.peek(address.wrapping_add(offset as u16))).collect();
+            (actual == *bytes, hex(&actual))
+        }
+        Condition::MaxCycles(limit) => (cycles <= *limit, format!("{} cycles", cycles)),
+    };
+    AssertionResult { assertion: condition.to_string(), passed, actual: if passed { String::new() } else { actual } }
+}
@@ -0,0 +1,85 @@
+use crate::state::Registers;
+
+// Incremental hash of the machine state, for telling when two runs that started
+// out the same have diverged: replays, lockstep sessions and fuzzers call it
+// every frame. It covers the registers, the cycles run and every byte written
+// since the baseline, not memory the CPU has only read. Memory is hashed per
+// 256 byte page and a page is only rehashed after a write to it, so a frame
+// that touches a few pages costs a few pages.
+//
+// Writes are mirrored into a shadow copy rather than read back, because
+// reading device registers can have side effects.
+
+const PAGES: usize = 256;
+
+// 64 bit FNV-1a.
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100_0000_01B3))
+}
+
+/// CRC-32 of `data`, as ZIP archives and ROM databases record it.
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| if crc & 1 != 0 { crc >> 1 ^ 0xEDB8_8320 } else { crc >> 1 })
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct StateHasher {
+    /// Last value written to each address.
+    shadow: Vec<u8>,
+    /// Pages written since the baseline, one bit each.
+    touched: [u64; PAGES / 64],
+    /// Pages written since their hash was last computed.
+    dirty: [u64; PAGES / 64],
+    pages: Vec<u64>,
+    start_clock: u64,
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self { shadow: vec![0; 0x10000], touched: [0; PAGES / 64], dirty: [0; PAGES / 64], pages: vec![0; PAGES], start_clock: 0 }
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_write(&mut self, address: u16, value: u8) {
+        self.shadow[address as usize] = value;
+        let page = (address >> 8) as usize;
+        self.touched[page / 64] |= 1 << (page % 64);
+        self.dirty[page / 64] |= 1 << (page % 64);
+    }
+
+    /// Forgets everything written so far and counts cycles from `clock`.
+    pub fn rebase(&mut self, clock: u64) {
+        self.shadow.fill(0);
+        self.touched = [0; PAGES / 64];
+        self.dirty = [0; PAGES / 64];
+        self.start_clock = clock;
+    }
+
+    pub fn hash(&mut self, registers: &Registers, clock: u64) -> u64 {
+        let mut hash = fnv1a(FNV_OFFSET, &registers.pc.to_le_bytes());
+        hash = fnv1a(hash, &[registers.a, registers.x, registers.y, registers.s, registers.p.bits()]);
+        hash = fnv1a(hash, &clock.wrapping_sub(self.start_clock).to_le_bytes());
+        for page in 0..PAGES {
+            let bit = 1 << (page % 64);
+            if self.touched[page / 64] & bit == 0 {
+                continue;
+            }
+            if self.dirty[page / 64] & bit != 0 {
+                self.pages[page] = fnv1a(FNV_OFFSET, &self.shadow[page << 8..(page + 1) << 8]);
+                self.dirty[page / 64] &= !bit;
+            }
+            hash = fnv1a(hash, &[page as u8]);
+            hash = fnv1a(hash, &self.pages[page].to_le_bytes());
+        }
+        hash
+    }
+}
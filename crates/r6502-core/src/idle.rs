@@ -0,0 +1,62 @@
+// Busy-wait detection. Programs without WAI wait for vblank or an interrupt by
+// spinning over a few instructions, polling a register and writing nothing,
+// and emulating that flat out burns a host core for no visible change.
+// `IdleWatch` notices such a spin so a frontend can sleep or skip host work
+// until the program does something again.
+//
+// The processor is idle while every instruction it runs starts within
+// `window` bytes of the others and none of them writes to memory, pushes
+// included, so an interrupt being taken ends the spin.
+
+/// A spin loop the processor is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Idle {
+    /// Lowest and highest address of the instructions in the loop so far.
+    pub start: u16,
+    pub end: u16,
+    /// Cycles spent in the loop so far.
+    pub cycles: u64,
+}
+
+/// Watches for the processor spending more than `cycles` cycles in a loop of
+/// instructions starting within `window` bytes of each other without writing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdleWatch {
+    pub window: u16,
+    pub cycles: u64,
+    // Lowest and highest address and the clock the current spin started at.
+    spin: Option<(u16, u16, u64)>,
+    // Idle cycles already reported for the current spin.
+    reported: u64,
+}
+
+impl IdleWatch {
+    pub fn new(window: u16, cycles: u64) -> Self {
+        Self { window, cycles, spin: None, reported: 0 }
+    }
+
+    /// Records the instruction at `address` that ran from clock `started` to
+    /// `clock`, and whether it wrote. Returns the loop every time another
+    /// `cycles` have been spent in it.
+    pub fn record(&mut self, address: u16, wrote: bool, started: u64, clock: u64) -> Option<Idle> {
+        if wrote {
+            self.spin = None;
+            return None;
+        }
+        let (start, end, since) = match self.spin {
+            Some((start, end, since)) if end.max(address) - start.min(address) < self.window => {
+                (start.min(address), end.max(address), since)
+            }
+            _ => {
+                self.reported = 0;
+                (address, address, started)
+            }
+        };
+        self.spin = Some((start, end, since));
+        let cycles = clock - since;
+        (cycles > self.reported + self.cycles).then(|| {
+            self.reported = cycles;
+            Idle { start, end, cycles }
+        })
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use bitflags::bitflags;
+
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+use crate::error::{R6502Error, Result};
+
+// Frame exact input scripts, for replaying a gameplay sequence the same way
+// every time. A script lists the frames on which the controllers change, one
+// line per change with a column per player:
+//
+//     # frame  player 1      player 2
+//     0        .
+//     60       start
+//     62       .
+//     200      right a     | left
+//
+// Frames are decimal and counted from the start of playback. Buttons are held
+// until the next line, `.` (or nothing) means none, and missing players are
+// released. `#` starts a comment. Start playback from a save state to make the
+// whole run reproducible.
+
+/// Number of controllers a script drives.
+pub const PLAYERS: usize = 2;
+
+bitflags! {
+    /// Controller buttons, in NES report order. The 2600 joystick's fire button is `A`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Buttons: u8 {
+        const A = 0x01;
+        const B = 0x02;
+        const SELECT = 0x04;
+        const START = 0x08;
+        const UP = 0x10;
+        const DOWN = 0x20;
+        const LEFT = 0x40;
+        const RIGHT = 0x80;
+    }
+}
+
+/// Names for each button as written in scripts, in bit order.
+const BUTTON_NAMES: [(&str, Buttons); 8] = [
+    ("a", Buttons::A),
+    ("b", Buttons::B),
+    ("select", Buttons::SELECT),
+    ("start", Buttons::START),
+    ("up", Buttons::UP),
+    ("down", Buttons::DOWN),
+    ("left", Buttons::LEFT),
+    ("right", Buttons::RIGHT),
+];
+
+impl std::fmt::Display for Buttons {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, ".");
+        }
+        let names: Vec<&str> = BUTTON_NAMES.iter().filter(|(_, button)| self.contains(*button)).map(|(name, _)| *name).collect();
+        write!(f, "{}", names.join(" "))
+    }
+}
+
+impl std::str::FromStr for Buttons {
+    type Err = R6502Error;
+
+    // `fire` is accepted for A.
+    fn from_str(text: &str) -> Result<Self> {
+        let mut buttons = Self::empty();
+        for name in text.split(|c: char| c.is_whitespace() || c == '+').filter(|name| !name.is_empty() && *name != ".") {
+            let name = name.to_ascii_lowercase();
+            buttons |= match BUTTON_NAMES.iter().find(|(candidate, _)| *candidate == name) {
+                Some((_, button)) => *button,
+                None if name == "fire" => Self::A,
+                None => return Err(R6502Error::BadInput(format!("Unknown button {}", name))),
+            };
+        }
+        Ok(buttons)
+    }
+}
+
+/// Hardware the controllers are wired to.
+pub trait Controllers {
+    fn set_controllers(&mut self, buttons: &[Buttons; PLAYERS]);
+}
+
+/// Controller states keyed by the frame they start on, see the module comment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputScript {
+    changes: BTreeMap<u64, [Buttons; PLAYERS]>,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Holds `buttons` from `frame` until the next change.
+    pub fn set(&mut self, frame: u64, buttons: [Buttons; PLAYERS]) {
+        self.changes.insert(frame, buttons);
+    }
+
+    /// Buttons held during `frame`.
+    pub fn at(&self, frame: u64) -> [Buttons; PLAYERS] {
+        self.changes.range(..=frame).next_back().map(|(_, buttons)| *buttons).unwrap_or_default()
+    }
+
+    /// Frames on which the input changes, with the new state.
+    pub fn changes(&self) -> impl Iterator<Item = (u64, &[Buttons; PLAYERS])> {
+        self.changes.iter().map(|(frame, buttons)| (*frame, buttons))
+    }
+
+    /// Frame of the last change, after which the input stays the same.
+    pub fn last_frame(&self) -> Option<u64> {
+        self.changes.keys().next_back().copied()
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut script = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bad = |reason: String| R6502Error::BadInput(format!("Line {}: {}", index + 1, reason));
+            let (frame, columns) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let frame: u64 = frame.parse().map_err(|_| bad(format!("Bad frame number {}", frame)))?;
+            let columns: Vec<&str> = columns.split('|').collect();
+            if columns.len() > PLAYERS {
+                return Err(bad(format!("More than {} players", PLAYERS)));
+            }
+            let mut buttons = [Buttons::empty(); PLAYERS];
+            for (player, column) in columns.iter().enumerate() {
+                buttons[player] = column.parse().map_err(|error: R6502Error| bad(error.to_string()))?;
+            }
+            script.set(frame, buttons);
+        }
+        Ok(script)
+    }
+}
+
+impl std::fmt::Display for InputScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (frame, buttons) in self.changes.iter() {
+            let columns: Vec<String> = buttons.iter().map(|held| held.to_string()).collect();
+            writeln!(f, "{:<8} {}", frame, columns.join(" | "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for InputScript {
+    type Err = R6502Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        Self::parse(text)
+    }
+}
+
+/// Feeds a script to the controllers a frame at a time.
+#[derive(Debug, Clone)]
+pub struct ScriptPlayer {
+    script: InputScript,
+    frame: u64,
+}
+
+impl ScriptPlayer {
+    pub fn new(script: InputScript) -> Self {
+        Self { script, frame: 0 }
+    }
+
+    /// Frames played so far, which is also the next frame to play.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn script(&self) -> &InputScript {
+        &self.script
+    }
+
+    /// Whether every change in the script has been applied.
+    pub fn is_finished(&self) -> bool {
+        self.script.last_frame().is_none_or(|last| self.frame > last)
+    }
+
+    /// Sets the controllers for the next frame and runs it. The frame only
+    /// counts as played if it ran to the end.
+    pub fn play_frame<M: VirtualMemory + Controllers>(&mut self, emulator: &mut CPUEmulator<M>) -> Result<StopReason> {
+        let buttons = self.script.at(self.frame);
+        emulator.with_memory(|memory| memory.set_controllers(&buttons));
+        let reason = emulator.step_frame()?;
+        if reason == StopReason::Stepped {
+            self.frame += 1;
+        }
+        Ok(reason)
+    }
+
+    /// Plays frames until the script is done or the emulator stops for some other reason.
+    pub fn play<M: VirtualMemory + Controllers>(&mut self, emulator: &mut CPUEmulator<M>) -> Result<StopReason> {
+        while !self.is_finished() {
+            match self.play_frame(emulator)? {
+                StopReason::Stepped => (),
+                reason => return Ok(reason),
+            }
+        }
+        Ok(StopReason::Stepped)
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::atomic::{fence, AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::state::{Registers, SystemFlags};
+
+// Read-only views of a running emulator for frontends on other threads. The
+// emulator publishes its registers, clock and raster position into atomics after
+// every instruction, so polling never waits on the emulation thread and the
+// emulator doesn't have to be shared behind a Mutex. Publishing is a sequence
+// lock: readers retry rather than see values from two different instructions.
+
+const NO_RASTER: u64 = u64::MAX;
+
+#[derive(Debug, Default)]
+pub(crate) struct Published {
+    // Odd while a publish is in progress.
+    sequence: AtomicU64,
+    registers: AtomicU64,
+    clock: AtomicU64,
+    raster: AtomicU64,
+    running: AtomicBool,
+}
+
+fn pack(registers: &Registers) -> u64 {
+    (registers.pc as u64) << 40
+        | (registers.a as u64) << 32
+        | (registers.x as u64) << 24
+        | (registers.y as u64) << 16
+        | (registers.s as u64) << 8
+        | registers.p.bits() as u64
+}
+
+fn unpack(packed: u64) -> Registers {
+    Registers {
+        pc: (packed >> 40) as u16,
+        a: (packed >> 32) as u8,
+        x: (packed >> 24) as u8,
+        y: (packed >> 16) as u8,
+        s: (packed >> 8) as u8,
+        p: SystemFlags::from_bits_retain(packed as u8),
+    }
+}
+
+impl Published {
+    // Only the emulator thread publishes.
+    pub(crate) fn publish(&self, registers: &Registers, clock: u64, raster: Option<(u64, u16)>, running: bool) {
+        let sequence = self.sequence.load(Ordering::Relaxed);
+        self.sequence.store(sequence.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.registers.store(pack(registers), Ordering::Relaxed);
+        self.clock.store(clock, Ordering::Relaxed);
+        let raster = raster.map_or(NO_RASTER, |(frame, scanline)| frame << 16 | scanline as u64);
+        self.raster.store(raster, Ordering::Relaxed);
+        self.running.store(running, Ordering::Relaxed);
+        self.sequence.store(sequence.wrapping_add(2), Ordering::Release);
+    }
+}
+
+/// What an [`InspectHandle`] saw, all from the same point between instructions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Inspection {
+    pub registers: Registers,
+    pub clock: u64,
+    /// (frame, scanline) of the video device, if there is one.
+    pub raster: Option<(u64, u16)>,
+    pub running: bool,
+}
+
+/// Cheap to clone and poll from any thread, see [`crate::emulator::CPUEmulator::inspect_handle`].
+#[derive(Debug, Clone)]
+pub struct InspectHandle {
+    published: Arc<Published>,
+}
+
+impl InspectHandle {
+    pub(crate) fn new(published: Arc<Published>) -> Self {
+        Self { published }
+    }
+
+    /// The state as of the last instruction the emulator finished.
+    pub fn inspect(&self) -> Inspection {
+        let published = &self.published;
+        loop {
+            let sequence = published.sequence.load(Ordering::Acquire);
+            if sequence % 2 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+            let inspection = Inspection {
+                registers: unpack(published.registers.load(Ordering::Relaxed)),
+                clock: published.clock.load(Ordering::Relaxed),
+                raster: match published.raster.load(Ordering::Relaxed) {
+                    NO_RASTER => None,
+                    raster => Some((raster >> 16, raster as u16)),
+                },
+                running: published.running.load(Ordering::Relaxed),
+            };
+            fence(Ordering::Acquire);
+            if published.sequence.load(Ordering::Relaxed) == sequence {
+                return inspection;
+            }
+        }
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.inspect().registers
+    }
+
+    pub fn clock(&self) -> u64 {
+        self.inspect().clock
+    }
+
+    /// Whether the emulator still exists to publish anything new.
+    pub fn is_attached(&self) -> bool {
+        Arc::strong_count(&self.published) > 1
+    }
+}
@@ -0,0 +1,2896 @@
+
+use crate::{diagnostics::AnomalyKind, emulator::{CPUEmulator, DefaultVirtualMemory, VirtualMemory}, state::{EmulatorError, SystemFlags, SystemState}, vectors::Vector};
+use crate::error::Result;
+use std::marker::PhantomData;
+
+use strum_macros::{Display, EnumIter, EnumString, IntoStaticStr};
+const DECIMAL_MODE_TABLE: [u8; 100] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 32, 33, 34, 35, 36, 37, 38,
+    39, 40, 41, 48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 64, 65, 66, 67, 68, 69, 70, 71, 72, 73, 80,
+    81, 82, 83, 84, 85, 86, 87, 88, 89, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 112, 113,
+    114, 115, 116, 117, 118, 119, 120, 121, 128, 129, 130, 131, 132, 133, 134, 135, 136, 137, 144,
+    145, 146, 147, 148, 149, 150, 151, 152, 153,
+];
+
+trait Decimal {
+    fn as_bcd(&self) -> u8;
+    fn as_dec(&self) -> u8;
+}
+
+impl Decimal for u8 {
+
+    // New strategy
+    // Lookup table 256 * 256 wide
+    // fill in sane defaults and use bitmatching to determine closest
+    // update values as we come to understand them better.
+    fn as_bcd(&self) -> u8 {
+        let pair = DECIMAL_MODE_TABLE.iter().enumerate().find(|(_, bcd)| *bcd == self);
+        match pair {
+            Some((dec, _)) => dec as u8,
+            None => {
+                // todo: fix
+                // println!("Value {} (hex {:#02x}) is outside of DECIMAL_MODE_TABLE", self, self);
+                return 0;
+            }
+        }
+    }
+    fn as_dec(&self) -> u8 {
+        let pair = DECIMAL_MODE_TABLE.iter().enumerate().find(|(dec, _)| ((*dec) as u8) == *self);
+        match pair {
+            Some((_,bcd)) => *bcd,
+            None => {
+                // todo: fix
+                // println!("Value {} (hex {:#02x}) is outside of DECIMAL_MODE_TABLE", self, self);
+                return 0;
+            }
+        }
+    }
+}
+
+/// Displayed as the conventional assembler shorthand, e.g. `zp,X` or `(zp),Y`. Parsing
+/// also accepts the variant name, ignoring case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString, IntoStaticStr)]
+#[strum(ascii_case_insensitive)]
+pub enum AddressingMode {
+    #[strum(to_string = "impl", serialize = "Implied")]
+    Implied,
+    #[strum(to_string = "A", serialize = "Accumulator")]
+    Accumulator,
+    #[strum(to_string = "#", serialize = "Immediate")]
+    Immediate,
+    #[strum(to_string = "abs", serialize = "DirectAbsolute")]
+    DirectAbsolute,
+    #[strum(to_string = "abs,X", serialize = "DirectAbsoluteX")]
+    DirectAbsoluteX,
+    #[strum(to_string = "abs,Y", serialize = "DirectAbsoluteY")]
+    DirectAbsoluteY,
+    #[strum(to_string = "(abs)", serialize = "IndirectAbsolute")]
+    IndirectAbsolute,
+    #[strum(to_string = "zp", serialize = "DirectZeroPage")]
+    DirectZeroPage,
+    #[strum(to_string = "zp,X", serialize = "DirectZeroPageX")]
+    DirectZeroPageX,
+    #[strum(to_string = "zp,Y", serialize = "DirectZeroPageY")]
+    DirectZeroPageY,
+    #[strum(to_string = "(zp,X)", serialize = "IndirectZeroPageX")]
+    IndirectZeroPageX,
+    #[strum(to_string = "(zp),Y", serialize = "IndirectZeroPageY")]
+    IndirectZeroPageY,
+    #[strum(to_string = "rel", serialize = "Relative")]
+    Relative,
+}
+
+impl AddressingMode {
+    /// Number of operand bytes following the opcode.
+    pub fn operand_length(&self) -> u16 {
+        match self {
+            Self::Implied | Self::Accumulator => 0,
+            Self::Immediate
+            | Self::DirectZeroPage
+            | Self::DirectZeroPageX
+            | Self::DirectZeroPageY
+            | Self::IndirectZeroPageX
+            | Self::IndirectZeroPageY
+            | Self::Relative => 1,
+            Self::DirectAbsolute
+            | Self::DirectAbsoluteX
+            | Self::DirectAbsoluteY
+            | Self::IndirectAbsolute => 2,
+        }
+    }
+
+    /// Conventional assembler shorthand, e.g. `zp,X` or `(zp),Y`.
+    pub fn short_name(&self) -> &'static str {
+        self.into()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: OpCode,
+    pub mode: Option<AddressingMode>,
+}
+
+/// Displayed and parsed as the mnemonic, ignoring case when parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, Display, EnumString)]
+#[strum(ascii_case_insensitive)]
+pub enum OpCode {
+    ORA,
+    AND,
+    EOR,
+    ADC,
+    STA,
+    LDA,
+    CMP,
+    SBC,
+    ASL,
+    ROL,
+    LSR,
+    ROR,
+    STX,
+    LDX,
+    DEC,
+    INC,
+    BIT, // 001
+    JMP, // 010
+    JSR,
+    STY,         // 100
+    LDY,         // 101
+    CPY,         // 110
+    CPX,         // 111
+    // Special bytes
+    BPL,
+    BMI,
+    BVC,
+    BVS,
+    BCC,
+    BCS,
+    BNE,
+    BEQ,
+    PHP,
+    PLP,
+    PHA,
+    PLA,
+    DEY,
+    TAY,
+    INY,
+    INX,
+    CLC,
+    SEC,
+    CLI,
+    SEI,
+    TYA,
+    CLV,
+    CLD,
+    SED,
+    TXA,
+    TXS,
+    TAX,
+    TSX,
+    DEX,
+    NOP,
+    BRK,
+    RTI,
+    RTS,
+    BadInstruction,
+    UnknownInstruction,
+    // Handled by a user supplied CPUEmulator::override_opcode handler.
+    Override,
+    // Illegal Opcode Callout.
+    // https://www.masswerk.at/nowgobang/2021/6502-illegal-opcodes
+    // https://www.oxyron.de/html/opcodes02.html
+    ALR,
+    ANC,
+    ANC2,
+    ANE,
+    ARR,
+    DCP,
+    ISC,
+    LAS,
+    LAX,
+    LXA,
+    RLA,
+    RRA,
+    SAX,
+    SBX,
+    SHA,
+    SHX,
+    SHY,
+    SLO,
+    SRE,
+    TAS,
+    USBC,
+    INOP,
+    KIL,
+}
+
+impl OpCode {
+    /// Whether [`Instruction::execute`] has a handler for the opcode.
+    pub const fn is_implemented(&self) -> bool {
+        handler::<DefaultVirtualMemory>(*self).is_some()
+    }
+}
+
+/// Every opcode byte decoded ahead of time, so the executor doesn't redo the
+/// bit twiddling in [`decode`] for each instruction.
+pub const DECODE_TABLE: [Instruction; 256] = {
+    let mut table = [Instruction { opcode: OpCode::UnknownInstruction, mode: None }; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        table[byte] = decode(byte as u8);
+        byte += 1;
+    }
+    table
+};
+
+/// Opcode bytes the executor rejects, with what they decode to.
+pub fn unimplemented_opcodes() -> Vec<(u8, OpCode)> {
+    DECODE_TABLE
+        .iter()
+        .enumerate()
+        .filter(|(_, instruction)| !instruction.opcode.is_implemented())
+        .map(|(byte, instruction)| (byte as u8, instruction.opcode))
+        .collect()
+}
+
+impl From<u8> for Instruction {
+    fn from(value: u8) -> Self {
+        DECODE_TABLE[value as usize]
+    }
+}
+
+/// Decodes an opcode byte from its bit fields. [`DECODE_TABLE`] holds the result for
+/// every byte.
+pub const fn decode(value: u8) -> Instruction {
+    let group_bits = value & 0b11;
+    let instruction_bits = (0b11100000 & value) >> 5;
+    let mode_bits = (0b00011100 & value) >> 2;
+    // Illegal Opcode Callout.
+    // https://www.masswerk.at/nowgobang/2021/6502-illegal-opcodes
+    // https://www.oxyron.de/html/opcodes02.html
+    //
+    match value {
+        0x4b => {
+            return Instruction {
+                opcode: OpCode::ALR,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x0b => {
+            return Instruction {
+                opcode: OpCode::ANC,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x2b => {
+            return Instruction {
+                opcode: OpCode::ANC2,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x8b => {
+            return Instruction {
+                opcode: OpCode::ANE,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x6b => {
+            return Instruction {
+                opcode: OpCode::ARR,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0xc7 => {
+            return Instruction {
+                opcode: OpCode::DCP,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0xd7 => {
+            return Instruction {
+                opcode: OpCode::DCP,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0xcf => {
+            return Instruction {
+                opcode: OpCode::DCP,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0xdf => {
+            return Instruction {
+                opcode: OpCode::DCP,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0xdb => {
+            return Instruction {
+                opcode: OpCode::DCP,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0xc3 => {
+            return Instruction {
+                opcode: OpCode::DCP,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0xd3 => {
+            return Instruction {
+                opcode: OpCode::DCP,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0xe7 => {
+            return Instruction {
+                opcode: OpCode::ISC,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0xf7 => {
+            return Instruction {
+                opcode: OpCode::ISC,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0xef => {
+            return Instruction {
+                opcode: OpCode::ISC,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0xff => {
+            return Instruction {
+                opcode: OpCode::ISC,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0xfb => {
+            return Instruction {
+                opcode: OpCode::ISC,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0xe3 => {
+            return Instruction {
+                opcode: OpCode::ISC,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0xf3 => {
+            return Instruction {
+                opcode: OpCode::ISC,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0xbb => {
+            return Instruction {
+                opcode: OpCode::LAS,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0xa7 => {
+            return Instruction {
+                opcode: OpCode::LAX,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0xb7 => {
+            return Instruction {
+                opcode: OpCode::LAX,
+                mode: Some(AddressingMode::DirectZeroPageY),
+            }
+        }
+        0xaf => {
+            return Instruction {
+                opcode: OpCode::LAX,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0xbf => {
+            return Instruction {
+                opcode: OpCode::LAX,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0xa3 => {
+            return Instruction {
+                opcode: OpCode::LAX,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0xb3 => {
+            return Instruction {
+                opcode: OpCode::LAX,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0xab => {
+            return Instruction {
+                opcode: OpCode::LXA,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x27 => {
+            return Instruction {
+                opcode: OpCode::RLA,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x37 => {
+            return Instruction {
+                opcode: OpCode::RLA,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x2f => {
+            return Instruction {
+                opcode: OpCode::RLA,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0x3f => {
+            return Instruction {
+                opcode: OpCode::RLA,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x3b => {
+            return Instruction {
+                opcode: OpCode::RLA,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0x23 => {
+            return Instruction {
+                opcode: OpCode::RLA,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0x33 => {
+            return Instruction {
+                opcode: OpCode::RLA,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0x67 => {
+            return Instruction {
+                opcode: OpCode::RRA,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x77 => {
+            return Instruction {
+                opcode: OpCode::RRA,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x6f => {
+            return Instruction {
+                opcode: OpCode::RRA,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0x7f => {
+            return Instruction {
+                opcode: OpCode::RRA,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x7b => {
+            return Instruction {
+                opcode: OpCode::RRA,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0x63 => {
+            return Instruction {
+                opcode: OpCode::RRA,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0x73 => {
+            return Instruction {
+                opcode: OpCode::RRA,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0x87 => {
+            return Instruction {
+                opcode: OpCode::SAX,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x97 => {
+            return Instruction {
+                opcode: OpCode::SAX,
+                mode: Some(AddressingMode::DirectZeroPageY),
+            }
+        }
+        0x8f => {
+            return Instruction {
+                opcode: OpCode::SAX,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0x83 => {
+            return Instruction {
+                opcode: OpCode::SAX,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0xcb => {
+            return Instruction {
+                opcode: OpCode::SBX,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x9f => {
+            return Instruction {
+                opcode: OpCode::SHA,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0x93 => {
+            return Instruction {
+                opcode: OpCode::SHA,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0x9e => {
+            return Instruction {
+                opcode: OpCode::SHX,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0x9c => {
+            return Instruction {
+                opcode: OpCode::SHY,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x07 => {
+            return Instruction {
+                opcode: OpCode::SLO,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x17 => {
+            return Instruction {
+                opcode: OpCode::SLO,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x0f => {
+            return Instruction {
+                opcode: OpCode::SLO,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0x1f => {
+            return Instruction {
+                opcode: OpCode::SLO,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x1b => {
+            return Instruction {
+                opcode: OpCode::SLO,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0x03 => {
+            return Instruction {
+                opcode: OpCode::SLO,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0x13 => {
+            return Instruction {
+                opcode: OpCode::SLO,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0x47 => {
+            return Instruction {
+                opcode: OpCode::SRE,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x57 => {
+            return Instruction {
+                opcode: OpCode::SRE,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x4f => {
+            return Instruction {
+                opcode: OpCode::SRE,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0x5f => {
+            return Instruction {
+                opcode: OpCode::SRE,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x5b => {
+            return Instruction {
+                opcode: OpCode::SRE,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0x43 => {
+            return Instruction {
+                opcode: OpCode::SRE,
+                mode: Some(AddressingMode::IndirectZeroPageX),
+            }
+        }
+        0x53 => {
+            return Instruction {
+                opcode: OpCode::SRE,
+                mode: Some(AddressingMode::IndirectZeroPageY),
+            }
+        }
+        0x9b => {
+            return Instruction {
+                opcode: OpCode::TAS,
+                mode: Some(AddressingMode::DirectAbsoluteY),
+            }
+        }
+        0xeb => {
+            return Instruction {
+                opcode: OpCode::USBC,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x1a => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Implied),
+            }
+        }
+        0x3a => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Implied),
+            }
+        }
+        0x5a => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Implied),
+            }
+        }
+        0x7a => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Implied),
+            }
+        }
+        0xda => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Implied),
+            }
+        }
+        0xfa => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Implied),
+            }
+        }
+        0x80 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x82 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x89 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0xc2 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0xe2 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::Immediate),
+            }
+        }
+        0x04 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x44 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x64 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPage),
+            }
+        }
+        0x14 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x34 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x54 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x74 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0xd4 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0xf4 => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectZeroPageX),
+            }
+        }
+        0x0c => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        0x1c => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x3c => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x5c => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x7c => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0xdc => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0xfc => {
+            return Instruction {
+                opcode: OpCode::INOP,
+                mode: Some(AddressingMode::DirectAbsoluteX),
+            }
+        }
+        0x02 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x12 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x22 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x32 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x42 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x52 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x62 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x72 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0x92 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0xb2 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0xd2 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        0xf2 => {
+            return Instruction {
+                opcode: OpCode::KIL,
+                mode: None,
+            }
+        }
+        _ => (),
+    }
+
+    // Single byte and special multibyte carveout as an exception
+    match value {
+        // $6C is JMP (absolute indirect)
+        0x6C => {
+            return Instruction {
+                opcode: OpCode::JMP,
+                mode: Some(AddressingMode::IndirectAbsolute)
+            }
+        }
+        // $60 is RTS
+        0x60 => {
+            return Instruction {
+                opcode: OpCode::RTS,
+                mode: Some(AddressingMode::Implied)
+            }
+        }
+        // $40 is RTI
+        0x40 => {
+            return Instruction {
+                opcode: OpCode::RTI,
+                mode: Some(AddressingMode::Implied)
+            }
+        }
+        // https://llx.com/Neil/a2/opcodes.html
+        // Note that bbb = 100 and 110 are missing. Also, with STX and LDX, "zero page,X" addressing becomes "zero page,Y", and with LDX, "absolute,X" becomes "absolute,Y". 
+        0x96 => {
+            return Instruction {
+                opcode: OpCode::STX,
+                mode: Some(AddressingMode::DirectZeroPageY)
+            }
+        }
+        0xB6 => {
+            return Instruction {
+                opcode: OpCode::LDX,
+                mode: Some(AddressingMode::DirectZeroPageY)
+            }
+        }
+        0xBE => {
+            return Instruction {
+                opcode: OpCode::LDX,
+                mode: Some(AddressingMode::DirectAbsoluteY)
+            }
+        }
+        // BIT immediate should be JSR ABS for some reason
+        0x20 => {
+            return Instruction {
+                opcode: OpCode::JSR,
+                mode: Some(AddressingMode::DirectAbsolute),
+            }
+        }
+        // https://www.masswerk.at/6502/6502_instruction_set.html
+        0x00 => {
+            return Instruction {
+                opcode: OpCode::BRK,
+                mode: Some(AddressingMode::Implied),
+            }
+        }
+        0x08 => {
+            return Instruction {
+                opcode: OpCode::PHP,
+                mode: None,
+            }
+        }
+        0x28 => {
+            return Instruction {
+                opcode: OpCode::PLP,
+                mode: None,
+            }
+        }
+        0x48 => {
+            return Instruction {
+                opcode: OpCode::PHA,
+                mode: None,
+            }
+        }
+        0x68 => {
+            return Instruction {
+                opcode: OpCode::PLA,
+                mode: None,
+            }
+        }
+        0x88 => {
+            return Instruction {
+                opcode: OpCode::DEY,
+                mode: None,
+            }
+        }
+        0xA8 => {
+            return Instruction {
+                opcode: OpCode::TAY,
+                mode: None,
+            }
+        }
+        0xC8 => {
+            return Instruction {
+                opcode: OpCode::INY,
+                mode: None,
+            }
+        }
+        0xE8 => {
+            return Instruction {
+                opcode: OpCode::INX,
+                mode: None,
+            }
+        }
+        0x18 => {
+            return Instruction {
+                opcode: OpCode::CLC,
+                mode: None,
+            }
+        }
+        0x38 => {
+            return Instruction {
+                opcode: OpCode::SEC,
+                mode: None,
+            }
+        }
+        0x58 => {
+            return Instruction {
+                opcode: OpCode::CLI,
+                mode: None,
+            }
+        }
+        0x78 => {
+            return Instruction {
+                opcode: OpCode::SEI,
+                mode: None,
+            }
+        }
+        0x98 => {
+            return Instruction {
+                opcode: OpCode::TYA,
+                mode: None,
+            }
+        }
+        0xB8 => {
+            return Instruction {
+                opcode: OpCode::CLV,
+                mode: None,
+            }
+        }
+        0xD8 => {
+            return Instruction {
+                opcode: OpCode::CLD,
+                mode: None,
+            }
+        }
+        0xF8 => {
+            return Instruction {
+                opcode: OpCode::SED,
+                mode: None,
+            }
+        }
+        0x8A => {
+            return Instruction {
+                opcode: OpCode::TXA,
+                mode: None,
+            }
+        }
+        0x9A => {
+            return Instruction {
+                opcode: OpCode::TXS,
+                mode: None,
+            }
+        }
+        0xAA => {
+            return Instruction {
+                opcode: OpCode::TAX,
+                mode: None,
+            }
+        }
+        0xBA => {
+            return Instruction {
+                opcode: OpCode::TSX,
+                mode: None,
+            }
+        }
+        0xCA => {
+            return Instruction {
+                opcode: OpCode::DEX,
+                mode: None,
+            }
+        }
+        0xEA => {
+            return Instruction {
+                opcode: OpCode::NOP,
+                mode: None,
+            }
+        }
+        0x10 => {
+            return Instruction {
+                opcode: OpCode::BPL,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        0x30 => {
+            return Instruction {
+                opcode: OpCode::BMI,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        0x50 => {
+            return Instruction {
+                opcode: OpCode::BVC,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        0x70 => {
+            return Instruction {
+                opcode: OpCode::BVS,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        0x90 => {
+            return Instruction {
+                opcode: OpCode::BCC,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        0xB0 => {
+            return Instruction {
+                opcode: OpCode::BCS,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        0xD0 => {
+            return Instruction {
+                opcode: OpCode::BNE,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        0xF0 => {
+            return Instruction {
+                opcode: OpCode::BEQ,
+                mode: Some(AddressingMode::Relative),
+            }
+        }
+        // note: resolved from official table. This isn't mapped onto reality though.
+        0x80 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x02 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x12 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x22 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x32 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x42 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x52 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x62 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x72 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x82 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x92 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xb2 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xc2 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xd2 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xe2 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xf2 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x03 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x13 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x23 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x33 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x43 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x53 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x63 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x73 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x83 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x93 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xa3 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xb3 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xc3 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xd3 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xe3 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xf3 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x04 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x14 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x34 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x44 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x54 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x64 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x74 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xd4 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xf4 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x07 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x17 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x27 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x37 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x47 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x57 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x67 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x77 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x87 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x97 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xa7 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xb7 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xc7 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xd7 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xe7 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xf7 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x89 => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x1a => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x3a => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x5a => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x7a => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xda => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xfa => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x0b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x1b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x2b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x3b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x4b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x5b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x6b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x7b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x8b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x9b => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xab => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xbb => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xcb => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xdb => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xeb => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xfb => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x0c => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x1c => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x3c => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x5c => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x7c => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x9c => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xdc => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xfc => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x0f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x1f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x2f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x3f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x4f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x5f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x6f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x7f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x8f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0x9f => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xaf => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xbf => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xcf => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xdf => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xef => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        0xff => {
+            return Instruction {
+                opcode: OpCode::BadInstruction,
+                mode: None,
+            }
+        }
+        _ => (),
+    };
+
+    match group_bits {
+        0b01 => {
+            let instruction = match instruction_bits {
+                0b000 => OpCode::ORA,
+                0b001 => OpCode::AND,
+                0b010 => OpCode::EOR,
+                0b011 => OpCode::ADC,
+                0b100 => OpCode::STA,
+                0b101 => OpCode::LDA,
+                0b110 => OpCode::CMP,
+                0b111 => OpCode::SBC,
+                _ => {
+                    return Instruction {
+                        opcode: OpCode::UnknownInstruction,
+                        mode: None,
+                    }
+                }
+            };
+
+            let mode = match mode_bits {
+                0b000 => AddressingMode::IndirectZeroPageX,
+                0b001 => AddressingMode::DirectZeroPage,
+                0b010 => AddressingMode::Immediate,
+                0b011 => AddressingMode::DirectAbsolute,
+                0b100 => AddressingMode::IndirectZeroPageY,
+                0b101 => AddressingMode::DirectZeroPageX,
+                0b110 => AddressingMode::DirectAbsoluteY,
+                0b111 => AddressingMode::DirectAbsoluteX,
+                _ => {
+                    return Instruction {
+                        opcode: OpCode::UnknownInstruction,
+                        mode: None,
+                    }
+                }
+            };
+
+            Instruction {
+                opcode: instruction,
+                mode: Some(mode),
+            }
+        }
+        0b10 => {
+            let instruction = match instruction_bits {
+                0b000 => OpCode::ASL,
+                0b001 => OpCode::ROL,
+                0b010 => OpCode::LSR,
+                0b011 => OpCode::ROR,
+                0b100 => OpCode::STX,
+                0b101 => OpCode::LDX,
+                0b110 => OpCode::DEC,
+                0b111 => OpCode::INC,
+                _ => {
+                    return Instruction {
+                        opcode: OpCode::UnknownInstruction,
+                        mode: None,
+                    }
+                }
+            };
+
+            let mode = match mode_bits {
+                0b000 => AddressingMode::Immediate,
+                0b001 => AddressingMode::DirectZeroPage,
+                0b010 => AddressingMode::Accumulator,
+                0b011 => AddressingMode::DirectAbsolute,
+                0b101 => AddressingMode::DirectZeroPageX,
+                0b111 => AddressingMode::DirectAbsoluteX,
+                _ => {
+                    return Instruction {
+                        opcode: OpCode::UnknownInstruction,
+                        mode: None,
+                    }
+                }
+            };
+
+            Instruction {
+                opcode: instruction,
+                mode: Some(mode),
+            }
+        }
+        0b00 => {
+            let instruction = match instruction_bits {
+                0b001 => OpCode::BIT,
+                0b010 => OpCode::JMP,
+                0b100 => OpCode::STY,
+                0b101 => OpCode::LDY,
+                0b110 => OpCode::CPY,
+                0b111 => OpCode::CPX,
+                _ => {
+                    return Instruction {
+                        opcode: OpCode::UnknownInstruction,
+                        mode: None,
+                    }
+                }
+            };
+
+            let mode = match mode_bits {
+                0b000 => AddressingMode::Immediate,
+                0b001 => AddressingMode::DirectZeroPage,
+                0b011 => AddressingMode::DirectAbsolute,
+                0b101 => AddressingMode::DirectZeroPageX,
+                0b111 => AddressingMode::DirectAbsoluteX,
+                _ => {
+                    return Instruction {
+                        opcode: OpCode::UnknownInstruction,
+                        mode: None,
+                    }
+                }
+            };
+
+            Instruction {
+                opcode: instruction,
+                mode: Some(mode),
+            }
+        }
+        _ => Instruction {
+            opcode: OpCode::UnknownInstruction,
+            mode: None,
+        },
+    }
+}
+
+impl Instruction {
+    /// Total encoded length in bytes, including the opcode.
+    pub fn length(&self) -> u16 {
+        1 + self.mode.map(|mode| mode.operand_length()).unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Instruction {:?} ", self.opcode)?;
+        let mode = match &self.mode {
+            Some(mode) => match mode {
+                AddressingMode::Implied => "in Implied mode",
+                AddressingMode::Accumulator => "in Accumulator mode",
+                AddressingMode::Immediate => "in Immediate mode",
+                AddressingMode::DirectAbsolute => "in Absolute mode",
+                AddressingMode::IndirectAbsolute => "in Indirect Absolute mode",
+                AddressingMode::DirectAbsoluteX => "in Absolute X mode",
+                AddressingMode::DirectAbsoluteY => "in Absolute Y mode",
+                AddressingMode::DirectZeroPage => "in Zero Page mode",
+                AddressingMode::DirectZeroPageX => "in Zero Page X mode",
+                AddressingMode::DirectZeroPageY => "in Zero Page Y mode",
+                AddressingMode::IndirectZeroPageX => "in Indirect Zero Page X mode",
+                AddressingMode::IndirectZeroPageY => "in Indirect Zero Page Y mode",
+                AddressingMode::Relative => "in Relative mode",
+            },
+            None => "with no mode",
+        };
+        write!(f, "{}", mode)
+    }
+}
+
+// Decimal mode on NMOS parts produces well defined but meaningless results for
+// nibbles above 9, which is almost always a sign of uninitialised data.
+fn check_bcd_operands<M>(emulator: &mut CPUEmulator<M>, argument: u8)
+where M: VirtualMemory {
+    for value in [emulator.state.a, argument] {
+        if (value & 0xF) > 9 || (value >> 4) > 9 {
+            emulator.diagnostics.record(AnomalyKind::InvalidBcdDigit, emulator.instruction_pc(), None, value);
+        }
+    }
+}
+
+// Read-modify-write instructions write the unmodified value back while they
+// work out the result, then write the result. Registers that act on writes,
+// like TIA strobes or interrupt acknowledges, see both.
+fn write_modified<M>(emulator: &mut CPUEmulator<M>, address: u16, original: u8, modified: u8)
+where M: VirtualMemory {
+    emulator.write(address, original);
+    emulator.write(address, modified);
+}
+
+// Taken branches cost a cycle, two if the target is in another page. A taken
+// branch that stays in its page doesn't poll for interrupts on its last cycle,
+// so a pending interrupt waits for one more instruction.
+fn branch<M>(emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>, taken: bool) -> Result<()>
+where M: VirtualMemory {
+    let offset = memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?.value as i8;
+    if !taken {
+        return Ok(());
+    }
+    let from = emulator.state.pc;
+    let target = from.wrapping_add(offset as u16);
+    emulator.state.pc = target;
+    if (from ^ target) & 0xFF00 != 0 {
+        emulator.extra_cycles += 2;
+    } else {
+        emulator.extra_cycles += 1;
+        emulator.interrupt_delayed = true;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct MemoryPair {
+    pub address: u16,
+    pub value: u8,
+}
+
+impl Instruction {
+    // Stores and jumps only need the effective address, so they don't read it,
+    // which matters for registers with read side effects such as PPUDATA.
+    fn reads_operand(&self) -> bool {
+        !matches!(self.opcode, OpCode::STA | OpCode::STX | OpCode::STY | OpCode::SAX | OpCode::SHA | OpCode::SHX | OpCode::SHY | OpCode::TAS | OpCode::JMP | OpCode::JSR)
+    }
+
+    pub fn execute <'a, M>(&self, emulator: &mut CPUEmulator<M>)-> Result<()> 
+    where M: VirtualMemory {
+        let memory_pair = self.fetch_operand(emulator);
+        match Handlers::<M>::TABLE[self.opcode as usize] {
+            Some(handler) => handler(self.mode, emulator, memory_pair),
+            None => Err(EmulatorError::UnimplementedInstruction.into()),
+        }
+    }
+
+    /// Like [`Instruction::execute`], but finds the handler with a match on the
+    /// opcode every time, as the executor used to. Only there so
+    /// `benches/dispatch.rs` can compare the two.
+    #[cfg(feature = "bench")]
+    pub fn execute_matched<M>(&self, emulator: &mut CPUEmulator<M>) -> Result<()>
+    where M: VirtualMemory {
+        let memory_pair = self.fetch_operand(emulator);
+        match handler::<M>(self.opcode) {
+            Some(handler) => handler(self.mode, emulator, memory_pair),
+            None => Err(EmulatorError::UnimplementedInstruction.into()),
+        }
+    }
+
+    fn fetch_operand<M>(&self, emulator: &mut CPUEmulator<M>) -> Option<MemoryPair>
+    where M: VirtualMemory {
+        let reads = self.reads_operand();
+        match self.mode {
+            Some(AddressingMode::Immediate | AddressingMode::Relative) => {
+                let address = emulator.state.pc;
+                let value = if reads { emulator.read(address) } else { 0 };
+                emulator.state.pc = emulator.state.pc.wrapping_add(1);
+                Some(MemoryPair { address, value })
+            }
+            Some(AddressingMode::DirectZeroPage) => {
+                let address = emulator.state.pc;
+                let address = emulator.read(address) as u16;
+                emulator.state.pc = emulator.state.pc.wrapping_add(1);
+                let value = if reads { emulator.read(address) } else { 0 };
+                Some(MemoryPair { address, value })
+            }
+            Some(AddressingMode::DirectZeroPageX) => {
+                let address = emulator.state.pc;
+                let address = emulator.read(address).overflowing_add(emulator.state.x).0;
+                emulator.state.pc = emulator.state.pc.wrapping_add(1);
+                let value = if reads { emulator.read(address.into()) } else { 0 };
+                Some(MemoryPair {
+                    address: address.into(),
+                    value,
+                })
+            }
+            Some(AddressingMode::DirectZeroPageY) => {
+                let address = emulator.state.pc;
+                let address = emulator.read(address).overflowing_add(emulator.state.y).0;
+                emulator.state.pc = emulator.state.pc.wrapping_add(1);
+                let value = if reads { emulator.read(address.into()) } else { 0 };
+                Some(MemoryPair {
+                    address: address.into(),
+                    value,
+                })
+            }
+            Some(AddressingMode::DirectAbsolute) => {
+                // In absolute addressing, the second byte of the instruction specifies the eight low order bits of the effective address while the third byte specifies the eight high order bits. Thus, the absolute addressing mode allows access to the entire 65 K bytes of addressable memory.
+
+                let address = emulator.read_u16(emulator.state.pc);
+                emulator.state.pc = emulator.state.pc.wrapping_add(2);
+                let value = if reads { emulator.read(address) } else { 0 };
+                Some(MemoryPair { address, value })
+            }
+            Some(AddressingMode::IndirectAbsolute) => {
+                // In absolute addressing, the second byte of the instruction specifies the eight low order bits of the effective address while the third byte specifies the eight high order bits. Thus, the absolute addressing mode allows access to the entire 65 K bytes of addressable memory.
+
+                let address = emulator.read_u16(emulator.state.pc);
+                emulator.state.pc = emulator.state.pc.wrapping_add(2);
+                let value = if reads { emulator.read(address) } else { 0 };
+                Some(MemoryPair { address, value })
+            }
+            Some(AddressingMode::DirectAbsoluteX) => {
+                let base = emulator.read_u16(emulator.state.pc);
+                emulator.state.pc = emulator.state.pc.wrapping_add(2);
+                let address = base.wrapping_add(emulator.state.x.into());
+                emulator.page_crossed = (base ^ address) & 0xFF00 != 0;
+                let value = if reads { emulator.read(address) } else { 0 };
+                Some(MemoryPair { address, value })
+            }
+            Some(AddressingMode::DirectAbsoluteY) => {
+                let base = emulator.read_u16(emulator.state.pc);
+                emulator.state.pc = emulator.state.pc.wrapping_add(2);
+                let address = base.wrapping_add(emulator.state.y.into());
+                emulator.page_crossed = (base ^ address) & 0xFF00 != 0;
+                let value = if reads { emulator.read(address) } else { 0 };
+                Some(MemoryPair { address, value })
+            }
+            Some(AddressingMode::IndirectZeroPageX) => {
+                let pointer = emulator.read(emulator.state.pc).wrapping_add(emulator.state.x);
+                emulator.state.pc = emulator.state.pc.wrapping_add(1);
+                let address = emulator.read_u16_zp_wrapped(pointer);
+                let value = if reads { emulator.read(address) } else { 0 };
+                Some(MemoryPair { address, value })
+            }
+            Some(AddressingMode::Accumulator) | None | Some(AddressingMode::Implied) => None,
+            Some(AddressingMode::IndirectZeroPageY) => {
+                // In indirect indexed addressing, the second byte of the instruction points to a memory
+                //location in page zero. The contents of this memory location is added to the contents of
+                //the Y index register, the result being the low order eight bits of the effective address.
+                //The carry from this addition is added to the contents of the next page zero memory location,
+                //the result being the high order eight bits of the effective address.
+                let pointer = emulator.read(emulator.state.pc);
+                emulator.state.pc = emulator.state.pc.wrapping_add(1);
+                let base = emulator.read_u16_zp_wrapped(pointer);
+                let address = base.wrapping_add(emulator.state.y.into());
+                emulator.page_crossed = (base ^ address) & 0xFF00 != 0;
+                let value = if reads { emulator.read(address) } else { 0 };
+                Some(MemoryPair { address, value })
+            }
+        }
+    }
+}
+
+// One handler per opcode, so executing an instruction is a table lookup and an
+// indirect call rather than a match over every opcode. Handlers get the
+// addressing mode, for the instructions that also work on the accumulator, and
+// the operand fetched for them.
+
+type Handler<M> = fn(Option<AddressingMode>, &mut CPUEmulator<M>, Option<MemoryPair>) -> Result<()>;
+
+const OPCODES: usize = OpCode::KIL as usize + 1;
+
+/// The handler for `opcode`, or `None` if the executor doesn't implement it.
+/// [`Handlers::TABLE`] holds the result for every opcode, and
+/// [`OpCode::is_implemented`] is whether there is one.
+const fn handler<M>(opcode: OpCode) -> Option<Handler<M>>
+where M: VirtualMemory {
+    match opcode {
+        OpCode::ADC => Some(adc::<M>),
+        OpCode::AND => Some(and::<M>),
+        OpCode::ASL => Some(asl::<M>),
+        OpCode::BCC => Some(bcc::<M>),
+        OpCode::BCS => Some(bcs::<M>),
+        OpCode::BEQ => Some(beq::<M>),
+        OpCode::BIT => Some(bit::<M>),
+        OpCode::BMI => Some(bmi::<M>),
+        OpCode::BNE => Some(bne::<M>),
+        OpCode::BPL => Some(bpl::<M>),
+        OpCode::BRK => Some(brk::<M>),
+        OpCode::BVC => Some(bvc::<M>),
+        OpCode::BVS => Some(bvs::<M>),
+        OpCode::CLC => Some(clc::<M>),
+        OpCode::CLD => Some(cld::<M>),
+        OpCode::CLI => Some(cli::<M>),
+        OpCode::CLV => Some(clv::<M>),
+        OpCode::CMP => Some(cmp::<M>),
+        OpCode::CPX => Some(cpx::<M>),
+        OpCode::CPY => Some(cpy::<M>),
+        OpCode::DEC => Some(dec::<M>),
+        OpCode::DEX => Some(dex::<M>),
+        OpCode::DEY => Some(dey::<M>),
+        OpCode::EOR => Some(eor::<M>),
+        OpCode::INC => Some(inc::<M>),
+        OpCode::INX => Some(inx::<M>),
+        OpCode::INY => Some(iny::<M>),
+        OpCode::JMP => Some(jmp::<M>),
+        OpCode::JSR => Some(jsr::<M>),
+        OpCode::LDA => Some(lda::<M>),
+        OpCode::LDX => Some(ldx::<M>),
+        OpCode::LDY => Some(ldy::<M>),
+        OpCode::LSR => Some(lsr::<M>),
+        OpCode::NOP => Some(nop::<M>),
+        OpCode::ORA => Some(ora::<M>),
+        OpCode::PHA => Some(pha::<M>),
+        OpCode::PHP => Some(php::<M>),
+        OpCode::PLA => Some(pla::<M>),
+        OpCode::PLP => Some(plp::<M>),
+        OpCode::ROL => Some(rol::<M>),
+        OpCode::ROR => Some(ror::<M>),
+        OpCode::RTI => Some(rti::<M>),
+        OpCode::RTS => Some(rts::<M>),
+        OpCode::SBC => Some(sbc::<M>),
+        OpCode::SEI => Some(sei::<M>),
+        OpCode::SEC => Some(sec::<M>),
+        OpCode::SED => Some(sed::<M>),
+        OpCode::STA => Some(sta::<M>),
+        OpCode::STX => Some(stx::<M>),
+        OpCode::STY => Some(sty::<M>),
+        OpCode::TAX => Some(tax::<M>),
+        OpCode::TAY => Some(tay::<M>),
+        OpCode::TSX => Some(tsx::<M>),
+        OpCode::TXA => Some(txa::<M>),
+        OpCode::TXS => Some(txs::<M>),
+        OpCode::TYA => Some(tya::<M>),
+        OpCode::INOP => Some(nop::<M>),
+        OpCode::KIL => Some(kil::<M>),
+        _ => None,
+    }
+}
+
+/// [`handler`] for every opcode, indexed by `OpCode as usize`. Opcodes no byte
+/// decodes to are left out.
+pub(crate) struct Handlers<M>(PhantomData<M>);
+
+impl<M: VirtualMemory> Handlers<M> {
+    pub(crate) const TABLE: [Option<Handler<M>>; OPCODES] = {
+        let mut table: [Option<Handler<M>>; OPCODES] = [None; OPCODES];
+        let mut byte = 0;
+        while byte < 256 {
+            let opcode = DECODE_TABLE[byte].opcode;
+            table[opcode as usize] = handler::<M>(opcode);
+            byte += 1;
+        }
+        table
+    };
+}
+
+fn adc<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let argument = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+    
+    let carry_flag = match emulator.state.p.contains(SystemFlags::carry) {
+        true => 1,
+        false => 0,
+    };
+
+    let is_adc_mode = emulator.state.p.contains(SystemFlags::decimal);
+    let result = emulator.state.a as u16 + argument as u16 + carry_flag as u16;
+
+    let argument_is_positive = argument & 0b10000000;
+    let state_a_is_positive =   emulator.state.a & 0b10000000;
+    // If the arguments are in agreement for their sign bit
+    if argument_is_positive == state_a_is_positive {
+        // Set this based on if the resulting sign bit differs
+        emulator.state.p.set(
+            SystemFlags::overflow,
+            ((result as u8) & 0b10000000) != argument_is_positive,
+        );
+    }
+    else {
+        emulator.state.p.remove(SystemFlags::overflow);
+    }
+
+    if is_adc_mode {
+        check_bcd_operands(emulator, argument);
+        let mut lower_nibble = (emulator.state.a & 0xF) + (argument & 0xF) + carry_flag;
+        let mut upper_nibble = ((emulator.state.a >> 4) & 0xF) + ((argument >> 4) & 0xF);
+        // println!("emulator.state.a: {:#02x}", emulator.state.a);
+        // println!("argument: {:#02x}", argument);
+        // println!("lower NIBBLE: {:#02x}", lower_nibble);
+        // println!("upper NIBBLE: {:#02x}", upper_nibble);
+        
+        if lower_nibble > 9 {
+            lower_nibble += 6;
+            lower_nibble &= 0xF;
+            upper_nibble += 1;
+        }
+        // TODO: negative flag is decided here?
+        emulator.state.p.set(SystemFlags::negative, (upper_nibble & 0b1000) == 0b1000);
+        if upper_nibble > 9 {
+            upper_nibble += 6;
+            upper_nibble &= 0xF;
+            emulator.state.p.insert(SystemFlags::carry);
+        }
+        else {
+            emulator.state.p.remove(SystemFlags::carry);
+        }
+        emulator.state.a = (upper_nibble << 4) + lower_nibble;
+    }
+    else {
+        emulator.state.p.set(SystemFlags::carry, result > u8::MAX.into());
+        emulator.state.a = result as u8;
+
+        //The negative flag is set if the accumulator result contains bit 7 on, otherwise the negative flag is reset.
+        emulator.state
+            .p
+            .set(SystemFlags::negative, (result & 0b10000000) == 0b10000000);
+    }
+
+
+    //The zero flag is set if the accumulator result is 0, otherwise the zero flag is reset.
+    emulator.state.p.set(SystemFlags::zero, emulator.state.a == 0);
+    Ok(())
+}
+
+// OpCode::ADC => {
+//     let argument = memory_pair
+//         .ok_or(EmulatorError::ExpectedMemoryPair)?
+//         .value;
+
+//     // TODO: Decimal mode
+//     let carry_flag: u16 = match emulator.state.p.contains(SystemFlags::carry) {
+//         true => 1,
+//         false => 0,
+//     };
+//     let is_decimal_mode = emulator.state.p.contains(SystemFlags::decimal);
+//     let result: u16 = match is_decimal_mode {
+//         true => emulator.state.a.as_bcd() as u16 + argument.as_bcd() as u16 + carry_flag,
+//         false => emulator.state.a as u16 + argument as u16 + carry_flag,
+//     };
+
+//     if is_decimal_mode {
+//         println!("result after bcd mode add: {}", result);
+//     }
+//     // sets the carry flag when the sum of a binary add exceeds 255 or when the sum of a decimal add exceeds 99, otherwise carry is reset.
+//     emulator.state.p.set(SystemFlags::carry, match is_decimal_mode {
+//         true => result > 99,
+//         false => result > u8::MAX.into()
+//     });
+//     //The overflow flag is set when the sign or bit 7 is changed due to the result exceeding +127 or -128, otherwise overflow is reset.
+
+//     emulator.state.p.set(
+//         SystemFlags::overflow,
+//         (!(emulator.state.a ^ argument) & (emulator.state.a ^ argument) & 0b10000000) == 0b10000000,
+//     );
+//     //The negative flag is set if the accumulator result contains bit 7 on, otherwise the negative flag is reset.
+//     emulator.state
+//         .p
+//         .set(SystemFlags::negative, (result & 0b10000000) == 0b10000000);
+//     //The zero flag is set if the accumulator result is 0, otherwise the zero flag is reset.
+//     emulator.state.a = match is_decimal_mode {
+//         true => ((result as u8) % 100).as_dec(),
+//         false => result as u8 
+//     };
+//     emulator.state.p.set(SystemFlags::zero, emulator.state.a == 0);
+// }
+fn and<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let argument = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+
+    emulator.state.a &= argument;
+    emulator.state.p.set_nz(emulator.state.a);
+    Ok(())
+}
+
+fn asl<M>(mode: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let (value, overflow) = match mode {
+        Some(AddressingMode::Accumulator) => {
+            let value = emulator.state.a;
+            let out = value << 1;
+            emulator.state.a = out;
+            (out, (value & 0b10000000) == 0b10000000)
+        }
+        _ => {
+            let memory_pair =
+                memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+            let address = memory_pair.address;
+            let value = memory_pair.value;
+            let out = value << 1;
+            write_modified(emulator, address, value, out);
+            (out, (value & 0b10000000) == 0b10000000)
+        }
+    };
+
+    emulator.state.p.set(SystemFlags::carry, overflow);
+    emulator.state.p.set_nz(value);
+    Ok(())
+}
+
+fn bcc<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, !emulator.state.p.contains(SystemFlags::carry))
+}
+
+fn bcs<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, emulator.state.p.contains(SystemFlags::carry))
+}
+
+fn beq<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, emulator.state.p.contains(SystemFlags::zero))
+}
+
+fn bit<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let argument = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+    let result = argument & emulator.state.a;
+    emulator.state.p.set(SystemFlags::zero, result == 0);
+    emulator.state
+        .p
+        .set(SystemFlags::overflow, (argument & 0b01000000) == 0b01000000);
+    emulator.state
+        .p
+        .set(SystemFlags::negative, (argument & 0b10000000) == 0b10000000);
+    Ok(())
+}
+
+fn bmi<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, emulator.state.p.contains(SystemFlags::negative))
+}
+
+fn bne<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, !emulator.state.p.contains(SystemFlags::zero))
+}
+
+fn bpl<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, !emulator.state.p.contains(SystemFlags::negative))
+}
+
+fn brk<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    if let Some((signature, handler)) = emulator.syscall() {
+        log::trace!("{:#06x}: syscall {:#04x}", emulator.state.pc.wrapping_sub(1), signature);
+        emulator.state.pc = emulator.state.pc.wrapping_add(1);
+        return handler(emulator);
+    }
+    let next_pc = emulator.state.pc.wrapping_add(1);
+    emulator.push_word(next_pc);
+    emulator.push(emulator.state.p.to_push(true));
+    
+    emulator.state.p |= SystemFlags::interrupt_disable;
+
+    emulator.state.pc = emulator.read_u16(Vector::Irq.address());
+    Ok(())
+}
+
+fn bvc<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, !emulator.state.p.contains(SystemFlags::overflow))
+}
+
+fn bvs<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    branch(emulator, memory_pair, emulator.state.p.contains(SystemFlags::overflow))
+}
+
+fn clc<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.p.remove(SystemFlags::carry);
+    Ok(())
+}
+
+fn cld<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.p.remove(SystemFlags::decimal);
+    Ok(())
+}
+
+fn cli<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.p.remove(SystemFlags::interrupt_disable);
+    Ok(())
+}
+
+fn clv<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.p.remove(SystemFlags::overflow);
+    Ok(())
+}
+
+fn cmp<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let memory_pair = memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+    let value = memory_pair.value;
+    let (result, _) = emulator.state.a.overflowing_sub(value);
+    emulator.state.p.set(SystemFlags::zero, result == 0);
+    emulator.state.p.set(SystemFlags::carry, value <= emulator.state.a);
+    emulator.state
+        .p
+        .set(SystemFlags::negative, (result & 0b10000000) == 0b10000000 );
+    Ok(())
+}
+
+fn cpx<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let memory_pair = memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+    let value = memory_pair.value;
+    let result = emulator.state.x.overflowing_sub(value).0;
+    emulator.state.p.set(SystemFlags::zero, result == 0);
+    emulator.state.p.set(SystemFlags::carry, emulator.state.x >= value);
+    emulator.state
+        .p
+        .set(SystemFlags::negative, (result & 0b10000000) == 0b10000000);
+    Ok(())
+}
+
+fn cpy<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let memory_pair = memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+    let value = memory_pair.value;
+
+    let result = emulator.state.y.overflowing_sub(value).0;
+    emulator.state.p.set(SystemFlags::zero, result == 0);
+    emulator.state.p.set(SystemFlags::carry, emulator.state.y >= value);
+    emulator.state
+        .p
+        .set(SystemFlags::negative, (result & 0b10000000) == 0b10000000);
+    Ok(())
+}
+
+fn dec<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let memory_pair = memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+    let address = memory_pair.address;
+    let value = memory_pair.value;
+
+    let value = value.wrapping_sub(1);
+    write_modified(emulator, address, memory_pair.value, value);
+
+    emulator.state.p.set_nz(value);
+    Ok(())
+}
+
+fn dex<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.x = emulator.state.x.overflowing_sub(1).0;
+    emulator.state.p.set_nz(emulator.state.x);
+    Ok(())
+}
+
+fn dey<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.y = emulator.state.y.overflowing_sub(1).0;
+    emulator.state.p.set_nz(emulator.state.y);
+    Ok(())
+}
+
+fn eor<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let memory_pair = memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+    let _ = memory_pair.address;
+    let value = memory_pair.value;
+    emulator.state.a ^= value;
+    emulator.state.p.set_nz(emulator.state.a);
+    Ok(())
+}
+
+fn inc<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let memory_pair = memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+    let address = memory_pair.address;
+    let value = memory_pair.value;
+    let value = value.wrapping_add(1);
+
+    write_modified(emulator, address, memory_pair.value, value);
+
+    emulator.state.p.set_nz(value);
+    Ok(())
+}
+
+fn inx<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.x = emulator.state.x.wrapping_add(1);
+
+    emulator.state.p.set_nz(emulator.state.x);
+    Ok(())
+}
+
+fn iny<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.y = emulator.state.y.wrapping_add(1);
+
+    emulator.state.p.set_nz(emulator.state.y);
+    Ok(())
+}
+
+fn jmp<M>(mode: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let address = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .address;
+
+    emulator.state.pc = match mode {
+        Some(AddressingMode::IndirectAbsolute) => emulator.read_u16(address),
+        _ => address,
+    };
+    Ok(())
+}
+
+fn jsr<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    // TODO: THIS WORKS BUT ITS SUPPOSED TO BE AN ADD 2. SOMETHING WEIRD IS GOING ON
+    // BETWEEN THE AGREEMENT OF THE ADDRESSING MODE AND JSR. JSR IS VERY OOD THOUGH.
+    let address = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .address;
+    let next_pc = emulator.state.pc.wrapping_sub(1);
+    emulator.push_word(next_pc);
+
+    emulator.state.pc = address;
+    Ok(())
+}
+
+fn lda<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+    emulator.state.a = value;
+    emulator.state.p.set_nz(emulator.state.a);
+    Ok(())
+}
+
+fn ldx<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+    emulator.state.x = value;
+    emulator.state.p.set_nz(emulator.state.x);
+    Ok(())
+}
+
+fn ldy<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+    emulator.state.y = value;
+    emulator.state.p.set_nz(emulator.state.y);
+    Ok(())
+}
+
+fn lsr<M>(mode: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let (value, overflow) = match mode {
+        Some(AddressingMode::Accumulator) => {
+            let value = emulator.state.a;
+            let out = value >> 1;
+            emulator.state.a = out;
+            (out, (value & 0x1) == 0x1)
+        }
+        _ => {
+            let memory_pair =
+                memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+            let address = memory_pair.address;
+            let value = memory_pair.value;
+
+            let out = value >> 1;
+            write_modified(emulator, address, value, out);
+            (out, (value & 0x1) == 0x1)
+        }
+    };
+    emulator.state.p.set(SystemFlags::carry, overflow);
+    emulator.state.p.set_nz(value);
+    Ok(())
+}
+
+fn nop<M>(_: Option<AddressingMode>, _: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    Ok(())
+}
+
+fn ora<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+    emulator.state.a |= value;
+
+    emulator.state.p.set_nz(emulator.state.a);
+    Ok(())
+}
+
+fn pha<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.push(emulator.state.a);
+    Ok(())
+}
+
+fn php<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    // from http://forum.6502.org/viewtopic.php?f=8&t=3111
+    // The emulators just follow the behavior of a real 6502 or any of its hardware successors. 
+    // The unused bit (B| Break) returns a 1 when read, because it is not present in hardware and reading an open circuit simply returns a logic high emulator.state. 
+    // The same is true for the break bit, as it is not an existing flag bit register but a forced low to an otherwise open circuit. 
+    // The bit is forced low only when the processor flag bits are pushed onto the stack during either an IRQ or a NMI. 
+    let saved_p = emulator.state.p.to_push(true);
+    emulator.push(saved_p);
+    Ok(())
+}
+
+fn pla<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.a = emulator.pull();
+
+    emulator.state.p.set_nz(emulator.state.a);
+    Ok(())
+}
+
+fn plp<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    // http://forum.6502.org/viewtopic.php?f=12&t=7890
+    // When SR is pulled from the stack with a PLP instruction, bits 4 (break_command) and 5 (expansion) will not be affected by whatever is on the stack.  
+    // The sequence PHP - PLA will result in bits 4 and 5 always being set in the accumulator copy of SR.
+    let loaded_p = emulator.pull();
+    emulator.state.p.pull(loaded_p);
+    Ok(())
+}
+
+fn rol<M>(mode: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let (input, output) = match mode {
+        Some(AddressingMode::Accumulator) => {
+            let input = emulator.state.a;
+            let output = match emulator.state.p.contains(SystemFlags::carry) {
+                false => input << 1,
+                true => (input << 1) | 0x1,
+            };
+            emulator.state.a = output;
+            (input, output)
+        }
+        _ => {
+            let memory_pair =
+                memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+            // println!("MemoryPair@ROL: {:?}", memory_pair);
+            let address = memory_pair.address;
+            let value: u8 = memory_pair.value;
+            let input = value;
+            let output = match emulator.state.p.contains(SystemFlags::carry) {
+                false => input << 1,
+                true => (input << 1) | 0x1,
+            };
+            write_modified(emulator, address, input, output);
+            (input, output)
+        }
+    };
+
+    emulator.state
+        .p
+        .set(SystemFlags::carry, (input & 0b10000000) == 0b10000000);
+    emulator.state.p.set(SystemFlags::zero, output == 0);
+    emulator.state
+        .p
+        .set(SystemFlags::negative, (input & 0b01000000) == 0b01000000);
+    Ok(())
+}
+
+
+fn ror<M>(mode: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let (input, output) = match mode {
+        Some(AddressingMode::Accumulator) => {
+            let input = emulator.state.a;
+            let output = match emulator.state.p.contains(SystemFlags::carry) {
+                false => input >> 1,
+                true => (input >> 1) | (0x1 << 7),
+            };
+            emulator.state.a = output;
+            (input, output)
+        }
+        _ => {
+            let memory_pair =
+                memory_pair.ok_or(EmulatorError::ExpectedMemoryPair)?;
+            let address = memory_pair.address;
+            let value = memory_pair.value;
+            let input = value;
+            let output = match emulator.state.p.contains(SystemFlags::carry) {
+                false => input >> 1,
+                true => (input >> 1) | (0x1 << 7),
+            };
+            write_modified(emulator, address, input, output);
+            (input, output)
+        }
+    };
+
+    emulator.state
+        .p
+        .set(SystemFlags::negative, emulator.state.p.contains(SystemFlags::carry));
+    emulator.state
+        .p
+        .set(SystemFlags::carry, (input & 0b00000001) == 0b00000001);
+    emulator.state.p.set(SystemFlags::zero, output == 0);
+    Ok(())
+}
+
+fn rti<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let loaded_p = emulator.pull();
+    emulator.state.p.pull(loaded_p);
+    emulator.state.pc = emulator.pull_word();
+    Ok(())
+}
+
+fn rts<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.pc = emulator.pull_word().wrapping_add(1);
+    Ok(())
+}
+
+fn sbc<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let argument = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .value;
+    
+    let carry_flag: u8 = match emulator.state.p.contains(SystemFlags::carry) {
+        true => (!1u8).into(),
+        false => (!0u8).into(),
+    };
+
+    let is_adc_mode = emulator.state.p.contains(SystemFlags::decimal);
+    let result = (emulator.state.a as u16).wrapping_sub(argument as u16).wrapping_sub(carry_flag as u16);
+
+    let argument_is_positive = argument & 0b10000000;
+    let state_a_is_positive =   emulator.state.a & 0b10000000;
+    // If the arguments are in agreement for their sign bit
+    if argument_is_positive == state_a_is_positive {
+        // Set this based on if the resulting sign bit differs
+        emulator.state.p.set(
+            SystemFlags::overflow,
+            ((result as u8) & 0b10000000) != argument_is_positive,
+        );
+    }
+    else {
+        emulator.state.p.remove(SystemFlags::overflow);
+    }
+
+    
+    if is_adc_mode {
+        check_bcd_operands(emulator, argument);
+        // TODO: decimal mode
+        return Ok(())
+    }
+    else {
+        emulator.state.p.set(SystemFlags::carry, result > u8::MAX.into());
+        emulator.state.a = result as u8;
+
+        //The negative flag is set if the accumulator result contains bit 7 on, otherwise the negative flag is reset.
+        emulator.state
+            .p
+            .set(SystemFlags::negative, (result & 0b10000000) == 0b10000000);
+    }
+
+
+    //The zero flag is set if the accumulator result is 0, otherwise the zero flag is reset.
+    emulator.state.p.set(SystemFlags::zero, emulator.state.a == 0);
+    Ok(())
+}
+
+fn sei<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.p.insert(SystemFlags::interrupt_disable);
+    Ok(())
+}
+
+fn sec<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.p.insert(SystemFlags::carry);
+    Ok(())
+}
+
+fn sed<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    emulator.state.p.insert(SystemFlags::decimal);
+    Ok(())
+}
+
+fn sta<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let address = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .address;
+    emulator.write(address, emulator.state.a);
+    Ok(())
+}
+
+fn stx<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let address = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .address;
+    
+    emulator.write(address, emulator.state.x);
+    Ok(())
+}
+
+fn sty<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, memory_pair: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let address = memory_pair
+        .ok_or(EmulatorError::ExpectedMemoryPair)?
+        .address;
+    emulator.write(address, emulator.state.y);
+    Ok(())
+}
+
+fn tax<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = emulator.state.a;
+    emulator.state.x = value;
+    emulator.state.p.set(SystemFlags::negative, (value & 0b10000000) == 0b10000000);
+    emulator.state.p.set(SystemFlags::zero, value == 0);
+    Ok(())
+}
+
+fn tay<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = emulator.state.a;
+    emulator.state.y = value;
+    emulator.state.p.set(SystemFlags::negative, (value & 0b10000000) == 0b10000000);
+    emulator.state.p.set(SystemFlags::zero, value == 0);
+    Ok(())
+}
+
+fn tsx<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = emulator.state.s;
+    emulator.state.x = value;
+    emulator.state.p.set(SystemFlags::negative, (value & 0b10000000) == 0b10000000);
+    emulator.state.p.set(SystemFlags::zero, value == 0);
+    Ok(())
+}
+
+fn txa<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = emulator.state.x;
+    emulator.state.a = value;
+    emulator.state.p.set(SystemFlags::negative, (value & 0b10000000) == 0b10000000);
+    emulator.state.p.set(SystemFlags::zero, value == 0);
+    Ok(())
+}
+
+fn txs<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = emulator.state.x;
+    emulator.state.s = value;
+    Ok(())
+}
+
+fn tya<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    let value = emulator.state.y;
+    emulator.state.a = value;
+    emulator.state.p.set(SystemFlags::negative, (value & 0b10000000) == 0b10000000);
+    emulator.state.p.set(SystemFlags::zero, value == 0);
+    Ok(())
+}
+
+fn kil<M>(_: Option<AddressingMode>, emulator: &mut CPUEmulator<M>, _: Option<MemoryPair>) -> Result<()>
+where M: VirtualMemory {
+    // TODO: Not working
+    emulator.state.running = false;
+    Ok(())
+}
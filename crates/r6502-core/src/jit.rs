@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::cycle_log::CycleLog;
+use crate::disassembler::{disassemble_at, DisassembledInstruction};
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+use crate::instructions::{AddressingMode, Instruction, OpCode};
+use crate::opcodes;
+use crate::state::{SystemFlags, SystemState};
+
+// Experimental block compiler. Straight-line runs of instructions that execute
+// often are turned into a list of host closures. Register-only instructions get
+// a specialised closure with their operand baked in, everything else goes back
+// through the interpreter. A write into a compiled block throws it away and
+// keeps it from being compiled again, since the code modifies itself. When the
+// write comes from inside the block itself, the rest of it isn't run: the
+// specialised closures hold copies of the old operands.
+//
+// Compiled instructions skip the opcode and operand fetches, so those don't
+// show up in the cycle log.
+
+/// Longest block compiled, in instructions.
+const MAX_BLOCK: usize = 32;
+
+type Op<M> = Box<dyn Fn(&mut CPUEmulator<M>) -> Result<(), Option<Instruction>> + Send>;
+
+struct Block<M: VirtualMemory> {
+    start: u16,
+    /// Address of the last byte of the block.
+    end: u16,
+    /// Each op with the address of the instruction it runs.
+    ops: Vec<(u16, Op<M>)>,
+}
+
+impl<M: VirtualMemory> Block<M> {
+    fn contains(&self, address: u16) -> bool {
+        self.start <= address && address <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JitStats {
+    pub compiled: usize,
+    pub invalidated: usize,
+    /// Instructions executed from compiled blocks.
+    pub compiled_instructions: u64,
+    pub interpreted_instructions: u64,
+}
+
+pub struct Jit<M: VirtualMemory> {
+    threshold: u32,
+    counts: HashMap<u16, u32>,
+    blocks: HashMap<u16, Block<M>>,
+    /// Block starts that were invalidated by self-modifying code.
+    blacklist: HashSet<u16>,
+    /// Number of compiled blocks touching each page, to skip invalidation checks.
+    pages: [u16; 256],
+    stats: JitStats,
+}
+
+impl<M: VirtualMemory> Default for Jit<M> {
+    fn default() -> Self {
+        Self {
+            threshold: 16,
+            counts: HashMap::new(),
+            blocks: HashMap::new(),
+            blacklist: HashSet::new(),
+            pages: [0; 256],
+            stats: JitStats::default(),
+        }
+    }
+}
+
+// Closure for instructions that only touch registers, or `None` to interpret.
+fn specialise<M: VirtualMemory>(instruction: &DisassembledInstruction) -> Option<Op<M>> {
+    let update: fn(&mut SystemState, u8) = match (instruction.instruction.opcode, instruction.instruction.mode) {
+        (OpCode::LDA, Some(AddressingMode::Immediate)) => |state, value| { state.a = value; state.p.set_nz(value) },
+        (OpCode::LDX, Some(AddressingMode::Immediate)) => |state, value| { state.x = value; state.p.set_nz(value) },
+        (OpCode::LDY, Some(AddressingMode::Immediate)) => |state, value| { state.y = value; state.p.set_nz(value) },
+        (OpCode::INX, _) => |state, _| { state.x = state.x.wrapping_add(1); state.p.set_nz(state.x) },
+        (OpCode::INY, _) => |state, _| { state.y = state.y.wrapping_add(1); state.p.set_nz(state.y) },
+        (OpCode::DEX, _) => |state, _| { state.x = state.x.wrapping_sub(1); state.p.set_nz(state.x) },
+        (OpCode::DEY, _) => |state, _| { state.y = state.y.wrapping_sub(1); state.p.set_nz(state.y) },
+        (OpCode::TAX, _) => |state, _| { state.x = state.a; state.p.set_nz(state.x) },
+        (OpCode::TAY, _) => |state, _| { state.y = state.a; state.p.set_nz(state.y) },
+        (OpCode::TXA, _) => |state, _| { state.a = state.x; state.p.set_nz(state.a) },
+        (OpCode::TYA, _) => |state, _| { state.a = state.y; state.p.set_nz(state.a) },
+        (OpCode::CLC, _) => |state, _| state.p.remove(SystemFlags::carry),
+        (OpCode::SEC, _) => |state, _| state.p.insert(SystemFlags::carry),
+        (OpCode::NOP, _) => |_, _| (),
+        _ => return None,
+    };
+    let address = instruction.address;
+    let length = instruction.length();
+    let next = address.wrapping_add(length);
+    let operand = instruction.operand().unwrap_or(0) as u8;
+    let cycles = opcodes::base_cycles(instruction.bytes[0]) as u64;
+    Some(Box::new(move |emulator: &mut CPUEmulator<M>| {
+        update(&mut emulator.state, operand);
+        emulator.state.pc = next;
+        emulator.retire(address, length, cycles);
+        Ok(())
+    }))
+}
+
+fn interpret<M: VirtualMemory>() -> Op<M> {
+    Box::new(|emulator: &mut CPUEmulator<M>| emulator.execute_next_instruction().map(|_| ()))
+}
+
+fn ends_block(instruction: &DisassembledInstruction) -> bool {
+    !instruction.is_valid()
+        || instruction.instruction.mode == Some(AddressingMode::Relative)
+        || matches!(
+            instruction.instruction.opcode,
+            OpCode::JMP | OpCode::JSR | OpCode::RTS | OpCode::RTI | OpCode::BRK | OpCode::KIL
+        )
+}
+
+impl<M: VirtualMemory> Jit<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of times a block start has to be reached before it is compiled.
+    pub fn threshold(mut self, threshold: u32) -> Self {
+        self.threshold = threshold.max(1);
+        self
+    }
+
+    pub fn stats(&self) -> JitStats {
+        self.stats
+    }
+
+    fn compile(&mut self, emulator: &CPUEmulator<M>, start: u16) {
+        let mut ops = vec![];
+        let mut address = start;
+        let mut end = start;
+        while ops.len() < MAX_BLOCK {
+            let bytes: Vec<u8> = (0..3).map(|offset| emulator.peek(address.wrapping_add(offset))).collect();
+            let instruction = disassemble_at(&bytes, address);
+            if emulator.has_override(bytes[0]) {
+                break;
+            }
+            end = address.wrapping_add(instruction.length() - 1);
+            ops.push((address, specialise(&instruction).unwrap_or_else(interpret)));
+            if ends_block(&instruction) || end < address {
+                break;
+            }
+            address = end.wrapping_add(1);
+        }
+        if ops.is_empty() {
+            return;
+        }
+        for page in (start >> 8)..=(end >> 8) {
+            self.pages[page as usize] += 1;
+        }
+        self.blocks.insert(start, Block { start, end, ops });
+        self.stats.compiled += 1;
+    }
+
+    fn invalidate(&mut self, address: u16) {
+        if self.pages[(address >> 8) as usize] == 0 {
+            return;
+        }
+        let stale: Vec<u16> = self.blocks.values().filter(|block| block.contains(address)).map(|block| block.start).collect();
+        for start in stale {
+            let block = self.blocks.remove(&start).unwrap();
+            for page in (block.start >> 8)..=(block.end >> 8) {
+                self.pages[page as usize] -= 1;
+            }
+            self.blacklist.insert(start);
+            self.stats.invalidated += 1;
+        }
+    }
+
+    /// Drops every compiled block, e.g. after loading new code with `poke`, which the
+    /// compiler can't see.
+    pub fn flush(&mut self) {
+        self.blocks.clear();
+        self.counts.clear();
+        self.pages = [0; 256];
+    }
+
+    /// Equivalent of [`CPUEmulator::run`] that executes hot blocks from compiled code.
+    pub fn run(&mut self, emulator: &mut CPUEmulator<M>) -> StopReason {
+        if !emulator.state.running {
+            return emulator.halted();
+        }
+        loop {
+            if !emulator.state.running {
+                return emulator.stopped(emulator.halted());
+            }
+            let pc = emulator.state.pc;
+            let logged = emulator.state.cycles.len();
+            let result = match self.blocks.get(&pc) {
+                // Blocks are stepped over whole, so fall back to the interpreter if one
+                // has a breakpoint in the middle or an interrupt has to be taken first.
+                // An interrupt coming due in the block leaves it before the next op, so
+                // the interpreter takes it at the same instruction it would have.
+                Some(block) if !emulator.interrupt_next() && !emulator.breakpoints().any(|address| *address != pc && block.contains(*address)) => {
+                    let mut result = Ok(());
+                    for (address, op) in block.ops.iter() {
+                        if emulator.state.pc != *address || emulator.interrupt_next() {
+                            break;
+                        }
+                        let before = emulator.state.cycles.len();
+                        result = op(emulator);
+                        self.stats.compiled_instructions += 1;
+                        if result.is_err() || emulator.state.cycles.between(before..usize::MAX).writes().any(|cycle| block.contains(cycle.address)) {
+                            break;
+                        }
+                    }
+                    result
+                }
+                _ => {
+                    if !self.blacklist.contains(&pc) {
+                        let count = self.counts.entry(pc).or_default();
+                        *count += 1;
+                        if *count >= self.threshold && !self.blocks.contains_key(&pc) {
+                            self.compile(emulator, pc);
+                        }
+                    }
+                    self.stats.interpreted_instructions += 1;
+                    emulator.execute_next_instruction().map(|_| ())
+                }
+            };
+            let writes: Vec<u16> =
+                emulator.state.cycles.between(logged..usize::MAX).writes().map(|cycle| cycle.address).collect();
+            for address in writes {
+                self.invalidate(address);
+            }
+            match result {
+                Ok(()) => (),
+                Err(None) => return emulator.stopped(emulator.halted()),
+                Err(Some(instruction)) => return emulator.stopped(StopReason::Error(Some(instruction))),
+            }
+            if let Some(address) = emulator.take_vector_write() {
+                return StopReason::VectorWrite(address);
+            }
+            if emulator.breakpoints().any(|address| *address == emulator.state.pc) {
+                return StopReason::Breakpoint(emulator.state.pc);
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// The 6502 processor and the tooling that only needs the processor: the
+// assembler and disassembler, the monitor, analysis, movies and the test
+// harness. Devices, machines and frontends live in their own crates and build
+// on the traits here, so this one keeps few dependencies and a slow moving
+// API. Pretty printing, the JIT and the test harness are behind features.
+
+pub mod state;
+pub mod error;
+pub mod format;
+pub mod instructions;
+pub mod cache;
+pub mod emulator;
+pub mod cpu;
+pub mod diagnostics;
+pub mod crash;
+pub mod hashing;
+pub mod inspect;
+pub mod vectors;
+pub mod symbols;
+pub mod disassembler;
+pub mod assembler;
+pub mod export;
+pub mod batch;
+pub mod superopt;
+pub mod opcodes;
+pub mod analysis;
+pub mod registers;
+pub mod loaders;
+pub mod shutdown;
+pub mod cooperative;
+pub mod scheduler;
+pub mod sync;
+pub mod charset;
+pub mod search;
+pub mod monitor;
+#[cfg(feature = "testing")]
+pub mod fixture;
+#[cfg(feature = "testing")]
+pub mod grader;
+#[cfg(feature = "testing")]
+pub mod snapshot;
+#[cfg(feature = "testing")]
+pub mod differential;
+#[cfg(feature = "testing")]
+pub mod determinism;
+pub mod input;
+pub mod movie;
+pub mod netplay;
+pub mod pinning;
+pub mod power_on;
+pub mod faults;
+pub mod cycle_log;
+#[cfg(feature = "zip")]
+pub mod zip;
+pub mod idle;
+#[cfg(feature = "jit")]
+pub mod jit;
+
+pub use emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, StopReason, VirtualMemory};
+pub use error::{R6502Error, Result};
+pub use state::{Registers, SystemFlags, SystemState};
@@ -0,0 +1,398 @@
+use std::path::Path;
+
+use crate::emulator::{DefaultVirtualMemory, ADDRESS_SPACE};
+use crate::error::{R6502Error, Result};
+use crate::vectors::Vector;
+
+// Readers for common 6502 program and ROM formats. Each one produces the memory
+// image plus where execution should begin. `Format::detect` tells the formats
+// apart by signature, extension and size, for loading whatever file a user
+// points the emulator at. With the `zip` feature, files read from disk may also
+// be ZIP archives holding the ROM.
+
+/// A program placed in memory, ready to be run.
+#[derive(Clone)]
+pub struct LoadedProgram {
+    pub memory: DefaultVirtualMemory,
+    /// Where to start executing, or `None` to go through the reset vector.
+    pub entry: Option<u16>,
+}
+
+/// Places `image` at `origin`. Execution starts at the reset vector when the image
+/// covers it, at `origin` otherwise.
+pub fn binary(image: &[u8], origin: u16) -> Result<LoadedProgram> {
+    if origin as usize + image.len() > ADDRESS_SPACE {
+        return Err(R6502Error::ImageTooLarge { length: image.len(), origin });
+    }
+    let covers_vector = Vector::Reset.in_image(origin, image.len());
+    Ok(LoadedProgram {
+        memory: DefaultVirtualMemory::default().with_image(origin, image),
+        entry: if covers_vector { None } else { Some(origin) },
+    })
+}
+
+pub fn binary_file<P: AsRef<Path>>(path: P, origin: u16) -> Result<LoadedProgram> {
+    binary(&read_rom(path)?.0, origin)
+}
+
+/// Reads the file at `path`, or with the `zip` feature the ROM inside it when
+/// it is a ZIP archive (see [`crate::zip::rom`]). Returns the contents and the
+/// extension of the file they came from.
+pub fn read_rom<P: AsRef<Path>>(path: P) -> Result<(Vec<u8>, Option<String>)> {
+    let path = path.as_ref();
+    let data = std::fs::read(path)?;
+    #[cfg(feature = "zip")]
+    if crate::zip::is_zip(&data) {
+        let (name, contents) = crate::zip::rom(&data)?;
+        log::debug!("loading {} from {}", name, path.display());
+        return Ok((contents, Path::new(&name).extension().and_then(|extension| extension.to_str()).map(str::to_owned)));
+    }
+    Ok((data, path.extension().and_then(|extension| extension.to_str()).map(str::to_owned)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    FourScreen,
+}
+
+/// Contents of an iNES (.nes) file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct INesImage {
+    pub mapper: u8,
+    pub mirroring: Mirroring,
+    pub battery: bool,
+    pub prg: Vec<u8>,
+    pub chr: Vec<u8>,
+}
+
+impl INesImage {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 16 || &data[0..4] != b"NES\x1A" {
+            return Err(R6502Error::NotINes);
+        }
+        let prg_size = data[4] as usize * 0x4000;
+        let chr_size = data[5] as usize * 0x2000;
+        let flags6 = data[6];
+        let flags7 = data[7];
+        let mut offset = 16;
+        if flags6 & 0x04 != 0 {
+            // 512 byte trainer, only meaningful to a few old copier dumps.
+            offset += 512;
+        }
+        if data.len() < offset + prg_size + chr_size {
+            return Err(R6502Error::TruncatedImage);
+        }
+        let mirroring = match (flags6 & 0x08 != 0, flags6 & 0x01 != 0) {
+            (true, _) => Mirroring::FourScreen,
+            (false, true) => Mirroring::Vertical,
+            (false, false) => Mirroring::Horizontal,
+        };
+        Ok(Self {
+            mapper: (flags7 & 0xF0) | (flags6 >> 4),
+            mirroring,
+            battery: flags6 & 0x02 != 0,
+            prg: data[offset..offset + prg_size].to_vec(),
+            chr: data[offset + prg_size..offset + prg_size + chr_size].to_vec(),
+        })
+    }
+
+    /// Maps the PRG ROM the way mapper 0 (NROM) does: 32K at $8000, or a 16K bank
+    /// mirrored at $8000 and $C000. Other mappers need bank switching hardware.
+    pub fn load(&self) -> Result<LoadedProgram> {
+        if self.mapper != 0 {
+            return Err(R6502Error::UnsupportedMapper(self.mapper));
+        }
+        let memory = match self.prg.len() {
+            0x4000 => DefaultVirtualMemory::default().with_image(0x8000, &self.prg).with_image(0xC000, &self.prg),
+            0x8000 => DefaultVirtualMemory::default().with_image(0x8000, &self.prg),
+            size => return Err(R6502Error::UnsupportedPrgSize(size)),
+        };
+        Ok(LoadedProgram { memory: memory.with_rom(0x8000, 0xFFFF), entry: None })
+    }
+}
+
+pub fn ines_file<P: AsRef<Path>>(path: P) -> Result<LoadedProgram> {
+    INesImage::parse(&read_rom(path)?.0)?.load()
+}
+
+/// Commodore PRG: a two byte little endian load address followed by the data.
+/// Programs loaded at the start of BASIC usually begin with a `SYS` line, which is
+/// followed to find the machine code entry point.
+pub fn prg(data: &[u8]) -> Result<LoadedProgram> {
+    let [low, high, image @ ..] = data else {
+        return Err(R6502Error::MissingLoadAddress);
+    };
+    let origin = ((*high as u16) << 8) + *low as u16;
+    let mut program = binary(image, origin)?;
+    program.entry = Some(sys_target(image).unwrap_or(origin));
+    Ok(program)
+}
+
+pub fn prg_file<P: AsRef<Path>>(path: P) -> Result<LoadedProgram> {
+    prg(&read_rom(path)?.0)
+}
+
+// First BASIC line of the form `10 SYS 2061`: next line pointer, line number,
+// then the SYS token ($9E) and the address in decimal.
+fn sys_target(image: &[u8]) -> Option<u16> {
+    let line = image.get(4..)?;
+    let digits = line.strip_prefix(&[0x9E])?;
+    let digits: String = digits
+        .iter()
+        .skip_while(|byte| **byte == b' ')
+        .take_while(|byte| byte.is_ascii_digit())
+        .map(|byte| *byte as char)
+        .collect();
+    digits.parse().ok()
+}
+
+/// Atari 2600 cartridge that needs no bank switching, 2K or 4K. The cartridge
+/// answers whenever address line 12 is set, so it is mapped at $1000 and $F000,
+/// a 2K one twice over.
+pub fn atari2600(data: &[u8]) -> Result<LoadedProgram> {
+    let image = match data.len() {
+        0x0800 => [data, data].concat(),
+        0x1000 => data.to_vec(),
+        size => return Err(R6502Error::UnsupportedCartridgeSize(size)),
+    };
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x1000, &image)
+        .with_image(0xF000, &image)
+        .with_rom(0x1000, 0x1FFF)
+        .with_rom(0xF000, 0xFFFF);
+    Ok(LoadedProgram { memory, entry: None })
+}
+
+pub fn atari2600_file<P: AsRef<Path>>(path: P) -> Result<LoadedProgram> {
+    atari2600(&read_rom(path)?.0)
+}
+
+/// Intel HEX, as written by most assemblers and EPROM programmers. Execution
+/// starts at the start address record if there is one, else at the reset vector
+/// when the data covers it, else at the first data record.
+pub fn intel_hex(text: &str) -> Result<LoadedProgram> {
+    let mut loaded = Records::default();
+    for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let bad = |reason: &str| R6502Error::BadRecord { line: index + 1, reason: reason.to_owned() };
+        let bytes = line.trim().strip_prefix(':').ok_or_else(|| bad("missing ':'")).and_then(|hex| record_bytes(hex).ok_or_else(|| bad("not hex")))?;
+        let [length, high, low, kind, ..] = bytes[..] else { return Err(bad("too short")) };
+        if bytes.len() != length as usize + 5 {
+            return Err(bad("length doesn't match the data"));
+        }
+        if bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0 {
+            return Err(bad("bad checksum"));
+        }
+        let data = &bytes[4..bytes.len() - 1];
+        match kind {
+            0x00 if !loaded.data(u16::from_be_bytes([high, low]), data) => return Err(bad("data past $FFFF")),
+            0x00 => (),
+            0x01 => break,
+            // Segment and linear bases past the first 64K.
+            0x02 | 0x04 if data.iter().any(|byte| *byte != 0) => return Err(bad("address past $FFFF")),
+            0x02 | 0x04 => (),
+            0x03 | 0x05 => {
+                let [.., high, low] = data[..] else { return Err(bad("too short")) };
+                loaded.start = Some(u16::from_be_bytes([high, low]));
+            }
+            _ => return Err(bad("unknown record type")),
+        }
+    }
+    Ok(loaded.finish())
+}
+
+pub fn intel_hex_file<P: AsRef<Path>>(path: P) -> Result<LoadedProgram> {
+    Format::IntelHex.load(&read_rom(path)?.0, 0)
+}
+
+/// Motorola S-records (S19, S28 and S37 files). Execution starts the same way
+/// as for [`intel_hex`], from the S7, S8 or S9 record if there is one.
+pub fn srec(text: &str) -> Result<LoadedProgram> {
+    let mut loaded = Records::default();
+    for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let bad = |reason: &str| R6502Error::BadRecord { line: index + 1, reason: reason.to_owned() };
+        let line = line.trim();
+        let kind = line.strip_prefix('S').and_then(|rest| rest.chars().next()).ok_or_else(|| bad("missing 'S'"))?;
+        let bytes = line.get(2..).and_then(record_bytes).ok_or_else(|| bad("not hex"))?;
+        if bytes.is_empty() || bytes.len() != bytes[0] as usize + 1 {
+            return Err(bad("length doesn't match the data"));
+        }
+        if bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) != 0xFF {
+            return Err(bad("bad checksum"));
+        }
+        let width = match kind {
+            '0' | '1' | '5' | '9' => 2,
+            '2' | '6' | '8' => 3,
+            '3' | '7' => 4,
+            _ => return Err(bad("unknown record type")),
+        };
+        let Some(fields) = bytes.get(1..bytes.len() - 1).filter(|fields| fields.len() >= width) else {
+            return Err(bad("too short"));
+        };
+        let (address, data) = fields.split_at(width);
+        let address = address.iter().fold(0u32, |address, byte| address << 8 | *byte as u32);
+        let address = || u16::try_from(address).map_err(|_| bad("address past $FFFF"));
+        match kind {
+            '1' | '2' | '3' if !loaded.data(address()?, data) => return Err(bad("data past $FFFF")),
+            '7' | '8' | '9' => loaded.start = Some(address()?),
+            // Header and record counts.
+            _ => (),
+        }
+    }
+    Ok(loaded.finish())
+}
+
+pub fn srec_file<P: AsRef<Path>>(path: P) -> Result<LoadedProgram> {
+    Format::Srec.load(&read_rom(path)?.0, 0)
+}
+
+// Memory built up from the data records of a HEX or S-record file.
+#[derive(Default)]
+struct Records {
+    memory: DefaultVirtualMemory,
+    first: Option<u16>,
+    start: Option<u16>,
+    covers_vector: bool,
+}
+
+impl Records {
+    // Whether `data` fit below $10000.
+    fn data(&mut self, address: u16, data: &[u8]) -> bool {
+        let start = address as usize;
+        let Some(bytes) = self.memory.bytes_mut().get_mut(start..start + data.len()) else { return false };
+        bytes.copy_from_slice(data);
+        self.first.get_or_insert(address);
+        self.covers_vector |= Vector::Reset.in_image(address, data.len());
+        true
+    }
+
+    fn finish(self) -> LoadedProgram {
+        let entry = match self.covers_vector {
+            true => self.start,
+            false => self.start.or(self.first),
+        };
+        LoadedProgram { memory: self.memory, entry }
+    }
+}
+
+// The bytes spelled out by the hex digits of a record.
+fn record_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok()).collect()
+}
+
+/// The formats files are loaded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// A raw image, loaded at an origin.
+    Binary,
+    INes,
+    Prg,
+    Atari2600,
+    IntelHex,
+    Srec,
+}
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Binary => "binary",
+            Self::INes => "ines",
+            Self::Prg => "prg",
+            Self::Atari2600 => "a26",
+            Self::IntelHex => "hex",
+            Self::Srec => "srec",
+        }
+    }
+
+    /// Guesses the format of `data`: from its signature where it has one, then
+    /// from the file's `extension`, then from its size and shape. Anything
+    /// unrecognised is a raw binary.
+    pub fn detect(data: &[u8], extension: Option<&str>) -> Self {
+        if data.starts_with(b"NES\x1A") {
+            return Self::INes;
+        }
+        let first_line = std::str::from_utf8(data).ok().and_then(|text| text.lines().map(str::trim).find(|line| !line.is_empty()));
+        match first_line {
+            Some(line) if line.len() > 10 && line.starts_with(':') && record_bytes(&line[1..]).is_some() => return Self::IntelHex,
+            Some(line) if line.len() > 9 && line.starts_with('S') && line.as_bytes()[1].is_ascii_digit() && line.get(2..).and_then(record_bytes).is_some() => return Self::Srec,
+            _ => (),
+        }
+        if let Some(format) = extension.and_then(Self::from_extension) {
+            return format;
+        }
+        if matches!(data.len(), 0x0800 | 0x1000) {
+            // A cartridge's reset vector points back into the cartridge.
+            let reset = u16::from_le_bytes([data[data.len() - 4], data[data.len() - 3]]);
+            if reset & 0x1000 != 0 {
+                return Self::Atari2600;
+            }
+        }
+        if let [low, high, image @ ..] = data {
+            // A Commodore program with a SYS line at the start of BASIC.
+            if matches!(u16::from_le_bytes([*low, *high]), 0x0401 | 0x0801 | 0x1001 | 0x1201) && sys_target(image).is_some() {
+                return Self::Prg;
+            }
+        }
+        Self::Binary
+    }
+
+    /// The format files with `extension` are usually in, if that says.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "nes" => Some(Self::INes),
+            "prg" => Some(Self::Prg),
+            "a26" => Some(Self::Atari2600),
+            "hex" | "ihx" | "ihex" => Some(Self::IntelHex),
+            "srec" | "s19" | "s28" | "s37" | "mot" => Some(Self::Srec),
+            _ => None,
+        }
+    }
+
+    /// Loads `data` in this format. `origin` is only used for raw binaries.
+    pub fn load(&self, data: &[u8], origin: u16) -> Result<LoadedProgram> {
+        let text = || std::str::from_utf8(data).map_err(|_| R6502Error::BadRecord { line: 0, reason: "not text".to_owned() });
+        match self {
+            Self::Binary => binary(data, origin),
+            Self::INes => INesImage::parse(data)?.load(),
+            Self::Prg => prg(data),
+            Self::Atari2600 => atari2600(data),
+            Self::IntelHex => intel_hex(text()?),
+            Self::Srec => srec(text()?),
+        }
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = R6502Error;
+
+    /// A format by name, or by one of its file extensions.
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "binary" | "bin" | "raw" => Ok(Self::Binary),
+            "ines" => Ok(Self::INes),
+            "atari2600" | "2600" => Ok(Self::Atari2600),
+            "intelhex" => Ok(Self::IntelHex),
+            other => Self::from_extension(other).ok_or_else(|| R6502Error::UnknownFormat(name.to_owned())),
+        }
+    }
+}
+
+/// Loads the file at `path` in the format [`Format::detect`] takes it for,
+/// returning the format too. `origin` is only used for raw binaries.
+pub fn auto_load<P: AsRef<Path>>(path: P, origin: u16) -> Result<(Format, LoadedProgram)> {
+    let path = path.as_ref();
+    let (data, extension) = read_rom(path)?;
+    let format = Format::detect(&data, extension.as_deref());
+    log::debug!("loading {} as {}", path.display(), format);
+    Ok((format, format.load(&data, origin)?))
+}
@@ -0,0 +1,302 @@
+use crate::emulator::{CPUEmulator, VirtualMemory};
+use crate::error::{R6502Error, Result};
+use crate::symbols::SymbolTable;
+
+// Expressions for monitor arguments and breakpoint conditions, with C operators
+// and precedence:
+//
+//     m[vector+2]       byte at an address, w[..] for a little endian word
+//     *(word)$FFFC      the same as a C style dereference, *(byte) or plain *
+//     a + x             registers pc, a, x, y, s and p
+//     main+3            symbols
+//
+// Numbers are hex like everywhere else in the monitor, with an optional `$` or
+// `0x`; `+` in front of a number makes it decimal and `%` binary. A bare name
+// is a register first, then a symbol, then a hex number, so `$a` is ten.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(String),
+    Operator(&'static str),
+}
+
+// Longest first, so `<<` isn't read as two `<`.
+const OPERATORS: [&str; 24] = [
+    "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+", "-", "*", "/", "%", "&", "|", "^", "!", "~", "<", ">", "(", ")", "[", "]",
+];
+
+fn bad(reason: String) -> R6502Error {
+    R6502Error::BadCommand(reason)
+}
+
+// Tokens along with the offset in `text` just past each one, up to the first
+// character that can't be part of an expression.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>> {
+    let bytes = text.as_bytes();
+    let mut tokens: Vec<(Token, usize)> = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let c = bytes[index] as char;
+        if c.is_ascii_whitespace() {
+            index += 1;
+            continue;
+        }
+        // Whether a value is expected here, as opposed to a binary operator, which
+        // tells a decimal or binary number from addition or remainder.
+        let operand = !matches!(tokens.last(), Some((Token::Number(_) | Token::Name(_), _)) | Some((Token::Operator(")" | "]"), _)));
+        let word_end = |start: usize| start + text[start..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.')).unwrap_or(text.len() - start);
+
+        let (token, end) = if (c == '$' || (operand && (c == '+' || c == '%'))) && bytes.get(index + 1).is_some_and(|next| next.is_ascii_alphanumeric()) {
+            let end = word_end(index + 1);
+            let digits = &text[index + 1..end];
+            let radix = match c {
+                '+' => 10,
+                '%' => 2,
+                _ => 16,
+            };
+            let value = i64::from_str_radix(digits, radix).map_err(|_| bad(format!("Bad number {}", &text[index..end])))?;
+            (Token::Number(value), end)
+        } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+            let end = word_end(index);
+            let word = &text[index..end];
+            let token = if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+                Token::Number(i64::from_str_radix(hex, 16).map_err(|_| bad(format!("Bad number {}", word)))?)
+            } else if c.is_ascii_digit() {
+                Token::Number(i64::from_str_radix(word, 16).map_err(|_| bad(format!("Bad number {}", word)))?)
+            } else {
+                Token::Name(word.to_owned())
+            };
+            (token, end)
+        } else {
+            // Anything else ends the expression, leaving it to the command, e.g. a
+            // quoted string after an address.
+            let Some(operator) = OPERATORS.iter().find(|operator| text[index..].starts_with(**operator)) else {
+                break;
+            };
+            (Token::Operator(operator), index + operator.len())
+        };
+        tokens.push((token, end));
+        index = end;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(i64),
+    Name(String),
+    Byte(Box<Node>),
+    Word(Box<Node>),
+    Unary(&'static str, Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+}
+
+// Binary operators from the loosest binding to the tightest.
+const PRECEDENCE: [&[&str]; 10] = [
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, operator: &str) -> Result<()> {
+        match self.next() {
+            Some(Token::Operator(found)) if found == operator => Ok(()),
+            _ => Err(bad(format!("Expected {} in expression", operator))),
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Node> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(Token::Operator(operator)) = self.peek() {
+            let Some(operator) = PRECEDENCE[level].iter().find(|candidate| *candidate == operator) else {
+                break;
+            };
+            self.position += 1;
+            let right = self.binary(level + 1)?;
+            left = Node::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        match self.next() {
+            Some(Token::Operator("*")) => {
+                // An optional (byte) or (word) cast picks the width.
+                let cast = match (self.tokens.get(self.position), self.tokens.get(self.position + 1), self.tokens.get(self.position + 2)) {
+                    (Some((Token::Operator("("), _)), Some((Token::Name(name), _)), Some((Token::Operator(")"), _)))
+                        if name == "byte" || name == "word" => Some(name.clone()),
+                    _ => None,
+                };
+                if cast.is_some() {
+                    self.position += 3;
+                }
+                let address = Box::new(self.unary()?);
+                Ok(match cast.as_deref() {
+                    Some("word") => Node::Word(address),
+                    _ => Node::Byte(address),
+                })
+            }
+            Some(Token::Operator(operator @ ("-" | "!" | "~"))) => Ok(Node::Unary(operator, Box::new(self.unary()?))),
+            Some(Token::Operator("(")) => {
+                let inner = self.binary(0)?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            Some(Token::Number(value)) => Ok(Node::Number(value)),
+            Some(Token::Name(name)) => {
+                if (name == "m" || name == "w") && self.peek() == Some(&Token::Operator("[")) {
+                    self.position += 1;
+                    let address = Box::new(self.binary(0)?);
+                    self.expect("]")?;
+                    return Ok(if name == "m" { Node::Byte(address) } else { Node::Word(address) });
+                }
+                Ok(Node::Name(name))
+            }
+            Some(token) => Err(bad(format!("Unexpected {:?} in expression", token))),
+            None => Err(bad("Expression ends early".to_owned())),
+        }
+    }
+}
+
+/// A parsed expression, evaluated against a paused emulator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    text: String,
+    root: Node,
+}
+
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+impl std::str::FromStr for Expression {
+    type Err = R6502Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (expression, rest) = Self::parse_prefix(text)?;
+        match rest.trim().is_empty() {
+            true => Ok(expression),
+            false => Err(bad(format!("Unexpected {} after expression", rest.trim()))),
+        }
+    }
+}
+
+impl Expression {
+    /// Parses the expression at the start of `text` and returns it with the rest
+    /// of the text, so several can be given in a row: `m a+x 10`.
+    pub fn parse_prefix(text: &str) -> Result<(Self, &str)> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens: &tokens, position: 0 };
+        let root = parser.binary(0)?;
+        let end = match parser.position {
+            0 => 0,
+            position => tokens[position - 1].1,
+        };
+        Ok((Self { text: text[..end].trim().to_owned(), root }, &text[end..]))
+    }
+
+    pub fn evaluate<M: VirtualMemory>(&self, emulator: &CPUEmulator<M>, symbols: Option<&SymbolTable>) -> Result<i64> {
+        evaluate(&self.root, emulator, symbols)
+    }
+
+    /// Evaluates to an address, which has to fit in 16 bits.
+    pub fn address<M: VirtualMemory>(&self, emulator: &CPUEmulator<M>, symbols: Option<&SymbolTable>) -> Result<u16> {
+        let value = self.evaluate(emulator, symbols)?;
+        u16::try_from(value).map_err(|_| R6502Error::AddressOutOfRange(value as u32))
+    }
+}
+
+fn evaluate<M: VirtualMemory>(node: &Node, emulator: &CPUEmulator<M>, symbols: Option<&SymbolTable>) -> Result<i64> {
+    let address = |node: &Node| -> Result<u16> { Ok(evaluate(node, emulator, symbols)? as u16) };
+    Ok(match node {
+        Node::Number(value) => *value,
+        Node::Name(name) => {
+            let state = &emulator.state;
+            match name.to_ascii_lowercase().as_str() {
+                "pc" => state.pc as i64,
+                "a" => state.a as i64,
+                "x" => state.x as i64,
+                "y" => state.y as i64,
+                "s" => state.s as i64,
+                "p" => state.p.bits() as i64,
+                _ => match symbols.and_then(|symbols| symbols.lookup(name)) {
+                    Some(address) => address as i64,
+                    None => i64::from_str_radix(name, 16).map_err(|_| bad(format!("Unknown symbol {}", name)))?,
+                },
+            }
+        }
+        Node::Byte(node) => emulator.peek(address(node)?) as i64,
+        Node::Word(node) => {
+            let address = address(node)?;
+            emulator.peek(address) as i64 | (emulator.peek(address.wrapping_add(1)) as i64) << 8
+        }
+        Node::Unary(operator, node) => {
+            let value = evaluate(node, emulator, symbols)?;
+            match *operator {
+                "-" => value.wrapping_neg(),
+                "!" => (value == 0) as i64,
+                _ => !value,
+            }
+        }
+        Node::Binary(operator, left, right) => {
+            let left = evaluate(left, emulator, symbols)?;
+            // Short circuit, so `x != 0 && m[$10] / x` doesn't divide by zero.
+            match *operator {
+                "&&" if left == 0 => return Ok(0),
+                "||" if left != 0 => return Ok(1),
+                _ => (),
+            }
+            let right = evaluate(right, emulator, symbols)?;
+            match *operator {
+                "+" => left.wrapping_add(right),
+                "-" => left.wrapping_sub(right),
+                "*" => left.wrapping_mul(right),
+                "/" | "%" if right == 0 => return Err(bad("Division by zero".to_owned())),
+                "/" => left.wrapping_div(right),
+                "%" => left.wrapping_rem(right),
+                "&" => left & right,
+                "|" => left | right,
+                "^" => left ^ right,
+                "<<" => left.wrapping_shl(right as u32),
+                ">>" => left.wrapping_shr(right as u32),
+                "==" => (left == right) as i64,
+                "!=" => (left != right) as i64,
+                "<" => (left < right) as i64,
+                "<=" => (left <= right) as i64,
+                ">" => (left > right) as i64,
+                ">=" => (left >= right) as i64,
+                "&&" | "||" => (right != 0) as i64,
+                _ => unreachable!("operator {} is not in PRECEDENCE", operator),
+            }
+        }
+    })
+}
@@ -0,0 +1,401 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::analysis::interrupts::InterruptStats;
+use crate::analysis::zero_page::ZeroPageUsage;
+use crate::charset::Charset;
+use crate::disassembler::disassemble_at;
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+use crate::error::{R6502Error, Result};
+use crate::format::{number_format, set_number_format};
+use crate::search;
+use crate::symbols::SymbolTable;
+use crate::vectors::Vector;
+
+pub mod expression;
+
+use expression::Expression;
+
+// Interactive machine language monitor. Commands are read a line at a time and
+// operate on a paused emulator. Addresses can be any expression (see
+// `expression`), e.g. `d main+3` or `m w[$FFFC]`; counts are decimal.
+
+/// What the caller should do with the emulator when the monitor returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonitorAction {
+    Continue,
+    Quit,
+}
+
+#[derive(Default)]
+pub struct Monitor {
+    symbols: Option<SymbolTable>,
+    /// Where `d` and `m` continue from when given no address.
+    next: Option<u16>,
+    /// Breakpoints that only stop when their condition is true.
+    conditions: HashMap<u16, Expression>,
+    /// How `m` shows memory as text.
+    charset: Charset,
+}
+
+const HELP: &str = "\
+r                   registers
+d [addr] [count]    disassemble
+m [addr] [len]      memory dump
+cs [charset]        text beside dumps: ascii, petscii, screen or apple
+fmt [format]        numbers as hex, $, 0x, dec or bin
+s [count]           step instructions
+sf [count]          run to the start of the next frame
+sl [count]          run to the start of the next scanline
+b addr              toggle breakpoint
+b addr if expr      break when expr is true
+bl                  list breakpoints
+e expr              evaluate, e.g. e m[vector+2] or e a + x
+> addr bytes        write bytes
+f from to bytes     fill a range with a repeating pattern
+t from to dest      copy a range
+find from to bytes  list where a byte pattern occurs
+find from to \"txt\"  .. or ASCII text, p\"..\" PETSCII, s\"..\" screen codes
+find from to w val  .. or a little endian word
+v                   show the nmi, reset and irq vectors
+v vector addr       point a vector at addr
+stats               interrupt statistics, collected from first use
+zp                  zero page usage by code region, likewise
+who addr [end]      instructions that wrote there, watched from first use
+c                   continue
+q                   quit
+";
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn symbols(mut self, symbols: SymbolTable) -> Self {
+        self.symbols = Some(symbols);
+        self
+    }
+
+    /// Character set for the text beside memory dumps, see `Machine::charset` in r6502-devices.
+    pub fn charset(mut self, charset: Charset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Registers and the instruction about to execute.
+    pub fn status<M: VirtualMemory>(&self, emulator: &CPUEmulator<M>) -> String {
+        format!("{}\n{}", emulator.state.registers(), self.disassembly(emulator, emulator.state.pc, 1).0)
+    }
+
+    // Listing of `count` instructions from `address`, and the address that follows.
+    fn disassembly<M: VirtualMemory>(&self, emulator: &CPUEmulator<M>, address: u16, count: usize) -> (String, u16) {
+        let mut out = String::new();
+        let mut address = address;
+        for _ in 0..count {
+            let bytes: Vec<u8> = (0..3).map(|offset| emulator.peek(address.wrapping_add(offset))).collect();
+            let instruction = disassemble_at(&bytes, address);
+            if let Some(name) = self.symbols.as_ref().and_then(|symbols| symbols.name_for(address)) {
+                out.push_str(&format!("{}:\n", name));
+            }
+            let hex: Vec<String> = instruction.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            out.push_str(&format!("{:04X}  {:<8}  {}\n", address, hex.join(" "), instruction.text(self.symbols.as_ref())));
+            address = address.wrapping_add(instruction.length());
+        }
+        (out, address)
+    }
+
+    // Address expression at the start of `text`, and what follows it.
+    fn parse_address<'a, M: VirtualMemory>(&self, emulator: &CPUEmulator<M>, text: &'a str) -> Result<(u16, &'a str)> {
+        let (expression, rest) = Expression::parse_prefix(text)?;
+        Ok((expression.address(emulator, self.symbols.as_ref())?, rest))
+    }
+
+    // Every expression in `text`, in order.
+    fn parse_values<M: VirtualMemory>(&self, emulator: &CPUEmulator<M>, text: &str) -> Result<Vec<i64>> {
+        let mut values = vec![];
+        let mut rest = text;
+        while !rest.trim().is_empty() {
+            let (expression, remainder) = Expression::parse_prefix(rest)?;
+            values.push(expression.evaluate(emulator, self.symbols.as_ref())?);
+            rest = remainder;
+        }
+        Ok(values)
+    }
+
+    fn parse_bytes<M: VirtualMemory>(&self, emulator: &CPUEmulator<M>, text: &str) -> Result<Vec<u8>> {
+        let bytes = self
+            .parse_values(emulator, text)?
+            .into_iter()
+            .map(|value| u8::try_from(value).map_err(|_| R6502Error::BadCommand(format!("{:#x} is not a byte", value))))
+            .collect::<Result<Vec<u8>>>()?;
+        match bytes.is_empty() {
+            true => Err(R6502Error::BadCommand("No bytes given".to_owned())),
+            false => Ok(bytes),
+        }
+    }
+
+    // Inclusive range given as two addresses, and what follows it.
+    fn parse_range<'a, M: VirtualMemory>(&self, emulator: &CPUEmulator<M>, text: &'a str) -> Result<(u16, u16, &'a str)> {
+        let (start, rest) = self.parse_address(emulator, text)?;
+        if rest.trim().is_empty() {
+            return Err(R6502Error::BadCommand("Expected an end address".to_owned()));
+        }
+        let (end, rest) = self.parse_address(emulator, rest)?;
+        if end < start {
+            return Err(R6502Error::BadCommand(format!("Range {:04X}-{:04X} ends before it starts", start, end)));
+        }
+        Ok((start, end, rest))
+    }
+
+    fn parse_count(token: Option<&str>, default: usize) -> Result<usize> {
+        match token {
+            Some(token) => token.parse().map_err(|_| R6502Error::BadCommand(format!("Bad count {}", token))),
+            None => Ok(default),
+        }
+    }
+
+    /// Runs one command line, writing its output to `out`. Returns an action for
+    /// commands that leave the monitor.
+    pub fn execute<M: VirtualMemory, W: Write>(&mut self, emulator: &mut CPUEmulator<M>, line: &str, out: &mut W) -> Result<Option<MonitorAction>> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let (command, arguments) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let arguments = arguments.trim();
+        match command {
+            "r" => write!(out, "{}", self.status(emulator))?,
+            "d" => {
+                let (address, rest) = match arguments.is_empty() {
+                    false => self.parse_address(emulator, arguments)?,
+                    true => (self.next.unwrap_or(emulator.state.pc), ""),
+                };
+                let (listing, next) = self.disassembly(emulator, address, Self::parse_count(rest.split_whitespace().next(), 10)?);
+                write!(out, "{}", listing)?;
+                self.next = Some(next);
+            }
+            "m" => {
+                let (address, rest) = match arguments.is_empty() {
+                    false => self.parse_address(emulator, arguments)?,
+                    true => (self.next.unwrap_or(emulator.state.pc), ""),
+                };
+                let length = Self::parse_count(rest.split_whitespace().next(), 64)?;
+                for row in (0..length).step_by(16) {
+                    let start = address.wrapping_add(row as u16);
+                    let bytes: Vec<u8> = (0..16.min(length - row)).map(|offset| emulator.peek(start.wrapping_add(offset as u16))).collect();
+                    let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                    let text = self.charset.decode_bytes(&bytes);
+                    writeln!(out, "{:04X}  {:<47}  {}", start, hex.join(" "), text)?;
+                }
+                self.next = Some(address.wrapping_add(length as u16));
+            }
+            ">" => {
+                let (address, rest) = self.parse_address(emulator, arguments)?;
+                let bytes = self.parse_bytes(emulator, rest)?;
+                for (offset, byte) in bytes.iter().enumerate() {
+                    emulator.poke(address.wrapping_add(offset as u16), *byte);
+                }
+                self.next = Some(address);
+            }
+            "f" | "fill" => {
+                let (start, end, rest) = self.parse_range(emulator, arguments)?;
+                let pattern = self.parse_bytes(emulator, rest)?;
+                for (address, byte) in (start..=end).zip(pattern.iter().cycle()) {
+                    emulator.poke(address, *byte);
+                }
+            }
+            "t" | "copy" => {
+                let (start, end, rest) = self.parse_range(emulator, arguments)?;
+                let (destination, _) = self.parse_address(emulator, rest)?;
+                // Read everything first so overlapping ranges copy like memmove.
+                let bytes: Vec<u8> = (start..=end).map(|address| emulator.peek(address)).collect();
+                for (offset, byte) in bytes.iter().enumerate() {
+                    emulator.poke(destination.wrapping_add(offset as u16), *byte);
+                }
+            }
+            "find" => {
+                let (start, end, rest) = self.parse_range(emulator, arguments)?;
+                let rest = rest.trim();
+                let quoted = |text: &str| text.strip_prefix('"').and_then(|text| text.strip_suffix('"')).map(str::to_owned);
+                let found = if let Some(text) = quoted(rest) {
+                    search::find_text(emulator, start, end, &text, Charset::Ascii)
+                } else if let Some(text) = rest.strip_prefix('p').and_then(quoted) {
+                    search::find_text(emulator, start, end, &text, Charset::Petscii)
+                } else if let Some(text) = rest.strip_prefix('s').and_then(quoted) {
+                    search::find_text(emulator, start, end, &text, Charset::ScreenCode)
+                } else if let Some(value) = rest.strip_prefix("w ") {
+                    let (value, _) = self.parse_address(emulator, value)?;
+                    search::find_word(emulator, start, end, value)
+                } else {
+                    search::find_bytes(emulator, start, end, &self.parse_bytes(emulator, rest)?)
+                };
+                for address in found.iter() {
+                    writeln!(out, "{}", number_format().word(*address))?;
+                }
+                writeln!(out, "{} found", found.len())?;
+            }
+            "fmt" => match arguments {
+                "" => writeln!(out, "{:?}", number_format())?,
+                name => set_number_format(name.parse().map_err(R6502Error::BadCommand)?),
+            },
+            "cs" => match arguments {
+                "" => writeln!(out, "{:?}", self.charset)?,
+                name => self.charset = name.parse().map_err(R6502Error::BadCommand)?,
+            },
+            "s" => {
+                for _ in 0..Self::parse_count(arguments.split_whitespace().next(), 1)? {
+                    if emulator.execute_next_instruction().is_err() {
+                        writeln!(out, "stopped")?;
+                        break;
+                    }
+                }
+                self.next = None;
+                write!(out, "{}", self.status(emulator))?;
+            }
+            "sf" | "sl" => {
+                for _ in 0..Self::parse_count(arguments.split_whitespace().next(), 1)? {
+                    let reason = match command {
+                        "sf" => emulator.step_frame()?,
+                        _ => emulator.step_scanline()?,
+                    };
+                    if reason != StopReason::Stepped {
+                        writeln!(out, "stopped: {:?}", reason)?;
+                        break;
+                    }
+                }
+                self.next = None;
+                write!(out, "{}", self.status(emulator))?;
+            }
+            "b" => {
+                if arguments.is_empty() {
+                    return Err(R6502Error::BadCommand("b needs an address".to_owned()));
+                }
+                let (address, rest) = self.parse_address(emulator, arguments)?;
+                let rest = rest.trim();
+                if let Some(condition) = rest.strip_prefix("if ") {
+                    let condition: Expression = condition.parse()?;
+                    emulator.add_breakpoint(address);
+                    writeln!(out, "breakpoint at {} if {} set", number_format().word(address), condition)?;
+                    self.conditions.insert(address, condition);
+                } else if !rest.is_empty() {
+                    return Err(R6502Error::BadCommand(format!("Unexpected {} after the address", rest)));
+                } else if emulator.remove_breakpoint(address) {
+                    self.conditions.remove(&address);
+                    writeln!(out, "breakpoint at {} removed", number_format().word(address))?;
+                } else {
+                    emulator.add_breakpoint(address);
+                    writeln!(out, "breakpoint at {} set", number_format().word(address))?;
+                }
+            }
+            "bl" => {
+                let mut breakpoints: Vec<u16> = emulator.breakpoints().copied().collect();
+                breakpoints.sort();
+                for address in breakpoints {
+                    match self.conditions.get(&address) {
+                        Some(condition) => writeln!(out, "{} if {}", number_format().word(address), condition)?,
+                        None => writeln!(out, "{}", number_format().word(address))?,
+                    }
+                }
+            }
+            "e" => {
+                let value = arguments.parse::<Expression>()?.evaluate(emulator, self.symbols.as_ref())?;
+                match value {
+                    0.. => writeln!(out, "${:04X}  +{}  %{:b}", value, value, value)?,
+                    _ => writeln!(out, "{}", value)?,
+                }
+            }
+            "v" => match arguments.split_once(char::is_whitespace) {
+                Some((vector, target)) => {
+                    let vector: Vector = vector.parse().map_err(R6502Error::BadCommand)?;
+                    let (target, _) = self.parse_address(emulator, target)?;
+                    emulator.set_vector(vector, target);
+                }
+                None if arguments.is_empty() => {
+                    let vectors = emulator.vectors();
+                    for vector in Vector::ALL {
+                        let target = vectors.get(vector);
+                        let name = self.symbols.as_ref().and_then(|symbols| symbols.name_for(target)).map(|name| format!("  {}", name)).unwrap_or_default();
+                        writeln!(out, "{:<5} {}  {}{}", vector.to_string(), number_format().word(vector.address()), number_format().word(target), name)?;
+                    }
+                }
+                None => return Err(R6502Error::BadCommand("Expected v or v vector addr".to_owned())),
+            },
+            "stats" => match emulator.interrupt_stats.as_ref() {
+                Some(stats) => write!(out, "{}", stats.report())?,
+                None => {
+                    emulator.interrupt_stats = Some(InterruptStats::new());
+                    writeln!(out, "collecting interrupt statistics from now on")?;
+                }
+            },
+            "zp" => match emulator.zero_page.as_ref() {
+                Some(usage) => write!(out, "{}", usage.report(self.symbols.as_ref()))?,
+                None => {
+                    emulator.zero_page = Some(ZeroPageUsage::new());
+                    writeln!(out, "collecting zero page usage from now on")?;
+                }
+            },
+            "who" => {
+                if arguments.is_empty() {
+                    return Err(R6502Error::BadCommand("who needs an address".to_owned()));
+                }
+                let (start, rest) = self.parse_address(emulator, arguments)?;
+                let end = match rest.trim() {
+                    "" => start,
+                    rest => self.parse_address(emulator, rest)?.0,
+                };
+                let format = number_format();
+                if !emulator.watches.as_ref().is_some_and(|watches| (start..=end).all(|address| watches.contains(address))) {
+                    emulator.watch_region(start, end);
+                    writeln!(out, "watching {}-{} from now on", format.word(start), format.word(end))?;
+                }
+                for writer in emulator.who_wrote(start, end) {
+                    let (listing, _) = self.disassembly(emulator, writer.pc, 1);
+                    writeln!(
+                        out,
+                        "{:>6}x  last {} <- {} at cycle {}  {}",
+                        writer.writes,
+                        format.word(writer.address),
+                        format.byte(writer.value),
+                        writer.last,
+                        listing.lines().last().unwrap_or_default()
+                    )?;
+                }
+            }
+            "c" => return Ok(Some(MonitorAction::Continue)),
+            "q" => return Ok(Some(MonitorAction::Quit)),
+            "h" | "?" => write!(out, "{}", HELP)?,
+            _ => return Err(R6502Error::BadCommand(format!("Unknown command {}, h for help", command))),
+        }
+        Ok(None)
+    }
+
+    /// Whether a breakpoint at the current pc should stop the emulator: always,
+    /// unless it has a condition that is false. Conditions that fail to evaluate
+    /// stop, so the problem shows up in the monitor.
+    pub fn breaks<M: VirtualMemory>(&self, emulator: &CPUEmulator<M>) -> bool {
+        match self.conditions.get(&emulator.state.pc) {
+            Some(condition) => condition.evaluate(emulator, self.symbols.as_ref()).map_or(true, |value| value != 0),
+            None => true,
+        }
+    }
+
+    /// Shows the current status and reads commands until one leaves the monitor.
+    /// End of input counts as quitting.
+    pub fn interact<M: VirtualMemory, R: BufRead, W: Write>(&mut self, emulator: &mut CPUEmulator<M>, input: R, mut out: W) -> Result<MonitorAction> {
+        self.next = None;
+        write!(out, "{}", self.status(emulator))?;
+        let mut lines = input.lines();
+        loop {
+            write!(out, "> ")?;
+            out.flush()?;
+            let Some(line) = lines.next() else {
+                return Ok(MonitorAction::Quit);
+            };
+            match self.execute(emulator, &line?, &mut out) {
+                Ok(Some(action)) => return Ok(action),
+                Ok(None) => (),
+                Err(error) => writeln!(out, "{}", error)?,
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::path::Path;
+
+use crate::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason, VirtualMemory};
+use crate::error::{R6502Error, Result};
+use crate::input::{Buttons, Controllers, InputScript, ScriptPlayer, PLAYERS};
+use crate::state::{Registers, SystemFlags};
+
+// Movies record a play session: the machine as it was when recording started,
+// the input of every frame as an input script, and the emulator's state hash
+// every `interval` frames. Playing a movie back restores the start, replays
+// the input and fails at the first checksum that doesn't match, which catches
+// emulator changes that make runs nondeterministic.
+//
+// Only the CPU registers and RAM are captured, not the internal state of
+// devices, so record from a machine whose devices are in their power-on state.
+//
+// A .r6m file is little endian:
+//
+//     "R6M" 1                       magic and version
+//     pc:u16 a x y s p              registers at the start
+//     length:u32 memory             RAM at the start
+//     frames:u64 interval:u64       length of the movie, frames between checksums
+//     count:u32 (frame:u64 sum:u64)*
+//     length:u32 script             input script text, see `input`
+
+const MAGIC: &[u8; 4] = b"R6M\x01";
+
+/// Checksum every second at 60 frames per second.
+pub const DEFAULT_INTERVAL: u64 = 60;
+
+/// Memory that can be captured into and restored from a flat image.
+pub trait MemoryImage {
+    fn image(&self) -> Vec<u8>;
+    fn restore_image(&mut self, image: &[u8]);
+}
+
+impl MemoryImage for DefaultVirtualMemory {
+    fn image(&self) -> Vec<u8> {
+        self.bytes().to_vec()
+    }
+
+    fn restore_image(&mut self, image: &[u8]) {
+        let bytes = self.bytes_mut();
+        let length = image.len().min(bytes.len());
+        bytes[..length].copy_from_slice(&image[..length]);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub registers: Registers,
+    pub memory: Vec<u8>,
+    pub script: InputScript,
+    /// Frames recorded.
+    pub frames: u64,
+    pub interval: u64,
+    /// (frame, checksum) after each `interval` frames.
+    pub checksums: Vec<(u64, u64)>,
+}
+
+impl Movie {
+    /// Restores the start of the movie into `emulator` and plays it to the end,
+    /// checking each checksum on the way. Returns early if the emulator stops
+    /// for another reason, e.g. a breakpoint.
+    pub fn play<M: VirtualMemory + MemoryImage + Controllers>(&self, emulator: &mut CPUEmulator<M>) -> Result<StopReason> {
+        emulator.state.set_registers(self.registers);
+        emulator.state.running = true;
+        emulator.with_memory(|memory| memory.restore_image(&self.memory));
+        emulator.rebase_state_hash();
+        let mut player = ScriptPlayer::new(self.script.clone());
+        let mut checksums = self.checksums.iter().peekable();
+        while player.frame() < self.frames {
+            match player.play_frame(emulator)? {
+                StopReason::Stepped => (),
+                reason => return Ok(reason),
+            }
+            if let Some((frame, expected)) = checksums.next_if(|(frame, _)| *frame == player.frame()) {
+                let actual = emulator.state_hash();
+                if actual != *expected {
+                    return Err(R6502Error::Desync { frame: *frame, expected: *expected, actual });
+                }
+            }
+        }
+        Ok(StopReason::Stepped)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend(self.registers.pc.to_le_bytes());
+        out.extend([self.registers.a, self.registers.x, self.registers.y, self.registers.s, self.registers.p.bits()]);
+        out.extend((self.memory.len() as u32).to_le_bytes());
+        out.extend(&self.memory);
+        out.extend(self.frames.to_le_bytes());
+        out.extend(self.interval.to_le_bytes());
+        out.extend((self.checksums.len() as u32).to_le_bytes());
+        for (frame, sum) in self.checksums.iter() {
+            out.extend(frame.to_le_bytes());
+            out.extend(sum.to_le_bytes());
+        }
+        let script = self.script.to_string();
+        out.extend((script.len() as u32).to_le_bytes());
+        out.extend(script.as_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, position: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err(R6502Error::BadMovie("Not an r6502 movie".to_owned()));
+        }
+        let pc = reader.u16()?;
+        let [a, x, y, s, p] = reader.take(5)?.try_into().unwrap();
+        let registers = Registers { pc, a, x, y, s, p: SystemFlags::from(p) };
+        let length = reader.u32()? as usize;
+        let memory = reader.take(length)?.to_vec();
+        let frames = reader.u64()?;
+        let interval = reader.u64()?;
+        let count = reader.u32()?;
+        let checksums = (0..count).map(|_| Ok((reader.u64()?, reader.u64()?))).collect::<Result<Vec<_>>>()?;
+        let length = reader.u32()? as usize;
+        let script = std::str::from_utf8(reader.take(length)?).map_err(|_| R6502Error::BadMovie("Input script is not UTF-8".to_owned()))?;
+        Ok(Self { registers, memory, script: script.parse()?, frames, interval, checksums })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, length: usize) -> Result<&[u8]> {
+        let bytes = self.bytes.get(self.position..self.position + length).ok_or_else(|| R6502Error::BadMovie("File is truncated".to_owned()))?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+/// Records a movie while the caller supplies the input a frame at a time.
+pub struct MovieRecorder {
+    movie: Movie,
+    previous: Option<[Buttons; PLAYERS]>,
+}
+
+impl MovieRecorder {
+    /// Captures the start of the movie from `emulator` as it is now.
+    pub fn start<M: VirtualMemory + MemoryImage>(emulator: &mut CPUEmulator<M>, interval: u64) -> Self {
+        emulator.rebase_state_hash();
+        let movie = Movie {
+            registers: emulator.state.registers(),
+            memory: emulator.with_memory(|memory| memory.image()),
+            script: InputScript::new(),
+            frames: 0,
+            interval: interval.max(1),
+            checksums: vec![],
+        };
+        Self { movie, previous: None }
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.movie.frames
+    }
+
+    /// Runs one frame with `buttons` held. The frame is only recorded if it ran to the end.
+    pub fn record_frame<M: VirtualMemory + MemoryImage + Controllers>(&mut self, emulator: &mut CPUEmulator<M>, buttons: [Buttons; PLAYERS]) -> Result<StopReason> {
+        emulator.with_memory(|memory| memory.set_controllers(&buttons));
+        let reason = emulator.step_frame()?;
+        if reason != StopReason::Stepped {
+            return Ok(reason);
+        }
+        if self.previous != Some(buttons) {
+            self.movie.script.set(self.movie.frames, buttons);
+            self.previous = Some(buttons);
+        }
+        self.movie.frames += 1;
+        if self.movie.frames.is_multiple_of(self.movie.interval) {
+            self.movie.checksums.push((self.movie.frames, emulator.state_hash()));
+        }
+        Ok(reason)
+    }
+
+    pub fn finish(self) -> Movie {
+        self.movie
+    }
+}
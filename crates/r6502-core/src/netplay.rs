@@ -0,0 +1,129 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+use crate::error::{R6502Error, Result};
+use crate::input::{Buttons, Controllers, PLAYERS};
+
+// Lockstep synchronisation of two emulators running the same machine, e.g. on
+// two hosts for netplay, or two builds side by side for differential testing.
+// Before every frame each side sends its local player's input and receives the
+// other's, so both run the frame with the same controllers. Every `interval`
+// frames the packets also carry the emulator's state hash, and a mismatch stops
+// both sides with a desync error.
+//
+// How packets get across is up to the caller's `Transport`. A packet is 18
+// bytes, little endian: frame:u64, buttons:u8, has checksum:u8, checksum:u64.
+
+/// Frames between checksum exchanges by default.
+pub const DEFAULT_INTERVAL: u64 = 60;
+
+const PACKET_LENGTH: usize = 18;
+
+/// Delivers packets to the peer in order, blocking in `receive` until one arrives.
+pub trait Transport {
+    fn send(&mut self, packet: &[u8]) -> Result<()>;
+    fn receive(&mut self) -> Result<Vec<u8>>;
+}
+
+/// Transport between two lockstep sessions in the same process.
+pub struct ChannelTransport {
+    sender: Sender<Vec<u8>>,
+    receiver: Receiver<Vec<u8>>,
+}
+
+impl ChannelTransport {
+    /// Two connected ends.
+    pub fn pair() -> (Self, Self) {
+        let (to_right, from_left) = channel();
+        let (to_left, from_right) = channel();
+        (Self { sender: to_right, receiver: from_right }, Self { sender: to_left, receiver: from_left })
+    }
+}
+
+impl Transport for ChannelTransport {
+    fn send(&mut self, packet: &[u8]) -> Result<()> {
+        self.sender.send(packet.to_vec()).map_err(|_| R6502Error::Netplay("Peer hung up".to_owned()))
+    }
+
+    fn receive(&mut self) -> Result<Vec<u8>> {
+        self.receiver.recv().map_err(|_| R6502Error::Netplay("Peer hung up".to_owned()))
+    }
+}
+
+/// Input for one frame, as exchanged between peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramePacket {
+    pub frame: u64,
+    pub buttons: Buttons,
+    /// Checksum of the machine before the frame runs, on checksum frames.
+    pub checksum: Option<u64>,
+}
+
+impl FramePacket {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.frame.to_le_bytes().to_vec();
+        out.push(self.buttons.bits());
+        out.push(self.checksum.is_some() as u8);
+        out.extend(self.checksum.unwrap_or(0).to_le_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes: &[u8; PACKET_LENGTH] = bytes
+            .try_into()
+            .map_err(|_| R6502Error::Netplay(format!("Expected a {} byte packet, got {}", PACKET_LENGTH, bytes.len())))?;
+        Ok(Self {
+            frame: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            buttons: Buttons::from_bits_retain(bytes[8]),
+            checksum: (bytes[9] != 0).then(|| u64::from_le_bytes(bytes[10..18].try_into().unwrap())),
+        })
+    }
+}
+
+/// One side of a lockstep session.
+pub struct Lockstep<T: Transport> {
+    transport: T,
+    player: usize,
+    frame: u64,
+    interval: u64,
+}
+
+impl<T: Transport> Lockstep<T> {
+    /// Starts a session in which the local controller is `player`. Both sides
+    /// have to start from the same machine state with the same `interval`.
+    pub fn new<M: VirtualMemory>(transport: T, player: usize, emulator: &mut CPUEmulator<M>, interval: u64) -> Self {
+        emulator.rebase_state_hash();
+        Self { transport, player: player.min(PLAYERS - 1), frame: 0, interval: interval.max(1) }
+    }
+
+    /// Frames run so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Exchanges input with the peer and runs the next frame with both players'
+    /// buttons. Fails if the peer is on another frame or its checksum differs.
+    pub fn advance<M: VirtualMemory + Controllers>(&mut self, emulator: &mut CPUEmulator<M>, local: Buttons) -> Result<StopReason> {
+        let local_checksum = self.frame.is_multiple_of(self.interval).then(|| emulator.state_hash());
+        let packet = FramePacket { frame: self.frame, buttons: local, checksum: local_checksum };
+        self.transport.send(&packet.to_bytes())?;
+        let remote = FramePacket::from_bytes(&self.transport.receive()?)?;
+        if remote.frame != self.frame {
+            return Err(R6502Error::Netplay(format!("Peer is on frame {}, expected {}", remote.frame, self.frame)));
+        }
+        if let (Some(actual), Some(expected)) = (local_checksum, remote.checksum) {
+            if actual != expected {
+                return Err(R6502Error::Desync { frame: self.frame, expected, actual });
+            }
+        }
+
+        let mut buttons = [remote.buttons; PLAYERS];
+        buttons[self.player] = local;
+        emulator.with_memory(|memory| memory.set_controllers(&buttons));
+        let reason = emulator.step_frame()?;
+        if reason == StopReason::Stepped {
+            self.frame += 1;
+        }
+        Ok(reason)
+    }
+}
@@ -0,0 +1,286 @@
+#[cfg(feature = "pretty")]
+use colored::Colorize;
+#[cfg(feature = "pretty")]
+use tabled::{builder::Builder, settings::Style, Table};
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::emulator::{CPUEmulatorBuilder, DefaultVirtualMemory};
+use crate::instructions::{AddressingMode, Instruction, OpCode};
+use crate::state::{SystemFlags, SystemState};
+
+// Reference data for every opcode byte, taken from what the emulator does so the
+// generated documentation can't drift from it. Lengths and addressing modes come
+// from the decoder, whether an opcode is implemented from the executor's handler
+// table, and the flags it affects from running it. The cycle counts below are
+// the table the executor charges cycles from.
+
+// Base NMOS cycle counts, indexed by opcode byte.
+const CYCLES: [u8; 256] = [
+    7, 6, 2, 8, 3, 3, 5, 5, 3, 2, 2, 2, 4, 4, 6, 6, // 0x
+    2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 1x
+    6, 6, 2, 8, 3, 3, 5, 5, 4, 2, 2, 2, 4, 4, 6, 6, // 2x
+    2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 3x
+    6, 6, 2, 8, 3, 3, 5, 5, 3, 2, 2, 2, 3, 4, 6, 6, // 4x
+    2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 5x
+    6, 6, 2, 8, 3, 3, 5, 5, 4, 2, 2, 2, 5, 4, 6, 6, // 6x
+    2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // 7x
+    2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4, // 8x
+    2, 6, 2, 6, 4, 4, 4, 4, 2, 5, 2, 5, 5, 5, 5, 5, // 9x
+    2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4, // Ax
+    2, 5, 2, 5, 4, 4, 4, 4, 2, 4, 2, 4, 4, 4, 4, 4, // Bx
+    2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6, // Cx
+    2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // Dx
+    2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6, // Ex
+    2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7, // Fx
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodeInfo {
+    pub byte: u8,
+    pub instruction: Instruction,
+    /// Encoded length including the opcode byte.
+    pub length: u16,
+    /// Base cycle count.
+    pub cycles: u8,
+    /// Whether crossing a page (or taking a branch) adds cycles.
+    pub page_cross_penalty: bool,
+    /// Flags the instruction can modify.
+    pub flags: SystemFlags,
+    /// Part of the documented NMOS instruction set.
+    pub legal: bool,
+    /// Executed by r6502 rather than rejected as unimplemented.
+    pub implemented: bool,
+}
+
+impl OpcodeInfo {
+    pub fn mnemonic(&self) -> String {
+        self.instruction.opcode.to_string()
+    }
+
+    pub fn mode_name(&self) -> &'static str {
+        self.instruction.mode.map(|mode| mode.short_name()).unwrap_or("impl")
+    }
+
+    /// Flags in `NV-BDIZC` order, with `-` for flags left untouched.
+    pub fn flag_string(&self) -> String {
+        [
+            (SystemFlags::negative, 'N'),
+            (SystemFlags::overflow, 'V'),
+            (SystemFlags::expansion, '-'),
+            (SystemFlags::break_command, 'B'),
+            (SystemFlags::decimal, 'D'),
+            (SystemFlags::interrupt_disable, 'I'),
+            (SystemFlags::zero, 'Z'),
+            (SystemFlags::carry, 'C'),
+        ]
+        .iter()
+        .map(|(flag, name)| if self.flags.contains(*flag) { *name } else { '-' })
+        .collect()
+    }
+}
+
+/// Base cycle count of `byte`, without page crossing or branch penalties.
+pub fn base_cycles(byte: u8) -> u8 {
+    CYCLES[byte as usize]
+}
+
+/// Whether an indexed access that carries into the high byte costs `byte` an
+/// extra cycle. Read instructions take the short path unless the index carries;
+/// stores and read-modify-write always pay for the fixup.
+pub fn indexing_penalty(byte: u8) -> bool {
+    let cycles = CYCLES[byte as usize];
+    match Instruction::from(byte).mode {
+        Some(AddressingMode::DirectAbsoluteX | AddressingMode::DirectAbsoluteY) => cycles == 4,
+        Some(AddressingMode::IndirectZeroPageY) => cycles == 5,
+        _ => false,
+    }
+}
+
+pub fn describe(byte: u8) -> OpcodeInfo {
+    let instruction = Instruction::from(byte);
+    let cycles = CYCLES[byte as usize];
+    let page_cross_penalty = matches!(instruction.mode, Some(AddressingMode::Relative)) || indexing_penalty(byte);
+    OpcodeInfo {
+        byte,
+        instruction,
+        length: instruction.length(),
+        cycles,
+        page_cross_penalty,
+        flags: flags_affected(byte),
+        legal: is_legal(instruction.opcode),
+        implemented: instruction.opcode.is_implemented(),
+    }
+}
+
+pub fn describe_all() -> Vec<OpcodeInfo> {
+    (0..=255u8).map(describe).collect()
+}
+
+/// Flags `byte` changed when run on a scratch machine over a spread of register,
+/// memory and flag values. Opcodes without a handler change nothing.
+fn flags_affected(byte: u8) -> SystemFlags {
+    static FLAGS: OnceLock<[SystemFlags; 256]> = OnceLock::new();
+    FLAGS.get_or_init(|| {
+        let mut flags = [SystemFlags::empty(); 256];
+        for (byte, flags) in flags.iter_mut().enumerate() {
+            *flags = observe_flags(byte as u8);
+        }
+        flags
+    })[byte as usize]
+}
+
+// Values that land on both sides of every flag: zero, sign, signed overflow and carry out.
+const PROBES: [u8; 6] = [0x00, 0x01, 0x40, 0x7F, 0x80, 0xFF];
+
+fn observe_flags(byte: u8) -> SystemFlags {
+    let instruction = Instruction::from(byte);
+    if !instruction.opcode.is_implemented() {
+        return SystemFlags::empty();
+    }
+    let settable = SystemFlags::all() - SystemFlags::break_command - SystemFlags::expansion;
+    let mut emulator = CPUEmulatorBuilder::default()
+        .state(SystemState::default())
+        .memory(Arc::new(Mutex::new(DefaultVirtualMemory::default())))
+        .build()
+        .unwrap();
+    // Alternating patterns put N and C, or V and Z, on opposite sides, as ROR needs.
+    let flag_probes = [0x00, 0xFF, 0x55, 0xAA].map(|bits| SystemFlags::from_bits_retain(bits) & settable);
+    let mut changed = SystemFlags::empty();
+    for register in PROBES {
+        for operand in PROBES {
+            for p in flag_probes {
+                // With all of memory and the operand bytes holding the same value, that's
+                // what the instruction reads whatever its addressing mode, and what it
+                // pulls off the stack. None of the probes address the instruction itself.
+                emulator.with_memory(|memory| {
+                    let bytes = memory.bytes_mut();
+                    bytes.fill(operand);
+                    bytes[0x0300] = byte;
+                });
+                emulator.state = SystemState { pc: 0x0301, a: register, x: register, y: register, s: 0xFD, p, running: true, ..Default::default() };
+                let _ = instruction.execute(&mut emulator);
+                changed |= p ^ emulator.state.p;
+            }
+        }
+    }
+    changed
+}
+
+fn is_legal(opcode: OpCode) -> bool {
+    !matches!(
+        opcode,
+        OpCode::ALR | OpCode::ANC | OpCode::ANC2 | OpCode::ANE | OpCode::ARR | OpCode::DCP
+            | OpCode::ISC | OpCode::LAS | OpCode::LAX | OpCode::LXA | OpCode::RLA | OpCode::RRA
+            | OpCode::SAX | OpCode::SBX | OpCode::SHA | OpCode::SHX | OpCode::SHY | OpCode::SLO
+            | OpCode::SRE | OpCode::TAS | OpCode::USBC | OpCode::INOP | OpCode::KIL
+            | OpCode::BadInstruction | OpCode::UnknownInstruction
+    )
+}
+
+/// Renders the 16x16 opcode matrix followed by a per-opcode reference table.
+pub fn markdown() -> String {
+    let infos = describe_all();
+    let mut out = String::new();
+    out.push_str("|    |");
+    for low in 0..16 {
+        out.push_str(&format!(" x{:X} |", low));
+    }
+    out.push_str("\n|----|");
+    out.push_str(&"----|".repeat(16));
+    out.push('\n');
+    for high in 0..16 {
+        out.push_str(&format!("| {:X}x |", high));
+        for low in 0..16 {
+            let info = &infos[high * 16 + low];
+            let marker = match (info.legal, info.implemented) {
+                (_, false) => "†",
+                (false, true) => "*",
+                (true, true) => "",
+            };
+            out.push_str(&format!(" {}{} {} |", info.mnemonic(), marker, info.mode_name()));
+        }
+        out.push('\n');
+    }
+    out.push_str("\n`*` undocumented, `†` not implemented\n\n");
+    out.push_str("| Opcode | Mnemonic | Mode | Bytes | Cycles | Flags | Legal | Implemented |\n");
+    out.push_str("|--------|----------|------|-------|--------|-------|-------|-------------|\n");
+    for info in infos.iter() {
+        out.push_str(&format!(
+            "| ${:02X} | {} | {} | {} | {}{} | `{}` | {} | {} |\n",
+            info.byte,
+            info.mnemonic(),
+            info.mode_name(),
+            info.length,
+            info.cycles,
+            if info.page_cross_penalty { "+" } else { "" },
+            info.flag_string(),
+            if info.legal { "yes" } else { "no" },
+            if info.implemented { "yes" } else { "no" },
+        ));
+    }
+    out
+}
+
+/// Renders the 16x16 opcode matrix as an HTML table. Cells carry `legal`,
+/// `illegal` and `unimplemented` classes for styling.
+pub fn html() -> String {
+    let infos = describe_all();
+    let mut out = String::from("<table class=\"opcodes\">\n<tr><th></th>");
+    for low in 0..16 {
+        out.push_str(&format!("<th>x{:X}</th>", low));
+    }
+    out.push_str("</tr>\n");
+    for high in 0..16 {
+        out.push_str(&format!("<tr><th>{:X}x</th>", high));
+        for low in 0..16 {
+            let info = &infos[high * 16 + low];
+            let class = match (info.legal, info.implemented) {
+                (_, false) => "unimplemented",
+                (false, true) => "illegal",
+                (true, true) => "legal",
+            };
+            out.push_str(&format!(
+                "<td class=\"{}\" title=\"${:02X} {} bytes, {} cycles, flags {}\">{}<br>{}</td>",
+                class,
+                info.byte,
+                info.length,
+                info.cycles,
+                info.flag_string(),
+                info.mnemonic(),
+                info.mode_name(),
+            ));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</table>\n");
+    out
+}
+
+/// Builds the 16x16 opcode matrix as a terminal table. Documented opcodes are green,
+/// undocumented ones yellow and anything the executor rejects red.
+#[cfg(feature = "pretty")]
+pub fn matrix_table() -> Table {
+    let infos = describe_all();
+    let mut builder = Builder::default();
+    let mut header = vec![String::new()];
+    header.extend((0..16).map(|low| format!("x{:X}", low)));
+    builder.push_record(header);
+    for high in 0..16 {
+        let mut row = vec![format!("{:X}x", high)];
+        for low in 0..16 {
+            let info = &infos[high * 16 + low];
+            let cell = format!("{}\n{}", info.mnemonic(), info.mode_name());
+            let cell = match (info.legal, info.implemented) {
+                (_, false) => cell.red(),
+                (false, true) => cell.yellow(),
+                (true, true) => cell.green(),
+            };
+            row.push(cell.to_string());
+        }
+        builder.push_record(row);
+    }
+    let mut table = builder.build();
+    table.with(Style::modern());
+    table
+}
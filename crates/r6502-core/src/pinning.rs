@@ -0,0 +1,97 @@
+// Regions of memory the CPU must keep its hands off for a while, such as the
+// page a device is still reading for a DMA transfer. A CPU write into a pinned
+// region is either carried out and reported as an anomaly, to find the code
+// racing the device, or held back until the pin is released, the way a bus
+// arbiter would keep the CPU waiting. Pins end when they are unpinned or, if
+// given a length, once that many cycles have passed. Only writes made by
+// instructions are checked, not pokes from the host.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinPolicy {
+    /// Let the write through and record a [`PinnedWrite`](crate::diagnostics::AnomalyKind::PinnedWrite).
+    Report,
+    /// Hold the write back until the region is released, recording a
+    /// [`DelayedWrite`](crate::diagnostics::AnomalyKind::DelayedWrite).
+    Delay,
+}
+
+/// Identifies a pin for [`CPUEmulator::unpin`](crate::emulator::CPUEmulator::unpin).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PinId(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pin {
+    pub id: PinId,
+    pub start: u16,
+    pub end: u16,
+    /// Clock the pin lapses at, or `None` to hold until unpinned.
+    pub until: Option<u64>,
+    pub policy: PinPolicy,
+}
+
+impl Pin {
+    fn covers(&self, address: u16, clock: u64) -> bool {
+        self.start <= address && address <= self.end && self.until.is_none_or(|until| clock < until)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Pins {
+    pins: Vec<Pin>,
+    next: u64,
+    /// Writes held back, oldest first.
+    delayed: Vec<(u16, u8)>,
+}
+
+impl Pins {
+    /// Pins `start..=end` until the clock reaches `until`, or indefinitely.
+    pub fn pin(&mut self, start: u16, end: u16, until: Option<u64>, policy: PinPolicy) -> PinId {
+        let id = PinId(self.next);
+        self.next += 1;
+        self.pins.push(Pin { id, start, end, until, policy });
+        id
+    }
+
+    /// Removes a pin, returning whether it was still there. Writes it held back
+    /// are then returned by the next [`Pins::release`].
+    pub fn unpin(&mut self, id: PinId) -> bool {
+        let count = self.pins.len();
+        self.pins.retain(|pin| pin.id != id);
+        self.pins.len() != count
+    }
+
+    pub fn pins(&self) -> &[Pin] {
+        &self.pins
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty() && self.delayed.is_empty()
+    }
+
+    /// What happens to a CPU write to `address` at `clock`. Holding back wins
+    /// where pins overlap.
+    pub fn policy(&self, address: u16, clock: u64) -> Option<PinPolicy> {
+        let mut covering = self.pins.iter().filter(|pin| pin.covers(address, clock));
+        let first = covering.next()?.policy;
+        Some(if covering.any(|pin| pin.policy == PinPolicy::Delay) { PinPolicy::Delay } else { first })
+    }
+
+    pub fn delay(&mut self, address: u16, value: u8) {
+        self.delayed.push((address, value));
+    }
+
+    pub fn delayed(&self) -> &[(u16, u8)] {
+        &self.delayed
+    }
+
+    /// Drops pins that lapsed by `clock` and returns, in order, the held back
+    /// writes no longer covered by a pin that delays.
+    pub fn release(&mut self, clock: u64) -> Vec<(u16, u8)> {
+        self.pins.retain(|pin| pin.until.is_none_or(|until| clock < until));
+        let (still_held, released) = std::mem::take(&mut self.delayed)
+            .into_iter()
+            .partition(|(address, _)| self.pins.iter().any(|pin| pin.policy == PinPolicy::Delay && pin.covers(*address, clock)));
+        self.delayed = still_held;
+        released
+    }
+}
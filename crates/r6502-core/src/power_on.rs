@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+// What RAM holds at power on. Real machines don't start zeroed: DRAM usually
+// comes up in blocks of $00 and $FF that depend on the chips and the board,
+// and SRAM is close to random. Software that reads memory before writing it
+// works on one machine and not on another, so backends can be filled with any
+// of these to flush such bugs out. Images and ROMs are loaded over the pattern.
+
+/// Bytes in each block of [`RamPattern::Alternating`] on a typical DRAM board.
+pub const DRAM_BLOCK: usize = 64;
+
+/// A power-on RAM pattern. In a machine config it is a table such as
+/// `ram = { pattern = "random", seed = 7 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "pattern", rename_all = "lowercase")]
+pub enum RamPattern {
+    #[default]
+    Zero,
+    /// Every byte $FF.
+    #[serde(rename = "ff")]
+    Ones,
+    /// `block` bytes of $00, then `block` bytes of $FF, and so on.
+    Alternating { block: usize },
+    /// The same bytes for the same seed, from splitmix64.
+    Random { seed: u64 },
+}
+
+impl RamPattern {
+    /// The $00/$FF blocks of a typical DRAM board, see [`DRAM_BLOCK`].
+    pub fn dram() -> Self {
+        Self::Alternating { block: DRAM_BLOCK }
+    }
+
+    /// Fills `bytes` with the pattern, starting it at the first byte.
+    pub fn fill(&self, bytes: &mut [u8]) {
+        match *self {
+            Self::Zero => bytes.fill(0x00),
+            Self::Ones => bytes.fill(0xFF),
+            Self::Alternating { block } => {
+                for (index, chunk) in bytes.chunks_mut(block.max(1)).enumerate() {
+                    chunk.fill(if index % 2 == 0 { 0x00 } else { 0xFF });
+                }
+            }
+            Self::Random { seed } => {
+                let mut state = seed;
+                for chunk in bytes.chunks_mut(8) {
+                    let length = chunk.len();
+                    chunk.copy_from_slice(&splitmix64(&mut state).to_le_bytes()[..length]);
+                }
+            }
+        }
+    }
+}
+
+// The generator behind the random pattern, also used by fault injection.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
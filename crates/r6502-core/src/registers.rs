@@ -0,0 +1,186 @@
+// Declarative descriptions of memory mapped device registers, used to turn a raw
+// value written to a register into its individual bitfields.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldFormat {
+    /// Single bit shown as on/off.
+    Flag,
+    Unsigned,
+    /// Two's complement, e.g. TIA horizontal motion.
+    Signed,
+    /// One name per possible value.
+    Named(&'static [&'static str]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub shift: u8,
+    pub width: u8,
+    pub format: FieldFormat,
+}
+
+impl Field {
+    pub fn raw(&self, value: u8) -> u8 {
+        (value >> self.shift) & (((1u16 << self.width) - 1) as u8)
+    }
+
+    pub fn decode(&self, value: u8) -> String {
+        let raw = self.raw(value);
+        let shown = match self.format {
+            FieldFormat::Flag => if raw != 0 { "on".to_owned() } else { "off".to_owned() },
+            FieldFormat::Unsigned => raw.to_string(),
+            FieldFormat::Signed => {
+                let sign = 1u8 << (self.width - 1);
+                ((raw ^ sign) as i16 - sign as i16).to_string()
+            }
+            FieldFormat::Named(names) => names.get(raw as usize).map(|name| name.to_string()).unwrap_or_else(|| raw.to_string()),
+        };
+        format!("{}={}", self.name, shown)
+    }
+}
+
+const fn flag(name: &'static str, bit: u8) -> Field {
+    Field { name, shift: bit, width: 1, format: FieldFormat::Flag }
+}
+
+const fn bits(name: &'static str, shift: u8, width: u8) -> Field {
+    Field { name, shift, width, format: FieldFormat::Unsigned }
+}
+
+const fn signed(name: &'static str, shift: u8, width: u8) -> Field {
+    Field { name, shift, width, format: FieldFormat::Signed }
+}
+
+const fn named(name: &'static str, shift: u8, width: u8, names: &'static [&'static str]) -> Field {
+    Field { name, shift, width, format: FieldFormat::Named(names) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register {
+    pub name: &'static str,
+    pub address: u16,
+    pub fields: &'static [Field],
+}
+
+impl Register {
+    /// Renders `value` field by field, e.g. `ball size=2, playfield reflect=on`.
+    pub fn decode(&self, value: u8) -> String {
+        self.fields.iter().map(|field| field.decode(value)).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// Finds the description of the register at `address`, if any.
+pub fn find(registers: &[Register], address: u16) -> Option<&Register> {
+    registers.iter().find(|register| register.address == address)
+}
+
+const NUSIZ: &[Field] = &[
+    named("copies", 0, 3, &["one", "two close", "two medium", "three close", "two wide", "double", "three medium", "quad"]),
+    bits("missile size", 4, 2),
+];
+const COLOR: &[Field] = &[bits("luminance", 1, 3), bits("hue", 4, 4)];
+const REFLECT: &[Field] = &[flag("reflect", 3)];
+const ENABLE: &[Field] = &[flag("enable", 1)];
+const DELAY: &[Field] = &[flag("delay", 0)];
+const MOTION: &[Field] = &[signed("motion", 4, 4)];
+
+pub const TIA: &[Register] = &[
+    Register { name: "VSYNC", address: 0x00, fields: &[flag("vsync", 1)] },
+    Register { name: "VBLANK", address: 0x01, fields: &[flag("vblank", 1), flag("latch inputs", 6), flag("dump paddles", 7)] },
+    Register { name: "NUSIZ0", address: 0x04, fields: NUSIZ },
+    Register { name: "NUSIZ1", address: 0x05, fields: NUSIZ },
+    Register { name: "COLUP0", address: 0x06, fields: COLOR },
+    Register { name: "COLUP1", address: 0x07, fields: COLOR },
+    Register { name: "COLUPF", address: 0x08, fields: COLOR },
+    Register { name: "COLUBK", address: 0x09, fields: COLOR },
+    Register {
+        name: "CTRLPF",
+        address: 0x0A,
+        fields: &[flag("playfield reflect", 0), flag("score", 1), flag("priority", 2), bits("ball size", 4, 2)],
+    },
+    Register { name: "REFP0", address: 0x0B, fields: REFLECT },
+    Register { name: "REFP1", address: 0x0C, fields: REFLECT },
+    Register { name: "AUDC0", address: 0x15, fields: &[bits("control", 0, 4)] },
+    Register { name: "AUDC1", address: 0x16, fields: &[bits("control", 0, 4)] },
+    Register { name: "AUDF0", address: 0x17, fields: &[bits("divider", 0, 5)] },
+    Register { name: "AUDF1", address: 0x18, fields: &[bits("divider", 0, 5)] },
+    Register { name: "AUDV0", address: 0x19, fields: &[bits("volume", 0, 4)] },
+    Register { name: "AUDV1", address: 0x1A, fields: &[bits("volume", 0, 4)] },
+    Register { name: "ENAM0", address: 0x1D, fields: ENABLE },
+    Register { name: "ENAM1", address: 0x1E, fields: ENABLE },
+    Register { name: "ENABL", address: 0x1F, fields: ENABLE },
+    Register { name: "HMP0", address: 0x20, fields: MOTION },
+    Register { name: "HMP1", address: 0x21, fields: MOTION },
+    Register { name: "HMM0", address: 0x22, fields: MOTION },
+    Register { name: "HMM1", address: 0x23, fields: MOTION },
+    Register { name: "HMBL", address: 0x24, fields: MOTION },
+    Register { name: "VDELP0", address: 0x25, fields: DELAY },
+    Register { name: "VDELP1", address: 0x26, fields: DELAY },
+    Register { name: "VDELBL", address: 0x27, fields: DELAY },
+    Register { name: "RESMP0", address: 0x28, fields: &[flag("lock to player", 1)] },
+    Register { name: "RESMP1", address: 0x29, fields: &[flag("lock to player", 1)] },
+];
+
+pub const NES_PPU: &[Register] = &[
+    Register {
+        name: "PPUCTRL",
+        address: 0x2000,
+        fields: &[
+            named("nametable", 0, 2, &["$2000", "$2400", "$2800", "$2C00"]),
+            named("increment", 2, 1, &["1", "32"]),
+            named("sprite table", 3, 1, &["$0000", "$1000"]),
+            named("background table", 4, 1, &["$0000", "$1000"]),
+            named("sprite size", 5, 1, &["8x8", "8x16"]),
+            flag("ext output", 6),
+            flag("nmi", 7),
+        ],
+    },
+    Register {
+        name: "PPUMASK",
+        address: 0x2001,
+        fields: &[
+            flag("greyscale", 0),
+            flag("background left", 1),
+            flag("sprites left", 2),
+            flag("background", 3),
+            flag("sprites", 4),
+            flag("emphasize red", 5),
+            flag("emphasize green", 6),
+            flag("emphasize blue", 7),
+        ],
+    },
+];
+
+const VOICE_CONTROL: &[Field] = &[
+    flag("gate", 0),
+    flag("sync", 1),
+    flag("ring", 2),
+    flag("test", 3),
+    flag("triangle", 4),
+    flag("sawtooth", 5),
+    flag("pulse", 6),
+    flag("noise", 7),
+];
+
+pub const C64: &[Register] = &[
+    Register {
+        name: "SCROLY",
+        address: 0xD011,
+        fields: &[bits("y scroll", 0, 3), named("rows", 3, 1, &["24", "25"]), flag("display", 4), flag("bitmap", 5), flag("extended color", 6), bits("raster bit 8", 7, 1)],
+    },
+    Register {
+        name: "SCROLX",
+        address: 0xD016,
+        fields: &[bits("x scroll", 0, 3), named("columns", 3, 1, &["38", "40"]), flag("multicolor", 4)],
+    },
+    Register { name: "VMCSB", address: 0xD018, fields: &[bits("character base", 1, 3), bits("screen base", 4, 4)] },
+    Register { name: "VCREG1", address: 0xD404, fields: VOICE_CONTROL },
+    Register { name: "VCREG2", address: 0xD40B, fields: VOICE_CONTROL },
+    Register { name: "VCREG3", address: 0xD412, fields: VOICE_CONTROL },
+    Register {
+        name: "SIGVOL",
+        address: 0xD418,
+        fields: &[bits("volume", 0, 4), flag("low pass", 4), flag("band pass", 5), flag("high pass", 6), flag("voice 3 off", 7)],
+    },
+];
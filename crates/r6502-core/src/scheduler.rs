@@ -0,0 +1,172 @@
+use std::any::Any;
+
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+
+// Several complete machines run together: two boards joined by a serial
+// cable, a computer and the 6502 in its disk drive, a small network. The
+// scheduler runs them in turn on one thread, giving each a slice of cycles, so
+// none gets more than a slice ahead of the others in emulated time and a run
+// repeats exactly. The machines share nothing but the devices that connect
+// them, which pass messages between buses (see the devices crate's `link`).
+// Shorter slices model a tighter coupling at the cost of more switching.
+
+/// Cycles each machine runs per turn unless [`Scheduler::slice`] says otherwise.
+pub const DEFAULT_SLICE: u64 = 100;
+
+/// A machine the scheduler can run, so machines with different memories can
+/// be scheduled together. Implemented for every emulator.
+pub trait Instance: Any {
+    fn clock(&self) -> u64;
+    fn run_for_cycles(&mut self, cycles: u64) -> StopReason;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<M: VirtualMemory + 'static> Instance for CPUEmulator<M> {
+    fn clock(&self) -> u64 {
+        CPUEmulator::clock(self)
+    }
+
+    fn run_for_cycles(&mut self, cycles: u64) -> StopReason {
+        CPUEmulator::run_for_cycles(self, cycles)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct Slot {
+    name: String,
+    instance: Box<dyn Instance>,
+    // Clock when added, so machines that ran before joining start level.
+    start: u64,
+    stopped: Option<StopReason>,
+}
+
+/// Runs machines round-robin, a slice of cycles each.
+pub struct Scheduler {
+    slice: u64,
+    slots: Vec<Slot>,
+    elapsed: u64,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self { slice: DEFAULT_SLICE, slots: vec![], elapsed: 0 }
+    }
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn slice(mut self, cycles: u64) -> Self {
+        self.slice = cycles.max(1);
+        self
+    }
+
+    /// Adds `emulator` under `name` and returns its index.
+    pub fn add<M: VirtualMemory + 'static>(&mut self, name: &str, emulator: CPUEmulator<M>) -> usize {
+        self.add_boxed(name, Box::new(emulator))
+    }
+
+    /// Like [`Scheduler::add`], for machines whose type is only known at runtime.
+    pub fn add_boxed(&mut self, name: &str, instance: Box<dyn Instance>) -> usize {
+        let start = instance.clock().saturating_sub(self.elapsed);
+        self.slots.push(Slot { name: name.to_owned(), instance, start, stopped: None });
+        self.slots.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Cycles every running machine has been brought up to.
+    pub fn elapsed(&self) -> u64 {
+        self.elapsed
+    }
+
+    /// Index of the machine called `name`.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.name == name)
+    }
+
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.slots.get(index).map(|slot| slot.name.as_str())
+    }
+
+    /// The machine at `index`, if it is a `CPUEmulator<M>`.
+    pub fn get<M: VirtualMemory + 'static>(&self, index: usize) -> Option<&CPUEmulator<M>> {
+        self.slots.get(index)?.instance.as_any().downcast_ref()
+    }
+
+    pub fn get_mut<M: VirtualMemory + 'static>(&mut self, index: usize) -> Option<&mut CPUEmulator<M>> {
+        self.slots.get_mut(index)?.instance.as_any_mut().downcast_mut()
+    }
+
+    /// Why the machine at `index` stopped, if it has.
+    pub fn stopped(&self, index: usize) -> Option<&StopReason> {
+        self.slots.get(index)?.stopped.as_ref()
+    }
+
+    /// Lets a stopped machine run again, say after stepping it past a breakpoint.
+    /// It catches up with the others on its next turn.
+    pub fn resume(&mut self, index: usize) {
+        if let Some(slot) = self.slots.get_mut(index) {
+            slot.stopped = None;
+        }
+    }
+
+    /// Whether any machine is still running.
+    pub fn running(&self) -> bool {
+        self.slots.iter().any(|slot| slot.stopped.is_none())
+    }
+
+    /// Gives every running machine one turn, bringing it up to another slice
+    /// of cycles. Returns whether any machine is still running.
+    pub fn run_slice(&mut self) -> bool {
+        let target = self.elapsed + self.slice;
+        for slot in self.slots.iter_mut().filter(|slot| slot.stopped.is_none()) {
+            let ran = slot.instance.clock() - slot.start;
+            if ran >= target {
+                continue;
+            }
+            match slot.instance.run_for_cycles(target - ran) {
+                StopReason::Timeout => {}
+                reason => {
+                    log::debug!("{} stopped after {} cycles: {:?}", slot.name, slot.instance.clock() - slot.start, reason);
+                    slot.stopped = Some(reason);
+                }
+            }
+        }
+        self.elapsed = target;
+        self.running()
+    }
+
+    /// Runs slices until another `cycles` cycles have passed or every machine
+    /// has stopped. Returns whether any machine is still running.
+    pub fn run_for_cycles(&mut self, cycles: u64) -> bool {
+        let end = self.elapsed.saturating_add(cycles);
+        while self.elapsed < end {
+            if !self.run_slice() {
+                return false;
+            }
+        }
+        self.running()
+    }
+
+    /// Runs slices until every machine has stopped.
+    pub fn run(&mut self) {
+        while self.run_slice() {}
+    }
+}
@@ -0,0 +1,45 @@
+use crate::charset::Charset;
+use crate::emulator::{CPUEmulator, VirtualMemory};
+
+// Scans memory for byte patterns, text and 16 bit values, the usual first step
+// when hunting for where a game keeps its lives counter or where a message is
+// printed from. Ranges are inclusive and read through `peek`, so searching
+// device registers can have side effects.
+
+/// Addresses where `pattern` starts in `image`, which is loaded at `origin`.
+/// Matches may overlap.
+pub fn find_in(image: &[u8], origin: u16, pattern: &[u8]) -> Vec<u16> {
+    if pattern.is_empty() {
+        return vec![];
+    }
+    image
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| *window == pattern)
+        .map(|(offset, _)| origin.wrapping_add(offset as u16))
+        .collect()
+}
+
+fn read<M: VirtualMemory>(emulator: &CPUEmulator<M>, start: u16, end: u16) -> Vec<u8> {
+    (start..=end).map(|address| emulator.peek(address)).collect()
+}
+
+/// Addresses in `start..=end` where `pattern` starts. A match has to end inside
+/// the range too.
+pub fn find_bytes<M: VirtualMemory>(emulator: &CPUEmulator<M>, start: u16, end: u16, pattern: &[u8]) -> Vec<u16> {
+    find_in(&read(emulator, start, end), start, pattern)
+}
+
+/// Where `text` is stored in `charset`. Text the character set can't represent
+/// is never found.
+pub fn find_text<M: VirtualMemory>(emulator: &CPUEmulator<M>, start: u16, end: u16, text: &str, charset: Charset) -> Vec<u16> {
+    match charset.encode_str(text) {
+        Some(pattern) => find_bytes(emulator, start, end, &pattern),
+        None => vec![],
+    }
+}
+
+/// Where `value` is stored little endian.
+pub fn find_word<M: VirtualMemory>(emulator: &CPUEmulator<M>, start: u16, end: u16, value: u16) -> Vec<u16> {
+    find_bytes(emulator, start, end, &value.to_le_bytes())
+}
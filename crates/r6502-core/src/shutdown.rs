@@ -0,0 +1,143 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::error::{R6502Error, Result};
+
+// Lifecycle for frontends that spread the emulator, rendering and audio over
+// several threads. Every thread gets a `Shutdown` token and polls it (or waits
+// on it) in its main loop; the owning `ThreadGroup` requests the stop, joins
+// the threads and then runs cleanup hooks such as flushing traces or writing a
+// save state, so those see the final machine state.
+
+#[derive(Default)]
+struct Signal {
+    requested: AtomicBool,
+    lock: Mutex<()>,
+    condvar: Condvar,
+}
+
+/// Cheap to clone handle shared by everything that has to stop together.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    signal: Arc<Signal>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks every holder of this token to stop. Safe to call from any thread, repeatedly.
+    pub fn request(&self) {
+        let _guard = self.signal.lock.lock().unwrap();
+        self.signal.requested.store(true, Ordering::SeqCst);
+        self.signal.condvar.notify_all();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.signal.requested.load(Ordering::Relaxed)
+    }
+
+    /// Sleeps for up to `timeout`, waking early on a shutdown request. Returns whether
+    /// shutdown was requested, so it can stand in for the sleep in a frame pacing loop.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let guard = self.signal.lock.lock().unwrap();
+        let (_guard, _) = self
+            .signal
+            .condvar
+            .wait_timeout_while(guard, timeout, |_| !self.is_requested())
+            .unwrap();
+        self.is_requested()
+    }
+}
+
+type Hook = Box<dyn FnOnce() + Send>;
+
+/// Owns the threads of a frontend. Dropping the group shuts it down.
+#[derive(Default)]
+pub struct ThreadGroup {
+    shutdown: Shutdown,
+    threads: Vec<(String, JoinHandle<()>)>,
+    hooks: Vec<Hook>,
+}
+
+impl ThreadGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shutdown(&self) -> Shutdown {
+        self.shutdown.clone()
+    }
+
+    /// Starts a named thread that is handed the group's shutdown token. A thread that
+    /// panics requests shutdown on its way out, so the rest of the group stops with it.
+    pub fn spawn<F>(&mut self, name: &str, body: F) -> Result<()>
+    where F: FnOnce(Shutdown) + Send + 'static {
+        let shutdown = self.shutdown.clone();
+        let handle = thread::Builder::new().name(name.to_owned()).spawn(move || {
+            let _guard = PanicGuard(shutdown.clone());
+            body(shutdown)
+        })?;
+        self.threads.push((name.to_owned(), handle));
+        Ok(())
+    }
+
+    /// Registers cleanup to run once all threads have stopped. Hooks run in reverse
+    /// order of registration.
+    pub fn on_shutdown<F>(&mut self, hook: F)
+    where F: FnOnce() + Send + 'static {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Blocks until some thread (or a signal handler) requests shutdown, or every
+    /// thread has returned.
+    pub fn wait(&self) {
+        while !self.shutdown.wait_timeout(Duration::from_millis(100)) {
+            if self.threads.iter().all(|(_, handle)| handle.is_finished()) {
+                return;
+            }
+        }
+    }
+
+    /// Requests shutdown, joins every thread and runs the cleanup hooks. Hooks run
+    /// even if a thread panicked, the panic is reported afterwards.
+    pub fn finish(&mut self) -> Result<()> {
+        self.shutdown.request();
+        let mut panicked = vec![];
+        for (name, handle) in self.threads.drain(..) {
+            if handle.join().is_err() {
+                log::error!("thread {} panicked", name);
+                panicked.push(name);
+            }
+        }
+        while let Some(hook) = self.hooks.pop() {
+            hook();
+        }
+        if panicked.is_empty() {
+            Ok(())
+        } else {
+            Err(R6502Error::ThreadsPanicked(panicked))
+        }
+    }
+}
+
+struct PanicGuard(Shutdown);
+
+impl Drop for PanicGuard {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.request();
+        }
+    }
+}
+
+impl Drop for ThreadGroup {
+    fn drop(&mut self) {
+        if let Err(error) = self.finish() {
+            log::error!("{}", error);
+        }
+    }
+}
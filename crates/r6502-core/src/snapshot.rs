@@ -0,0 +1,212 @@
+use std::path::{Path, PathBuf};
+
+use crate::charset::Charset;
+use crate::disassembler::disassemble_at;
+use crate::emulator::{CPUEmulator, VirtualMemory};
+use crate::error::Result;
+
+// Golden snapshots of emulator output, so a change in behaviour shows up as a
+// diff to review instead of a pile of hand maintained expected values.
+// `assert_snapshot!` compares text against `tests/snapshots/<name>.snap`. When
+// it differs (or doesn't exist yet) the new output is written next to it as
+// `<name>.snap.new` and the test fails with a diff; rename the file, or rerun
+// with `R6502_SNAPSHOTS=accept`, to accept it.
+//
+// The renderers below turn traces, framebuffers and final machine state into
+// stable text for snapshotting.
+
+/// Set to `accept` to overwrite snapshots with the current output.
+pub const MODE_VARIABLE: &str = "R6502_SNAPSHOTS";
+
+/// Lines of context kept around each change in a diff.
+const CONTEXT: usize = 3;
+
+/// Output that doesn't match its stored snapshot.
+#[derive(Debug, Clone)]
+pub struct SnapshotMismatch {
+    pub name: String,
+    /// Where the new output was written.
+    pub pending: PathBuf,
+    pub diff: String,
+}
+
+impl std::fmt::Display for SnapshotMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "snapshot {} changed, new output in {} (set {}=accept to take it)\n{}",
+            self.name, self.pending.display(), MODE_VARIABLE, self.diff
+        )
+    }
+}
+
+impl std::error::Error for SnapshotMismatch {}
+
+/// Compares `actual` with the snapshot `name` in `directory`, see the module comment.
+pub fn check<P: AsRef<Path>>(directory: P, name: &str, actual: &str) -> Result<Option<SnapshotMismatch>> {
+    let directory = directory.as_ref();
+    let stored = directory.join(format!("{}.snap", name));
+    let pending = directory.join(format!("{}.snap.new", name));
+    let expected = std::fs::read_to_string(&stored).ok();
+    let actual = normalise(actual);
+
+    if expected.as_deref().map(normalise).as_deref() == Some(actual.as_str()) {
+        let _ = std::fs::remove_file(&pending);
+        return Ok(None);
+    }
+    std::fs::create_dir_all(directory)?;
+    if std::env::var(MODE_VARIABLE).is_ok_and(|mode| mode == "accept") {
+        std::fs::write(&stored, &actual)?;
+        let _ = std::fs::remove_file(&pending);
+        return Ok(None);
+    }
+    std::fs::write(&pending, &actual)?;
+    let diff = match expected {
+        Some(expected) => diff(&normalise(&expected), &actual),
+        None => format!("no snapshot stored yet, got:\n{}", actual),
+    };
+    Ok(Some(SnapshotMismatch { name: name.to_owned(), pending, diff }))
+}
+
+// Snapshots are compared without regard to line endings or trailing whitespace.
+fn normalise(text: &str) -> String {
+    let mut lines: Vec<&str> = text.lines().map(|line| line.trim_end()).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Line diff of `old` against `new`, `-` for removed and `+` for added lines.
+pub fn diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    // Longest common subsequence of the changed middle part, which is small
+    // unless the output changed wholesale.
+    let mut edits: Vec<(char, &str)> = vec![];
+    if old_middle.len() * new_middle.len() <= 4_000_000 {
+        let mut lengths = vec![vec![0u32; new_middle.len() + 1]; old_middle.len() + 1];
+        for i in (0..old_middle.len()).rev() {
+            for j in (0..new_middle.len()).rev() {
+                lengths[i][j] = match old_middle[i] == new_middle[j] {
+                    true => lengths[i + 1][j + 1] + 1,
+                    false => lengths[i + 1][j].max(lengths[i][j + 1]),
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old_middle.len() || j < new_middle.len() {
+            if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+                edits.push((' ', old_middle[i]));
+                i += 1;
+                j += 1;
+            } else if i < old_middle.len() && (j == new_middle.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+                edits.push(('-', old_middle[i]));
+                i += 1;
+            } else {
+                edits.push(('+', new_middle[j]));
+                j += 1;
+            }
+        }
+    } else {
+        edits.extend(old_middle.iter().map(|line| ('-', *line)));
+        edits.extend(new_middle.iter().map(|line| ('+', *line)));
+    }
+
+    let mut out = String::new();
+    let before = prefix.saturating_sub(CONTEXT);
+    out += &format!("@@ line {} @@\n", before + 1);
+    for line in old[before..prefix].iter() {
+        out += &format!("  {}\n", line);
+    }
+    for (kind, line) in edits {
+        out += &format!("{} {}\n", kind, line);
+    }
+    for line in old[old.len() - suffix..].iter().take(CONTEXT) {
+        out += &format!("  {}\n", line);
+    }
+    out
+}
+
+/// Steps up to `steps` instructions, one line per instruction with its address,
+/// disassembly and the registers after it ran. Stops early if the CPU halts.
+pub fn trace<M: VirtualMemory>(emulator: &mut CPUEmulator<M>, steps: usize) -> String {
+    let mut out = String::new();
+    for _ in 0..steps {
+        if !emulator.state.running {
+            break;
+        }
+        let pc = emulator.state.pc;
+        let bytes: Vec<u8> = (0..3).map(|offset| emulator.peek(pc.wrapping_add(offset))).collect();
+        let instruction = disassemble_at(&bytes, pc);
+        let result = emulator.execute_next_instruction();
+        emulator.state.cycles.clear();
+        match result {
+            Ok(_) => out += &format!("{:04X}  {:<14} {}\n", pc, instruction.to_string(), emulator.state.registers()),
+            Err(_) => {
+                out += &format!("{:04X}  {:<14} failed\n", pc, instruction.to_string());
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// Final registers followed by a hex dump of each of `ranges` (inclusive), for
+/// snapshotting what a program leaves behind.
+pub fn state_table<M: VirtualMemory>(emulator: &CPUEmulator<M>, ranges: &[(u16, u16)]) -> String {
+    let mut out = format!("{}\ncycles={}\n", emulator.state.registers(), emulator.clock());
+    for &(start, end) in ranges.iter() {
+        out += &dump(emulator, start, end, None);
+    }
+    out
+}
+
+/// Hex dump of `start..=end` with the bytes shown as text in `charset` beside
+/// each row, for traces that need to show a text buffer.
+pub fn memory_dump<M: VirtualMemory>(emulator: &CPUEmulator<M>, start: u16, end: u16, charset: Charset) -> String {
+    dump(emulator, start, end, Some(charset))
+}
+
+fn dump<M: VirtualMemory>(emulator: &CPUEmulator<M>, start: u16, end: u16, charset: Option<Charset>) -> String {
+    let mut out = String::new();
+    let mut address = start as u32;
+    while address <= end as u32 {
+        let row_end = (address + 15).min(end as u32);
+        let bytes: Vec<u8> = (address..=row_end).map(|address| emulator.peek(address as u16)).collect();
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        out += &match charset {
+            Some(charset) => format!("{:04X}: {:<47}  {}\n", address, hex.join(" "), charset.decode_bytes(&bytes)),
+            None => format!("{:04X}: {}\n", address, hex.join(" ")),
+        };
+        address = row_end + 1;
+    }
+    out
+}
+
+/// One line per row of `pixels`, each pixel as two hex digits.
+pub fn framebuffer(pixels: &[u8], width: usize) -> String {
+    pixels
+        .chunks(width.max(1))
+        .map(|row| row.iter().map(|pixel| format!("{:02X}", pixel)).collect::<String>() + "\n")
+        .collect()
+}
+
+/// Compares text with the snapshot `tests/snapshots/<name>.snap` of the calling
+/// crate and panics with a diff if it differs.
+#[macro_export]
+macro_rules! assert_snapshot {
+    ($name:expr, $value:expr $(,)?) => {
+        let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/snapshots");
+        match $crate::snapshot::check(directory, $name, ::std::convert::AsRef::<str>::as_ref(&$value)) {
+            Ok(None) => (),
+            Ok(Some(mismatch)) => panic!("{}", mismatch),
+            Err(error) => panic!("snapshot {}: {}", $name, error),
+        }
+    };
+}
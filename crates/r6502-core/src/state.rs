@@ -0,0 +1,299 @@
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "pretty")]
+use tabled::Tabled;
+use bitflags::bitflags;
+
+use crate::format::number_format;
+
+
+bitflags! {
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+    #[cfg_attr(feature = "pretty", derive(Tabled))]
+    #[serde(transparent)]
+    pub struct SystemFlags: u8 {
+        const negative = 0b10000000;
+        const overflow = 0b01000000;
+        const expansion = 0b00100000;
+        const break_command = 0b00010000;
+        const decimal = 0b00001000;
+        const interrupt_disable = 0b00000100;
+        const zero = 0b00000010;
+        const carry = 0b00000001;
+    }
+}
+
+impl std::fmt::Display for SystemFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // let value = *self;
+        // if (value & Self::negative) == Self::negative {
+        //     write!(f, "N | ")?;
+        // }
+        // if (value & Self::overflow) == Self::overflow {
+        //     write!(f, "O | ")?;
+        // }
+        // if (value & Self::expansion) == Self::expansion {
+        //     write!(f, "E | ")?;
+        // }
+        // if (value & Self::break_command) == Self::break_command {
+        //     write!(f, "B | ")?;
+        // }
+        // if (value & Self::decimal) == Self::decimal {
+        //     write!(f, "D | ")?;
+        // }
+        // if (value & Self::interrupt_disable) == Self::interrupt_disable {
+        //     write!(f, "I | ")?;
+        // }
+        // if (value & Self::zero) == Self::zero {
+        //     write!(f, "Z | ")?;
+        // }
+        // if (value & Self::carry) == Self::carry {
+        //     write!(f, "C | ")?;
+        // }
+        bitflags::parser::to_writer(self, f)?;
+        Ok(())
+        // write!(f, "]")
+    }
+    
+}
+
+// Impl blocks can be added to flags types
+impl SystemFlags {
+    pub fn as_u8(&self) -> u8 {
+        self.bits()
+    }
+
+    /// Sets zero and negative from a result, as almost every load and ALU op does.
+    pub fn set_nz(&mut self, value: u8) {
+        self.set(Self::zero, value == 0);
+        self.set(Self::negative, value & 0b10000000 != 0);
+    }
+
+    pub fn with_carry(mut self, carry: bool) -> Self {
+        self.set(Self::carry, carry);
+        self
+    }
+
+    // Bits 4 and 5 aren't latches in the processor: they only exist in the copy of
+    // the register written to the stack.
+    const STACK_ONLY: Self = Self::from_bits_retain(0b00110000);
+
+    /// Flags as pulled from the stack by PLP or RTI, without bits 4 and 5.
+    pub fn from_push(byte: u8) -> Self {
+        Self::from_bits_retain(byte) - Self::STACK_ONLY
+    }
+
+    /// Replaces the flags with `byte` pulled from the stack, leaving bits 4 and 5 alone.
+    pub fn pull(&mut self, byte: u8) {
+        *self = Self::from_push(byte) | (*self & Self::STACK_ONLY);
+    }
+
+    /// The byte pushed to the stack. Bit 5 always reads as set; bit 4 (break) is set
+    /// for `BRK` and `PHP` and clear for hardware interrupts.
+    pub fn to_push(&self, is_brk: bool) -> u8 {
+        let mut pushed = *self | Self::expansion;
+        pushed.set(Self::break_command, is_brk);
+        pushed.bits()
+    }
+}
+impl From<u8> for SystemFlags {
+    fn from(value: u8) -> Self {
+        Self::from_bits_retain(value)
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "pretty", derive(Tabled))]
+pub enum SystemAction {
+    // You can either read or write a U8 value.
+    READ,
+    WRITE,
+}
+
+impl std::fmt::Display for SystemAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::READ => {
+                write!(f, "read")
+            },
+            Self::WRITE=> {
+                write!(f, "write")
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "pretty", derive(Tabled))]
+pub struct SystemCycle {
+    pub address: u16,
+    pub value: u8,
+    pub action: SystemAction,
+}
+
+
+impl std::fmt::Display for SystemCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = number_format();
+        write!(f, "{} from {} with value {} ", self.action, format.word(self.address), format.byte(self.value))
+    }
+}
+
+// Table cells in the current number format.
+#[cfg(feature = "pretty")]
+fn display_word(value: &u16) -> String {
+    number_format().word(*value)
+}
+
+#[cfg(feature = "pretty")]
+fn display_byte(value: &u8) -> String {
+    number_format().byte(*value)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "pretty", derive(Tabled))]
+pub struct SystemState {
+    pub running: bool,
+    #[cfg_attr(feature = "pretty", tabled(display_with = "display_word"))]
+    pub pc: u16,
+    #[cfg_attr(feature = "pretty", tabled(display_with = "display_byte"))]
+    pub a: u8,
+    #[cfg_attr(feature = "pretty", tabled(display_with = "display_byte"))]
+    pub x: u8,
+    #[cfg_attr(feature = "pretty", tabled(display_with = "display_byte"))]
+    pub y: u8,
+    // Stack Pointer
+    // The processor supports a 256 byte stack located between $0100 and $01FF
+    #[cfg_attr(feature = "pretty", tabled(display_with = "display_byte"))]
+    pub s: u8,
+    pub p: SystemFlags,
+    #[cfg_attr(feature = "pretty", tabled(skip))]
+    pub cycles: Vec<SystemCycle>,
+}
+
+impl Default for SystemState {
+    fn default() -> Self {
+        Self {
+            running: Default::default(),
+            pc: Default::default(),
+            a: 0,
+            x: 0,
+            y: 0,
+            s: 0,
+            p: SystemFlags::default(),
+            cycles: Default::default(),
+        }
+    }
+}
+
+impl SystemState {
+    pub fn registers(&self) -> Registers {
+        Registers { pc: self.pc, a: self.a, x: self.x, y: self.y, s: self.s, p: self.p }
+    }
+
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.pc = registers.pc;
+        self.a = registers.a;
+        self.x = registers.x;
+        self.y = registers.y;
+        self.s = registers.s;
+        self.p = registers.p;
+    }
+
+    /// Reserves room for `capacity` bus cycles up front. As long as the log is cleared
+    /// before it fills up, executing instructions doesn't allocate.
+    pub fn with_cycle_capacity(mut self, capacity: usize) -> Self {
+        self.cycles.reserve(capacity);
+        self
+    }
+}
+
+/// The programmer visible registers, as shown by the monitor and stored in save states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Registers {
+    pub pc: u16,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub s: u8,
+    pub p: SystemFlags,
+}
+
+/// A register whose value differs between two [`Registers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterChange {
+    pub name: &'static str,
+    pub before: u16,
+    pub after: u16,
+}
+
+impl Registers {
+    /// Registers that differ in `other`, in display order.
+    pub fn diff(&self, other: &Registers) -> Vec<RegisterChange> {
+        [
+            ("pc", self.pc, other.pc),
+            ("a", self.a as u16, other.a as u16),
+            ("x", self.x as u16, other.x as u16),
+            ("y", self.y as u16, other.y as u16),
+            ("s", self.s as u16, other.s as u16),
+            ("p", self.p.bits() as u16, other.p.bits() as u16),
+        ]
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(name, before, after)| RegisterChange { name, before, after })
+        .collect()
+    }
+}
+
+impl std::fmt::Display for Registers {
+    // Flags are spelled out NV-BDIZC, upper case when set.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flags: String = "NV-BDIZC"
+            .chars()
+            .enumerate()
+            .map(|(index, name)| match self.p.bits() & (0x80 >> index) != 0 {
+                true => name,
+                false => name.to_ascii_lowercase(),
+            })
+            .collect();
+        let format = number_format();
+        write!(f, "pc={} a={} x={} y={} s={} p={}", format.word(self.pc), format.byte(self.a), format.byte(self.x), format.byte(self.y), format.byte(self.s), flags)
+    }
+}
+
+impl std::fmt::Display for RegisterChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = number_format();
+        match self.name {
+            "pc" => write!(f, "{}: {} -> {}", self.name, format.word(self.before), format.word(self.after)),
+            _ => write!(f, "{}: {} -> {}", self.name, format.byte(self.before as u8), format.byte(self.after as u8)),
+        }
+    }
+}
+
+pub type SharedSystemState = Arc<Mutex<SystemState>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmulatorError {
+    MemoryReadError,
+    MemoryWriteError,
+    UnimplementedInstruction,
+    InvalidInstructionMode,
+    ExpectedMemoryPair,
+}
+
+impl std::fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MemoryReadError =>  write!(f, "Memory read error"),
+            Self::MemoryWriteError => write!(f, "Memory write error"),
+            Self::UnimplementedInstruction => write!(f, "Instruction not implemented"),
+            Self::InvalidInstructionMode => write!(f, "Instruction mode is not a valid mode"),
+            Self::ExpectedMemoryPair => write!(f, "Memory pair was expected but received None"),
+        }
+    }
+}
+
+impl std::error::Error for EmulatorError {}
@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+
+use crate::batch::BatchRunner;
+use crate::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, VirtualMemory};
+use crate::instructions::{AddressingMode, Instruction, OpCode};
+use crate::state::SystemState;
+
+// Exhaustive search over short straight-line instruction sequences.
+// Every sequence built from the alphabet is run against each input state and
+// kept if the specification accepts all of the resulting machines.
+
+/// Address the candidate sequences are placed at.
+const ORIGIN: u16 = 0x0200;
+
+pub struct Superoptimizer {
+    alphabet: Vec<Vec<u8>>,
+    inputs: Vec<SystemState>,
+    max_length: usize,
+    runner: BatchRunner,
+}
+
+impl Superoptimizer {
+    pub fn new(max_length: usize) -> Self {
+        Self {
+            alphabet: vec![],
+            inputs: vec![],
+            max_length,
+            runner: BatchRunner::default(),
+        }
+    }
+
+    /// Adds one encoded instruction (opcode followed by its operand bytes) to the alphabet.
+    pub fn instruction(mut self, bytes: &[u8]) -> Self {
+        self.alphabet.push(bytes.to_vec());
+        self
+    }
+
+    /// Adds every implemented single byte instruction that doesn't transfer control.
+    pub fn implied_instructions(mut self) -> Self {
+        for ibyte in 0..=255u8 {
+            let instruction = Instruction::from(ibyte);
+            let single_byte = matches!(
+                instruction.mode,
+                None | Some(AddressingMode::Implied) | Some(AddressingMode::Accumulator)
+            );
+            let excluded = matches!(
+                instruction.opcode,
+                OpCode::BRK | OpCode::RTI | OpCode::RTS | OpCode::KIL | OpCode::INOP
+                    | OpCode::UnknownInstruction | OpCode::BadInstruction
+            );
+            if single_byte && !excluded {
+                self.alphabet.push(vec![ibyte]);
+            }
+        }
+        self
+    }
+
+    /// Adds an input state every candidate is evaluated against.
+    pub fn input(mut self, state: SystemState) -> Self {
+        self.inputs.push(state);
+        self
+    }
+
+    pub fn runner(mut self, runner: BatchRunner) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    /// Returns every shortest sequence for which `spec(input, machine)` holds on all inputs,
+    /// or an empty list if nothing up to `max_length` instructions qualifies.
+    pub fn search<F>(&self, spec: F) -> Vec<Vec<u8>>
+    where F: Fn(&SystemState, &mut CPUEmulator<DefaultVirtualMemory>) -> bool + Sync {
+        if self.alphabet.is_empty() {
+            return vec![];
+        }
+        for length in 1..=self.max_length {
+            let candidates = self.sequences(length);
+            let accepted = self.runner.run(&candidates, |sequence| {
+                self.inputs.iter().all(|input| self.evaluate(sequence, input, &spec))
+            });
+            let found: Vec<Vec<u8>> = candidates
+                .into_iter()
+                .zip(accepted)
+                .filter(|(_, accepted)| *accepted)
+                .map(|(sequence, _)| self.encode(&sequence))
+                .collect();
+            if !found.is_empty() {
+                return found;
+            }
+        }
+        vec![]
+    }
+
+    // All alphabet index tuples of the given length, in lexicographic order.
+    fn sequences(&self, length: usize) -> Vec<Vec<usize>> {
+        let mut sequences: Vec<Vec<usize>> = vec![vec![]];
+        for _ in 0..length {
+            sequences = sequences
+                .into_iter()
+                .flat_map(|prefix| {
+                    (0..self.alphabet.len()).map(move |index| {
+                        let mut sequence = prefix.clone();
+                        sequence.push(index);
+                        sequence
+                    })
+                })
+                .collect();
+        }
+        sequences
+    }
+
+    fn encode(&self, sequence: &[usize]) -> Vec<u8> {
+        sequence.iter().flat_map(|index| self.alphabet[*index].iter().copied()).collect()
+    }
+
+    fn evaluate<F>(&self, sequence: &[usize], input: &SystemState, spec: &F) -> bool
+    where F: Fn(&SystemState, &mut CPUEmulator<DefaultVirtualMemory>) -> bool {
+        let mut state = input.clone();
+        state.pc = ORIGIN;
+        state.running = true;
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(state)
+            .memory(Arc::new(Mutex::new(DefaultVirtualMemory::default())))
+            .build()
+            .unwrap();
+        for (offset, byte) in self.encode(sequence).into_iter().enumerate() {
+            emulator.write(ORIGIN.wrapping_add(offset as u16), byte);
+        }
+        emulator.state.cycles.clear();
+        for _ in sequence {
+            if emulator.execute_next_instruction().is_err() {
+                return false;
+            }
+        }
+        spec(input, &mut emulator)
+    }
+}
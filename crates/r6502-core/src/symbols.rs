@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use crate::error::{R6502Error, Result};
+
+// Symbol import for binaries produced by the cc65 toolchain.
+// ld65 can emit a map file (-m) with segment and export listings and a
+// VICE style label file (-Ln). Either one is enough to put names on addresses.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub name: String,
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Segment {
+    pub fn contains(&self, address: u16) -> bool {
+        self.start <= address && address <= self.end
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    pub segments: Vec<Segment>,
+    by_name: HashMap<String, u16>,
+    by_address: HashMap<u16, String>,
+}
+
+impl SymbolTable {
+    pub fn insert(&mut self, name: &str, address: u16) {
+        self.by_name.insert(name.to_owned(), address);
+        // Keep the first name seen for an address, ld65 lists aliases afterwards.
+        self.by_address.entry(address).or_insert_with(|| name.to_owned());
+    }
+
+    /// Looks up a symbol by name. cc65 prefixes C identifiers with an
+    /// underscore, so `main` also matches `_main`.
+    pub fn lookup(&self, name: &str) -> Option<u16> {
+        self.by_name
+            .get(name)
+            .or_else(|| self.by_name.get(&format!("_{}", name)))
+            .copied()
+    }
+
+    pub fn name_for(&self, address: u16) -> Option<&str> {
+        self.by_address.get(&address).map(|name| name.as_str())
+    }
+
+    pub fn segment(&self, name: &str) -> Option<&Segment> {
+        self.segments.iter().find(|segment| segment.name == name)
+    }
+
+    pub fn segment_for(&self, address: u16) -> Option<&Segment> {
+        self.segments.iter().find(|segment| segment.contains(address))
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.by_name.iter().map(|(name, address)| (name.as_str(), *address))
+    }
+
+    /// Parses the output of `ld65 -m`.
+    pub fn from_ld65_map(map: &str) -> Result<Self> {
+        let mut table = Self::default();
+        let mut section = "";
+        let mut lines = map.lines().peekable();
+        while let Some(line) = lines.next() {
+            // Section headers end in a colon and are underlined with dashes.
+            if line.ends_with(':') && lines.peek().is_some_and(|next| next.starts_with("---")) {
+                section = line.trim_end_matches(':').trim();
+                lines.next();
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match section {
+                "Segment list" => {
+                    // Name Start End Size Align, followed by a dashed rule.
+                    if tokens.len() < 3 || tokens[0] == "Name" || tokens[0].starts_with("---") {
+                        continue;
+                    }
+                    table.segments.push(Segment {
+                        name: tokens[0].to_owned(),
+                        start: parse_hex(tokens[1])?,
+                        end: parse_hex(tokens[2])?,
+                    });
+                }
+                "Exports list by name" => {
+                    // Up to two `name value flags` triples per line.
+                    for export in tokens.chunks(3) {
+                        if let [name, value, _] = export {
+                            table.insert(name, parse_hex(value)?);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(table)
+    }
+
+    /// Parses the output of `ld65 -Ln`, lines of the form `al 000200 ._main`.
+    pub fn from_ld65_labels(labels: &str) -> Result<Self> {
+        let mut table = Self::default();
+        for line in labels.lines() {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["al", value, name] => {
+                    table.insert(name.trim_start_matches('.'), parse_hex(value)?);
+                }
+                [] => (),
+                _ => return Err(R6502Error::BadSymbolLine(line.to_owned())),
+            }
+        }
+        Ok(table)
+    }
+
+    /// Merges another table into this one, e.g. labels on top of a map file.
+    pub fn extend(&mut self, other: SymbolTable) {
+        for (name, address) in other.by_name {
+            self.insert(&name, address);
+        }
+        self.segments.extend(other.segments);
+    }
+}
+
+fn parse_hex(value: &str) -> Result<u16> {
+    let value = u32::from_str_radix(value, 16).map_err(|_| R6502Error::BadHex(value.to_owned()))?;
+    u16::try_from(value).map_err(|_| R6502Error::AddressOutOfRange(value))
+}
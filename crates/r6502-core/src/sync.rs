@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use crate::emulator::{CPUEmulator, StopReason, VirtualMemory};
+use crate::error::Result;
+
+// How much a frontend lets the emulator run before it takes control back to
+// render, read input or pace against the host clock. Finer boundaries cut
+// latency and let the frontend see mid-frame effects, coarser ones run
+// faster. The boundary is set once, on the emulator or in a machine config,
+// and frontends loop on `step_sync` rather than picking a run method each.
+
+/// Where [`CPUEmulator::step_sync`] stops. In a machine config it is
+/// `sync = "frame"`, or `sync = { cycles = 1000 }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncBoundary {
+    #[default]
+    Instruction,
+    /// The video device starting a new scanline.
+    Scanline,
+    /// The video device starting a new frame.
+    Frame,
+    /// Another this many cycles, for machines without a video device.
+    Cycles(u64),
+}
+
+impl<M: VirtualMemory> CPUEmulator<M> {
+    /// Runs to the next `sync` boundary and returns [`StopReason::Stepped`], or
+    /// stops early for the same reasons as [`CPUEmulator::run`]. Scanlines and
+    /// frames need a video device on the bus.
+    pub fn step_sync(&mut self) -> Result<StopReason> {
+        let reason = match self.sync {
+            SyncBoundary::Instruction => self.run_with_limits(Some(1), None),
+            SyncBoundary::Scanline => self.step_scanline()?,
+            SyncBoundary::Frame => self.step_frame()?,
+            SyncBoundary::Cycles(cycles) => self.run_with_limits(Some(cycles), None),
+        };
+        Ok(match reason {
+            StopReason::Timeout => StopReason::Stepped,
+            reason => reason,
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+
+use crate::format::number_format;
+
+// The three vectors at the top of memory the 6502 takes its entry points from.
+// BRK goes through the IRQ vector.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Vector {
+    Nmi,
+    Reset,
+    Irq,
+}
+
+impl Vector {
+    pub const ALL: [Vector; 3] = [Vector::Nmi, Vector::Reset, Vector::Irq];
+
+    /// Where the vector's low byte is.
+    pub fn address(&self) -> u16 {
+        match self {
+            Self::Nmi => 0xFFFA,
+            Self::Reset => 0xFFFC,
+            Self::Irq => 0xFFFE,
+        }
+    }
+
+    /// The vector `address` is a byte of.
+    pub fn containing(address: u16) -> Option<Vector> {
+        Self::ALL.into_iter().find(|vector| address & !1 == vector.address())
+    }
+
+    /// Whether both bytes of the vector are in an image of `length` bytes at `origin`.
+    pub fn in_image(&self, origin: u16, length: usize) -> bool {
+        origin <= self.address() && origin as usize + length > self.address() as usize + 1
+    }
+}
+
+impl std::fmt::Display for Vector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Nmi => "nmi",
+            Self::Reset => "reset",
+            Self::Irq => "irq",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for Vector {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "nmi" => Ok(Self::Nmi),
+            "reset" | "res" => Ok(Self::Reset),
+            "irq" | "brk" => Ok(Self::Irq),
+            _ => Err(format!("Unknown vector {}, expected nmi, reset or irq", name)),
+        }
+    }
+}
+
+/// What the emulator does when a running program writes to a vector, see
+/// [`CPUEmulator::watch_vectors`](crate::emulator::CPUEmulator::watch_vectors).
+/// On machines with RAM up there it's usually a stray pointer, or a handler
+/// being swapped in on purpose that is worth seeing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorWatch {
+    /// Record a [`VectorWrite`](crate::diagnostics::AnomalyKind::VectorWrite) and carry on.
+    Report,
+    /// Record it and stop the run with [`StopReason::VectorWrite`](crate::emulator::StopReason::VectorWrite).
+    Break,
+}
+
+/// Where each vector points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct Vectors {
+    pub nmi: u16,
+    pub reset: u16,
+    pub irq: u16,
+}
+
+impl Vectors {
+    pub fn get(&self, vector: Vector) -> u16 {
+        match vector {
+            Vector::Nmi => self.nmi,
+            Vector::Reset => self.reset,
+            Vector::Irq => self.irq,
+        }
+    }
+
+    pub fn set(&mut self, vector: Vector, target: u16) {
+        match vector {
+            Vector::Nmi => self.nmi = target,
+            Vector::Reset => self.reset = target,
+            Vector::Irq => self.irq = target,
+        }
+    }
+}
+
+impl std::fmt::Display for Vectors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = number_format();
+        let vectors: Vec<String> = Vector::ALL.iter().map(|vector| format!("{}={}", vector, format.word(self.get(*vector)))).collect();
+        write!(f, "{}", vectors.join(" "))
+    }
+}
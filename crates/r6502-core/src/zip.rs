@@ -0,0 +1,284 @@
+use crate::error::{R6502Error, Result};
+use crate::hashing::crc32;
+use crate::loaders::Format;
+
+// ROMs distributed as ZIP archives, the usual way homebrew and test suites are
+// shared. Only what such archives use is read: stored and deflated files, no
+// encryption, no ZIP64. The inflater follows zlib's puff, a decoder written for
+// clarity over speed, which is plenty for files of a few kilobytes.
+
+const LOCAL_HEADER: &[u8] = b"PK\x03\x04";
+const CENTRAL_HEADER: &[u8] = b"PK\x01\x02";
+const END_OF_DIRECTORY: &[u8] = b"PK\x05\x06";
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Whether `data` starts like a ZIP archive.
+pub fn is_zip(data: &[u8]) -> bool {
+    data.starts_with(LOCAL_HEADER) || data.starts_with(END_OF_DIRECTORY)
+}
+
+/// A file in an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    method: u16,
+    crc: u32,
+    compressed: usize,
+    size: usize,
+    offset: usize,
+}
+
+/// The files in the archive, without directories and macOS resource forks.
+pub fn entries(data: &[u8]) -> Result<Vec<ZipEntry>> {
+    let search = data.len().saturating_sub(22 + 0xFFFF);
+    let end = (search..data.len().saturating_sub(21))
+        .rev()
+        .find(|index| data[*index..].starts_with(END_OF_DIRECTORY))
+        .ok_or_else(|| bad("no end of central directory"))?;
+    let count = u16_at(data, end + 10)? as usize;
+    let mut position = u32_at(data, end + 16)? as usize;
+    let mut entries = vec![];
+    for _ in 0..count {
+        if !data[position.min(data.len())..].starts_with(CENTRAL_HEADER) {
+            return Err(bad("bad central directory entry"));
+        }
+        if u16_at(data, position + 8)? & 0x0001 != 0 {
+            return Err(bad("encrypted files aren't supported"));
+        }
+        let name_length = u16_at(data, position + 28)? as usize;
+        let skipped = u16_at(data, position + 30)? as usize + u16_at(data, position + 32)? as usize;
+        let name = data.get(position + 46..position + 46 + name_length).ok_or_else(|| bad("truncated file name"))?;
+        let entry = ZipEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: u16_at(data, position + 10)?,
+            crc: u32_at(data, position + 16)?,
+            compressed: u32_at(data, position + 20)? as usize,
+            size: u32_at(data, position + 24)? as usize,
+            offset: u32_at(data, position + 42)? as usize,
+        };
+        if !entry.name.ends_with('/') && !entry.name.starts_with("__MACOSX/") {
+            entries.push(entry);
+        }
+        position += 46 + name_length + skipped;
+    }
+    Ok(entries)
+}
+
+/// The contents of `entry`, decompressed and checked.
+pub fn extract(data: &[u8], entry: &ZipEntry) -> Result<Vec<u8>> {
+    if !data[entry.offset.min(data.len())..].starts_with(LOCAL_HEADER) {
+        return Err(bad("bad local header"));
+    }
+    let start = entry.offset + 30 + u16_at(data, entry.offset + 26)? as usize + u16_at(data, entry.offset + 28)? as usize;
+    let compressed = data.get(start..start + entry.compressed).ok_or_else(|| bad("truncated file"))?;
+    let contents = match entry.method {
+        STORED => compressed.to_vec(),
+        DEFLATED => inflate(compressed, entry.size)?,
+        method => return Err(bad(&format!("compression method {} isn't supported", method))),
+    };
+    if contents.len() != entry.size || crc32(&contents) != entry.crc {
+        return Err(bad(&format!("{} is corrupt", entry.name)));
+    }
+    Ok(contents)
+}
+
+/// The name and contents of the one ROM in the archive. Besides it the archive
+/// may only hold files that aren't ROMs, such as a readme; which files are
+/// ROMs is told by their extension.
+pub fn rom(data: &[u8]) -> Result<(String, Vec<u8>)> {
+    let mut files = entries(data)?;
+    if files.len() > 1 {
+        files.retain(|entry| {
+            let extension = entry.name.rsplit_once('.').map(|(_, extension)| extension.to_ascii_lowercase());
+            extension.is_some_and(|extension| matches!(extension.as_str(), "bin" | "rom") || Format::from_extension(&extension).is_some())
+        });
+    }
+    match files.as_slice() {
+        [entry] => Ok((entry.name.clone(), extract(data, entry)?)),
+        [] => Err(bad("no ROM in the archive")),
+        _ => Err(bad(&format!("{} ROMs in the archive, expected one", files.len()))),
+    }
+}
+
+fn bad(reason: &str) -> R6502Error {
+    R6502Error::BadArchive(reason.to_owned())
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+    let bytes = data.get(offset..offset + 2).ok_or_else(|| bad("truncated header"))?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    let bytes = data.get(offset..offset + 4).ok_or_else(|| bad("truncated header"))?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+// Order the code length code lengths of a dynamic block come in.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+// Deflate's bit order: least significant bit of each byte first.
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn bits(&mut self, count: u32) -> Result<u32> {
+        while self.count < count {
+            let byte = *self.data.get(self.position).ok_or_else(|| bad("truncated deflate stream"))?;
+            self.buffer |= (byte as u32) << self.count;
+            self.position += 1;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    // Drops what is left of the current byte.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+}
+
+// A canonical Huffman code: how many codes there are of each length, and the
+// symbols in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate().filter(|(_, length)| **length != 0) {
+            symbols[offsets[*length as usize] as usize] = symbol as u16;
+            offsets[*length as usize] += 1;
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for count in self.counts[1..].iter().map(|count| *count as i32) {
+            code |= bits.bits(1)? as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(bad("bad Huffman code"))
+    }
+}
+
+fn inflate(data: &[u8], size: usize) -> Result<Vec<u8>> {
+    let mut bits = Bits { data, position: 0, buffer: 0, count: 0 };
+    let mut output = Vec::with_capacity(size);
+    loop {
+        let last = bits.bits(1)? == 1;
+        match bits.bits(2)? {
+            0 => {
+                bits.align();
+                let header = data.get(bits.position..bits.position + 4).ok_or_else(|| bad("truncated stored block"))?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(bad("bad stored block length"));
+                }
+                let start = bits.position + 4;
+                output.extend_from_slice(data.get(start..start + length as usize).ok_or_else(|| bad("truncated stored block"))?);
+                bits.position = start + length as usize;
+            }
+            1 => {
+                let mut lengths = [8u8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                inflate_block(&mut bits, &mut output, &Huffman::new(&lengths), &Huffman::new(&[5; 30]))?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut bits)?;
+                inflate_block(&mut bits, &mut output, &literals, &distances)?;
+            }
+            _ => return Err(bad("bad block type")),
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+// The literal/length and distance codes at the start of a dynamic block.
+fn dynamic_codes(bits: &mut Bits) -> Result<(Huffman, Huffman)> {
+    let literals = bits.bits(5)? as usize + 257;
+    let distances = bits.bits(5)? as usize + 1;
+    let mut code_lengths = [0u8; 19];
+    for index in CODE_LENGTH_ORDER.iter().take(bits.bits(4)? as usize + 4) {
+        code_lengths[*index] = bits.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (length, repeat) = match code_lengths.decode(bits)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or_else(|| bad("repeat with no previous length"))?, 3 + bits.bits(2)?),
+            17 => (0, 3 + bits.bits(3)?),
+            _ => (0, 11 + bits.bits(7)?),
+        };
+        if lengths.len() + repeat as usize > literals + distances {
+            return Err(bad("too many code lengths"));
+        }
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    Ok((Huffman::new(&lengths[..literals]), Huffman::new(&lengths[literals..])))
+}
+
+fn inflate_block(bits: &mut Bits, output: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> Result<()> {
+    loop {
+        let symbol = literals.decode(bits)? as usize;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err(bad("bad length code"));
+                }
+                let length = LENGTH_BASE[index] as usize + bits.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                let index = distances.decode(bits)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err(bad("bad distance code"));
+                }
+                let distance = DISTANCE_BASE[index] as usize + bits.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                if distance > output.len() {
+                    return Err(bad("distance before the start of the file"));
+                }
+                let start = output.len() - distance;
+                for offset in 0..length {
+                    output.push(output[start + offset]);
+                }
+            }
+        }
+    }
+}
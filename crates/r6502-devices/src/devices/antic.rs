@@ -0,0 +1,263 @@
+use r6502_core::diagnostics::AnomalyKind;
+use r6502_core::emulator::VirtualMemory;
+use r6502_core::state::SystemAction;
+
+use super::Device;
+
+// Atari 400/800 ANTIC, reduced to what software needs to run without a screen:
+// the beam position behind VCOUNT and WSYNC, the vertical blank NMI, and display
+// list interrupts. Nothing is drawn. At the top of each frame ANTIC fetches a page
+// of the display list over the bus and works out from the mode lines which
+// scanlines end an instruction with the DLI bit set. Lists that JMP elsewhere
+// part way down are followed no further than the jump.
+//
+// The registers decode the low four address bits.
+
+pub const DMACTL: u16 = 0x0;
+pub const CHACTL: u16 = 0x1;
+pub const DLISTL: u16 = 0x2;
+pub const DLISTH: u16 = 0x3;
+pub const HSCROL: u16 = 0x4;
+pub const VSCROL: u16 = 0x5;
+pub const PMBASE: u16 = 0x7;
+pub const CHBASE: u16 = 0x9;
+pub const WSYNC: u16 = 0xA;
+pub const VCOUNT: u16 = 0xB;
+pub const PENH: u16 = 0xC;
+pub const PENV: u16 = 0xD;
+pub const NMIEN: u16 = 0xE;
+/// NMIST when read, NMIRES when written.
+pub const NMIST: u16 = 0xF;
+
+/// NMIEN and NMIST bits.
+pub const DLI: u8 = 0x80;
+pub const VBI: u8 = 0x40;
+pub const RESET_KEY: u8 = 0x20;
+
+/// DMACTL: display list DMA on.
+pub const DL_DMA: u8 = 0x20;
+
+pub const CYCLES_PER_LINE: u64 = 114;
+/// First scanline of the display list.
+pub const FIRST_LINE: u16 = 8;
+/// Scanline the vertical blank starts on.
+pub const VBLANK_LINE: u16 = 248;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Standard {
+    #[default]
+    Ntsc,
+    Pal,
+}
+
+impl Standard {
+    pub fn lines(&self) -> u16 {
+        match self {
+            Self::Ntsc => 262,
+            Self::Pal => 312,
+        }
+    }
+
+    /// CPU clock in Hz.
+    pub fn clock(&self) -> u64 {
+        match self {
+            Self::Ntsc => 1_789_773,
+            Self::Pal => 1_773_447,
+        }
+    }
+}
+
+/// Scanlines each ANTIC mode takes, for modes 2 to F.
+const MODE_LINES: [u16; 16] = [0, 0, 8, 10, 8, 16, 8, 16, 8, 4, 4, 2, 1, 2, 1, 1];
+
+#[derive(Debug, Clone)]
+pub struct Antic {
+    standard: Standard,
+    registers: [u8; 16],
+    status: u8,
+    cycle: u64,
+    line: u16,
+    frame: u64,
+    /// Scanlines of this frame that raise a DLI.
+    dli_lines: Vec<u16>,
+    fetch: bool,
+    wsync: bool,
+    nmi: bool,
+}
+
+impl Antic {
+    pub fn new(standard: Standard) -> Self {
+        Self {
+            standard,
+            registers: [0; 16],
+            status: 0,
+            cycle: 0,
+            line: 0,
+            frame: 0,
+            dli_lines: vec![],
+            fetch: false,
+            wsync: false,
+            nmi: false,
+        }
+    }
+
+    pub fn standard(&self) -> Standard {
+        self.standard
+    }
+
+    /// Last value written to a register.
+    pub fn register(&self, register: u16) -> u8 {
+        self.registers[(register & 0x0F) as usize]
+    }
+
+    pub fn display_list(&self) -> u16 {
+        u16::from_le_bytes([self.registers[DLISTL as usize], self.registers[DLISTH as usize]])
+    }
+
+    /// Scanlines of the current frame that raise a display list interrupt.
+    pub fn dli_lines(&self) -> &[u16] {
+        &self.dli_lines
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn line(&self) -> u16 {
+        self.line
+    }
+
+    /// Signals the RESET key, which the 400/800 wire to NMI rather than to the CPU's reset.
+    pub fn press_reset(&mut self) {
+        self.status |= RESET_KEY;
+        self.nmi = true;
+    }
+
+    fn interrupt(&mut self, source: u8) {
+        self.status |= source;
+        if self.registers[NMIEN as usize] & source != 0 {
+            self.nmi = true;
+        }
+    }
+
+    fn start_line(&mut self) {
+        if self.line == VBLANK_LINE {
+            self.interrupt(VBI);
+        } else if self.dli_lines.contains(&self.line) {
+            self.interrupt(DLI);
+        }
+    }
+}
+
+/// Scanlines in `list` whose instruction has the DLI bit set, counting from
+/// `FIRST_LINE`. DLIs fire on the last scanline of their instruction.
+pub fn dli_lines(list: &[u8]) -> Vec<u16> {
+    let mut lines = vec![];
+    let mut line = FIRST_LINE;
+    let mut position = 0;
+    while let Some(&instruction) = list.get(position) {
+        position += 1;
+        let height = match instruction & 0x0F {
+            0 => (instruction >> 4 & 0x07) as u16 + 1,
+            // JMP, or JVB which waits for the vertical blank.
+            1 => {
+                if instruction & 0x80 != 0 {
+                    lines.push(line);
+                }
+                break;
+            }
+            mode => {
+                if instruction & 0x40 != 0 {
+                    position += 2;
+                }
+                MODE_LINES[mode as usize]
+            }
+        };
+        if instruction & 0x80 != 0 {
+            lines.push(line + height - 1);
+        }
+        line += height;
+        if line >= VBLANK_LINE {
+            break;
+        }
+    }
+    lines
+}
+
+impl VirtualMemory for Antic {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x0F {
+            VCOUNT => (self.line >> 1) as u8,
+            NMIST => self.status | 0x1F,
+            PENH | PENV => 0,
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address & 0x0F {
+            WSYNC => self.wsync = true,
+            NMIST => self.status = 0,
+            register => self.registers[register as usize] = value,
+        }
+    }
+
+    fn check_access(&self, address: u16, action: &SystemAction) -> Option<AnomalyKind> {
+        match (action, address & 0x0F) {
+            (SystemAction::READ, VCOUNT | PENH | PENV | NMIST) => None,
+            (SystemAction::READ, _) => Some(AnomalyKind::WriteOnlyRead),
+            _ => None,
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.cycle += cycles;
+        while self.cycle >= CYCLES_PER_LINE {
+            self.cycle -= CYCLES_PER_LINE;
+            self.line += 1;
+            if self.line == self.standard.lines() {
+                self.line = 0;
+                self.frame += 1;
+                self.fetch = self.registers[DMACTL as usize] & DL_DMA != 0;
+                if !self.fetch {
+                    self.dli_lines.clear();
+                }
+            }
+            self.start_line();
+        }
+    }
+
+    /// WSYNC holds the CPU until the start of the next scanline.
+    fn stall(&mut self) -> u64 {
+        match std::mem::take(&mut self.wsync) {
+            true => CYCLES_PER_LINE - self.cycle,
+            false => 0,
+        }
+    }
+
+    fn raster(&self) -> Option<(u64, u16)> {
+        Some((self.frame, self.line))
+    }
+
+    fn beam(&self) -> Option<(u16, u16)> {
+        Some((self.line, self.cycle as u16))
+    }
+
+    fn nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi)
+    }
+}
+
+impl Device for Antic {
+    fn name(&self) -> &'static str {
+        "ANTIC"
+    }
+
+    fn dma_source(&mut self) -> Option<u16> {
+        std::mem::take(&mut self.fetch).then(|| self.display_list())
+    }
+
+    fn dma_write(&mut self, bytes: &[u8]) {
+        self.dli_lines = dli_lines(bytes);
+    }
+}
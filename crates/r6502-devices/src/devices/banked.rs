@@ -0,0 +1,101 @@
+use r6502_core::emulator::VirtualMemory;
+use r6502_core::power_on::RamPattern;
+
+use super::Device;
+
+// RAM expansion seen through a window in the address space, one bank at a time,
+// with a latch that selects the bank. 32 banks of 16K give the classic 512K
+// board; a 4K window at $D000 with two banks is a language card. Map the window
+// and then the latch with `Bus::also_at`:
+//
+//     Bus::new(memory)
+//         .map(0x8000, 0xBFFF, BankedRam::new(0x8000, 0x4000, 32, 0xC000))
+//         .also_at(0xC000, 0xC000)
+//
+// Reading the latch returns the current bank. Banks start out zeroed unless
+// given a power-on pattern.
+
+#[derive(Debug, Clone)]
+pub struct BankedRam {
+    window: u16,
+    bank_size: usize,
+    banks: usize,
+    select: u16,
+    bank: usize,
+    data: Vec<u8>,
+}
+
+impl BankedRam {
+    /// `banks` banks of `bank_size` bytes seen at `window`, with the bank latch at
+    /// `select`. Up to 256 banks can be selected.
+    pub fn new(window: u16, bank_size: usize, banks: usize, select: u16) -> Self {
+        let banks = banks.clamp(1, 256);
+        let bank_size = bank_size.clamp(1, 0x10000 - window as usize);
+        Self { window, bank_size, banks, select, bank: 0, data: vec![0; bank_size * banks] }
+    }
+
+    /// Fills every bank with `pattern`, the banks one after another as the
+    /// board's chips would be.
+    pub fn with_pattern(mut self, pattern: RamPattern) -> Self {
+        pattern.fill(&mut self.data);
+        self
+    }
+
+    pub fn bank(&self) -> usize {
+        self.bank
+    }
+
+    /// Selects a bank as if the program wrote it to the latch.
+    pub fn set_bank(&mut self, bank: usize) {
+        self.bank = bank % self.banks;
+    }
+
+    pub fn banks(&self) -> usize {
+        self.banks
+    }
+
+    /// Total size of the expansion in bytes.
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Contents of one bank, e.g. to preload a RAM disk or inspect it after a run.
+    pub fn bank_data(&self, bank: usize) -> &[u8] {
+        let start = (bank % self.banks) * self.bank_size;
+        &self.data[start..start + self.bank_size]
+    }
+
+    pub fn bank_data_mut(&mut self, bank: usize) -> &mut [u8] {
+        let start = (bank % self.banks) * self.bank_size;
+        &mut self.data[start..start + self.bank_size]
+    }
+
+    // Offset into the backing store for an address in the window.
+    fn offset(&self, address: u16) -> Option<usize> {
+        let offset = address.wrapping_sub(self.window) as usize;
+        (offset < self.bank_size).then(|| self.bank * self.bank_size + offset)
+    }
+}
+
+impl VirtualMemory for BankedRam {
+    fn read(&mut self, address: u16) -> u8 {
+        if address == self.select {
+            return self.bank as u8;
+        }
+        self.offset(address).map(|offset| self.data[offset]).unwrap_or(0)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address == self.select {
+            self.set_bank(value as usize);
+        } else if let Some(offset) = self.offset(address) {
+            self.data[offset] = value;
+        }
+    }
+}
+
+impl Device for BankedRam {
+    fn name(&self) -> &'static str {
+        "banked RAM"
+    }
+}
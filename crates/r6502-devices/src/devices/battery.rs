@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+use r6502_core::emulator::DefaultVirtualMemory;
+use r6502_core::error::Result;
+
+// Battery backed RAM: parts of the bus's plain memory that keep their contents
+// between runs in a host file, like a cartridge's save RAM at $6000-$7FFF or
+// the NVRAM of a breadboard machine. The bytes stay in the bus's memory, so
+// anything that images it, such as the start of a movie, carries them along.
+// A region is loaded when it is declared and written back by `Bus::flush` or
+// when the bus is dropped, each time only if it changed.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatteryRam {
+    pub start: u16,
+    pub end: u16,
+    pub path: PathBuf,
+    /// Contents as last loaded or saved.
+    saved: Vec<u8>,
+}
+
+impl BatteryRam {
+    /// Loads the file at `path` into `start..=end` of `memory`, if there is
+    /// one. A short file fills the start of the region and a long one is cut.
+    pub fn load<P: AsRef<Path>>(start: u16, end: u16, path: P, memory: &mut DefaultVirtualMemory) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let range = start as usize..=end as usize;
+        match std::fs::read(&path) {
+            Ok(image) => {
+                let region = &mut memory.bytes_mut()[range.clone()];
+                if image.len() != region.len() {
+                    log::warn!("{} is {} bytes, expected {}", path.display(), image.len(), region.len());
+                }
+                let length = image.len().min(region.len());
+                region[..length].copy_from_slice(&image[..length]);
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+            Err(error) => return Err(error.into()),
+        }
+        let saved = memory.bytes()[range].to_vec();
+        Ok(Self { start, end, path, saved })
+    }
+
+    /// Whether the region differs from what was last loaded or saved.
+    pub fn dirty(&self, memory: &DefaultVirtualMemory) -> bool {
+        memory.bytes()[self.start as usize..=self.end as usize] != self.saved[..]
+    }
+
+    /// Writes the region to its file if it changed. Returns whether it did.
+    pub fn save(&mut self, memory: &DefaultVirtualMemory) -> Result<bool> {
+        if !self.dirty(memory) {
+            return Ok(false);
+        }
+        let contents = memory.bytes()[self.start as usize..=self.end as usize].to_vec();
+        std::fs::write(&self.path, &contents)?;
+        self.saved = contents;
+        Ok(true)
+    }
+}
@@ -0,0 +1,252 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// 6526 Complex Interface Adapter: two 8 bit ports, two 16 bit interval timers,
+// a time of day clock, a shift register and an interrupt controller. The chip
+// decodes the low four address bits. Both timers count system cycles, down from
+// their latch to zero and then reload, either stopping (one shot) or carrying on
+// (continuous); timer B can't count timer A underflows here. The time of day
+// clock and the shift register just hold what is written.
+//
+// Interrupts go to the IRQ line unless the CIA is `wired_to_nmi`, the way the
+// C64's second CIA is.
+
+pub const PRA: u16 = 0x0;
+pub const PRB: u16 = 0x1;
+pub const DDRA: u16 = 0x2;
+pub const DDRB: u16 = 0x3;
+pub const TA_LO: u16 = 0x4;
+pub const TA_HI: u16 = 0x5;
+pub const TB_LO: u16 = 0x6;
+pub const TB_HI: u16 = 0x7;
+pub const SDR: u16 = 0xC;
+pub const ICR: u16 = 0xD;
+pub const CRA: u16 = 0xE;
+pub const CRB: u16 = 0xF;
+
+/// Interrupt flag and mask bits.
+pub const TIMER_A: u8 = 0x01;
+pub const TIMER_B: u8 = 0x02;
+pub const ALARM: u8 = 0x04;
+pub const SERIAL: u8 = 0x08;
+pub const FLAG: u8 = 0x10;
+
+/// Control register bits.
+pub const START: u8 = 0x01;
+pub const ONE_SHOT: u8 = 0x08;
+/// Strobe that loads the counter from the latch, always read back as zero.
+pub const FORCE_LOAD: u8 = 0x10;
+
+#[derive(Debug, Clone)]
+struct Timer {
+    latch: u16,
+    counter: i64,
+    control: u8,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self { latch: 0xFFFF, counter: 0xFFFF, control: 0 }
+    }
+}
+
+impl Timer {
+    fn write_high(&mut self, value: u8) {
+        self.latch = (self.latch & 0x00FF) | (value as u16) << 8;
+        if self.control & START == 0 {
+            self.counter = self.latch as i64;
+        }
+    }
+
+    fn write_control(&mut self, value: u8) {
+        if value & FORCE_LOAD != 0 {
+            self.counter = self.latch as i64;
+        }
+        self.control = value & !FORCE_LOAD;
+    }
+
+    // Underflows in `cycles`, reloading from the latch each time.
+    fn count(&mut self, cycles: u64) -> bool {
+        if self.control & START == 0 {
+            return false;
+        }
+        self.counter -= cycles as i64;
+        let mut underflowed = false;
+        while self.counter < 0 {
+            underflowed = true;
+            self.counter += self.latch as i64 + 1;
+            if self.control & ONE_SHOT != 0 {
+                self.control &= !START;
+                self.counter = self.latch as i64;
+            }
+        }
+        underflowed
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Cia {
+    outputs: [u8; 2],
+    directions: [u8; 2],
+    /// Levels driven onto the ports from outside, pulled up when nothing does.
+    inputs: [u8; 2],
+    timers: [Timer; 2],
+    time_of_day: [u8; 4],
+    shift: u8,
+    flags: u8,
+    enabled: u8,
+    nmi: bool,
+    /// Whether an NMI edge is waiting for the CPU.
+    nmi_edge: bool,
+}
+
+impl Default for Cia {
+    fn default() -> Self {
+        Self {
+            outputs: [0; 2],
+            directions: [0; 2],
+            inputs: [0xFF; 2],
+            timers: [Timer::default(), Timer::default()],
+            time_of_day: [0; 4],
+            shift: 0,
+            flags: 0,
+            enabled: 0,
+            nmi: false,
+            nmi_edge: false,
+        }
+    }
+}
+
+impl Cia {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends interrupts to the NMI line instead of IRQ.
+    pub fn wired_to_nmi(mut self) -> Self {
+        self.nmi = true;
+        self
+    }
+
+    /// Drives port A (0) or B (1) from outside. Only bits set as inputs are read back.
+    pub fn set_input(&mut self, port: usize, value: u8) {
+        self.inputs[port.min(1)] = value;
+    }
+
+    /// What the CIA drives onto port A (0) or B (1), with inputs pulled high.
+    pub fn output(&self, port: usize) -> u8 {
+        let port = port.min(1);
+        (self.outputs[port] & self.directions[port]) | !self.directions[port]
+    }
+
+    /// Latch of timer A (0) or B (1), which sets its period: an underflow every `latch + 1` cycles.
+    pub fn latch(&self, timer: usize) -> u16 {
+        self.timers[timer.min(1)].latch
+    }
+
+    /// Whether timer A (0) or B (1) is counting.
+    pub fn running(&self, timer: usize) -> bool {
+        self.timers[timer.min(1)].control & START != 0
+    }
+
+    /// Signals an edge on the FLAG pin, such as the cassette read line.
+    pub fn trigger(&mut self) {
+        self.raise(FLAG);
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags | if self.interrupting() { 0x80 } else { 0 }
+    }
+
+    fn interrupting(&self) -> bool {
+        self.flags & self.enabled & 0x1F != 0
+    }
+
+    fn raise(&mut self, flag: u8) {
+        let was = self.interrupting();
+        self.flags |= flag;
+        if self.nmi && !was && self.interrupting() {
+            self.nmi_edge = true;
+        }
+    }
+
+    fn port_read(&self, port: usize) -> u8 {
+        (self.outputs[port] & self.directions[port]) | (self.inputs[port] & !self.directions[port])
+    }
+}
+
+impl VirtualMemory for Cia {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x0F {
+            PRA => self.port_read(0),
+            PRB => self.port_read(1),
+            DDRA => self.directions[0],
+            DDRB => self.directions[1],
+            TA_LO => self.timers[0].counter as u8,
+            TA_HI => (self.timers[0].counter >> 8) as u8,
+            TB_LO => self.timers[1].counter as u8,
+            TB_HI => (self.timers[1].counter >> 8) as u8,
+            SDR => self.shift,
+            // Reading the flags acknowledges them.
+            ICR => {
+                let flags = self.flags();
+                self.flags = 0;
+                flags
+            }
+            CRA => self.timers[0].control,
+            CRB => self.timers[1].control,
+            register => self.time_of_day[(register - 0x8) as usize],
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address & 0x0F {
+            PRA => self.outputs[0] = value,
+            PRB => self.outputs[1] = value,
+            DDRA => self.directions[0] = value,
+            DDRB => self.directions[1] = value,
+            TA_LO => self.timers[0].latch = (self.timers[0].latch & 0xFF00) | value as u16,
+            TA_HI => self.timers[0].write_high(value),
+            TB_LO => self.timers[1].latch = (self.timers[1].latch & 0xFF00) | value as u16,
+            TB_HI => self.timers[1].write_high(value),
+            SDR => self.shift = value,
+            ICR => {
+                let was = self.interrupting();
+                match value & 0x80 {
+                    0 => self.enabled &= !value,
+                    _ => self.enabled |= value & 0x1F,
+                }
+                if self.nmi && !was && self.interrupting() {
+                    self.nmi_edge = true;
+                }
+            }
+            CRA => self.timers[0].write_control(value),
+            CRB => self.timers[1].write_control(value),
+            register => self.time_of_day[(register - 0x8) as usize] = value,
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if self.timers[0].count(cycles) {
+            self.raise(TIMER_A);
+        }
+        if self.timers[1].count(cycles) {
+            self.raise(TIMER_B);
+        }
+    }
+
+    fn irq(&self) -> bool {
+        !self.nmi && self.interrupting()
+    }
+
+    fn nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi_edge)
+    }
+}
+
+impl Device for Cia {
+    fn name(&self) -> &'static str {
+        "CIA"
+    }
+}
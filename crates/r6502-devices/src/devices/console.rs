@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// A character terminal for programs with nothing else to talk to. Writing DATA
+// sends a byte to the host; reading it takes the next byte the host typed, or
+// zero if there is none, and STATUS has bit 0 set while input is waiting. The
+// host side is a `ConsoleHandle`, which can be cloned and kept after the device
+// is moved onto the bus. The device decodes the low address bit.
+
+pub const DATA: u16 = 0;
+pub const STATUS: u16 = 1;
+
+/// STATUS bit set while a typed byte is waiting.
+pub const INPUT_READY: u8 = 0x01;
+
+#[derive(Debug, Default)]
+struct Buffers {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+    waiting: bool,
+}
+
+/// The host's end of a [`Console`].
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleHandle {
+    buffers: Arc<Mutex<Buffers>>,
+}
+
+impl ConsoleHandle {
+    /// Queues `text` as if typed, with newlines sent as carriage returns.
+    pub fn type_text(&self, text: &str) {
+        let bytes = text.bytes().map(|byte| if byte == b'\n' { b'\r' } else { byte });
+        self.type_bytes(&bytes.collect::<Vec<_>>());
+    }
+
+    pub fn type_bytes(&self, bytes: &[u8]) {
+        let mut buffers = self.buffers.lock().unwrap();
+        buffers.input.extend(bytes);
+        buffers.waiting = false;
+    }
+
+    /// Whether the program last gave up waiting for input that wasn't there.
+    /// Typing clears it.
+    pub fn waiting(&self) -> bool {
+        self.buffers.lock().unwrap().waiting
+    }
+
+    /// Records that the program is blocked on input, for drivers that stop until
+    /// the host types something.
+    pub fn set_waiting(&self, waiting: bool) {
+        self.buffers.lock().unwrap().waiting = waiting;
+    }
+
+    /// Bytes typed but not yet read.
+    pub fn pending_input(&self) -> usize {
+        self.buffers.lock().unwrap().input.len()
+    }
+
+    /// Removes and returns the next typed byte.
+    pub fn read(&self) -> Option<u8> {
+        self.buffers.lock().unwrap().input.pop_front()
+    }
+
+    /// Removes and returns a whole line, carriage return included, once one has been typed.
+    pub fn read_line(&self) -> Option<Vec<u8>> {
+        let mut buffers = self.buffers.lock().unwrap();
+        let end = buffers.input.iter().position(|byte| *byte == b'\r')?;
+        Some(buffers.input.drain(..=end).collect())
+    }
+
+    pub fn write(&self, byte: u8) {
+        self.buffers.lock().unwrap().output.push(byte);
+    }
+
+    /// Everything written so far.
+    pub fn output(&self) -> Vec<u8> {
+        self.buffers.lock().unwrap().output.clone()
+    }
+
+    /// Takes the output written so far as text, dropping carriage returns.
+    pub fn take_text(&self) -> String {
+        let output = std::mem::take(&mut self.buffers.lock().unwrap().output);
+        output.into_iter().filter(|byte| *byte != b'\r').map(|byte| byte as char).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Console {
+    handle: ConsoleHandle,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A console whose host end is `handle`.
+    pub fn with_handle(handle: ConsoleHandle) -> Self {
+        Self { handle }
+    }
+
+    pub fn handle(&self) -> ConsoleHandle {
+        self.handle.clone()
+    }
+}
+
+impl VirtualMemory for Console {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x01 {
+            DATA => self.handle.read().unwrap_or(0),
+            _ => (self.handle.pending_input() > 0) as u8 * INPUT_READY,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address & 0x01 == DATA {
+            self.handle.write(value);
+        }
+    }
+}
+
+impl Device for Console {
+    fn name(&self) -> &'static str {
+        "console"
+    }
+}
@@ -0,0 +1,75 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// Atari 400/800 GTIA, as a register file. Writes position and colour the
+// players and playfield, which nothing draws; reads return no collisions, the
+// joystick triggers, the TV standard and the console keys. The chip decodes the
+// low five address bits.
+
+pub const TRIG0: u16 = 0x10;
+pub const PAL: u16 = 0x14;
+pub const CONSOL: u16 = 0x1F;
+/// HITCLR, clears the collision latches.
+pub const HITCLR: u16 = 0x1E;
+
+/// Console keys, active low in CONSOL.
+pub const START: u8 = 0x01;
+pub const SELECT: u8 = 0x02;
+pub const OPTION: u8 = 0x04;
+
+#[derive(Debug, Clone)]
+pub struct Gtia {
+    registers: [u8; 32],
+    pal: bool,
+    /// Trigger inputs, bit n set while trigger n is released.
+    triggers: u8,
+    /// Console keys held down.
+    console: u8,
+}
+
+impl Gtia {
+    pub fn new(pal: bool) -> Self {
+        Self { registers: [0; 32], pal, triggers: 0x0F, console: 0 }
+    }
+
+    /// Last value written to a register.
+    pub fn register(&self, register: u16) -> u8 {
+        self.registers[(register & 0x1F) as usize]
+    }
+
+    pub fn set_trigger(&mut self, trigger: usize, pressed: bool) {
+        let bit = 1 << trigger.min(3);
+        match pressed {
+            true => self.triggers &= !bit,
+            false => self.triggers |= bit,
+        }
+    }
+
+    /// Holds down the console keys in `keys`, releasing the others.
+    pub fn set_console(&mut self, keys: u8) {
+        self.console = keys & 0x07;
+    }
+}
+
+impl VirtualMemory for Gtia {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x1F {
+            0x00..=0x0F => 0,
+            register @ 0x10..=0x13 => self.triggers >> (register - TRIG0) & 1,
+            PAL if self.pal => 0x01,
+            CONSOL => !self.console & 0x07,
+            _ => 0x0F,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.registers[(address & 0x1F) as usize] = value;
+    }
+}
+
+impl Device for Gtia {
+    fn name(&self) -> &'static str {
+        "GTIA"
+    }
+}
@@ -0,0 +1,82 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// The two standard NES controllers at $4016 and $4017. Writing 1 then 0 to bit
+// 0 of $4016 latches both controllers' buttons into shift registers, and each
+// read returns the next button in bit 0: A, B, Select, Start, Up, Down, Left,
+// Right, then ones. While the strobe is held high reads keep returning A.
+//
+// Only $4016 and $4017 reads belong to the joypads ($4017 writes go to the APU
+// frame counter), so map the device at just those two addresses.
+
+pub const JOY1: u16 = 0x4016;
+pub const JOY2: u16 = 0x4017;
+
+/// The upper bits read back what was last on the data bus, usually $40 from the
+/// high byte of the address.
+const OPEN_BUS: u8 = 0x40;
+
+#[derive(Debug, Clone, Default)]
+pub struct Joypads {
+    /// Buttons held on each controller, bit 0 is A through bit 7 for Right.
+    buttons: [u8; 2],
+    shifts: [u8; 2],
+    /// How many bits have been read since the last latch, up to 8.
+    read: [u8; 2],
+    strobe: bool,
+}
+
+impl Joypads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_buttons(&mut self, player: usize, buttons: u8) {
+        if let Some(held) = self.buttons.get_mut(player) {
+            *held = buttons;
+        }
+    }
+
+    pub fn buttons(&self, player: usize) -> u8 {
+        self.buttons.get(player).copied().unwrap_or(0)
+    }
+
+    fn latch(&mut self) {
+        self.shifts = self.buttons;
+        self.read = [0; 2];
+    }
+}
+
+impl VirtualMemory for Joypads {
+    fn read(&mut self, address: u16) -> u8 {
+        let player = (address & 1) as usize;
+        if self.strobe {
+            self.latch();
+        }
+        if self.read[player] == 8 {
+            return OPEN_BUS | 1;
+        }
+        let bit = self.shifts[player] & 1;
+        self.shifts[player] >>= 1;
+        self.read[player] += 1;
+        OPEN_BUS | bit
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address != JOY1 {
+            return;
+        }
+        let strobe = value & 1 != 0;
+        if self.strobe && !strobe {
+            self.latch();
+        }
+        self.strobe = strobe;
+    }
+}
+
+impl Device for Joypads {
+    fn name(&self) -> &'static str {
+        "joypads"
+    }
+}
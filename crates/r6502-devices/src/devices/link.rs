@@ -0,0 +1,89 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// One end of a serial cable between two machines run by a `Scheduler`. A byte
+// written to DATA goes into the other end's receive queue, and reading DATA
+// takes the next byte received, or zero if there is none. STATUS has bit 0
+// set while a byte is waiting and bit 1 while the other end has bytes it
+// hasn't read yet, for programs that pace themselves. With `interrupts` the
+// end holds IRQ while a byte is waiting. Bytes arrive as soon as they are
+// sent, so the latency is whatever the scheduler's slice makes it. The device
+// decodes the low address bit, like the console.
+
+pub const DATA: u16 = 0;
+pub const STATUS: u16 = 1;
+
+/// STATUS bit set while a received byte is waiting.
+pub const RECEIVED: u8 = 0x01;
+/// STATUS bit set while the other end has unread bytes from this one.
+pub const SENDING: u8 = 0x02;
+
+type Queue = Arc<Mutex<VecDeque<u8>>>;
+
+#[derive(Debug, Clone, Default)]
+pub struct Link {
+    incoming: Queue,
+    outgoing: Queue,
+    pub interrupts: bool,
+}
+
+impl Link {
+    /// The two ends of a cable.
+    pub fn pair() -> (Link, Link) {
+        let (forward, backward) = (Queue::default(), Queue::default());
+        let first = Link { incoming: backward.clone(), outgoing: forward.clone(), interrupts: false };
+        (first, Link { incoming: forward, outgoing: backward, interrupts: false })
+    }
+
+    /// Raises IRQ while a received byte is waiting.
+    pub fn with_interrupts(self) -> Self {
+        Self { interrupts: true, ..self }
+    }
+
+    /// Received bytes not yet read.
+    pub fn pending(&self) -> usize {
+        self.incoming.lock().unwrap().len()
+    }
+
+    /// Sends `bytes` as if this end's program had written them.
+    pub fn send(&self, bytes: &[u8]) {
+        self.outgoing.lock().unwrap().extend(bytes);
+    }
+
+    /// Removes and returns the next received byte.
+    pub fn receive(&self) -> Option<u8> {
+        self.incoming.lock().unwrap().pop_front()
+    }
+}
+
+impl VirtualMemory for Link {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x01 {
+            DATA => self.receive().unwrap_or(0),
+            _ => {
+                let sending = !self.outgoing.lock().unwrap().is_empty();
+                ((self.pending() > 0) as u8 * RECEIVED) | (sending as u8 * SENDING)
+            }
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address & 0x01 == DATA {
+            self.send(&[value]);
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.interrupts && self.pending() > 0
+    }
+}
+
+impl Device for Link {
+    fn name(&self) -> &'static str {
+        "link"
+    }
+}
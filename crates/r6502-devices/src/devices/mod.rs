@@ -0,0 +1,305 @@
+use std::any::Any;
+use std::path::Path;
+
+use r6502_core::diagnostics::AnomalyKind;
+use r6502_core::emulator::{DefaultVirtualMemory, VirtualMemory};
+use r6502_core::error::Result;
+use r6502_core::input::{Buttons, Controllers, PLAYERS};
+use r6502_core::movie::MemoryImage;
+use r6502_core::state::SystemAction;
+
+use battery::BatteryRam;
+#[cfg(feature = "nes")]
+use joypad::Joypads;
+#[cfg(feature = "tia")]
+use riot::Riot;
+#[cfg(feature = "tia")]
+use tia::Tia;
+
+// Memory mapped devices and the bus that routes CPU accesses to them.
+// Devices see the full 16 bit address and decode their own registers, so
+// partially decoded chips handle their mirrors themselves.
+
+pub mod antic;
+pub mod banked;
+pub mod battery;
+pub mod cia;
+pub mod console;
+pub mod gtia;
+#[cfg(feature = "nes")]
+pub mod joypad;
+pub mod link;
+pub mod pia;
+pub mod pokey;
+pub mod printer;
+#[cfg(feature = "nes")]
+pub mod ppu;
+#[cfg(feature = "tia")]
+pub mod riot;
+pub mod rtc;
+pub mod semihost;
+pub mod sid;
+pub mod tape;
+pub mod tcp_serial;
+#[cfg(feature = "tia")]
+pub mod tia;
+pub mod timer;
+pub mod via;
+pub mod vic;
+
+/// Lets the bus hand out typed references to the devices it owns.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+pub trait Device: VirtualMemory + AsAny + Send {
+    fn name(&self) -> &'static str;
+    /// Start of a page the device wants copied to it over the bus, like the NES
+    /// OAM DMA. The bus asks after every `tick` and hands the page to `dma_write`.
+    fn dma_source(&mut self) -> Option<u16> {
+        None
+    }
+    fn dma_write(&mut self, _bytes: &[u8]) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Select {
+    /// Inclusive address range.
+    Range(u16, u16),
+    /// Addresses for which `address & mask == value`.
+    Decoded(u16, u16),
+}
+
+impl Select {
+    fn matches(&self, address: u16) -> bool {
+        match *self {
+            Self::Range(start, end) => start <= address && address <= end,
+            Self::Decoded(mask, value) => address & mask == value,
+        }
+    }
+}
+
+struct Mapping {
+    selects: Vec<Select>,
+    device: Box<dyn Device>,
+}
+
+impl Mapping {
+    fn matches(&self, address: u16) -> bool {
+        self.selects.iter().any(|select| select.matches(address))
+    }
+}
+
+/// Routes each access to the first device mapped at the address and everything
+/// else to plain memory.
+#[derive(Default)]
+pub struct Bus {
+    pub memory: DefaultVirtualMemory,
+    mappings: Vec<Mapping>,
+    battery: Vec<BatteryRam>,
+}
+
+impl Bus {
+    pub fn new(memory: DefaultVirtualMemory) -> Self {
+        Self { memory, mappings: vec![], battery: vec![] }
+    }
+
+    /// Keeps `start..=end` of memory in the file at `path` between runs, loading
+    /// it now if the file exists, see [`battery`].
+    pub fn battery_backed<P: AsRef<Path>>(mut self, start: u16, end: u16, path: P) -> Result<Self> {
+        let region = BatteryRam::load(start, end, path, &mut self.memory)?;
+        self.battery.push(region);
+        Ok(self)
+    }
+
+    pub fn battery(&self) -> &[BatteryRam] {
+        &self.battery
+    }
+
+    /// Saves the battery backed regions that changed since they were last loaded or saved.
+    pub fn flush(&mut self) -> Result<()> {
+        for region in self.battery.iter_mut() {
+            if region.save(&self.memory)? {
+                log::debug!("saved {:#06x}-{:#06x} to {}", region.start, region.end, region.path.display());
+            }
+        }
+        Ok(())
+    }
+
+    /// Maps `device` at `start..=end`.
+    pub fn map<D: Device + 'static>(self, start: u16, end: u16, device: D) -> Self {
+        self.map_boxed(start, end, Box::new(device))
+    }
+
+    /// Maps `device` wherever `address & mask == value`, the way chips with only a few
+    /// address lines wired up appear all over the address space.
+    pub fn map_decoded<D: Device + 'static>(self, mask: u16, value: u16, device: D) -> Self {
+        self.map_decoded_boxed(mask, value, Box::new(device))
+    }
+
+    /// Like [`Bus::map`], for devices whose type is only known at runtime.
+    pub fn map_boxed(mut self, start: u16, end: u16, device: Box<dyn Device>) -> Self {
+        self.mappings.push(Mapping { selects: vec![Select::Range(start, end)], device });
+        self
+    }
+
+    pub fn map_decoded_boxed(mut self, mask: u16, value: u16, device: Box<dyn Device>) -> Self {
+        self.mappings.push(Mapping { selects: vec![Select::Decoded(mask, value)], device });
+        self
+    }
+
+    /// Also routes `start..=end` to the device mapped last, for hardware whose
+    /// registers sit away from the memory it decodes, like a bank select latch in
+    /// the I/O page.
+    pub fn also_at(mut self, start: u16, end: u16) -> Self {
+        if let Some(mapping) = self.mappings.last_mut() {
+            mapping.selects.push(Select::Range(start, end));
+        }
+        self
+    }
+
+    /// First mapped device of type `D`.
+    pub fn device<D: Device + 'static>(&self) -> Option<&D> {
+        self.mappings.iter().find_map(|mapping| mapping.device.as_ref().as_any().downcast_ref::<D>())
+    }
+
+    pub fn device_mut<D: Device + 'static>(&mut self) -> Option<&mut D> {
+        self.mappings.iter_mut().find_map(|mapping| mapping.device.as_mut().as_any_mut().downcast_mut::<D>())
+    }
+
+    pub fn devices(&self) -> impl Iterator<Item = &dyn Device> {
+        self.mappings.iter().map(|mapping| mapping.device.as_ref())
+    }
+
+    fn device_at(&mut self, address: u16) -> Option<&mut Box<dyn Device>> {
+        self.mappings
+            .iter_mut()
+            .find(|mapping| mapping.matches(address))
+            .map(|mapping| &mut mapping.device)
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            log::warn!("Couldn't save battery backed RAM: {}", error);
+        }
+    }
+}
+
+impl VirtualMemory for Bus {
+    fn read(&mut self, address: u16) -> u8 {
+        match self.device_at(address) {
+            Some(device) => device.read(address),
+            None => self.memory.read(address),
+        }
+    }
+
+    fn peek(&mut self, address: u16) -> u8 {
+        match self.device_at(address) {
+            Some(device) => device.peek(address),
+            None => self.memory.peek(address),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match self.device_at(address) {
+            Some(device) => device.write(address, value),
+            None => self.memory.write(address, value),
+        }
+    }
+
+    fn check_access(&self, address: u16, action: &SystemAction) -> Option<AnomalyKind> {
+        match self.mappings.iter().find(|mapping| mapping.matches(address)) {
+            Some(mapping) => mapping.device.check_access(address, action),
+            None => self.memory.check_access(address, action),
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        for mapping in self.mappings.iter_mut() {
+            mapping.device.tick(cycles);
+        }
+        for index in 0..self.mappings.len() {
+            if let Some(source) = self.mappings[index].device.dma_source() {
+                let page: Vec<u8> = (0..0x100).map(|offset| self.read(source.wrapping_add(offset))).collect();
+                self.mappings[index].device.dma_write(&page);
+            }
+        }
+    }
+
+    fn stall(&mut self) -> u64 {
+        self.mappings.iter_mut().map(|mapping| mapping.device.stall()).max().unwrap_or(0)
+    }
+
+    fn raster(&self) -> Option<(u64, u16)> {
+        self.mappings.iter().find_map(|mapping| mapping.device.raster())
+    }
+
+    fn beam(&self) -> Option<(u16, u16)> {
+        self.mappings.iter().find_map(|mapping| mapping.device.beam())
+    }
+
+    // The IRQ line is wired-OR: any device can hold it.
+    fn irq(&self) -> bool {
+        self.memory.irq() || self.mappings.iter().any(|mapping| mapping.device.irq())
+    }
+
+    // Asks every device, so each one's edge is consumed.
+    fn nmi(&mut self) -> bool {
+        self.mappings.iter_mut().fold(false, |nmi, mapping| mapping.device.nmi() | nmi)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Bus::flush(self)
+    }
+}
+
+// Only the RAM behind the devices, devices keep their own state.
+impl MemoryImage for Bus {
+    fn image(&self) -> Vec<u8> {
+        self.memory.image()
+    }
+
+    fn restore_image(&mut self, image: &[u8]) {
+        self.memory.restore_image(image)
+    }
+}
+
+impl Controllers for Bus {
+    // Drives whichever controller hardware is on the bus: NES joypads, or the
+    // 2600's joystick directions on RIOT port A and fire buttons on the TIA.
+    #[cfg_attr(not(any(feature = "nes", feature = "tia")), allow(unused_variables))]
+    fn set_controllers(&mut self, buttons: &[Buttons; PLAYERS]) {
+        #[cfg(feature = "nes")]
+        if let Some(joypads) = self.device_mut::<Joypads>() {
+            for (player, held) in buttons.iter().enumerate() {
+                joypads.set_buttons(player, held.bits());
+            }
+        }
+        #[cfg(feature = "tia")]
+        if let Some(riot) = self.device_mut::<Riot>() {
+            // Player 0 on the high nibble, each nibble right, left, down, up from
+            // the top bit, which is the order of `Buttons`. Active low.
+            let directions = |held: Buttons| held.bits() >> 4;
+            riot.set_port_a(!(directions(buttons[0]) << 4 | directions(buttons[1])));
+        }
+        #[cfg(feature = "tia")]
+        if let Some(tia) = self.device_mut::<Tia>() {
+            for (player, held) in buttons.iter().enumerate() {
+                tia.set_fire_button(player, held.contains(Buttons::A));
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// 6520 Peripheral Interface Adapter, as used for the Atari 400/800 joystick
+// ports. Two 8 bit ports share their address with their data direction
+// register; bit 2 of each control register picks which one is seen. The
+// control lines and their interrupts aren't wired up. The chip decodes the low
+// two address bits: port A, port B, then their control registers.
+
+pub const PORTA: u16 = 0x0;
+pub const PORTB: u16 = 0x1;
+pub const PACTL: u16 = 0x2;
+pub const PBCTL: u16 = 0x3;
+
+/// Control register bit that selects the port rather than its direction register.
+pub const PORT_SELECT: u8 = 0x04;
+
+#[derive(Debug, Clone)]
+pub struct Pia {
+    outputs: [u8; 2],
+    directions: [u8; 2],
+    controls: [u8; 2],
+    /// Levels driven onto the ports from outside, pulled up when nothing does.
+    inputs: [u8; 2],
+}
+
+impl Default for Pia {
+    fn default() -> Self {
+        Self { outputs: [0; 2], directions: [0; 2], controls: [0; 2], inputs: [0xFF; 2] }
+    }
+}
+
+impl Pia {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drives port A (0) or B (1) from outside. Only bits set as inputs are read back.
+    pub fn set_input(&mut self, port: usize, value: u8) {
+        self.inputs[port.min(1)] = value;
+    }
+
+    /// What the PIA drives onto port A (0) or B (1), with inputs pulled high.
+    pub fn output(&self, port: usize) -> u8 {
+        let port = port.min(1);
+        (self.outputs[port] & self.directions[port]) | !self.directions[port]
+    }
+}
+
+impl VirtualMemory for Pia {
+    fn read(&mut self, address: u16) -> u8 {
+        let port = (address & 0x01) as usize;
+        match address & 0x02 {
+            0 if self.controls[port] & PORT_SELECT != 0 => {
+                (self.outputs[port] & self.directions[port]) | (self.inputs[port] & !self.directions[port])
+            }
+            0 => self.directions[port],
+            _ => self.controls[port],
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let port = (address & 0x01) as usize;
+        match address & 0x02 {
+            0 if self.controls[port] & PORT_SELECT != 0 => self.outputs[port] = value,
+            0 => self.directions[port] = value,
+            _ => self.controls[port] = value & 0x3F,
+        }
+    }
+}
+
+impl Device for Pia {
+    fn name(&self) -> &'static str {
+        "PIA"
+    }
+}
@@ -0,0 +1,116 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// Atari POKEY, reduced to the keyboard and the random number generator. The
+// sound, timer and serial registers just hold what is written, so programs that
+// wait on a serial transfer or a POKEY timer IRQ will wait forever. Reads
+// decode the low four address bits.
+
+pub const ALLPOT: u16 = 0x8;
+pub const KBCODE: u16 = 0x9;
+pub const RANDOM: u16 = 0xA;
+pub const SERIN: u16 = 0xD;
+/// IRQST when read, IRQEN when written.
+pub const IRQST: u16 = 0xE;
+/// SKSTAT when read, SKCTL when written.
+pub const SKSTAT: u16 = 0xF;
+
+/// IRQEN and IRQST bit for a key press.
+pub const KEY_IRQ: u8 = 0x40;
+/// IRQEN and IRQST bit for the BREAK key.
+pub const BREAK_IRQ: u8 = 0x80;
+
+/// Paddle reading with nothing plugged in.
+const NO_PADDLE: u8 = 228;
+
+#[derive(Debug, Clone)]
+pub struct Pokey {
+    registers: [u8; 16],
+    enabled: u8,
+    /// Pending interrupts, as set bits. IRQST reads them inverted.
+    pending: u8,
+    key: u8,
+    key_down: bool,
+    random: u32,
+}
+
+impl Default for Pokey {
+    fn default() -> Self {
+        Self { registers: [0; 16], enabled: 0, pending: 0, key: 0xFF, key_down: false, random: 0x1FFFF }
+    }
+}
+
+impl Pokey {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last value written to a register.
+    pub fn register(&self, register: u16) -> u8 {
+        self.registers[(register & 0x0F) as usize]
+    }
+
+    /// Presses the key with keyboard code `code`, as read from KBCODE.
+    pub fn press_key(&mut self, code: u8) {
+        self.key = code;
+        self.key_down = true;
+        self.pending |= KEY_IRQ & self.enabled;
+    }
+
+    pub fn release_key(&mut self) {
+        self.key_down = false;
+    }
+
+    pub fn press_break(&mut self) {
+        self.pending |= BREAK_IRQ & self.enabled;
+    }
+
+    // 17 bit polynomial counter, stepped once per CPU cycle.
+    fn step_random(&mut self, cycles: u64) {
+        for _ in 0..cycles {
+            let bit = (self.random ^ (self.random >> 5)) & 1;
+            self.random = (self.random >> 1) | (bit << 16);
+        }
+    }
+}
+
+impl VirtualMemory for Pokey {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x0F {
+            0x0..=0x7 => NO_PADDLE,
+            ALLPOT => 0,
+            KBCODE => self.key,
+            RANDOM => (self.random >> 9) as u8,
+            SERIN => 0xFF,
+            IRQST => !self.pending,
+            // Bit 2 is low while a key is held.
+            SKSTAT if self.key_down => 0xFB,
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let register = address & 0x0F;
+        if register == IRQST {
+            self.enabled = value;
+            // Disabling a source also clears it.
+            self.pending &= value;
+        }
+        self.registers[register as usize] = value;
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.step_random(cycles);
+    }
+
+    fn irq(&self) -> bool {
+        self.pending & self.enabled != 0
+    }
+}
+
+impl Device for Pokey {
+    fn name(&self) -> &'static str {
+        "POKEY"
+    }
+}
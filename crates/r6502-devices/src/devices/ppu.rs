@@ -0,0 +1,482 @@
+use r6502_core::diagnostics::AnomalyKind;
+use r6502_core::emulator::VirtualMemory;
+use r6502_core::loaders::Mirroring;
+use r6502_core::state::SystemAction;
+
+use super::Device;
+
+// NES 2C02 picture processing unit. The eight registers are mirrored through
+// $2000-$3FFF, and a write to OAMDMA at $4014 copies a page of CPU memory into
+// sprite memory, stalling the CPU. The PPU's own address space has the cartridge's
+// pattern tables at $0000-$1FFF, 2K of nametable RAM mirrored according to the
+// cartridge wiring at $2000-$3EFF, and 32 bytes of palette at $3F00.
+//
+// Each scanline is drawn in one go as the beam leaves it, using the scroll and
+// control registers as they are at that moment, which is enough for title
+// screens and status bar splits but not for mid-line effects. Sprite zero hit
+// and overflow are likewise only flagged once the line is done.
+
+pub const PPUCTRL: u16 = 0;
+pub const PPUMASK: u16 = 1;
+pub const PPUSTATUS: u16 = 2;
+pub const OAMADDR: u16 = 3;
+pub const OAMDATA: u16 = 4;
+pub const PPUSCROLL: u16 = 5;
+pub const PPUADDR: u16 = 6;
+pub const PPUDATA: u16 = 7;
+pub const OAMDMA: u16 = 0x4014;
+
+/// PPUCTRL: raise an NMI at the start of vertical blank.
+pub const NMI_ENABLE: u8 = 0x80;
+/// PPUCTRL: 8x16 sprites, which pick their pattern table with bit 0 of the tile.
+pub const TALL_SPRITES: u8 = 0x20;
+/// PPUCTRL: background patterns at $1000 rather than $0000.
+pub const BACKGROUND_TABLE: u8 = 0x10;
+/// PPUCTRL: 8x8 sprite patterns at $1000 rather than $0000.
+pub const SPRITE_TABLE: u8 = 0x08;
+/// PPUCTRL: PPUDATA steps 32 bytes, a nametable column, instead of one.
+pub const INCREMENT_32: u8 = 0x04;
+/// PPUMASK: draw the background.
+pub const SHOW_BACKGROUND: u8 = 0x08;
+/// PPUMASK: draw sprites.
+pub const SHOW_SPRITES: u8 = 0x10;
+/// PPUMASK: draw sprites in the leftmost 8 pixels too.
+pub const SHOW_SPRITES_LEFT: u8 = 0x04;
+/// PPUMASK: draw the background in the leftmost 8 pixels too.
+pub const SHOW_BACKGROUND_LEFT: u8 = 0x02;
+/// PPUMASK: only the grey column of the palette.
+pub const GREYSCALE: u8 = 0x01;
+/// PPUSTATUS: in vertical blank. Reading PPUSTATUS clears it.
+pub const VBLANK: u8 = 0x80;
+/// PPUSTATUS: an opaque pixel of sprite 0 was drawn over opaque background.
+pub const SPRITE_ZERO_HIT: u8 = 0x40;
+/// PPUSTATUS: more than eight sprites on a scanline.
+pub const SPRITE_OVERFLOW: u8 = 0x20;
+
+/// Sprite attributes: behind opaque background.
+pub const BEHIND_BACKGROUND: u8 = 0x20;
+pub const FLIP_HORIZONTAL: u8 = 0x40;
+pub const FLIP_VERTICAL: u8 = 0x80;
+
+/// Sprites the PPU draws on one scanline.
+pub const SPRITES_PER_LINE: usize = 8;
+/// CPU cycles OAM DMA takes (514 when it starts on an odd cycle).
+pub const DMA_CYCLES: u64 = 513;
+
+pub const WIDTH: usize = 256;
+pub const HEIGHT: usize = 240;
+pub const DOTS_PER_LINE: u64 = 341;
+pub const LINES_PER_FRAME: u16 = 262;
+/// First scanline of vertical blank.
+pub const VBLANK_LINE: u16 = 241;
+/// Scanline before the first visible one, where vertical blank ends.
+pub const PRE_RENDER_LINE: u16 = 261;
+
+#[derive(Debug, Clone)]
+pub struct Ppu {
+    control: u8,
+    mask: u8,
+    status: u8,
+    oam_address: u8,
+    oam: [u8; 256],
+    /// Pattern tables, CHR RAM when the cartridge has no CHR ROM.
+    chr: Vec<u8>,
+    chr_writable: bool,
+    nametables: Vec<u8>,
+    mirroring: Mirroring,
+    palette: [u8; 32],
+    /// VRAM address PPUDATA accesses.
+    address: u16,
+    /// Whether the next PPUSCROLL or PPUADDR write is the second of the pair.
+    second_write: bool,
+    scroll_x: u8,
+    scroll_y: u8,
+    /// Vertical scroll latched at the start of the frame.
+    frame_scroll_y: u8,
+    /// PPUDATA reads below the palette return the previous read's byte.
+    read_buffer: u8,
+    /// Last byte written to any register, which undriven status bits read back.
+    latch: u8,
+    dot: u64,
+    scanline: u16,
+    frame: u64,
+    nmi: bool,
+    /// Page OAMDMA asked to copy, until the bus does.
+    dma_page: Option<u8>,
+    stall: u64,
+    framebuffer: Vec<u8>,
+}
+
+impl Ppu {
+    /// PPU for a cartridge with `chr` pattern ROM, or 8K of pattern RAM if it's empty.
+    pub fn new(chr: &[u8], mirroring: Mirroring) -> Self {
+        let chr_writable = chr.is_empty();
+        let mut chr = chr.to_vec();
+        chr.resize(0x2000.max(chr.len()), 0);
+        let nametables = match mirroring {
+            Mirroring::FourScreen => 0x1000,
+            _ => 0x800,
+        };
+        Self {
+            control: 0,
+            mask: 0,
+            status: 0,
+            oam_address: 0,
+            oam: [0; 256],
+            chr,
+            chr_writable,
+            nametables: vec![0; nametables],
+            mirroring,
+            palette: [0; 32],
+            address: 0,
+            second_write: false,
+            scroll_x: 0,
+            scroll_y: 0,
+            frame_scroll_y: 0,
+            read_buffer: 0,
+            latch: 0,
+            dot: 0,
+            scanline: 0,
+            frame: 0,
+            nmi: false,
+            dma_page: None,
+            stall: 0,
+            framebuffer: vec![0; WIDTH * HEIGHT],
+        }
+    }
+
+    /// The last frame drawn, `WIDTH` x `HEIGHT` NES colour numbers ($00-$3F).
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    pub fn control(&self) -> u8 {
+        self.control
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    pub fn status(&self) -> u8 {
+        self.status
+    }
+
+    /// Sprite memory: Y, tile, attributes and X for each of 64 sprites.
+    pub fn oam(&self) -> &[u8; 256] {
+        &self.oam
+    }
+
+    /// Reads the PPU's own address space, without PPUDATA's side effects.
+    pub fn vram(&self, address: u16) -> u8 {
+        match address & 0x3FFF {
+            address @ 0x0000..=0x1FFF => self.chr[address as usize],
+            address @ 0x2000..=0x3EFF => self.nametables[self.nametable_index(address)],
+            address => self.palette[palette_index(address)],
+        }
+    }
+
+    pub fn set_vram(&mut self, address: u16, value: u8) {
+        match address & 0x3FFF {
+            address @ 0x0000..=0x1FFF if self.chr_writable => self.chr[address as usize] = value,
+            0x0000..=0x1FFF => (),
+            address @ 0x2000..=0x3EFF => {
+                let index = self.nametable_index(address);
+                self.nametables[index] = value;
+            }
+            address => self.palette[palette_index(address)] = value & 0x3F,
+        }
+    }
+
+    // Horizontal mirroring puts $2000 and $2400 on the same 1K, vertical $2000
+    // and $2800.
+    fn nametable_index(&self, address: u16) -> usize {
+        let offset = (address & 0x0FFF) as usize;
+        match self.mirroring {
+            Mirroring::Horizontal => (offset >> 1 & 0x400) | (offset & 0x3FF),
+            Mirroring::Vertical => offset & 0x7FF,
+            Mirroring::FourScreen => offset,
+        }
+    }
+
+    fn increment(&self) -> u16 {
+        if self.control & INCREMENT_32 != 0 { 32 } else { 1 }
+    }
+
+    // Colour of palette entry `index` as shown, taking greyscale into account.
+    fn colour(&self, index: usize) -> u8 {
+        let colour = self.palette[palette_index(index as u16)];
+        if self.mask & GREYSCALE != 0 { colour & 0x30 } else { colour }
+    }
+
+    // Palette index (0-15) of the background at `x` on visible line `line`, 0
+    // meaning transparent.
+    fn background_pixel(&self, x: usize, line: usize) -> usize {
+        if self.mask & SHOW_BACKGROUND == 0 || (x < 8 && self.mask & SHOW_BACKGROUND_LEFT == 0) {
+            return 0;
+        }
+        // Position in the 512x480 plane of the four nametables.
+        let x = (x + self.scroll_x as usize + (self.control as usize & 1) * WIDTH) % (2 * WIDTH);
+        let y = (line + self.frame_scroll_y as usize + (self.control as usize >> 1 & 1) * HEIGHT) % (2 * HEIGHT);
+        let nametable = 0x2000 + 0x400 * (x / WIDTH + 2 * (y / HEIGHT)) as u16;
+        let (column, row) = ((x % WIDTH / 8) as u16, (y % HEIGHT / 8) as u16);
+        let tile = self.vram(nametable + row * 32 + column) as u16;
+        let attribute = self.vram(nametable + 0x3C0 + row / 4 * 8 + column / 4);
+        let palette = attribute >> ((row & 2) << 1 | (column & 2)) & 3;
+
+        let table = if self.control & BACKGROUND_TABLE != 0 { 0x1000 } else { 0 };
+        let pattern = table + tile * 16 + (y % 8) as u16;
+        let bit = 7 - x % 8;
+        let pixel = (self.vram(pattern) >> bit & 1) | (self.vram(pattern + 8) >> bit & 1) << 1;
+        match pixel {
+            0 => 0,
+            pixel => palette as usize * 4 + pixel as usize,
+        }
+    }
+
+    fn sprite_height(&self) -> usize {
+        if self.control & TALL_SPRITES != 0 { 16 } else { 8 }
+    }
+
+    // Indexes of the sprites on `line` in priority order, at most eight. Finding
+    // a ninth sets the overflow flag (without the hardware's evaluation bug).
+    fn sprites_on(&mut self, line: usize) -> Vec<usize> {
+        let height = self.sprite_height();
+        // Sprites are drawn one line below their Y coordinate.
+        let mut sprites = (0..64).filter(|sprite| (1..=height).contains(&line.wrapping_sub(self.oam[sprite * 4] as usize)));
+        let visible: Vec<usize> = sprites.by_ref().take(SPRITES_PER_LINE).collect();
+        if sprites.next().is_some() {
+            self.status |= SPRITE_OVERFLOW;
+        }
+        visible
+    }
+
+    // Colour 1-3 of `sprite` at `x` on `line`, 0 where it's transparent or absent.
+    fn sprite_pixel(&self, sprite: usize, x: usize, line: usize) -> u8 {
+        let [y, tile, attributes, left] = [0, 1, 2, 3].map(|offset| self.oam[sprite * 4 + offset]);
+        let column = x.wrapping_sub(left as usize);
+        if column >= 8 {
+            return 0;
+        }
+        let height = self.sprite_height();
+        let mut row = line - (y as usize + 1);
+        if attributes & FLIP_VERTICAL != 0 {
+            row = height - 1 - row;
+        }
+        let column = if attributes & FLIP_HORIZONTAL != 0 { column } else { 7 - column };
+        let (table, tile) = match height {
+            // The bottom half of a tall sprite is the next tile.
+            16 => ((tile as u16 & 1) * 0x1000, (tile as u16 & 0xFE) + row as u16 / 8),
+            _ => (if self.control & SPRITE_TABLE != 0 { 0x1000 } else { 0 }, tile as u16),
+        };
+        let pattern = table + tile * 16 + (row % 8) as u16;
+        (self.vram(pattern) >> column & 1) | (self.vram(pattern + 8) >> column & 1) << 1
+    }
+
+    fn render_line(&mut self, line: usize) {
+        let sprites = match self.mask & SHOW_SPRITES {
+            0 => vec![],
+            _ => self.sprites_on(line),
+        };
+        for x in 0..WIDTH {
+            let background = self.background_pixel(x, line);
+            let sprite = match x < 8 && self.mask & SHOW_SPRITES_LEFT == 0 {
+                true => None,
+                false => sprites.iter().find_map(|sprite| match self.sprite_pixel(*sprite, x, line) {
+                    0 => None,
+                    pixel => Some((*sprite, pixel)),
+                }),
+            };
+            let index = match sprite {
+                Some((sprite, pixel)) => {
+                    // The hit ignores priority, but never happens at x = 255.
+                    if sprite == 0 && background != 0 && x != 255 {
+                        self.status |= SPRITE_ZERO_HIT;
+                    }
+                    let attributes = self.oam[sprite * 4 + 2];
+                    match background != 0 && attributes & BEHIND_BACKGROUND != 0 {
+                        true => background,
+                        false => 0x10 + (attributes as usize & 3) * 4 + pixel as usize,
+                    }
+                }
+                None => background,
+            };
+            self.framebuffer[line * WIDTH + x] = self.colour(index);
+        }
+    }
+
+    // The beam moves past the end of the current scanline.
+    fn end_line(&mut self) {
+        if (self.scanline as usize) < HEIGHT {
+            self.render_line(self.scanline as usize);
+        }
+        self.scanline += 1;
+        match self.scanline {
+            VBLANK_LINE => {
+                self.status |= VBLANK;
+                self.frame += 1;
+                if self.control & NMI_ENABLE != 0 {
+                    self.nmi = true;
+                }
+            }
+            PRE_RENDER_LINE => self.status &= !(VBLANK | SPRITE_ZERO_HIT | SPRITE_OVERFLOW),
+            LINES_PER_FRAME => {
+                self.scanline = 0;
+                self.frame_scroll_y = self.scroll_y;
+            }
+            _ => (),
+        }
+    }
+}
+
+// Palette entries $10, $14, $18 and $1C are the same bytes as $00, $04, $08 and $0C.
+fn palette_index(address: u16) -> usize {
+    let index = (address & 0x1F) as usize;
+    if index & 0x13 == 0x10 { index & 0x0F } else { index }
+}
+
+impl VirtualMemory for Ppu {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x07 {
+            PPUSTATUS => {
+                let status = self.status | (self.latch & 0x1F);
+                self.status &= !VBLANK;
+                self.second_write = false;
+                status
+            }
+            OAMDATA => self.oam[self.oam_address as usize],
+            PPUDATA => {
+                let address = self.address & 0x3FFF;
+                let value = match address {
+                    0x3F00.. => {
+                        // Palette reads are immediate, the buffer gets the nametable byte underneath.
+                        self.read_buffer = self.vram(address - 0x1000);
+                        self.vram(address)
+                    }
+                    _ => {
+                        let value = self.vram(address);
+                        std::mem::replace(&mut self.read_buffer, value)
+                    }
+                };
+                self.address = self.address.wrapping_add(self.increment());
+                value
+            }
+            _ => self.latch,
+        }
+    }
+
+    // What a read would return, without acknowledging vblank, resetting the
+    // write toggle or moving the VRAM address.
+    fn peek(&mut self, address: u16) -> u8 {
+        match address & 0x07 {
+            PPUSTATUS => self.status | (self.latch & 0x1F),
+            OAMDATA => self.oam[self.oam_address as usize],
+            PPUDATA => match self.address & 0x3FFF {
+                address @ 0x3F00.. => self.vram(address),
+                _ => self.read_buffer,
+            },
+            _ => self.latch,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address == OAMDMA {
+            self.dma_page = Some(value);
+            return;
+        }
+        self.latch = value;
+        match address & 0x07 {
+            PPUCTRL => {
+                // Enabling NMIs during vertical blank raises one straight away.
+                if self.control & NMI_ENABLE == 0 && value & NMI_ENABLE != 0 && self.status & VBLANK != 0 {
+                    self.nmi = true;
+                }
+                self.control = value;
+            }
+            PPUMASK => self.mask = value,
+            PPUSTATUS => (),
+            OAMADDR => self.oam_address = value,
+            OAMDATA => {
+                self.oam[self.oam_address as usize] = value;
+                self.oam_address = self.oam_address.wrapping_add(1);
+            }
+            PPUSCROLL => {
+                match self.second_write {
+                    false => self.scroll_x = value,
+                    true => self.scroll_y = value,
+                }
+                self.second_write = !self.second_write;
+            }
+            PPUADDR => {
+                self.address = match self.second_write {
+                    false => (self.address & 0x00FF) | ((value as u16 & 0x3F) << 8),
+                    true => (self.address & 0xFF00) | value as u16,
+                };
+                self.second_write = !self.second_write;
+            }
+            _ => {
+                self.set_vram(self.address, value);
+                self.address = self.address.wrapping_add(self.increment());
+            }
+        }
+    }
+
+    fn check_access(&self, address: u16, action: &SystemAction) -> Option<AnomalyKind> {
+        match (action, address & 0x07) {
+            (SystemAction::READ, PPUCTRL | PPUMASK | OAMADDR | PPUSCROLL | PPUADDR) => Some(AnomalyKind::WriteOnlyRead),
+            _ => None,
+        }
+    }
+
+    // Three dots per CPU cycle on NTSC.
+    fn tick(&mut self, cycles: u64) {
+        self.dot += cycles * 3;
+        while self.dot >= DOTS_PER_LINE {
+            self.dot -= DOTS_PER_LINE;
+            self.end_line();
+        }
+    }
+
+    fn stall(&mut self) -> u64 {
+        std::mem::take(&mut self.stall)
+    }
+
+    fn raster(&self) -> Option<(u64, u16)> {
+        Some((self.frame, self.scanline))
+    }
+
+    fn beam(&self) -> Option<(u16, u16)> {
+        Some((self.scanline, self.dot as u16))
+    }
+
+    fn nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi)
+    }
+}
+
+impl Device for Ppu {
+    fn name(&self) -> &'static str {
+        "PPU"
+    }
+
+    fn dma_source(&mut self) -> Option<u16> {
+        self.dma_page.take().map(|page| (page as u16) << 8)
+    }
+
+    // Copies start at OAMADDR and wrap around.
+    fn dma_write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter().take(256) {
+            self.oam[self.oam_address as usize] = *byte;
+            self.oam_address = self.oam_address.wrapping_add(1);
+        }
+        self.stall = DMA_CYCLES;
+    }
+}
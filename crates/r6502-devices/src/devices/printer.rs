@@ -0,0 +1,208 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use r6502_core::emulator::VirtualMemory;
+
+use super::rtc::civil;
+use super::Device;
+
+// A line printer, or a paper tape punch: every byte written to DATA goes on
+// the paper, and STATUS always has bit 0 set, the printer never being busy or
+// out of paper. Programs of the era wrote reports this way, a form feed
+// between pages, and tests can check the printout through a `PrinterHandle`,
+// which can be cloned and kept after the device is moved onto the bus. Each
+// page remembers the cycle it started on. The device decodes the low address
+// bit.
+//
+// With `split_pages` a form feed ends the page rather than being printed on
+// it. With a spool directory the printout is also written to a file there,
+// named after the host time the first byte came at, such as
+// printout-20240229-235959.txt, or with split pages one file per page,
+// printout-20240229-235959-p1.txt and on.
+
+pub const DATA: u16 = 0;
+pub const STATUS: u16 = 1;
+
+/// STATUS bit set while the printer takes bytes, which is always.
+pub const READY: u8 = 0x01;
+
+pub const FORM_FEED: u8 = 0x0C;
+
+/// A page of the printout.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Page {
+    /// Cycle the first byte on the page was printed at.
+    pub cycle: u64,
+    pub bytes: Vec<u8>,
+}
+
+impl Page {
+    /// The page as text, dropping carriage returns.
+    pub fn text(&self) -> String {
+        self.bytes.iter().filter(|byte| **byte != b'\r').map(|byte| *byte as char).collect()
+    }
+}
+
+#[derive(Debug)]
+struct Spool {
+    directory: PathBuf,
+    // Host time of the first byte, as YYYYMMDD-HHMMSS.
+    stamp: Option<String>,
+    file: Option<BufWriter<File>>,
+}
+
+#[derive(Debug, Default)]
+struct Paper {
+    pages: Vec<Page>,
+    split_pages: bool,
+    // Whether the last page ended with a form feed, so the next byte starts another.
+    page_ended: bool,
+    spool: Option<Spool>,
+}
+
+impl Paper {
+    fn print(&mut self, byte: u8, cycle: u64) {
+        if self.pages.is_empty() || self.page_ended {
+            self.pages.push(Page { cycle, bytes: vec![] });
+            self.page_ended = false;
+        }
+        let ends_page = self.split_pages && byte == FORM_FEED;
+        if !ends_page {
+            self.pages.last_mut().unwrap().bytes.push(byte);
+        }
+        self.page_ended = ends_page;
+        if let Err(error) = self.spool(byte, ends_page) {
+            log::warn!("Couldn't spool the printout, no longer spooling: {}", error);
+            self.spool = None;
+        }
+    }
+
+    fn spool(&mut self, byte: u8, ends_page: bool) -> std::io::Result<()> {
+        let page = self.pages.len();
+        let split_pages = self.split_pages;
+        let Some(spool) = self.spool.as_mut() else {
+            return Ok(());
+        };
+        let file = match spool.file.as_mut() {
+            Some(file) => file,
+            None => {
+                let stamp = spool.stamp.get_or_insert_with(host_stamp);
+                let name = match split_pages {
+                    true => format!("printout-{}-p{}.txt", stamp, page),
+                    false => format!("printout-{}.txt", stamp),
+                };
+                let path = spool.directory.join(name);
+                log::info!("spooling the printout to {}", path.display());
+                spool.file.insert(BufWriter::new(File::create(path)?))
+            }
+        };
+        if ends_page {
+            file.flush()?;
+            spool.file = None;
+            return Ok(());
+        }
+        file.write_all(&[byte])?;
+        if matches!(byte, b'\n' | b'\r' | FORM_FEED) {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
+
+fn host_stamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0);
+    let (year, month, day, hours, minutes, seconds, _) = civil(now);
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, hours, minutes, seconds)
+}
+
+/// The host's end of a [`Printer`].
+#[derive(Debug, Clone, Default)]
+pub struct PrinterHandle {
+    paper: Arc<Mutex<Paper>>,
+}
+
+impl PrinterHandle {
+    pub fn pages(&self) -> Vec<Page> {
+        self.paper.lock().unwrap().pages.clone()
+    }
+
+    /// Everything printed so far, pages run together.
+    pub fn output(&self) -> Vec<u8> {
+        self.paper.lock().unwrap().pages.iter().flat_map(|page| page.bytes.iter().copied()).collect()
+    }
+
+    /// Everything printed so far as text, dropping carriage returns.
+    pub fn text(&self) -> String {
+        self.paper.lock().unwrap().pages.iter().map(Page::text).collect()
+    }
+
+    /// Takes the pages printed so far, so the next byte starts a new page.
+    pub fn take_pages(&self) -> Vec<Page> {
+        let mut paper = self.paper.lock().unwrap();
+        paper.page_ended = false;
+        std::mem::take(&mut paper.pages)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Printer {
+    handle: PrinterHandle,
+    cycles: u64,
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A printer whose host end is `handle`.
+    pub fn with_handle(handle: PrinterHandle) -> Self {
+        Self { handle, cycles: 0 }
+    }
+
+    pub fn handle(&self) -> PrinterHandle {
+        self.handle.clone()
+    }
+
+    /// Starts a new page at each form feed.
+    pub fn split_pages(self) -> Self {
+        self.handle.paper.lock().unwrap().split_pages = true;
+        self
+    }
+
+    /// Also writes the printout to files in `directory`, named as described at
+    /// the top of this file.
+    pub fn spool<P: AsRef<Path>>(self, directory: P) -> Self {
+        let spool = Spool { directory: directory.as_ref().to_path_buf(), stamp: None, file: None };
+        self.handle.paper.lock().unwrap().spool = Some(spool);
+        self
+    }
+}
+
+impl VirtualMemory for Printer {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x01 {
+            DATA => 0,
+            _ => READY,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address & 0x01 == DATA {
+            self.handle.paper.lock().unwrap().print(value, self.cycles);
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.cycles += cycles;
+    }
+}
+
+impl Device for Printer {
+    fn name(&self) -> &'static str {
+        "printer"
+    }
+}
@@ -0,0 +1,203 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// 6532 RAM-I/O-Timer as wired in the Atari 2600. A9 selects between the 128
+// bytes of RAM and the I/O registers. Port A carries the joysticks, port B the
+// console switches, both active low.
+
+/// Switches on the console, readable through SWCHB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConsoleSwitch {
+    /// Game reset, held while pressed.
+    Reset,
+    /// Game select, held while pressed.
+    Select,
+    /// Color (on) or black and white (off) TV type.
+    Color,
+    /// Left player difficulty, A (on) or B (off).
+    LeftDifficulty,
+    RightDifficulty,
+}
+
+impl ConsoleSwitch {
+    fn bit(&self) -> u8 {
+        match self {
+            Self::Reset => 0,
+            Self::Select => 1,
+            Self::Color => 3,
+            Self::LeftDifficulty => 6,
+            Self::RightDifficulty => 7,
+        }
+    }
+
+    /// Reset and select spring back when released, the rest stay where they are put.
+    pub fn is_momentary(&self) -> bool {
+        matches!(self, Self::Reset | Self::Select)
+    }
+
+    pub fn for_key(key: &str) -> Option<Self> {
+        KEY_BINDINGS.iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, switch)| *switch)
+    }
+}
+
+/// Default keys for the console switches, the same layout as Stella.
+pub const KEY_BINDINGS: &[(&str, ConsoleSwitch)] = &[
+    ("F1", ConsoleSwitch::Select),
+    ("F2", ConsoleSwitch::Reset),
+    ("F3", ConsoleSwitch::Color),
+    ("F5", ConsoleSwitch::LeftDifficulty),
+    ("F7", ConsoleSwitch::RightDifficulty),
+];
+
+#[derive(Debug, Clone)]
+pub struct Riot {
+    ram: [u8; 128],
+    /// Levels driven onto the ports from outside.
+    inputs: [u8; 2],
+    outputs: [u8; 2],
+    /// Data direction registers, set bits are outputs.
+    directions: [u8; 2],
+    timer: u8,
+    interval: u16,
+    countdown: u16,
+    underflow: bool,
+}
+
+impl Default for Riot {
+    fn default() -> Self {
+        Self {
+            ram: [0; 128],
+            // Nothing pressed, color TV, both difficulties on B.
+            inputs: [0xFF, 0x0B],
+            outputs: [0; 2],
+            directions: [0; 2],
+            timer: 0,
+            interval: 1024,
+            countdown: 1024,
+            underflow: false,
+        }
+    }
+}
+
+impl Riot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `switch` is pressed (reset, select) or in its on position.
+    pub fn switch(&self, switch: ConsoleSwitch) -> bool {
+        let level = self.inputs[1] & (1 << switch.bit()) != 0;
+        match switch {
+            ConsoleSwitch::Reset | ConsoleSwitch::Select => !level,
+            _ => level,
+        }
+    }
+
+    pub fn set_switch(&mut self, switch: ConsoleSwitch, on: bool) {
+        let level = match switch {
+            ConsoleSwitch::Reset | ConsoleSwitch::Select => !on,
+            _ => on,
+        };
+        let bit = 1 << switch.bit();
+        if level {
+            self.inputs[1] |= bit;
+        } else {
+            self.inputs[1] &= !bit;
+        }
+    }
+
+    pub fn toggle_switch(&mut self, switch: ConsoleSwitch) {
+        self.set_switch(switch, !self.switch(switch));
+    }
+
+    /// Applies a key press or release using [`KEY_BINDINGS`]. Returns whether the key is bound.
+    pub fn key_event(&mut self, key: &str, pressed: bool) -> bool {
+        let Some(switch) = ConsoleSwitch::for_key(key) else {
+            return false;
+        };
+        if switch.is_momentary() {
+            self.set_switch(switch, pressed);
+        } else if pressed {
+            self.toggle_switch(switch);
+        }
+        true
+    }
+
+    /// Sets the levels on port A (SWCHA). Joystick directions read low while pushed.
+    pub fn set_port_a(&mut self, value: u8) {
+        self.inputs[0] = value;
+    }
+
+    fn port(&self, port: usize) -> u8 {
+        (self.outputs[port] & self.directions[port]) | (self.inputs[port] & !self.directions[port])
+    }
+}
+
+impl VirtualMemory for Riot {
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.peek(address);
+        // Reading INTIM clears the timer interrupt flag.
+        if address & 0x0205 == 0x0204 {
+            self.underflow = false;
+        }
+        value
+    }
+
+    fn peek(&mut self, address: u16) -> u8 {
+        if address & 0x0200 == 0 {
+            return self.ram[(address & 0x7F) as usize];
+        }
+        match address & 0x07 {
+            0x00 => self.port(0),
+            0x01 => self.directions[0],
+            0x02 => self.port(1),
+            0x03 => self.directions[1],
+            0x04 | 0x06 => self.timer,
+            _ => if self.underflow { 0xC0 } else { 0 },
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address & 0x0200 == 0 {
+            self.ram[(address & 0x7F) as usize] = value;
+            return;
+        }
+        // A2 picks the ports or the timer, A4 the timer over the PA7 edge detect
+        // control, which the 2600 doesn't use.
+        if address & 0x04 == 0 {
+            match address & 0x03 {
+                0x00 => self.outputs[0] = value,
+                0x01 => self.directions[0] = value,
+                0x02 => self.outputs[1] = value,
+                _ => self.directions[1] = value,
+            }
+        } else if address & 0x10 != 0 {
+            self.interval = [1, 8, 64, 1024][(address & 0x03) as usize];
+            self.countdown = self.interval;
+            self.timer = value;
+            self.underflow = false;
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        for _ in 0..cycles {
+            self.countdown -= 1;
+            if self.countdown > 0 {
+                continue;
+            }
+            // Once the timer passes zero it keeps counting down once per cycle.
+            if self.timer == 0 {
+                self.underflow = true;
+            }
+            self.timer = self.timer.wrapping_sub(1);
+            self.countdown = if self.underflow { 1 } else { self.interval };
+        }
+    }
+}
+
+impl Device for Riot {
+    fn name(&self) -> &'static str {
+        "RIOT"
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// Real time clock. Reading SECONDS latches the whole date, so a program that
+// reads the registers in order sees a consistent time even across a minute
+// boundary. Times are UTC and can't be set from the 6502 side.
+//
+// The device decodes the low three address bits.
+
+pub const SECONDS: u16 = 0;
+pub const MINUTES: u16 = 1;
+pub const HOURS: u16 = 2;
+/// Day of the month, from 1.
+pub const DAY: u16 = 3;
+/// Month, from 1.
+pub const MONTH: u16 = 4;
+pub const YEAR_LOW: u16 = 5;
+pub const YEAR_HIGH: u16 = 6;
+/// Day of the week, 0 is Sunday.
+pub const WEEKDAY: u16 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Host,
+    /// Seconds since the Unix epoch when the emulator started, advanced by emulated time.
+    Fixed { start: u64, clock: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct Rtc {
+    source: Source,
+    cycles: u64,
+    latched: [u8; 8],
+}
+
+impl Default for Rtc {
+    fn default() -> Self {
+        Self::host()
+    }
+}
+
+impl Rtc {
+    /// Follows the host's clock.
+    pub fn host() -> Self {
+        Self { source: Source::Host, cycles: 0, latched: [0; 8] }
+    }
+
+    /// Starts at `start` (seconds since the Unix epoch) and runs at the speed of the
+    /// emulated CPU, clocked at `clock` Hz, so tests see the same time on every run.
+    pub fn fixed(start: u64, clock: u64) -> Self {
+        Self { source: Source::Fixed { start, clock: clock.max(1) }, cycles: 0, latched: [0; 8] }
+    }
+
+    /// Seconds since the Unix epoch.
+    pub fn now(&self) -> u64 {
+        match self.source {
+            Source::Host => SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or(0),
+            Source::Fixed { start, clock } => start + self.cycles / clock,
+        }
+    }
+
+    fn latch(&mut self) {
+        let (year, month, day, hours, minutes, seconds, weekday) = civil(self.now());
+        self.latched = [seconds, minutes, hours, day, month, year as u8, (year >> 8) as u8, weekday];
+    }
+}
+
+// Splits seconds since the epoch into (year, month, day, hours, minutes, seconds,
+// weekday), using the days to civil algorithm from
+// http://howardhinnant.github.io/date_algorithms.html
+pub(crate) fn civil(time: u64) -> (u16, u8, u8, u8, u8, u8, u8) {
+    let days = (time / 86400) as i64;
+    let seconds_of_day = time % 86400;
+    let shifted = days + 719468;
+    let era = shifted.div_euclid(146097);
+    let day_of_era = shifted.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    // 1970-01-01 was a Thursday.
+    let weekday = (days + 4).rem_euclid(7);
+    (
+        year as u16,
+        month as u8,
+        day as u8,
+        (seconds_of_day / 3600) as u8,
+        (seconds_of_day / 60 % 60) as u8,
+        (seconds_of_day % 60) as u8,
+        weekday as u8,
+    )
+}
+
+impl VirtualMemory for Rtc {
+    fn read(&mut self, address: u16) -> u8 {
+        let register = address & 0x07;
+        if register == SECONDS {
+            self.latch();
+        }
+        self.latched[register as usize]
+    }
+
+    fn write(&mut self, _address: u16, _value: u8) {}
+
+    fn tick(&mut self, cycles: u64) {
+        self.cycles += cycles;
+    }
+}
+
+impl Device for Rtc {
+    fn name(&self) -> &'static str {
+        "RTC"
+    }
+}
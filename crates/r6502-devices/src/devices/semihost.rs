@@ -0,0 +1,185 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// Host file access for test ROMs and toy operating systems. A program writes a
+// file name to NAME one byte at a time, picks one of four channels, then issues
+// an OPEN command; after that each read of DATA returns the next byte of the
+// file and each write appends one. COMMAND reads back the status of the last
+// operation. Names are relative to a root directory and can't escape it.
+//
+// The device decodes the low three address bits, so it fits in eight bytes.
+
+pub const COMMAND: u16 = 0;
+pub const DATA: u16 = 1;
+pub const NAME: u16 = 2;
+pub const CHANNEL: u16 = 3;
+
+pub const OPEN_READ: u8 = 1;
+/// Creates the file, or truncates it if it exists.
+pub const OPEN_WRITE: u8 = 2;
+pub const OPEN_APPEND: u8 = 3;
+pub const CLOSE: u8 = 4;
+pub const DELETE: u8 = 5;
+/// Forgets the name written so far.
+pub const CLEAR_NAME: u8 = 6;
+
+pub const CHANNELS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Status {
+    Ok = 0,
+    /// DATA was read past the end of the file. Reads return zero.
+    EndOfFile = 1,
+    NotFound = 2,
+    /// The name is absolute or climbs out of the root directory.
+    Denied = 3,
+    /// The channel has no file open, or not in the direction used.
+    NotOpen = 4,
+    BadCommand = 5,
+    /// Any other host I/O error.
+    Failed = 6,
+}
+
+enum Handle {
+    Read(BufReader<File>),
+    Write(BufWriter<File>),
+}
+
+pub struct Semihost {
+    root: PathBuf,
+    name: Vec<u8>,
+    channel: usize,
+    handles: [Option<Handle>; CHANNELS],
+    status: Status,
+}
+
+impl Default for Semihost {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl Semihost {
+    /// Serves files from `root`.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            name: vec![],
+            channel: 0,
+            handles: Default::default(),
+            status: Status::Ok,
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    // Host path for the name written so far, if it stays inside the root.
+    fn path(&self) -> Option<PathBuf> {
+        let name = String::from_utf8_lossy(&self.name).into_owned();
+        let relative = Path::new(&name);
+        let inside = !name.is_empty() && relative.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        inside.then(|| self.root.join(relative))
+    }
+
+    fn command(&mut self, command: u8) -> Status {
+        match command {
+            OPEN_READ | OPEN_WRITE | OPEN_APPEND | DELETE => {
+                let path = self.path();
+                self.name.clear();
+                let Some(path) = path else {
+                    return Status::Denied;
+                };
+                let result = match command {
+                    OPEN_READ => File::open(&path).map(|file| Some(Handle::Read(BufReader::new(file)))),
+                    OPEN_WRITE => File::create(&path).map(|file| Some(Handle::Write(BufWriter::new(file)))),
+                    OPEN_APPEND => OpenOptions::new()
+                        .append(true)
+                        .create(true)
+                        .open(&path)
+                        .map(|file| Some(Handle::Write(BufWriter::new(file)))),
+                    _ => std::fs::remove_file(&path).map(|_| None),
+                };
+                match result {
+                    Ok(Some(handle)) => {
+                        self.handles[self.channel] = Some(handle);
+                        Status::Ok
+                    }
+                    Ok(None) => Status::Ok,
+                    Err(error) if error.kind() == ErrorKind::NotFound => Status::NotFound,
+                    Err(error) => {
+                        log::warn!("semihost: {}: {}", path.display(), error);
+                        Status::Failed
+                    }
+                }
+            }
+            CLOSE => match self.handles[self.channel].take() {
+                Some(Handle::Write(mut writer)) => match writer.flush() {
+                    Ok(_) => Status::Ok,
+                    Err(_) => Status::Failed,
+                },
+                Some(Handle::Read(_)) => Status::Ok,
+                None => Status::NotOpen,
+            },
+            CLEAR_NAME => {
+                self.name.clear();
+                Status::Ok
+            }
+            _ => Status::BadCommand,
+        }
+    }
+}
+
+impl VirtualMemory for Semihost {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x07 {
+            COMMAND => self.status as u8,
+            CHANNEL => self.channel as u8,
+            DATA => {
+                let mut byte = [0];
+                let (status, value) = match self.handles[self.channel].as_mut() {
+                    Some(Handle::Read(reader)) => match reader.read(&mut byte) {
+                        Ok(1) => (Status::Ok, byte[0]),
+                        Ok(_) => (Status::EndOfFile, 0),
+                        Err(_) => (Status::Failed, 0),
+                    },
+                    _ => (Status::NotOpen, 0),
+                };
+                self.status = status;
+                value
+            }
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address & 0x07 {
+            COMMAND => self.status = self.command(value),
+            NAME => self.name.push(value),
+            CHANNEL => self.channel = value as usize % CHANNELS,
+            DATA => {
+                self.status = match self.handles[self.channel].as_mut() {
+                    Some(Handle::Write(writer)) => match writer.write_all(&[value]) {
+                        Ok(_) => Status::Ok,
+                        Err(_) => Status::Failed,
+                    },
+                    _ => Status::NotOpen,
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Device for Semihost {
+    fn name(&self) -> &'static str {
+        "semihost"
+    }
+}
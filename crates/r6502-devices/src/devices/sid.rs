@@ -0,0 +1,200 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// MOS 6581/8580 Sound Interface Device, without the sound. Every write is
+// logged with the cycle it happened on, so a music player's output can be
+// checked against a reference: `dump` prints the registers frame by frame the
+// way siddump does, and `to_csv` gives the raw writes. Reads return the paddles
+// as centred and voice 3's oscillator and envelope as silent. The chip decodes
+// the low five address bits, so it repeats every 32 bytes through $D400-$D7FF.
+
+pub const VOICES: usize = 3;
+/// Registers per voice: frequency, pulse width, control, attack/decay, sustain/release.
+pub const VOICE_REGISTERS: usize = 7;
+pub const FC_LO: u8 = 0x15;
+pub const FC_HI: u8 = 0x16;
+pub const RES_FILT: u8 = 0x17;
+pub const MODE_VOL: u8 = 0x18;
+pub const POTX: u8 = 0x19;
+pub const POTY: u8 = 0x1A;
+pub const OSC3: u8 = 0x1B;
+pub const ENV3: u8 = 0x1C;
+
+/// Registers that can be written.
+pub const WRITABLE: usize = 0x19;
+
+/// CPU cycles per 50Hz frame on a PAL C64.
+pub const PAL_FRAME_CYCLES: u64 = 19_656;
+/// CPU clock of a PAL C64, which siddump's note names assume.
+pub const PAL_CLOCK: f64 = 985_248.0;
+
+/// A write to a SID register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SidWrite {
+    /// Cycles since the SID was created.
+    pub cycle: u64,
+    pub register: u8,
+    pub value: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sid {
+    registers: [u8; WRITABLE],
+    cycle: u64,
+    writes: Vec<SidWrite>,
+}
+
+impl Default for Sid {
+    fn default() -> Self {
+        Self { registers: [0; WRITABLE], cycle: 0, writes: vec![] }
+    }
+}
+
+impl Sid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last value written to a register.
+    pub fn register(&self, register: u8) -> u8 {
+        self.registers.get(register as usize & 0x1F).copied().unwrap_or(0)
+    }
+
+    pub fn registers(&self) -> &[u8; WRITABLE] {
+        &self.registers
+    }
+
+    /// Every write so far, oldest first.
+    pub fn writes(&self) -> &[SidWrite] {
+        &self.writes
+    }
+
+    pub fn take_writes(&mut self) -> Vec<SidWrite> {
+        std::mem::take(&mut self.writes)
+    }
+
+    /// Cycles counted since the SID was created.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    /// The log as CSV with a `cycle,register,value` header, in hex apart from the cycle.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("cycle,register,value\n");
+        for write in self.writes.iter() {
+            out += &format!("{},{:02X},{:02X}\n", write.cycle, write.register, write.value);
+        }
+        out
+    }
+
+    /// The writes replayed as a siddump style table, see [`dump`].
+    pub fn dump(&self, frame_cycles: u64) -> String {
+        dump(&self.writes, frame_cycles)
+    }
+}
+
+/// Registers as they stand at the end of each frame of `frame_cycles` cycles,
+/// up to the frame of the last write.
+pub fn frames(writes: &[SidWrite], frame_cycles: u64) -> Vec<[u8; WRITABLE]> {
+    let Some(last) = writes.last() else {
+        return vec![];
+    };
+    let frame_cycles = frame_cycles.max(1);
+    let mut registers = [0; WRITABLE];
+    let mut frames = vec![];
+    let mut pending = writes.iter().peekable();
+    for frame in 0..=last.cycle / frame_cycles {
+        while let Some(write) = pending.next_if(|write| write.cycle / frame_cycles == frame) {
+            if let Some(register) = registers.get_mut(write.register as usize) {
+                *register = write.value;
+            }
+        }
+        frames.push(registers);
+    }
+    frames
+}
+
+/// Name and absolute number of the note nearest `frequency`, a SID frequency
+/// register value, with A-4 at 440Hz and C-0 as note 0.
+pub fn note(frequency: u16) -> Option<(String, u8)> {
+    const NAMES: [&str; 12] = ["C-", "C#", "D-", "D#", "E-", "F-", "F#", "G-", "G#", "A-", "A#", "B-"];
+    let hertz = frequency as f64 * PAL_CLOCK / 16_777_216.0;
+    if hertz < 16.0 {
+        return None;
+    }
+    let number = (12.0 * (hertz / 440.0).log2()).round() as i32 + 57;
+    (0..96).contains(&number).then(|| (format!("{}{}", NAMES[number as usize % 12], number / 12), number as u8))
+}
+
+/// Prints registers frame by frame in the column layout of siddump: for each
+/// voice the frequency with its note, waveform, ADSR and pulse width, then the
+/// filter cutoff, resonance and routing, filter type and volume. Fields that
+/// didn't change since the previous frame are shown as dots.
+pub fn dump(writes: &[SidWrite], frame_cycles: u64) -> String {
+    const TYPES: [&str; 8] = ["Off", "Low", "Bnd", "L+B", "Hi ", "L+H", "B+H", "LBH"];
+    let mut out = String::from(
+        "| Frame | Freq Note/Abs WF ADSR Pul | Freq Note/Abs WF ADSR Pul | Freq Note/Abs WF ADSR Pul | FCut RC Typ V |\n\
+         +-------+---------------------------+---------------------------+---------------------------+---------------+\n",
+    );
+    let mut previous: Option<[u8; WRITABLE]> = None;
+    for (frame, registers) in frames(writes, frame_cycles).iter().enumerate() {
+        let changed = |registers_of: &[usize]| previous.is_none_or(|previous| registers_of.iter().any(|&index| previous[index] != registers[index]));
+        let field = |text: String, registers_of: &[usize]| if changed(registers_of) { text } else { ".".repeat(text.len()) };
+        out += &format!("| {:5} |", frame);
+        for voice in 0..VOICES {
+            let base = voice * VOICE_REGISTERS;
+            let frequency = u16::from_le_bytes([registers[base], registers[base + 1]]);
+            let pulse = u16::from_le_bytes([registers[base + 2], registers[base + 3] & 0x0F]);
+            let note = match note(frequency) {
+                Some((name, number)) => format!("{} {:02X}", name, number),
+                None => "... ..".to_owned(),
+            };
+            out += &format!(
+                " {} {}  {} {} {} |",
+                field(format!("{:04X}", frequency), &[base, base + 1]),
+                field(note, &[base, base + 1]),
+                field(format!("{:02X}", registers[base + 4]), &[base + 4]),
+                field(format!("{:02X}{:02X}", registers[base + 5], registers[base + 6]), &[base + 5, base + 6]),
+                field(format!("{:03X}", pulse), &[base + 2, base + 3]),
+            );
+        }
+        let cutoff = (registers[FC_HI as usize] as u16) << 3 | (registers[FC_LO as usize] & 0x07) as u16;
+        out += &format!(
+            " {} {} {} {} |\n",
+            field(format!("{:04X}", cutoff), &[FC_LO as usize, FC_HI as usize]),
+            field(format!("{:02X}", registers[RES_FILT as usize]), &[RES_FILT as usize]),
+            field(TYPES[(registers[MODE_VOL as usize] >> 4 & 0x07) as usize].to_owned(), &[MODE_VOL as usize]),
+            field(format!("{:X}", registers[MODE_VOL as usize] & 0x0F), &[MODE_VOL as usize]),
+        );
+        previous = Some(*registers);
+    }
+    out
+}
+
+impl VirtualMemory for Sid {
+    fn read(&mut self, address: u16) -> u8 {
+        match (address & 0x1F) as u8 {
+            POTX | POTY => 0x80,
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let register = (address & 0x1F) as u8;
+        if let Some(slot) = self.registers.get_mut(register as usize) {
+            *slot = value;
+            self.writes.push(SidWrite { cycle: self.cycle, register, value });
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.cycle += cycles;
+    }
+}
+
+impl Device for Sid {
+    fn name(&self) -> &'static str {
+        "SID"
+    }
+}
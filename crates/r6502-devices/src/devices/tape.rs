@@ -0,0 +1,212 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use r6502_core::emulator::VirtualMemory;
+use r6502_core::error::{R6502Error, Result};
+use r6502_core::loaders;
+
+use super::Device;
+
+// A cassette player on the machine's tape input. Vintage tape loaders don't
+// get bytes from the hardware, they time the edges of a square wave and
+// decide from the lengths what was recorded, so that is what this device
+// plays: the bytes of a tape image are encoded into a wave ahead of time, and
+// the level moves on with the emulated clock. A loader only works here if its
+// timing is right, which is the point.
+//
+// Reading LEVEL gives the input level in bit 7 and, in bit 6, whether a
+// falling edge came since LEVEL was last read, the way the C64's datasette
+// sets the CIA's FLAG. With `interrupts` the device holds IRQ until then.
+// CONTROL has bit 0 set while the motor runs and bit 1 once the tape has
+// played to the end; writing bit 0 starts and stops the motor. The tape plays
+// from the start with the motor on. The device decodes the low address bit.
+
+pub const LEVEL: u16 = 0;
+pub const CONTROL: u16 = 1;
+
+/// LEVEL bits.
+pub const HIGH: u8 = 0x80;
+pub const FALLING_EDGE: u8 = 0x40;
+
+/// CONTROL bits.
+pub const MOTOR: u8 = 0x01;
+pub const END: u8 = 0x02;
+
+/// How bytes become a square wave. Lengths are half periods in CPU cycles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// One wave per bit, short for 0 and long for 1, most significant bit
+    /// first, after `leader_waves` waves of `leader` and one of `sync`, which
+    /// loaders look for to find the data. The Apple II records this way.
+    PulseWidth { zero: u64, one: u64, leader: u64, leader_waves: u32, sync: u64 },
+    /// Each bit lasts `bit` cycles, filled with waves of `zero` or `one`,
+    /// least significant bit first. Bytes have a 0 start bit and `stop_bits`
+    /// 1 bits, and `leader` 1 bits go before the first. The Kansas City
+    /// standard records this way.
+    Fsk { bit: u64, zero: u64, one: u64, stop_bits: u8, leader: u32 },
+}
+
+impl Encoding {
+    /// The Apple II's, at its 1.023 MHz: 1 kHz and 2 kHz waves for the bits
+    /// after a second of 770 Hz leader.
+    pub fn apple2() -> Self {
+        Self::PulseWidth { zero: 250, one: 500, leader: 650, leader_waves: 770, sync: 225 }
+    }
+
+    /// The Kansas City standard at 300 baud, for a CPU clocked at `clock` Hz:
+    /// four waves of 1200 Hz for a 0, eight of 2400 Hz for a 1, two stop bits
+    /// and a second of leader.
+    pub fn kansas_city(clock: u64) -> Self {
+        Self::Fsk { bit: clock / 300, zero: clock / 2400, one: clock / 4800, stop_bits: 2, leader: 300 }
+    }
+
+    /// The half periods `data` is recorded as, starting from a low level.
+    pub fn encode(&self, data: &[u8]) -> Vec<u64> {
+        let mut halves = vec![];
+        match *self {
+            Self::PulseWidth { zero, one, leader, leader_waves, sync } => {
+                let wave = |halves: &mut Vec<u64>, half: u64| halves.extend([half, half]);
+                (0..leader_waves).for_each(|_| wave(&mut halves, leader));
+                if leader_waves > 0 {
+                    wave(&mut halves, sync);
+                }
+                for bit in data.iter().flat_map(|byte| (0..8).rev().map(move |bit| byte >> bit & 1)) {
+                    wave(&mut halves, if bit == 1 { one } else { zero });
+                }
+            }
+            Self::Fsk { bit, zero, one, stop_bits, leader } => {
+                let mut tone = |value: bool| {
+                    let half = if value { one } else { zero }.clamp(1, bit.max(1));
+                    let count = (bit / half).max(1);
+                    halves.extend((0..count).map(|_| half));
+                    // Whatever the waves leave of the bit goes on the last one.
+                    *halves.last_mut().unwrap() += bit.saturating_sub(half * count);
+                };
+                (0..leader).for_each(|_| tone(true));
+                for byte in data {
+                    tone(false);
+                    (0..8).for_each(|bit| tone(byte >> bit & 1 == 1));
+                    (0..stop_bits).for_each(|_| tone(true));
+                }
+            }
+        }
+        halves
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = R6502Error;
+
+    /// `apple2`, or `kansas-city` at 1 MHz.
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "apple2" => Ok(Self::apple2()),
+            "kansas-city" | "kcs" => Ok(Self::kansas_city(1_000_000)),
+            _ => Err(R6502Error::Config(format!("unknown tape encoding {}", name))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tape {
+    halves: Vec<u64>,
+    // Half period playing, and cycles of it played.
+    position: usize,
+    into: u64,
+    high: bool,
+    falling_edge: bool,
+    motor: bool,
+    pub interrupts: bool,
+}
+
+impl Tape {
+    /// A tape with `data` recorded on it in `encoding`.
+    pub fn new(data: &[u8], encoding: Encoding) -> Self {
+        Self::from_halves(encoding.encode(data))
+    }
+
+    /// A tape playing a wave of these half periods, for recordings made
+    /// elsewhere.
+    pub fn from_halves(halves: Vec<u64>) -> Self {
+        Self { halves, motor: true, ..Self::default() }
+    }
+
+    /// The tape image at `path`, which may be zipped.
+    pub fn load<P: AsRef<Path>>(path: P, encoding: Encoding) -> Result<Self> {
+        Ok(Self::new(&loaders::read_rom(path)?.0, encoding))
+    }
+
+    /// Holds IRQ from each falling edge until LEVEL is read.
+    pub fn with_interrupts(self) -> Self {
+        Self { interrupts: true, ..self }
+    }
+
+    /// Cycles the whole tape takes to play.
+    pub fn length(&self) -> u64 {
+        self.halves.iter().sum()
+    }
+
+    pub fn ended(&self) -> bool {
+        self.position >= self.halves.len()
+    }
+
+    pub fn motor(&self) -> bool {
+        self.motor
+    }
+
+    pub fn set_motor(&mut self, on: bool) {
+        self.motor = on;
+    }
+
+    pub fn rewind(&mut self) {
+        self.position = 0;
+        self.into = 0;
+        self.high = false;
+        self.falling_edge = false;
+    }
+}
+
+impl VirtualMemory for Tape {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x01 {
+            LEVEL => {
+                let level = (self.high as u8 * HIGH) | (self.falling_edge as u8 * FALLING_EDGE);
+                self.falling_edge = false;
+                level
+            }
+            _ => (self.motor as u8 * MOTOR) | (self.ended() as u8 * END),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address & 0x01 == CONTROL {
+            self.motor = value & MOTOR != 0;
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        if !self.motor {
+            return;
+        }
+        self.into += cycles;
+        while let Some(half) = self.halves.get(self.position).copied() {
+            if self.into < half {
+                break;
+            }
+            self.into -= half;
+            self.position += 1;
+            self.falling_edge |= self.high;
+            self.high = !self.high;
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.interrupts && self.falling_edge
+    }
+}
+
+impl Device for Tape {
+    fn name(&self) -> &'static str {
+        "tape"
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use r6502_core::emulator::VirtualMemory;
+use r6502_core::error::Result;
+
+use super::Device;
+
+// A UART whose other end is a TCP socket, so telnet, minicom (through socat)
+// or a script can be the terminal of a program developed on r6502. The
+// registers are the console's: writing DATA sends a byte, reading it takes the
+// next byte received or zero, and STATUS has bit 0 set while a byte is
+// waiting and bit 1 while a peer is connected. With `interrupts` the device
+// holds IRQ while a byte is waiting. It decodes the low address bit.
+//
+// The device either listens, taking one client at a time and the next once
+// that one hangs up, or connects out to a listening peer. Bytes written while
+// nobody is connected are dropped, as on an unplugged cable. Each connection
+// gets a thread reading the socket and one writing it, so the emulator never
+// blocks on the network; output a stalled peer hasn't taken is queued up to
+// OUTGOING_LIMIT writes, past which it's dropped like a UART overrun.
+//
+// With `telnet` the device speaks just enough of the protocol for a telnet
+// client to work as a terminal: it offers to echo and to suppress go-ahead,
+// which puts the client in character mode, drops the client's negotiation
+// and the NUL it sends after a carriage return, and doubles 0xFF on output.
+
+pub const DATA: u16 = 0;
+pub const STATUS: u16 = 1;
+
+/// STATUS bit set while a received byte is waiting.
+pub const RECEIVED: u8 = 0x01;
+/// STATUS bit set while a peer is connected.
+pub const CONNECTED: u8 = 0x02;
+
+/// Writes queued for a peer that isn't reading before further ones are dropped.
+pub const OUTGOING_LIMIT: usize = 64 * 1024;
+
+const IAC: u8 = 0xFF;
+const WILL: u8 = 0xFB;
+const SB: u8 = 0xFA;
+const SE: u8 = 0xF0;
+const ECHO: u8 = 0x01;
+const SUPPRESS_GO_AHEAD: u8 = 0x03;
+
+#[derive(Debug, Default)]
+struct Shared {
+    incoming: Mutex<VecDeque<u8>>,
+    outgoing: Mutex<Option<SyncSender<Vec<u8>>>>,
+    stream: Mutex<Option<TcpStream>>,
+    closed: AtomicBool,
+}
+
+#[derive(Debug)]
+pub struct TcpSerial {
+    shared: Arc<Shared>,
+    telnet: bool,
+    pub interrupts: bool,
+    // Where the listener is, to wake it when the device is dropped.
+    listening: Option<SocketAddr>,
+}
+
+impl TcpSerial {
+    /// Listens on `address` for a terminal to connect. Port 0 picks a free
+    /// port, see [`TcpSerial::local_addr`].
+    pub fn listen<A: ToSocketAddrs>(address: A, telnet: bool) -> Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let local = listener.local_addr()?;
+        log::info!("serial port listening on {}", local);
+        let shared = Arc::new(Shared::default());
+        let accepting = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accepting.closed.load(Ordering::Relaxed) {
+                    break;
+                }
+                match stream {
+                    Ok(stream) => serve(&accepting, stream, telnet),
+                    Err(error) => log::warn!("serial port couldn't accept a connection: {}", error),
+                }
+            }
+        });
+        Ok(Self { shared, telnet, interrupts: false, listening: Some(local) })
+    }
+
+    /// Connects to a peer listening on `address`.
+    pub fn connect<A: ToSocketAddrs>(address: A, telnet: bool) -> Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let shared = Arc::new(Shared::default());
+        let reading = shared.clone();
+        thread::spawn(move || serve(&reading, stream, telnet));
+        Ok(Self { shared, telnet, interrupts: false, listening: None })
+    }
+
+    /// Raises IRQ while a received byte is waiting.
+    pub fn with_interrupts(mut self) -> Self {
+        self.interrupts = true;
+        self
+    }
+
+    /// The address a listening device is bound to.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listening
+    }
+
+    pub fn connected(&self) -> bool {
+        self.shared.stream.lock().unwrap().is_some()
+    }
+
+    /// Received bytes not yet read.
+    pub fn pending(&self) -> usize {
+        self.shared.incoming.lock().unwrap().len()
+    }
+
+    /// Removes and returns the next received byte.
+    pub fn receive(&self) -> Option<u8> {
+        self.shared.incoming.lock().unwrap().pop_front()
+    }
+
+    /// Queues `bytes` for the peer, if there is one. Never blocks.
+    pub fn send(&self, bytes: &[u8]) {
+        let mut outgoing = self.shared.outgoing.lock().unwrap();
+        let Some(sender) = outgoing.as_ref() else {
+            return;
+        };
+        let escaped: Vec<u8> = match self.telnet {
+            true => bytes.iter().flat_map(|byte| if *byte == IAC { vec![IAC, IAC] } else { vec![*byte] }).collect(),
+            false => bytes.to_vec(),
+        };
+        match sender.try_send(escaped) {
+            Ok(()) => (),
+            Err(TrySendError::Full(_)) => log::debug!("serial peer isn't reading, output dropped"),
+            Err(TrySendError::Disconnected(_)) => *outgoing = None,
+        }
+    }
+}
+
+// Reads `stream` into the receive queue until the peer hangs up, with a second
+// thread writing out what `send` queues.
+fn serve(shared: &Shared, mut stream: TcpStream, telnet: bool) {
+    let peer = stream.peer_addr().map(|peer| peer.to_string()).unwrap_or_default();
+    log::info!("serial peer {} connected", peer);
+    let _ = stream.set_nodelay(true);
+    if telnet {
+        let _ = stream.write_all(&[IAC, WILL, ECHO, IAC, WILL, SUPPRESS_GO_AHEAD]);
+    }
+    let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(OUTGOING_LIMIT);
+    match stream.try_clone() {
+        Ok(mut writer) => {
+            thread::spawn(move || {
+                for bytes in receiver {
+                    if let Err(error) = writer.write_all(&bytes) {
+                        // Shutting down ends the read below as well.
+                        log::info!("serial peer went away: {}", error);
+                        let _ = writer.shutdown(Shutdown::Both);
+                        break;
+                    }
+                }
+            });
+            *shared.outgoing.lock().unwrap() = Some(sender);
+        }
+        Err(error) => log::warn!("serial port can't write to {}: {}", peer, error),
+    }
+    *shared.stream.lock().unwrap() = stream.try_clone().ok();
+    let mut filter = TelnetFilter::default();
+    let mut buffer = [0u8; 256];
+    loop {
+        match stream.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(count) => {
+                let mut incoming = shared.incoming.lock().unwrap();
+                match telnet {
+                    true => incoming.extend(buffer[..count].iter().filter_map(|byte| filter.next(*byte))),
+                    false => incoming.extend(&buffer[..count]),
+                }
+            }
+        }
+    }
+    *shared.outgoing.lock().unwrap() = None;
+    *shared.stream.lock().unwrap() = None;
+    log::info!("serial peer {} disconnected", peer);
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum TelnetFilter {
+    #[default]
+    Data,
+    CarriageReturn,
+    Command,
+    // WILL, WONT, DO or DONT, waiting for the option.
+    Option,
+    Subnegotiation,
+    SubnegotiationCommand,
+}
+
+impl TelnetFilter {
+    // The data byte `byte` stands for, if it isn't part of a command.
+    fn next(&mut self, byte: u8) -> Option<u8> {
+        let (state, data) = match (*self, byte) {
+            (Self::Data | Self::CarriageReturn, IAC) => (Self::Command, None),
+            (Self::CarriageReturn, 0) => (Self::Data, None),
+            (Self::Data | Self::CarriageReturn, b'\r') => (Self::CarriageReturn, Some(byte)),
+            (Self::Data | Self::CarriageReturn, _) => (Self::Data, Some(byte)),
+            (Self::Command, IAC) => (Self::Data, Some(IAC)),
+            (Self::Command, SB) => (Self::Subnegotiation, None),
+            (Self::Command, 0xFB..=0xFE) => (Self::Option, None),
+            (Self::Command | Self::Option, _) => (Self::Data, None),
+            (Self::Subnegotiation, IAC) => (Self::SubnegotiationCommand, None),
+            (Self::Subnegotiation, _) => (Self::Subnegotiation, None),
+            (Self::SubnegotiationCommand, SE) => (Self::Data, None),
+            (Self::SubnegotiationCommand, _) => (Self::Subnegotiation, None),
+        };
+        *self = state;
+        data
+    }
+}
+
+impl Drop for TcpSerial {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Relaxed);
+        *self.shared.outgoing.lock().unwrap() = None;
+        if let Some(stream) = self.shared.stream.lock().unwrap().take() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        // The listening thread only looks at `closed` when a connection comes in.
+        if let Some(address) = self.listening {
+            let _ = TcpStream::connect(address);
+        }
+    }
+}
+
+impl VirtualMemory for TcpSerial {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x01 {
+            DATA => self.receive().unwrap_or(0),
+            _ => ((self.pending() > 0) as u8 * RECEIVED) | (self.connected() as u8 * CONNECTED),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if address & 0x01 == DATA {
+            self.send(&[value]);
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.interrupts && self.pending() > 0
+    }
+}
+
+impl Device for TcpSerial {
+    fn name(&self) -> &'static str {
+        "serial"
+    }
+}
@@ -0,0 +1,214 @@
+use r6502_core::diagnostics::AnomalyKind;
+use r6502_core::emulator::VirtualMemory;
+use r6502_core::state::SystemAction;
+
+use super::Device;
+
+// Atari 2600 Television Interface Adaptor.
+// The TIA only decodes six address lines. Writes select one of the 45 write
+// registers, reads one of 14 collision and input latches at the same
+// addresses, so reading back a write register never returns what was written.
+// Strobe registers act on the write itself and ignore the value.
+
+pub const COLOR_CLOCKS_PER_LINE: u16 = 228;
+pub const HBLANK_CLOCKS: u16 = 68;
+/// Visible pixels per scanline.
+pub const PIXELS: u16 = COLOR_CLOCKS_PER_LINE - HBLANK_CLOCKS;
+
+const VSYNC: u8 = 0x00;
+const WSYNC: u8 = 0x02;
+const RSYNC: u8 = 0x03;
+const RESP0: u8 = 0x10;
+const RESBL: u8 = 0x14;
+const HMP0: u8 = 0x20;
+const HMBL: u8 = 0x24;
+const HMOVE: u8 = 0x2A;
+const HMCLR: u8 = 0x2B;
+const CXCLR: u8 = 0x2C;
+const LAST_WRITE: u8 = CXCLR;
+
+/// Movable objects, in register order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Object {
+    Player0,
+    Player1,
+    Missile0,
+    Missile1,
+    Ball,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tia {
+    registers: [u8; 0x40],
+    /// Collision latches CXM0P..CXPPMM, only bits 7 and 6 are meaningful.
+    collisions: [u8; 8],
+    /// INPT0..INPT5, bit 7 is the input level.
+    inputs: [u8; 6],
+    /// Horizontal position of each object, in visible pixels.
+    positions: [u8; 5],
+    color_clock: u16,
+    scanline: u16,
+    frame: u64,
+    wsync: bool,
+}
+
+impl Default for Tia {
+    fn default() -> Self {
+        Self {
+            registers: [0; 0x40],
+            collisions: [0; 8],
+            // Joystick buttons are active low and read as released.
+            inputs: [0, 0, 0, 0, 0x80, 0x80],
+            positions: [0; 5],
+            color_clock: 0,
+            scanline: 0,
+            frame: 0,
+            wsync: false,
+        }
+    }
+}
+
+impl Tia {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Last value written to a (non-strobe) write register.
+    pub fn register(&self, register: u8) -> u8 {
+        self.registers[(register & 0x3F) as usize]
+    }
+
+    pub fn collision(&self, register: u8) -> u8 {
+        self.collisions[(register & 0x07) as usize]
+    }
+
+    /// Latches a collision bit, for use by a renderer.
+    pub fn set_collision(&mut self, register: u8, bit: u8) {
+        self.collisions[(register & 0x07) as usize] |= 1 << bit;
+    }
+
+    /// Drives bit 7 of INPT0..INPT5. Fire buttons read low while pressed.
+    pub fn set_input(&mut self, input: usize, high: bool) {
+        if let Some(value) = self.inputs.get_mut(input) {
+            *value = if high { 0x80 } else { 0 };
+        }
+    }
+
+    pub fn set_fire_button(&mut self, player: usize, pressed: bool) {
+        self.set_input(4 + player.min(1), !pressed);
+    }
+
+    pub fn position(&self, object: Object) -> u8 {
+        self.positions[object as usize]
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    /// Current scanline and color clock within it. Dots below 68 are in horizontal blank.
+    pub fn beam(&self) -> (u16, u16) {
+        (self.scanline, self.color_clock)
+    }
+
+    fn strobe(&mut self, register: u8) {
+        match register {
+            WSYNC => self.wsync = true,
+            RSYNC => self.color_clock = 0,
+            RESP0..=RESBL => {
+                // Objects reset during horizontal blank appear at the left edge, otherwise
+                // a few pixels to the right of the beam because of the TIA's pipeline.
+                let delay = if register <= RESP0 + 1 { 5 } else { 4 };
+                let pixel = match self.color_clock.checked_sub(HBLANK_CLOCKS) {
+                    Some(pixel) => (pixel + delay) % PIXELS,
+                    None => delay - 2,
+                };
+                self.positions[(register - RESP0) as usize] = pixel as u8;
+            }
+            HMOVE => {
+                for (index, motion) in (HMP0..=HMBL).enumerate() {
+                    // Signed high nibble, positive values move left.
+                    let motion = (self.registers[motion as usize] as i8 >> 4) as i16;
+                    let position = self.positions[index] as i16 - motion;
+                    self.positions[index] = position.rem_euclid(PIXELS as i16) as u8;
+                }
+            }
+            HMCLR => self.registers[HMP0 as usize..=HMBL as usize].fill(0),
+            CXCLR => self.collisions.fill(0),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl VirtualMemory for Tia {
+    fn read(&mut self, address: u16) -> u8 {
+        self.peek(address)
+    }
+
+    // Collision latches only clear on a CXCLR write, so reads have no side effects.
+    fn peek(&mut self, address: u16) -> u8 {
+        match address & 0x0F {
+            register @ 0x00..=0x07 => self.collisions[register as usize],
+            register @ 0x08..=0x0D => self.inputs[register as usize - 0x08],
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        let register = (address & 0x3F) as u8;
+        match register {
+            WSYNC | RSYNC | RESP0..=RESBL | HMOVE | HMCLR | CXCLR => self.strobe(register),
+            VSYNC => {
+                // The frame ends when the kernel starts vertical sync.
+                if value & 0x02 != 0 && self.registers[VSYNC as usize] & 0x02 == 0 {
+                    self.scanline = 0;
+                    self.frame += 1;
+                }
+                self.registers[VSYNC as usize] = value;
+            }
+            _ if register <= LAST_WRITE => self.registers[register as usize] = value,
+            _ => (),
+        }
+    }
+
+    fn check_access(&self, address: u16, action: &SystemAction) -> Option<AnomalyKind> {
+        match action {
+            SystemAction::READ if address & 0x0F >= 0x0E => Some(AnomalyKind::WriteOnlyRead),
+            _ => None,
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        let clocks = self.color_clock as u64 + cycles * 3;
+        self.color_clock = (clocks % COLOR_CLOCKS_PER_LINE as u64) as u16;
+        self.scanline = self.scanline.wrapping_add((clocks / COLOR_CLOCKS_PER_LINE as u64) as u16);
+    }
+
+    /// WSYNC holds the CPU until the start of the next scanline.
+    fn stall(&mut self) -> u64 {
+        if !self.wsync {
+            return 0;
+        }
+        self.wsync = false;
+        let remaining = COLOR_CLOCKS_PER_LINE - self.color_clock;
+        remaining.div_ceil(3) as u64
+    }
+
+    fn raster(&self) -> Option<(u64, u16)> {
+        Some((self.frame, self.scanline))
+    }
+
+    fn beam(&self) -> Option<(u16, u16)> {
+        Some(Tia::beam(self))
+    }
+}
+
+impl Device for Tia {
+    fn name(&self) -> &'static str {
+        "TIA"
+    }
+}
@@ -0,0 +1,111 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// Programmable interval timer for the generic machine. The period is counted
+// in CPU cycles and latched from two registers; enabling the timer through
+// CONTROL reloads the counter. When it runs out the timer sets its flag, which
+// holds the IRQ line low (if enabled) until the program reads or writes STATUS,
+// then either reloads (repeating) or stops (one shot).
+//
+// The device decodes the low three address bits.
+
+pub const PERIOD_LOW: u16 = 0;
+pub const PERIOD_HIGH: u16 = 1;
+pub const CONTROL: u16 = 2;
+/// Bit 7 is set once the timer has run out. Accessing it clears the flag.
+pub const STATUS: u16 = 3;
+pub const COUNTER_LOW: u16 = 4;
+pub const COUNTER_HIGH: u16 = 5;
+
+pub const ENABLE: u8 = 0x01;
+pub const REPEAT: u8 = 0x02;
+pub const IRQ_ENABLE: u8 = 0x04;
+
+#[derive(Debug, Clone, Default)]
+pub struct Timer {
+    period: u16,
+    control: u8,
+    counter: u32,
+    expired: bool,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A period of zero counts the full 65536 cycles.
+    fn reload(&mut self) {
+        self.counter = if self.period == 0 { 0x10000 } else { self.period as u32 };
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.control & ENABLE != 0
+    }
+
+    pub fn expired(&self) -> bool {
+        self.expired
+    }
+}
+
+impl VirtualMemory for Timer {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x07 {
+            PERIOD_LOW => self.period as u8,
+            PERIOD_HIGH => (self.period >> 8) as u8,
+            CONTROL => self.control,
+            STATUS => {
+                let status = if self.expired { 0x80 } else { 0 };
+                self.expired = false;
+                status
+            }
+            COUNTER_LOW => self.counter as u8,
+            COUNTER_HIGH => (self.counter >> 8) as u8,
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address & 0x07 {
+            PERIOD_LOW => self.period = (self.period & 0xFF00) | value as u16,
+            PERIOD_HIGH => self.period = (self.period & 0x00FF) | ((value as u16) << 8),
+            CONTROL => {
+                if value & ENABLE != 0 && !self.is_running() {
+                    self.reload();
+                }
+                self.control = value;
+            }
+            STATUS => self.expired = false,
+            _ => (),
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        let mut cycles = cycles;
+        while self.is_running() && cycles > 0 {
+            if cycles < self.counter as u64 {
+                self.counter -= cycles as u32;
+                return;
+            }
+            cycles -= self.counter as u64;
+            self.expired = true;
+            if self.control & REPEAT != 0 {
+                self.reload();
+            } else {
+                self.counter = 0;
+                self.control &= !ENABLE;
+            }
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.expired && self.control & IRQ_ENABLE != 0
+    }
+}
+
+impl Device for Timer {
+    fn name(&self) -> &'static str {
+        "timer"
+    }
+}
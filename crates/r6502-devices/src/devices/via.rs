@@ -0,0 +1,262 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// 6522 Versatile Interface Adapter: two 8 bit ports, two 16 bit timers, a shift
+// register and an interrupt controller. The chip decodes the low four address
+// bits. Timer 1 counts down once per cycle and either stops (one shot) or
+// reloads from its latch (free running, ACR bit 6); timer 2 is one shot only.
+// The shift register just holds what is written, and the handshake lines are
+// reduced to CA1 and CB1 edges that set their flags.
+//
+// Interrupts go to the IRQ line unless the VIA is `wired_to_nmi`, the way the
+// VIC-20's RESTORE key VIA is.
+
+pub const ORB: u16 = 0x0;
+pub const ORA: u16 = 0x1;
+pub const DDRB: u16 = 0x2;
+pub const DDRA: u16 = 0x3;
+pub const T1C_L: u16 = 0x4;
+pub const T1C_H: u16 = 0x5;
+pub const T1L_L: u16 = 0x6;
+pub const T1L_H: u16 = 0x7;
+pub const T2C_L: u16 = 0x8;
+pub const T2C_H: u16 = 0x9;
+pub const SR: u16 = 0xA;
+pub const ACR: u16 = 0xB;
+pub const PCR: u16 = 0xC;
+pub const IFR: u16 = 0xD;
+pub const IER: u16 = 0xE;
+/// Port A without the handshake, which here is the same as ORA.
+pub const ORA_NO_HANDSHAKE: u16 = 0xF;
+
+/// Interrupt flag and enable bits.
+pub const CA2: u8 = 0x01;
+pub const CA1: u8 = 0x02;
+pub const SHIFT: u8 = 0x04;
+pub const CB2: u8 = 0x08;
+pub const CB1: u8 = 0x10;
+pub const TIMER2: u8 = 0x20;
+pub const TIMER1: u8 = 0x40;
+
+/// ACR: timer 1 reloads from its latch when it runs out.
+pub const FREE_RUN: u8 = 0x40;
+
+#[derive(Debug, Clone)]
+pub struct Via {
+    outputs: [u8; 2],
+    directions: [u8; 2],
+    /// Levels driven onto the ports from outside, pulled up when nothing does.
+    inputs: [u8; 2],
+    t1_latch: u16,
+    t1_counter: i64,
+    t1_armed: bool,
+    t2_latch_low: u8,
+    t2_counter: i64,
+    t2_armed: bool,
+    shift: u8,
+    acr: u8,
+    pcr: u8,
+    flags: u8,
+    enabled: u8,
+    nmi: bool,
+    /// Whether an NMI edge is waiting for the CPU.
+    nmi_edge: bool,
+}
+
+impl Default for Via {
+    fn default() -> Self {
+        Self {
+            outputs: [0; 2],
+            directions: [0; 2],
+            inputs: [0xFF; 2],
+            t1_latch: 0xFFFF,
+            t1_counter: 0xFFFF,
+            t1_armed: false,
+            t2_latch_low: 0xFF,
+            t2_counter: 0xFFFF,
+            t2_armed: false,
+            shift: 0,
+            acr: 0,
+            pcr: 0,
+            flags: 0,
+            enabled: 0,
+            nmi: false,
+            nmi_edge: false,
+        }
+    }
+}
+
+impl Via {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends interrupts to the NMI line instead of IRQ.
+    pub fn wired_to_nmi(mut self) -> Self {
+        self.nmi = true;
+        self
+    }
+
+    /// Drives port A (0) or B (1) from outside. Only bits set as inputs are read back.
+    pub fn set_input(&mut self, port: usize, value: u8) {
+        self.inputs[port.min(1)] = value;
+    }
+
+    /// What the VIA drives onto port A (0) or B (1), with inputs pulled high.
+    pub fn output(&self, port: usize) -> u8 {
+        let port = port.min(1);
+        (self.outputs[port] & self.directions[port]) | !self.directions[port]
+    }
+
+    /// Signals an active edge on CA1 (`false`) or CB1 (`true`), such as a key press.
+    pub fn trigger(&mut self, cb1: bool) {
+        self.raise(if cb1 { CB1 } else { CA1 });
+    }
+
+    pub fn flags(&self) -> u8 {
+        self.flags | if self.interrupting() { 0x80 } else { 0 }
+    }
+
+    fn interrupting(&self) -> bool {
+        self.flags & self.enabled & 0x7F != 0
+    }
+
+    fn raise(&mut self, flag: u8) {
+        let was = self.interrupting();
+        self.flags |= flag;
+        if self.nmi && !was && self.interrupting() {
+            self.nmi_edge = true;
+        }
+    }
+
+    fn port_read(&self, port: usize) -> u8 {
+        (self.outputs[port] & self.directions[port]) | (self.inputs[port] & !self.directions[port])
+    }
+}
+
+impl VirtualMemory for Via {
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.peek(address);
+        // Reading a port or a counter acknowledges its interrupts.
+        match address & 0x0F {
+            ORB => self.flags &= !(CB1 | CB2),
+            ORA => self.flags &= !(CA1 | CA2),
+            T1C_L => self.flags &= !TIMER1,
+            T2C_L => self.flags &= !TIMER2,
+            SR => self.flags &= !SHIFT,
+            _ => (),
+        }
+        value
+    }
+
+    fn peek(&mut self, address: u16) -> u8 {
+        match address & 0x0F {
+            ORB => self.port_read(1),
+            ORA => self.port_read(0),
+            DDRB => self.directions[1],
+            DDRA => self.directions[0],
+            T1C_L => self.t1_counter as u8,
+            T1C_H => (self.t1_counter >> 8) as u8,
+            T1L_L => self.t1_latch as u8,
+            T1L_H => (self.t1_latch >> 8) as u8,
+            T2C_L => self.t2_counter as u8,
+            T2C_H => (self.t2_counter >> 8) as u8,
+            SR => self.shift,
+            ACR => self.acr,
+            PCR => self.pcr,
+            IFR => self.flags(),
+            IER => self.enabled | 0x80,
+            _ => self.port_read(0),
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address & 0x0F {
+            ORB => {
+                self.flags &= !(CB1 | CB2);
+                self.outputs[1] = value;
+            }
+            ORA => {
+                self.flags &= !(CA1 | CA2);
+                self.outputs[0] = value;
+            }
+            DDRB => self.directions[1] = value,
+            DDRA => self.directions[0] = value,
+            T1C_L | T1L_L => self.t1_latch = (self.t1_latch & 0xFF00) | value as u16,
+            T1C_H => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (value as u16) << 8;
+                self.t1_counter = self.t1_latch as i64;
+                self.t1_armed = true;
+                self.flags &= !TIMER1;
+            }
+            T1L_H => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (value as u16) << 8;
+                self.flags &= !TIMER1;
+            }
+            T2C_L => self.t2_latch_low = value,
+            T2C_H => {
+                self.t2_counter = ((value as u16) << 8 | self.t2_latch_low as u16) as i64;
+                self.t2_armed = true;
+                self.flags &= !TIMER2;
+            }
+            SR => {
+                self.flags &= !SHIFT;
+                self.shift = value;
+            }
+            ACR => self.acr = value,
+            PCR => self.pcr = value,
+            // Writing ones clears those flags.
+            IFR => self.flags &= !(value & 0x7F),
+            IER => {
+                let was = self.interrupting();
+                match value & 0x80 {
+                    0 => self.enabled &= !value,
+                    _ => self.enabled |= value & 0x7F,
+                }
+                if self.nmi && !was && self.interrupting() {
+                    self.nmi_edge = true;
+                }
+            }
+            _ => self.outputs[0] = value,
+        }
+    }
+
+    // The counters wrap through $FFFF once they pass zero, so a stopped timer
+    // keeps counting without raising its flag again.
+    fn tick(&mut self, cycles: u64) {
+        self.t1_counter -= cycles as i64;
+        while self.t1_counter < 0 {
+            if self.t1_armed {
+                self.raise(TIMER1);
+                self.t1_armed = self.acr & FREE_RUN != 0;
+            }
+            // A free running period is the latch plus the two reload cycles.
+            self.t1_counter += match self.acr & FREE_RUN {
+                0 => 0x10000,
+                _ => self.t1_latch as i64 + 2,
+            };
+        }
+        self.t2_counter -= cycles as i64;
+        while self.t2_counter < 0 {
+            if std::mem::take(&mut self.t2_armed) {
+                self.raise(TIMER2);
+            }
+            self.t2_counter += 0x10000;
+        }
+    }
+
+    fn irq(&self) -> bool {
+        !self.nmi && self.interrupting()
+    }
+
+    fn nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi_edge)
+    }
+}
+
+impl Device for Via {
+    fn name(&self) -> &'static str {
+        "VIA"
+    }
+}
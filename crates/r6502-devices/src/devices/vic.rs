@@ -0,0 +1,182 @@
+use r6502_core::emulator::VirtualMemory;
+
+use super::Device;
+
+// VIC-20 Video Interface Chip, 6561 (PAL) or 6560 (NTSC). Sixteen registers at
+// $9000 set the screen geometry, where the screen and characters are in the
+// VIC's 16K view of memory, and the colours. The device keeps the registers and
+// the raster counter; drawing needs memory the VIC doesn't own, so it is done by
+// `machines::vic20::render`. The sound and light pen registers just hold what
+// is written.
+
+pub const COLUMNS: u16 = 0x2;
+pub const ROWS: u16 = 0x3;
+pub const RASTER: u16 = 0x4;
+pub const MEMORY: u16 = 0x5;
+pub const AUXILIARY: u16 = 0xE;
+pub const COLOURS: u16 = 0xF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Standard {
+    Pal,
+    Ntsc,
+}
+
+impl Standard {
+    pub fn cycles_per_line(&self) -> u64 {
+        match self {
+            Self::Pal => 71,
+            Self::Ntsc => 65,
+        }
+    }
+
+    pub fn lines(&self) -> u16 {
+        match self {
+            Self::Pal => 312,
+            Self::Ntsc => 261,
+        }
+    }
+
+    /// CPU clock in Hz.
+    pub fn clock(&self) -> u64 {
+        match self {
+            Self::Pal => 1_108_405,
+            Self::Ntsc => 1_022_727,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vic {
+    standard: Standard,
+    registers: [u8; 16],
+    cycle: u64,
+    line: u16,
+    frame: u64,
+}
+
+impl Vic {
+    /// A VIC with the registers the KERNAL sets up at reset.
+    pub fn new(standard: Standard) -> Self {
+        let registers = match standard {
+            Standard::Pal => [0x0C, 0x26, 0x96, 0x2E, 0, 0xF0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x1B],
+            Standard::Ntsc => [0x05, 0x19, 0x96, 0x2E, 0, 0xF0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x1B],
+        };
+        Self { standard, registers, cycle: 0, line: 0, frame: 0 }
+    }
+
+    pub fn standard(&self) -> Standard {
+        self.standard
+    }
+
+    pub fn register(&self, register: u16) -> u8 {
+        self.registers[(register & 0x0F) as usize]
+    }
+
+    pub fn columns(&self) -> usize {
+        (self.registers[COLUMNS as usize] & 0x7F) as usize
+    }
+
+    pub fn rows(&self) -> usize {
+        (self.registers[ROWS as usize] >> 1 & 0x3F) as usize
+    }
+
+    /// Whether characters are 8x16 rather than 8x8.
+    pub fn tall_characters(&self) -> bool {
+        self.registers[ROWS as usize] & 0x01 != 0
+    }
+
+    /// Start of screen memory in the VIC's address space.
+    pub fn screen(&self) -> u16 {
+        (self.registers[MEMORY as usize] as u16 & 0xF0) << 6 | (self.registers[COLUMNS as usize] as u16 & 0x80) << 2
+    }
+
+    /// Start of character memory in the VIC's address space.
+    pub fn characters(&self) -> u16 {
+        (self.registers[MEMORY as usize] as u16 & 0x0F) << 10
+    }
+
+    /// Colour RAM follows bit 9 of the screen address.
+    pub fn colour_ram(&self) -> u16 {
+        0x9400 | (self.registers[COLUMNS as usize] as u16 & 0x80) << 2
+    }
+
+    pub fn background(&self) -> u8 {
+        self.registers[COLOURS as usize] >> 4
+    }
+
+    pub fn border(&self) -> u8 {
+        self.registers[COLOURS as usize] & 0x07
+    }
+
+    pub fn auxiliary(&self) -> u8 {
+        self.registers[AUXILIARY as usize] >> 4
+    }
+
+    /// Clear for inverted characters, as set by bit 3 of the colour register.
+    pub fn normal_mode(&self) -> bool {
+        self.registers[COLOURS as usize] & 0x08 != 0
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn line(&self) -> u16 {
+        self.line
+    }
+}
+
+/// CPU address the VIC sees at `address` in its 16K view: the character ROM and
+/// I/O at $8000-$9FFF come first, then RAM from $0000.
+pub fn cpu_address(address: u16) -> u16 {
+    match address & 0x2000 {
+        0 => 0x8000 | (address & 0x1FFF),
+        _ => address & 0x1FFF,
+    }
+}
+
+impl VirtualMemory for Vic {
+    fn read(&mut self, address: u16) -> u8 {
+        match address & 0x0F {
+            // Bit 0 of the raster line is bit 7 of the rows register.
+            ROWS => (self.registers[ROWS as usize] & 0x7F) | ((self.line as u8 & 1) << 7),
+            RASTER => (self.line >> 1) as u8,
+            register => self.registers[register as usize],
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address & 0x0F {
+            RASTER => (),
+            register => self.registers[register as usize] = value,
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.cycle += cycles;
+        let per_line = self.standard.cycles_per_line();
+        while self.cycle >= per_line {
+            self.cycle -= per_line;
+            self.line += 1;
+            if self.line == self.standard.lines() {
+                self.line = 0;
+                self.frame += 1;
+            }
+        }
+    }
+
+    fn raster(&self) -> Option<(u64, u16)> {
+        Some((self.frame, self.line))
+    }
+
+    fn beam(&self) -> Option<(u16, u16)> {
+        Some((self.line, self.cycle as u16))
+    }
+}
+
+impl Device for Vic {
+    fn name(&self) -> &'static str {
+        "VIC"
+    }
+}
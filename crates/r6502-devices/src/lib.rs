@@ -0,0 +1,11 @@
+// Memory mapped devices, the bus that maps them into the processor's address
+// space, and machine profiles and config files built from them. The console
+// chips are behind features: `tia` for the Atari 2600 and `nes` for the NES
+// PPU, joypads and iNES cartridges.
+
+pub mod devices;
+pub mod machines;
+#[cfg(feature = "nes")]
+pub mod nes;
+
+pub use devices::{Bus, Device};
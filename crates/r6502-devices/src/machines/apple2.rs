@@ -0,0 +1,136 @@
+use r6502_core::emulator::{CPUEmulator, VirtualMemory};
+
+// Apple II 40 column text. The 24 rows of a text page aren't stored in order:
+// each third of the screen is eight rows 128 bytes apart, and the thirds are 40
+// bytes apart within those 128, which leaves 8 unused "screen holes" per 128.
+// Bit 7 set is normal video, $40-$7F flash and $00-$3F are inverse.
+
+pub const TEXT_PAGE1: u16 = 0x0400;
+pub const TEXT_PAGE2: u16 = 0x0800;
+pub const COLUMNS: usize = 40;
+pub const ROWS: usize = 24;
+
+/// Pixels across a rendered text page, seven per character.
+pub const WIDTH: usize = COLUMNS * 7;
+/// Pixels down a rendered text page, eight per character.
+pub const HEIGHT: usize = ROWS * 8;
+
+/// Address of the first character of `row` on the page at `page`.
+pub fn row_address(page: u16, row: usize) -> u16 {
+    page + 0x80 * (row % 8) as u16 + 0x28 * (row / 8) as u16
+}
+
+/// The screen codes of `row`, left to right.
+pub fn row<M: VirtualMemory>(emulator: &CPUEmulator<M>, page: u16, row: usize) -> Vec<u8> {
+    let start = row_address(page, row);
+    (0..COLUMNS as u16).map(|column| emulator.peek(start + column)).collect()
+}
+
+/// The page as text, one line per row, with inverse and flashing characters
+/// shown like normal ones.
+pub fn text<M: VirtualMemory>(emulator: &CPUEmulator<M>, page: u16) -> String {
+    (0..ROWS).map(|index| row(emulator, page, index).into_iter().map(character).collect::<String>() + "\n").collect()
+}
+
+/// Character the 2513 generator shows for a screen byte. The original Apple II
+/// has no lower case, so only the low six bits pick the character.
+pub fn character(byte: u8) -> char {
+    match byte & 0x3F {
+        code @ 0x00..=0x1F => (code + 0x40) as char,
+        code => code as char,
+    }
+}
+
+/// Renders the page to a `WIDTH` x `HEIGHT` framebuffer of 0 (black) and 1
+/// (white). `flash` is the phase of the flashing characters, which the hardware
+/// swaps between normal and inverse a few times a second.
+pub fn render<M: VirtualMemory>(emulator: &CPUEmulator<M>, page: u16, flash: bool) -> Vec<u8> {
+    let mut pixels = vec![0; WIDTH * HEIGHT];
+    for row_index in 0..ROWS {
+        for (column, byte) in row(emulator, page, row_index).into_iter().enumerate() {
+            let inverse = match byte {
+                0x00..=0x3F => true,
+                0x40..=0x7F => flash,
+                _ => false,
+            };
+            let glyph = &GLYPHS[(byte & 0x3F) as usize];
+            for line in 0..8 {
+                // Glyphs are 5x7 in the middle of the 7x8 cell.
+                let bits = glyph.get(line).map_or(0, |bits| bits << 1);
+                for x in 0..7 {
+                    let lit = bits >> (6 - x) & 1 == 1;
+                    pixels[(row_index * 8 + line) * WIDTH + column * 7 + x] = (lit != inverse) as u8;
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// 2513 character generator glyphs for $00-$3F, `@` first, five bits per line.
+const GLYPHS: [[u8; 7]; 64] = [
+    [0x0E, 0x11, 0x17, 0x15, 0x16, 0x10, 0x0F],
+    [0x04, 0x0A, 0x11, 0x11, 0x1F, 0x11, 0x11],
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+    [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E],
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+    [0x0F, 0x10, 0x10, 0x10, 0x13, 0x11, 0x0F],
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+    [0x01, 0x01, 0x01, 0x01, 0x01, 0x11, 0x0E],
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+    [0x0E, 0x11, 0x10, 0x0E, 0x01, 0x11, 0x0E],
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x1B, 0x11],
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+    [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04],
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+    [0x1F, 0x18, 0x18, 0x18, 0x18, 0x18, 0x1F],
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00],
+    [0x1F, 0x03, 0x03, 0x03, 0x03, 0x03, 0x1F],
+    [0x00, 0x00, 0x04, 0x0A, 0x11, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+    [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00],
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04],
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+    [0x08, 0x14, 0x14, 0x08, 0x15, 0x12, 0x0D],
+    [0x04, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00],
+    [0x04, 0x08, 0x10, 0x10, 0x10, 0x08, 0x04],
+    [0x04, 0x02, 0x01, 0x01, 0x01, 0x02, 0x04],
+    [0x04, 0x15, 0x0E, 0x04, 0x0E, 0x15, 0x04],
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x04, 0x04, 0x08],
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04],
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+    [0x0E, 0x11, 0x01, 0x06, 0x08, 0x10, 0x1F],
+    [0x1F, 0x01, 0x02, 0x06, 0x01, 0x11, 0x0E],
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+    [0x07, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x1C],
+    [0x00, 0x00, 0x04, 0x00, 0x04, 0x00, 0x00],
+    [0x00, 0x00, 0x04, 0x00, 0x04, 0x04, 0x08],
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+    [0x0E, 0x11, 0x02, 0x04, 0x04, 0x00, 0x04],
+];
@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+use crate::devices::antic::{Antic, Standard};
+use crate::devices::gtia::Gtia;
+use crate::devices::pia::Pia;
+use crate::devices::pokey::Pokey;
+use crate::devices::Bus;
+use r6502_core::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory};
+use r6502_core::error::{R6502Error, Result};
+use r6502_core::state::SystemState;
+
+// Atari 400/800, with the 6502C "SALLY" treated as a plain 6502. RAM runs up
+// from $0000 to the installed size, at most 48K. An 8K cartridge sits at $A000
+// and a 16K one at $8000, over the top of RAM. GTIA, POKEY, PIA and ANTIC are
+// at $D000, $D200, $D300 and $D400, each mirrored through its page, and the 10K
+// OS ROM fills $D800-$FFFF. The OS isn't included, load it with `Atari8::os`.
+//
+// ANTIC only keeps time and raises the display list and vertical blank NMIs,
+// see `devices::antic`, which is enough for the OS to boot and for programs that
+// don't need a picture.
+
+pub const GTIA: u16 = 0xD000;
+pub const POKEY: u16 = 0xD200;
+pub const PIA: u16 = 0xD300;
+pub const ANTIC: u16 = 0xD400;
+pub const OS_ROM: u16 = 0xD800;
+pub const OS_ROM_SIZE: usize = 0x2800;
+pub const MAX_RAM: usize = 0xC000;
+
+/// An Atari 400/800 to build.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Atari8 {
+    pub standard: Standard,
+    /// Installed RAM in bytes, from $0000.
+    pub ram: usize,
+    pub os: Vec<u8>,
+    /// 8K or 16K cartridge image.
+    pub cartridge: Option<Vec<u8>>,
+}
+
+impl Atari8 {
+    /// A machine with `ram` bytes of RAM, up to 48K.
+    pub fn new(standard: Standard, ram: usize) -> Self {
+        Self { standard, ram: ram.min(MAX_RAM), os: vec![], cartridge: None }
+    }
+
+    /// The 10K OS ROM, floating point package included.
+    pub fn os(mut self, image: &[u8]) -> Self {
+        self.os = image.to_vec();
+        self
+    }
+
+    pub fn cartridge(mut self, image: &[u8]) -> Self {
+        self.cartridge = Some(image.to_vec());
+        self
+    }
+
+    pub fn bus(&self) -> Result<Bus> {
+        if self.os.len() > OS_ROM_SIZE {
+            return Err(R6502Error::ImageTooLarge { length: self.os.len(), origin: OS_ROM });
+        }
+        let mut memory = DefaultVirtualMemory::default().with_image(OS_ROM, &self.os).with_rom(OS_ROM, 0xFFFF);
+        if self.ram < MAX_RAM {
+            memory = memory.with_rom(self.ram as u16, (MAX_RAM - 1) as u16);
+        }
+        if let Some(image) = self.cartridge.as_ref() {
+            let origin = match image.len() {
+                0..=0x2000 => 0xA000,
+                0x2001..=0x4000 => 0x8000,
+                length => return Err(R6502Error::ImageTooLarge { length, origin: 0x8000 }),
+            };
+            memory = memory.with_image(origin, image).with_rom(origin, (MAX_RAM - 1) as u16);
+        }
+
+        Ok(Bus::new(memory)
+            .map(GTIA, GTIA + 0xFF, Gtia::new(self.standard == Standard::Pal))
+            .map(POKEY, POKEY + 0xFF, Pokey::new())
+            .map(PIA, PIA + 0xFF, Pia::new())
+            .map(ANTIC, ANTIC + 0xFF, Antic::new(self.standard)))
+    }
+
+    /// Builds the machine, reset and ready to run.
+    pub fn build(&self) -> Result<CPUEmulator<Bus>> {
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState::default())
+            .memory(Arc::new(Mutex::new(self.bus()?)))
+            .build()?;
+        emulator.reset();
+        Ok(emulator)
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "tia")]
+use crate::devices::riot::Riot;
+#[cfg(feature = "tia")]
+use crate::devices::tia::Tia;
+use crate::devices::Bus;
+use crate::machines::romdb::{KnownRom, RomDatabase};
+use crate::machines::Machine;
+#[cfg(feature = "nes")]
+use crate::nes::Cartridge;
+use r6502_core::emulator::{CPUEmulator, CPUEmulatorBuilder};
+use r6502_core::error::Result;
+#[cfg(feature = "nes")]
+use r6502_core::loaders::INesImage;
+use r6502_core::loaders::{self, Format};
+use r6502_core::state::SystemState;
+
+// "Just run this file": the format is sniffed by `Format::detect`, the machine
+// guessed from the format, and the emulator built with that machine's devices
+// where this crate has them (the 2600's TIA and RIOT, the NES PPU) and its
+// register descriptions. Either guess can be overridden when it is wrong, and
+// a file a `RomDatabase` knows gets the machine and origin recorded for it.
+// Files are read with `loaders::read_rom`, so they may be zipped.
+
+/// A file loaded by an [`AutoLoader`], with what it was taken for.
+pub struct AutoLoaded {
+    pub format: Format,
+    pub machine: Machine,
+    /// What the database took the file for.
+    pub identified: Option<KnownRom>,
+    /// Reset and ready to run.
+    pub emulator: CPUEmulator<Bus>,
+}
+
+/// Loads files in whatever format they are in. Fields left `None` are guessed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AutoLoader {
+    pub format: Option<Format>,
+    pub machine: Option<Machine>,
+    /// Where a raw binary goes, $0000 unless given or known to the database.
+    pub origin: Option<u16>,
+    /// Known ROMs, looked up by the CRC-32 of the whole file.
+    pub database: Option<RomDatabase>,
+}
+
+impl AutoLoader {
+    pub fn format(self, format: Format) -> Self {
+        Self { format: Some(format), ..self }
+    }
+
+    pub fn machine(self, machine: Machine) -> Self {
+        Self { machine: Some(machine), ..self }
+    }
+
+    pub fn origin(self, origin: u16) -> Self {
+        Self { origin: Some(origin), ..self }
+    }
+
+    pub fn database(self, database: RomDatabase) -> Self {
+        Self { database: Some(database), ..self }
+    }
+
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<AutoLoaded> {
+        let (data, extension) = loaders::read_rom(path)?;
+        self.load_bytes(&data, extension.as_deref())
+    }
+
+    /// Loads `data`, using the `extension` of the file it came from, if any, to
+    /// help tell the format.
+    pub fn load_bytes(&self, data: &[u8], extension: Option<&str>) -> Result<AutoLoaded> {
+        let identified = self.database.as_ref().and_then(|database| database.identify(data)).cloned();
+        // A known ROM with an origin is a raw image, whatever its shape suggests.
+        let raw = identified.as_ref().and_then(|rom| rom.origin).map(|_| Format::Binary);
+        let format = self.format.or(raw).unwrap_or_else(|| Format::detect(data, extension));
+        let machine = self.machine.or(identified.as_ref().map(|rom| rom.machine)).unwrap_or_else(|| guess_machine(format, data));
+        log::info!("loading a {} file for the {} machine", format, machine);
+        let origin = self.origin.or(identified.as_ref().and_then(|rom| rom.origin)).unwrap_or(0);
+        let program = format.load(data, origin)?;
+        let entry = program.entry;
+        let bus = match (format, machine) {
+            #[cfg(feature = "nes")]
+            (Format::INes, Machine::Nes) => INesImage::parse(data)?.bus()?,
+            #[cfg(feature = "tia")]
+            (_, Machine::Atari2600) => Bus::new(program.memory).map(0x0000, 0x003F, Tia::new()).map(0x0280, 0x029F, Riot::new()),
+            _ => Bus::new(program.memory),
+        };
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState::default())
+            .memory(Arc::new(Mutex::new(bus)))
+            .registers(machine.registers())
+            .build()?;
+        emulator.reset();
+        if let Some(entry) = entry {
+            emulator.state.pc = entry;
+        }
+        Ok(AutoLoaded { format, machine, identified, emulator })
+    }
+}
+
+/// Loads the file at `path`, guessing its format and machine unless the
+/// built-in ROM database knows it.
+pub fn auto_load<P: AsRef<Path>>(path: P) -> Result<AutoLoaded> {
+    AutoLoader::default().database(RomDatabase::builtin()).load(path)
+}
+
+/// The machine a file in `format` is most likely for. PRG files loaded at the
+/// start of C64 BASIC are for the C64, other PRG files for the VIC-20.
+pub fn guess_machine(format: Format, data: &[u8]) -> Machine {
+    match format {
+        Format::INes => Machine::Nes,
+        Format::Atari2600 => Machine::Atari2600,
+        Format::Prg if data.starts_with(&[0x01, 0x08]) => Machine::C64,
+        Format::Prg => Machine::Vic20,
+        Format::Binary | Format::IntelHex | Format::Srec => Machine::Generic,
+    }
+}
@@ -0,0 +1,374 @@
+use std::sync::{Arc, Mutex};
+
+use r6502_core::assembler::assemble;
+use crate::devices::banked::BankedRam;
+use crate::devices::console::{Console, ConsoleHandle};
+use crate::devices::via::Via;
+use crate::devices::Bus;
+use r6502_core::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, StopReason, VirtualMemory};
+use r6502_core::error::{R6502Error, Result};
+use r6502_core::state::{SystemFlags, SystemState};
+
+// BBC Micro model B. 32K of RAM, sixteen 16K sideways ROM slots at $8000 picked
+// by ROMSEL at $FE30, and the MOS at $C000 with the SHEILA I/O page at $FE00.
+// The system and user VIAs sit at $FE40 and $FE60. The slots are the banks of a
+// `BankedRam`, so they also behave as sideways RAM.
+//
+// Without a MOS image the machine runs a small stand-in OS instead. It keeps
+// the MOS entry points at $FFE0-$FFF7 and the vectors in page 2, but OSWRCH,
+// OSRDCH, OSBYTE, OSWORD and OSCLI end up at `BRK` syscalls with signatures
+// from $E0 that are handled in Rust and talk to a `Console` at $FC00. Reset
+// prints the banner and enters the highest slot holding a language, such as
+// BBC BASIC, so it runs to its prompt and takes input typed on the console.
+// There's no screen, keyboard, sound or filing system behind it.
+
+pub const SIDEWAYS: u16 = 0x8000;
+pub const ROMSEL: u16 = 0xFE30;
+pub const SYSTEM_VIA: u16 = 0xFE40;
+pub const USER_VIA: u16 = 0xFE60;
+pub const CONSOLE: u16 = 0xFC00;
+pub const MOS: u16 = 0xC000;
+pub const SLOTS: usize = 16;
+pub const SLOT_SIZE: usize = 0x4000;
+
+pub const OSRDCH: u16 = 0xFFE0;
+pub const OSASCI: u16 = 0xFFE3;
+pub const OSNEWL: u16 = 0xFFE7;
+pub const OSWRCH: u16 = 0xFFEE;
+pub const OSWORD: u16 = 0xFFF1;
+pub const OSBYTE: u16 = 0xFFF4;
+pub const OSCLI: u16 = 0xFFF7;
+
+/// Where the MOS keeps the number of the current language ROM.
+const LANGUAGE: u16 = 0x028C;
+/// OSBYTE's result for HIMEM, the bottom of the mode 7 screen.
+const HIMEM: u16 = 0x7C00;
+/// OSBYTE's result for OSHWM, the default PAGE without a filing system.
+const OSHWM: u16 = 0x0E00;
+
+/// Syscall signatures of the stand-in OS calls.
+pub const SYSCALL_WRCH: u8 = 0xE0;
+pub const SYSCALL_RDCH: u8 = 0xE1;
+pub const SYSCALL_BYTE: u8 = 0xE2;
+pub const SYSCALL_WORD: u8 = 0xE3;
+pub const SYSCALL_CLI: u8 = 0xE4;
+
+const HLE_OS: &str = "
+        .org $f800
+oswrch: brk
+        .byte $e0
+        rts
+osrdch: brk
+        .byte $e1
+        rts
+osbyte: brk
+        .byte $e2
+        rts
+osword: brk
+        .byte $e3
+        rts
+oscli:  brk
+        .byte $e4
+        rts
+none:   rts
+irq1:   lda $fc
+nmi:    rti
+reset:  sei
+        cld
+        ldx #$ff
+        txs
+        ldx #defaults_end-defaults-1
+copy:   lda defaults,x
+        sta $0200,x
+        dex
+        bpl copy
+        ldx $028c
+        stx $f4
+        stx $fe30
+        ldx #0
+banner: lda message,x
+        beq title
+        jsr $ffe3
+        inx
+        bne banner
+title:  ldx #0
+name:   lda $8009,x
+        beq enter
+        jsr $ffee
+        inx
+        bne name
+enter:  jsr $ffe7
+        jsr $ffe7
+        cli
+        lda #1
+        jmp $8000
+irq:    sta $fc
+        pla
+        pha
+        and #$10
+        bne brk
+        jmp ($0204)
+brk:    txa
+        pha
+        tsx
+        lda $0104,x
+        sta $fe
+        lda $0103,x
+        sta $fd
+        bne low
+        dec $fe
+low:    dec $fd
+        pla
+        tax
+        lda $fc
+        cli
+        jmp ($0202)
+error:  ldy #1
+print:  lda ($fd),y
+        beq halt
+        jsr $ffe3
+        iny
+        bne print
+halt:   jmp halt
+message: .byte \"BBC Computer 32K\", 13, 13, 0
+defaults: .word none, error, irq1, none, oscli, osbyte, osword, oswrch, osrdch
+        .word none, none, none, none, none, none, none
+defaults_end:
+        .org $ffe0
+        jmp ($0210)
+        cmp #13
+        bne $ffee
+        lda #10
+        jsr $ffee
+        lda #13
+        jmp ($020e)
+        jmp ($020c)
+        jmp ($020a)
+        jmp ($0208)
+        .word nmi, reset, irq
+";
+
+/// A BBC Micro to build, with its sideways ROMs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BbcMicro {
+    /// The real MOS, in place of the stand-in OS.
+    pub mos: Option<Vec<u8>>,
+    pub sideways: Vec<(usize, Vec<u8>)>,
+}
+
+impl BbcMicro {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mos(mut self, image: &[u8]) -> Self {
+        self.mos = Some(image.to_vec());
+        self
+    }
+
+    /// Puts `image` in sideways slot `slot`, 0-15.
+    pub fn sideways_rom(mut self, slot: usize, image: &[u8]) -> Self {
+        self.sideways.push((slot % SLOTS, image.to_vec()));
+        self
+    }
+
+    /// Highest slot whose ROM header says it's a language.
+    pub fn language(&self) -> Option<usize> {
+        self.sideways.iter().filter(|(_, image)| image.get(6).is_some_and(|kind| kind & 0x40 != 0)).map(|(slot, _)| *slot).max()
+    }
+
+    pub fn bus(&self) -> Result<Bus> {
+        let os = match self.mos.as_ref() {
+            Some(image) => image.clone(),
+            None => {
+                let assembly = assemble(HLE_OS, 0xF800)?;
+                let mut image = vec![0; 0x3800];
+                image.extend(assembly.image);
+                image
+            }
+        };
+        if os.len() > 0x4000 {
+            return Err(R6502Error::ImageTooLarge { length: os.len(), origin: MOS });
+        }
+        let language = self.language().unwrap_or(0) as u8;
+        let memory = DefaultVirtualMemory::default()
+            .with_image(MOS, &os)
+            .with_image(LANGUAGE, &[language])
+            .with_rom(MOS, 0xFBFF)
+            .with_rom(0xFF00, 0xFFFF);
+
+        let mut sideways = BankedRam::new(SIDEWAYS, SLOT_SIZE, SLOTS, ROMSEL);
+        for (slot, image) in self.sideways.iter() {
+            if image.len() > SLOT_SIZE {
+                return Err(R6502Error::ImageTooLarge { length: image.len(), origin: SIDEWAYS });
+            }
+            sideways.bank_data_mut(*slot)[..image.len()].copy_from_slice(image);
+        }
+        sideways.set_bank(language as usize);
+
+        Ok(Bus::new(memory)
+            .map(SIDEWAYS, 0xBFFF, sideways)
+            .also_at(ROMSEL, ROMSEL)
+            .map(SYSTEM_VIA, SYSTEM_VIA + 0x1F, Via::new())
+            .map(USER_VIA, USER_VIA + 0x1F, Via::new())
+            .map(CONSOLE, CONSOLE + 1, Console::new()))
+    }
+
+    /// Builds the machine, reset and ready to run, with the stand-in OS calls
+    /// hooked up unless a real MOS was given.
+    pub fn build(&self) -> Result<CPUEmulator<Bus>> {
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState::default())
+            .memory(Arc::new(Mutex::new(self.bus()?)))
+            .build()?;
+        if self.mos.is_none() {
+            install_os(&mut emulator);
+        }
+        emulator.reset();
+        Ok(emulator)
+    }
+}
+
+/// The host end of the console the stand-in OS reads and writes.
+pub fn console(emulator: &CPUEmulator<Bus>) -> Option<ConsoleHandle> {
+    emulator.with_memory(|bus| bus.device::<Console>().map(|console| console.handle()))
+}
+
+/// Runs until the program asks for input that hasn't been typed yet, such as
+/// BASIC sitting at its prompt.
+pub fn run_until_input(emulator: &mut CPUEmulator<Bus>) -> StopReason {
+    let Some(console) = console(emulator) else {
+        return emulator.run();
+    };
+    console.set_waiting(false);
+    emulator.run_until(|| console.waiting())
+}
+
+// OS state the handlers share: the OSBYTE variables from &A6 and the text column.
+#[derive(Debug)]
+struct OsState {
+    variables: [u8; 256],
+    column: u8,
+}
+
+// Sends the BRK back to itself, so the call is made again on the next
+// instruction, until the input it needs has been typed.
+fn block<M: VirtualMemory>(emulator: &mut CPUEmulator<M>, console: &ConsoleHandle) {
+    emulator.state.pc = emulator.state.pc.wrapping_sub(2);
+    console.set_waiting(true);
+}
+
+fn install_os(emulator: &mut CPUEmulator<Bus>) {
+    let console = console(emulator).expect("the console is on the bus");
+    let state = Arc::new(Mutex::new(OsState { variables: [0; 256], column: 0 }));
+
+    let (output, shared) = (console.clone(), state.clone());
+    emulator.on_syscall(SYSCALL_WRCH, move |emulator| {
+        let byte = emulator.state.a;
+        output.write(byte);
+        let mut state = shared.lock().unwrap();
+        state.column = match byte {
+            13 => 0,
+            0x20..=0x7E => (state.column + 1) % 40,
+            _ => state.column,
+        };
+        Ok(())
+    });
+
+    let input = console.clone();
+    emulator.on_syscall(SYSCALL_RDCH, move |emulator| {
+        match input.read() {
+            Some(byte) => {
+                emulator.state.a = byte;
+                emulator.state.p.remove(SystemFlags::carry);
+            }
+            None => block(emulator, &input),
+        }
+        Ok(())
+    });
+
+    let (input, shared) = (console.clone(), state);
+    emulator.on_syscall(SYSCALL_BYTE, move |emulator| {
+        let (a, x, y) = (emulator.state.a, emulator.state.x, emulator.state.y);
+        let mut state = shared.lock().unwrap();
+        let (x, y) = match a {
+            // OS 1.20.
+            0x00 => (1, y),
+            0x7E => (0, y),
+            // INKEY with a time limit, which doesn't wait here.
+            0x81 if y < 0x80 => match input.read() {
+                Some(byte) => {
+                    emulator.state.p.remove(SystemFlags::carry);
+                    (byte, 0)
+                }
+                None => {
+                    emulator.state.p.insert(SystemFlags::carry);
+                    (x, 0xFF)
+                }
+            },
+            0x81 => (0, 0),
+            0x82 => (0xFF, 0xFF),
+            0x83 => (OSHWM as u8, (OSHWM >> 8) as u8),
+            0x84 | 0x85 => (HIMEM as u8, (HIMEM >> 8) as u8),
+            0x86 => (state.column, 0),
+            0x87 => (0x20, 7),
+            // Read or write a variable: new = (old AND Y) EOR X.
+            0xA6..=0xFF => {
+                let old = state.variables[a as usize];
+                state.variables[a as usize] = (old & y) ^ x;
+                (old, state.variables[a.wrapping_add(1) as usize])
+            }
+            _ => {
+                log::debug!("OSBYTE {:#04x} isn't emulated", a);
+                (x, y)
+            }
+        };
+        emulator.state.x = x;
+        emulator.state.y = y;
+        Ok(())
+    });
+
+    let input = console;
+    emulator.on_syscall(SYSCALL_WORD, move |emulator| {
+        let block_address = u16::from_le_bytes([emulator.state.x, emulator.state.y]);
+        match emulator.state.a {
+            // Read a line into the buffer the control block points at, echoing it.
+            0x00 => {
+                let control: Vec<u8> = (0..5).map(|offset| emulator.peek(block_address.wrapping_add(offset))).collect();
+                let Some(line) = input.read_line() else {
+                    block(emulator, &input);
+                    return Ok(());
+                };
+                let buffer = u16::from_le_bytes([control[0], control[1]]);
+                let kept: Vec<u8> =
+                    line.into_iter().filter(|byte| (control[3]..=control[4]).contains(byte)).take(control[2] as usize).collect();
+                for (offset, byte) in kept.iter().enumerate() {
+                    emulator.poke(buffer.wrapping_add(offset as u16), *byte);
+                    input.write(*byte);
+                }
+                emulator.poke(buffer.wrapping_add(kept.len() as u16), 13);
+                input.write(10);
+                input.write(13);
+                emulator.state.y = kept.len() as u8;
+                emulator.state.p.remove(SystemFlags::carry);
+            }
+            // The centisecond clock, from cycles at 2MHz.
+            0x01 => {
+                let centiseconds = emulator.clock() / 20_000;
+                for (offset, byte) in centiseconds.to_le_bytes()[..5].iter().enumerate() {
+                    emulator.poke(block_address.wrapping_add(offset as u16), *byte);
+                }
+            }
+            a => log::debug!("OSWORD {:#04x} isn't emulated", a),
+        }
+        Ok(())
+    });
+
+    emulator.on_syscall(SYSCALL_CLI, |emulator| {
+        let address = u16::from_le_bytes([emulator.state.x, emulator.state.y]);
+        let command: String =
+            (0..256u16).map(|offset| emulator.peek(address.wrapping_add(offset))).take_while(|byte| *byte != 13).map(|byte| byte as char).collect();
+        log::debug!("OSCLI {} ignored", command.trim_start_matches(['*', ' ']));
+        Ok(())
+    });
+}
@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::devices::cia::Cia;
+use crate::devices::console::Console;
+use crate::devices::printer::Printer;
+#[cfg(feature = "nes")]
+use crate::devices::joypad::Joypads;
+#[cfg(feature = "tia")]
+use crate::devices::riot::Riot;
+use crate::devices::rtc::Rtc;
+use crate::devices::semihost::Semihost;
+use crate::devices::sid::Sid;
+use crate::devices::tape::{Encoding, Tape};
+use crate::devices::tcp_serial::TcpSerial;
+#[cfg(feature = "tia")]
+use crate::devices::tia::Tia;
+use crate::devices::timer::Timer;
+use crate::devices::via::Via;
+use crate::devices::{Bus, Device};
+use r6502_core::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory};
+use r6502_core::error::{R6502Error, Result};
+use r6502_core::power_on::RamPattern;
+use r6502_core::state::SystemState;
+use r6502_core::sync::SyncBoundary;
+
+// Machines described in TOML rather than Rust, e.g. a breadboard computer:
+//
+//     name = "breadboard"
+//     clock = 1_000_000
+//     ram = { pattern = "alternating", block = 64 }
+//     sync = "frame"
+//
+//     [[region]]
+//     start = 0x8000
+//     end = 0xFFFF
+//     kind = "rom"
+//     file = "monitor.bin"
+//
+//     [[region]]
+//     start = 0x7000
+//     end = 0x7FFF
+//     save = "nvram.bin"
+//
+//     [[device]]
+//     type = "riot"
+//     start = 0x6000
+//     end = 0x60FF
+//
+// Memory that no region or device covers is RAM, holding the `ram` pattern at
+// power on (zero unless given, see `RamPattern`). Device types are looked up in
+// a `DeviceRegistry`, which crates providing their own peripherals can add to.
+// `sync` is how often the frontend takes control back, see `SyncBoundary`.
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MachineConfig {
+    #[serde(default)]
+    pub name: String,
+    /// CPU clock in Hz, for frontends that pace emulation in real time.
+    pub clock: Option<u64>,
+    /// Where execution starts, instead of going through the reset vector.
+    pub reset: Option<u16>,
+    /// What RAM holds at power on, under the regions.
+    #[serde(default)]
+    pub ram: RamPattern,
+    /// Where the frontend takes control back, every instruction unless given.
+    #[serde(default)]
+    pub sync: SyncBoundary,
+    #[serde(default, rename = "region")]
+    pub regions: Vec<RegionConfig>,
+    #[serde(default, rename = "device")]
+    pub devices: Vec<DeviceConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionKind {
+    #[default]
+    Ram,
+    /// Writes are dropped and reported as anomalies.
+    Rom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RegionConfig {
+    pub start: u16,
+    pub end: u16,
+    #[serde(default)]
+    pub kind: RegionKind,
+    /// Image loaded at `start`. Relative paths are relative to the config file.
+    pub file: Option<PathBuf>,
+    /// Byte the region holds before any image is loaded.
+    pub fill: Option<u8>,
+    /// File that keeps a RAM region's contents between runs, like battery backed
+    /// NVRAM. Loaded over `fill` and `file`, and saved when the machine is dropped.
+    pub save: Option<PathBuf>,
+}
+
+/// A device mapped either at `start..=end`, or wherever `address & mask == value`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DeviceConfig {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub start: Option<u16>,
+    pub end: Option<u16>,
+    pub mask: Option<u16>,
+    pub value: Option<u16>,
+    /// Settings for the device itself, from a `[device.options]` table.
+    #[serde(default)]
+    pub options: toml::Table,
+}
+
+/// Creates a device from its config entry.
+pub type DeviceFactory = Arc<dyn Fn(&DeviceConfig) -> Result<Box<dyn Device>> + Send + Sync>;
+
+/// Device types a config can refer to, by case insensitive name.
+#[derive(Clone)]
+pub struct DeviceRegistry {
+    factories: HashMap<String, DeviceFactory>,
+}
+
+impl Default for DeviceRegistry {
+    /// The devices r6502 provides itself.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        #[cfg(feature = "tia")]
+        registry.register("riot", |_| Ok(Box::new(Riot::new())));
+        #[cfg(feature = "tia")]
+        registry.register("tia", |_| Ok(Box::new(Tia::new())));
+        registry.register("timer", |_| Ok(Box::new(Timer::new())));
+        #[cfg(feature = "nes")]
+        registry.register("joypads", |_| Ok(Box::new(Joypads::new())));
+        registry.register("console", |_| Ok(Box::new(Console::new())));
+        registry.register("sid", |_| Ok(Box::new(Sid::new())));
+        // `split_pages = true` starts a page at each form feed, `spool = "dir"`
+        // also writes the printout to files there.
+        registry.register("printer", |config| {
+            let printer = match config.options.get("split_pages").and_then(|split| split.as_bool()) {
+                Some(true) => Printer::new().split_pages(),
+                _ => Printer::new(),
+            };
+            Ok(Box::new(match config.options.get("spool").and_then(|spool| spool.as_str()) {
+                Some(directory) => printer.spool(directory),
+                None => printer,
+            }))
+        });
+        // `nmi = true` sends the VIA's interrupts to NMI instead of IRQ.
+        registry.register("via", |config| {
+            let via = Via::new();
+            Ok(Box::new(match config.options.get("nmi").and_then(|nmi| nmi.as_bool()) {
+                Some(true) => via.wired_to_nmi(),
+                _ => via,
+            }))
+        });
+        // Likewise for the CIA, as on the C64's second one.
+        registry.register("cia", |config| {
+            let cia = Cia::new();
+            Ok(Box::new(match config.options.get("nmi").and_then(|nmi| nmi.as_bool()) {
+                Some(true) => cia.wired_to_nmi(),
+                _ => cia,
+            }))
+        });
+        // `time` pins the clock to a start time for reproducible runs.
+        registry.register("rtc", |config| {
+            let option = |name: &str| config.options.get(name).and_then(|value| value.as_integer());
+            Ok(Box::new(match option("time") {
+                Some(start) => Rtc::fixed(start as u64, option("clock").unwrap_or(1_000_000) as u64),
+                None => Rtc::host(),
+            }))
+        });
+        // `listen = "127.0.0.1:6502"` waits for a terminal, `connect = "host:port"`
+        // dials out. `telnet = true` and `irq = true` as on `TcpSerial`.
+        registry.register("serial", |config| {
+            let option = |name: &str| config.options.get(name).and_then(|value| value.as_str());
+            let flag = |name: &str| config.options.get(name).and_then(|value| value.as_bool()).unwrap_or(false);
+            let serial = match (option("listen"), option("connect")) {
+                (_, Some(address)) => TcpSerial::connect(address, flag("telnet"))?,
+                (address, None) => TcpSerial::listen(address.unwrap_or("127.0.0.1:6502"), flag("telnet"))?,
+            };
+            Ok(Box::new(if flag("irq") { serial.with_interrupts() } else { serial }))
+        });
+        // `file` is the tape image, `encoding` how it is recorded, `apple2`
+        // unless given, and `irq = true` as on `Tape`.
+        registry.register("tape", |config| {
+            let option = |name: &str| config.options.get(name).and_then(|value| value.as_str());
+            let file = option("file").ok_or_else(|| R6502Error::Config("a tape needs a file".to_owned()))?;
+            let tape = Tape::load(file, option("encoding").unwrap_or("apple2").parse::<Encoding>()?)?;
+            Ok(Box::new(match config.options.get("irq").and_then(|irq| irq.as_bool()) {
+                Some(true) => tape.with_interrupts(),
+                _ => tape,
+            }))
+        });
+        registry.register("semihost", |config| {
+            let root = config.options.get("root").and_then(|root| root.as_str()).unwrap_or(".");
+            Ok(Box::new(Semihost::new(root)))
+        });
+        registry
+    }
+}
+
+impl DeviceRegistry {
+    pub fn empty() -> Self {
+        Self { factories: HashMap::new() }
+    }
+
+    /// Adds a device type, replacing any existing one of the same name.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where F: Fn(&DeviceConfig) -> Result<Box<dyn Device>> + Send + Sync + 'static {
+        self.factories.insert(name.to_ascii_lowercase(), Arc::new(factory));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(&name.to_ascii_lowercase())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(|name| name.as_str())
+    }
+
+    pub fn create(&self, config: &DeviceConfig) -> Result<Box<dyn Device>> {
+        match self.factories.get(&config.kind.to_ascii_lowercase()) {
+            Some(factory) => factory(config),
+            None => Err(R6502Error::UnknownDevice(config.kind.clone())),
+        }
+    }
+}
+
+impl MachineConfig {
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        for region in config.regions.iter() {
+            if region.end < region.start {
+                return Err(R6502Error::Config(format!("region {:#06x}-{:#06x} ends before it starts", region.start, region.end)));
+            }
+            if region.kind == RegionKind::Rom && region.save.is_some() {
+                return Err(R6502Error::Config(format!("region {:#06x}-{:#06x} is ROM, so it can't be saved", region.start, region.end)));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Builds the machine with the built-in devices, reset and ready to run.
+    pub fn build(&self) -> Result<CPUEmulator<Bus>> {
+        self.build_with(&DeviceRegistry::default())
+    }
+
+    /// Builds the machine, creating its devices from `registry`.
+    pub fn build_with(&self, registry: &DeviceRegistry) -> Result<CPUEmulator<Bus>> {
+        let mut memory = DefaultVirtualMemory::default().with_pattern(self.ram);
+        for region in self.regions.iter() {
+            let length = (region.end - region.start) as usize + 1;
+            if let Some(fill) = region.fill {
+                memory = memory.with_image(region.start, &vec![fill; length]);
+            }
+            if let Some(file) = region.file.as_ref() {
+                let image = std::fs::read(file)?;
+                if image.len() > length {
+                    return Err(R6502Error::Config(format!(
+                        "{} is {} bytes, region {:#06x}-{:#06x} only holds {}",
+                        file.display(), image.len(), region.start, region.end, length
+                    )));
+                }
+                memory = memory.with_image(region.start, &image);
+            }
+            if region.kind == RegionKind::Rom {
+                memory = memory.with_rom(region.start, region.end);
+            }
+        }
+
+        let mut bus = Bus::new(memory);
+        for region in self.regions.iter() {
+            if let Some(save) = region.save.as_ref() {
+                bus = bus.battery_backed(region.start, region.end, save)?;
+            }
+        }
+        for device in self.devices.iter() {
+            let instance = registry.create(device)?;
+            bus = match (device.start, device.end, device.mask, device.value) {
+                (Some(start), Some(end), None, None) => bus.map_boxed(start, end, instance),
+                (None, None, Some(mask), Some(value)) => bus.map_decoded_boxed(mask, value, instance),
+                _ => {
+                    return Err(R6502Error::Config(format!(
+                        "device {} needs either start and end or mask and value",
+                        device.kind
+                    )))
+                }
+            };
+        }
+
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState::default())
+            .memory(Arc::new(Mutex::new(bus)))
+            .sync(self.sync)
+            .build()?;
+        emulator.reset();
+        if let Some(reset) = self.reset {
+            emulator.state.pc = reset;
+        }
+        Ok(emulator)
+    }
+}
+
+/// Reads a machine config, resolving the files it refers to against its directory.
+pub fn from_config<P: AsRef<Path>>(path: P) -> Result<MachineConfig> {
+    let path = path.as_ref();
+    let mut config = MachineConfig::parse(&std::fs::read_to_string(path)?)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    for region in config.regions.iter_mut() {
+        if let Some(file) = region.file.as_mut() {
+            *file = base.join(&*file);
+        }
+        if let Some(save) = region.save.as_mut() {
+            *save = base.join(&*save);
+        }
+    }
+    Ok(config)
+}
@@ -0,0 +1,309 @@
+use r6502_core::charset::Charset;
+use r6502_core::error::{R6502Error, Result};
+use strum_macros::EnumIter;
+
+use r6502_core::registers::{self, Register};
+use r6502_core::symbols::SymbolTable;
+
+// Machine profiles. A profile selects the hardware the emulated 6502 is wired
+// to, starting with the register names shown in disassembly. Machines that
+// aren't built in can be described in a config file, see `config`, and files
+// of unknown provenance loaded with a guessed profile, see `auto`, or the one a
+// known ROM was made for, see `romdb`.
+
+pub mod apple2;
+pub mod auto;
+pub mod atari8;
+pub mod bbc;
+pub mod config;
+pub mod romdb;
+pub mod sidplay;
+pub mod vic20;
+
+pub use auto::{auto_load, AutoLoader};
+pub use config::from_config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, EnumIter)]
+pub enum Machine {
+    /// Flat 64K of RAM, no devices.
+    #[default]
+    Generic,
+    Atari2600,
+    Nes,
+    C64,
+    Apple2,
+    Vic20,
+    Atari8,
+    Bbc,
+}
+
+impl Machine {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Generic => "generic",
+            Self::Atari2600 => "atari2600",
+            Self::Nes => "nes",
+            Self::C64 => "c64",
+            Self::Apple2 => "apple2",
+            Self::Vic20 => "vic20",
+            Self::Atari8 => "atari8",
+            Self::Bbc => "bbc",
+        }
+    }
+
+    /// Register packs making up this machine's built-in symbols.
+    pub fn register_packs(&self) -> &'static [&'static [(&'static str, u16)]] {
+        match self {
+            Self::Generic => &[],
+            Self::Atari2600 => &[TIA_WRITE, TIA_READ, RIOT],
+            Self::Nes => &[NES_PPU, NES_APU],
+            Self::C64 => &[C64_VIC, C64_SID, C64_CIA1, C64_CIA2],
+            Self::Apple2 => &[APPLE2_IO],
+            Self::Vic20 => &[VIC20_VIC, VIC20_VIA1, VIC20_VIA2],
+            Self::Atari8 => &[ATARI8_GTIA, ATARI8_POKEY, ATARI8_PIA, ATARI8_ANTIC],
+            Self::Bbc => &[BBC_MOS, BBC_SHEILA],
+        }
+    }
+
+    /// How text in memory is shown beside hex dumps.
+    pub fn charset(&self) -> Charset {
+        match self {
+            Self::C64 | Self::Vic20 => Charset::Petscii,
+            Self::Apple2 => Charset::Apple,
+            _ => Charset::Ascii,
+        }
+    }
+
+    /// Bitfield descriptions for the registers of this machine's devices.
+    pub fn registers(&self) -> &'static [Register] {
+        match self {
+            Self::Generic => &[],
+            Self::Atari2600 => registers::TIA,
+            Self::Nes => registers::NES_PPU,
+            Self::C64 => registers::C64,
+            Self::Apple2 | Self::Vic20 | Self::Atari8 | Self::Bbc => &[],
+        }
+    }
+
+    /// Hardware register names for this machine, ready to be merged with program symbols.
+    pub fn symbols(&self) -> SymbolTable {
+        let mut table = SymbolTable::default();
+        for pack in self.register_packs() {
+            for (name, address) in pack.iter() {
+                table.insert(name, *address);
+            }
+        }
+        table
+    }
+}
+
+impl std::fmt::Display for Machine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Machine {
+    type Err = R6502Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "generic" => Ok(Self::Generic),
+            "atari2600" | "2600" | "vcs" => Ok(Self::Atari2600),
+            "nes" | "famicom" => Ok(Self::Nes),
+            "c64" => Ok(Self::C64),
+            "apple2" | "appleii" | "a2" => Ok(Self::Apple2),
+            "vic20" | "vic" => Ok(Self::Vic20),
+            "atari8" | "atari800" | "atari400" | "a8" => Ok(Self::Atari8),
+            "bbc" | "beeb" | "bbcmicro" => Ok(Self::Bbc),
+            _ => Err(R6502Error::UnknownMachine(name.to_owned())),
+        }
+    }
+}
+
+/// TIA write registers.
+pub const TIA_WRITE: &[(&str, u16)] = &[
+    ("VSYNC", 0x00), ("VBLANK", 0x01), ("WSYNC", 0x02), ("RSYNC", 0x03),
+    ("NUSIZ0", 0x04), ("NUSIZ1", 0x05), ("COLUP0", 0x06), ("COLUP1", 0x07),
+    ("COLUPF", 0x08), ("COLUBK", 0x09), ("CTRLPF", 0x0A), ("REFP0", 0x0B),
+    ("REFP1", 0x0C), ("PF0", 0x0D), ("PF1", 0x0E), ("PF2", 0x0F),
+    ("RESP0", 0x10), ("RESP1", 0x11), ("RESM0", 0x12), ("RESM1", 0x13),
+    ("RESBL", 0x14), ("AUDC0", 0x15), ("AUDC1", 0x16), ("AUDF0", 0x17),
+    ("AUDF1", 0x18), ("AUDV0", 0x19), ("AUDV1", 0x1A), ("GRP0", 0x1B),
+    ("GRP1", 0x1C), ("ENAM0", 0x1D), ("ENAM1", 0x1E), ("ENABL", 0x1F),
+    ("HMP0", 0x20), ("HMP1", 0x21), ("HMM0", 0x22), ("HMM1", 0x23),
+    ("HMBL", 0x24), ("VDELP0", 0x25), ("VDELP1", 0x26), ("VDELBL", 0x27),
+    ("RESMP0", 0x28), ("RESMP1", 0x29), ("HMOVE", 0x2A), ("HMCLR", 0x2B),
+    ("CXCLR", 0x2C),
+];
+
+/// TIA read registers, at the $30 mirror so they don't collide with the write names.
+pub const TIA_READ: &[(&str, u16)] = &[
+    ("CXM0P", 0x30), ("CXM1P", 0x31), ("CXP0FB", 0x32), ("CXP1FB", 0x33),
+    ("CXM0FB", 0x34), ("CXM1FB", 0x35), ("CXBLPF", 0x36), ("CXPPMM", 0x37),
+    ("INPT0", 0x38), ("INPT1", 0x39), ("INPT2", 0x3A), ("INPT3", 0x3B),
+    ("INPT4", 0x3C), ("INPT5", 0x3D),
+];
+
+/// 6532 RIOT ports and timer.
+pub const RIOT: &[(&str, u16)] = &[
+    ("SWCHA", 0x280), ("SWACNT", 0x281), ("SWCHB", 0x282), ("SWBCNT", 0x283),
+    ("INTIM", 0x284), ("TIMINT", 0x285), ("TIM1T", 0x294), ("TIM8T", 0x295),
+    ("TIM64T", 0x296), ("T1024T", 0x297),
+];
+
+pub const NES_PPU: &[(&str, u16)] = &[
+    ("PPUCTRL", 0x2000), ("PPUMASK", 0x2001), ("PPUSTATUS", 0x2002), ("OAMADDR", 0x2003),
+    ("OAMDATA", 0x2004), ("PPUSCROLL", 0x2005), ("PPUADDR", 0x2006), ("PPUDATA", 0x2007),
+];
+
+/// APU channels plus the OAM DMA and controller ports that share its address range.
+pub const NES_APU: &[(&str, u16)] = &[
+    ("SQ1_VOL", 0x4000), ("SQ1_SWEEP", 0x4001), ("SQ1_LO", 0x4002), ("SQ1_HI", 0x4003),
+    ("SQ2_VOL", 0x4004), ("SQ2_SWEEP", 0x4005), ("SQ2_LO", 0x4006), ("SQ2_HI", 0x4007),
+    ("TRI_LINEAR", 0x4008), ("TRI_LO", 0x400A), ("TRI_HI", 0x400B), ("NOISE_VOL", 0x400C),
+    ("NOISE_LO", 0x400E), ("NOISE_HI", 0x400F), ("DMC_FREQ", 0x4010), ("DMC_RAW", 0x4011),
+    ("DMC_START", 0x4012), ("DMC_LEN", 0x4013), ("OAMDMA", 0x4014), ("SND_CHN", 0x4015),
+    ("JOY1", 0x4016), ("JOY2", 0x4017),
+];
+
+// C64 names follow "Mapping the Commodore 64".
+
+pub const C64_VIC: &[(&str, u16)] = &[
+    ("SP0X", 0xD000), ("SP0Y", 0xD001), ("SP1X", 0xD002), ("SP1Y", 0xD003),
+    ("SP2X", 0xD004), ("SP2Y", 0xD005), ("SP3X", 0xD006), ("SP3Y", 0xD007),
+    ("SP4X", 0xD008), ("SP4Y", 0xD009), ("SP5X", 0xD00A), ("SP5Y", 0xD00B),
+    ("SP6X", 0xD00C), ("SP6Y", 0xD00D), ("SP7X", 0xD00E), ("SP7Y", 0xD00F),
+    ("MSIGX", 0xD010), ("SCROLY", 0xD011), ("RASTER", 0xD012), ("LPENX", 0xD013),
+    ("LPENY", 0xD014), ("SPENA", 0xD015), ("SCROLX", 0xD016), ("YXPAND", 0xD017),
+    ("VMCSB", 0xD018), ("VICIRQ", 0xD019), ("IRQMSK", 0xD01A), ("SPBGPR", 0xD01B),
+    ("SPMC", 0xD01C), ("XXPAND", 0xD01D), ("SPSPCL", 0xD01E), ("SPBGCL", 0xD01F),
+    ("EXTCOL", 0xD020), ("BGCOL0", 0xD021), ("BGCOL1", 0xD022), ("BGCOL2", 0xD023),
+    ("BGCOL3", 0xD024), ("SPMC0", 0xD025), ("SPMC1", 0xD026), ("SP0COL", 0xD027),
+    ("SP1COL", 0xD028), ("SP2COL", 0xD029), ("SP3COL", 0xD02A), ("SP4COL", 0xD02B),
+    ("SP5COL", 0xD02C), ("SP6COL", 0xD02D), ("SP7COL", 0xD02E),
+];
+
+pub const C64_SID: &[(&str, u16)] = &[
+    ("FRELO1", 0xD400), ("FREHI1", 0xD401), ("PWLO1", 0xD402), ("PWHI1", 0xD403),
+    ("VCREG1", 0xD404), ("ATDCY1", 0xD405), ("SUREL1", 0xD406),
+    ("FRELO2", 0xD407), ("FREHI2", 0xD408), ("PWLO2", 0xD409), ("PWHI2", 0xD40A),
+    ("VCREG2", 0xD40B), ("ATDCY2", 0xD40C), ("SUREL2", 0xD40D),
+    ("FRELO3", 0xD40E), ("FREHI3", 0xD40F), ("PWLO3", 0xD410), ("PWHI3", 0xD411),
+    ("VCREG3", 0xD412), ("ATDCY3", 0xD413), ("SUREL3", 0xD414),
+    ("CUTLO", 0xD415), ("CUTHI", 0xD416), ("RESON", 0xD417), ("SIGVOL", 0xD418),
+    ("POTX", 0xD419), ("POTY", 0xD41A), ("RANDOM", 0xD41B), ("ENV3", 0xD41C),
+];
+
+pub const C64_CIA1: &[(&str, u16)] = &[
+    ("CIAPRA", 0xDC00), ("CIAPRB", 0xDC01), ("CIDDRA", 0xDC02), ("CIDDRB", 0xDC03),
+    ("TIMALO", 0xDC04), ("TIMAHI", 0xDC05), ("TIMBLO", 0xDC06), ("TIMBHI", 0xDC07),
+    ("TODTEN", 0xDC08), ("TODSEC", 0xDC09), ("TODMIN", 0xDC0A), ("TODHRS", 0xDC0B),
+    ("CIASDR", 0xDC0C), ("CIAICR", 0xDC0D), ("CIACRA", 0xDC0E), ("CIACRB", 0xDC0F),
+];
+
+pub const C64_CIA2: &[(&str, u16)] = &[
+    ("CI2PRA", 0xDD00), ("CI2PRB", 0xDD01), ("C2DDRA", 0xDD02), ("C2DDRB", 0xDD03),
+    ("TI2ALO", 0xDD04), ("TI2AHI", 0xDD05), ("TI2BLO", 0xDD06), ("TI2BHI", 0xDD07),
+    ("TO2TEN", 0xDD08), ("TO2SEC", 0xDD09), ("TO2MIN", 0xDD0A), ("TO2HRS", 0xDD0B),
+    ("CI2SDR", 0xDD0C), ("CI2ICR", 0xDD0D), ("CI2CRA", 0xDD0E), ("CI2CRB", 0xDD0F),
+];
+
+/// Apple II soft switches and I/O locations, named as in the Apple II Reference Manual.
+pub const APPLE2_IO: &[(&str, u16)] = &[
+    ("KBD", 0xC000), ("KBDSTRB", 0xC010), ("TAPEOUT", 0xC020), ("SPKR", 0xC030),
+    ("STROBE", 0xC040), ("TXTCLR", 0xC050), ("TXTSET", 0xC051), ("MIXCLR", 0xC052),
+    ("MIXSET", 0xC053), ("LOWSCR", 0xC054), ("HISCR", 0xC055), ("LORES", 0xC056),
+    ("HIRES", 0xC057), ("TAPEIN", 0xC060), ("PB0", 0xC061), ("PB1", 0xC062),
+    ("PB2", 0xC063), ("PADDL0", 0xC064), ("PADDL1", 0xC065), ("PTRIG", 0xC070),
+];
+
+// VIC-20 names follow "Mapping the VIC".
+
+pub const VIC20_VIC: &[(&str, u16)] = &[
+    ("VICCR0", 0x9000), ("VICCR1", 0x9001), ("VICCR2", 0x9002), ("VICCR3", 0x9003),
+    ("VICCR4", 0x9004), ("VICCR5", 0x9005), ("VICCR6", 0x9006), ("VICCR7", 0x9007),
+    ("VICCR8", 0x9008), ("VICCR9", 0x9009), ("VICCRA", 0x900A), ("VICCRB", 0x900B),
+    ("VICCRC", 0x900C), ("VICCRD", 0x900D), ("VICCRE", 0x900E), ("VICCRF", 0x900F),
+];
+
+pub const VIC20_VIA1: &[(&str, u16)] = &[
+    ("VIA1PB", 0x9110), ("VIA1PA1", 0x9111), ("VIA1DDRB", 0x9112), ("VIA1DDRA", 0x9113),
+    ("VIA1T1CL", 0x9114), ("VIA1T1CH", 0x9115), ("VIA1T1LL", 0x9116), ("VIA1T1LH", 0x9117),
+    ("VIA1T2CL", 0x9118), ("VIA1T2CH", 0x9119), ("VIA1SR", 0x911A), ("VIA1ACR", 0x911B),
+    ("VIA1PCR", 0x911C), ("VIA1IFR", 0x911D), ("VIA1IER", 0x911E), ("VIA1PA2", 0x911F),
+];
+
+pub const VIC20_VIA2: &[(&str, u16)] = &[
+    ("VIA2PB", 0x9120), ("VIA2PA1", 0x9121), ("VIA2DDRB", 0x9122), ("VIA2DDRA", 0x9123),
+    ("VIA2T1CL", 0x9124), ("VIA2T1CH", 0x9125), ("VIA2T1LL", 0x9126), ("VIA2T1LH", 0x9127),
+    ("VIA2T2CL", 0x9128), ("VIA2T2CH", 0x9129), ("VIA2SR", 0x912A), ("VIA2ACR", 0x912B),
+    ("VIA2PCR", 0x912C), ("VIA2IFR", 0x912D), ("VIA2IER", 0x912E), ("VIA2PA2", 0x912F),
+];
+
+// Atari 400/800 names follow "Mapping the Atari". Where a register reads
+// differently from how it's written, the write name comes first.
+
+pub const ATARI8_GTIA: &[(&str, u16)] = &[
+    ("HPOSP0", 0xD000), ("HPOSP1", 0xD001), ("HPOSP2", 0xD002), ("HPOSP3", 0xD003),
+    ("HPOSM0", 0xD004), ("HPOSM1", 0xD005), ("HPOSM2", 0xD006), ("HPOSM3", 0xD007),
+    ("SIZEP0", 0xD008), ("SIZEP1", 0xD009), ("SIZEP2", 0xD00A), ("SIZEP3", 0xD00B),
+    ("SIZEM", 0xD00C), ("GRAFP0", 0xD00D), ("GRAFP1", 0xD00E), ("GRAFP2", 0xD00F),
+    ("GRAFP3", 0xD010), ("GRAFM", 0xD011), ("COLPM0", 0xD012), ("COLPM1", 0xD013),
+    ("COLPM2", 0xD014), ("COLPM3", 0xD015), ("COLPF0", 0xD016), ("COLPF1", 0xD017),
+    ("COLPF2", 0xD018), ("COLPF3", 0xD019), ("COLBK", 0xD01A), ("PRIOR", 0xD01B),
+    ("VDELAY", 0xD01C), ("GRACTL", 0xD01D), ("HITCLR", 0xD01E), ("CONSOL", 0xD01F),
+    ("M0PF", 0xD000), ("M1PF", 0xD001), ("M2PF", 0xD002), ("M3PF", 0xD003),
+    ("P0PF", 0xD004), ("P1PF", 0xD005), ("P2PF", 0xD006), ("P3PF", 0xD007),
+    ("M0PL", 0xD008), ("M1PL", 0xD009), ("M2PL", 0xD00A), ("M3PL", 0xD00B),
+    ("P0PL", 0xD00C), ("P1PL", 0xD00D), ("P2PL", 0xD00E), ("P3PL", 0xD00F),
+    ("TRIG0", 0xD010), ("TRIG1", 0xD011), ("TRIG2", 0xD012), ("TRIG3", 0xD013),
+    ("PAL", 0xD014),
+];
+
+pub const ATARI8_POKEY: &[(&str, u16)] = &[
+    ("AUDF1", 0xD200), ("AUDC1", 0xD201), ("AUDF2", 0xD202), ("AUDC2", 0xD203),
+    ("AUDF3", 0xD204), ("AUDC3", 0xD205), ("AUDF4", 0xD206), ("AUDC4", 0xD207),
+    ("AUDCTL", 0xD208), ("STIMER", 0xD209), ("SKRES", 0xD20A), ("POTGO", 0xD20B),
+    ("SEROUT", 0xD20D), ("IRQEN", 0xD20E), ("SKCTL", 0xD20F),
+    ("POT0", 0xD200), ("POT1", 0xD201), ("POT2", 0xD202), ("POT3", 0xD203),
+    ("POT4", 0xD204), ("POT5", 0xD205), ("POT6", 0xD206), ("POT7", 0xD207),
+    ("ALLPOT", 0xD208), ("KBCODE", 0xD209), ("RANDOM", 0xD20A), ("SERIN", 0xD20D),
+    ("IRQST", 0xD20E), ("SKSTAT", 0xD20F),
+];
+
+pub const ATARI8_PIA: &[(&str, u16)] = &[
+    ("PORTA", 0xD300), ("PORTB", 0xD301), ("PACTL", 0xD302), ("PBCTL", 0xD303),
+];
+
+pub const ATARI8_ANTIC: &[(&str, u16)] = &[
+    ("DMACTL", 0xD400), ("CHACTL", 0xD401), ("DLISTL", 0xD402), ("DLISTH", 0xD403),
+    ("HSCROL", 0xD404), ("VSCROL", 0xD405), ("PMBASE", 0xD407), ("CHBASE", 0xD409),
+    ("WSYNC", 0xD40A), ("VCOUNT", 0xD40B), ("PENH", 0xD40C), ("PENV", 0xD40D),
+    ("NMIEN", 0xD40E), ("NMIRES", 0xD40F), ("NMIST", 0xD40F),
+];
+
+/// BBC Micro MOS entry points and page 2 vectors, as in the Advanced User Guide.
+pub const BBC_MOS: &[(&str, u16)] = &[
+    ("OSFIND", 0xFFCE), ("OSGBPB", 0xFFD1), ("OSBPUT", 0xFFD4), ("OSBGET", 0xFFD7),
+    ("OSARGS", 0xFFDA), ("OSFILE", 0xFFDD), ("OSRDCH", 0xFFE0), ("OSASCI", 0xFFE3),
+    ("OSNEWL", 0xFFE7), ("OSWRCH", 0xFFEE), ("OSWORD", 0xFFF1), ("OSBYTE", 0xFFF4),
+    ("OSCLI", 0xFFF7),
+    ("USERV", 0x0200), ("BRKV", 0x0202), ("IRQ1V", 0x0204), ("IRQ2V", 0x0206),
+    ("CLIV", 0x0208), ("BYTEV", 0x020A), ("WORDV", 0x020C), ("WRCHV", 0x020E),
+    ("RDCHV", 0x0210), ("FILEV", 0x0212), ("ARGSV", 0x0214), ("BGETV", 0x0216),
+    ("BPUTV", 0x0218), ("GBPBV", 0x021A), ("FINDV", 0x021C), ("FSCV", 0x021E),
+];
+
+/// SHEILA registers: the ROM select latch and the two VIAs.
+pub const BBC_SHEILA: &[(&str, u16)] = &[
+    ("ROMSEL", 0xFE30),
+    ("SYSVIA_ORB", 0xFE40), ("SYSVIA_ORA", 0xFE41), ("SYSVIA_DDRB", 0xFE42), ("SYSVIA_DDRA", 0xFE43),
+    ("SYSVIA_T1CL", 0xFE44), ("SYSVIA_T1CH", 0xFE45), ("SYSVIA_T1LL", 0xFE46), ("SYSVIA_T1LH", 0xFE47),
+    ("SYSVIA_T2CL", 0xFE48), ("SYSVIA_T2CH", 0xFE49), ("SYSVIA_SR", 0xFE4A), ("SYSVIA_ACR", 0xFE4B),
+    ("SYSVIA_PCR", 0xFE4C), ("SYSVIA_IFR", 0xFE4D), ("SYSVIA_IER", 0xFE4E), ("SYSVIA_ORAN", 0xFE4F),
+    ("USRVIA_ORB", 0xFE60), ("USRVIA_ORA", 0xFE61), ("USRVIA_DDRB", 0xFE62), ("USRVIA_DDRA", 0xFE63),
+    ("USRVIA_T1CL", 0xFE64), ("USRVIA_T1CH", 0xFE65), ("USRVIA_T1LL", 0xFE66), ("USRVIA_T1LH", 0xFE67),
+    ("USRVIA_T2CL", 0xFE68), ("USRVIA_T2CH", 0xFE69), ("USRVIA_SR", 0xFE6A), ("USRVIA_ACR", 0xFE6B),
+    ("USRVIA_PCR", 0xFE6C), ("USRVIA_IFR", 0xFE6D), ("USRVIA_IER", 0xFE6E), ("USRVIA_ORAN", 0xFE6F),
+];
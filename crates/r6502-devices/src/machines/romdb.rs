@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::machines::Machine;
+use r6502_core::error::Result;
+use r6502_core::hashing::crc32;
+
+// ROMs recognised by their CRC-32, so a loaded file can be named and run on
+// the machine it was made for without being told. A few system ROMs are built
+// in; more come from TOML files such as
+//
+//     [[rom]]
+//     crc32 = 0xDBE3E7C7
+//     name = "C64 KERNAL 901227-03"
+//     machine = "c64"
+//     origin = 0xE000
+//
+// `origin` is where the ROM sits in the address space, if it is visible to the
+// processor at all. A ROM with an origin is loaded there as a raw image.
+
+/// A ROM the database knows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KnownRom {
+    pub crc32: u32,
+    pub name: String,
+    pub machine: Machine,
+    pub origin: Option<u16>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RomDatabase {
+    roms: HashMap<u32, KnownRom>,
+}
+
+// (crc32, name, machine, origin)
+const BUILTIN: &[(u32, &str, Machine, Option<u16>)] = &[
+    (0xF833_D117, "C64 BASIC V2 901226-01", Machine::C64, Some(0xA000)),
+    (0xDBE3_E7C7, "C64 KERNAL 901227-03", Machine::C64, Some(0xE000)),
+    (0xEC42_72EE, "C64 character ROM 901225-01", Machine::C64, None),
+    (0xDB4C_43C1, "VIC-20 BASIC V2 901486-01", Machine::Vic20, Some(0xC000)),
+    (0xE5E7_C174, "VIC-20 KERNAL 901486-06 (NTSC)", Machine::Vic20, Some(0xE000)),
+    (0x4BE0_7CB4, "VIC-20 KERNAL 901486-07 (PAL)", Machine::Vic20, Some(0xE000)),
+    (0x83E0_32A6, "VIC-20 character ROM 901460-03", Machine::Vic20, Some(0x8000)),
+];
+
+#[derive(Deserialize)]
+struct DatabaseFile {
+    #[serde(default)]
+    rom: Vec<RomEntry>,
+}
+
+#[derive(Deserialize)]
+struct RomEntry {
+    crc32: u32,
+    name: String,
+    #[serde(default)]
+    machine: Option<String>,
+    origin: Option<u16>,
+}
+
+impl RomDatabase {
+    /// The system ROMs of the built-in machine profiles.
+    pub fn builtin() -> Self {
+        BUILTIN.iter().fold(Self::default(), |database, (crc32, name, machine, origin)| {
+            database.with(KnownRom { crc32: *crc32, name: (*name).to_owned(), machine: *machine, origin: *origin })
+        })
+    }
+
+    /// Reads a database in the format shown at the top of this file. A ROM
+    /// without a machine is for the generic one.
+    pub fn parse(text: &str) -> Result<Self> {
+        let file: DatabaseFile = toml::from_str(text)?;
+        file.rom.into_iter().try_fold(Self::default(), |database, entry| {
+            let machine = entry.machine.as_deref().map(str::parse).transpose()?.unwrap_or_default();
+            Ok(database.with(KnownRom { crc32: entry.crc32, name: entry.name, machine, origin: entry.origin }))
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Adds `rom`, replacing any entry with the same CRC.
+    pub fn with(mut self, rom: KnownRom) -> Self {
+        self.roms.insert(rom.crc32, rom);
+        self
+    }
+
+    /// Adds the entries of `other`, which win over these.
+    pub fn merged(mut self, other: RomDatabase) -> Self {
+        self.roms.extend(other.roms);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.roms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roms.is_empty()
+    }
+
+    pub fn get(&self, crc32: u32) -> Option<&KnownRom> {
+        self.roms.get(&crc32)
+    }
+
+    /// The ROM `data` is, if the database knows it.
+    pub fn identify(&self, data: &[u8]) -> Option<&KnownRom> {
+        let crc = crc32(data);
+        let rom = self.get(crc);
+        match rom {
+            Some(rom) => log::info!("identified {} byte image as {}", data.len(), rom.name),
+            None => log::debug!("no ROM with CRC {:08x} in the database", crc),
+        }
+        rom
+    }
+}
@@ -0,0 +1,475 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use r6502_core::assembler::assemble;
+use crate::devices::cia::Cia;
+use crate::devices::sid::{Sid, SidWrite};
+use crate::devices::{Bus, Device};
+use r6502_core::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, StopReason, VirtualMemory, ADDRESS_SPACE};
+use r6502_core::error::{R6502Error, Result};
+use r6502_core::state::{SystemFlags, SystemState};
+
+// Player for C64 music in the PSID and RSID formats of the High Voltage SID
+// Collection. The tune's driver is installed at its load address in a C64
+// cut down to what players touch: 64K of RAM, the processor port at $00/$01
+// switching the I/O area at $D000 in and out, and behind it a SID, both CIAs
+// and the VIC's raster interrupt. There are no BASIC, KERNAL or character
+// ROMs; where the KERNAL would be there is RAM with just its interrupt entry
+// and exit code ($FF48, $EA31, $EA81) and vectors.
+//
+// PSID tunes are called the way sidplay does: init once with the song number
+// in A, then play once a frame, or once per period of CIA 1 timer A for songs
+// whose speed bit is set. Tunes without a play address, and all RSID tunes,
+// install their own interrupt handler, so they run freely with interrupts on
+// and the CIA or raster interrupts they set up pace them. Either way every SID
+// write is captured with its cycle for `dump` or `to_csv`.
+
+pub const IO: u16 = 0xD000;
+pub const SID: u16 = 0xD400;
+pub const COLOUR_RAM: u16 = 0xD800;
+pub const CIA1: u16 = 0xDC00;
+pub const CIA2: u16 = 0xDD00;
+
+/// VIC registers behind the raster interrupt.
+pub const VIC_CONTROL: u16 = 0x11;
+pub const VIC_RASTER: u16 = 0x12;
+pub const VIC_IRQ: u16 = 0x19;
+pub const VIC_IRQ_ENABLE: u16 = 0x1A;
+
+/// Where init and play return to; the player stops there.
+pub const RETURN: u16 = 0xFF40;
+/// A `JMP` to itself that the CPU waits in between calls.
+pub const IDLE: u16 = 0xFF43;
+
+/// CIA 1 timer A latch for the KERNAL's 60Hz interrupt, on PAL and NTSC.
+pub const PAL_TIMER: u16 = 0x4025;
+pub const NTSC_TIMER: u16 = 0x4295;
+
+/// Cycles init gets to return before the tune is given up on.
+pub const INIT_CYCLES: u64 = 2_000_000;
+
+const KERNAL: &str = "
+        .org $ea31
+        lda $dc0d
+        jmp $ea81
+        .org $ea81
+        pla
+        tay
+        pla
+        tax
+        pla
+        rti
+        .org $fe43
+        jmp ($0318)
+        rti
+        .org $ff40
+        jmp $ff43
+        jmp $ff43
+        .org $ff48
+        pha
+        txa
+        pha
+        tya
+        pha
+        tsx
+        lda $0104,x
+        and #$10
+        beq irq
+        jmp ($0316)
+irq:    jmp ($0314)
+        .org $fffa
+        .word $fe43, $ff43, $ff48
+";
+
+/// IRQ, BRK and NMI vectors in page 3 as the KERNAL leaves them, minus the
+/// keyboard scan and BASIC warm start.
+const RAM_VECTORS: [u8; 6] = [0x31, 0xEA, 0x81, 0xEA, 0x46, 0xFE];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidFormat {
+    /// Tunes that are called for each frame and only need the SID.
+    Psid,
+    /// Tunes that need a real C64 environment and drive themselves.
+    Rsid,
+}
+
+/// A parsed .sid file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SidTune {
+    pub format: SidFormat,
+    pub version: u16,
+    pub load: u16,
+    pub init: u16,
+    /// Zero for tunes that install their own interrupt handler.
+    pub play: u16,
+    pub songs: u16,
+    /// First song to play, from 1.
+    pub start_song: u16,
+    /// Bit n set if song n + 1 is paced by CIA 1 timer A instead of the frame rate.
+    pub speed: u32,
+    pub name: String,
+    pub author: String,
+    pub released: String,
+    /// Version 2 flags, zero before.
+    pub flags: u16,
+    /// The C64 data, without its load address.
+    pub data: Vec<u8>,
+}
+
+impl SidTune {
+    /// Parses the big endian header: magic, version, data offset, load, init and
+    /// play addresses, song count, start song, speed bits, three 32 byte strings
+    /// and from version 2 the flags.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let format = match data.get(..4) {
+            Some(b"PSID") => SidFormat::Psid,
+            Some(b"RSID") => SidFormat::Rsid,
+            _ => return Err(R6502Error::NotSidFile),
+        };
+        if data.len() < 0x76 {
+            return Err(R6502Error::TruncatedImage);
+        }
+        let word = |offset: usize| u16::from_be_bytes([data[offset], data[offset + 1]]);
+        let text = |offset: usize| data[offset..offset + 32].iter().take_while(|byte| **byte != 0).map(|byte| *byte as char).collect();
+        let version = word(0x04);
+        let offset = word(0x06) as usize;
+        let flags = if version >= 2 && data.len() >= 0x78 { word(0x76) } else { 0 };
+        let mut body = data.get(offset..).ok_or(R6502Error::TruncatedImage)?;
+        let load = match word(0x08) {
+            0 => {
+                let [low, high, rest @ ..] = body else {
+                    return Err(R6502Error::MissingLoadAddress);
+                };
+                body = rest;
+                u16::from_le_bytes([*low, *high])
+            }
+            load => load,
+        };
+        if load as usize + body.len() > ADDRESS_SPACE {
+            return Err(R6502Error::ImageTooLarge { length: body.len(), origin: load });
+        }
+        Ok(Self {
+            format,
+            version,
+            load,
+            init: match word(0x0A) {
+                0 => load,
+                init => init,
+            },
+            play: word(0x0C),
+            songs: word(0x0E).max(1),
+            start_song: word(0x10).max(1),
+            speed: u32::from_be_bytes([data[0x12], data[0x13], data[0x14], data[0x15]]),
+            name: text(0x16),
+            author: text(0x36),
+            released: text(0x56),
+            flags,
+            data: body.to_vec(),
+        })
+    }
+
+    /// Whether `song`, from 1, is paced by CIA 1 timer A.
+    pub fn uses_cia(&self, song: u16) -> bool {
+        self.speed >> (song.saturating_sub(1)).min(31) & 1 != 0
+    }
+
+    /// Whether the flags say the tune is for NTSC machines only.
+    pub fn ntsc(&self) -> bool {
+        self.flags >> 2 & 0x03 == 0x02
+    }
+
+    /// Whether the tune runs from its own interrupt handler rather than being called.
+    pub fn interrupt_driven(&self) -> bool {
+        self.format == SidFormat::Rsid || self.play == 0
+    }
+}
+
+pub fn sid_file<P: AsRef<Path>>(path: P) -> Result<SidTune> {
+    SidTune::parse(&std::fs::read(path)?)
+}
+
+/// The C64's processor port and I/O area. Port bits 0-2 bank the I/O chips in
+/// at $D000-$DFFF when bit 2 and either of the others are set, and RAM
+/// otherwise. The VIC is reduced to its raster counter and interrupt, the rest
+/// of its registers only hold what is written.
+#[derive(Debug, Clone)]
+pub struct C64Io {
+    directions: u8,
+    port: u8,
+    /// RAM under the I/O area.
+    ram: Vec<u8>,
+    vic: [u8; 0x40],
+    raster_flags: u8,
+    line: u16,
+    line_cycle: u64,
+    lines: u16,
+    cycles_per_line: u64,
+    colour_ram: Vec<u8>,
+    sid: Sid,
+    cia1: Cia,
+    cia2: Cia,
+}
+
+impl C64Io {
+    pub fn new(ntsc: bool) -> Self {
+        let (lines, cycles_per_line) = if ntsc { (263, 65) } else { (312, 63) };
+        Self {
+            directions: 0x2F,
+            port: 0x37,
+            ram: vec![0; 0x1000],
+            vic: [0; 0x40],
+            raster_flags: 0,
+            line: 0,
+            line_cycle: 0,
+            lines,
+            cycles_per_line,
+            colour_ram: vec![0; 0x400],
+            sid: Sid::new(),
+            cia1: Cia::new(),
+            cia2: Cia::new().wired_to_nmi(),
+        }
+    }
+
+    /// Cycles in a video frame.
+    pub fn frame_cycles(&self) -> u64 {
+        self.lines as u64 * self.cycles_per_line
+    }
+
+    /// Whether the chips, rather than RAM, are seen at $D000-$DFFF.
+    pub fn io_visible(&self) -> bool {
+        let banks = (self.port | !self.directions) & 0x07;
+        banks & 0x04 != 0 && banks & 0x03 != 0
+    }
+
+    pub fn raster_line(&self) -> u16 {
+        self.line
+    }
+
+    pub fn sid(&self) -> &Sid {
+        &self.sid
+    }
+
+    pub fn sid_mut(&mut self) -> &mut Sid {
+        &mut self.sid
+    }
+
+    pub fn cia1(&self) -> &Cia {
+        &self.cia1
+    }
+
+    pub fn cia2(&self) -> &Cia {
+        &self.cia2
+    }
+
+    fn compare_line(&self) -> u16 {
+        (self.vic[VIC_CONTROL as usize] as u16 & 0x80) << 1 | self.vic[VIC_RASTER as usize] as u16
+    }
+
+    fn raster_interrupting(&self) -> bool {
+        self.raster_flags & self.vic[VIC_IRQ_ENABLE as usize] & 0x0F != 0
+    }
+
+    fn read_vic(&self, register: u16) -> u8 {
+        match register {
+            VIC_CONTROL => (self.vic[VIC_CONTROL as usize] & 0x7F) | ((self.line >> 1) as u8 & 0x80),
+            VIC_RASTER => self.line as u8,
+            VIC_IRQ => self.raster_flags | 0x70 | if self.raster_interrupting() { 0x80 } else { 0 },
+            VIC_IRQ_ENABLE => self.vic[VIC_IRQ_ENABLE as usize] | 0xF0,
+            register => self.vic.get(register as usize).copied().unwrap_or(0xFF),
+        }
+    }
+}
+
+impl VirtualMemory for C64Io {
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000 => self.directions,
+            0x0001 => (self.port & self.directions) | (0x17 & !self.directions),
+            _ if !self.io_visible() => self.ram[(address & 0x0FFF) as usize],
+            0xD000..=0xD3FF => self.read_vic(address & 0x3F),
+            0xD400..=0xD7FF => self.sid.read(address),
+            0xD800..=0xDBFF => self.colour_ram[(address & 0x03FF) as usize] | 0xF0,
+            0xDC00..=0xDCFF => self.cia1.read(address),
+            0xDD00..=0xDDFF => self.cia2.read(address),
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000 => self.directions = value,
+            0x0001 => self.port = value,
+            _ if !self.io_visible() => self.ram[(address & 0x0FFF) as usize] = value,
+            0xD000..=0xD3FF => match address & 0x3F {
+                // Writing ones acknowledges those interrupts.
+                VIC_IRQ => self.raster_flags &= !(value & 0x0F),
+                register => self.vic[register as usize] = value,
+            },
+            0xD400..=0xD7FF => self.sid.write(address, value),
+            0xD800..=0xDBFF => self.colour_ram[(address & 0x03FF) as usize] = value & 0x0F,
+            0xDC00..=0xDCFF => self.cia1.write(address, value),
+            0xDD00..=0xDDFF => self.cia2.write(address, value),
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self, cycles: u64) {
+        self.sid.tick(cycles);
+        self.cia1.tick(cycles);
+        self.cia2.tick(cycles);
+        self.line_cycle += cycles;
+        while self.line_cycle >= self.cycles_per_line {
+            self.line_cycle -= self.cycles_per_line;
+            self.line = (self.line + 1) % self.lines;
+            if self.line == self.compare_line() {
+                self.raster_flags |= 0x01;
+            }
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.raster_interrupting() || self.cia1.irq()
+    }
+
+    fn nmi(&mut self) -> bool {
+        self.cia2.nmi()
+    }
+}
+
+impl Device for C64Io {
+    fn name(&self) -> &'static str {
+        "C64 I/O"
+    }
+}
+
+/// A tune installed and initialised, ready to play.
+pub struct SidPlayer {
+    emulator: CPUEmulator<Bus>,
+    tune: SidTune,
+    song: u16,
+    /// Cycles between play calls, or between frames for interrupt driven tunes.
+    period: u64,
+    /// Clock at which the current frame ends.
+    frame_end: u64,
+}
+
+impl SidPlayer {
+    /// Installs `tune`, sets up the CIA 1 timer interrupt the way the KERNAL does
+    /// and calls init for `song`, from 1, or the tune's start song.
+    pub fn new(tune: SidTune, song: Option<u16>) -> Result<Self> {
+        let song = song.unwrap_or(tune.start_song).clamp(1, tune.songs);
+        let ntsc = tune.ntsc();
+        let kernal = assemble(KERNAL, 0xEA31)?;
+        let memory = DefaultVirtualMemory::default().with_image(0xEA31, &kernal.image).with_image(0x0314, &RAM_VECTORS);
+        let bus = Bus::new(memory).map(IO, 0xDFFF, C64Io::new(ntsc)).also_at(0x0000, 0x0001);
+        let mut emulator = CPUEmulatorBuilder::default().state(SystemState::default()).memory(Arc::new(Mutex::new(bus))).build()?;
+        emulator.reset();
+
+        // The tune goes in with everything banked out, so data under the I/O area lands in RAM.
+        emulator.poke(0x0001, 0x34);
+        for (offset, byte) in tune.data.iter().enumerate() {
+            emulator.poke(tune.load.wrapping_add(offset as u16), *byte);
+        }
+        emulator.poke(0x0001, 0x37);
+        let timer = if ntsc { NTSC_TIMER } else { PAL_TIMER };
+        for (register, value) in [(0x04, timer as u8), (0x05, (timer >> 8) as u8), (0x0D, 0x81), (0x0E, 0x11)] {
+            emulator.poke(CIA1 + register, value);
+        }
+
+        let frame_cycles = emulator.with_memory(|bus| bus.device::<C64Io>().map(|io| io.frame_cycles())).unwrap_or(0);
+        let mut player = Self { emulator, tune, song, period: frame_cycles, frame_end: 0 };
+        if player.tune.format == SidFormat::Rsid {
+            player.emulator.state.p.remove(SystemFlags::interrupt_disable);
+        }
+        let returned = player.call(player.tune.init, (song - 1) as u8, INIT_CYCLES)?;
+        if !returned && player.tune.format == SidFormat::Psid {
+            return Err(R6502Error::SidRoutine { routine: player.tune.init, reason: format!("still running after {} cycles", INIT_CYCLES) });
+        }
+        if player.tune.interrupt_driven() {
+            player.emulator.state.p.remove(SystemFlags::interrupt_disable);
+        } else if player.tune.uses_cia(song) {
+            player.period = player.emulator.with_memory(|bus| bus.device::<C64Io>().map(|io| io.cia1().latch(0) as u64 + 1)).unwrap_or(frame_cycles);
+        }
+        player.frame_end = player.emulator.clock();
+        Ok(player)
+    }
+
+    pub fn tune(&self) -> &SidTune {
+        &self.tune
+    }
+
+    pub fn song(&self) -> u16 {
+        self.song
+    }
+
+    /// Cycles per frame: between play calls, or of the video frame for tunes
+    /// that drive themselves.
+    pub fn frame_cycles(&self) -> u64 {
+        self.period
+    }
+
+    pub fn emulator(&self) -> &CPUEmulator<Bus> {
+        &self.emulator
+    }
+
+    pub fn emulator_mut(&mut self) -> &mut CPUEmulator<Bus> {
+        &mut self.emulator
+    }
+
+    /// Plays `frames` more frames, calling play at the start of each unless the
+    /// tune drives itself.
+    pub fn play_frames(&mut self, frames: u64) -> Result<()> {
+        for _ in 0..frames {
+            self.frame_end += self.period;
+            if !self.tune.interrupt_driven() {
+                self.emulator.state.p.insert(SystemFlags::interrupt_disable);
+                if !self.call(self.tune.play, 0, self.period)? {
+                    return Err(R6502Error::SidRoutine { routine: self.tune.play, reason: "still running at the next frame".to_owned() });
+                }
+            }
+            let remaining = self.frame_end.saturating_sub(self.emulator.clock());
+            if remaining > 0 {
+                let routine = if self.tune.interrupt_driven() { self.tune.init } else { self.tune.play };
+                stopped(routine, self.emulator.run_for_cycles(remaining))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// SID writes so far, with cycles counted from when the tune was installed.
+    pub fn writes(&self) -> Vec<SidWrite> {
+        self.emulator.with_memory(|bus| bus.device::<C64Io>().map(|io| io.sid().writes().to_vec())).unwrap_or_default()
+    }
+
+    /// The writes as a siddump style table, a row per frame.
+    pub fn dump(&self) -> String {
+        crate::devices::sid::dump(&self.writes(), self.period)
+    }
+
+    pub fn to_csv(&self) -> String {
+        self.emulator.with_memory(|bus| bus.device::<C64Io>().map(|io| io.sid().to_csv())).unwrap_or_default()
+    }
+
+    // JSRs to `routine` with `a` in A and runs until it returns to RETURN, or
+    // `cycles` run out.
+    fn call(&mut self, routine: u16, a: u8, cycles: u64) -> Result<bool> {
+        self.emulator.push_word(RETURN.wrapping_sub(1));
+        self.emulator.state.pc = routine;
+        self.emulator.state.a = a;
+        self.emulator.add_breakpoint(RETURN);
+        let reason = self.emulator.run_for_cycles(cycles);
+        self.emulator.remove_breakpoint(RETURN);
+        match reason {
+            StopReason::Breakpoint(RETURN) => Ok(true),
+            reason => stopped(routine, reason).map(|_| false),
+        }
+    }
+}
+
+// Running out of cycles is how runs end here, anything else is the tune failing.
+fn stopped(routine: u16, reason: StopReason) -> Result<()> {
+    match reason {
+        StopReason::Timeout | StopReason::Breakpoint(_) => Ok(()),
+        reason => Err(R6502Error::SidRoutine { routine, reason: format!("{:?}", reason) }),
+    }
+}
@@ -0,0 +1,229 @@
+use std::sync::{Arc, Mutex};
+
+use r6502_core::charset::Charset;
+use crate::devices::banked::BankedRam;
+use crate::devices::via::Via;
+use crate::devices::vic::{self, Standard, Vic};
+use crate::devices::Bus;
+use r6502_core::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, VirtualMemory};
+use r6502_core::error::{R6502Error, Result};
+use r6502_core::state::SystemState;
+
+// Commodore VIC-20. 5K of RAM sits at $0000-$03FF and $1000-$1FFF, the
+// character ROM at $8000, the VIC at $9000, the two VIAs at $9110 and $9120,
+// colour RAM at $9400, then BASIC at $C000 and the KERNAL at $E000. Expansion
+// RAM fills the 3K gap at $0400 and the 8K blocks from $2000 up; blocks nothing
+// is plugged into drop writes. The ROMs aren't included, load them with
+// `Vic20::roms`.
+//
+// VIA 1 carries the RESTORE key on CA1 and is wired to NMI; VIA 2 scans the
+// keyboard and its timer 1 drives the 60 Hz jiffy IRQ.
+
+pub const CHARACTER_ROM: u16 = 0x8000;
+pub const VIC: u16 = 0x9000;
+pub const VIA1: u16 = 0x9110;
+pub const VIA2: u16 = 0x9120;
+pub const COLOUR_RAM: u16 = 0x9400;
+/// Block 5, where cartridges and banked RAM boards appear.
+pub const BLOCK5: u16 = 0xA000;
+/// Bank latch of a banked RAM board, in the I/O 3 area.
+pub const BANK_LATCH: u16 = 0x9C00;
+pub const BASIC: u16 = 0xC000;
+pub const KERNAL: u16 = 0xE000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Expansion {
+    /// The stock 5K.
+    #[default]
+    None,
+    /// 3K at $0400.
+    Ram3K,
+    /// 8K in block 1.
+    Ram8K,
+    /// Blocks 1 and 2.
+    Ram16K,
+    /// Blocks 1 to 3.
+    Ram24K,
+    /// 3K at $0400 and blocks 1 to 3.
+    Full,
+}
+
+impl Expansion {
+    /// Address ranges the expansion fills with RAM.
+    pub fn ranges(&self) -> &'static [(u16, u16)] {
+        match self {
+            Self::None => &[],
+            Self::Ram3K => &[(0x0400, 0x0FFF)],
+            Self::Ram8K => &[(0x2000, 0x3FFF)],
+            Self::Ram16K => &[(0x2000, 0x5FFF)],
+            Self::Ram24K => &[(0x2000, 0x7FFF)],
+            Self::Full => &[(0x0400, 0x0FFF), (0x2000, 0x7FFF)],
+        }
+    }
+
+    pub fn covers(&self, address: u16) -> bool {
+        self.ranges().iter().any(|&(start, end)| start <= address && address <= end)
+    }
+}
+
+/// A VIC-20 to build, with its expansion and ROM images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vic20 {
+    pub standard: Standard,
+    pub expansion: Expansion,
+    pub characters: Vec<u8>,
+    pub basic: Vec<u8>,
+    pub kernal: Vec<u8>,
+    /// Cartridge image at block 5.
+    pub cartridge: Option<Vec<u8>>,
+    /// 8K banks of RAM switched into block 5 through the latch at `BANK_LATCH`.
+    pub banked_ram: Option<usize>,
+}
+
+impl Vic20 {
+    pub fn new(standard: Standard, expansion: Expansion) -> Self {
+        Self {
+            standard,
+            expansion,
+            characters: vec![],
+            basic: vec![],
+            kernal: vec![],
+            cartridge: None,
+            banked_ram: None,
+        }
+    }
+
+    /// The 4K character, 8K BASIC and 8K KERNAL ROM images.
+    pub fn roms(mut self, characters: &[u8], basic: &[u8], kernal: &[u8]) -> Self {
+        self.characters = characters.to_vec();
+        self.basic = basic.to_vec();
+        self.kernal = kernal.to_vec();
+        self
+    }
+
+    pub fn cartridge(mut self, image: &[u8]) -> Self {
+        self.cartridge = Some(image.to_vec());
+        self
+    }
+
+    /// Puts a banked RAM board in block 5 instead of a cartridge.
+    pub fn banked_ram(mut self, banks: usize) -> Self {
+        self.banked_ram = Some(banks);
+        self
+    }
+
+    pub fn bus(&self) -> Result<Bus> {
+        let mut memory = DefaultVirtualMemory::default();
+        let images = [
+            (CHARACTER_ROM, 0x1000, &self.characters),
+            (BASIC, 0x2000, &self.basic),
+            (KERNAL, 0x2000, &self.kernal),
+        ];
+        for (origin, size, image) in images {
+            if image.len() > size {
+                return Err(R6502Error::ImageTooLarge { length: image.len(), origin });
+            }
+            memory = memory.with_image(origin, image).with_rom(origin, origin + (size - 1) as u16);
+        }
+        for (start, end) in [(0x0400, 0x0FFF), (0x2000, 0x3FFF), (0x4000, 0x5FFF), (0x6000, 0x7FFF)] {
+            if !self.expansion.covers(start) {
+                memory = memory.with_rom(start, end);
+            }
+        }
+        if let Some(image) = self.cartridge.as_ref().filter(|_| self.banked_ram.is_none()) {
+            if image.len() > 0x2000 {
+                return Err(R6502Error::ImageTooLarge { length: image.len(), origin: BLOCK5 });
+            }
+            memory = memory.with_image(BLOCK5, image).with_rom(BLOCK5, 0xBFFF);
+        }
+
+        let mut bus = Bus::new(memory)
+            .map(VIC, VIC + 0x0F, Vic::new(self.standard))
+            .map(VIA1, VIA1 + 0x0F, Via::new().wired_to_nmi())
+            .map(VIA2, VIA2 + 0x0F, Via::new());
+        if let Some(banks) = self.banked_ram {
+            bus = bus.map(BLOCK5, 0xBFFF, BankedRam::new(BLOCK5, 0x2000, banks, BANK_LATCH)).also_at(BANK_LATCH, BANK_LATCH);
+        }
+        Ok(bus)
+    }
+
+    /// Builds the machine, reset and ready to run.
+    pub fn build(&self) -> Result<CPUEmulator<Bus>> {
+        let mut emulator = CPUEmulatorBuilder::default()
+            .state(SystemState::default())
+            .memory(Arc::new(Mutex::new(self.bus()?)))
+            .build()?;
+        emulator.reset();
+        Ok(emulator)
+    }
+}
+
+/// A rendered screen of palette indices 0-15, one byte per pixel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+// Reads a byte at `address` in the VIC's 16K view.
+fn vic_peek<M: VirtualMemory>(emulator: &CPUEmulator<M>, address: u16) -> u8 {
+    emulator.peek(vic::cpu_address(address & 0x3FFF))
+}
+
+/// The screen codes of `row`, left to right.
+pub fn row<M: VirtualMemory>(emulator: &CPUEmulator<M>, vic: &Vic, row: usize) -> Vec<u8> {
+    let start = vic.screen() as usize + row * vic.columns();
+    (0..vic.columns()).map(|column| vic_peek(emulator, (start + column) as u16)).collect()
+}
+
+/// The screen as text, one line per row, with reversed characters shown like normal ones.
+pub fn text(emulator: &CPUEmulator<Bus>) -> Result<String> {
+    let vic = video(emulator)?;
+    Ok((0..vic.rows())
+        .map(|index| row(emulator, &vic, index).into_iter().map(|code| Charset::ScreenCode.decode(code & 0x7F)).collect::<String>() + "\n")
+        .collect())
+}
+
+/// Draws the character matrix the way the VIC is set up right now, without the
+/// border. Characters whose colour has bit 3 set are multicolour, two pixels
+/// wide per pair of bits: background, border, character colour, auxiliary.
+pub fn render(emulator: &CPUEmulator<Bus>) -> Result<Frame> {
+    let vic = video(emulator)?;
+    let height = if vic.tall_characters() { 16 } else { 8 };
+    let width = vic.columns() * 8;
+    let mut pixels = vec![vic.background(); width * vic.rows() * height];
+    for row_index in 0..vic.rows() {
+        for (column, code) in row(emulator, &vic, row_index).into_iter().enumerate() {
+            let index = (row_index * vic.columns() + column) as u16;
+            let colour = emulator.peek(vic.colour_ram() + index) & 0x0F;
+            let glyph = vic.characters() as usize + code as usize * height;
+            for line in 0..height {
+                let bits = vic_peek(emulator, (glyph + line) as u16);
+                let start = (row_index * height + line) * width + column * 8;
+                let out = &mut pixels[start..start + 8];
+                if colour & 0x08 != 0 {
+                    for pair in 0..4 {
+                        let shade = match bits >> (6 - pair * 2) & 0x03 {
+                            0 => vic.background(),
+                            1 => vic.border(),
+                            2 => colour & 0x07,
+                            _ => vic.auxiliary(),
+                        };
+                        out[pair * 2..pair * 2 + 2].fill(shade);
+                    }
+                } else {
+                    for (x, pixel) in out.iter_mut().enumerate() {
+                        let lit = bits >> (7 - x) & 1 == 1;
+                        *pixel = if lit == vic.normal_mode() { colour } else { vic.background() };
+                    }
+                }
+            }
+        }
+    }
+    Ok(Frame { width, height: vic.rows() * height, pixels })
+}
+
+fn video(emulator: &CPUEmulator<Bus>) -> Result<Vic> {
+    emulator.with_memory(|bus| bus.device::<Vic>().cloned()).ok_or(R6502Error::NoVideoDevice)
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use r6502_core::emulator::{CPUEmulator, CPUEmulatorBuilder};
+use r6502_core::error::Result;
+use r6502_core::loaders::{self, INesImage};
+use r6502_core::state::SystemState;
+
+use crate::devices::ppu::{Ppu, OAMDMA};
+use crate::devices::Bus;
+
+// NES cartridges on a bus. The iNES parser is in the core's loaders and knows
+// nothing of the PPU, so putting a cartridge on a bus is an extension of it.
+
+/// An iNES cartridge put on a bus.
+pub trait Cartridge {
+    /// The cartridge loaded as by [`INesImage::load`], with a PPU showing its
+    /// pattern tables mapped at $2000-$3FFF and OAMDMA.
+    fn bus(&self) -> Result<Bus>;
+
+    /// Like [`Cartridge::bus`], with the work RAM at $6000-$7FFF kept in `save`
+    /// when the cartridge has a battery.
+    fn bus_with_save<P: AsRef<Path>>(&self, save: P) -> Result<Bus>;
+}
+
+impl Cartridge for INesImage {
+    fn bus(&self) -> Result<Bus> {
+        Ok(Bus::new(self.load()?.memory).map(0x2000, 0x3FFF, Ppu::new(&self.chr, self.mirroring)).also_at(OAMDMA, OAMDMA))
+    }
+
+    fn bus_with_save<P: AsRef<Path>>(&self, save: P) -> Result<Bus> {
+        match self.battery {
+            true => self.bus()?.battery_backed(0x6000, 0x7FFF, save),
+            false => self.bus(),
+        }
+    }
+}
+
+/// NES cartridge in iNES format, with a PPU at $2000.
+pub fn emulator<P: AsRef<Path>>(path: P) -> Result<CPUEmulator<Bus>> {
+    let mut emulator = CPUEmulatorBuilder::default()
+        .state(SystemState::default())
+        .memory(Arc::new(Mutex::new(INesImage::parse(&loaders::read_rom(path)?.0)?.bus()?)))
+        .build()?;
+    emulator.reset();
+    Ok(emulator)
+}
@@ -0,0 +1,241 @@
+use r6502::{emulator::{DefaultVirtualMemory, CPUEmulator, CPUEmulatorBuilder, StopReason, VirtualMemory, EXIT_PORT}, loaders::Format, machines, machines::{auto::AutoLoaded, romdb::RomDatabase, AutoLoader, Machine}, monitor::{Monitor, MonitorAction}, shutdown::ThreadGroup, state::SystemState};
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+// Minimal stderr logger. Verbosity comes from R6502_LOG (error, warn, info, debug, trace).
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}: {}", record.level(), record.target(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+// Ctrl-C presses not yet handled by the monitor.
+static INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+// Exit code the program reported through the exit port.
+static EXIT_CODE: AtomicI32 = AtomicI32::new(0);
+
+// The first Ctrl-C pauses into the monitor. A second one before that has been
+// dealt with (including from inside the monitor) quits through the shutdown
+// hooks, and only a third exits straight away in case quitting is stuck.
+#[cfg(unix)]
+extern "C" fn on_sigint(_: libc::c_int) {
+    if INTERRUPTS.fetch_add(1, Ordering::SeqCst) > 1 {
+        unsafe { libc::_exit(130) };
+    }
+}
+
+#[cfg(unix)]
+fn install_sigint_handler() {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = on_sigint as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigemptyset(&mut action.sa_mask);
+        // No SA_RESTART, so Ctrl-C interrupts the monitor waiting for a line.
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+    }
+}
+
+// Ctrl-C goes to a thread that doesn't block it, so `run` blocks it everywhere
+// but the emulator thread, where it can interrupt the monitor.
+#[cfg(unix)]
+fn block_sigint(blocked: bool) {
+    unsafe {
+        let mut signals: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut signals);
+        libc::sigaddset(&mut signals, libc::SIGINT);
+        let how = if blocked { libc::SIG_BLOCK } else { libc::SIG_UNBLOCK };
+        libc::pthread_sigmask(how, &signals, std::ptr::null_mut());
+    }
+}
+
+#[cfg(not(unix))]
+fn install_sigint_handler() {}
+
+#[cfg(not(unix))]
+fn block_sigint(_: bool) {}
+
+fn quitting() -> bool {
+    INTERRUPTS.load(Ordering::SeqCst) > 1
+}
+
+// Standard input for the monitor that ends at the second Ctrl-C. Reading
+// through std would retry the interrupted read and keep waiting for a line.
+struct Console;
+
+#[cfg(unix)]
+impl Read for Console {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let read = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len()) };
+            if read >= 0 {
+                return Ok(read as usize);
+            }
+            let error = std::io::Error::last_os_error();
+            match error.kind() {
+                ErrorKind::Interrupted if quitting() => return Ok(0),
+                ErrorKind::Interrupted => (),
+                _ => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl Read for Console {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        std::io::stdin().read(buffer)
+    }
+}
+
+// r6502 [--exit[=port]] [--crash=file] [--format=name] [--machine=name] [--romdb=file] [image [origin]]:
+// the image's format (iNES, PRG, 2600 cartridge, Intel HEX or S-records) is
+// recognised by its contents and extension unless --format gives it, and the
+// machine is guessed from the format unless --machine gives it or the image
+// is a ROM the built-in database or the one in --romdb knows. Anything
+// unrecognised is a raw binary loaded at origin (hex, default 0). Machines
+// described in a .toml config are handled in `main`. With --exit a write to the
+// exit port ($FFF0 unless given) ends the run and becomes the process exit
+// code, so test ROMs can pass or fail a CI job. With --crash a program that
+// faults leaves its crash report in the file as JSON.
+fn load(args: &[String], loader: AutoLoader) -> anyhow::Result<AutoLoaded> {
+    match args {
+        [path] => Ok(loader.load(path)?),
+        [path, origin] => Ok(loader.origin(u16::from_str_radix(origin.trim_start_matches('$'), 16)?).load(path)?),
+        _ => Err(anyhow::anyhow!("usage: r6502 [--exit[=port]] [--crash=file] [--format=name] [--machine=name] [--romdb=file] [image [origin]]")),
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let level = std::env::var("R6502_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(log::LevelFilter::Info);
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(level);
+
+    // Instructions from https://codeburst.io/an-introduction-to-6502-assembly-and-low-level-programming-7c11fa6b9cb9
+    // LDA   $60
+    // ADC   $61
+    // STA   $62
+
+    let mut memory = vec![0; 0x10000];
+    memory.append(&mut vec![
+        0x78, 0xd8, 0xa2, 0xff, 0x9a, 0xa9, 0x00, 0x95, 0x00, 0xca, 0xd0, 0xfb, 0x85, 0x00,
+        0xa9, 0x30, 0x85, 0x09, 0x4c, 0x00, 0xf0, 0x00, 0xf0, 0x00, 0xf0,
+    ]);
+    
+
+    // https://llx.com/Neil/a2/opcodes.html
+    let (flags, args): (Vec<String>, Vec<String>) = std::env::args().skip(1).partition(|arg| arg.starts_with("--"));
+    let mut exit_port = None;
+    let mut crash_file = None;
+    let mut loader = AutoLoader::default();
+    let mut database = RomDatabase::builtin();
+    for flag in flags.iter() {
+        match flag.split_once('=') {
+            None if flag == "--exit" => exit_port = Some(EXIT_PORT),
+            Some(("--exit", port)) => exit_port = Some(u16::from_str_radix(port.trim_start_matches('$'), 16)?),
+            Some(("--crash", path)) => crash_file = Some(PathBuf::from(path)),
+            Some(("--format", name)) => loader = loader.format(name.parse::<Format>()?),
+            Some(("--machine", name)) => loader = loader.machine(name.parse::<Machine>()?),
+            Some(("--romdb", path)) => database = database.merged(RomDatabase::load(path)?),
+            _ => return Err(anyhow::anyhow!("unknown option {}", flag)),
+        }
+    }
+    match args.as_slice() {
+        [] => {
+            let emulator = CPUEmulatorBuilder::default().state(SystemState::default()).memory(Arc::new(Mutex::new(DefaultVirtualMemory::default()))).build()?;
+            run(emulator, Monitor::new(), exit_port, crash_file)?
+        }
+        [path] if path.ends_with(".toml") => run(machines::from_config(path)?.build()?, Monitor::new(), exit_port, crash_file)?,
+        _ => {
+            let loaded = load(&args, loader.database(database))?;
+            let monitor = Monitor::new().charset(loaded.machine.charset()).symbols(loaded.machine.symbols());
+            run(loaded.emulator, monitor, exit_port, crash_file)?
+        }
+    }
+    match EXIT_CODE.load(Ordering::SeqCst) {
+        0 => Ok(()),
+        code => std::process::exit(code),
+    }
+}
+
+fn run<M: VirtualMemory + Send + 'static>(mut emulator: CPUEmulator<M>, mut monitor: Monitor, exit_port: Option<u16>, crash_file: Option<PathBuf>) -> anyhow::Result<()> {
+    install_sigint_handler();
+    // Threads start with the signal mask of the one spawning them.
+    block_sigint(true);
+    emulator.set_exit_port(exit_port);
+
+    // The emulator thread holds the lock until it stops, so the hook sees the final state.
+    let emulator = Arc::new(Mutex::new(emulator));
+    let mut threads = ThreadGroup::new();
+    let stopped = emulator.clone();
+    threads.on_shutdown(move || {
+        let mut emulator = stopped.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(error) = emulator.flush() {
+            log::error!("Couldn't write out the trace or battery backed RAM: {}", error);
+        }
+    });
+    threads.spawn("emulator", move |shutdown| {
+        block_sigint(false);
+        let mut emulator = emulator.lock().unwrap();
+        let mut console = BufReader::new(Console);
+        loop {
+            match emulator.run_until(|| shutdown.is_requested() || INTERRUPTS.load(Ordering::Relaxed) > 0) {
+                StopReason::Interrupted if shutdown.is_requested() || quitting() => break,
+                StopReason::Breakpoint(_) if !monitor.breaks(&emulator) => (),
+                StopReason::Interrupted | StopReason::Breakpoint(_) => {
+                    match monitor.interact(&mut *emulator, &mut console, std::io::stdout()) {
+                        Ok(MonitorAction::Continue) => INTERRUPTS.store(0, Ordering::SeqCst),
+                        Ok(MonitorAction::Quit) => break,
+                        Err(error) => {
+                            log::error!("monitor: {}", error);
+                            break;
+                        }
+                    }
+                }
+                StopReason::Error(instruction) => {
+                    log::error!("Failed to execute the instruction {:?}", instruction);
+                    if let Some(crash) = emulator.crash() {
+                        eprint!("{}", crash.render());
+                        if let Some(path) = crash_file.as_ref() {
+                            match crash.save(path) {
+                                Ok(()) => log::info!("Crash report saved to {}", path.display()),
+                                Err(error) => log::error!("Couldn't save the crash report to {}: {}", path.display(), error),
+                            }
+                        }
+                    }
+                    break;
+                }
+                StopReason::Exit(code) => {
+                    log::info!("Exited with code {}", code);
+                    EXIT_CODE.store(code as i32, Ordering::SeqCst);
+                    break;
+                }
+                reason => {
+                    log::info!("Stopped: {:?}", reason);
+                    break;
+                }
+            }
+        }
+        shutdown.request();
+    })?;
+    threads.wait();
+    Ok(threads.finish()?)
+    // println!("{:?}", state)
+}
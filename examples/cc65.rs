@@ -0,0 +1,43 @@
+//! Runs a cc65 build in r6502 and stops every time a C function is entered.
+//!
+//! Build the ROM in `examples/cc65` with `make`, then:
+//!
+//! ```text
+//! cargo run --example cc65 -- examples/cc65/build/hello.bin examples/cc65/build/hello.map tick
+//! ```
+use anyhow::{anyhow, Result};
+use r6502::emulator::{CPUEmulator, StopReason};
+use r6502::symbols::SymbolTable;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let [_, binary, map, function] = args.as_slice() else {
+        return Err(anyhow!("usage: cc65 <binary> <ld65 map> <function>"));
+    };
+
+    let symbols = SymbolTable::from_ld65_map(&std::fs::read_to_string(map)?)?;
+    let entry = symbols
+        .lookup(function)
+        .ok_or(anyhow!("{} is not exported in {}", function, map))?;
+
+    // The linker config places the ROM image at $8000.
+    let mut emulator = CPUEmulator::from_binary(binary, 0x8000)?;
+    emulator.add_breakpoint(entry);
+
+    loop {
+        match emulator.run() {
+            StopReason::Breakpoint(pc) => {
+                let name = symbols.name_for(pc).unwrap_or("?");
+                println!("{:#06x} {} | a={:#04x} x={:#04x} y={:#04x} s={:#04x} p={}",
+                    pc, name, emulator.state.a, emulator.state.x, emulator.state.y, emulator.state.s, emulator.state.p);
+            }
+            StopReason::Halted => {
+                println!("main returned {}", emulator.state.a);
+                return Ok(());
+            }
+            reason => {
+                return Err(anyhow!("stopped at {:#06x}: {:?}", emulator.state.pc, reason));
+            }
+        }
+    }
+}
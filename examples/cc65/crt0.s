@@ -0,0 +1,39 @@
+; Minimal startup code: set up the hardware and C stacks, initialise
+; DATA/BSS, call main and stop the processor when it returns.
+
+        .export   _init, _exit
+        .import   _main
+
+        .export   __STARTUP__ : absolute = 1
+        .import   __RAM_START__, __RAM_SIZE__
+
+        .import   copydata, zerobss, initlib, donelib
+
+        .include  "zeropage.inc"
+
+.segment  "STARTUP"
+
+_init:  ldx     #$FF
+        txs
+        cld
+
+        lda     #<(__RAM_START__ + __RAM_SIZE__)
+        sta     sp
+        lda     #>(__RAM_START__ + __RAM_SIZE__)
+        sta     sp+1
+
+        jsr     zerobss
+        jsr     copydata
+        jsr     initlib
+
+        jsr     _main
+
+_exit:  jsr     donelib
+        ; KIL stops r6502 and returns StopReason::Halted to the host.
+        .byte   $02
+
+.segment  "VECTORS"
+
+        .addr   _init
+        .addr   _init
+        .addr   _init
@@ -0,0 +1,16 @@
+unsigned char counter;
+
+void tick(void)
+{
+    ++counter;
+}
+
+int main(void)
+{
+    unsigned char i;
+
+    for (i = 0; i < 10; ++i) {
+        tick();
+    }
+    return counter;
+}
@@ -0,0 +1,26 @@
+//! Writes the static control flow graph of a ROM image as Graphviz DOT.
+//!
+//! ```text
+//! cargo run --example cfg -- rom.bin 8000 > rom.dot && dot -Tsvg rom.dot > rom.svg
+//! ```
+//! Entry points default to the reset, NMI and IRQ vectors when the image covers them.
+use anyhow::{anyhow, Result};
+use r6502::analysis::cfg;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let (path, origin, entries) = match args.as_slice() {
+        [_, path, origin, entries @ ..] => (path, u16::from_str_radix(origin, 16)?, entries),
+        _ => return Err(anyhow!("usage: cfg <image> <origin hex> [entry hex...]")),
+    };
+    let image = std::fs::read(path)?;
+    let mut entries: Vec<u16> = entries
+        .iter()
+        .map(|entry| u16::from_str_radix(entry, 16))
+        .collect::<Result<_, _>>()?;
+    if entries.is_empty() {
+        entries = cfg::vector_entries(&image, origin);
+    }
+    print!("{}", cfg::build(&image, origin, &entries).to_dot(None));
+    Ok(())
+}
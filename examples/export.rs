@@ -0,0 +1,34 @@
+//! Exports a ROM image as re-assemblable source.
+//!
+//! ```text
+//! cargo run --example export -- rom.bin f000 dasm atari2600 > rom.asm
+//! ```
+//! Code is found by tracing control flow from the interrupt vectors; everything
+//! the trace doesn't reach is written out as data. Naming a machine profile adds
+//! its hardware registers as equates.
+use anyhow::{anyhow, Result};
+use r6502::analysis::cfg;
+use r6502::analysis::coverage::ExecutedBytes;
+use r6502::export::{self, Syntax};
+use r6502::machines::Machine;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let (path, origin, syntax, machine) = match args.as_slice() {
+        [_, path, origin, rest @ ..] if rest.len() <= 2 => {
+            let syntax = match rest.first().map(|syntax| syntax.as_str()) {
+                None | Some("ca65") => Syntax::Ca65,
+                Some("dasm") => Syntax::Dasm,
+                Some(other) => return Err(anyhow!("Unknown syntax {}", other)),
+            };
+            let machine: Machine = rest.get(1).map(|name| name.parse()).transpose()?.unwrap_or_default();
+            (path, origin, syntax, machine)
+        }
+        _ => return Err(anyhow!("usage: export <image> <origin hex> [ca65|dasm] [machine]")),
+    };
+    let origin = u16::from_str_radix(origin, 16)?;
+    let image = std::fs::read(path)?;
+    let graph = cfg::build(&image, origin, &cfg::vector_entries(&image, origin));
+    print!("{}", export::project(&image, origin, &ExecutedBytes::from(&graph), Some(&machine.symbols()), syntax));
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! Runs 6502 programs submitted over HTTP, e.g. as the grading backend of an
+//! assembly course.
+//!
+//! ```text
+//! cargo run --example job_server -- 127.0.0.1:6502
+//! curl --data-binary @program.bin 'http://127.0.0.1:6502/run?origin=0200&cycles=100000&dump=0000-00ff&trace=20'
+//! ```
+//!
+//! The body is the raw program. `origin` and `entry` are hex (entry defaults to
+//! the origin), `cycles` is the budget and `trace` the number of instructions to
+//! trace from the start. The JSON response has the stop reason, the final
+//! registers and cycle count, a hex dump of `dump` and the trace. Programs end by
+//! halting, writing an exit code to $FFF0 or jumping to themselves.
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Context, Result};
+use r6502::charset::Charset;
+use r6502::cooperative::block_on;
+use r6502::emulator::{CPUEmulatorBuilder, DefaultVirtualMemory, EXIT_PORT};
+use r6502::snapshot;
+use r6502::state::SystemState;
+use serde_json::{json, Value};
+
+const MAX_CYCLES: u64 = 50_000_000;
+const MAX_TRACE: usize = 1_000;
+const MAX_BODY: usize = 0x10000;
+
+struct Job {
+    image: Vec<u8>,
+    origin: u16,
+    entry: u16,
+    cycles: u64,
+    dump: Option<(u16, u16)>,
+    trace: usize,
+}
+
+fn hex(value: &str) -> Result<u16> {
+    u16::from_str_radix(value.trim_start_matches('$'), 16).with_context(|| format!("bad hex number {}", value))
+}
+
+fn parse_job(query: &str, image: Vec<u8>) -> Result<Job> {
+    let parameters: HashMap<&str, &str> = query.split('&').filter_map(|pair| pair.split_once('=')).collect();
+    let origin = parameters.get("origin").map_or(Ok(0x0200), |origin| hex(origin))?;
+    let entry = parameters.get("entry").map_or(Ok(origin), |entry| hex(entry))?;
+    let cycles: u64 = parameters.get("cycles").map_or(Ok(1_000_000), |cycles| cycles.parse())?;
+    let trace: usize = parameters.get("trace").map_or(Ok(0), |trace| trace.parse())?;
+    let dump = match parameters.get("dump") {
+        Some(range) => {
+            let (start, end) = range.split_once('-').ok_or(anyhow!("dump wants start-end, got {}", range))?;
+            Some((hex(start)?, hex(end)?))
+        }
+        None => None,
+    };
+    if origin as usize + image.len() > 0x10000 {
+        return Err(anyhow!("{} bytes don't fit at {:04X}", image.len(), origin));
+    }
+    Ok(Job { image, origin, entry, cycles: cycles.min(MAX_CYCLES), dump, trace: trace.min(MAX_TRACE) })
+}
+
+fn run_job(job: &Job) -> Result<Value> {
+    let memory = DefaultVirtualMemory::default().with_image(job.origin, &job.image);
+    let mut emulator = CPUEmulatorBuilder::default()
+        .state(SystemState { pc: job.entry, s: 0xFD, running: true, ..Default::default() })
+        .memory(Arc::new(Mutex::new(memory)))
+        .exit_port(Some(EXIT_PORT))
+        .detect_traps(true)
+        .build()?;
+
+    let trace = snapshot::trace(&mut emulator, job.trace);
+    let remaining = job.cycles.saturating_sub(emulator.clock());
+    let reason = block_on(emulator.run_async(remaining));
+    Ok(json!({
+        "reason": format!("{:?}", reason),
+        "exit_code": emulator.exit_code(),
+        "registers": emulator.state.registers(),
+        "cycles": emulator.clock(),
+        "memory": job.dump.map(|(start, end)| snapshot::memory_dump(&emulator, start, end, Charset::Ascii)),
+        "trace": trace,
+    }))
+}
+
+// Reads one request and returns its target and body.
+fn read_request(stream: &TcpStream) -> Result<(String, Vec<u8>)> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut words = line.split_whitespace();
+    let (Some("POST"), Some(target)) = (words.next(), words.next()) else {
+        return Err(anyhow!("expected POST /run, got {}", line.trim()));
+    };
+    let target = target.to_owned();
+
+    let mut length = 0;
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse()?;
+            }
+        }
+    }
+    if length > MAX_BODY {
+        return Err(anyhow!("programs are at most {} bytes", MAX_BODY));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok((target, body))
+}
+
+fn handle(mut stream: TcpStream) -> Result<()> {
+    let response = read_request(&stream).and_then(|(target, body)| {
+        let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+        match path {
+            "/run" => run_job(&parse_job(query, body)?),
+            _ => Err(anyhow!("unknown path {}", path)),
+        }
+    });
+    let (status, body) = match response {
+        Ok(body) => ("200 OK", body),
+        Err(error) => ("400 Bad Request", json!({ "error": error.to_string() })),
+    };
+    let body = body.to_string();
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)?;
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let address = std::env::args().nth(1).unwrap_or("127.0.0.1:6502".to_owned());
+    let listener = TcpListener::bind(&address)?;
+    println!("listening on {}", address);
+    for stream in listener.incoming() {
+        let stream = stream?;
+        std::thread::spawn(move || {
+            if let Err(error) = handle(stream) {
+                eprintln!("{}", error);
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+//! Prints the opcode reference generated from the emulator itself.
+//!
+//! ```text
+//! cargo run --example opcode_docs -- markdown > OPCODES.md
+//! cargo run --example opcode_docs -- html > opcodes.html
+//! cargo run --example opcode_docs -- terminal
+//! ```
+use r6502::opcodes;
+
+fn main() {
+    match std::env::args().nth(1).as_deref() {
+        Some("html") => print!("{}", opcodes::html()),
+        Some("terminal") => println!("{}", opcodes::matrix_table()),
+        Some("markdown") | None => print!("{}", opcodes::markdown()),
+        Some(format) => {
+            eprintln!("unknown format {}, expected markdown, html or terminal", format);
+            std::process::exit(1);
+        }
+    }
+}
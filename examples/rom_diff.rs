@@ -0,0 +1,18 @@
+//! Compares the static opcode statistics of two ROM images.
+//!
+//! ```text
+//! cargo run --example rom_diff -- original.bin patched.bin
+//! ```
+use anyhow::{anyhow, Result};
+use r6502::analysis::histogram::{self, Histogram};
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let [_, left, right] = args.as_slice() else {
+        return Err(anyhow!("usage: rom_diff <left> <right>"));
+    };
+    let left = Histogram::from_image(&std::fs::read(left)?);
+    let right = Histogram::from_image(&std::fs::read(right)?);
+    print!("{}", histogram::diff(&left, &right));
+    Ok(())
+}
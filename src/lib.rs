@@ -1,3 +1,11 @@
-pub mod state;
-pub mod instructions;
-pub mod emulator;
\ No newline at end of file
+// A 6502 emulator and the tooling around it. This crate gathers the workspace
+// under one name: the processor from `r6502-core`, and with the `bus` feature
+// the devices and machines of `r6502-devices`. Consumers that only embed the
+// processor can depend on `r6502-core` directly. The binary is in
+// `r6502-frontends`.
+
+pub use r6502_core::*;
+#[cfg(feature = "bus")]
+pub use r6502_devices::{devices, machines, Bus, Device};
+#[cfg(feature = "devices-nes")]
+pub use r6502_devices::nes;
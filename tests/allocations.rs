@@ -0,0 +1,53 @@
+mod common;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::state::SystemState;
+
+// Counts allocations made by the current thread, so the test harness doesn't interfere.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn execute_does_not_allocate() {
+    // loop: LDA #$01; ADC $10; STA $0200,X; INX; LDA ($20),Y; BNE loop; JMP loop
+    let program = [
+        0xA9, 0x01, 0x65, 0x10, 0x9D, 0x00, 0x02, 0xE8, 0xB1, 0x20, 0xD0, 0xF4, 0x4C, 0x00, 0x04,
+    ];
+    let memory = DefaultVirtualMemory::default().with_image(0x0400, &program);
+    let mut emulator = common::builder(memory, SystemState { pc: 0x0400, running: true, ..Default::default() }.with_cycle_capacity(16))
+        .build()
+        .unwrap();
+
+    let before = ALLOCATIONS.with(|count| count.get());
+    for _ in 0..10_000 {
+        emulator.execute_next_instruction().unwrap();
+        emulator.state.cycles.clear();
+    }
+    let after = ALLOCATIONS.with(|count| count.get());
+    assert_eq!(after - before, 0);
+}
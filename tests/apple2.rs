@@ -0,0 +1,62 @@
+mod common;
+
+use r6502::charset::Charset;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::machines::apple2::{self, HEIGHT, TEXT_PAGE1, WIDTH};
+use r6502::machines::Machine;
+
+fn emulator() -> CPUEmulator<DefaultVirtualMemory> {
+    common::stopped(DefaultVirtualMemory::default()).build().unwrap()
+}
+
+fn print(emulator: &mut CPUEmulator<DefaultVirtualMemory>, row: usize, text: &str) {
+    let start = apple2::row_address(TEXT_PAGE1, row);
+    for (offset, byte) in Charset::Apple.encode_str(text).unwrap().into_iter().enumerate() {
+        emulator.poke(start + offset as u16, byte);
+    }
+}
+
+#[test]
+fn rows_are_interleaved() {
+    let starts: Vec<u16> = [0, 1, 7, 8, 16, 23].iter().map(|row| apple2::row_address(TEXT_PAGE1, *row)).collect();
+    assert_eq!(starts, [0x0400, 0x0480, 0x0780, 0x0428, 0x0450, 0x07D0]);
+}
+
+#[test]
+fn shows_the_monitor_prompt() {
+    let mut emulator = emulator();
+    for address in TEXT_PAGE1..TEXT_PAGE1 + 0x400 {
+        emulator.poke(address, 0xA0);
+    }
+    print(&mut emulator, 22, "APPLE ][");
+    print(&mut emulator, 23, "*");
+    let text = apple2::text(&emulator, TEXT_PAGE1);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 24);
+    assert_eq!(lines[22].trim_end(), "APPLE ][");
+    assert_eq!(lines[23].trim_end(), "*");
+    assert_eq!(Machine::Apple2.charset(), Charset::Apple);
+    assert_eq!("apple2".parse::<Machine>().unwrap(), Machine::Apple2);
+}
+
+#[test]
+fn renders_glyphs_in_normal_inverse_and_flash() {
+    let mut emulator = emulator();
+    // "I" in normal video, then a flashing and an inverse space.
+    emulator.poke(TEXT_PAGE1, 0xC9);
+    emulator.poke(TEXT_PAGE1 + 1, 0x60);
+    emulator.poke(TEXT_PAGE1 + 2, 0x20);
+    let pixels = apple2::render(&emulator, TEXT_PAGE1, false);
+    assert_eq!(pixels.len(), WIDTH * HEIGHT);
+    let cell = |pixels: &[u8], column: usize, line: usize| -> String {
+        pixels[line * WIDTH + column * 7..][..7].iter().map(|pixel| if *pixel == 1 { '#' } else { '.' }).collect()
+    };
+    assert_eq!(cell(&pixels, 0, 0), "..###..");
+    assert_eq!(cell(&pixels, 0, 3), "...#...");
+    assert_eq!(cell(&pixels, 0, 7), ".......");
+    assert_eq!(cell(&pixels, 1, 0), ".......");
+    assert_eq!(cell(&pixels, 2, 0), "#######");
+
+    let flashed = apple2::render(&emulator, TEXT_PAGE1, true);
+    assert_eq!(cell(&flashed, 1, 0), "#######");
+}
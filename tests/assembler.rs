@@ -0,0 +1,44 @@
+use r6502::assembler::assemble;
+use r6502::error::R6502Error;
+
+#[test]
+fn assembles_labels_modes_and_directives() {
+    let source = "
+        counter = $10
+        start:  ldx #<(table+2)   ; comment
+        loop:   inc counter
+                sta $1234,x
+                lda (counter),y
+                asl
+                dex
+                bne loop
+                jmp (table)
+                .byte 1, 'A', \"hi\"
+        table:  .word start, $FFFF
+    ";
+    let assembly = assemble(source, 0x0200).unwrap();
+    assert_eq!(
+        assembly.image,
+        [
+            0xA2, 0x16, // ldx #<(table+2)
+            0xE6, 0x10, // inc counter
+            0x9D, 0x34, 0x12, // sta $1234,x
+            0xB1, 0x10, // lda (counter),y
+            0x0A, // asl
+            0xCA, // dex
+            0xD0, 0xF5, // bne loop
+            0x6C, 0x14, 0x02, // jmp (table)
+            0x01, 0x41, 0x68, 0x69, // .byte
+            0x00, 0x02, 0xFF, 0xFF, // .word
+        ]
+    );
+    assert_eq!(assembly.symbols.lookup("table"), Some(0x0214));
+}
+
+#[test]
+fn reports_the_failing_line() {
+    let error = assemble("nop\nbeq nowhere", 0x0200).unwrap_err();
+    assert!(matches!(error, R6502Error::Assembly { line: 2, .. }), "{}", error);
+    assert!(assemble("lda #$100", 0x0200).is_err());
+    assert!(assemble("stx $1234,x", 0x0200).is_err());
+}
@@ -0,0 +1,99 @@
+use r6502::assembler::assemble;
+use r6502::devices::antic::{self, Antic, Standard, CYCLES_PER_LINE};
+use r6502::devices::pokey::{self, Pokey};
+use r6502::emulator::VirtualMemory;
+use r6502::machines::atari8::{Atari8, OS_ROM, OS_ROM_SIZE};
+use r6502::machines::Machine;
+
+// Points ANTIC at a display list in RAM with one DLI, turns on DMA and both
+// NMIs, then counts DLIs at $10 and VBIs at $11. The DLI handler waits for the
+// next line with WSYNC like a colour change would.
+const OS: &str = "
+reset: sei
+ldx #$ff
+txs
+ldx #$00
+copy: lda list,x
+sta $1000,x
+inx
+cpx #$0c
+bne copy
+lda #$00
+sta $d402
+lda #$10
+sta $d403
+lda #$22
+sta $d400
+lda #$c0
+sta $d40e
+loop: jmp loop
+nmi: bit $d40f
+bpl vbi
+inc $10
+sta $d40a
+sta $d40f
+rti
+vbi: inc $11
+sta $d40f
+rti
+list: .byte $70, $70, $70, $42, $00, $20, $02, $82, $02, $41, $00, $10
+";
+
+fn os() -> Vec<u8> {
+    let program = assemble(OS, OS_ROM).unwrap();
+    let mut image = program.image.clone();
+    image.resize(OS_ROM_SIZE, 0);
+    let nmi = program.symbols.lookup("nmi").unwrap().to_le_bytes();
+    let reset = program.symbols.lookup("reset").unwrap().to_le_bytes();
+    image[OS_ROM_SIZE - 6..].copy_from_slice(&[nmi[0], nmi[1], reset[0], reset[1], 0, 0]);
+    image
+}
+
+#[test]
+fn display_list_and_vertical_blank_nmis() {
+    let mut emulator = Atari8::new(Standard::Ntsc, 0xC000).os(&os()).build().unwrap();
+    emulator.run_for_cycles(CYCLES_PER_LINE * 262 * 4);
+    let (dlis, vbis) = (emulator.read(0x10), emulator.read(0x11));
+    assert!((3..=4).contains(&vbis), "{} VBIs", vbis);
+    // The first frame runs before the display list is set up.
+    assert!((2..=3).contains(&dlis), "{} DLIs", dlis);
+    emulator.with_memory(|bus| {
+        // 24 blank lines and three mode 2 lines put the DLI on the third's last scanline.
+        assert_eq!(bus.device::<Antic>().unwrap().dli_lines(), [8 + 24 + 23]);
+    });
+}
+
+#[test]
+fn dli_lines_follow_mode_heights() {
+    assert_eq!(antic::dli_lines(&[0xF0, 0x4F, 0, 0, 0x8F, 0x87, 0x41]), [15, 17, 33]);
+    assert!(antic::dli_lines(&[0x41, 0, 0, 0x82]).is_empty());
+}
+
+#[test]
+fn memory_map_limits_ram_and_maps_cartridges() {
+    let cartridge = vec![0xCA; 0x2000];
+    let mut emulator = Atari8::new(Standard::Pal, 0x4000).cartridge(&cartridge).build().unwrap();
+    emulator.poke(0x3FFF, 0x11);
+    emulator.poke(0x4000, 0x22);
+    emulator.poke(0xA000, 0x33);
+    assert_eq!((emulator.peek(0x3FFF), emulator.peek(0x4000), emulator.peek(0xA000)), (0x11, 0, 0xCA));
+    assert_eq!(emulator.peek(0xD014), 0x01);
+    assert_eq!(emulator.peek(0xD01F), 0x07);
+    assert!(Atari8::new(Standard::Ntsc, 0xC000).os(&vec![0; OS_ROM_SIZE + 1]).bus().is_err());
+    assert_eq!("atari800".parse::<Machine>().unwrap(), Machine::Atari8);
+    assert_eq!(Machine::Atari8.symbols().name_for(0xD40A), Some("WSYNC"));
+}
+
+#[test]
+fn key_presses_raise_the_pokey_irq() {
+    let mut pokey = Pokey::new();
+    pokey.press_key(0x3F);
+    assert!(!pokey.irq());
+    pokey.write(pokey::IRQST, pokey::KEY_IRQ);
+    pokey.press_key(0x3F);
+    assert!(pokey.irq());
+    assert_eq!(pokey.read(pokey::KBCODE), 0x3F);
+    assert_eq!(pokey.read(pokey::IRQST), !pokey::KEY_IRQ);
+    pokey.write(pokey::IRQST, 0);
+    assert!(!pokey.irq());
+}
@@ -0,0 +1,81 @@
+use r6502::error::R6502Error;
+use r6502::loaders::{self, Format};
+use r6502::machines::{AutoLoader, Machine};
+
+// LDA #$42; STA $10; KIL at $C000.
+const HEX: &str = "\
+:06C00000A94285100200B8
+:02FFFC0000C043
+:00000001FF
+";
+
+const SREC: &str = "\
+S00600004844521B
+S109C000A94285100200B4
+S9030200FA
+";
+
+#[test]
+fn reads_intel_hex() {
+    let program = loaders::intel_hex(HEX).unwrap();
+    assert_eq!(&program.memory.bytes()[0xC000..0xC006], [0xA9, 0x42, 0x85, 0x10, 0x02, 0x00]);
+    // The data covers the reset vector, so that's where execution starts.
+    assert_eq!(program.entry, None);
+    assert!(matches!(loaders::intel_hex(":06C00000A94285100200B9\n"), Err(R6502Error::BadRecord { line: 1, .. })));
+    assert!(matches!(loaders::intel_hex(":020000040001F9\n"), Err(R6502Error::BadRecord { line: 1, .. })));
+}
+
+#[test]
+fn reads_s_records() {
+    let program = loaders::srec(SREC).unwrap();
+    assert_eq!(&program.memory.bytes()[0xC000..0xC006], [0xA9, 0x42, 0x85, 0x10, 0x02, 0x00]);
+    assert_eq!(program.entry, Some(0x0200));
+    assert!(matches!(loaders::srec("S00600004844521B\nS109C000A94285100200B5\n"), Err(R6502Error::BadRecord { line: 2, .. })));
+}
+
+#[test]
+fn detects_formats_by_signature_extension_and_shape() {
+    assert_eq!(Format::detect(b"NES\x1A\x01\x00", Some("bin")), Format::INes);
+    assert_eq!(Format::detect(HEX.as_bytes(), None), Format::IntelHex);
+    assert_eq!(Format::detect(SREC.as_bytes(), Some("txt")), Format::Srec);
+    assert_eq!(Format::detect(&[0x00, 0xC0], Some("PRG")), Format::Prg);
+
+    let mut cartridge = vec![0xEA; 0x1000];
+    cartridge[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+    assert_eq!(Format::detect(&cartridge, None), Format::Atari2600);
+    cartridge[0xFFD] = 0x00;
+    assert_eq!(Format::detect(&cartridge, None), Format::Binary);
+
+    // 10 SYS 2061
+    let basic = [0x01, 0x08, 0x0B, 0x08, 0x0A, 0x00, 0x9E, b'2', b'0', b'6', b'1', 0x00, 0x00, 0x00, 0x60];
+    assert_eq!(Format::detect(&basic, None), Format::Prg);
+    assert_eq!("s19".parse::<Format>().unwrap(), Format::Srec);
+    assert!("zip".parse::<Format>().is_err());
+}
+
+#[test]
+fn picks_the_machine_and_its_devices() {
+    let mut cartridge = vec![0xEA; 0x0800];
+    // STA WSYNC, then JMP back, in a 2K cartridge starting at $F800.
+    cartridge[..5].copy_from_slice(&[0x85, 0x02, 0x4C, 0x00, 0xF8]);
+    cartridge[0x7FC..].copy_from_slice(&[0x00, 0xF8, 0x00, 0xF8]);
+    let mut loaded = AutoLoader::default().load_bytes(&cartridge, None).unwrap();
+    assert_eq!((loaded.format, loaded.machine), (Format::Atari2600, Machine::Atari2600));
+    assert_eq!(loaded.emulator.state.pc, 0xF800);
+    // The TIA is there: WSYNC holds the processor to the end of the scanline.
+    assert!(loaded.emulator.step_scanline().is_ok());
+
+    let basic = [0x01, 0x08, 0x0B, 0x08, 0x0A, 0x00, 0x9E, b'2', b'0', b'6', b'1', 0x00, 0x00, 0x00, 0x02];
+    let loaded = AutoLoader::default().load_bytes(&basic, None).unwrap();
+    assert_eq!((loaded.machine, loaded.emulator.state.pc), (Machine::C64, 2061));
+}
+
+#[test]
+fn guesses_can_be_overridden() {
+    let loaded = AutoLoader::default().format(Format::Binary).origin(0x0400).load_bytes(HEX.as_bytes(), None).unwrap();
+    assert_eq!(loaded.format, Format::Binary);
+    assert_eq!(loaded.emulator.peek(0x0400), b':');
+
+    let loaded = AutoLoader::default().machine(Machine::Apple2).load_bytes(SREC.as_bytes(), None).unwrap();
+    assert_eq!((loaded.format, loaded.machine), (Format::Srec, Machine::Apple2));
+}
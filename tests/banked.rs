@@ -0,0 +1,39 @@
+mod common;
+
+use r6502::devices::banked::BankedRam;
+use r6502::devices::Bus;
+use r6502::emulator::DefaultVirtualMemory;
+
+#[test]
+fn each_bank_keeps_its_own_contents() {
+    // Writes the bank number to the first byte of each of the 32 banks, then
+    // leaves bank 5 selected.
+    let program = [
+        0xA2, 0x1F, // LDX #31
+        0x8E, 0x00, 0xC0, // loop: STX $C000
+        0x8E, 0x00, 0x80, // STX $8000
+        0xCA, // DEX
+        0x10, 0xF7, // BPL loop
+        0xA9, 0x05, 0x8D, 0x00, 0xC0, // LDA #5; STA $C000
+        0xAD, 0x00, 0x80, // LDA $8000
+        0x85, 0x10, // STA $10
+        0x00, // BRK
+    ];
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &program);
+    let bus = Bus::new(memory)
+        .map(0x8000, 0xBFFF, BankedRam::new(0x8000, 0x4000, 32, 0xC000))
+        .also_at(0xC000, 0xC000);
+    let mut emulator = common::running(bus, 0x0200).build().unwrap();
+
+    for _ in 0..(2 + 32 * 4 + 5) {
+        emulator.execute_next_instruction().unwrap();
+    }
+    assert_eq!(emulator.peek(0x10), 5);
+    assert_eq!(emulator.peek(0xC000), 5);
+
+    emulator.with_memory(|bus| {
+        let ram = bus.device::<BankedRam>().unwrap();
+        assert_eq!(ram.capacity(), 512 * 1024);
+        assert!((0..32).all(|bank| ram.bank_data(bank)[0] == bank as u8));
+    });
+}
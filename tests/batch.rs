@@ -0,0 +1,44 @@
+mod common;
+
+use r6502::batch::BatchRunner;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::state::SystemFlags;
+
+// Doubles `value` into $10 and halts, leaving the shifted out bit in carry.
+fn double(value: u8) -> CPUEmulator<DefaultVirtualMemory> {
+    let program = [
+        0xA9, value, // LDA #value
+        0x0A, // ASL A
+        0x85, 0x10, // STA $10
+        0x02, // KIL
+    ];
+    common::program(&program, 0x0200).build().unwrap()
+}
+
+#[test]
+fn results_come_back_in_case_order() {
+    let cases: Vec<u8> = (0..64).collect();
+    let results = BatchRunner::new().threads(4).run(&cases, |&value| {
+        let mut emulator = double(value);
+        assert_eq!(emulator.run(), StopReason::Halted);
+        emulator.peek(0x10)
+    });
+    assert_eq!(results, cases.iter().map(|value| value * 2).collect::<Vec<_>>());
+}
+
+#[test]
+fn checked_runs_report_the_failing_cases() {
+    let cases = [0x01, 0x80, 0x40, 0xC0];
+    let summary = BatchRunner::new().threads(3).run_checked(&cases, |&value| {
+        let mut emulator = double(value);
+        emulator.run();
+        match emulator.state.p.contains(SystemFlags::carry) {
+            true => Err(format!("{:02X} overflowed", value)),
+            false => Ok(()),
+        }
+    });
+    assert_eq!(summary.total, 4);
+    assert_eq!(summary.passed, 2);
+    assert!(!summary.all_passed());
+    assert_eq!(summary.failures, vec![(1, "80 overflowed".to_owned()), (3, "C0 overflowed".to_owned())]);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use std::path::Path;
+
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::machines;
+
+// INC $6000; KIL, so each run bumps the saved counter.
+const PROGRAM: [u8; 4] = [0xEE, 0x00, 0x60, 0x02];
+
+fn emulator(save: &Path) -> CPUEmulator<Bus> {
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &PROGRAM);
+    let bus = Bus::new(memory).battery_backed(0x6000, 0x60FF, save).unwrap();
+    common::running(bus, 0x0200).build().unwrap()
+}
+
+#[test]
+fn keeps_ram_between_runs() {
+    let directory = std::env::temp_dir().join(format!("r6502-battery-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let save = directory.join("game.sav");
+
+    let mut first = emulator(&save);
+    first.run();
+    assert!(!save.exists());
+    first.with_memory(|bus| bus.flush()).unwrap();
+    assert_eq!(std::fs::read(&save).unwrap().len(), 0x100);
+    // Nothing changed since, so the next flush leaves the file alone.
+    std::fs::remove_file(&save).unwrap();
+    first.with_memory(|bus| bus.flush()).unwrap();
+    assert!(!save.exists());
+    first.poke(0x6001, 0x55);
+    drop(first);
+    assert_eq!(std::fs::read(&save).unwrap()[..2], [0x01, 0x55]);
+
+    let mut second = emulator(&save);
+    second.run();
+    assert_eq!((second.peek(0x6000), second.peek(0x6001)), (0x02, 0x55));
+    drop(second);
+    assert_eq!(std::fs::read(&save).unwrap()[0], 0x02);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn config_regions_can_be_saved() {
+    let directory = std::env::temp_dir().join(format!("r6502-battery-config-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("nvram.bin"), [0x07]).unwrap();
+    std::fs::write(
+        directory.join("machine.toml"),
+        r#"
+reset = 0x0200
+
+[[region]]
+start = 0x0200
+end = 0x0203
+file = "program.bin"
+
+[[region]]
+start = 0x6000
+end = 0x6003
+fill = 0xFF
+save = "nvram.bin"
+"#,
+    )
+    .unwrap();
+    std::fs::write(directory.join("program.bin"), PROGRAM).unwrap();
+
+    let mut emulator = machines::from_config(directory.join("machine.toml")).unwrap().build().unwrap();
+    emulator.run();
+    assert_eq!((emulator.peek(0x6000), emulator.peek(0x6001)), (0x08, 0xFF));
+    drop(emulator);
+    assert_eq!(std::fs::read(directory.join("nvram.bin")).unwrap(), [0x08, 0xFF, 0xFF, 0xFF]);
+
+    let rom = "[[region]]\nstart = 0x6000\nend = 0x6003\nkind = \"rom\"\nsave = \"x.bin\"\n";
+    assert!(machines::config::MachineConfig::parse(rom).is_err());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
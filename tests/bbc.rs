@@ -0,0 +1,81 @@
+use r6502::assembler::assemble;
+use r6502::emulator::StopReason;
+use r6502::machines::bbc::{self, BbcMicro};
+use r6502::machines::Machine;
+
+// A language ROM that reads HIMEM into $70, then reads lines with OSWORD 0 and
+// prints them back, until a line starting with E raises an error.
+const LANGUAGE: &str = "
+        jmp language
+        jmp service
+        .byte $c2, copyright-$8000, 1
+        .byte \"TEST\", 0
+copyright: .byte 0, \"(C)\", 0
+service: rts
+language: lda #$84
+        jsr $fff4
+        stx $70
+        sty $71
+        lda #$00
+        sta $0900
+        lda #$0a
+        sta $0901
+        lda #20
+        sta $0902
+        lda #32
+        sta $0903
+        lda #126
+        sta $0904
+prompt: lda #'>'
+        jsr $ffee
+        lda #0
+        ldx #$00
+        ldy #$09
+        jsr $fff1
+        lda $0a00
+        cmp #'E'
+        beq fail
+        ldx #0
+echo:   lda $0a00,x
+        jsr $ffe3
+        inx
+        cmp #13
+        bne echo
+        jmp prompt
+fail:   brk
+        .byte 17, \"Escape\", 0
+";
+
+fn machine() -> BbcMicro {
+    let language = assemble(LANGUAGE, 0x8000).unwrap().image;
+    // A service ROM in a higher slot isn't a language.
+    let service = [0x60, 0, 0, 0x60, 0, 0, 0x82, 0x07, 0, 0, 0];
+    BbcMicro::new().sideways_rom(12, &language).sideways_rom(14, &service)
+}
+
+#[test]
+fn runs_a_language_to_its_prompt_over_the_console() {
+    assert_eq!(machine().language(), Some(12));
+    let mut emulator = machine().build().unwrap();
+    let console = bbc::console(&emulator).unwrap();
+    assert!(matches!(bbc::run_until_input(&mut emulator), StopReason::Interrupted));
+    assert_eq!(console.take_text(), "BBC Computer 32K\n\nTEST\n\n>");
+    assert_eq!((emulator.peek(0x70), emulator.peek(0x71)), (0x00, 0x7C));
+    assert_eq!(emulator.peek(0xF4), 12);
+
+    console.type_text("hello\n");
+    bbc::run_until_input(&mut emulator);
+    assert_eq!(console.take_text(), "hello\nhello\n>");
+}
+
+#[test]
+fn errors_go_through_brkv() {
+    let mut emulator = machine().build().unwrap();
+    let console = bbc::console(&emulator).unwrap();
+    console.type_text("E\n");
+    emulator.run_for_cycles(100_000);
+    let text = console.take_text();
+    assert!(text.ends_with(">E\nEscape"), "{:?}", text);
+    assert_eq!("beeb".parse::<Machine>().unwrap(), Machine::Bbc);
+    assert_eq!(Machine::Bbc.symbols().name_for(0xFFEE), Some("OSWRCH"));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use r6502::devices::tia::Tia;
+use r6502::devices::Bus;
+use r6502::emulator::{DefaultVirtualMemory, StopReason};
+
+// Keeps the instruction trace lines the emulator logs.
+struct Capture(Mutex<Vec<String>>);
+
+impl Log for Capture {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Trace
+    }
+
+    fn log(&self, record: &Record) {
+        self.0.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(vec![]));
+
+#[test]
+fn trace_lines_carry_the_beam_position() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let program = [
+        0xEA, // NOP
+        0xEA, // NOP
+        0x85, 0x02, // STA WSYNC
+        0xEA, // NOP
+        0x02, // KIL
+    ];
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x1000, &program)).map(0x0000, 0x003F, Tia::new());
+    let mut emulator = common::running(bus, 0x1000).build().unwrap();
+    assert_eq!(emulator.run(), StopReason::Halted);
+
+    // WSYNC holds the processor until the start of the next scanline.
+    let lines = CAPTURE.0.lock().unwrap();
+    let beams: Vec<(&str, &str)> = lines
+        .iter()
+        .filter_map(|line| Some((line.split_once(':')?.0, line.rsplit_once(" [")?.1)))
+        .collect();
+    assert_eq!(
+        beams,
+        [("1000", "  0,  0]"), ("1001", "  0,  6]"), ("1002", "  0, 12]"), ("1004", "  1,  0]"), ("1005", "  1,  6]")]
+    );
+}
@@ -0,0 +1,58 @@
+use r6502::analysis::cfg::{self, Edge, EdgeKind};
+use r6502::symbols::SymbolTable;
+
+const ORIGIN: u16 = 0x0200;
+
+const PROGRAM: [u8; 13] = [
+    0xA2, 0x03, // LDX #3
+    0xCA, // loop: DEX
+    0xD0, 0xFD, // BNE loop
+    0x20, 0x00, 0xF0, // JSR $F000
+    0x6C, 0x00, 0x03, // JMP ($0300)
+    0xEA, 0xEA, // never reached
+];
+
+fn edge(from: u16, to: u16, kind: EdgeKind) -> Edge {
+    Edge { from, to, kind }
+}
+
+#[test]
+fn build_splits_blocks_at_branch_targets() {
+    let graph = cfg::build(&PROGRAM, ORIGIN, &[ORIGIN]);
+    assert_eq!(graph.blocks.keys().copied().collect::<Vec<_>>(), [0x0200, 0x0202, 0x0205, 0x0208]);
+    assert_eq!(graph.blocks[&0x0202].last(), 0x0203);
+    assert_eq!(
+        graph.edges,
+        [
+            edge(0x0200, 0x0202, EdgeKind::FallThrough),
+            edge(0x0202, 0x0202, EdgeKind::Branch),
+            edge(0x0202, 0x0205, EdgeKind::FallThrough),
+            edge(0x0205, 0x0208, EdgeKind::FallThrough),
+        ]
+    );
+    assert_eq!(graph.external, [edge(0x0205, 0xF000, EdgeKind::Call)]);
+    assert_eq!(graph.indirect_jumps, [0x0208]);
+    assert_eq!(graph.unreachable, [(0x020B, 0x020C)]);
+}
+
+#[test]
+fn entries_default_to_the_vectors_the_image_covers() {
+    let mut image = vec![0xEA; 0x100];
+    image[0xFC] = 0x10; // reset -> $FF10
+    image[0xFD] = 0xFF;
+    let entries = cfg::vector_entries(&image, 0xFF00);
+    assert!(entries.contains(&0xFF10));
+    assert!(cfg::vector_entries(&PROGRAM, ORIGIN).is_empty());
+}
+
+#[test]
+fn to_dot_labels_blocks_and_styles_edges() {
+    let mut symbols = SymbolTable::default();
+    symbols.insert("loop", 0x0202);
+    let dot = cfg::build(&PROGRAM, ORIGIN, &[ORIGIN]).to_dot(Some(&symbols));
+    assert!(dot.starts_with("digraph cfg {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("    \"0202\" [label=\"loop:\\l0202: DEX\\l0203: BNE loop\\l\"];\n"));
+    assert!(dot.contains("    \"0202\" -> \"0202\" [label=\"branch\" color=blue];\n"));
+    assert!(dot.contains("    \"0205\" -> \"F000\" [label=\"call\" style=dashed];\n"));
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use r6502::charset::Charset;
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::machines::Machine;
+use r6502::monitor::Monitor;
+use r6502::snapshot;
+
+#[test]
+fn decodes_each_character_set() {
+    assert_eq!(Charset::Ascii.decode_bytes(b"Hi!\x00\x7F"), "Hi!..");
+    assert_eq!(Charset::Petscii.decode_bytes(&[0x48, 0x49, 0x5C, 0x0D, 0xA0, 0xC1]), "HI£. .");
+    // "HELLO", then a reverse video "@1".
+    assert_eq!(Charset::ScreenCode.decode_bytes(&[8, 5, 12, 12, 15, 0x80, 0xB1]), "HELLO@1");
+    assert_eq!(Charset::ScreenCode.encode_str("Hello 1"), Some(vec![8, 5, 12, 12, 15, 0x20, 0x31]));
+    assert_eq!("screen".parse(), Ok(Charset::ScreenCode));
+    assert!("ebcdic".parse::<Charset>().is_err());
+    assert_eq!(Machine::C64.charset(), Charset::Petscii);
+}
+
+#[test]
+fn dumps_show_text_in_the_chosen_charset() {
+    let mut emulator = common::stopped(DefaultVirtualMemory::default()).build().unwrap();
+    for (offset, byte) in [8u8, 9, 0x21].iter().enumerate() {
+        emulator.poke(0x0400 + offset as u16, *byte);
+    }
+
+    let mut monitor = Monitor::new().charset(Charset::ScreenCode);
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "m 0400 3", &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), format!("0400  {:<47}  HI!\n", "08 09 21"));
+
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "cs ascii", &mut out).unwrap();
+    monitor.execute(&mut emulator, "m 0400 3", &mut out).unwrap();
+    assert!(String::from_utf8(out).unwrap().ends_with("  ..!\n"));
+
+    assert_eq!(snapshot::memory_dump(&emulator, 0x0400, 0x0402, Charset::ScreenCode), format!("0400: {:<47}  HI!\n", "08 09 21"));
+}
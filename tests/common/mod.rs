@@ -0,0 +1,34 @@
+// Emulators for the integration tests, so each test only spells out what it
+// does differently. Not every test uses every helper.
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use r6502::assembler::assemble;
+use r6502::emulator::{CPUEmulatorBuilder, DefaultVirtualMemory, VirtualMemory};
+use r6502::state::SystemState;
+
+/// A processor in `state` over `memory`.
+pub fn builder<M: VirtualMemory>(memory: M, state: SystemState) -> CPUEmulatorBuilder<M> {
+    CPUEmulatorBuilder::default().state(state).memory(Arc::new(Mutex::new(memory)))
+}
+
+/// A processor running from `pc` over `memory`, with S where reset leaves it.
+pub fn running<M: VirtualMemory>(memory: M, pc: u16) -> CPUEmulatorBuilder<M> {
+    builder(memory, SystemState { pc, s: 0xFD, running: true, ..Default::default() })
+}
+
+/// A processor in its power-on state over `memory`, not running until reset.
+pub fn stopped<M: VirtualMemory>(memory: M) -> CPUEmulatorBuilder<M> {
+    builder(memory, SystemState::default())
+}
+
+/// `program` loaded at `origin` and running from its first byte.
+pub fn program(program: &[u8], origin: u16) -> CPUEmulatorBuilder<DefaultVirtualMemory> {
+    running(DefaultVirtualMemory::default().with_image(origin, program), origin)
+}
+
+/// `source` assembled at `origin` and running from its first line.
+pub fn assembled(source: &str, origin: u16) -> CPUEmulatorBuilder<DefaultVirtualMemory> {
+    program(&assemble(source, origin).unwrap().image, origin)
+}
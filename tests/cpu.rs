@@ -0,0 +1,48 @@
+mod common;
+
+use r6502::cpu::Cpu6502;
+use r6502::emulator::DefaultVirtualMemory;
+
+// Counts in $10 forever; the NMI handler at $0300 counts in $11 and the IRQ
+// handler at $0310 in $12.
+fn core() -> impl Cpu6502 {
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x0200, &[0x58, 0xE6, 0x10, 0x4C, 0x01, 0x02]) // CLI; loop: INC $10; JMP loop
+        .with_image(0x0300, &[0xE6, 0x11, 0x40]) // INC $11; RTI
+        .with_image(0x0310, &[0xE6, 0x12, 0x40]) // INC $12; RTI
+        .with_image(0xFFFA, &[0x00, 0x03, 0x00, 0x02, 0x10, 0x03]);
+    common::stopped(memory).build().unwrap()
+}
+
+// Hosts only see the trait.
+fn run(cpu: &mut dyn Cpu6502, steps: usize) {
+    for _ in 0..steps {
+        assert!(cpu.step());
+    }
+}
+
+#[test]
+fn hosts_drive_cores_through_the_trait() {
+    let mut cpu = core();
+    cpu.reset();
+    assert_eq!(cpu.registers().pc, 0x0200);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.peek(0x10), 1);
+    assert_eq!(cpu.last_writes().last(), Some(&(0x10, 1)));
+    run(&mut cpu, 1);
+
+    // The NMI is taken before the next instruction and returns to the loop.
+    cpu.nmi();
+    run(&mut cpu, 2);
+    assert_eq!(cpu.peek(0x11), 1);
+    assert_eq!(cpu.registers().pc, 0x0201);
+
+    // The IRQ keeps firing while the line is held.
+    cpu.set_irq(true);
+    run(&mut cpu, 4);
+    assert_eq!(cpu.peek(0x12), 2);
+    cpu.set_irq(false);
+    run(&mut cpu, 2);
+    assert_eq!(cpu.peek(0x12), 2);
+    assert!(cpu.clock() > 0);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use r6502::crash::{CrashReport, TraceRing};
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::state::EmulatorError;
+
+// Calls a routine using an opcode of an extended CPU that isn't implemented.
+const PROGRAM: &str = "
+        ldx #$ff
+        txs
+        lda #$12
+        pha
+        jsr broken
+broken: .byte $02
+";
+
+fn emulator(trace: TraceRing) -> CPUEmulator<DefaultVirtualMemory> {
+    let mut emulator = common::assembled(PROGRAM, 0x0200)
+        .trace(trace)
+        .build()
+        .unwrap();
+    emulator.override_opcode(0x02, |_| Err(EmulatorError::UnimplementedInstruction.into()));
+    emulator
+}
+
+#[test]
+fn faults_leave_a_crash_report() {
+    let mut emulator = emulator(TraceRing::new(3));
+    assert!(matches!(emulator.run(), StopReason::Error(Some(_))));
+    let crash = emulator.crash().unwrap();
+    assert_eq!((crash.fault.as_str(), crash.opcode, crash.registers.pc), ("override for 0x02 failed: Instruction not implemented", 0x02, 0x0209));
+    assert_eq!(crash.trace.iter().map(|retired| (retired.registers.pc, retired.opcode)).collect::<Vec<_>>(), [(0x0203, 0xA9), (0x0205, 0x48), (0x0206, 0x20)]);
+    assert_eq!(crash.trace[2].registers.s, 0xFE);
+    assert_eq!(crash.stack, [0x08, 0x02, 0x12]);
+    assert_eq!(crash.memory_start, 0x01F9);
+    assert_eq!(crash.memory[0x10 - 4..0x10 + 1], [0x48, 0x20, 0x09, 0x02, 0x02]);
+    assert!(crash.to_string().ends_with("0209 after 15 cycles"), "{}", crash);
+
+    emulator.reset();
+    assert!(emulator.crash().is_none());
+    assert_eq!(emulator.trace.len(), 3);
+}
+
+#[test]
+fn crash_reports_round_trip_through_json() {
+    let mut emulator = emulator(TraceRing::default());
+    emulator.run();
+    let crash = emulator.crash().unwrap();
+    assert_eq!(crash.trace.len(), 5);
+
+    let path = std::env::temp_dir().join(format!("r6502-crash-{}.json", std::process::id()));
+    crash.save(&path).unwrap();
+    assert_eq!(&CrashReport::load(&path).unwrap(), crash);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn renders_crash_reports() {
+    colored::control::set_override(false);
+    let mut emulator = emulator(TraceRing::default());
+    assert!(emulator.crash_report().is_none());
+    emulator.run();
+    let report = emulator.crash_report().unwrap();
+    assert!(report.starts_with("Crashed: override for 0x02 failed"), "{}", report);
+    // The listing starts at the first instruction traced and marks the program counter.
+    assert!(report.contains("   0200  A2 FF     LDX #$FF\n"), "{}", report);
+    assert!(report.contains("=> 0209  02        "), "{}", report);
+    assert!(report.contains("Last 5 instructions"), "{}", report);
+    assert!(report.contains("Stack\n01FD  08 02 12\n"), "{}", report);
+    assert!(report.contains("Last 7 bus cycles"), "{}", report);
+}
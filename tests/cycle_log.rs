@@ -0,0 +1,98 @@
+mod common;
+
+use std::io::BufWriter;
+
+use r6502::cycle_log::{CycleLog, CycleReader, CycleWriter};
+use r6502::error::R6502Error;
+use r6502::state::{SystemAction, SystemCycle};
+
+fn cycle(address: u16, value: u8, action: SystemAction) -> SystemCycle {
+    SystemCycle { address, value, action }
+}
+
+fn round_trip(cycles: &[SystemCycle]) -> (usize, Vec<SystemCycle>) {
+    let mut writer = CycleWriter::new(vec![]).unwrap();
+    for cycle in cycles {
+        writer.push(cycle).unwrap();
+    }
+    writer.flush().unwrap();
+    let bytes = writer.get_ref().clone();
+    let decoded = CycleReader::new(&bytes[..]).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    (bytes.len(), decoded)
+}
+
+#[test]
+fn cycles_survive_the_encoding() {
+    let cycles = vec![
+        cycle(0x1234, 0x01, SystemAction::READ),
+        cycle(0x1235, 0x02, SystemAction::READ),
+        cycle(0x01FD, 0x03, SystemAction::WRITE),
+        cycle(0x01FC, 0x04, SystemAction::WRITE),
+        cycle(0x0180, 0x05, SystemAction::READ),
+        cycle(0xFFFF, 0x06, SystemAction::READ),
+        cycle(0x0000, 0x07, SystemAction::WRITE),
+    ];
+    let (length, decoded) = round_trip(&cycles);
+    assert_eq!(decoded, cycles);
+    // Header, an absolute address, the next, another absolute, two deltas, an
+    // absolute and the next one wrapping round.
+    assert_eq!(length, 4 + 4 + 2 + 4 + 3 + 3 + 4 + 2);
+}
+
+#[test]
+fn repeated_cycles_are_run_length_encoded() {
+    let polls = vec![cycle(0x2002, 0x80, SystemAction::READ); 100];
+    let (length, decoded) = round_trip(&polls);
+    assert_eq!(decoded, polls);
+    // Runs of 32, 32, 32 and 4.
+    assert_eq!(length, 4 + 4 + 2 + 2 + 2);
+}
+
+#[test]
+fn rejects_other_files() {
+    assert!(matches!(CycleReader::new(&b"R6M\x01"[..]), Err(R6502Error::BadCycleLog(_))));
+    let mut truncated = CycleWriter::new(vec![]).unwrap();
+    truncated.push(&cycle(0x1234, 0x01, SystemAction::READ)).unwrap();
+    truncated.flush().unwrap();
+    let bytes = truncated.get_ref()[..6].to_vec();
+    assert!(matches!(CycleReader::new(&bytes[..]).unwrap().next(), Some(Err(R6502Error::BadCycleLog(_)))));
+}
+
+#[test]
+fn streams_the_emulator_cycle_log_to_a_file() {
+    let mut emulator = common::assembled("ldx #$ff\nloop: txa\nsta $0400,x\ndex\nbne loop\nkil\n", 0x0200).build().unwrap();
+    let path = std::env::temp_dir().join(format!("r6502-cycles-{}.r6c", std::process::id()));
+    emulator.stream_cycles(BufWriter::new(std::fs::File::create(&path).unwrap())).unwrap();
+    emulator.run();
+    assert!(emulator.state.cycles.len() < 4);
+    let streamed = emulator.stop_streaming_cycles().unwrap();
+
+    let cycles = CycleReader::open(&path).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(cycles.len() as u64, streamed);
+    let writes: Vec<&SystemCycle> = cycles.writes().collect();
+    assert_eq!(writes.len(), 255);
+    assert_eq!((writes[0].address, writes[0].value), (0x04FF, 0xFF));
+    assert_eq!((writes[254].address, writes[254].value), (0x0401, 0x01));
+}
+
+#[test]
+fn queries_pick_cycles_by_address_action_and_position() {
+    let cycles = [
+        cycle(0x2002, 0x00, SystemAction::READ),
+        cycle(0x2006, 0x20, SystemAction::WRITE),
+        cycle(0x2002, 0x80, SystemAction::READ),
+        cycle(0x2007, 0x11, SystemAction::WRITE),
+        cycle(0x0010, 0x05, SystemAction::READ),
+        cycle(0x2002, 0x00, SystemAction::WRITE),
+    ];
+    let values = |found: Vec<&SystemCycle>| found.iter().map(|cycle| cycle.value).collect::<Vec<_>>();
+    assert_eq!(values(cycles.reads_of(0x2002).collect()), [0x00, 0x80]);
+    assert_eq!(values(cycles.writes_to(0x2002).collect()), [0x00]);
+    assert_eq!(cycles.accesses_to(0x2002).count(), 3);
+    assert_eq!(values(cycles.within(0x2006..=0x2007).collect()), [0x20, 0x11]);
+    assert_eq!(cycles.reads().count(), 3);
+    assert_eq!(values(cycles.between(1..4).reads_of(0x2002).collect()), [0x80]);
+    assert_eq!(cycles.between(4..100).len(), 2);
+    assert!(cycles.between(10..20).is_empty());
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use r6502::cpu::Cpu6502;
+use r6502::emulator::DefaultVirtualMemory;
+
+// The IRQ handler at $0310 counts in $12.
+fn core(origin: u16, program: &[u8]) -> impl Cpu6502 {
+    let memory = DefaultVirtualMemory::default()
+        .with_image(origin, program)
+        .with_image(0x0010, &[0xF0, 0x02])
+        .with_image(0x0310, &[0xE6, 0x12, 0x40]) // INC $12; RTI
+        .with_image(0xFFFE, &[0x10, 0x03]);
+    common::running(memory, origin).build().unwrap()
+}
+
+fn cycles(origin: u16, program: &[u8], steps: usize) -> u64 {
+    let mut cpu = core(origin, program);
+    for _ in 0..steps {
+        assert!(cpu.step());
+    }
+    cpu.clock()
+}
+
+#[test]
+fn taken_branches_cost_a_cycle_and_another_across_a_page() {
+    assert_eq!(cycles(0x0200, &[0x18, 0xB0, 0x00], 2), 2 + 2); // CLC; BCS (not taken)
+    assert_eq!(cycles(0x0200, &[0x18, 0x90, 0x00], 2), 2 + 3); // CLC; BCC +0
+    assert_eq!(cycles(0x02FC, &[0x18, 0x90, 0x01], 2), 2 + 4); // CLC; BCC to $0300
+    assert_eq!(cycles(0x0300, &[0x18, 0x90, 0xFB], 2), 2 + 4); // CLC; BCC to $02FE
+}
+
+#[test]
+fn indexed_reads_pay_for_carrying_into_the_high_byte() {
+    assert_eq!(cycles(0x0200, &[0xA2, 0x0F, 0xBD, 0xF0, 0x02], 2), 2 + 4); // LDX #$0F; LDA $02F0,X
+    assert_eq!(cycles(0x0200, &[0xA2, 0x10, 0xBD, 0xF0, 0x02], 2), 2 + 5); // LDX #$10; LDA $02F0,X
+    assert_eq!(cycles(0x0200, &[0xA0, 0x10, 0xB9, 0xF0, 0x02], 2), 2 + 5); // LDY #$10; LDA $02F0,Y
+    assert_eq!(cycles(0x0200, &[0xA0, 0x0F, 0xB1, 0x10], 2), 2 + 5); // LDY #$0F; LDA ($10),Y
+    assert_eq!(cycles(0x0200, &[0xA0, 0x10, 0xB1, 0x10], 2), 2 + 6); // LDY #$10; LDA ($10),Y
+
+    // Stores always take the long path.
+    assert_eq!(cycles(0x0200, &[0xA2, 0x0F, 0x9D, 0xF0, 0x02], 2), 2 + 5); // LDX #$0F; STA $02F0,X
+    assert_eq!(cycles(0x0200, &[0xA2, 0x10, 0x9D, 0xF0, 0x02], 2), 2 + 5); // LDX #$10; STA $02F0,X
+}
+
+#[test]
+fn a_taken_branch_in_its_page_delays_an_irq_by_one_instruction() {
+    // CLI; CLC; BCC +0; NOP; NOP
+    let mut cpu = core(0x0200, &[0x58, 0x18, 0x90, 0x00, 0xEA, 0xEA]);
+    for _ in 0..3 {
+        assert!(cpu.step());
+    }
+    cpu.set_irq(true);
+    assert!(cpu.step());
+    assert_eq!(cpu.peek(0x12), 0);
+    assert_eq!(cpu.registers().pc, 0x0205);
+    assert!(cpu.step());
+    assert_eq!(cpu.peek(0x12), 1);
+
+    // Without the branch being taken the IRQ comes straight away.
+    // CLI; SEC; BCC +0; NOP
+    let mut cpu = core(0x0200, &[0x58, 0x38, 0x90, 0x00, 0xEA]);
+    for _ in 0..3 {
+        assert!(cpu.step());
+    }
+    cpu.set_irq(true);
+    assert!(cpu.step());
+    assert_eq!(cpu.peek(0x12), 1);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use r6502::determinism::Audit;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::error::{R6502Error, Result};
+
+// Stores a rising X to $10-$FF over and over.
+const PROGRAM: [u8; 7] = [
+    0x8A, 0x95, 0x10, // loop: TXA; STA $10,X
+    0xE8, // INX
+    0x4C, 0x00, 0x02, // JMP loop
+];
+
+fn emulator() -> Result<CPUEmulator<DefaultVirtualMemory>> {
+    emulator_with(&PROGRAM)
+}
+
+fn emulator_with(program: &[u8]) -> Result<CPUEmulator<DefaultVirtualMemory>> {
+    Ok(common::program(program, 0x0200).build()?)
+}
+
+#[test]
+fn a_deterministic_workload_passes_every_checkpoint() {
+    let checkpoints = Audit::new(100, 10).run(emulator).unwrap();
+    assert_eq!(checkpoints.len(), 10);
+    assert!(checkpoints.windows(2).all(|pair| pair[0].clock < pair[1].clock && pair[0].hash != pair[1].hash));
+}
+
+#[test]
+fn stops_early_when_the_workload_halts() {
+    // One pass of the loop, then KIL.
+    let halting = || emulator_with(&[0x8A, 0x95, 0x10, 0xE8, 0x02]);
+    let checkpoints = Audit::new(100, 10).run(halting).unwrap();
+    assert_eq!(checkpoints.len(), 1);
+}
+
+#[test]
+fn catches_a_workload_seeded_from_outside() {
+    // Each build gets its own seed, which TXA adds once X passes $80.
+    let mut seed = 0u8;
+    let seeded = || {
+        seed += 1;
+        let seed = seed;
+        let mut emulator = emulator()?;
+        emulator.override_opcode(0x8A, move |emulator| {
+            let x = emulator.state.x;
+            emulator.state.a = if x < 0x80 { x } else { x.wrapping_add(seed) };
+            Ok(())
+        });
+        Ok(emulator)
+    };
+    match Audit::new(100, 100).run(seeded) {
+        Err(R6502Error::Nondeterministic { clock, first, second }) => {
+            // Not before X reaches $80, a thousand or so cycles in.
+            assert!(clock > 1000);
+            assert_ne!(first, second);
+        }
+        other => panic!("expected the runs to diverge, got {:?}", other.map(|checkpoints| checkpoints.len())),
+    }
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use r6502::cache::DecodeCache;
+use r6502::differential::{DifferentialOutcome, DifferentialRunner};
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+
+// Writes 5, 4, .. 1 to $10-$14 and halts.
+const PROGRAM: [u8; 10] = [
+    0xA2, 0x05, // LDX #5
+    0x8A, 0x95, 0x0F, // loop: TXA; STA $0F,X
+    0xCA, 0xD0, 0xFA, // DEX; BNE loop
+    0x02, 0x00, // KIL
+];
+
+fn emulator() -> CPUEmulator<DefaultVirtualMemory> {
+    common::program(&PROGRAM, 0x0200).build().unwrap()
+}
+
+#[test]
+fn identical_processors_agree_until_they_halt() {
+    let mut cached = emulator();
+    cached.decode_cache = Some(DecodeCache::default());
+    let mut runner = DifferentialRunner::new(emulator(), cached);
+    assert_eq!(runner.run(1000), DifferentialOutcome::Stopped { steps: 22 });
+    assert_eq!(runner.reference.peek(0x14), 5);
+}
+
+#[test]
+fn stops_at_the_first_instruction_that_differs() {
+    // A candidate whose TXA stores A plus one.
+    let mut candidate = emulator();
+    candidate.override_opcode(0x8A, |emulator| {
+        emulator.state.a = emulator.state.x.wrapping_add(1);
+        Ok(())
+    });
+    let mut runner = DifferentialRunner::new(emulator(), candidate);
+    let DifferentialOutcome::Diverged(divergence) = runner.run(1000) else {
+        panic!("expected a divergence");
+    };
+    assert_eq!((divergence.step, divergence.pc), (1, 0x0202));
+    assert_eq!((divergence.registers.0.a, divergence.registers.1.a), (5, 6));
+    assert!(divergence.to_string().contains("a: 05 -> 06"));
+}
@@ -0,0 +1,12 @@
+use r6502::error::R6502Error;
+use r6502::loaders::{self, INesImage};
+
+#[test]
+fn loader_errors_can_be_matched() {
+    assert!(matches!(INesImage::parse(b"not a cartridge"), Err(R6502Error::NotINes)));
+    assert!(matches!(
+        loaders::binary(&[0; 0x20], 0xFFF0),
+        Err(R6502Error::ImageTooLarge { length: 0x20, origin: 0xFFF0 })
+    ));
+    assert!(matches!(loaders::prg(&[0x01]), Err(R6502Error::MissingLoadAddress)));
+}
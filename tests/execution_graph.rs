@@ -0,0 +1,70 @@
+mod common;
+
+use r6502::analysis::execution::{ExecutionGraph, Transfer, TransferKind};
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::instructions::Instruction;
+use r6502::symbols::SymbolTable;
+
+const PROGRAM: [u8; 15] = [
+    0xA2, 0x02, // LDX #2
+    0x20, 0x0A, 0x02, // loop: JSR sub
+    0xCA, // DEX
+    0xD0, 0xFA, // BNE loop
+    0x02, // KIL
+    0xEA, // padding
+    0x20, 0x0E, 0x02, // sub: JSR leaf
+    0x60, // RTS
+    0x60, // leaf: RTS
+];
+
+fn emulator() -> CPUEmulator<DefaultVirtualMemory> {
+    common::program(&PROGRAM, 0x0200)
+        .execution_graph(Some(ExecutionGraph::default()))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn records_each_taken_transfer_with_its_count() {
+    let mut emulator = emulator();
+    assert_eq!(emulator.run(), StopReason::Halted);
+    let graph = emulator.execution_graph.as_ref().unwrap();
+
+    let mut transfers: Vec<(Transfer, u64)> = graph.transfers().map(|(transfer, count)| (*transfer, count)).collect();
+    transfers.sort_by_key(|(transfer, _)| (transfer.from, transfer.to));
+    let transfer = |from, to, kind| Transfer { from, to, kind };
+    assert_eq!(
+        transfers,
+        [
+            (transfer(0x0202, 0x020A, TransferKind::Call), 2),
+            (transfer(0x0206, 0x0202, TransferKind::Branch), 1),
+            (transfer(0x020A, 0x020E, TransferKind::Call), 2),
+            (transfer(0x020D, 0x0205, TransferKind::Return), 2),
+            (transfer(0x020E, 0x020D, TransferKind::Return), 2),
+        ]
+    );
+
+    let mut calls: Vec<_> = graph.calls().collect();
+    calls.sort();
+    assert_eq!(calls, [(None, 0x020A, 2), (Some(0x020A), 0x020E, 2)]);
+}
+
+#[test]
+fn renders_the_call_graph_with_symbols() {
+    let mut emulator = emulator();
+    emulator.run();
+    let mut symbols = SymbolTable::default();
+    symbols.insert("sub", 0x020A);
+    assert_eq!(
+        emulator.execution_graph.as_ref().unwrap().call_graph_dot(Some(&symbols)),
+        "digraph calls {\n    node [shape=box fontname=\"monospace\"];\n    \
+         \"entry\" -> \"sub\" [label=\"2\"];\n    \"sub\" -> \"$020E\" [label=\"2\"];\n}\n"
+    );
+}
+
+#[test]
+fn branches_not_taken_are_not_transfers() {
+    let bne = Instruction::from(0xD0);
+    assert_eq!(TransferKind::classify(&bne, 0x0206, 0x0208), None);
+    assert_eq!(TransferKind::classify(&bne, 0x0206, 0x0202), Some(TransferKind::Branch));
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason, EXIT_PORT};
+
+fn emulator(source: &str, exit_port: Option<u16>) -> CPUEmulator<DefaultVirtualMemory> {
+    common::assembled(source, 0x0200)
+        .exit_port(exit_port)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn a_write_to_the_exit_port_stops_the_run_with_its_value() {
+    let mut emulator = emulator("lda #3\nsta $fff0\nlda #9\n.byte $02", Some(EXIT_PORT));
+    assert_eq!(emulator.run(), StopReason::Exit(3));
+    assert_eq!(emulator.exit_code(), Some(3));
+    assert_eq!(emulator.state.a, 3);
+    assert_eq!(emulator.run(), StopReason::Exit(3));
+
+    emulator.reset();
+    assert_eq!(emulator.exit_code(), None);
+}
+
+#[test]
+fn the_exit_port_is_plain_memory_unless_enabled() {
+    let mut emulator = emulator("lda #3\nsta $fff0\nlda #9\n.byte $02", None);
+    assert_eq!(emulator.run(), StopReason::Halted);
+    assert_eq!(emulator.state.a, 9);
+    assert_eq!(emulator.peek(EXIT_PORT), 3);
+}
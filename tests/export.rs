@@ -0,0 +1,40 @@
+use r6502::analysis::cfg;
+use r6502::analysis::coverage::ExecutedBytes;
+use r6502::export::{self, Syntax};
+use r6502::symbols::SymbolTable;
+
+const ORIGIN: u16 = 0x0200;
+
+const IMAGE: [u8; 12] = [
+    0xAD, 0x10, 0x00, // LDA $0010, absolute despite the small address
+    0x8D, 0x00, 0xD0, // STA $D000
+    0x4C, 0x0B, 0x02, // JMP $020B
+    0x01, 0x02, // data
+    0x60, // RTS
+];
+
+fn project(syntax: Syntax) -> String {
+    let code = ExecutedBytes::from(&cfg::build(&IMAGE, ORIGIN, &[ORIGIN]));
+    let mut symbols = SymbolTable::default();
+    symbols.insert("start", 0x0200);
+    symbols.insert("PORT", 0xD000);
+    export::project(&IMAGE, ORIGIN, &code, Some(&symbols), syntax)
+}
+
+#[test]
+fn ca65_source_has_labels_equates_and_data() {
+    assert_eq!(
+        project(Syntax::Ca65),
+        "; Generated by r6502\n\n.setcpu \"6502\"\n\nPORT = $D000\n\n.org $0200\n\n\
+         start:\n    LDA a:$0010\n    STA PORT\n    JMP L020B\n    .byte $01, $02\nL020B:\n    RTS\n"
+    );
+}
+
+#[test]
+fn dasm_source_spells_labels_and_overrides_its_own_way() {
+    assert_eq!(
+        project(Syntax::Dasm),
+        "; Generated by r6502\n\n    processor 6502\n\nPORT = $D000\n\n    org $0200\n\n\
+         start\n    LDA.w $0010\n    STA PORT\n    JMP L020B\n    .byte $01, $02\nL020B\n    RTS\n"
+    );
+}
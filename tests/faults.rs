@@ -0,0 +1,77 @@
+mod common;
+
+use r6502::assembler::assemble;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::faults::{Fault, FaultPlan, FaultRule, FaultyMemory, InjectedFault};
+use r6502::state::SystemAction;
+
+// Copies a page zero table to another, so there are plenty of accesses to fault.
+const PROGRAM: &str = "
+        ldx #0
+copy:   lda $20,x
+        sta $40,x
+        inx
+        cpx #$10
+        bne copy
+        lda $30
+        sta $10
+        kil
+";
+
+fn emulator(plan: FaultPlan) -> CPUEmulator<FaultyMemory<DefaultVirtualMemory>> {
+    let program = assemble(PROGRAM, 0x0200).unwrap();
+    let table: Vec<u8> = (0x80..0xA0).collect();
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &program.image).with_image(0x20, &table);
+    common::running(FaultyMemory::new(memory, plan), 0x0200).build().unwrap()
+}
+
+fn run(plan: FaultPlan) -> (Vec<InjectedFault>, Vec<u8>) {
+    let mut emulator = emulator(plan);
+    emulator.run();
+    emulator.with_memory(|memory| (memory.injected().to_vec(), memory.inner().bytes()[0x40..0x50].to_vec()))
+}
+
+#[test]
+fn faults_follow_the_seed() {
+    let plan = |seed| FaultPlan::new(seed).with(FaultRule::new(Fault::WrongData, 0x40, 0x4F, 30.0).writes_only());
+    let (faults, copy) = run(plan(1));
+    assert!(!faults.is_empty() && faults.len() < 16, "{:?}", faults);
+    assert!(faults.iter().all(|fault| fault.action == SystemAction::WRITE && (0x40..=0x4F).contains(&fault.address)));
+    for fault in faults.iter() {
+        assert_eq!(Some(copy[fault.address as usize - 0x40]), fault.actual);
+    }
+    assert_eq!(run(plan(1)), (faults.clone(), copy));
+    assert_ne!(run(plan(2)).0, faults);
+}
+
+#[test]
+fn flips_one_bit() {
+    let (faults, copy) = run(FaultPlan::new(5).with(FaultRule::new(Fault::FlipBit, 0x45, 0x45, 100.0).writes_only()));
+    assert_eq!(faults.len(), 1);
+    assert_eq!((faults[0].expected ^ copy[5]).count_ones(), 1);
+    assert_eq!(copy[4], 0x84);
+}
+
+#[test]
+fn failed_accesses_see_an_open_bus_or_are_dropped() {
+    let mut emulator = emulator(
+        FaultPlan::new(0)
+            .with(FaultRule::new(Fault::Fail, 0x30, 0x30, 100.0).reads_only())
+            .with(FaultRule::new(Fault::Fail, 0x41, 0x41, 100.0)),
+    );
+    emulator.run();
+    emulator.with_memory(|memory| {
+        assert_eq!(memory.inner().bytes()[0x10], 0xFF);
+        assert_eq!(memory.inner().bytes()[0x41], 0x00);
+        assert_eq!(memory.injected()[0].actual, None);
+        memory.set_enabled(false);
+    });
+    assert_eq!(emulator.peek(0x30), 0x90);
+}
+
+#[test]
+fn an_empty_plan_changes_nothing() {
+    let (faults, copy) = run(FaultPlan::new(9).with(FaultRule::new(Fault::FlipBit, 0x0000, 0xFFFF, 0.0)));
+    assert!(faults.is_empty());
+    assert_eq!(copy, (0x80..0x90).collect::<Vec<u8>>());
+}
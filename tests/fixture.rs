@@ -0,0 +1,39 @@
+use r6502::fixture::{Expectations, Fixture};
+use r6502::run_fixture;
+
+#[test]
+fn fixture_macro_checks_registers_and_memory() {
+    let run = run_fixture! {
+        source: "
+            ldx #3
+    loop:   dex
+            txa
+            sta $10,x
+            bne loop
+            brk
+        ",
+        origin: 0x0200,
+        registers: { x: 0, pc: 0x0209 },
+        memory: { 0x0010 => [0, 1, 2] },
+    };
+    assert!(run.emulator.clock() > 0);
+
+    run_fixture! {
+        binary: &[0xA9, 0x2A, 0x02],
+        origin: 0x0400,
+        registers: { a: 0x2A },
+    };
+}
+
+#[test]
+fn failures_report_every_difference() {
+    let run = Fixture::source("lda #1\nsta $20\nbrk", 0x0200).unwrap().run().unwrap();
+    let expected = Expectations::default().register("a", 2).memory(0x0020, &[1, 5]);
+    let report = run.check(&expected).unwrap_err().to_string();
+    assert!(report.contains("a: expected 02, got 01"), "{}", report);
+    assert!(report.contains("expected 01 05"), "{}", report);
+    assert!(report.contains("got      01 00"), "{}", report);
+
+    let endless = Fixture::source("loop: jmp loop", 0x0200).unwrap().max_cycles(100).run().unwrap();
+    assert!(endless.check(&Expectations::default()).unwrap_err().to_string().contains("did not halt"));
+}
@@ -0,0 +1,22 @@
+use r6502::state::SystemFlags;
+
+#[test]
+fn stack_copy_of_the_status_register() {
+    let flags = SystemFlags::carry | SystemFlags::negative;
+    assert_eq!(flags.to_push(true), 0b10110001);
+    assert_eq!(flags.to_push(false), 0b10100001);
+    assert_eq!(SystemFlags::from_push(0xFF), SystemFlags::all() - SystemFlags::break_command - SystemFlags::expansion);
+
+    let mut pulled = SystemFlags::expansion;
+    pulled.pull(0b00010011);
+    assert_eq!(pulled, SystemFlags::expansion | SystemFlags::zero | SystemFlags::carry);
+}
+
+#[test]
+fn nz_from_result() {
+    let mut flags = SystemFlags::empty().with_carry(true);
+    flags.set_nz(0x80);
+    assert_eq!(flags, SystemFlags::carry | SystemFlags::negative);
+    flags.set_nz(0);
+    assert_eq!(flags, SystemFlags::carry | SystemFlags::zero);
+}
@@ -0,0 +1,60 @@
+use r6502::grader::{Condition, Grader, TestCase};
+
+// Adds A and X into sum, with a bug: the carry isn't cleared first.
+const STUDENT: &str = "
+add:    stx sum
+        adc sum
+        sta sum
+        rts
+sum:    .byte 0
+";
+
+const SPEC: &str = "
+# sum = a + x
+case small numbers
+  call add
+  given a=03 x=04 c=0
+  expect sum: 07
+  expect cycles<=30
+
+case carry left set
+  call add
+  given a=03 x=04 c=1
+  expect a=07
+  expect sum: 07
+";
+
+#[test]
+fn specs_report_each_assertion() {
+    let grader = Grader::source(STUDENT, 0x0200).unwrap();
+    let report = grader.grade_spec(SPEC).unwrap();
+    assert_eq!(report.cases.len(), 2);
+    assert!(report.cases[0].passed(), "{}", report);
+    assert!(!report.cases[1].passed());
+    assert!(!report.all_passed());
+
+    let text = report.to_string();
+    assert!(text.contains("PASS small numbers"), "{}", text);
+    assert!(text.contains("ok   cycles<=30"), "{}", text);
+    assert!(text.contains("FAIL carry left set"), "{}", text);
+    assert!(text.contains("FAIL a=07, got a=08"), "{}", text);
+    assert!(text.contains("FAIL 020A: 07, got 08"), "{}", text);
+    assert!(text.ends_with("1/2 cases passed"), "{}", text);
+}
+
+#[test]
+fn routines_that_never_return_fail() {
+    let grader = Grader::binary(&[0x4C, 0x00, 0x02], 0x0200).unwrap().max_cycles(1000); // JMP $0200
+    let report = grader.grade(&[TestCase::new("forever", 0x0200).expect(Condition::Register("a", 0))]);
+    assert!(!report.cases[0].returned);
+    assert_eq!(report.cases[0].stop, "Timeout");
+    assert!(report.to_string().contains("did not return: Timeout"));
+}
+
+#[test]
+fn bad_specs_name_the_line() {
+    let grader = Grader::source(STUDENT, 0x0200).unwrap();
+    let error = grader.parse_spec("case one\n  call add\n  expect q=1").unwrap_err();
+    assert_eq!(error.to_string(), "Spec line 3: unknown register or flag q");
+    assert!(grader.parse_spec("expect a=1").is_err());
+}
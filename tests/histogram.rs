@@ -0,0 +1,43 @@
+use r6502::analysis::histogram::{self, entropy, Histogram};
+use r6502::instructions::{AddressingMode, OpCode};
+
+const ORIGINAL: [u8; 5] = [
+    0xA9, 0x01, // LDA #1
+    0x85, 0x10, // STA $10
+    0x60, // RTS
+];
+
+// The store patched out with two NOPs.
+const PATCHED: [u8; 5] = [0xA9, 0x01, 0xEA, 0xEA, 0x60];
+
+#[test]
+fn diff_lists_the_changed_opcodes_and_modes() {
+    let diff = histogram::diff(&Histogram::from_image(&ORIGINAL), &Histogram::from_image(&PATCHED));
+    assert_eq!(diff.opcodes, vec![(OpCode::NOP, 0, 2), (OpCode::STA, 1, 0)]);
+    // NOP decodes without an addressing mode.
+    assert_eq!(diff.modes, vec![(None, 0, 2), (Some(AddressingMode::DirectZeroPage), 1, 0)]);
+    assert!(diff.entropy.0 > diff.entropy.1);
+    assert_eq!(
+        diff.to_string().lines().skip(1).collect::<Vec<_>>(),
+        [
+            "NOP         0 -> 2      (+2)",
+            "STA         1 -> 0      (-1)",
+            "none        0 -> 2      (+2)",
+            "zp          1 -> 0      (-1)",
+        ]
+    );
+}
+
+#[test]
+fn identical_images_have_an_empty_diff() {
+    let histogram = Histogram::from_image(&ORIGINAL);
+    assert!(histogram::diff(&histogram, &histogram).is_empty());
+}
+
+#[test]
+fn entropy_spans_constant_to_uniform() {
+    let uniform: Vec<u8> = (0..=255).collect();
+    assert_eq!(entropy(&[0; 64]), 0.0);
+    assert_eq!(entropy(&uniform), 8.0);
+    assert_eq!(entropy(&[]), 0.0);
+}
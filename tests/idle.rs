@@ -0,0 +1,52 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::idle::{Idle, IdleWatch};
+
+fn emulator(source: &str) -> CPUEmulator<DefaultVirtualMemory> {
+    common::assembled(source, 0x0200).build().unwrap()
+}
+
+fn watch(emulator: &mut CPUEmulator<DefaultVirtualMemory>, watch: IdleWatch) -> Arc<Mutex<Vec<Idle>>> {
+    let seen = Arc::new(Mutex::new(vec![]));
+    let recorded = seen.clone();
+    emulator.on_idle(watch, move |_, idle| recorded.lock().unwrap().push(*idle));
+    seen
+}
+
+#[test]
+fn fires_for_a_polling_loop() {
+    // A vblank wait: read a register until bit 7 comes up, which it never does.
+    let mut emulator = emulator("wait: lda $2002\nbpl wait\n");
+    let seen = watch(&mut emulator, IdleWatch::new(8, 1000));
+    emulator.run_for_cycles(3500);
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 3);
+    assert_eq!((seen[0].start, seen[0].end), (0x0200, 0x0203));
+    assert!(seen[0].cycles > 1000 && seen[0].cycles <= 1007, "{:?}", seen[0]);
+    assert!(seen[2].cycles > 3000, "{:?}", seen[2]);
+}
+
+#[test]
+fn writes_and_wide_loops_are_not_idle() {
+    let mut busy = emulator("loop: inc $10\njmp loop\n");
+    let seen = watch(&mut busy, IdleWatch::new(8, 100));
+    busy.run_for_cycles(2000);
+    assert!(seen.lock().unwrap().is_empty());
+
+    let mut wide = emulator("loop: lda $2002\nnop\nnop\nnop\nnop\nnop\nnop\njmp loop\n");
+    let seen = watch(&mut wide, IdleWatch::new(8, 100));
+    wide.run_for_cycles(2000);
+    assert!(seen.lock().unwrap().is_empty());
+}
+
+#[test]
+fn the_handler_can_stop_the_spin() {
+    let mut emulator = emulator("wait: lda $10\nbeq wait\nkil\n");
+    emulator.on_idle(IdleWatch::new(4, 50), |emulator, _| emulator.poke(0x10, 1));
+    emulator.run_for_cycles(1000);
+    assert!(!emulator.state.running);
+    assert!(emulator.clear_on_idle());
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use r6502::devices::joypad::{Joypads, JOY1};
+use r6502::devices::riot::Riot;
+use r6502::devices::tia::Tia;
+use r6502::devices::Bus;
+use r6502::emulator::{DefaultVirtualMemory, StopReason, VirtualMemory};
+use r6502::input::{Buttons, InputScript, ScriptPlayer};
+
+const SCRIPT: &str = "\
+# frame  player 1   player 2
+0        .
+1        right a   | up
+3        .
+";
+
+#[test]
+fn scripts_round_trip_and_hold_input_until_the_next_change() {
+    let script: InputScript = SCRIPT.parse().unwrap();
+    assert_eq!(script.at(2), [Buttons::RIGHT | Buttons::A, Buttons::UP]);
+    assert_eq!(script.at(300), [Buttons::empty(); 2]);
+    assert_eq!(script.to_string().parse::<InputScript>().unwrap(), script);
+    assert_eq!("fire+left".parse::<Buttons>().unwrap(), Buttons::A | Buttons::LEFT);
+    assert!(InputScript::parse("10 jump").is_err());
+    assert!(InputScript::parse("ten a").is_err());
+}
+
+#[test]
+fn playback_drives_the_2600_controllers_frame_by_frame() {
+    // Each frame starts vertical sync, then records SWCHA and INPT4 at $0400+n and $0500+n.
+    let program = [
+        0xA9, 0x00, 0x85, 0x00, // LDA #0; STA VSYNC
+        0xA9, 0x02, 0x85, 0x00, // LDA #2; STA VSYNC
+        0xE8, // INX
+        0xAD, 0x80, 0x02, 0x9D, 0x00, 0x04, // LDA SWCHA; STA $0400,X
+        0xA5, 0x0C, 0x9D, 0x00, 0x05, // LDA INPT4; STA $0500,X
+        0x4C, 0x00, 0x10, // JMP $1000
+    ];
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x1000, &program))
+        .map(0x0000, 0x003F, Tia::new())
+        .map(0x0280, 0x029F, Riot::new());
+    let mut emulator = common::running(bus, 0x1000).build().unwrap();
+
+    let mut player = ScriptPlayer::new(SCRIPT.parse().unwrap());
+    assert_eq!(player.play(&mut emulator).unwrap(), StopReason::Stepped);
+    assert_eq!(player.frame(), 4);
+    // Player 1 right (bit 7) and player 2 up (bit 0) read low.
+    assert_eq!([emulator.peek(0x0401), emulator.peek(0x0402), emulator.peek(0x0403)], [0x7E, 0x7E, 0xFF]);
+    assert_eq!([emulator.peek(0x0501), emulator.peek(0x0502), emulator.peek(0x0503)], [0x00, 0x00, 0x80]);
+}
+
+#[test]
+fn nes_joypads_shift_out_buttons_in_report_order() {
+    let mut joypads = Joypads::new();
+    joypads.set_buttons(0, (Buttons::A | Buttons::START | Buttons::RIGHT).bits());
+    joypads.write(JOY1, 1);
+    joypads.write(JOY1, 0);
+    let bits: Vec<u8> = (0..9).map(|_| joypads.read(JOY1) & 1).collect();
+    assert_eq!(bits, [1, 0, 0, 1, 0, 0, 0, 1, 1]);
+    assert_eq!(joypads.read(JOY1 + 1) & 1, 0);
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use r6502::emulator::StopReason;
+
+#[test]
+fn frontends_poll_a_running_emulator_without_locking_it() {
+    let mut emulator = common::assembled("loop: inx\nbne loop\niny\njmp loop", 0x0200).build().unwrap();
+    let handle = emulator.inspect_handle();
+    assert_eq!(handle.registers().pc, 0x0200);
+    assert!(handle.inspect().raster.is_none());
+
+    let thread = std::thread::spawn(move || {
+        let reason = emulator.run_for_cycles(200_000);
+        (reason, emulator.state.registers(), emulator.clock())
+    });
+    let mut last = 0;
+    while handle.is_attached() {
+        let inspection = handle.inspect();
+        assert!(inspection.clock >= last);
+        assert!((0x0200..0x0207).contains(&inspection.registers.pc));
+        last = inspection.clock;
+    }
+    let (reason, registers, clock) = thread.join().unwrap();
+    assert_eq!(reason, StopReason::Timeout);
+    assert_eq!(handle.registers(), registers);
+    assert_eq!(handle.clock(), clock);
+    assert!(handle.inspect().running);
+}
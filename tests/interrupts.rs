@@ -0,0 +1,72 @@
+mod common;
+
+use r6502::analysis::interrupts::{InterruptKind, InterruptStats};
+use r6502::assembler::assemble;
+use r6502::emulator::{DefaultVirtualMemory, StopReason};
+use r6502::monitor::Monitor;
+use r6502::state::{SystemFlags, SystemState};
+
+// Keeps interrupts masked for a few instructions, then lets an IRQ in and
+// finishes with a BRK. Both go through the same handler.
+const PROGRAM: &str = "
+main:   sei
+        nop
+        nop
+        cli
+        nop
+        brk
+        .byte 0
+done:   jmp done
+irq:    inc $10
+irq_end: rti
+nmi:    inc $11
+        rti
+";
+
+#[test]
+fn counts_nested_handlers_and_latency() {
+    let program = assemble(PROGRAM, 0x8000).unwrap();
+    let symbol = |name: &str| program.symbols.lookup(name).unwrap();
+    let vectors = [symbol("nmi").to_le_bytes(), symbol("main").to_le_bytes(), symbol("irq").to_le_bytes()].concat();
+    let memory = DefaultVirtualMemory::default().with_image(0x8000, &program.image).with_image(0xFFFA, &vectors);
+    let mut emulator = common::builder(memory, SystemState { pc: 0x8000, running: true, p: SystemFlags::interrupt_disable, ..Default::default() })
+        .interrupt_stats(Some(InterruptStats::new()))
+        .build()
+        .unwrap();
+
+    // The IRQ is held from the start but waits for CLI; an NMI then lands in its handler.
+    emulator.set_irq_line(true);
+    emulator.add_breakpoint(symbol("irq_end"));
+    assert_eq!(emulator.run(), StopReason::Breakpoint(symbol("irq_end")));
+    emulator.set_irq_line(false);
+    emulator.nmi();
+    emulator.remove_breakpoint(symbol("irq_end"));
+    emulator.add_breakpoint(symbol("done"));
+    assert_eq!(emulator.run(), StopReason::Breakpoint(symbol("done")));
+
+    let stats = emulator.interrupt_stats.as_ref().unwrap();
+    let (irq, nmi, brk) = (stats.get(InterruptKind::Irq), stats.get(InterruptKind::Nmi), stats.get(InterruptKind::Brk));
+    assert_eq!((irq.entries, irq.exits, irq.nested), (1, 1, 0));
+    assert_eq!((nmi.entries, nmi.exits, nmi.nested), (1, 1, 1));
+    assert_eq!((brk.entries, brk.exits, brk.max_latency), (1, 1, 0));
+    assert_eq!((stats.max_depth(), stats.depth()), (2, 0));
+    // SEI, two NOPs and CLI at two cycles each.
+    assert_eq!(irq.max_latency, 8);
+    // Entry, INC, RTI, around the nested NMI's own 7 + 5 + 6.
+    assert_eq!((nmi.cycles, irq.cycles), (18, 36));
+    assert_eq!(brk.max_cycles, 18);
+    assert!(stats.report().contains("NMI          1        1        1         18"), "{}", stats.report());
+}
+
+#[test]
+fn monitor_starts_collecting_then_reports() {
+    let memory = DefaultVirtualMemory::default();
+    let mut emulator = common::stopped(memory).build().unwrap();
+    let mut monitor = Monitor::new();
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "stats", &mut out).unwrap();
+    assert!(emulator.interrupt_stats.is_some());
+    monitor.execute(&mut emulator, "stats", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("collecting") && out.contains("max depth 0"), "{}", out);
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "jit")]
+
+mod common;
+
+use r6502::assembler::assemble;
+use r6502::devices::timer::Timer;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::jit::Jit;
+use r6502::state::Registers;
+use r6502::Bus;
+
+// Run with `cargo test --features jit --test jit`. Each program runs once on
+// the interpreter and once with every block compiled on its first visit, and
+// the two have to end up in the same place at the same cycle.
+
+fn emulator(source: &str) -> CPUEmulator<Bus> {
+    let assembly = assemble(source, 0x0200).unwrap();
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &assembly.image);
+    let bus = Bus::new(memory).map(0xD000, 0xD007, Timer::new());
+    common::running(bus, 0x0200).build().unwrap()
+}
+
+fn outcome(emulator: &CPUEmulator<Bus>) -> (Registers, u64, Vec<u8>) {
+    (emulator.state.registers(), emulator.clock(), (0..0x100).map(|address| emulator.peek(address)).collect())
+}
+
+// Runs `source` both ways with `run`, and returns the outcome and how many
+// instructions ran compiled.
+fn compare<F>(source: &str, mut run: F) -> ((Registers, u64, Vec<u8>), u64)
+where F: FnMut(&mut CPUEmulator<Bus>, &mut dyn FnMut(&mut CPUEmulator<Bus>) -> StopReason) {
+    let mut interpreted = emulator(source);
+    run(&mut interpreted, &mut |emulator| emulator.run());
+    let mut compiled = emulator(source);
+    let mut jit = Jit::new().threshold(1);
+    run(&mut compiled, &mut |emulator| jit.run(emulator));
+    assert_eq!(outcome(&compiled), outcome(&interpreted));
+    (outcome(&compiled), jit.stats().compiled_instructions)
+}
+
+#[test]
+fn hot_loops_give_the_interpreters_results() {
+    let source = "
+              ldx #0
+              ldy #$40
+        loop: txa
+              clc
+              inx
+              sta $10,x
+              dey
+              bne loop
+              kil
+    ";
+    let ((registers, _, memory), compiled) = compare(source, |emulator, run| assert_eq!(run(emulator), StopReason::Halted));
+    assert_eq!((registers.x, registers.y), (0x40, 0));
+    assert_eq!(memory[0x50], 0x3F);
+    assert!(compiled > 0x100);
+}
+
+#[test]
+fn code_a_block_modifies_runs_as_modified() {
+    // On the pass with X = 3 the store lands on the operand of the second
+    // LDA, so from then on it loads 9.
+    let source = "
+              ldx #4
+        loop: ldy table,x
+              lda #9
+              sta $0200,y
+        load: lda #0
+              sta $10,x
+              dex
+              bne loop
+              kil
+        table: .byte 0, $80, $80, <(load+1), $80
+    ";
+    let ((_, _, memory), _) = compare(source, |emulator, run| assert_eq!(run(emulator), StopReason::Halted));
+    assert_eq!(memory[0x11..=0x14], [9, 9, 9, 0]);
+}
+
+#[test]
+fn interrupts_are_taken_at_the_same_instruction() {
+    // A timer interrupts a loop of compiled instructions every 97 cycles, and
+    // the handler records X at the time. An NMI is also raised at the top of
+    // the loop on its fifth pass, stopped on a breakpoint there.
+    let source = "
+              lda #97
+              sta $d000
+              lda #0
+              sta $d001
+              lda #7
+              sta $d002
+              ldx #0
+              cli
+        loop: inx
+              inx
+              dex
+              inx
+              clc
+              sec
+              nop
+              dec $30
+              bne loop
+              sei
+              kil
+              .org $0300
+        irq:  ldy $20
+              txa
+              sta $40,y
+              inc $20
+              lda $d003
+              rti
+        nmi:  stx $21
+              rti
+              .org $fffa
+              .word nmi, $0200, irq
+    ";
+    let ((_, _, memory), compiled) = compare(source, |emulator, run| {
+        emulator.poke(0x30, 100);
+        emulator.add_breakpoint(0x0212);
+        for _ in 0..5 {
+            assert_eq!(run(emulator), StopReason::Breakpoint(0x0212));
+        }
+        emulator.remove_breakpoint(0x0212);
+        emulator.nmi();
+        assert_eq!(run(emulator), StopReason::Halted);
+    });
+    assert!(memory[0x20] > 10, "{} interrupts", memory[0x20]);
+    // The loop is entered by a taken branch, which delays the NMI past the first INX.
+    assert_eq!(memory[0x21], 9);
+    assert!(compiled > 0);
+}
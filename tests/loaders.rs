@@ -0,0 +1,57 @@
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::error::R6502Error;
+
+fn bytes(emulator: &CPUEmulator<DefaultVirtualMemory>, start: u16, length: u16) -> Vec<u8> {
+    (start..start + length).map(|address| emulator.peek(address)).collect()
+}
+
+#[test]
+fn constructors_load_each_format_from_disk() {
+    let directory = std::env::temp_dir().join(format!("r6502-loaders-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+
+    // A binary clear of the vectors starts at its origin.
+    std::fs::write(directory.join("program.bin"), [0xA9, 0x01, 0x02]).unwrap();
+    let emulator = CPUEmulator::from_binary(directory.join("program.bin"), 0xC000).unwrap();
+    assert_eq!(emulator.state.pc, 0xC000);
+    assert_eq!(bytes(&emulator, 0xC000, 3), [0xA9, 0x01, 0x02]);
+
+    // A 16K NROM cartridge is mirrored at $C000 and starts through the reset vector.
+    let mut ines = b"NES\x1A\x01\x00\x00\x00".to_vec();
+    ines.resize(16, 0);
+    let mut prg = vec![0xEA; 0x4000];
+    prg[0x3FFC..0x3FFE].copy_from_slice(&[0x10, 0xC0]);
+    ines.extend(prg);
+    std::fs::write(directory.join("game.nes"), ines).unwrap();
+    let emulator = CPUEmulator::from_ines(directory.join("game.nes")).unwrap();
+    assert_eq!(emulator.state.pc, 0xC010);
+    assert_eq!(bytes(&emulator, 0x8000, 2), [0xEA, 0xEA]);
+    assert_eq!(bytes(&emulator, 0xBFFC, 2), bytes(&emulator, 0xFFFC, 2));
+
+    // 10 SYS 2061, then the machine code.
+    let prg = [0x01, 0x08, 0x0B, 0x08, 0x0A, 0x00, 0x9E, b'2', b'0', b'6', b'1', 0x00, 0x00, 0x00, 0x02];
+    std::fs::write(directory.join("game.prg"), prg).unwrap();
+    let emulator = CPUEmulator::from_prg(directory.join("game.prg")).unwrap();
+    assert_eq!(emulator.state.pc, 2061);
+    assert_eq!(bytes(&emulator, 0x0801, 13), prg[2..]);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn constructors_report_bad_files() {
+    let directory = std::env::temp_dir().join(format!("r6502-loaders-errors-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("large.bin"), [0xEA; 0x20]).unwrap();
+    std::fs::write(directory.join("short.prg"), [0x01]).unwrap();
+
+    let error = CPUEmulator::from_binary(directory.join("large.bin"), 0xFFF0).err().unwrap();
+    assert!(matches!(error, R6502Error::ImageTooLarge { length: 0x20, origin: 0xFFF0 }), "{:?}", error);
+    let error = CPUEmulator::from_ines(directory.join("large.bin")).err().unwrap();
+    assert!(matches!(error, R6502Error::NotINes), "{:?}", error);
+    let error = CPUEmulator::from_prg(directory.join("short.prg")).err().unwrap();
+    assert!(matches!(error, R6502Error::MissingLoadAddress), "{:?}", error);
+    assert!(CPUEmulator::from_prg(directory.join("missing.prg")).is_err());
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,83 @@
+use r6502::devices::Device;
+use r6502::emulator::VirtualMemory;
+use r6502::machines::{self, config::{DeviceRegistry, MachineConfig}};
+
+#[test]
+fn builds_a_machine_from_toml() {
+    let directory = std::env::temp_dir().join(format!("r6502-config-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    // LDA #$42; STA $6080; KIL, with the reset vector pointing at it.
+    let mut rom = vec![0xEA; 0x100];
+    rom[..6].copy_from_slice(&[0xA9, 0x42, 0x8D, 0x80, 0x60, 0x02]);
+    rom[0xFC..].copy_from_slice(&[0x00, 0xFF, 0x00, 0xFF]);
+    std::fs::write(directory.join("rom.bin"), &rom).unwrap();
+    std::fs::write(
+        directory.join("machine.toml"),
+        r#"
+name = "breadboard"
+clock = 1_000_000
+
+[[region]]
+start = 0xFF00
+end = 0xFFFF
+kind = "rom"
+file = "rom.bin"
+
+[[device]]
+type = "riot"
+start = 0x6000
+end = 0x60FF
+"#,
+    )
+    .unwrap();
+
+    let config = machines::from_config(directory.join("machine.toml")).unwrap();
+    assert_eq!(config.clock, Some(1_000_000));
+    let mut emulator = config.build().unwrap();
+    assert_eq!(emulator.state.pc, 0xFF00);
+    emulator.run();
+    assert_eq!(emulator.peek(0x6080), 0x42);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn rejects_unknown_devices() {
+    let config = MachineConfig::parse("[[device]]\ntype = \"uart\"\nstart = 0x8000\nend = 0x8003\n").unwrap();
+    assert!(matches!(config.build(), Err(r6502::error::R6502Error::UnknownDevice(name)) if name == "uart"));
+}
+
+// A write-only port that remembers the last byte written to it.
+struct Latch {
+    value: u8,
+}
+
+impl VirtualMemory for Latch {
+    fn read(&mut self, _address: u16) -> u8 {
+        self.value
+    }
+
+    fn write(&mut self, _address: u16, value: u8) {
+        self.value = value;
+    }
+}
+
+impl Device for Latch {
+    fn name(&self) -> &'static str {
+        "latch"
+    }
+}
+
+#[test]
+fn registered_devices_get_their_options() {
+    let mut registry = DeviceRegistry::default();
+    registry.register("latch", |config| {
+        let reset = config.options.get("reset").and_then(|value| value.as_integer()).unwrap_or(0) as u8;
+        Ok(Box::new(Latch { value: reset }))
+    });
+    let config = MachineConfig::parse(
+        "reset = 0x0200\n\n[[device]]\ntype = \"latch\"\nstart = 0xD000\nend = 0xD000\n\n[device.options]\nreset = 0x5A\n",
+    )
+    .unwrap();
+    let emulator = config.build_with(&registry).unwrap();
+    assert_eq!(emulator.peek(0xD000), 0x5A);
+}
@@ -0,0 +1,28 @@
+use r6502::emulator::{checked_address, DefaultVirtualMemory, VirtualMemory, ADDRESS_SPACE};
+use r6502::error::R6502Error;
+
+#[test]
+fn memory_is_exactly_64k() {
+    let mut memory = DefaultVirtualMemory::try_from(vec![1, 2, 3]).unwrap();
+    assert_eq!(memory.bytes().len(), ADDRESS_SPACE);
+    assert_eq!(memory.read(0x0002), 3);
+    memory.write(0xFFFF, 9);
+    assert_eq!(memory.read(0xFFFF), 9);
+    assert_eq!(memory.bytes().len(), ADDRESS_SPACE);
+
+    let error = DefaultVirtualMemory::try_from(vec![0; ADDRESS_SPACE + 1]).err().unwrap();
+    assert!(matches!(error, R6502Error::ImageTooLarge { length: 0x10001, origin: 0 }));
+}
+
+#[test]
+fn images_past_ffff_are_clipped() {
+    let mut memory = DefaultVirtualMemory::default().with_image(0xFFFE, &[1, 2, 3, 4]);
+    assert_eq!((memory.read(0xFFFE), memory.read(0xFFFF), memory.read(0x0000)), (1, 2, 0));
+    assert_eq!(memory.bytes().len(), ADDRESS_SPACE);
+}
+
+#[test]
+fn address_arithmetic_is_checked() {
+    assert_eq!(checked_address(0xFFFF).unwrap(), 0xFFFF);
+    assert!(matches!(checked_address(0x10000), Err(R6502Error::AddressOutOfRange(0x10000))));
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::monitor::expression::Expression;
+use r6502::monitor::Monitor;
+use r6502::state::SystemState;
+use r6502::symbols::SymbolTable;
+
+fn emulator() -> CPUEmulator<DefaultVirtualMemory> {
+    // LDX #0; loop: INX; JMP loop
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x0200, &[0xA2, 0x00, 0xE8, 0x4C, 0x02, 0x02])
+        .with_image(0x0300, &[0x11, 0x22, 0x34, 0x12])
+        .with_image(0xFFFC, &[0x00, 0x02]);
+    common::builder(memory, SystemState { pc: 0x0200, a: 0x10, x: 0x05, running: true, ..Default::default() }).build().unwrap()
+}
+
+fn evaluate(text: &str) -> i64 {
+    let mut symbols = SymbolTable::default();
+    symbols.insert("vector", 0x0300);
+    text.parse::<Expression>().unwrap().evaluate(&emulator(), Some(&symbols)).unwrap()
+}
+
+#[test]
+fn evaluates_registers_memory_and_symbols() {
+    assert_eq!(evaluate("a + x"), 0x15);
+    assert_eq!(evaluate("m[vector+2]"), 0x34);
+    assert_eq!(evaluate("w[vector + 2]"), 0x1234);
+    assert_eq!(evaluate("*(word)$FFFC"), 0x0200);
+    assert_eq!(evaluate("*vector"), 0x11);
+    assert_eq!(evaluate("+10 * 2 + %101"), 25);
+    assert_eq!(evaluate("(1 + 2) << 4 | 1"), 0x31);
+    assert_eq!(evaluate("x == 5 && !(a < 4)"), 1);
+    assert_eq!(evaluate("c000 - $a"), 0xBFF6);
+    assert!("m[1".parse::<Expression>().is_err());
+    assert!("1 / (x - 5)".parse::<Expression>().unwrap().evaluate(&emulator(), None).is_err());
+}
+
+#[test]
+fn commands_take_expressions_and_conditional_breakpoints() {
+    let mut emulator = emulator();
+    let mut monitor = Monitor::new();
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "m w[fffc]+1 2", &mut out).unwrap();
+    assert!(String::from_utf8_lossy(&out).starts_with("0201  00 E8"), "{}", String::from_utf8_lossy(&out));
+
+    monitor.execute(&mut emulator, "b 0202 if x == 3", &mut out).unwrap();
+    let mut stops = 0;
+    loop {
+        assert_eq!(emulator.run(), StopReason::Breakpoint(0x0202));
+        stops += 1;
+        if monitor.breaks(&emulator) {
+            break;
+        }
+    }
+    assert_eq!(emulator.state.x, 3);
+    assert_eq!(stops, 4);
+
+    out.clear();
+    monitor.execute(&mut emulator, "e x * +100", &mut out).unwrap();
+    assert_eq!(String::from_utf8_lossy(&out), "$012C  +300  %100101100\n");
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use r6502::charset::Charset;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::monitor::Monitor;
+use r6502::search;
+
+fn run(emulator: &mut CPUEmulator<DefaultVirtualMemory>, monitor: &mut Monitor, line: &str) -> String {
+    let mut out = vec![];
+    monitor.execute(emulator, line, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn edits_fills_copies_and_finds_memory() {
+    let mut emulator = common::stopped(DefaultVirtualMemory::default()).build().unwrap();
+    let mut monitor = Monitor::new();
+
+    run(&mut emulator, &mut monitor, "> 0200 de ad be ef");
+    assert_eq!((0x0200..0x0204).map(|address| emulator.peek(address)).collect::<Vec<_>>(), [0xDE, 0xAD, 0xBE, 0xEF]);
+
+    run(&mut emulator, &mut monitor, "f 0300 0306 1 2");
+    assert_eq!((0x0300..=0x0307).map(|address| emulator.peek(address)).collect::<Vec<_>>(), [1, 2, 1, 2, 1, 2, 1, 0]);
+
+    // Overlapping copy, shifting the range up by one.
+    run(&mut emulator, &mut monitor, "t 0200 0203 0201");
+    assert_eq!((0x0200..0x0205).map(|address| emulator.peek(address)).collect::<Vec<_>>(), [0xDE, 0xDE, 0xAD, 0xBE, 0xEF]);
+
+    assert_eq!(run(&mut emulator, &mut monitor, "find 0300 03ff 2 1"), "0301\n0303\n0305\n3 found\n");
+    let mut out = vec![];
+    assert!(monitor.execute(&mut emulator, "f 0300 0200 0", &mut out).is_err());
+    assert!(monitor.execute(&mut emulator, "> 0300 100", &mut out).is_err());
+}
+
+#[test]
+fn finds_text_and_words() {
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x1000, b"Hello")
+        .with_image(0x2000, &[0x48, 0x45, 0x4C, 0x4C, 0x4F]) // HELLO in PETSCII
+        .with_image(0x3000, &[0x34, 0x12, 0x00, 0x34, 0x12]);
+    let mut emulator = common::stopped(memory).build().unwrap();
+    let mut monitor = Monitor::new();
+
+    assert_eq!(search::find_text(&emulator, 0x0000, 0xFFFF, "Hello", Charset::Ascii), [0x1000]);
+    assert_eq!(search::find_text(&emulator, 0x0000, 0xFFFF, "hello", Charset::Petscii), [0x2000]);
+    assert_eq!(search::find_word(&emulator, 0x3000, 0x3004, 0x1234), [0x3000, 0x3003]);
+    // A match has to fit inside the range.
+    assert!(search::find_word(&emulator, 0x3000, 0x3003, 0x1234) == [0x3000]);
+
+    assert_eq!(run(&mut emulator, &mut monitor, "find 0 ffff p\"HELLO\""), "2000\n1 found\n");
+    assert_eq!(run(&mut emulator, &mut monitor, "find 0 ffff \"Hello\""), "1000\n1 found\n");
+    assert_eq!(run(&mut emulator, &mut monitor, "find 3000 30ff w 1234"), "3000\n3003\n2 found\n");
+}
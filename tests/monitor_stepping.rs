@@ -0,0 +1,61 @@
+mod common;
+
+use r6502::assembler::assemble;
+use r6502::devices::tia::Tia;
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, VirtualMemory};
+use r6502::monitor::Monitor;
+
+// Five scanline frames, starting with vertical sync.
+const KERNEL: &str = "
+frame:  lda #2
+        sta $00
+        sta $02
+        lda #0
+        sta $00
+        ldx #4
+line:   sta $02
+        dex
+        bne line
+        jmp frame
+";
+
+fn emulator() -> CPUEmulator<Bus> {
+    let program = assemble(KERNEL, 0x1000).unwrap();
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x1000, &program.image)).map(0x0000, 0x003F, Tia::new());
+    common::running(bus, 0x1000).build().unwrap()
+}
+
+fn raster<M: VirtualMemory>(emulator: &CPUEmulator<M>) -> Option<(u64, u16)> {
+    emulator.with_memory(|memory| memory.raster())
+}
+
+#[test]
+fn steps_by_frame_and_scanline() {
+    let mut emulator = emulator();
+    let mut monitor = Monitor::new();
+    let mut out = vec![];
+
+    // The frame starts with the write to VSYNC.
+    monitor.execute(&mut emulator, "sf", &mut out).unwrap();
+    assert_eq!((raster(&emulator), emulator.state.pc), (Some((1, 0)), 0x1004));
+    assert!(String::from_utf8(out.split_off(0)).unwrap().ends_with("1004  85 02     STA $02\n"));
+
+    monitor.execute(&mut emulator, "sl 2", &mut out).unwrap();
+    assert_eq!(raster(&emulator), Some((1, 2)));
+    assert_eq!(emulator.with_memory(|memory| memory.beam()), Some((2, 0)));
+
+    monitor.execute(&mut emulator, "sf 2", &mut out).unwrap();
+    assert_eq!((raster(&emulator), emulator.state.pc), (Some((3, 0)), 0x1004));
+}
+
+#[test]
+fn stepping_needs_a_video_device() {
+    let mut emulator = common::running(DefaultVirtualMemory::default(), 0x0200).build().unwrap();
+    let mut out = vec![];
+    for command in ["sf", "sl 3"] {
+        let error = Monitor::new().execute(&mut emulator, command, &mut out).unwrap_err();
+        assert_eq!(error.to_string(), "No video device is attached");
+    }
+    assert_eq!(emulator.clock(), 0);
+}
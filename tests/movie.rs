@@ -0,0 +1,60 @@
+mod common;
+
+use r6502::devices::riot::Riot;
+use r6502::devices::tia::Tia;
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::error::R6502Error;
+use r6502::input::Buttons;
+use r6502::movie::{Movie, MovieRecorder};
+
+// Starts vertical sync every frame, then stores SWCHA and INPT4 at $0400+n and $0500+n.
+fn machine() -> CPUEmulator<Bus> {
+    let program = [
+        0xA9, 0x00, 0x85, 0x00, 0xA9, 0x02, 0x85, 0x00, // VSYNC off, on
+        0xE8, 0xAD, 0x80, 0x02, 0x9D, 0x00, 0x04, // INX; LDA SWCHA; STA $0400,X
+        0xA5, 0x0C, 0x9D, 0x00, 0x05, // LDA INPT4; STA $0500,X
+        0x4C, 0x00, 0x10, // JMP $1000
+    ];
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x1000, &program))
+        .map(0x0000, 0x003F, Tia::new())
+        .map(0x0280, 0x029F, Riot::new());
+    common::running(bus, 0x1000).build().unwrap()
+}
+
+fn record() -> Movie {
+    let mut emulator = machine();
+    let mut recorder = MovieRecorder::start(&mut emulator, 3);
+    for frame in 0..10 {
+        let held = if (4..7).contains(&frame) { Buttons::LEFT | Buttons::A } else { Buttons::empty() };
+        assert_eq!(recorder.record_frame(&mut emulator, [held, Buttons::empty()]).unwrap(), StopReason::Stepped);
+    }
+    recorder.finish()
+}
+
+#[test]
+fn recorded_movies_play_back_through_a_file() {
+    let movie = record();
+    assert_eq!(movie.frames, 10);
+    assert_eq!(movie.checksums.iter().map(|(frame, _)| *frame).collect::<Vec<_>>(), [3, 6, 9]);
+    assert_eq!(movie.script.changes().count(), 3);
+
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+    let mut emulator = machine();
+    assert_eq!(movie.play(&mut emulator).unwrap(), StopReason::Stepped);
+    assert_eq!(emulator.peek(0x0405), 0xBF);
+    assert_eq!(emulator.peek(0x0505), 0x00);
+
+    assert!(matches!(Movie::from_bytes(b"R6M\x01\x00"), Err(R6502Error::BadMovie(_))));
+}
+
+#[test]
+fn playback_reports_the_first_checksum_that_differs() {
+    let mut movie = record();
+    // Different input from frame 5 on changes the recorded SWCHA values.
+    movie.script.set(5, [Buttons::RIGHT, Buttons::empty()]);
+    match movie.play(&mut machine()) {
+        Err(R6502Error::Desync { frame, .. }) => assert_eq!(frame, 6),
+        other => panic!("expected a desync, got {:?}", other),
+    }
+}
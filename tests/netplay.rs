@@ -0,0 +1,63 @@
+mod common;
+
+use std::thread;
+
+use r6502::devices::riot::Riot;
+use r6502::devices::tia::Tia;
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::error::R6502Error;
+use r6502::input::Buttons;
+use r6502::netplay::{ChannelTransport, FramePacket, Lockstep};
+
+// Starts vertical sync every frame, then stores SWCHA at $0400+n.
+fn machine() -> CPUEmulator<Bus> {
+    let program = [
+        0xA9, 0x00, 0x85, 0x00, 0xA9, 0x02, 0x85, 0x00, // VSYNC off, on
+        0xE8, 0xAD, 0x80, 0x02, 0x9D, 0x00, 0x04, // INX; LDA SWCHA; STA $0400,X
+        0x4C, 0x00, 0x10, // JMP $1000
+    ];
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x1000, &program))
+        .map(0x0000, 0x003F, Tia::new())
+        .map(0x0280, 0x029F, Riot::new());
+    common::running(bus, 0x1000).build().unwrap()
+}
+
+// Runs `frames` frames as `player`, holding `held` on every frame.
+fn peer(transport: ChannelTransport, player: usize, held: Buttons, frames: u64, poke: Option<(u16, u8)>) -> Result<Vec<u8>, R6502Error> {
+    let mut emulator = machine();
+    let mut session = Lockstep::new(transport, player, &mut emulator, 4);
+    if let Some((address, value)) = poke {
+        emulator.poke(address, value);
+    }
+    while session.frame() < frames {
+        session.advance(&mut emulator, held)?;
+    }
+    Ok((0x0400..0x0410).map(|address| emulator.peek(address)).collect())
+}
+
+#[test]
+fn peers_see_each_others_input() {
+    let (left, right) = ChannelTransport::pair();
+    let other = thread::spawn(move || peer(right, 1, Buttons::DOWN, 10, None));
+    let mine = peer(left, 0, Buttons::UP, 10, None).unwrap();
+    let theirs = other.join().unwrap().unwrap();
+    assert_eq!(mine, theirs);
+    // Player 1 up (bit 4) and player 2 down (bit 1) read low.
+    assert_eq!(mine[5], 0xED);
+}
+
+#[test]
+fn diverging_machines_desync_at_the_next_checksum() {
+    let (left, right) = ChannelTransport::pair();
+    let other = thread::spawn(move || peer(right, 1, Buttons::empty(), 10, Some((0x0600, 1))));
+    assert!(matches!(peer(left, 0, Buttons::empty(), 10, None), Err(R6502Error::Desync { frame: 0, .. })));
+    assert!(matches!(other.join().unwrap(), Err(R6502Error::Desync { frame: 0, .. })));
+}
+
+#[test]
+fn packets_round_trip() {
+    let packet = FramePacket { frame: 77, buttons: Buttons::A | Buttons::START, checksum: Some(0xDEAD_BEEF) };
+    assert_eq!(FramePacket::from_bytes(&packet.to_bytes()).unwrap(), packet);
+    assert!(FramePacket::from_bytes(&[0; 3]).is_err());
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use r6502::disassembler::disassemble_at;
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::format::{number_format, set_number_format, NumberFormat};
+use r6502::monitor::Monitor;
+use r6502::state::{Registers, SystemAction, SystemCycle};
+
+// The format is process wide, so everything that changes it is one test.
+#[test]
+fn every_view_follows_the_number_format() {
+    let lda = disassemble_at(&[0xAD, 0x00, 0xC0], 0x0200);
+    let registers = Registers { pc: 0xC000, a: 10, ..Default::default() };
+    let change = Registers::default().diff(&registers)[0];
+    let cycle = SystemCycle { address: 0xC000, value: 10, action: SystemAction::READ };
+
+    assert_eq!(number_format(), NumberFormat::Hex);
+    assert_eq!(lda.text(None), "LDA $C000");
+    assert!(registers.to_string().starts_with("pc=C000 a=0A "));
+    assert_eq!(change.to_string(), "pc: 0000 -> C000");
+
+    set_number_format(NumberFormat::ZeroX);
+    assert_eq!(lda.text(None), "LDA 0xC000");
+    assert_eq!(disassemble_at(&[0xA9, 0x0A], 0).text(None), "LDA #0x0A");
+    assert_eq!(change.to_string(), "pc: 0x0000 -> 0xC000");
+
+    set_number_format(NumberFormat::Decimal);
+    assert_eq!(lda.text(None), "LDA 49152");
+    assert!(registers.to_string().starts_with("pc=49152 a=10 "));
+    assert_eq!(cycle.to_string().trim_end(), "read from 49152 with value 10");
+
+    set_number_format(NumberFormat::Binary);
+    assert_eq!(disassemble_at(&[0xA9, 0x0A], 0).text(None), "LDA #%00001010");
+
+    // The monitor can switch it too.
+    let mut emulator = common::stopped(DefaultVirtualMemory::default()).build().unwrap();
+    let mut monitor = Monitor::new();
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "fmt $", &mut out).unwrap();
+    assert_eq!(number_format(), NumberFormat::Dollar);
+    monitor.execute(&mut emulator, "b c000", &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "breakpoint at $C000 set\n");
+    assert!(monitor.execute(&mut emulator, "fmt octal", &mut vec![]).is_err());
+}
@@ -0,0 +1,54 @@
+use r6502::instructions::{unimplemented_opcodes, AddressingMode, OpCode};
+use r6502::opcodes;
+use r6502::state::SystemFlags;
+
+// The flags each documented instruction affects, per the NMOS programming manual.
+fn documented_flags(opcode: OpCode) -> SystemFlags {
+    let nz = SystemFlags::negative | SystemFlags::zero;
+    let nzc = nz | SystemFlags::carry;
+    match opcode {
+        OpCode::ADC | OpCode::SBC => nzc | SystemFlags::overflow,
+        OpCode::ASL | OpCode::LSR | OpCode::ROL | OpCode::ROR | OpCode::CMP | OpCode::CPX | OpCode::CPY => nzc,
+        OpCode::AND | OpCode::ORA | OpCode::EOR | OpCode::DEC | OpCode::INC | OpCode::DEX | OpCode::DEY
+        | OpCode::INX | OpCode::INY | OpCode::LDA | OpCode::LDX | OpCode::LDY | OpCode::TAX | OpCode::TAY
+        | OpCode::TSX | OpCode::TXA | OpCode::TYA | OpCode::PLA => nz,
+        OpCode::BIT => nz | SystemFlags::overflow,
+        OpCode::PLP | OpCode::RTI => SystemFlags::all() - SystemFlags::break_command - SystemFlags::expansion,
+        OpCode::CLC | OpCode::SEC => SystemFlags::carry,
+        OpCode::CLD | OpCode::SED => SystemFlags::decimal,
+        OpCode::CLI | OpCode::SEI | OpCode::BRK => SystemFlags::interrupt_disable,
+        OpCode::CLV => SystemFlags::overflow,
+        _ => SystemFlags::empty(),
+    }
+}
+
+#[test]
+fn implemented_opcodes_match_the_executor() {
+    let unimplemented = unimplemented_opcodes();
+    for info in opcodes::describe_all() {
+        let listed = unimplemented.iter().any(|(byte, _)| *byte == info.byte);
+        assert_eq!(listed, !info.implemented, "{:#04x} {:?}", info.byte, info.instruction.opcode);
+    }
+}
+
+#[test]
+fn mnemonics_and_modes_round_trip() {
+    assert_eq!("jsr".parse::<OpCode>().unwrap(), OpCode::JSR);
+    assert_eq!(OpCode::LDA.to_string(), "LDA");
+    assert_eq!("(zp),y".parse::<AddressingMode>().unwrap(), AddressingMode::IndirectZeroPageY);
+    assert_eq!("DirectAbsoluteX".parse::<AddressingMode>().unwrap(), AddressingMode::DirectAbsoluteX);
+    assert_eq!(AddressingMode::DirectZeroPageX.to_string(), "zp,X");
+    assert!("XYZ".parse::<OpCode>().is_err());
+}
+
+#[test]
+fn executed_flags_match_the_documentation() {
+    for info in opcodes::describe_all() {
+        let expected = match (info.implemented, info.legal) {
+            (false, _) => SystemFlags::empty(),
+            (true, true) => documented_flags(info.instruction.opcode),
+            (true, false) => continue,
+        };
+        assert_eq!(info.flags, expected, "{:#04x} {}", info.byte, info.instruction);
+    }
+}
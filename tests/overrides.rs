@@ -0,0 +1,44 @@
+mod common;
+
+use r6502::devices::tia::Tia;
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason, VirtualMemory, OVERRIDE_CYCLES};
+
+const PROGRAM: [u8; 5] = [
+    0x02, // overridden
+    0x03, // overridden, slower
+    0x4C, 0x00, 0x10, // JMP $1000
+];
+
+fn emulator() -> CPUEmulator<Bus> {
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x1000, &PROGRAM)).map(0x0000, 0x003F, Tia::new());
+    let mut emulator = common::running(bus, 0x1000).build().unwrap();
+    emulator.override_opcode(0x02, |_| Ok(()));
+    emulator.override_opcode(0x03, |emulator| {
+        emulator.add_cycles(3);
+        Ok(())
+    });
+    emulator
+}
+
+#[test]
+fn overrides_are_charged_cycles_and_tick_the_devices() {
+    let mut emulator = emulator();
+    emulator.execute_next_instruction().unwrap();
+    assert_eq!(emulator.clock(), OVERRIDE_CYCLES);
+    // Three color clocks a cycle.
+    assert_eq!(emulator.with_memory(|memory| memory.beam()), Some((0, 6)));
+
+    emulator.execute_next_instruction().unwrap();
+    assert_eq!(emulator.clock(), 2 * OVERRIDE_CYCLES + 3);
+    assert_eq!(emulator.trace.iter().map(|retired| retired.opcode).collect::<Vec<_>>(), [0x02, 0x03]);
+}
+
+#[test]
+fn overrides_count_against_cycle_budgets() {
+    let mut emulator = emulator();
+    // 2 + 5 + 3 cycles a loop.
+    assert_eq!(emulator.run_for_cycles(30), StopReason::Timeout);
+    assert_eq!(emulator.clock(), 30);
+    assert_eq!(emulator.state.pc, 0x1000);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use r6502::diagnostics::AnomalyKind;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::pinning::PinPolicy;
+
+// Writes the page a device might be reading, reads it straight back, then
+// again after a delay loop.
+const PROGRAM: &str = "
+        lda #$2a
+        sta $0400
+        lda $0400
+        sta $10
+        ldx #10
+wait:   dex
+        bne wait
+        lda $0400
+        sta $11
+        kil
+";
+
+fn emulator() -> CPUEmulator<DefaultVirtualMemory> {
+    common::assembled(PROGRAM, 0x0200).build().unwrap()
+}
+
+#[test]
+fn reported_writes_go_through() {
+    let mut emulator = emulator();
+    emulator.pin(0x0400, 0x04FF, None, PinPolicy::Report);
+    emulator.run();
+    assert_eq!((emulator.peek(0x10), emulator.peek(0x11)), (0x2A, 0x2A));
+    let anomalies: Vec<_> = emulator.diagnostics.of_kind(AnomalyKind::PinnedWrite).collect();
+    assert_eq!(anomalies.len(), 1);
+    assert_eq!((anomalies[0].pc, anomalies[0].address, anomalies[0].value), (0x0202, Some(0x0400), 0x2A));
+}
+
+#[test]
+fn delayed_writes_land_when_the_pin_lapses() {
+    let mut emulator = emulator();
+    emulator.pin(0x0400, 0x04FF, Some(20), PinPolicy::Delay);
+    emulator.run();
+    assert_eq!((emulator.peek(0x10), emulator.peek(0x11)), (0x00, 0x2A));
+    assert_eq!(emulator.diagnostics.of_kind(AnomalyKind::DelayedWrite).count(), 1);
+    assert!(emulator.delayed_writes().is_empty());
+}
+
+#[test]
+fn unpinning_releases_held_writes() {
+    let mut emulator = emulator();
+    let pin = emulator.pin(0x0400, 0x0400, None, PinPolicy::Delay);
+    emulator.run();
+    assert_eq!(emulator.peek(0x11), 0x00);
+    assert_eq!(emulator.delayed_writes(), [(0x0400, 0x2A)]);
+    assert!(emulator.unpin(pin));
+    assert!(!emulator.unpin(pin));
+    assert_eq!(emulator.peek(0x0400), 0x2A);
+}
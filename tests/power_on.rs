@@ -0,0 +1,55 @@
+use r6502::devices::banked::BankedRam;
+use r6502::emulator::{DefaultVirtualMemory, VirtualMemory};
+use r6502::machines::config::MachineConfig;
+use r6502::power_on::RamPattern;
+
+#[test]
+fn fills_memory_with_patterns() {
+    let zero = DefaultVirtualMemory::default().with_pattern(RamPattern::Zero);
+    assert!(zero.bytes().iter().all(|byte| *byte == 0x00));
+    let ones = DefaultVirtualMemory::default().with_pattern(RamPattern::Ones);
+    assert!(ones.bytes().iter().all(|byte| *byte == 0xFF));
+
+    let dram = DefaultVirtualMemory::default().with_pattern(RamPattern::dram());
+    assert_eq!((dram.bytes()[0x00], dram.bytes()[0x3F], dram.bytes()[0x40], dram.bytes()[0x7F], dram.bytes()[0x80]), (0x00, 0x00, 0xFF, 0xFF, 0x00));
+    let mut bytes = [0x55; 5];
+    RamPattern::Alternating { block: 0 }.fill(&mut bytes);
+    assert_eq!(bytes, [0x00, 0xFF, 0x00, 0xFF, 0x00]);
+}
+
+#[test]
+fn random_ram_follows_its_seed() {
+    let first = DefaultVirtualMemory::default().with_pattern(RamPattern::Random { seed: 7 });
+    let again = DefaultVirtualMemory::default().with_pattern(RamPattern::Random { seed: 7 });
+    let other = DefaultVirtualMemory::default().with_pattern(RamPattern::Random { seed: 8 });
+    assert!(first == again);
+    assert!(first != other);
+    assert!(first.bytes().iter().filter(|byte| **byte == 0).count() < 1024);
+}
+
+#[test]
+fn images_load_over_the_pattern() {
+    let mut memory = DefaultVirtualMemory::default().with_pattern(RamPattern::Ones).with_image(0x0200, &[0x01, 0x02]);
+    assert_eq!((memory.read(0x01FF), memory.read(0x0200), memory.read(0x0201), memory.read(0x0202)), (0xFF, 0x01, 0x02, 0xFF));
+}
+
+#[test]
+fn banked_ram_takes_a_pattern() {
+    let mut ram = BankedRam::new(0x8000, 0x40, 4, 0xC000).with_pattern(RamPattern::dram());
+    assert_eq!(ram.read(0x8000), 0x00);
+    ram.set_bank(1);
+    assert_eq!(ram.read(0x8000), 0xFF);
+}
+
+#[test]
+fn configs_choose_the_pattern() {
+    let config = MachineConfig::parse("ram = { pattern = \"ff\" }\n").unwrap();
+    assert_eq!(config.ram, RamPattern::Ones);
+    let emulator = config.build().unwrap();
+    assert_eq!(emulator.peek(0x1234), 0xFF);
+
+    let config = MachineConfig::parse("ram = { pattern = \"random\", seed = 3 }\n").unwrap();
+    assert_eq!(config.ram, RamPattern::Random { seed: 3 });
+    assert_eq!(MachineConfig::parse("").unwrap().ram, RamPattern::Zero);
+    assert!(MachineConfig::parse("ram = { pattern = \"alternating\" }\n").is_err());
+}
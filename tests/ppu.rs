@@ -0,0 +1,103 @@
+mod common;
+
+use r6502::assembler::assemble;
+use r6502::devices::ppu::{Ppu, HEIGHT, WIDTH};
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, VirtualMemory};
+use r6502::loaders::Mirroring;
+
+// Waits for vertical blank, puts tile 1 in the top left corner, then turns on
+// the background and NMIs, which count frames at $10.
+const PROGRAM: &str = "
+reset: sei
+ldx #$ff
+txs
+bit $2002
+vwait: bit $2002
+bpl vwait
+lda #$3f
+sta $2006
+lda #$00
+sta $2006
+lda #$0f
+sta $2007
+lda #$30
+sta $2007
+lda #$20
+sta $2006
+lda #$00
+sta $2006
+lda #$01
+sta $2007
+lda #$00
+sta $2005
+sta $2005
+lda #$80
+sta $2000
+lda #$0a
+sta $2001
+loop: jmp loop
+nmi: inc $10
+rti
+";
+
+fn emulator() -> CPUEmulator<Bus> {
+    let program = assemble(PROGRAM, 0x8000).unwrap();
+    let vectors = [program.symbols.lookup("nmi").unwrap(), program.symbols.lookup("reset").unwrap(), 0];
+    let vectors: Vec<u8> = vectors.iter().flat_map(|vector| vector.to_le_bytes()).collect();
+    let memory = DefaultVirtualMemory::default().with_image(0x8000, &program.image).with_image(0xFFFA, &vectors);
+    // Tile 1 has its top row solid in colour 1.
+    let mut chr = vec![0; 0x2000];
+    chr[0x10] = 0xFF;
+    let bus = Bus::new(memory).map(0x2000, 0x3FFF, Ppu::new(&chr, Mirroring::Vertical));
+    let mut emulator = common::stopped(bus).build().unwrap();
+    emulator.reset();
+    emulator
+}
+
+#[test]
+fn draws_the_background_and_raises_vblank_nmis() {
+    let mut emulator = emulator();
+    emulator.run_for_cycles(4 * 29781);
+    assert!((2..=3).contains(&emulator.read(0x10)), "{} NMIs", emulator.read(0x10));
+
+    emulator.with_memory(|bus| {
+        let ppu = bus.device::<Ppu>().unwrap();
+        let framebuffer = ppu.framebuffer();
+        assert_eq!(framebuffer.len(), WIDTH * HEIGHT);
+        assert_eq!(&framebuffer[..9], [0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x30, 0x0F]);
+        assert!(framebuffer[WIDTH..].iter().all(|colour| *colour == 0x0F));
+    });
+}
+
+#[test]
+fn ppudata_reads_are_buffered_and_nametables_mirrored() {
+    let mut ppu = Ppu::new(&[], Mirroring::Horizontal);
+    ppu.set_vram(0x2005, 0x42);
+    assert_eq!(ppu.vram(0x2405), 0x42);
+    assert_eq!(ppu.vram(0x2805), 0);
+    ppu.set_vram(0x3F10, 0x21);
+    assert_eq!(ppu.vram(0x3F00), 0x21);
+
+    ppu.write(0x2006, 0x20);
+    ppu.write(0x2006, 0x05);
+    ppu.read(0x2007);
+    assert_eq!(ppu.read(0x2007), 0x42);
+    // Pattern RAM when the cartridge has no CHR ROM.
+    ppu.set_vram(0x0000, 0x99);
+    assert_eq!(ppu.vram(0x0000), 0x99);
+}
+
+#[test]
+fn peeks_leave_the_status_and_address_alone() {
+    let mut ppu = Ppu::new(&[], Mirroring::Horizontal);
+    ppu.set_vram(0x2005, 0x42);
+    ppu.write(0x2006, 0x20);
+    ppu.write(0x2006, 0x05);
+    ppu.read(0x2007);
+    assert_eq!(ppu.peek(0x2007), 0x42);
+    assert_eq!(ppu.peek(0x2007), 0x42);
+    // The address moved on with the read, not with the peeks.
+    assert_eq!(ppu.read(0x2007), 0x42);
+    assert_eq!(ppu.read(0x2007), 0x00);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use r6502::devices::printer::{self, Printer, PrinterHandle};
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::Bus;
+
+// Prints the zero terminated report at $0300 on the printer at $D000.
+const PROGRAM: [u8; 14] = [
+    0xA2, 0x00, // LDX #0
+    0xBD, 0x00, 0x03, // loop: LDA $0300,X
+    0xF0, 0x06, // BEQ done
+    0x8D, 0x00, 0xD0, // STA $D000
+    0xE8, // INX
+    0xD0, 0xF5, // BNE loop
+    0x02, // done: KIL
+];
+
+const REPORT: &[u8] = b"TOTALS\r\n\x0cPAGE 2\r\n\0";
+
+fn print(printer: Printer) -> CPUEmulator<Bus> {
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &PROGRAM).with_image(0x0300, REPORT);
+    let mut emulator = common::running(Bus::new(memory).map(0xD000, 0xD001, printer), 0x0200).build().unwrap();
+    emulator.run();
+    emulator
+}
+
+#[test]
+fn collects_the_printout() {
+    let handle = PrinterHandle::default();
+    let emulator = print(Printer::with_handle(handle.clone()));
+    assert_eq!(emulator.peek(0xD001), printer::READY);
+    assert_eq!(handle.output(), &REPORT[..REPORT.len() - 1]);
+    assert_eq!(handle.text(), "TOTALS\n\x0cPAGE 2\n");
+    assert_eq!(handle.pages().len(), 1);
+}
+
+#[test]
+fn splits_pages_at_form_feeds() {
+    let printer = Printer::new().split_pages();
+    let handle = printer.handle();
+    print(printer);
+    let pages = handle.take_pages();
+    assert_eq!(pages.iter().map(|page| page.text()).collect::<Vec<_>>(), ["TOTALS\n", "PAGE 2\n"]);
+    // Each character takes a pass of the loop, 15 cycles.
+    assert_eq!(pages[1].cycle - pages[0].cycle, 9 * 15);
+    assert!(handle.pages().is_empty());
+}
+
+#[test]
+fn spools_each_page_to_a_file() {
+    let directory = std::env::temp_dir().join(format!("r6502-printer-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    print(Printer::new().split_pages().spool(&directory));
+    let mut files: Vec<_> = std::fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().path()).collect();
+    files.sort();
+    let names: Vec<_> = files.iter().map(|file| file.file_name().unwrap().to_string_lossy().into_owned()).collect();
+    assert_eq!(names.len(), 2);
+    assert!(names[0].starts_with("printout-") && names[0].ends_with("-p1.txt"), "{:?}", names);
+    assert_eq!(names[1], names[0].replace("-p1", "-p2"));
+    assert_eq!(std::fs::read(&files[0]).unwrap(), b"TOTALS\r\n");
+    assert_eq!(std::fs::read(&files[1]).unwrap(), b"PAGE 2\r\n");
+    std::fs::remove_dir_all(directory).unwrap();
+}
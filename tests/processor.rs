@@ -1,4 +1,5 @@
 use r6502::emulator::{DefaultVirtualMemory, CPUEmulator, CPUEmulatorBuilder, VirtualMemory};
+use r6502::format::number_format;
 use r6502::instructions::{Instruction, OpCode};
 use r6502::state::{SystemAction, SystemCycle, SystemFlags, SystemState};
 
@@ -10,6 +11,7 @@ use tabled::settings::Style;
 use tabled::Table;
 use std::fs::File;
 use std::io::Read;
+use std::sync::{Arc, Mutex};
 use colored::Colorize;
 
 
@@ -27,7 +29,7 @@ fn json_to_state(state_map: &Value, key: &str, include_cycles: bool) -> CPUEmula
     };
 
 
-    let mut emulator = CPUEmulatorBuilder::default().memory(DefaultVirtualMemory::default()).state(state).build().unwrap();
+    let mut emulator = CPUEmulatorBuilder::default().memory(Arc::new(Mutex::new(DefaultVirtualMemory::default()))).state(state).build().unwrap();
 
     for memory in state_map[key]["ram"].as_array().unwrap().iter() {
         let memory = memory.as_array().unwrap();
@@ -78,7 +80,10 @@ fn debug_state_comparison(
     //     }
     //     (final_vec, tested_vec)
     // };
-    let (final_vec, tested_vec) = (final_state.iter_memory(), tested_state.iter_memory());
+    let (final_vec, tested_vec) = (
+        final_state.with_memory(|memory| memory.bytes().to_vec()).into_iter(),
+        tested_state.with_memory(|memory| memory.bytes().to_vec()).into_iter(),
+    );
 
     let result = {
             final_state.state.pc == tested_state.state.pc &&
@@ -102,7 +107,7 @@ fn debug_state_comparison(
             .filter(|(_, (a, b))| a != b)
             .map(
                 |(addr, (exp, fin))| {
-                    vec![addr.to_string(), exp.to_string(), fin.to_string()]
+                    vec![number_format().word(addr as u16), number_format().byte(exp), number_format().byte(fin)]
         })
             .collect();
 
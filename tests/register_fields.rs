@@ -0,0 +1,40 @@
+use r6502::machines::Machine;
+use r6502::registers::{self, Field, FieldFormat};
+
+#[test]
+fn decodes_a_write_field_by_field() {
+    let ctrlpf = registers::find(Machine::Atari2600.registers(), 0x0A).unwrap();
+    assert_eq!(ctrlpf.name, "CTRLPF");
+    assert_eq!(ctrlpf.decode(0x21), "playfield reflect=on, score=off, priority=off, ball size=2");
+
+    let nusiz = registers::find(registers::TIA, 0x04).unwrap();
+    assert_eq!(nusiz.decode(0x15), "copies=double, missile size=1");
+
+    let ppuctrl = registers::find(Machine::Nes.registers(), 0x2000).unwrap();
+    assert_eq!(
+        ppuctrl.decode(0x84),
+        "nametable=$2000, increment=32, sprite table=$0000, background table=$0000, \
+         sprite size=8x8, ext output=off, nmi=on"
+    );
+}
+
+#[test]
+fn signed_fields_are_twos_complement() {
+    let hmp0 = registers::find(registers::TIA, 0x20).unwrap();
+    assert_eq!(hmp0.decode(0x70), "motion=7");
+    assert_eq!(hmp0.decode(0x80), "motion=-8");
+    assert_eq!(hmp0.decode(0xF0), "motion=-1");
+}
+
+#[test]
+fn unnamed_values_fall_back_to_the_number() {
+    let field = Field { name: "mode", shift: 0, width: 2, format: FieldFormat::Named(&["off", "on"]) };
+    assert_eq!(field.decode(0x03), "mode=3");
+    assert_eq!(field.raw(0xFD), 1);
+}
+
+#[test]
+fn unknown_addresses_have_no_description() {
+    assert!(registers::find(registers::TIA, 0x30).is_none());
+    assert!(Machine::Generic.registers().is_empty());
+}
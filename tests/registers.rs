@@ -0,0 +1,14 @@
+use r6502::state::{Registers, SystemFlags};
+
+#[test]
+fn display_diff_and_serde() {
+    let before = Registers { pc: 0x0400, a: 0x01, s: 0xFD, p: SystemFlags::carry | SystemFlags::negative, ..Default::default() };
+    let after = Registers { pc: 0x0402, a: 0x80, ..before };
+    assert_eq!(before.to_string(), "pc=0400 a=01 x=00 y=00 s=FD p=Nv-bdizC");
+
+    let changes: Vec<String> = before.diff(&after).iter().map(|change| change.to_string()).collect();
+    assert_eq!(changes, ["pc: 0400 -> 0402", "a: 01 -> 80"]);
+
+    let json = serde_json::to_string(&after).unwrap();
+    assert_eq!(serde_json::from_str::<Registers>(&json).unwrap(), after);
+}
@@ -0,0 +1,41 @@
+use r6502::devices::riot::{ConsoleSwitch, Riot};
+use r6502::devices::Bus;
+use r6502::emulator::{DefaultVirtualMemory, VirtualMemory};
+
+fn bus() -> Bus {
+    Bus::new(DefaultVirtualMemory::default()).map(0x0280, 0x029F, Riot::new())
+}
+
+#[test]
+fn swchb_reads_the_console_switches_at_every_mirror() {
+    let mut bus = bus();
+    // Color TV, both difficulties on B, nothing pressed.
+    assert_eq!(bus.read(0x0282), 0x0B);
+
+    let riot = bus.device_mut::<Riot>().unwrap();
+    riot.set_switch(ConsoleSwitch::Reset, true);
+    assert!(riot.key_event("F5", true));
+    assert!(riot.key_event("F5", false));
+    assert!(!riot.key_event("F9", true));
+
+    // A3 and A4 aren't decoded for the ports.
+    for address in [0x0282, 0x028A, 0x0292, 0x029A] {
+        assert_eq!(bus.read(address), 0x4A, "{:#06x}", address);
+    }
+}
+
+#[test]
+fn port_writes_decode_through_the_mirrors() {
+    let mut bus = bus();
+    bus.device_mut::<Riot>().unwrap().set_port_a(0xF0);
+    // SWACNT at a mirror makes the low nibble of port A outputs.
+    bus.write(0x0291, 0x0F);
+    bus.write(0x0298, 0x05);
+    assert_eq!(bus.read(0x0281), 0x0F);
+    assert_eq!(bus.read(0x0280), 0xF5);
+
+    // With A2 and A4 set it's the timer again, not a port.
+    bus.write(0x0294, 0x10);
+    assert_eq!(bus.read(0x0284), 0x10);
+    assert_eq!(bus.read(0x0281), 0x0F);
+}
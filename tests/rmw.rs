@@ -0,0 +1,29 @@
+mod common;
+
+use r6502::cpu::Cpu6502;
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::state::SystemState;
+
+// Runs `instruction` against $10 holding $81 (or $1081 for absolute operands,
+// with X = 1 for indexed ones) and returns the writes it made.
+fn writes(instruction: &[u8]) -> Vec<(u16, u8)> {
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, instruction).with_image(0x0010, &[0x81]).with_image(0x1081, &[0x81]);
+    let mut emulator = common::builder(memory, SystemState { pc: 0x0200, s: 0xFD, x: 1, running: true, ..Default::default() })
+        .build()
+        .unwrap();
+    assert!(emulator.step());
+    emulator.last_writes()
+}
+
+#[test]
+fn read_modify_write_instructions_write_the_old_value_first() {
+    assert_eq!(writes(&[0x06, 0x10]), [(0x10, 0x81), (0x10, 0x02)]); // ASL $10
+    assert_eq!(writes(&[0x46, 0x10]), [(0x10, 0x81), (0x10, 0x40)]); // LSR $10
+    assert_eq!(writes(&[0x26, 0x10]), [(0x10, 0x81), (0x10, 0x02)]); // ROL $10
+    assert_eq!(writes(&[0x66, 0x10]), [(0x10, 0x81), (0x10, 0x40)]); // ROR $10
+    assert_eq!(writes(&[0xE6, 0x10]), [(0x10, 0x81), (0x10, 0x82)]); // INC $10
+    assert_eq!(writes(&[0xC6, 0x10]), [(0x10, 0x81), (0x10, 0x80)]); // DEC $10
+    assert_eq!(writes(&[0xFE, 0x80, 0x10]), [(0x1081, 0x81), (0x1081, 0x82)]); // INC $1080,X
+    // The accumulator forms don't touch memory.
+    assert_eq!(writes(&[0x0A]), []);
+}
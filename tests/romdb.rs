@@ -0,0 +1,59 @@
+use r6502::hashing::crc32;
+use r6502::loaders::Format;
+use r6502::machines::romdb::{KnownRom, RomDatabase};
+use r6502::machines::{AutoLoader, Machine};
+
+// A monitor ROM for $F800 that does nothing but loop.
+fn monitor() -> Vec<u8> {
+    let mut rom = vec![0xEA; 0x0800];
+    rom[..3].copy_from_slice(&[0x4C, 0x00, 0xF8]);
+    rom[0x7FC..].copy_from_slice(&[0x00, 0xF8, 0x00, 0xF8]);
+    rom
+}
+
+fn database() -> RomDatabase {
+    let text = format!("[[rom]]\ncrc32 = {:#x}\nname = \"Loop monitor\"\nmachine = \"apple2\"\norigin = 0xF800\n", crc32(&monitor()));
+    RomDatabase::parse(&text).unwrap()
+}
+
+#[test]
+fn crc32_matches_zip_and_rom_databases() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(crc32(b""), 0);
+}
+
+#[test]
+fn identifies_roms_from_a_database_file() {
+    let database = database();
+    assert_eq!(database.len(), 1);
+    let rom = database.identify(&monitor()).unwrap();
+    assert_eq!((rom.name.as_str(), rom.machine, rom.origin), ("Loop monitor", Machine::Apple2, Some(0xF800)));
+    assert!(database.identify(&[0xEA; 16]).is_none());
+    assert!(RomDatabase::parse("[[rom]]\ncrc32 = 1\nname = \"x\"\nmachine = \"amiga\"\n").is_err());
+}
+
+#[test]
+fn builtin_roms_can_be_added_to_and_replaced() {
+    let builtin = RomDatabase::builtin();
+    assert_eq!(builtin.get(0xDBE3_E7C7).map(|rom| (rom.machine, rom.origin)), Some((Machine::C64, Some(0xE000))));
+    let renamed = KnownRom { crc32: 0xDBE3_E7C7, name: "Patched KERNAL".to_owned(), machine: Machine::C64, origin: Some(0xE000) };
+    let merged = builtin.clone().merged(database()).merged(RomDatabase::default().with(renamed));
+    assert_eq!(merged.len(), builtin.len() + 1);
+    assert_eq!(merged.get(0xDBE3_E7C7).unwrap().name, "Patched KERNAL");
+}
+
+#[test]
+fn known_roms_pick_their_machine_and_origin() {
+    let loaded = AutoLoader::default().database(database()).load_bytes(&monitor(), Some("bin")).unwrap();
+    assert_eq!((loaded.format, loaded.machine), (Format::Binary, Machine::Apple2));
+    assert_eq!(loaded.identified.unwrap().name, "Loop monitor");
+    assert_eq!(loaded.emulator.state.pc, 0xF800);
+
+    // Without the database its size and reset vector make it look like a 2600 cartridge.
+    let unknown = AutoLoader::default().load_bytes(&monitor(), Some("bin")).unwrap();
+    assert_eq!((unknown.format, unknown.machine), (Format::Atari2600, Machine::Atari2600));
+    assert!(unknown.identified.is_none());
+
+    let overridden = AutoLoader::default().database(database()).machine(Machine::Bbc).origin(0x1000).load_bytes(&monitor(), Some("bin")).unwrap();
+    assert_eq!((overridden.machine, overridden.emulator.peek(0x1000)), (Machine::Bbc, 0x4C));
+}
@@ -0,0 +1,14 @@
+use r6502::devices::rtc::{self, Rtc};
+use r6502::emulator::VirtualMemory;
+
+#[test]
+fn fixed_clock_advances_with_emulated_time() {
+    // 2024-02-29 23:59:59 UTC, a Thursday.
+    let mut clock = Rtc::fixed(1709251199, 1_000_000);
+    let date: Vec<u8> = (rtc::SECONDS..=rtc::WEEKDAY).map(|register| clock.read(register)).collect();
+    assert_eq!(date, [59, 59, 23, 29, 2, 0xE8, 0x07, 4]);
+
+    clock.tick(1_000_000);
+    let date: Vec<u8> = (rtc::SECONDS..=rtc::WEEKDAY).map(|register| clock.read(register)).collect();
+    assert_eq!(date, [0, 0, 0, 1, 3, 0xE8, 0x07, 5]);
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use r6502::cooperative::block_on;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+
+fn emulator(program: &[u8]) -> CPUEmulator<DefaultVirtualMemory> {
+    common::program(program, 0x0200).build().unwrap()
+}
+
+// Counts how often the wrapped future is polled.
+struct Polls<F> {
+    future: Pin<Box<F>>,
+    polls: usize,
+}
+
+impl<F: Future> Future for Polls<F> {
+    type Output = (F::Output, usize);
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        self.polls += 1;
+        let polls = self.polls;
+        self.future.as_mut().poll(context).map(|output| (output, polls))
+    }
+}
+
+#[test]
+fn long_runs_yield_between_slices() {
+    let mut emulator = emulator(&[0x4C, 0x00, 0x02]); // JMP $0200
+    let run = emulator.run_async(30_000).slice(3_000);
+    let (reason, polls) = block_on(Polls { future: Box::pin(run), polls: 0 });
+    assert_eq!(reason, StopReason::Timeout);
+    assert_eq!(polls, 10);
+    assert_eq!(emulator.clock(), 30_000);
+}
+
+#[test]
+fn programs_that_stop_resolve_straight_away() {
+    let mut emulator = emulator(&[0xEA, 0x02]); // NOP; KIL
+    assert_eq!(block_on(emulator.run_async(1_000_000)), StopReason::Halted);
+}
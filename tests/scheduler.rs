@@ -0,0 +1,80 @@
+mod common;
+
+use r6502::devices::link::Link;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::scheduler::Scheduler;
+use r6502::Bus;
+
+// Sends the zero terminated string at $0300 down the link at $D000.
+const SENDER: [u8; 14] = [
+    0xA2, 0x00, // LDX #0
+    0xBD, 0x00, 0x03, // loop: LDA $0300,X
+    0xF0, 0x06, // BEQ done
+    0x8D, 0x00, 0xD0, // STA $D000
+    0xE8, // INX
+    0xD0, 0xF5, // BNE loop
+    0x02, // done: KIL
+];
+
+// Waits for four bytes on the link and stores them at $10.
+const RECEIVER: [u8; 20] = [
+    0xA2, 0x00, // LDX #0
+    0xAD, 0x01, 0xD0, // wait: LDA $D001
+    0x29, 0x01, // AND #RECEIVED
+    0xF0, 0xF9, // BEQ wait
+    0xAD, 0x00, 0xD0, // LDA $D000
+    0x95, 0x10, // STA $10,X
+    0xE8, // INX
+    0xE0, 0x04, // CPX #4
+    0xD0, 0xEF, // BNE wait
+    0x02, // KIL
+];
+
+fn machine(program: &[u8], link: Link) -> CPUEmulator<Bus> {
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, program).with_image(0x0300, b"PING\0");
+    common::running(Bus::new(memory).map(0xD000, 0xD001, link), 0x0200).build().unwrap()
+}
+
+fn spinning() -> CPUEmulator<DefaultVirtualMemory> {
+    common::program(&[0xEA, 0x4C, 0x00, 0x02], 0x0200).build().unwrap()
+}
+
+#[test]
+fn linked_machines_pass_a_message() {
+    let (one, other) = Link::pair();
+    let mut scheduler = Scheduler::new().slice(20);
+    // The receiver goes first and has to wait for the sender's turns.
+    let receiver = scheduler.add("receiver", machine(&RECEIVER, other));
+    let sender = scheduler.add("sender", machine(&SENDER, one));
+    scheduler.run();
+    assert_eq!(scheduler.stopped(sender), Some(&StopReason::Halted));
+    assert_eq!(scheduler.stopped(receiver), Some(&StopReason::Halted));
+    let emulator = scheduler.get::<Bus>(receiver).unwrap();
+    assert_eq!((0x10..0x14).map(|address| emulator.peek(address)).collect::<Vec<_>>(), b"PING");
+}
+
+#[test]
+fn keeps_machines_within_a_slice_of_each_other() {
+    let mut scheduler = Scheduler::new().slice(50);
+    let first = scheduler.add("first", spinning());
+    let second = scheduler.add("second", spinning());
+    assert!(scheduler.run_for_cycles(1000));
+    assert_eq!(scheduler.elapsed(), 1000);
+    for index in [first, second] {
+        let clock = scheduler.get::<DefaultVirtualMemory>(index).unwrap().clock();
+        assert!((1000..1005).contains(&clock), "{} at {}", scheduler.name(index).unwrap(), clock);
+    }
+    assert_eq!(scheduler.index_of("second"), Some(second));
+    assert!(scheduler.get::<Bus>(first).is_none());
+}
+
+#[test]
+fn the_others_run_on_after_one_stops() {
+    let (one, _other) = Link::pair();
+    let mut scheduler = Scheduler::new();
+    let sender = scheduler.add("sender", machine(&SENDER, one));
+    let spinner = scheduler.add("spinner", spinning());
+    assert!(scheduler.run_for_cycles(500));
+    assert_eq!(scheduler.stopped(sender), Some(&StopReason::Halted));
+    assert_eq!(scheduler.stopped(spinner), None);
+}
@@ -0,0 +1,37 @@
+use r6502::devices::semihost::{self, Semihost, Status};
+use r6502::emulator::VirtualMemory;
+
+fn name(device: &mut Semihost, name: &str) {
+    for byte in name.bytes() {
+        device.write(semihost::NAME, byte);
+    }
+}
+
+#[test]
+fn writes_and_reads_back_a_host_file() {
+    let root = std::env::temp_dir().join(format!("r6502-semihost-{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let mut device = Semihost::new(&root);
+
+    name(&mut device, "out.txt");
+    device.write(semihost::COMMAND, semihost::OPEN_WRITE);
+    for byte in b"HI" {
+        device.write(semihost::DATA, *byte);
+    }
+    device.write(semihost::COMMAND, semihost::CLOSE);
+    assert_eq!(device.status(), Status::Ok);
+    assert_eq!(std::fs::read(root.join("out.txt")).unwrap(), b"HI");
+
+    device.write(semihost::CHANNEL, 1);
+    name(&mut device, "out.txt");
+    device.write(semihost::COMMAND, semihost::OPEN_READ);
+    let read: Vec<u8> = (0..2).map(|_| device.read(semihost::DATA)).collect();
+    assert_eq!(read, b"HI");
+    device.read(semihost::DATA);
+    assert_eq!(device.read(semihost::COMMAND), Status::EndOfFile as u8);
+
+    name(&mut device, "../escape.txt");
+    device.write(semihost::COMMAND, semihost::OPEN_WRITE);
+    assert_eq!(device.status(), Status::Denied);
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use r6502::devices::riot::Riot;
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, CPUEmulatorBuilder, DefaultVirtualMemory, StopReason, VirtualMemory};
+
+const PROGRAM: &str = "
+        ldx #2
+loop:   dex
+        bne loop
+        kil
+";
+
+// A trace writer the test can read back.
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn builder() -> CPUEmulatorBuilder<DefaultVirtualMemory> {
+    common::assembled(PROGRAM, 0x0200)
+}
+
+#[test]
+fn breakpoints_can_be_set_when_building() {
+    let mut emulator: CPUEmulator<_> = builder().breakpoints([0x0202, 0x0205]).build().unwrap();
+    assert_eq!(emulator.run(), StopReason::Breakpoint(0x0202));
+    assert_eq!(emulator.run(), StopReason::Breakpoint(0x0202));
+    assert_eq!(emulator.run(), StopReason::Breakpoint(0x0205));
+    assert_eq!(emulator.run(), StopReason::Halted);
+}
+
+#[test]
+fn traces_every_instruction() {
+    let trace = Shared::default();
+    let mut emulator = builder().trace_writer(trace.clone()).build().unwrap();
+    emulator.run();
+    let text = String::from_utf8(trace.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert!(lines[0].starts_with("0200  LDX #$02"), "{}", lines[0]);
+    assert!(lines[4].starts_with("0203  BNE $0202"), "{}", lines[4]);
+    assert!(lines[5].starts_with("0205  KIL"), "{}", lines[5]);
+}
+
+#[test]
+fn tracing_leaves_read_sensitive_registers_alone() {
+    // A timer that has already run out, with INTIM right after the last instruction.
+    let mut riot = Riot::new();
+    riot.write(0x0294, 0);
+    riot.tick(2);
+    let program = [0xEA, 0x02]; // NOP, KIL
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x0282, &program)).map(0x0284, 0x0287, riot);
+    let mut emulator = common::running(bus, 0x0282)
+        .trace_writer(Shared::default())
+        .build()
+        .unwrap();
+    assert_eq!(emulator.run(), StopReason::Halted);
+    emulator.peek(0x0284);
+    assert_eq!(emulator.peek(0x0285), 0xC0, "TIMINT was cleared");
+
+    // Unlike a read of INTIM by the processor.
+    emulator.with_memory(|bus| bus.read(0x0284));
+    assert_eq!(emulator.peek(0x0285), 0x00);
+}
+
+#[test]
+fn halt_handler_runs_once() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = calls.clone();
+    let mut emulator = builder()
+        .on_halt(move |emulator, reason| {
+            assert_eq!(*reason, StopReason::Halted);
+            assert_eq!(emulator.state.pc, 0x0206);
+            counted.fetch_add(1, Ordering::SeqCst);
+        })
+        .build()
+        .unwrap();
+    assert_eq!(emulator.run(), StopReason::Halted);
+    assert_eq!(emulator.run(), StopReason::Halted);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use r6502::emulator::StopReason;
+use r6502::error::R6502Error;
+use r6502::shutdown::ThreadGroup;
+
+#[test]
+fn a_panicking_thread_stops_the_group() {
+    let mut threads = ThreadGroup::new();
+    threads.spawn("emulator", |_| panic!("emulator crashed")).unwrap();
+    threads
+        .spawn("render", |shutdown| while !shutdown.wait_timeout(Duration::from_millis(10)) {})
+        .unwrap();
+    let cleaned_up = Arc::new(AtomicBool::new(false));
+    let hook = cleaned_up.clone();
+    threads.on_shutdown(move || hook.store(true, Ordering::SeqCst));
+
+    threads.wait();
+    match threads.finish() {
+        Err(R6502Error::ThreadsPanicked(names)) => assert_eq!(names, vec!["emulator".to_owned()]),
+        other => panic!("expected the emulator thread to be reported, got {:?}", other),
+    }
+    assert!(cleaned_up.load(Ordering::SeqCst));
+}
+
+#[test]
+fn wait_returns_once_every_thread_has_returned() {
+    let mut threads = ThreadGroup::new();
+    threads.spawn("loader", |_| ()).unwrap();
+    threads.spawn("audio", |_| std::thread::sleep(Duration::from_millis(50))).unwrap();
+
+    threads.wait();
+    assert!(!threads.shutdown().is_requested());
+    threads.finish().unwrap();
+}
+
+#[test]
+fn hooks_see_the_final_machine_state() {
+    let program = [
+        0xE6, 0x10, // loop: INC $10
+        0x4C, 0x00, 0x02, // JMP loop
+    ];
+    let emulator = common::program(&program, 0x0200).build().unwrap();
+    let emulator = Arc::new(Mutex::new(emulator));
+    let stopped = Arc::new(Mutex::new(None));
+    let seen = Arc::new(Mutex::new(None));
+
+    let mut threads = ThreadGroup::new();
+    let (finished, hook) = (emulator.clone(), seen.clone());
+    threads.on_shutdown(move || {
+        let emulator = finished.lock().unwrap();
+        *hook.lock().unwrap() = Some((emulator.clock(), emulator.peek(0x10)));
+    });
+    let last = stopped.clone();
+    threads
+        .spawn("emulator", move |shutdown| {
+            let mut emulator = emulator.lock().unwrap();
+            assert_eq!(emulator.run_until_shutdown(&shutdown), StopReason::Interrupted);
+            *last.lock().unwrap() = Some((emulator.clock(), emulator.peek(0x10)));
+        })
+        .unwrap();
+
+    let shutdown = threads.shutdown();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        shutdown.request();
+    });
+    threads.wait();
+    threads.finish().unwrap();
+
+    let seen = seen.lock().unwrap().expect("the hook didn't run");
+    assert!(seen.0 > 0);
+    assert_eq!(Some(seen), *stopped.lock().unwrap());
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use r6502::assembler::assemble;
+use r6502::devices::sid::{self, Sid, SidWrite};
+use r6502::devices::Bus;
+use r6502::emulator::DefaultVirtualMemory;
+
+// Sets up voice 1 and the volume, then waits a frame and changes the waveform
+// through the $D420 mirror.
+const PLAYER: &str = "
+lda #$d6
+sta $d400
+lda #$1c
+sta $d401
+lda #$09
+sta $d405
+lda #$0f
+sta $d418
+lda #$41
+sta $d404
+ldx #0
+ldy #16
+wait: dex
+bne wait
+dey
+bne wait
+lda #$40
+sta $d424
+kil
+";
+
+#[test]
+fn captures_writes_and_dumps_them_per_frame() {
+    let program = assemble(PLAYER, 0x0200).unwrap();
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &program.image);
+    let bus = Bus::new(memory).map(0xD400, 0xD7FF, Sid::new());
+    let mut emulator = common::running(bus, 0x0200).build().unwrap();
+    emulator.run();
+
+    let (writes, csv, dump) = emulator.with_memory(|bus| {
+        let sid = bus.device::<Sid>().unwrap();
+        (sid.writes().to_vec(), sid.to_csv(), sid.dump(sid::PAL_FRAME_CYCLES))
+    });
+    assert_eq!(writes.len(), 6);
+    assert_eq!((writes[0].register, writes[0].value), (0x00, 0xD6));
+    assert_eq!((writes[5].register, writes[5].value), (0x04, 0x40));
+    assert!(writes[5].cycle > sid::PAL_FRAME_CYCLES);
+    assert!(csv.starts_with("cycle,register,value\n"));
+    assert!(csv.lines().nth(1).unwrap().ends_with(",00,D6"));
+
+    let lines: Vec<&str> = dump.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[2], "|     0 | 1CD6 A-4 39  41 0900 000 | 0000 ... ..  00 0000 000 | 0000 ... ..  00 0000 000 | 0000 00 Off F |");
+    assert_eq!(lines[3], "|     1 | .... ......  40 .... ... | .... ......  .. .... ... | .... ......  .. .... ... | .... .. ... . |");
+}
+
+#[test]
+fn notes_follow_the_pal_clock() {
+    assert_eq!(sid::note(0x1CD6), Some(("A-4".to_owned(), 57)));
+    assert_eq!(sid::note(0x0000), None);
+    let writes = [SidWrite { cycle: 5, register: 0x18, value: 0x1F }];
+    assert_eq!(sid::frames(&writes, 100), [{
+        let mut registers = [0; sid::WRITABLE];
+        registers[0x18] = 0x1F;
+        registers
+    }]);
+}
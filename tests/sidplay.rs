@@ -0,0 +1,105 @@
+use r6502::assembler::assemble;
+use r6502::devices::sid::PAL_FRAME_CYCLES;
+use r6502::error::R6502Error;
+use r6502::machines::sidplay::{SidFormat, SidPlayer, SidTune, PAL_TIMER};
+
+// Init keeps the song number, sets the volume and pokes $D400 with the I/O
+// area banked out, which must land in RAM; play raises voice 1's frequency
+// by one each call.
+const CALLED: &str = "
+init:   sta $fb
+        lda #$0f
+        sta $d418
+        lda #$34
+        sta $01
+        lda #$55
+        sta $d400
+        lda #$37
+        sta $01
+        rts
+play:   inc $fc
+        lda $fc
+        sta $d400
+        rts
+";
+
+// Installs an IRQ handler through the KERNAL vector and turns interrupts on,
+// leaving the KERNAL's 60Hz CIA timer to pace it.
+const INTERRUPT: &str = "
+init:   lda #<handler
+        sta $0314
+        lda #>handler
+        sta $0315
+        cli
+        rts
+handler: inc $fc
+        lda $fc
+        sta $d401
+        jmp $ea31
+";
+
+fn sid_file(format: &[u8; 4], load: u16, init: u16, play: u16, speed: u32, image: &[u8]) -> Vec<u8> {
+    let mut data = format.to_vec();
+    for word in [2, 0x7C, 0, init, play, 3, 2] {
+        data.extend(u16::to_be_bytes(word));
+    }
+    data.extend(speed.to_be_bytes());
+    for text in ["Test Tune", "Someone", "2024 Nobody"] {
+        let mut field = text.as_bytes().to_vec();
+        field.resize(32, 0);
+        data.extend(field);
+    }
+    data.extend([0, 0x14, 0, 0, 0, 0]);
+    data.extend(load.to_le_bytes());
+    data.extend(image);
+    data
+}
+
+#[test]
+fn parses_the_header_with_the_load_address_in_the_data() {
+    let program = assemble(CALLED, 0x1000).unwrap();
+    let tune = SidTune::parse(&sid_file(b"PSID", 0x1000, 0x1000, program.symbols.lookup("play").unwrap(), 0, &program.image)).unwrap();
+    assert_eq!(tune.format, SidFormat::Psid);
+    assert_eq!((tune.load, tune.init, tune.songs, tune.start_song), (0x1000, 0x1000, 3, 2));
+    assert_eq!((tune.name.as_str(), tune.author.as_str(), tune.released.as_str()), ("Test Tune", "Someone", "2024 Nobody"));
+    assert_eq!(tune.data, program.image);
+    assert!(!tune.ntsc());
+    assert!(matches!(SidTune::parse(b"MUS\0"), Err(R6502Error::NotSidFile)));
+}
+
+#[test]
+fn calls_play_once_a_frame() {
+    let program = assemble(CALLED, 0x1000).unwrap();
+    let play = program.symbols.lookup("play").unwrap();
+    let tune = SidTune::parse(&sid_file(b"PSID", 0x1000, 0x1000, play, 0, &program.image)).unwrap();
+    let mut player = SidPlayer::new(tune, None).unwrap();
+    assert_eq!(player.emulator().peek(0xFB), 1);
+    player.play_frames(4).unwrap();
+
+    let writes = player.writes();
+    assert_eq!(writes.iter().map(|write| (write.register, write.value)).collect::<Vec<_>>(), [(0x18, 0x0F), (0, 1), (0, 2), (0, 3), (0, 4)]);
+    let gaps: Vec<u64> = writes[1..].windows(2).map(|pair| pair[1].cycle - pair[0].cycle).collect();
+    assert_eq!(gaps, [PAL_FRAME_CYCLES; 3]);
+    assert_eq!(player.dump().lines().count(), 2 + 4);
+    // The banked out write went to the RAM under the SID.
+    player.emulator_mut().poke(0x0001, 0x34);
+    assert_eq!(player.emulator().peek(0xD400), 0x55);
+}
+
+#[test]
+fn interrupt_driven_tunes_run_from_the_cia_timer() {
+    let program = assemble(INTERRUPT, 0x0C00).unwrap();
+    let tune = SidTune::parse(&sid_file(b"RSID", 0x0C00, 0x0C00, 0, 0, &program.image)).unwrap();
+    assert!(tune.interrupt_driven());
+    let mut player = SidPlayer::new(tune, Some(1)).unwrap();
+    player.play_frames(10).unwrap();
+
+    let writes = player.writes();
+    assert_eq!(writes.len(), 10 * PAL_FRAME_CYCLES as usize / (PAL_TIMER as usize + 1));
+    assert!(writes.iter().enumerate().all(|(index, write)| write.value == index as u8 + 1));
+    // Interrupts are taken at the end of the idle loop's three cycle JMP, so
+    // they jitter around the timer period without drifting from it.
+    let period = PAL_TIMER as u64 + 1;
+    assert!(writes.windows(2).all(|pair| (pair[1].cycle - pair[0].cycle).abs_diff(period) < 3));
+    assert!((writes[10].cycle - writes[0].cycle).abs_diff(10 * period) < 3);
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use r6502::assert_snapshot;
+use r6502::snapshot::{self, check};
+
+#[test]
+fn countdown_trace_and_final_state() {
+    let mut emulator = common::assembled("ldx #3\nloop: txa\nsta $10,x\ndex\nbne loop\n.byte $02", 0x0200).build().unwrap();
+
+    assert_snapshot!("countdown_trace", snapshot::trace(&mut emulator, 100));
+    assert_snapshot!("countdown_state", snapshot::state_table(&emulator, &[(0x0010, 0x0013)]));
+}
+
+#[test]
+fn mismatches_leave_the_new_output_for_review() {
+    let directory = std::env::temp_dir().join(format!("r6502-snapshot-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("frame.snap"), "0000\n0101\n").unwrap();
+
+    assert!(check(&directory, "frame", &snapshot::framebuffer(&[0, 0, 1, 1], 2)).unwrap().is_none());
+    let mismatch = check(&directory, "frame", &snapshot::framebuffer(&[0, 0, 1, 2], 2)).unwrap().unwrap();
+    assert!(mismatch.diff.contains("- 0101\n+ 0102"), "{}", mismatch.diff);
+    assert_eq!(std::fs::read_to_string(&mismatch.pending).unwrap(), "0000\n0102\n");
+    std::fs::remove_dir_all(&directory).unwrap();
+}
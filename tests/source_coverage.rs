@@ -0,0 +1,72 @@
+mod common;
+
+use r6502::analysis::coverage::ExecutedBytes;
+use r6502::analysis::listing::{Listing, ListingLine};
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::symbols::SymbolTable;
+
+// What `ca65 -l` makes of a file whose branch skips an INX, and `ld65 -m` of
+// placing it at $0800.
+const LISTING: &str = "\
+ca65 V2.19 - Git 0d0e4a6
+Main file   : game.s
+Current file: game.s
+
+000000r 1               .segment \"CODE\"
+000000r 1  A9 00        main:   lda #0
+000002r 1  F0 01                beq done
+000004r 1  E8                   inx
+000005r 1  02           done:   .byte $02 ; halt
+000006r 1               .include \"extra.inc\"
+000006r 2  EA                   nop
+";
+
+const MAP: &str = "\
+Segment list:
+-------------
+Name                   Start     End    Size  Align
+----------------------------------------------------
+CODE                  000800  000805  000006  00001
+";
+
+fn executed() -> ExecutedBytes {
+    let memory = DefaultVirtualMemory::default().with_image(0x0800, &[0xA9, 0x00, 0xF0, 0x01, 0xE8, 0x02]);
+    let mut emulator = common::running(memory, 0x0800)
+        .executed(Some(ExecutedBytes::default()))
+        .build()
+        .unwrap();
+    emulator.run();
+    emulator.executed.unwrap()
+}
+
+#[test]
+fn parses_ca65_listings() {
+    let symbols = SymbolTable::from_ld65_map(MAP).unwrap();
+    let listing = Listing::parse(LISTING, Some(&symbols)).unwrap();
+    assert_eq!(listing.file, "game.s");
+    assert_eq!(
+        listing.lines,
+        [
+            ListingLine { line: 2, address: 0x0800, length: 2 },
+            ListingLine { line: 3, address: 0x0802, length: 2 },
+            ListingLine { line: 4, address: 0x0804, length: 1 },
+        ]
+    );
+    // Without a map relocatable code stays at its segment offset.
+    assert_eq!(Listing::parse(LISTING, None).unwrap().lines[0].address, 0x0000);
+    assert!(Listing::parse("000000  1  EA  nop\n", None).is_err());
+}
+
+#[test]
+fn exports_line_coverage() {
+    let symbols = SymbolTable::from_ld65_map(MAP).unwrap();
+    let listing = Listing::parse(LISTING, Some(&symbols)).unwrap();
+    let executed = executed();
+    assert_eq!(executed.lcov(std::slice::from_ref(&listing)), "TN:\nSF:game.s\nDA:2,1\nDA:3,1\nDA:4,0\nLF:3\nLH:2\nend_of_record\n");
+
+    let xml = executed.cobertura(&[listing]);
+    assert!(xml.starts_with("<?xml version=\"1.0\" ?>\n<coverage line-rate=\"0.6667\" branch-rate=\"0\" lines-covered=\"2\" lines-valid=\"3\""), "{}", xml);
+    assert!(xml.contains("<class name=\"game.s\" filename=\"game.s\" line-rate=\"0.6667\""), "{}", xml);
+    assert!(xml.contains("<line number=\"3\" hits=\"1\"/>\n            <line number=\"4\" hits=\"0\"/>\n"), "{}", xml);
+    assert!(xml.ends_with("</coverage>\n"));
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use r6502::assembler::assemble;
+use r6502::devices::ppu::{self, Ppu, SPRITE_OVERFLOW, SPRITE_ZERO_HIT, WIDTH};
+use r6502::devices::Bus;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, VirtualMemory};
+use r6502::loaders::Mirroring;
+
+// Copies the sprites at $0200 with OAM DMA, fills the top row of the screen
+// with tile 1, turns everything on and waits for sprite zero to hit it.
+const PROGRAM: &str = "
+reset: sei
+ldx #$ff
+txs
+vwait: bit $2002
+bpl vwait
+lda #$3f
+sta $2006
+lda #$00
+sta $2006
+ldx #$00
+palette: lda colours,x
+sta $2007
+inx
+cpx #$20
+bne palette
+lda #$20
+sta $2006
+lda #$00
+sta $2006
+lda #$01
+ldx #$20
+row: sta $2007
+dex
+bne row
+lda #$00
+sta $2003
+lda #$02
+sta $4014
+lda #$00
+sta $2005
+sta $2005
+lda #$1e
+sta $2001
+hit: bit $2002
+bvc hit
+lda $2002
+sta $10
+done: jmp done
+colours: .byte $0f, $30, $30, $30, $0f, $0f, $0f, $0f, $0f, $0f, $0f, $0f, $0f, $0f, $0f, $0f
+.byte $0f, $16, $16, $16, $0f, $2a, $2a, $2a, $0f, $0f, $0f, $0f, $0f, $0f, $0f, $0f
+";
+
+fn emulator(sprites: &[u8]) -> CPUEmulator<Bus> {
+    let program = assemble(PROGRAM, 0x8000).unwrap();
+    let reset = program.symbols.lookup("reset").unwrap();
+    let mut oam = vec![0xFF; 0x100];
+    oam[..sprites.len()].copy_from_slice(sprites);
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x8000, &program.image)
+        .with_image(0x0200, &oam)
+        .with_image(0xFFFC, &reset.to_le_bytes());
+    // Tile 1 is solid colour 1, tile 2 a single pixel in its top left corner.
+    let mut chr = vec![0; 0x2000];
+    chr[0x10..0x18].fill(0xFF);
+    chr[0x20] = 0x80;
+    let bus = Bus::new(memory).map(0x2000, 0x3FFF, Ppu::new(&chr, Mirroring::Vertical)).also_at(ppu::OAMDMA, ppu::OAMDMA);
+    let mut emulator = common::stopped(bus).build().unwrap();
+    emulator.reset();
+    emulator
+}
+
+#[test]
+fn draws_sprites_and_flags_sprite_zero_hits() {
+    // Sprite 0 at (16, 3) over the background, sprite 1 flipped at (100, 19).
+    let mut emulator = emulator(&[2, 2, 0x00, 16, 18, 2, 0x41, 100]);
+    emulator.run_for_cycles(4 * 29781);
+    assert_eq!(emulator.read(0x10) & SPRITE_ZERO_HIT, SPRITE_ZERO_HIT);
+
+    emulator.with_memory(|bus| {
+        let ppu = bus.device::<Ppu>().unwrap();
+        assert_eq!(ppu.oam()[..8], [2, 2, 0x00, 16, 18, 2, 0x41, 100]);
+        let pixels = ppu.framebuffer();
+        assert_eq!(pixels[3 * WIDTH + 16], 0x16);
+        assert_eq!(pixels[3 * WIDTH + 17], 0x30);
+        // Flipped horizontally, so the pixel is at the right of the sprite.
+        assert_eq!(pixels[19 * WIDTH + 100], 0x0F);
+        assert_eq!(pixels[19 * WIDTH + 107], 0x2A);
+    });
+}
+
+#[test]
+fn more_than_eight_sprites_on_a_line_overflow() {
+    let mut chr = vec![0; 0x2000];
+    chr[0x20] = 0x80;
+    let mut ppu = Ppu::new(&chr, Mirroring::Vertical);
+    ppu.write(0x2001, 0x1E);
+    ppu.write(0x2003, 0);
+    for index in 0..64u8 {
+        // The rest are below the screen.
+        let sprite = if index < 9 { [40, 2, 0, index * 10 + 20] } else { [0xFF; 4] };
+        for byte in sprite {
+            ppu.write(0x2004, byte);
+        }
+    }
+    ppu.set_vram(0x3F11, 0x16);
+    // Up to the end of line 41, where the sprites are.
+    ppu.tick(114 * 41);
+    assert_eq!(ppu.status() & SPRITE_OVERFLOW, 0);
+    ppu.tick(114);
+    assert_eq!(ppu.status() & SPRITE_OVERFLOW, SPRITE_OVERFLOW);
+    let pixels = ppu.framebuffer();
+    assert_eq!(pixels[41 * WIDTH + 90], 0x16);
+    assert_eq!(pixels[41 * WIDTH + 100], 0x00);
+    // Sprite zero had no background under it.
+    assert_eq!(ppu.status() & SPRITE_ZERO_HIT, 0);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use r6502::cpu::Cpu6502;
+use r6502::emulator::{stack_address, DefaultVirtualMemory};
+use r6502::state::SystemState;
+
+// Runs `program` at $0200 with the stack pointer at `s`; BRK and subroutine
+// calls go to $0300, which holds `handler`.
+fn core(program: &[u8], handler: &[u8], s: u8) -> impl Cpu6502 {
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x0200, program)
+        .with_image(0x0300, handler)
+        .with_image(0xFFFE, &[0x00, 0x03]);
+    common::builder(memory, SystemState { pc: 0x0200, s, a: 0x42, running: true, ..Default::default() }).build().unwrap()
+}
+
+#[test]
+fn stack_addresses_stay_in_page_one() {
+    assert_eq!(stack_address(0x00), 0x0100);
+    assert_eq!(stack_address(0xFF), 0x01FF);
+}
+
+#[test]
+fn pha_and_pla_wrap_at_every_stack_pointer() {
+    for s in 0..=0xFFu8 {
+        let mut cpu = core(&[0x48, 0xA9, 0x00, 0x68], &[], s); // PHA; LDA #0; PLA
+        assert!(cpu.step());
+        assert_eq!(cpu.last_writes(), vec![(stack_address(s), 0x42)], "s={:02X}", s);
+        assert_eq!(cpu.registers().s, s.wrapping_sub(1));
+        assert!(cpu.step());
+        assert!(cpu.step());
+        assert_eq!(cpu.registers().a, 0x42, "s={:02X}", s);
+        assert_eq!(cpu.registers().s, s);
+    }
+}
+
+#[test]
+fn jsr_and_rts_wrap_at_every_stack_pointer() {
+    for s in 0..=0xFFu8 {
+        let mut cpu = core(&[0x20, 0x00, 0x03], &[0x60], s); // JSR $0300 / RTS
+        assert!(cpu.step());
+        let expected = vec![(stack_address(s), 0x02), (stack_address(s.wrapping_sub(1)), 0x02)];
+        assert_eq!(cpu.last_writes(), expected, "s={:02X}", s);
+        assert_eq!(cpu.registers().s, s.wrapping_sub(2));
+        assert!(cpu.step());
+        assert_eq!(cpu.registers().pc, 0x0203, "s={:02X}", s);
+        assert_eq!(cpu.registers().s, s);
+    }
+}
+
+#[test]
+fn brk_and_rti_wrap_at_every_stack_pointer() {
+    for s in 0..=0xFFu8 {
+        let mut cpu = core(&[0x00, 0xEA], &[0x40], s); // BRK / RTI
+        let flags = cpu.registers().p.to_push(true);
+        assert!(cpu.step());
+        let expected = vec![
+            (stack_address(s), 0x02),
+            (stack_address(s.wrapping_sub(1)), 0x02),
+            (stack_address(s.wrapping_sub(2)), flags),
+        ];
+        assert_eq!(cpu.last_writes(), expected, "s={:02X}", s);
+        assert_eq!(cpu.registers().s, s.wrapping_sub(3));
+        assert!(cpu.step());
+        assert_eq!(cpu.registers().pc, 0x0202, "s={:02X}", s);
+        assert_eq!(cpu.registers().s, s);
+    }
+}
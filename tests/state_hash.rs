@@ -0,0 +1,40 @@
+mod common;
+
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory};
+use r6502::state::SystemState;
+
+// Fills $0300-$03FF with X, counting down from `start`.
+fn run(start: u8) -> CPUEmulator<DefaultVirtualMemory> {
+    let program = [
+        0xA2, start, // LDX #start
+        0x8A, 0x9D, 0x00, 0x03, // loop: TXA; STA $0300,X
+        0xCA, 0xD0, 0xF9, // DEX; BNE loop
+        0x02, // KIL
+    ];
+    let mut emulator = common::program(&program, 0x0200).build().unwrap();
+    emulator.run();
+    emulator
+}
+
+#[test]
+fn equal_runs_hash_equal_until_they_diverge() {
+    let (mut left, mut right) = (run(0x80), run(0x80));
+    assert_eq!(left.state_hash(), right.state_hash());
+    // Hashing again without changes gives the same value.
+    assert_eq!(left.state_hash(), right.state_hash());
+
+    right.poke(0x0310, 0);
+    assert_ne!(left.state_hash(), right.state_hash());
+    right.poke(0x0310, 0x10);
+    assert_eq!(left.state_hash(), right.state_hash());
+
+    let mut other = run(0x7F);
+    assert_ne!(left.state_hash(), other.state_hash());
+
+    // After a rebase only what happens next counts.
+    left.rebase_state_hash();
+    other.rebase_state_hash();
+    left.state.pc = 0x1234;
+    other.state = SystemState { pc: 0x1234, ..left.state.clone() };
+    assert_eq!(left.state_hash(), other.state_hash());
+}
@@ -0,0 +1,37 @@
+use r6502::state::{SystemFlags, SystemState};
+use r6502::superopt::Superoptimizer;
+
+fn inputs(search: Superoptimizer) -> Superoptimizer {
+    [0x00, 0x41, 0x7F, 0xFF]
+        .into_iter()
+        .fold(search, |search, a| search.input(SystemState { a, ..Default::default() }))
+}
+
+#[test]
+fn finds_the_shortest_sequences_meeting_the_spec() {
+    let search = Superoptimizer::new(3)
+        .instruction(&[0xAA]) // TAX
+        .instruction(&[0xE8]) // INX
+        .instruction(&[0xCA]) // DEX
+        .instruction(&[0x8A]); // TXA
+    let found = inputs(search).search(|input, machine| machine.state.x == input.a.wrapping_add(1));
+    assert_eq!(found, vec![vec![0xAA, 0xE8]]);
+}
+
+#[test]
+fn immediate_operands_are_kept_with_their_opcode() {
+    let search = Superoptimizer::new(2)
+        .instruction(&[0x69, 0x02]) // ADC #2
+        .instruction(&[0x18]) // CLC
+        .instruction(&[0x38]) // SEC
+        .input(SystemState { a: 0x10, p: SystemFlags::carry, ..Default::default() });
+    let found = inputs(search).search(|input, machine| machine.state.a == input.a.wrapping_add(2));
+    assert_eq!(found, vec![vec![0x18, 0x69, 0x02]]);
+}
+
+#[test]
+fn nothing_is_found_past_the_length_limit() {
+    let search = Superoptimizer::new(2).instruction(&[0xE8]); // INX
+    let found = inputs(search).search(|input, machine| machine.state.x == input.x.wrapping_add(3));
+    assert!(found.is_empty());
+}
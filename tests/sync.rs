@@ -0,0 +1,56 @@
+mod common;
+
+use r6502::emulator::{CPUEmulatorBuilder, DefaultVirtualMemory, StopReason};
+use r6502::error::R6502Error;
+use r6502::machines::config::MachineConfig;
+use r6502::sync::SyncBoundary;
+
+// NOPs forever.
+fn builder() -> CPUEmulatorBuilder<DefaultVirtualMemory> {
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &[0xEA, 0x4C, 0x00, 0x02]);
+    common::running(memory, 0x0200)
+}
+
+#[test]
+fn steps_an_instruction_or_a_number_of_cycles() {
+    let mut emulator = builder().build().unwrap();
+    assert_eq!(emulator.step_sync().unwrap(), StopReason::Stepped);
+    assert_eq!((emulator.state.pc, emulator.clock()), (0x0201, 2));
+
+    emulator.sync = SyncBoundary::Cycles(100);
+    assert_eq!(emulator.step_sync().unwrap(), StopReason::Stepped);
+    assert!((102..107).contains(&emulator.clock()), "{}", emulator.clock());
+}
+
+#[test]
+fn stops_early_at_breakpoints() {
+    let mut emulator = builder().sync(SyncBoundary::Cycles(1000)).breakpoints([0x0201]).build().unwrap();
+    assert_eq!(emulator.step_sync().unwrap(), StopReason::Breakpoint(0x0201));
+}
+
+#[test]
+fn scanlines_and_frames_need_a_video_device() {
+    let mut emulator = builder().sync(SyncBoundary::Frame).build().unwrap();
+    assert!(matches!(emulator.step_sync(), Err(R6502Error::NoVideoDevice)));
+}
+
+#[test]
+fn machine_configs_set_the_boundary() {
+    let config = MachineConfig::parse(
+        "reset = 0x1000\nsync = \"scanline\"\n\n[[region]]\nstart = 0x1000\nend = 0x1003\nfill = 0xEA\n\n[[device]]\ntype = \"tia\"\nstart = 0x0000\nend = 0x003F\n",
+    )
+    .unwrap();
+    assert_eq!(config.sync, SyncBoundary::Scanline);
+    let mut emulator = config.build().unwrap();
+    emulator.poke(0x1004, 0x4C);
+    emulator.poke(0x1005, 0x00);
+    emulator.poke(0x1006, 0x10);
+    for _ in 0..3 {
+        assert_eq!(emulator.step_sync().unwrap(), StopReason::Stepped);
+    }
+    // 76 cycles a scanline on the 2600.
+    assert!((3 * 76..3 * 76 + 4).contains(&emulator.clock()), "{}", emulator.clock());
+
+    let cycles = MachineConfig::parse("sync = { cycles = 500 }\n").unwrap();
+    assert_eq!(cycles.sync, SyncBoundary::Cycles(500));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use r6502::assembler::assemble;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason, VirtualMemory};
+use r6502::error::R6502Error;
+
+// Prints A with syscall 1, doubles it with syscall 2, then takes an unhandled BRK
+// 3 through the IRQ vector to a handler that stops.
+const PROGRAM: &str = "
+lda #$41
+brk
+.byte 1
+sta $10
+brk
+.byte 2
+sta $11
+brk
+.byte 3
+nop
+irq: kil
+";
+
+fn emulator() -> CPUEmulator<DefaultVirtualMemory> {
+    let program = assemble(PROGRAM, 0x0200).unwrap();
+    let irq = program.symbols.lookup("irq").unwrap();
+    let memory = DefaultVirtualMemory::default().with_image(0x0200, &program.image).with_image(0xFFFE, &irq.to_le_bytes());
+    common::running(memory, 0x0200).build().unwrap()
+}
+
+#[test]
+fn dispatches_brk_on_its_signature_byte() {
+    let printed = Arc::new(Mutex::new(vec![]));
+    let mut emulator = emulator();
+    let output = printed.clone();
+    emulator.on_syscall(1, move |emulator| {
+        output.lock().unwrap().push(emulator.state.a);
+        Ok(())
+    });
+    emulator.on_syscall(2, |emulator| {
+        emulator.state.a = emulator.state.a.wrapping_mul(2);
+        Ok(())
+    });
+    emulator.run();
+
+    assert_eq!(*printed.lock().unwrap(), [0x41]);
+    assert_eq!((emulator.read(0x10), emulator.read(0x11)), (0x41, 0x82));
+    // Only the unhandled BRK pushed a return address and flags.
+    assert_eq!(emulator.state.s, 0xFA);
+    assert_eq!(emulator.read(0x01FB) & 0x10, 0x10);
+}
+
+#[test]
+fn failing_syscalls_stop_the_emulator() {
+    let mut emulator = emulator();
+    emulator.on_syscall(1, |_| Err(R6502Error::BadCommand("no such file".to_owned())));
+    assert!(matches!(emulator.run(), StopReason::Error(Some(_))));
+    assert_eq!(emulator.state.pc, 0x0204);
+
+    let mut emulator = self::emulator();
+    emulator.on_syscall(1, |_| Ok(()));
+    assert!(emulator.clear_syscall(1));
+    emulator.run();
+    assert_eq!(emulator.read(0x10), 0);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use r6502::devices::tape::{self, Encoding, Tape};
+use r6502::emulator::{DefaultVirtualMemory, VirtualMemory};
+use r6502::Bus;
+
+// Apple II timings without the leader, so the loader below can start on the data.
+const BITS_ONLY: Encoding = Encoding::PulseWidth { zero: 250, one: 500, leader: 650, leader_waves: 0, sync: 225 };
+
+// Reads a byte from the tape at $D000 into $10 by timing the waves between
+// falling edges, nine cycles a count: 1 kHz waves count about 110, 2 kHz ones 55.
+const LOADER: [u8; 18] = [
+    0xA2, 0x08, // LDX #8
+    0xA0, 0x00, // bit: LDY #0
+    0xC8, // wait: INY
+    0x2C, 0x00, 0xD0, // BIT $D000
+    0x50, 0xFA, // BVC wait
+    0xC0, 0x53, // CPY #83
+    0x26, 0x10, // ROL $10
+    0xCA, // DEX
+    0xD0, 0xF1, // BNE bit
+    0x02, // KIL
+];
+
+#[test]
+fn a_software_loader_reads_the_tape_by_timing_it() {
+    let bus = Bus::new(DefaultVirtualMemory::default().with_image(0x0200, &LOADER)).map(0xD000, 0xD001, Tape::new(&[0xA5], BITS_ONLY));
+    let mut emulator = common::running(bus, 0x0200).build().unwrap();
+    emulator.run();
+    assert_eq!(emulator.peek(0x10), 0xA5);
+    // Four long and four short waves.
+    assert!(emulator.clock() >= 4 * 1000 + 4 * 500);
+}
+
+#[test]
+fn plays_the_wave_with_the_motor_on() {
+    let mut tape = Tape::new(&[0x80], BITS_ONLY);
+    assert_eq!(tape.length(), 2 * 500 + 7 * 2 * 250);
+    assert_eq!(tape.read(tape::LEVEL), 0);
+    tape.tick(499);
+    assert_eq!(tape.read(tape::LEVEL), 0);
+    tape.tick(1);
+    assert_eq!(tape.read(tape::LEVEL), tape::HIGH);
+    tape.tick(500);
+    assert_eq!(tape.read(tape::LEVEL), tape::FALLING_EDGE);
+    assert_eq!(tape.read(tape::LEVEL), 0);
+
+    tape.write(tape::CONTROL, 0);
+    tape.tick(10_000);
+    assert_eq!(tape.read(tape::CONTROL), 0);
+    tape.write(tape::CONTROL, tape::MOTOR);
+    tape.tick(10_000);
+    assert_eq!(tape.read(tape::CONTROL), tape::MOTOR | tape::END);
+    tape.rewind();
+    assert!(!tape.ended());
+}
+
+#[test]
+fn frames_kansas_city_bytes_with_start_and_stop_bits() {
+    let encoding = Encoding::kansas_city(1_200_000);
+    let halves = encoding.encode(&[0x01]);
+    // A second of leader, then a start bit, eight data bits and two stop bits.
+    assert_eq!(halves.len(), 300 * 16 + 8 + 16 + 7 * 8 + 2 * 16);
+    assert_eq!(halves.iter().sum::<u64>(), (300 + 11) * 4000);
+    let data = &halves[300 * 16..];
+    assert!(data[..8].iter().all(|half| *half == 500));
+    assert!(data[8..24].iter().all(|half| *half == 250));
+}
+
+#[test]
+fn interrupts_on_falling_edges() {
+    let mut tape = Tape::new(&[0x00], BITS_ONLY).with_interrupts();
+    tape.tick(499);
+    assert!(!tape.irq());
+    tape.tick(1);
+    assert!(tape.irq());
+    tape.read(tape::LEVEL);
+    assert!(!tape.irq());
+}
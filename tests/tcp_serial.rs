@@ -0,0 +1,123 @@
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use r6502::devices::tcp_serial::{self, TcpSerial};
+use r6502::emulator::VirtualMemory;
+use r6502::machines::config::MachineConfig;
+
+// Polls `condition` for up to a few seconds, the socket threads being asynchronous.
+fn eventually<F: Fn() -> bool>(condition: F) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    false
+}
+
+fn received(serial: &mut TcpSerial, count: usize) -> Vec<u8> {
+    assert!(eventually(|| serial.pending() >= count));
+    (0..count).map(|_| serial.read(tcp_serial::DATA)).collect()
+}
+
+#[test]
+fn a_terminal_connects_to_a_listening_port() {
+    let mut serial = TcpSerial::listen("127.0.0.1:0", false).unwrap();
+    assert_eq!(serial.read(tcp_serial::STATUS), 0);
+    let mut terminal = TcpStream::connect(serial.local_addr().unwrap()).unwrap();
+    assert!(eventually(|| serial.connected()));
+    assert_eq!(serial.read(tcp_serial::STATUS), tcp_serial::CONNECTED);
+
+    terminal.write_all(b"hi\xFF").unwrap();
+    assert_eq!(received(&mut serial, 3), b"hi\xFF");
+    for byte in b"ok\r\n" {
+        serial.write(tcp_serial::DATA, *byte);
+    }
+    let mut reply = [0u8; 4];
+    terminal.read_exact(&mut reply).unwrap();
+    assert_eq!(&reply, b"ok\r\n");
+
+    drop(terminal);
+    assert!(eventually(|| !serial.connected()));
+    // The next terminal is taken once the first hangs up.
+    let mut terminal = TcpStream::connect(serial.local_addr().unwrap()).unwrap();
+    terminal.write_all(b"again").unwrap();
+    assert_eq!(received(&mut serial, 5), b"again");
+}
+
+#[test]
+fn connects_out_to_a_peer() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut serial = TcpSerial::connect(listener.local_addr().unwrap(), false).unwrap().with_interrupts();
+    let (mut peer, _) = listener.accept().unwrap();
+    assert!(!serial.irq());
+    peer.write_all(b"x").unwrap();
+    assert!(eventually(|| serial.irq()));
+    assert_eq!(serial.read(tcp_serial::STATUS), tcp_serial::RECEIVED | tcp_serial::CONNECTED);
+    assert_eq!(serial.read(tcp_serial::DATA), b'x');
+    assert!(!serial.irq());
+}
+
+#[test]
+fn a_peer_that_stops_reading_doesnt_block_the_emulator() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let serial = TcpSerial::connect(listener.local_addr().unwrap(), false).unwrap();
+    let (mut peer, _) = listener.accept().unwrap();
+    assert!(eventually(|| serial.connected()));
+
+    // Far more than the socket buffers hold, so writing straight to the socket would stall.
+    let (done, finished) = mpsc::channel();
+    let writer = thread::spawn(move || {
+        for _ in 0..4096 {
+            serial.send(&[b'.'; 4096]);
+        }
+        done.send(()).unwrap();
+        serial
+    });
+    assert!(finished.recv_timeout(Duration::from_secs(5)).is_ok());
+    let serial = writer.join().unwrap();
+    assert!(serial.connected());
+
+    // The queued output still arrives once the peer reads again.
+    let mut start = [0u8; 16];
+    peer.read_exact(&mut start).unwrap();
+    assert_eq!(start, [b'.'; 16]);
+}
+
+#[test]
+fn speaks_enough_telnet_for_character_mode() {
+    let mut serial = TcpSerial::listen("127.0.0.1:0", true).unwrap();
+    let mut terminal = TcpStream::connect(serial.local_addr().unwrap()).unwrap();
+    let mut offer = [0u8; 6];
+    terminal.read_exact(&mut offer).unwrap();
+    assert_eq!(offer, [0xFF, 0xFB, 0x01, 0xFF, 0xFB, 0x03]);
+
+    // DO ECHO, a window size subnegotiation, then "a", Return and an escaped 0xFF.
+    terminal.write_all(&[0xFF, 0xFD, 0x01, 0xFF, 0xFA, 0x1F, 0x00, 0x50, 0x00, 0x18, 0xFF, 0xF0, b'a', b'\r', 0x00, 0xFF, 0xFF]).unwrap();
+    assert_eq!(received(&mut serial, 3), [b'a', b'\r', 0xFF]);
+    assert_eq!(serial.pending(), 0);
+
+    serial.write(tcp_serial::DATA, 0xFF);
+    let mut escaped = [0u8; 2];
+    terminal.read_exact(&mut escaped).unwrap();
+    assert_eq!(escaped, [0xFF, 0xFF]);
+}
+
+#[test]
+fn machine_configs_can_listen() {
+    let config = MachineConfig::parse(
+        "[[device]]\ntype = \"serial\"\nstart = 0xD000\nend = 0xD001\noptions = { listen = \"127.0.0.1:0\", irq = true }\n",
+    )
+    .unwrap();
+    let emulator = config.build().unwrap();
+    let address = emulator.with_memory(|bus| bus.device::<TcpSerial>().unwrap().local_addr().unwrap());
+    let mut terminal = TcpStream::connect(address).unwrap();
+    terminal.write_all(b"!").unwrap();
+    assert!(eventually(|| emulator.with_memory(|bus| bus.irq())));
+    assert_eq!(emulator.peek(0xD000), b'!');
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+
+fn emulator(program: &[u8]) -> CPUEmulator<DefaultVirtualMemory> {
+    common::program(program, 0x0200).build().unwrap()
+}
+
+// JMP $0200
+const FOREVER: [u8; 3] = [0x4C, 0x00, 0x02];
+
+#[test]
+fn cycle_budgets_stop_infinite_loops() {
+    let mut emulator = emulator(&FOREVER);
+    assert_eq!(emulator.run_for_cycles(300), StopReason::Timeout);
+    assert_eq!(emulator.clock(), 300);
+    assert_eq!(emulator.run_for_cycles(30), StopReason::Timeout);
+    assert_eq!(emulator.clock(), 330);
+}
+
+#[test]
+fn wall_clock_timeouts_stop_infinite_loops() {
+    let mut emulator = emulator(&FOREVER);
+    let start = Instant::now();
+    assert_eq!(emulator.run_for_duration(Duration::from_millis(20)), StopReason::Timeout);
+    assert!(start.elapsed() >= Duration::from_millis(20));
+}
+
+#[test]
+fn programs_that_stop_in_time_report_why() {
+    let mut emulator = emulator(&[0xEA, 0x02]); // NOP; KIL
+    assert_eq!(emulator.run_with_limits(Some(1000), Some(Duration::from_secs(10))), StopReason::Halted);
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use r6502::devices::timer::{self, Timer};
+use r6502::devices::Bus;
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::state::SystemState;
+
+#[test]
+fn repeating_timer_interrupts_the_main_loop() {
+    let main = [
+        0xA9, 100, 0x8D, 0x00, 0xD0, // LDA #100; STA PERIOD_LOW
+        0xA9, timer::ENABLE | timer::REPEAT | timer::IRQ_ENABLE, 0x8D, 0x02, 0xD0, // LDA #$07; STA CONTROL
+        0x58, // CLI
+        0x4C, 0x0B, 0x02, // JMP *
+    ];
+    // INC $10; LDA STATUS; RTI
+    let handler = [0xE6, 0x10, 0xAD, 0x03, 0xD0, 0x40];
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x0200, &main)
+        .with_image(0x0300, &handler)
+        .with_image(0xFFFE, &[0x00, 0x03]);
+    let bus = Bus::new(memory).map(0xD000, 0xD007, Timer::new());
+    let mut emulator = common::builder(bus, SystemState { pc: 0x0200, s: 0xFF, running: true, ..Default::default() })
+        .build()
+        .unwrap();
+
+    while emulator.clock() < 1000 {
+        emulator.execute_next_instruction().unwrap();
+        emulator.state.cycles.clear();
+    }
+    // One interrupt every 100 cycles, give or take the instruction in progress.
+    assert!((9..=10).contains(&emulator.peek(0x10)), "{} interrupts", emulator.peek(0x10));
+    assert_eq!(emulator.state.s, 0xFF);
+}
@@ -0,0 +1,27 @@
+mod common;
+
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+
+fn emulator(source: &str, detect_traps: bool) -> CPUEmulator<DefaultVirtualMemory> {
+    common::assembled(source, 0x0200)
+        .detect_traps(detect_traps)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn jumps_and_branches_to_themselves_are_traps() {
+    // The countdown loop branches back too, but not to the same instruction.
+    let mut emulator = emulator("ldx #5\nloop: dex\nbne loop\ndone: jmp done", true);
+    assert_eq!(emulator.run(), StopReason::TrapLoop(0x0205));
+    assert_eq!(emulator.state.x, 0);
+
+    let mut emulator = self::emulator("lda #1\nfail: bne fail", true);
+    assert_eq!(emulator.run(), StopReason::TrapLoop(0x0202));
+}
+
+#[test]
+fn traps_are_only_detected_when_asked_for() {
+    let mut emulator = emulator("done: jmp done", false);
+    assert_eq!(emulator.run_for_cycles(1000), StopReason::Timeout);
+}
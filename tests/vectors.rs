@@ -0,0 +1,101 @@
+mod common;
+
+use r6502::diagnostics::AnomalyKind;
+use r6502::emulator::{CPUEmulator, DefaultVirtualMemory, StopReason};
+use r6502::loaders;
+use r6502::monitor::Monitor;
+use r6502::vectors::{Vector, VectorWatch, Vectors};
+
+fn emulator(memory: DefaultVirtualMemory) -> CPUEmulator<DefaultVirtualMemory> {
+    common::stopped(memory).build().unwrap()
+}
+
+#[test]
+fn reads_and_overrides_vectors() {
+    let memory = DefaultVirtualMemory::default().with_image(0xFFFA, &[0x00, 0x90, 0x00, 0x80, 0x00, 0xA0]);
+    let mut emulator = emulator(memory);
+    assert_eq!(emulator.vectors(), Vectors { nmi: 0x9000, reset: 0x8000, irq: 0xA000 });
+
+    emulator.set_vector(Vector::Reset, 0x1234);
+    assert_eq!(emulator.vectors().reset, 0x1234);
+    emulator.reset();
+    assert_eq!(emulator.state.pc, 0x1234);
+}
+
+#[test]
+fn vectors_in_rom_stay_put() {
+    let memory = DefaultVirtualMemory::default().with_image(0xFFFC, &[0x00, 0x80]).with_rom(0xF000, 0xFFFF);
+    let mut emulator = emulator(memory);
+    emulator.set_vector(Vector::Reset, 0x1234);
+    assert_eq!(emulator.vectors().reset, 0x8000);
+}
+
+#[test]
+fn parses_vector_names() {
+    assert_eq!("NMI".parse(), Ok(Vector::Nmi));
+    assert_eq!("brk".parse(), Ok(Vector::Irq));
+    assert!("fire".parse::<Vector>().is_err());
+    assert!(Vector::Reset.in_image(0xF000, 0x0FFE));
+    assert!(!Vector::Reset.in_image(0xF000, 0x0FFD));
+}
+
+#[test]
+fn loaders_enter_through_a_covered_reset_vector() {
+    assert_eq!(loaders::binary(&[0; 0x10], 0xFFF0).unwrap().entry, None);
+    // Ends just after the reset vector, short of the IRQ vector.
+    assert_eq!(loaders::binary(&[0; 0x0E], 0xFFF0).unwrap().entry, None);
+    assert_eq!(loaders::binary(&[0; 0x0C], 0xFFF0).unwrap().entry, Some(0xFFF0));
+}
+
+#[test]
+fn monitor_shows_and_sets_vectors() {
+    let mut emulator = emulator(DefaultVirtualMemory::default());
+    let mut monitor = Monitor::new();
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "v irq 0300", &mut out).unwrap();
+    monitor.execute(&mut emulator, "v", &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "nmi   FFFA  0000\nreset FFFC  0000\nirq   FFFE  0300\n");
+    assert!(monitor.execute(&mut emulator, "v fire 0300", &mut vec![]).is_err());
+}
+
+// Points the IRQ vector at a new handler, as a program running from RAM might.
+const REDIRECT: &str = "
+        lda #<handler
+        sta $fffe
+        lda #>handler
+        sta $ffff
+        kil
+handler: rti
+";
+
+fn redirecting(watch: Option<VectorWatch>) -> CPUEmulator<DefaultVirtualMemory> {
+    common::assembled(REDIRECT, 0x0200)
+        .watch_vectors(watch)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn reports_writes_to_vectors() {
+    let mut emulator = redirecting(Some(VectorWatch::Report));
+    emulator.set_vector(Vector::Nmi, 0x1234);
+    emulator.run();
+    assert_eq!(emulator.vectors().irq, 0x020B);
+    let writes: Vec<_> = emulator.diagnostics.of_kind(AnomalyKind::VectorWrite).map(|anomaly| (anomaly.pc, anomaly.address)).collect();
+    assert_eq!(writes, [(0x0202, Some(0xFFFE)), (0x0207, Some(0xFFFF))]);
+
+    let mut unwatched = redirecting(None);
+    unwatched.run();
+    assert_eq!(unwatched.diagnostics.of_kind(AnomalyKind::VectorWrite).count(), 0);
+}
+
+#[test]
+fn breaks_on_writes_to_vectors() {
+    let mut emulator = redirecting(Some(VectorWatch::Break));
+    assert_eq!(emulator.run(), StopReason::VectorWrite(0xFFFE));
+    assert_eq!(emulator.state.pc, 0x0205);
+    assert_eq!(emulator.run(), StopReason::VectorWrite(0xFFFF));
+    assert_eq!(emulator.vectors().irq, 0x020B);
+    assert_eq!(Vector::containing(0xFFFF), Some(Vector::Irq));
+    assert_eq!(Vector::containing(0xFFF9), None);
+}
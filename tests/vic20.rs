@@ -0,0 +1,112 @@
+use r6502::assembler::assemble;
+use r6502::devices::via::{self, Via};
+use r6502::devices::vic::{Standard, Vic};
+use r6502::emulator::VirtualMemory;
+use r6502::machines::vic20::{self, Expansion, Vic20, BANK_LATCH, BLOCK5};
+use r6502::machines::Machine;
+
+// Sets VIA 2's timer 1 free running and counts its interrupts at $10.
+const KERNAL: &str = "
+reset: sei
+ldx #$ff
+txs
+lda #$40
+sta $912b
+lda #$00
+sta $9124
+lda #$10
+sta $9125
+lda #$c0
+sta $912e
+cli
+loop: jmp loop
+irq: inc $10
+lda $9124
+rti
+";
+
+fn kernal() -> Vec<u8> {
+    let program = assemble(KERNAL, 0xE000).unwrap();
+    let mut image = program.image.clone();
+    image.resize(0x2000, 0);
+    let irq = program.symbols.lookup("irq").unwrap().to_le_bytes();
+    let reset = program.symbols.lookup("reset").unwrap().to_le_bytes();
+    image[0x1FFA..].copy_from_slice(&[irq[0], irq[1], reset[0], reset[1], irq[0], irq[1]]);
+    image
+}
+
+#[test]
+fn expansion_ram_only_where_plugged_in() {
+    let mut stock = Vic20::new(Standard::Pal, Expansion::None).build().unwrap();
+    stock.poke(0x1000, 0x11);
+    stock.poke(0x2000, 0x22);
+    stock.poke(0x0400, 0x33);
+    assert_eq!((stock.peek(0x1000), stock.peek(0x2000), stock.peek(0x0400)), (0x11, 0, 0));
+
+    let mut expanded = Vic20::new(Standard::Pal, Expansion::Full).build().unwrap();
+    expanded.poke(0x2000, 0x22);
+    expanded.poke(0x7FFF, 0x44);
+    expanded.poke(0x0400, 0x33);
+    assert_eq!((expanded.peek(0x2000), expanded.peek(0x7FFF), expanded.peek(0x0400)), (0x22, 0x44, 0x33));
+    assert_eq!("vic20".parse::<Machine>().unwrap(), Machine::Vic20);
+    assert_eq!(Machine::Vic20.symbols().lookup("VIA2IER"), Some(0x912E));
+}
+
+#[test]
+fn banked_ram_switches_in_block5() {
+    let mut emulator = Vic20::new(Standard::Ntsc, Expansion::None).banked_ram(4).build().unwrap();
+    emulator.poke(BLOCK5, 0xAA);
+    emulator.poke(BANK_LATCH, 2);
+    assert_eq!(emulator.peek(BLOCK5), 0);
+    emulator.poke(BLOCK5, 0xBB);
+    emulator.poke(BANK_LATCH, 0);
+    assert_eq!(emulator.peek(BLOCK5), 0xAA);
+}
+
+#[test]
+fn via_timer_raises_the_jiffy_irq() {
+    let mut emulator = Vic20::new(Standard::Pal, Expansion::None).roms(&[], &[], &kernal()).build().unwrap();
+    emulator.run_for_cycles(0x1002 * 5 + 100);
+    assert!((4..=5).contains(&emulator.read(0x10)), "{} IRQs", emulator.read(0x10));
+}
+
+#[test]
+fn via_on_nmi_signals_one_edge() {
+    let mut via = Via::new().wired_to_nmi();
+    via.write(via::IER, 0x80 | via::CA1);
+    via.trigger(false);
+    assert!(!via.irq());
+    assert!(via.nmi());
+    assert!(!via.nmi());
+    assert_eq!(via.read(via::IFR), 0x80 | via::CA1);
+    via.read(via::ORA);
+    assert_eq!(via.read(via::IFR), 0);
+}
+
+#[test]
+fn renders_the_character_matrix() {
+    // Character 1 is a bar across its top line.
+    let mut characters = vec![0; 0x1000];
+    characters[8] = 0xF0;
+    let mut emulator = Vic20::new(Standard::Pal, Expansion::None).roms(&characters, &[], &[]).build().unwrap();
+    let vic = emulator.with_memory(|bus| bus.device::<Vic>().cloned()).unwrap();
+    assert_eq!((vic.screen(), vic.columns(), vic.rows()), (0x3E00, 22, 23));
+    for offset in 0..22 * 23 {
+        emulator.poke(0x1E00 + offset, 0x20);
+    }
+    emulator.poke(0x1E00, 0x01);
+    emulator.poke(0x9600, 0x02);
+    assert!(vic20::text(&emulator).unwrap().starts_with("A    "));
+
+    let frame = vic20::render(&emulator).unwrap();
+    assert_eq!((frame.width, frame.height), (22 * 8, 23 * 8));
+    assert_eq!(&frame.pixels[..8], [2, 2, 2, 2, 1, 1, 1, 1]);
+    assert!(frame.pixels[frame.width..frame.width + 8].iter().all(|pixel| *pixel == 1));
+
+    // Multicolour: pairs of bits pick background, border, character, auxiliary.
+    emulator.poke(0x9600, 0x0A);
+    emulator.poke(0x900E, 0x50);
+    emulator.poke(0x900F, 0x1B);
+    let frame = vic20::render(&emulator).unwrap();
+    assert_eq!(&frame.pixels[..8], [5, 5, 5, 5, 1, 1, 1, 1]);
+}
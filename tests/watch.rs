@@ -0,0 +1,54 @@
+mod common;
+
+use r6502::assembler::assemble;
+use r6502::emulator::{DefaultVirtualMemory, StopReason};
+use r6502::monitor::Monitor;
+
+#[test]
+fn logs_writes_into_watched_regions_as_csv() {
+    let mut emulator = common::assembled("lda #5\nsta $10\nsta $20\nlda #7\nsta $11\nsta $10\n.byte $02", 0x0200).build().unwrap();
+    emulator.watch_region(0x0010, 0x0011);
+    assert_eq!(emulator.run(), StopReason::Halted);
+
+    let log = emulator.watches.as_ref().unwrap();
+    assert_eq!(log.writes.len(), 3);
+    assert_eq!(log.history(0x0010).map(|write| (write.old, write.new)).collect::<Vec<_>>(), [(0, 5), (5, 7)]);
+    assert_eq!(
+        log.to_csv(),
+        "cycle,pc,address,old,new\n2,0202,0010,00,05\n10,0208,0011,00,07\n13,020A,0010,05,07\n"
+    );
+}
+
+// A loop that fills a buffer, and a stray store that runs over its end.
+const CORRUPTION: &str = "
+        ldx #3
+fill:   txa
+        sta $0200,x
+        dex
+        bpl fill
+        lda #$ff
+        sta $0204
+        kil
+";
+
+#[test]
+fn finds_who_wrote_to_a_range() {
+    let program = assemble(CORRUPTION, 0x0800).unwrap();
+    let memory = DefaultVirtualMemory::default().with_image(0x0800, &program.image);
+    let mut emulator = common::running(memory, 0x0800).build().unwrap();
+    assert!(emulator.who_wrote(0x0200, 0x02FF).is_empty());
+
+    let mut monitor = Monitor::new().symbols(program.symbols.clone());
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "who $0204", &mut out).unwrap();
+    monitor.execute(&mut emulator, "who $0200 $0204", &mut out).unwrap();
+    emulator.run();
+    monitor.execute(&mut emulator, "who $0204", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("watching 0204-0204 from now on\nwatching 0200-0204 from now on\n"), "{}", out);
+    assert!(out.ends_with("     1x  last 0204 <- FF at cycle 51  080B  8D 04 02  STA $0204\n"), "{}", out);
+
+    let writers = emulator.who_wrote(0x0200, 0x02FF);
+    assert_eq!(writers.iter().map(|writer| (writer.pc, writer.writes)).collect::<Vec<_>>(), [(0x0803, 4), (0x080B, 1)]);
+    assert_eq!((writers[0].first, writers[0].address, writers[0].value), (4, 0x0200, 0x00));
+}
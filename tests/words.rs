@@ -0,0 +1,21 @@
+use r6502::emulator::{DefaultVirtualMemory, VirtualMemory};
+
+#[test]
+fn words_are_little_endian_and_wrap() {
+    let mut memory = DefaultVirtualMemory::default();
+    memory.write_u16(0x1234, 0xBEEF);
+    assert_eq!((memory.read(0x1234), memory.read(0x1235)), (0xEF, 0xBE));
+    assert_eq!(memory.read_u16(0x1234), 0xBEEF);
+
+    // The high byte of a word at $FFFF comes from $0000.
+    memory.write_u16(0xFFFF, 0x5678);
+    assert_eq!((memory.read(0xFFFF), memory.read(0x0000)), (0x78, 0x56));
+    assert_eq!(memory.read_u16(0xFFFF), 0x5678);
+}
+
+#[test]
+fn zero_page_words_wrap_within_page_zero() {
+    let mut memory = DefaultVirtualMemory::default().with_image(0x0000, &[0x12]).with_image(0x00FF, &[0x34, 0xEE]);
+    assert_eq!(memory.read_u16_zp_wrapped(0xFF), 0x1234);
+    assert_eq!(memory.read_u16(0x00FF), 0xEE34);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use r6502::cpu::Cpu6502;
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::state::SystemState;
+
+// Every byte in page zero holds its own address, so the pointer at $nn is
+// $(nn+1)nn and the page it comes from is easy to see.
+fn core(program: &[u8], x: u8, y: u8) -> impl Cpu6502 {
+    let page_zero: Vec<u8> = (0..=0xFF).collect();
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x0000, &page_zero)
+        .with_image(0x0100, &[0xEE, 0xEE])
+        .with_image(0x0200, program);
+    common::builder(memory, SystemState { pc: 0x0200, s: 0xFD, a: 0x42, x, y, running: true, ..Default::default() })
+        .build()
+        .unwrap()
+}
+
+fn pointer(address: u8) -> u16 {
+    ((address.wrapping_add(1) as u16) << 8) | address as u16
+}
+
+#[test]
+fn indexed_indirect_pointers_wrap_within_page_zero() {
+    for operand in 0..=0xFFu8 {
+        for x in [0x00, 0x01, 0x80, 0xFF] {
+            let mut cpu = core(&[0x81, operand], x, 0); // STA (operand,X)
+            assert!(cpu.step());
+            let expected = pointer(operand.wrapping_add(x));
+            assert_eq!(cpu.last_writes(), vec![(expected, 0x42)], "operand={:02X} x={:02X}", operand, x);
+        }
+    }
+}
+
+#[test]
+fn indirect_indexed_pointers_wrap_within_page_zero() {
+    for operand in 0..=0xFFu8 {
+        for y in [0x00, 0x01, 0xFF] {
+            let mut cpu = core(&[0x91, operand], 0, y); // STA (operand),Y
+            assert!(cpu.step());
+            let expected = pointer(operand).wrapping_add(y as u16);
+            assert_eq!(cpu.last_writes(), vec![(expected, 0x42)], "operand={:02X} y={:02X}", operand, y);
+        }
+    }
+}
+
+#[test]
+fn pointers_at_ff_take_their_high_byte_from_00() {
+    // The pointer at $FF is $00:$FF = $1234, not $0100:$FF = $EE34.
+    let memory = DefaultVirtualMemory::default()
+        .with_image(0x0000, &[0x12])
+        .with_image(0x00FF, &[0x34, 0xEE])
+        .with_image(0x0200, &[0xB1, 0xFF, 0xA1, 0xFE]) // LDA ($FF),Y; LDA ($FE,X)
+        .with_image(0x1234, &[0x77, 0x99]);
+    let mut cpu = common::builder(memory, SystemState { pc: 0x0200, s: 0xFD, x: 0x01, y: 0x01, running: true, ..Default::default() })
+        .build()
+        .unwrap();
+    assert!(Cpu6502::step(&mut cpu));
+    assert_eq!(Cpu6502::registers(&cpu).a, 0x99);
+    assert!(Cpu6502::step(&mut cpu));
+    assert_eq!(Cpu6502::registers(&cpu).a, 0x77);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use r6502::analysis::zero_page::{Access, ZeroPageUsage};
+use r6502::assembler::assemble;
+use r6502::emulator::DefaultVirtualMemory;
+use r6502::monitor::Monitor;
+
+// Two libraries that both help themselves to $FB: one as the low byte of a
+// pointer, the other as a counter. $02 is only used by the main program.
+const PROGRAM: &str = "
+main:   lda #1
+        sta $02
+        jsr print
+        jsr count
+        kil
+print:  lda #<text
+        sta $fb
+        lda #>text
+        sta $fc
+        ldy #0
+        lda ($fb),y
+        rts
+count:  inc $fb
+        rts
+text:   .byte 0
+";
+
+#[test]
+fn attributes_zero_page_accesses_to_code_regions() {
+    let program = assemble(PROGRAM, 0x0800).unwrap();
+    let memory = DefaultVirtualMemory::default().with_image(0x0800, &program.image);
+    let mut emulator = common::running(memory, 0x0800)
+        .zero_page(Some(ZeroPageUsage::new()))
+        .build()
+        .unwrap();
+    emulator.run();
+
+    let usage = emulator.zero_page.as_ref().unwrap();
+    assert_eq!(usage.used(), [0x02, 0xFB, 0xFC]);
+    assert_eq!(usage.free().len(), 253);
+    // INC reads and writes back the old value before the new one.
+    assert_eq!(usage.access(0xFB), Access { reads: 2, writes: 3 });
+    assert_eq!(usage.users(0xFC), [(0x0811, Access { reads: 0, writes: 1 }), (0x0815, Access { reads: 1, writes: 0 })]);
+
+    let symbols = &program.symbols;
+    assert_eq!(
+        usage.regions(0xFB, Some(symbols)),
+        [("count".to_owned(), Access { reads: 1, writes: 2 }), ("print".to_owned(), Access { reads: 1, writes: 1 })]
+    );
+    assert_eq!(usage.shared(Some(symbols)), [0xFB]);
+    let report = usage.report(Some(symbols));
+    assert!(report.starts_with("zero page: 3 of 256 bytes used, 1 shared\n"), "{}", report);
+    assert!(report.contains("$FB       2       3  count (rw), print (rw) *\n"), "{}", report);
+    assert!(report.contains("$02       0       1  main (w)\n"), "{}", report);
+    // Without symbols every access comes from page 8.
+    assert!(usage.shared(None).is_empty());
+}
+
+#[test]
+fn monitor_reports_zero_page_usage() {
+    let program = assemble(PROGRAM, 0x0800).unwrap();
+    let memory = DefaultVirtualMemory::default().with_image(0x0800, &program.image);
+    let mut emulator = common::running(memory, 0x0800).build().unwrap();
+    let mut monitor = Monitor::new().symbols(program.symbols.clone());
+    let mut out = vec![];
+    monitor.execute(&mut emulator, "zp", &mut out).unwrap();
+    monitor.execute(&mut emulator, "s 2", &mut out).unwrap();
+    monitor.execute(&mut emulator, "zp", &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("collecting zero page usage") && out.contains("$02       0       1  main (w)"), "{}", out);
+}
@@ -0,0 +1,122 @@
+use r6502::error::R6502Error;
+use r6502::loaders::Format;
+use r6502::machines::{AutoLoader, Machine};
+use r6502::zip;
+
+// Deflate streams made by zlib: the cartridge below in a fixed Huffman block,
+// and the listing below in a dynamic one.
+const CARTRIDGE_DEFLATED: &str = "6b65f261f8f16a148c8251300a46c1281805230530fc60f80100";
+const LISTING_DEFLATED: &str = "\
+3dcfbb71c43010c0d0fcaad08c5d00b5e4925248fd22f75f8f0f8110227a8312a52ccbdf35979fdf523e254abc1949b637db24fb9bfd21b737f746ce378f9d3c\
+dfbc6ef216aadf5c75632375db45ea8e2075f741ea1e27a97bafa46ee9a46e1cdf0cdde4377407bfa1bbf31bba07bfa17bf31bba85dfd00d7e4337f9adba83df\
+aabbf35b754f7eabeecd6fd52dfc56ddca6fd54d7eabeee0b7e94e7e9beec96fd3bdf96dba85dfa65bf96dbac96fd31dfc36ddc96fea9efca6eecd6feaaefca6\
+6ee5377593dfd4ddf84dddc96fea9efc76dd87dfaebbf2db752bbf5d37f9edba1bbf5d77f2db754f7ebbeec3efd05df91dba95dfa1dbf91dba1bbf4377f23b74\
+2f7e87eec3efd05d8fcf3f";
+
+struct File {
+    name: &'static str,
+    method: u16,
+    data: Vec<u8>,
+    crc: u32,
+    size: usize,
+}
+
+fn stored(name: &'static str, data: &[u8], crc: u32) -> File {
+    File { name, method: 0, data: data.to_vec(), crc, size: data.len() }
+}
+
+fn deflated(name: &'static str, hex: &str, crc: u32, size: usize) -> File {
+    let data = (0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap()).collect();
+    File { name, method: 8, data, crc, size }
+}
+
+// A ZIP archive of `files`, laid out the way zip tools write them.
+fn archive(files: &[File]) -> Vec<u8> {
+    let (mut bytes, mut directory) = (vec![], vec![]);
+    for file in files {
+        let offset = bytes.len() as u32;
+        let mut header = vec![];
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&file.method.to_le_bytes());
+        header.extend_from_slice(&[0; 4]);
+        header.extend_from_slice(&file.crc.to_le_bytes());
+        header.extend_from_slice(&(file.data.len() as u32).to_le_bytes());
+        header.extend_from_slice(&(file.size as u32).to_le_bytes());
+        header.extend_from_slice(&(file.name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        bytes.extend_from_slice(b"PK\x03\x04");
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(file.name.as_bytes());
+        bytes.extend_from_slice(&file.data);
+        directory.extend_from_slice(b"PK\x01\x02\x14\x00");
+        directory.extend_from_slice(&header);
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(file.name.as_bytes());
+    }
+    let start = bytes.len() as u32;
+    bytes.extend_from_slice(&directory);
+    bytes.extend_from_slice(b"PK\x05\x06\x00\x00\x00\x00");
+    bytes.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&start.to_le_bytes());
+    bytes.extend_from_slice(&0u16.to_le_bytes());
+    bytes
+}
+
+// STA WSYNC, then JMP back, in a 2K cartridge starting at $F800.
+fn cartridge() -> Vec<u8> {
+    let mut cartridge = vec![0xEA; 0x0800];
+    cartridge[..5].copy_from_slice(&[0x85, 0x02, 0x4C, 0x00, 0xF8]);
+    cartridge[0x7FC..].copy_from_slice(&[0x00, 0xF8, 0x00, 0xF8]);
+    cartridge
+}
+
+fn listing() -> String {
+    (0..64).map(|index| format!("{:04X}  LDA #${:02X}\n", 0x0200 + index * 2, (index * 37) & 0xFF)).collect()
+}
+
+#[test]
+fn finds_the_rom_beside_a_readme() {
+    let data = archive(&[
+        stored("games/", b"", 0),
+        stored("README.txt", b"Public domain.\n", 0x311B_8FA9),
+        deflated("games/wsync.a26", CARTRIDGE_DEFLATED, 0xF77B_9A3E, 0x0800),
+    ]);
+    assert!(zip::is_zip(&data));
+    assert_eq!(zip::entries(&data).unwrap().len(), 2);
+    let (name, contents) = zip::rom(&data).unwrap();
+    assert_eq!(name, "games/wsync.a26");
+    assert_eq!(contents, cartridge());
+}
+
+#[test]
+fn inflates_dynamic_blocks() {
+    let data = archive(&[deflated("listing.s", LISTING_DEFLATED, 0xE1F7_AEC4, 960)]);
+    let entries = zip::entries(&data).unwrap();
+    assert_eq!(String::from_utf8(zip::extract(&data, &entries[0]).unwrap()).unwrap(), listing());
+}
+
+#[test]
+fn rejects_corrupt_and_ambiguous_archives() {
+    let corrupt = archive(&[deflated("wsync.a26", CARTRIDGE_DEFLATED, 0xF77B_9A3F, 0x0800)]);
+    assert!(matches!(zip::rom(&corrupt), Err(R6502Error::BadArchive(_))));
+    let two = archive(&[stored("a.bin", &[1], 0xA505_DF1B), stored("b.bin", &[2], 0x3C0C_8EA1)]);
+    assert!(matches!(zip::rom(&two), Err(R6502Error::BadArchive(_))));
+    assert!(matches!(zip::rom(&cartridge()), Err(R6502Error::BadArchive(_))));
+}
+
+#[test]
+fn loaders_look_inside_archives() {
+    let path = std::env::temp_dir().join(format!("r6502-zip-{}.zip", std::process::id()));
+    std::fs::write(&path, archive(&[deflated("wsync.a26", CARTRIDGE_DEFLATED, 0xF77B_9A3E, 0x0800)])).unwrap();
+    let loaded = AutoLoader::default().load(&path);
+    let cartridge = r6502::loaders::atari2600_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!((loaded.format, loaded.machine), (Format::Atari2600, Machine::Atari2600));
+    assert_eq!(loaded.emulator.state.pc, 0xF800);
+    assert_eq!(cartridge.unwrap().memory.bytes()[0x1000], 0x85);
+}